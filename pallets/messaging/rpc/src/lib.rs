@@ -0,0 +1,443 @@
+//! JSON-RPC interface for the secure messaging pallet.
+//!
+//! Wraps [`MessagingRuntimeApi`] so front ends can read inboxes, contacts and conversations
+//! and verify message hashes without decoding pallet storage themselves. Every method takes
+//! an optional `at` block hash and falls back to the best block when it is omitted.
+//!
+//! Responses use camelCase field names and hex-encode message hashes.
+//!
+//! Chains can set the pallet's event privacy mode to `Minimal`, in which `MessageSent` and
+//! the contact events leave out the accounts involved. Indexers check the mode with
+//! `messaging_eventPrivacy` and then resolve each message through `messaging_message`, which
+//! reads storage instead of the event.
+
+use std::sync::Arc;
+
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{ErrorObject, ErrorObjectOwned},
+};
+use pallet_messaging::{
+	ConversationSummary, EventPrivacyMode, MessageId, MessageRecordView, MessageSummary,
+	VerificationBatch, VerificationResult, MAX_VERIFICATION_BATCH, MAX_VIEW_PAGE_SIZE,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sp_api::{ApiError, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+pub use pallet_messaging_runtime_api::MessagingApi as MessagingRuntimeApi;
+
+#[cfg(test)]
+mod tests;
+
+/// A message as returned by `messaging_inbox` and `messaging_conversation`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageEntry<AccountId, BlockNumber> {
+	/// ID the message is stored under.
+	pub message_id: MessageId,
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Hash of the message content.
+	pub hash: Bytes,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Whether the message has expired.
+	pub expired: bool,
+	/// Whether a payment for the message is awaiting acknowledgement.
+	pub paid: bool,
+}
+
+impl<AccountId, Hash: AsRef<[u8]>, BlockNumber>
+	From<(MessageId, MessageSummary<AccountId, Hash, BlockNumber>)>
+	for MessageEntry<AccountId, BlockNumber>
+{
+	fn from((message_id, summary): (MessageId, MessageSummary<AccountId, Hash, BlockNumber>)) -> Self {
+		Self {
+			message_id,
+			sender: summary.sender,
+			hash: summary.hash.as_ref().to_vec().into(),
+			sent_at: summary.sent_at,
+			expired: summary.expired,
+			paid: summary.paid,
+		}
+	}
+}
+
+/// A message as returned by `messaging_message`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageDetails<AccountId, BlockNumber> {
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Account the message was sent to.
+	pub recipient: AccountId,
+	/// Hash of the message content.
+	pub hash: Bytes,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Last block at which the hash verifies.
+	pub expires_at: BlockNumber,
+}
+
+impl<AccountId, Hash: AsRef<[u8]>, BlockNumber>
+	From<MessageRecordView<AccountId, Hash, BlockNumber>> for MessageDetails<AccountId, BlockNumber>
+{
+	fn from(view: MessageRecordView<AccountId, Hash, BlockNumber>) -> Self {
+		Self {
+			sender: view.sender,
+			recipient: view.recipient,
+			hash: view.hash.as_ref().to_vec().into(),
+			sent_at: view.sent_at,
+			expires_at: view.expires_at,
+		}
+	}
+}
+
+/// A conversation as seen by one of its parties, as returned by
+/// `messaging_conversationSummary`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationDetails<BlockNumber> {
+	/// Messages of the conversation still stored, in either direction.
+	pub messages: u32,
+	/// Paid messages sent by the account that the peer has not acknowledged yet.
+	pub unacknowledged_sent: u32,
+	/// Paid messages received by the account that it has not acknowledged yet.
+	pub unacknowledged_received: u32,
+	/// ID of the last message sent in the conversation.
+	pub last_message_id: MessageId,
+	/// Block of the last message sent or acknowledged in the conversation.
+	pub last_activity: BlockNumber,
+}
+
+impl<BlockNumber> From<ConversationSummary<BlockNumber>> for ConversationDetails<BlockNumber> {
+	fn from(summary: ConversationSummary<BlockNumber>) -> Self {
+		Self {
+			messages: summary.messages,
+			unacknowledged_sent: summary.unacknowledged_sent,
+			unacknowledged_received: summary.unacknowledged_received,
+			last_message_id: summary.last_message_id,
+			last_activity: summary.last_activity,
+		}
+	}
+}
+
+/// How much the pallet's events reveal, as returned by `messaging_eventPrivacy`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EventPrivacy {
+	/// Events name the accounts involved.
+	Full,
+	/// Events carry only IDs and hashes; read the rest with `messaging_message`.
+	Minimal,
+}
+
+impl From<EventPrivacyMode> for EventPrivacy {
+	fn from(mode: EventPrivacyMode) -> Self {
+		match mode {
+			EventPrivacyMode::Full => Self::Full,
+			EventPrivacyMode::Minimal => Self::Minimal,
+		}
+	}
+}
+
+/// Outcome of checking one entry of a `messaging_verifyBatch` call.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Verification {
+	/// The hash matches the stored message.
+	Valid,
+	/// The hash differs from the stored message.
+	Mismatch,
+	/// No message is stored under the ID.
+	NotFound,
+	/// The message exists but has expired.
+	Expired,
+	/// The message was removed by governance.
+	Removed,
+	/// The sender retracted the message.
+	Retracted,
+}
+
+impl From<VerificationResult> for Verification {
+	fn from(result: VerificationResult) -> Self {
+		match result {
+			VerificationResult::Valid => Self::Valid,
+			VerificationResult::Mismatch => Self::Mismatch,
+			VerificationResult::NotFound => Self::NotFound,
+			VerificationResult::Expired => Self::Expired,
+			VerificationResult::RemovedByGovernance => Self::Removed,
+			VerificationResult::Retracted => Self::Retracted,
+		}
+	}
+}
+
+/// Messaging RPC methods.
+#[rpc(client, server)]
+pub trait MessagingApi<BlockHash, AccountId, Hash, BlockNumber> {
+	/// All messages sent to `account`, in ID order.
+	#[method(name = "messaging_inbox")]
+	fn inbox(
+		&self,
+		account: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<MessageEntry<AccountId, BlockNumber>>>;
+
+	/// All contacts approved by `account`.
+	#[method(name = "messaging_contacts")]
+	fn contacts(&self, account: AccountId, at: Option<BlockHash>) -> RpcResult<Vec<AccountId>>;
+
+	/// Whether `hash` matches the message stored under `message_id`.
+	///
+	/// Fails if the message does not exist, has expired, was retracted or was removed by
+	/// governance.
+	#[method(name = "messaging_verify")]
+	fn verify(&self, message_id: MessageId, hash: Hash, at: Option<BlockHash>) -> RpcResult<bool>;
+
+	/// Check each `(message_id, hash)` pair of `batch`, returning one outcome per pair in the
+	/// order given.
+	///
+	/// A missing or removed message only affects its own entry. Fails if `batch` holds more
+	/// than `MAX_VERIFICATION_BATCH` pairs.
+	#[method(name = "messaging_verifyBatch")]
+	fn verify_batch(
+		&self,
+		batch: Vec<(MessageId, Hash)>,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<Verification>>;
+
+	/// Messages between `a` and `b` in either direction, starting at ID `from`.
+	///
+	/// Returns at most `limit` messages; the runtime caps a page at `MAX_VIEW_PAGE_SIZE`.
+	#[method(name = "messaging_conversation")]
+	fn conversation(
+		&self,
+		a: AccountId,
+		b: AccountId,
+		from: MessageId,
+		limit: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<MessageEntry<AccountId, BlockNumber>>>;
+
+	/// The message stored under `message_id`, or `None` if there is none.
+	///
+	/// Reads storage, so it also serves chains whose events leave out the accounts.
+	#[method(name = "messaging_message")]
+	fn message(
+		&self,
+		message_id: MessageId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<MessageDetails<AccountId, BlockNumber>>>;
+
+	/// Whether message and contact events name the accounts involved.
+	#[method(name = "messaging_eventPrivacy")]
+	fn event_privacy(&self, at: Option<BlockHash>) -> RpcResult<EventPrivacy>;
+
+	/// Summary of the conversation between `account` and `peer`, from `account`'s side, or
+	/// `None` if neither has messaged the other.
+	///
+	/// The answer is always given from the side of a party to the conversation. The summary
+	/// is public chain state, so this is no access control: anyone can ask about any pair.
+	#[method(name = "messaging_conversationSummary")]
+	fn conversation_summary(
+		&self,
+		account: AccountId,
+		peer: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<ConversationDetails<BlockNumber>>>;
+}
+
+/// Error codes returned by the messaging RPC.
+pub enum Error {
+	/// The runtime call failed.
+	RuntimeError,
+	/// No message is stored under the ID.
+	MessageNotFound,
+	/// The message exists but has expired.
+	MessageExpired,
+	/// The message was removed by governance.
+	MessageRemoved,
+	/// The sender retracted the message.
+	MessageRetracted,
+	/// More pairs were passed to `messaging_verifyBatch` than the runtime checks at once.
+	BatchTooLarge,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::MessageNotFound => 2,
+			Error::MessageExpired => 3,
+			Error::MessageRemoved => 4,
+			Error::MessageRetracted => 5,
+			Error::BatchTooLarge => 6,
+		}
+	}
+}
+
+fn runtime_error(e: ApiError) -> ErrorObjectOwned {
+	ErrorObject::owned(
+		Error::RuntimeError.into(),
+		"Unable to query messaging state.",
+		Some(e.to_string()),
+	)
+}
+
+/// Provides the messaging RPC methods on top of a client.
+pub struct Messaging<C, Block> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<Block>,
+}
+
+impl<C, Block> Messaging<C, Block> {
+	/// Create a new instance backed by `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> Messaging<C, Block>
+where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	fn at_or_best(&self, at: Option<Block::Hash>) -> Block::Hash {
+		at.unwrap_or_else(|| self.client.info().best_hash)
+	}
+}
+
+/// Fetch pages of at most `MAX_VIEW_PAGE_SIZE` entries until a short page is returned.
+fn collect_pages<Item, Cursor>(
+	mut fetch: impl FnMut(Option<Cursor>) -> Result<Vec<Item>, ApiError>,
+	cursor: impl Fn(&Item) -> Cursor,
+) -> Result<Vec<Item>, ApiError> {
+	let mut items = Vec::new();
+	let mut after = None;
+	loop {
+		let page = fetch(after)?;
+		let full = page.len() >= MAX_VIEW_PAGE_SIZE as usize;
+		after = page.last().map(&cursor);
+		items.extend(page);
+		if !full {
+			return Ok(items)
+		}
+	}
+}
+
+impl<C, Block, AccountId, Hash, BlockNumber>
+	MessagingApiServer<<Block as BlockT>::Hash, AccountId, Hash, BlockNumber> for Messaging<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: MessagingRuntimeApi<Block, AccountId, Hash, BlockNumber>,
+	AccountId: codec::Codec + Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+	Hash: codec::Codec + AsRef<[u8]> + Send + Sync + DeserializeOwned + 'static,
+	BlockNumber: codec::Codec + Send + Sync + Serialize + 'static,
+{
+	fn inbox(
+		&self,
+		account: AccountId,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Vec<MessageEntry<AccountId, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = self.at_or_best(at);
+		let messages = collect_pages(
+			|after| api.inbox(at, account.clone(), after, MAX_VIEW_PAGE_SIZE),
+			|(message_id, _)| *message_id,
+		)
+		.map_err(runtime_error)?;
+		Ok(messages.into_iter().map(Into::into).collect())
+	}
+
+	fn contacts(&self, account: AccountId, at: Option<Block::Hash>) -> RpcResult<Vec<AccountId>> {
+		let api = self.client.runtime_api();
+		let at = self.at_or_best(at);
+		collect_pages(
+			|after| api.contacts(at, account.clone(), after, MAX_VIEW_PAGE_SIZE),
+			Clone::clone,
+		)
+		.map_err(runtime_error)
+	}
+
+	fn verify(&self, message_id: MessageId, hash: Hash, at: Option<Block::Hash>) -> RpcResult<bool> {
+		let api = self.client.runtime_api();
+		let at = self.at_or_best(at);
+		let (code, message) = match api.verify_message(at, message_id, hash).map_err(runtime_error)? {
+			VerificationResult::Valid => return Ok(true),
+			VerificationResult::Mismatch => return Ok(false),
+			VerificationResult::NotFound => (Error::MessageNotFound, "Message not found."),
+			VerificationResult::Expired => (Error::MessageExpired, "Message has expired."),
+			VerificationResult::RemovedByGovernance =>
+				(Error::MessageRemoved, "Message was removed by governance."),
+			VerificationResult::Retracted =>
+				(Error::MessageRetracted, "Message was retracted by its sender."),
+		};
+		Err(ErrorObject::owned(code.into(), message, Some(message_id)))
+	}
+
+	fn verify_batch(
+		&self,
+		batch: Vec<(MessageId, Hash)>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Vec<Verification>> {
+		let batch: VerificationBatch<Hash> = batch.try_into().map_err(|_| {
+			ErrorObject::owned(
+				Error::BatchTooLarge.into(),
+				"Too many messages to verify at once.",
+				Some(MAX_VERIFICATION_BATCH),
+			)
+		})?;
+		let api = self.client.runtime_api();
+		let at = self.at_or_best(at);
+		let results = api.verify_messages(at, batch).map_err(runtime_error)?;
+		Ok(results.into_iter().map(Into::into).collect())
+	}
+
+	fn conversation(
+		&self,
+		a: AccountId,
+		b: AccountId,
+		from: MessageId,
+		limit: u32,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Vec<MessageEntry<AccountId, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = self.at_or_best(at);
+		let messages = api.conversation(at, a, b, from, limit).map_err(runtime_error)?;
+		Ok(messages.into_iter().map(Into::into).collect())
+	}
+
+	fn message(
+		&self,
+		message_id: MessageId,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<MessageDetails<AccountId, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = self.at_or_best(at);
+		let message = api.message_info(at, message_id).map_err(runtime_error)?;
+		Ok(message.map(Into::into))
+	}
+
+	fn event_privacy(&self, at: Option<Block::Hash>) -> RpcResult<EventPrivacy> {
+		let api = self.client.runtime_api();
+		let at = self.at_or_best(at);
+		Ok(api.event_privacy(at).map_err(runtime_error)?.into())
+	}
+
+	fn conversation_summary(
+		&self,
+		account: AccountId,
+		peer: AccountId,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<ConversationDetails<BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = self.at_or_best(at);
+		let summary = api.conversation_summary(at, account, peer).map_err(runtime_error)?;
+		Ok(summary.map(Into::into))
+	}
+}
@@ -0,0 +1,295 @@
+use super::*;
+use sp_api::ApiRef;
+use sp_core::H256;
+use sp_runtime::{generic, traits::BlakeTwo256, OpaqueExtrinsic};
+
+type BlockNumber = u32;
+type Header = generic::Header<BlockNumber, BlakeTwo256>;
+type Block = generic::Block<Header, OpaqueExtrinsic>;
+type AccountId = u64;
+
+const BEST: H256 = H256::repeat_byte(0xbb);
+const PRUNED: H256 = H256::repeat_byte(0xdd);
+
+/// Account whose inbox spans more than one runtime page.
+const BUSY: AccountId = 9;
+
+fn summary(sender: AccountId, id: MessageId) -> MessageSummary<AccountId, H256, BlockNumber> {
+	MessageSummary {
+		sender,
+		hash: H256::repeat_byte(id as u8),
+		sent_at: id as BlockNumber,
+		expired: id == 1,
+		paid: false,
+	}
+}
+
+/// Verifies against a fixed set of messages: 0 is valid, 1 expired, 2 removed, 4 retracted,
+/// anything else missing.
+fn verification(message_id: MessageId, hash: H256) -> VerificationResult {
+	match message_id {
+		0 if hash == H256::repeat_byte(0) => VerificationResult::Valid,
+		0 => VerificationResult::Mismatch,
+		1 => VerificationResult::Expired,
+		2 => VerificationResult::RemovedByGovernance,
+		4 => VerificationResult::Retracted,
+		_ => VerificationResult::NotFound,
+	}
+}
+
+struct MockApi;
+
+sp_api::mock_impl_runtime_apis! {
+	impl MessagingRuntimeApi<Block, AccountId, H256, BlockNumber> for MockApi {
+		#[advanced]
+		fn verify_message(
+			&self,
+			at: <Block as BlockT>::Hash,
+			message_id: MessageId,
+			hash: H256,
+		) -> Result<VerificationResult, ApiError> {
+			if at == PRUNED {
+				return Err(ApiError::Application("state pruned".into()))
+			}
+			Ok(verification(message_id, hash))
+		}
+
+		fn verify_messages(&self, batch: VerificationBatch<H256>) -> Vec<VerificationResult> {
+			batch.into_iter().map(|(message_id, hash)| verification(message_id, hash)).collect()
+		}
+
+		#[advanced]
+		fn inbox(
+			&self,
+			at: <Block as BlockT>::Hash,
+			who: AccountId,
+			after: Option<MessageId>,
+			limit: u32,
+		) -> Result<Vec<(MessageId, MessageSummary<AccountId, H256, BlockNumber>)>, ApiError> {
+			let count = match (who, at) {
+				(BUSY, _) => 250,
+				(_, BEST) => 2,
+				_ => 1,
+			};
+			let start = after.map_or(0, |id| id + 1);
+			Ok((start..count).take(limit as usize).map(|id| (id, summary(1, id))).collect())
+		}
+
+		fn contacts(&self, _who: AccountId, after: Option<AccountId>, limit: u32) -> Vec<AccountId> {
+			let start = after.map_or(0, |who| who + 1);
+			(start..150).take(limit as usize).collect()
+		}
+
+		fn conversation(
+			&self,
+			a: AccountId,
+			b: AccountId,
+			from: MessageId,
+			limit: u32,
+		) -> Vec<(MessageId, MessageSummary<AccountId, H256, BlockNumber>)> {
+			(from..10)
+				.map(|id| (id, summary(if id % 2 == 0 { a } else { b }, id)))
+				.take(limit as usize)
+				.collect()
+		}
+
+		fn message_info(
+			&self,
+			message_id: MessageId,
+		) -> Option<MessageRecordView<AccountId, H256, BlockNumber>> {
+			(message_id == 0).then(|| MessageRecordView {
+				hash: H256::repeat_byte(0),
+				sender: 1,
+				recipient: 2,
+				sent_at: 1,
+				expires_at: 1001,
+			})
+		}
+
+		fn event_privacy(&self) -> EventPrivacyMode {
+			EventPrivacyMode::Minimal
+		}
+
+		fn conversation_summary(
+			&self,
+			a: AccountId,
+			b: AccountId,
+		) -> Option<ConversationSummary<BlockNumber>> {
+			// Accounts 1 and 2 exchanged three messages; one paid message from 1 awaits 2
+			let (sent, received) = match (a, b) {
+				(1, 2) => (1, 0),
+				(2, 1) => (0, 1),
+				_ => return None,
+			};
+			Some(ConversationSummary {
+				messages: 3,
+				unacknowledged_sent: sent,
+				unacknowledged_received: received,
+				last_message_id: 7,
+				last_activity: 12,
+			})
+		}
+	}
+}
+
+struct TestClient;
+
+impl ProvideRuntimeApi<Block> for TestClient {
+	type Api = MockApi;
+
+	fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+		MockApi.into()
+	}
+}
+
+impl HeaderBackend<Block> for TestClient {
+	fn header(&self, _hash: H256) -> sp_blockchain::Result<Option<Header>> {
+		Ok(None)
+	}
+
+	fn info(&self) -> sp_blockchain::Info<Block> {
+		sp_blockchain::Info {
+			best_hash: BEST,
+			best_number: 1,
+			genesis_hash: H256::zero(),
+			finalized_hash: H256::zero(),
+			finalized_number: 0,
+			finalized_state: None,
+			number_leaves: 1,
+			block_gap: None,
+		}
+	}
+
+	fn status(&self, _hash: H256) -> sp_blockchain::Result<sp_blockchain::BlockStatus> {
+		Ok(sp_blockchain::BlockStatus::InChain)
+	}
+
+	fn number(&self, _hash: H256) -> sp_blockchain::Result<Option<BlockNumber>> {
+		Ok(None)
+	}
+
+	fn hash(&self, _number: BlockNumber) -> sp_blockchain::Result<Option<H256>> {
+		Ok(None)
+	}
+}
+
+fn rpc() -> Messaging<TestClient, Block> {
+	Messaging::new(Arc::new(TestClient))
+}
+
+#[test]
+fn inbox_defaults_to_best_block() {
+	let rpc = rpc();
+	assert_eq!(rpc.inbox(1, None).unwrap().len(), 2);
+	assert_eq!(rpc.inbox(1, Some(H256::repeat_byte(0xaa))).unwrap().len(), 1);
+}
+
+#[test]
+fn inbox_follows_runtime_pages() {
+	let ids: Vec<_> = rpc().inbox(BUSY, None).unwrap().iter().map(|m| m.message_id).collect();
+	assert_eq!(ids, (0..250).collect::<Vec<_>>());
+}
+
+#[test]
+fn contacts_follow_runtime_pages() {
+	assert_eq!(rpc().contacts(1, None).unwrap(), (0..150).collect::<Vec<_>>());
+}
+
+#[test]
+fn entries_serialize_as_camel_case_with_hex_hashes() {
+	let entries = rpc().inbox(1, None).unwrap();
+	assert_eq!(
+		serde_json::to_value(&entries[1]).unwrap(),
+		serde_json::json!({
+			"messageId": 1,
+			"sender": 1,
+			"hash": format!("0x{}", "01".repeat(32)),
+			"sentAt": 1,
+			"expired": true,
+			"paid": false,
+		}),
+	);
+}
+
+#[test]
+fn verify_reports_match_and_mismatch() {
+	let rpc = rpc();
+	assert_eq!(rpc.verify(0, H256::repeat_byte(0), None), Ok(true));
+	assert_eq!(rpc.verify(0, H256::repeat_byte(1), None), Ok(false));
+}
+
+#[test]
+fn verify_maps_missing_messages_to_distinct_errors() {
+	let rpc = rpc();
+	let code = |message_id| rpc.verify(message_id, H256::zero(), None).unwrap_err().code();
+	assert_eq!(code(1), i32::from(Error::MessageExpired));
+	assert_eq!(code(2), i32::from(Error::MessageRemoved));
+	assert_eq!(code(3), i32::from(Error::MessageNotFound));
+	assert_eq!(code(4), i32::from(Error::MessageRetracted));
+}
+
+#[test]
+fn verify_batch_reports_each_entry() {
+	let batch = (0..5).map(|message_id| (message_id, H256::zero())).collect();
+	let results = rpc().verify_batch(batch, None).unwrap();
+	assert_eq!(
+		serde_json::to_value(results).unwrap(),
+		serde_json::json!(["valid", "expired", "removed", "notFound", "retracted"]),
+	);
+}
+
+#[test]
+fn verify_batch_is_bounded() {
+	let batch = vec![(0, H256::zero()); MAX_VERIFICATION_BATCH as usize + 1];
+	let err = rpc().verify_batch(batch, None).unwrap_err();
+	assert_eq!(err.code(), i32::from(Error::BatchTooLarge));
+}
+
+#[test]
+fn runtime_failures_are_reported() {
+	let err = rpc().verify(0, H256::zero(), Some(PRUNED)).unwrap_err();
+	assert_eq!(err.code(), i32::from(Error::RuntimeError));
+}
+
+#[test]
+fn conversation_respects_range() {
+	let page = rpc().conversation(4, 5, 3, 4, None).unwrap();
+	let senders: Vec<_> = page.iter().map(|m| (m.message_id, m.sender)).collect();
+	assert_eq!(senders, vec![(3, 5), (4, 4), (5, 5), (6, 4)]);
+}
+
+#[test]
+fn message_is_read_from_storage() {
+	let rpc = rpc();
+	assert_eq!(rpc.event_privacy(None), Ok(EventPrivacy::Minimal));
+	assert_eq!(rpc.message(1, None), Ok(None));
+	assert_eq!(
+		serde_json::to_value(rpc.message(0, None).unwrap()).unwrap(),
+		serde_json::json!({
+			"sender": 1,
+			"recipient": 2,
+			"hash": format!("0x{}", "00".repeat(32)),
+			"sentAt": 1,
+			"expiresAt": 1001,
+		}),
+	);
+	assert_eq!(serde_json::to_value(EventPrivacy::Minimal).unwrap(), "minimal");
+}
+
+#[test]
+fn conversation_summary_is_given_from_the_account_side() {
+	let rpc = rpc();
+	assert_eq!(rpc.conversation_summary(1, 3, None), Ok(None));
+	let received = rpc.conversation_summary(2, 1, None).unwrap().unwrap();
+	assert_eq!((received.unacknowledged_sent, received.unacknowledged_received), (0, 1));
+	assert_eq!(
+		serde_json::to_value(rpc.conversation_summary(1, 2, None).unwrap()).unwrap(),
+		serde_json::json!({
+			"messages": 3,
+			"unacknowledgedSent": 1,
+			"unacknowledgedReceived": 0,
+			"lastMessageId": 7,
+			"lastActivity": 12,
+		}),
+	);
+}
@@ -0,0 +1,163 @@
+//! Runtime API definitions for the secure messaging pallet.
+//!
+//! Lets RPC clients and off-chain tooling read pallet state through the runtime instead of
+//! decoding raw storage.
+//!
+//! ## Versions
+//!
+//! - 1: `admin_audit_log`.
+//! - 2: adds `verify_message`, `message_info`, `is_expired` and `profile`.
+//! - 3: adds the paginated `inbox`, `contacts` and `conversation`.
+//! - 4: adds `event_privacy`.
+//! - 5: `conversation` pages by sequence number within the conversation and reports the
+//!   lowest sequence number still stored. Clients of older nodes call
+//!   `conversation_before_version_5`.
+//! - 6: adds `has_profile` and `can_message`.
+//! - 7: adds `profile_activity`.
+//! - 8: adds `message_by_hash`.
+//! - 9: adds `inbox_revision`.
+//! - 10: adds `conversation_summary`.
+//! - 11: adds `message_state`. `verify_message` can report `Retracted`.
+//! - 12: adds `verify_messages`.
+//!
+//! Clients should check the API version reported by a node before calling methods added
+//! in later versions.
+//!
+//! Queries involving balances live in [`MessagingStandingApi`], which takes the balance type
+//! as a parameter so `MessagingApi` keeps its signature.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use codec::Codec;
+use pallet_messaging::{
+	AccountStanding, AuditEntry, CanMessageError, ConversationPage, ConversationSummary,
+	EventPrivacyMode, MessageId, MessageRecordView, MessageState, MessageSummary, ProfileActivity,
+	ProfileView, VerificationBatch, VerificationResult,
+};
+
+sp_api::decl_runtime_apis! {
+	/// Queries over pallet-messaging state.
+	#[api_version(12)]
+	pub trait MessagingApi<AccountId, Hash, BlockNumber>
+	where
+		AccountId: Codec,
+		Hash: Codec,
+		BlockNumber: Codec,
+	{
+		/// Privileged actions recorded in the admin audit log, oldest first.
+		fn admin_audit_log() -> Vec<AuditEntry<BlockNumber, Hash>>;
+
+		/// Check `hash` against the message stored under `message_id`.
+		#[api_version(2)]
+		fn verify_message(message_id: MessageId, hash: Hash) -> VerificationResult;
+
+		/// The message stored under `message_id`, if any.
+		#[api_version(2)]
+		fn message_info(
+			message_id: MessageId,
+		) -> Option<MessageRecordView<AccountId, Hash, BlockNumber>>;
+
+		/// Whether the message under `message_id` has expired or does not exist. Superseded by
+		/// `message_state`, which tells these apart.
+		#[api_version(2)]
+		fn is_expired(message_id: MessageId) -> bool;
+
+		/// The profile registered by `who`, if any.
+		#[api_version(2)]
+		fn profile(who: AccountId) -> Option<ProfileView>;
+
+		/// Messages sent to `who` with an ID greater than `after`, at most `limit` of them.
+		#[api_version(3)]
+		fn inbox(
+			who: AccountId,
+			after: Option<MessageId>,
+			limit: u32,
+		) -> Vec<(MessageId, MessageSummary<AccountId, Hash, BlockNumber>)>;
+
+		/// Contacts approved by `who` after `after`, at most `limit` of them.
+		#[api_version(3)]
+		fn contacts(who: AccountId, after: Option<AccountId>, limit: u32) -> Vec<AccountId>;
+
+		/// Messages between `a` and `b` starting at ID `from`, at most `limit` of them.
+		#[changed_in(5)]
+		fn conversation(
+			a: AccountId,
+			b: AccountId,
+			from: MessageId,
+			limit: u32,
+		) -> Vec<(MessageId, MessageSummary<AccountId, Hash, BlockNumber>)>;
+
+		/// Messages between `a` and `b` in sequence order, starting at sequence number
+		/// `from_seq`, at most `limit` of them, capped at 128.
+		#[api_version(3)]
+		fn conversation(
+			a: AccountId,
+			b: AccountId,
+			from_seq: u64,
+			limit: u32,
+		) -> ConversationPage<AccountId, Hash, BlockNumber>;
+
+		/// How much the pallet's events currently reveal.
+		///
+		/// Under `EventPrivacyMode::Minimal`, message and contact events leave out the
+		/// accounts involved and clients read them through `message_info` instead.
+		#[api_version(4)]
+		fn event_privacy() -> EventPrivacyMode;
+
+		/// Whether `who` has registered a profile.
+		#[api_version(6)]
+		fn has_profile(who: AccountId) -> bool;
+
+		/// Whether `sender` can message `recipient` now, or why `send_message_hash` would
+		/// fail.
+		#[api_version(6)]
+		fn can_message(sender: AccountId, recipient: AccountId) -> Result<(), CanMessageError>;
+
+		/// Last activity of `who` and whether its profile is dormant, or `None` if it has no
+		/// profile.
+		#[api_version(7)]
+		fn profile_activity(who: AccountId) -> Option<ProfileActivity<BlockNumber>>;
+
+		/// The message sent with `hash` and its ID, if it has not been pruned or removed.
+		#[api_version(8)]
+		fn message_by_hash(
+			hash: Hash,
+		) -> Option<(MessageId, MessageRecordView<AccountId, Hash, BlockNumber>)>;
+
+		/// Revision of the inbox of `who`, bumped on every change to the messages `inbox`
+		/// lists.
+		#[api_version(9)]
+		fn inbox_revision(who: AccountId) -> u64;
+
+		/// Summary of the conversation between `a` and `b` as seen by `a`, or `None` if
+		/// neither has messaged the other.
+		#[api_version(10)]
+		fn conversation_summary(
+			a: AccountId,
+			b: AccountId,
+		) -> Option<ConversationSummary<BlockNumber>>;
+
+		/// Where the message under `message_id` stands.
+		#[api_version(11)]
+		fn message_state(message_id: MessageId) -> MessageState<BlockNumber>;
+
+		/// Check each hash in `batch` against the message stored under its ID, in the order
+		/// given. One bad entry does not fail the others.
+		#[api_version(12)]
+		fn verify_messages(batch: VerificationBatch<Hash>) -> Vec<VerificationResult>;
+	}
+
+	/// Standing of pallet-messaging accounts.
+	pub trait MessagingStandingApi<AccountId, Balance, BlockNumber>
+	where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// Bond, strikes, rate limit and restrictions deciding whether `who` can send.
+		fn account_standing(who: AccountId) -> AccountStanding<Balance, BlockNumber>;
+	}
+}
@@ -0,0 +1,733 @@
+//! Calls into `MessagingApi` through the runtime's API dispatcher, the same SCALE boundary
+//! RPC clients go through.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	assert_ok, derive_impl, parameter_types,
+	traits::{
+		ConstBool, ConstU128, ConstU32, ConstU64, Everything, NeverEnsureOrigin, Nothing, OnGenesis,
+	},
+	PalletId,
+};
+use frame_system::{pallet_prelude::HeaderFor, EnsureRoot};
+use pallet_messaging::{
+	AccountStanding, CanMessageError, ConversationMessage, ConversationPage, ConversationSummary,
+	EventPrivacyMode, KeyAlgorithm, MessageId, MessageRecordView, MessageState, MessageStatus,
+	MessageSummary, ProfileActivity, ProfileView, VerificationBatch, VerificationResult,
+	MAX_VERIFICATION_BATCH,
+};
+use pallet_messaging_runtime_api::{MessagingApi, MessagingStandingApi};
+use sp_api::RuntimeApiInfo;
+use sp_core::H256;
+use sp_runtime::{BuildStorage, Perbill};
+use xcm::latest::Location;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+type AccountId = u64;
+type BlockNumber = u64;
+type Balance = u128;
+
+#[frame_support::runtime]
+mod runtime {
+	#[runtime::runtime]
+	#[runtime::derive(
+		RuntimeCall,
+		RuntimeEvent,
+		RuntimeError,
+		RuntimeOrigin,
+		RuntimeFreezeReason,
+		RuntimeHoldReason,
+		RuntimeSlashReason,
+		RuntimeLockId,
+		RuntimeTask,
+		RuntimeViewFunction
+	)]
+	pub struct Test;
+
+	#[runtime::pallet_index(0)]
+	pub type System = frame_system;
+
+	#[runtime::pallet_index(1)]
+	pub type Balances = pallet_balances;
+
+	#[runtime::pallet_index(2)]
+	pub type Timestamp = pallet_timestamp;
+
+	#[runtime::pallet_index(3)]
+	pub type Messaging = pallet_messaging;
+}
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type AccountData = pallet_balances::AccountData<Balance>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type ExistentialDeposit = ConstU128<1>;
+	type AccountStore = System;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<5>;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MessagingPalletId: PalletId = PalletId(*b"py/msgng");
+	pub const Percent50: Perbill = Perbill::from_percent(50);
+	pub const AutoSlashEnabled: bool = true;
+}
+
+impl pallet_messaging::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type Currency = Balances;
+	type Time = Timestamp;
+	type WeightInfo = ();
+	type SpamBond = ConstU128<100>;
+	type MaxContactsPerUser = ConstU32<100>;
+	type MaxPendingContactRequests = ConstU32<3>;
+	type MessageHashExpiry = ConstU64<1000>;
+	type ResolutionOrigin = EnsureRoot<AccountId>;
+	type MaxMessagesPerWindow = ConstU32<10>;
+	type RateLimitWindow = ConstU64<10>;
+	type RateLimitPenaltyStrikes = ConstU32<1>;
+	type BondPenaltyStrikes = ConstU32<2>;
+	type MaxStrikes = ConstU32<3>;
+	type StrikeDecayPeriod = ConstU64<100>;
+	type MinBondIncrement = ConstU128<10>;
+	type BondUnbondingPeriod = ConstU64<50>;
+	type InactivityThreshold = ConstU64<200>;
+	type DormantSweepThreshold = ConstU64<1000>;
+	type PalletId = MessagingPalletId;
+	type ChallengeBond = ConstU128<20>;
+	type ChallengeSlashPercent = Percent50;
+	type ChallengerRewardPercent = Percent50;
+	type BaseChallengeReward = ConstU128<10>;
+	type RewardPoolFeeShare = Percent50;
+	type AppealWindow = ConstU64<10>;
+	type ChallengeWindow = ConstU64<500>;
+	type AppealBond = ConstU128<40>;
+	type AppealOrigin = EnsureRoot<AccountId>;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type MaxFreezeDuration = ConstU64<100>;
+	type MaxReportsPerWindow = ConstU32<3>;
+	type ReportDecayPeriod = ConstU64<100>;
+	type AutoChallengeThreshold = ConstU32<3>;
+	type AutoSlashEnabled = AutoSlashEnabled;
+	type AutoSlashThreshold = ConstU32<3>;
+	type AutoSlashPercent = Percent50;
+	type ChallengeMaxDuration = ConstU64<100>;
+	type MaxFreeMessagesPerBlock = ConstU32<2>;
+	type InboxPassDuration = ConstU64<20>;
+	type SlashIncome = ();
+	type FeeIncome = ();
+	type ForfeitedDeposits = ();
+	type ProtocolFeeIncome = ();
+	type MaxKeyResetNotifications = ConstU32<3>;
+	type TakedownRetention = ConstU64<50>;
+	type MaxPrunesPerCall = ConstU32<3>;
+	type MaxExpiryEventsPerBlock = ConstU32<2>;
+	type PruneInterval = ConstU64<5>;
+	type PruneUnsignedPriority = ConstU64<100>;
+	type OffchainIndexing = ConstBool<false>;
+	type XcmSender = ();
+	type XcmOrigin = NeverEnsureOrigin<Location>;
+	type RemoteProfileTtl = ConstU64<100>;
+	type ProfileQueryTimeout = ConstU64<10>;
+	type RegistrationGate = Everything;
+	type IdentityVerifier = Nothing;
+	type GroupBaseDeposit = ConstU128<40>;
+	type GroupPerMemberDeposit = ConstU128<10>;
+	type MaxGroupsPerAccount = ConstU32<3>;
+	type MaxGroupMembers = ConstU32<4>;
+	type MaxGroupAdmins = ConstU32<2>;
+	type MaxGroupMessagesPerWindow = ConstU32<5>;
+	type GroupMessageExpiry = ConstU64<500>;
+	type GroupInviteExpiry = ConstU64<10>;
+	type MaxGroupInvites = ConstU32<2>;
+	type MaxPendingGroupInvites = ConstU32<2>;
+	type MaxGroupBans = ConstU32<2>;
+	type MaxDisbandRemovalsPerCall = ConstU32<3>;
+	type GroupMetadataDepositPerByte = ConstU128<1>;
+	type MaxDiscoverableGroups = ConstU32<2>;
+	type MaxGroupJoinRequests = ConstU32<2>;
+	type MaxGroupModerationLog = ConstU32<3>;
+	type StealthMessageDeposit = ConstU128<30>;
+	type RelayBond = ConstU128<500>;
+	type MaxRelayCarriesPerBlock = ConstU32<2>;
+	type PendingMessageDeposit = ConstU128<40>;
+	type PendingMessageExpiry = ConstU64<3000>;
+	type PendingMessageRefund = Percent50;
+	type MaxClaimsPerCall = ConstU32<2>;
+	type EvidenceRevealPeriod = ConstU64<20>;
+	type MembershipProofVerifier = ();
+	type AccumulatorUpdater =
+		pallet_messaging::membership::HashChain<sp_runtime::traits::BlakeTwo256>;
+	type MaxEphemeralKeys = ConstU32<3>;
+	type RecipientSetDeposit = ConstU128<10>;
+	type BackupDeposit = ConstU128<5>;
+	type MaxAutoRefundsPerReply = ConstU32<2>;
+	type MaxSendBatch = ConstU32<4>;
+	type MaxDevicesPerAccount = ConstU32<2>;
+	type DeviceDeposit = ConstU128<20>;
+	type CreditPrice = ConstU128<10>;
+	type MaxCreditsPerAccount = ConstU32<20>;
+	type CreditRefund = Percent50;
+	type OffchainSignature = sp_runtime::testing::TestSignature;
+	type SigningPublicKey = sp_runtime::testing::UintAuthorityId;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+impl<C> frame_system::offchain::CreateTransactionBase<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	type Extrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+	type RuntimeCall = RuntimeCall;
+}
+
+impl<C> frame_system::offchain::CreateBare<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	fn create_bare(call: RuntimeCall) -> frame_system::mocking::MockUncheckedExtrinsic<Test> {
+		frame_system::mocking::MockUncheckedExtrinsic::<Test>::new_bare(call)
+	}
+}
+
+sp_api::impl_runtime_apis! {
+	// Required by the macro; only the API versions are ever read
+	impl sp_api::Core<Block> for Test {
+		fn version() -> sp_version::RuntimeVersion {
+			sp_version::RuntimeVersion { apis: RUNTIME_API_VERSIONS, ..Default::default() }
+		}
+
+		fn execute_block(_: Block) {
+			unimplemented!()
+		}
+
+		fn initialize_block(_: &HeaderFor<Test>) -> sp_runtime::ExtrinsicInclusionMode {
+			unimplemented!()
+		}
+	}
+
+	impl pallet_messaging_runtime_api::MessagingApi<Block, AccountId, H256, BlockNumber> for Test {
+		fn admin_audit_log() -> Vec<pallet_messaging::AuditEntry<BlockNumber, H256>> {
+			Messaging::audit_log()
+		}
+
+		fn verify_message(message_id: MessageId, hash: H256) -> VerificationResult {
+			Messaging::verification_result(message_id, hash)
+		}
+
+		fn message_info(
+			message_id: MessageId,
+		) -> Option<MessageRecordView<AccountId, H256, BlockNumber>> {
+			Messaging::message_view(message_id)
+		}
+
+		fn is_expired(message_id: MessageId) -> bool {
+			!Messaging::message_state(message_id).is_live()
+		}
+
+		fn profile(who: AccountId) -> Option<ProfileView> {
+			Messaging::profile_view(&who)
+		}
+
+		fn inbox(
+			who: AccountId,
+			after: Option<MessageId>,
+			limit: u32,
+		) -> Vec<(MessageId, MessageSummary<AccountId, H256, BlockNumber>)> {
+			Messaging::inbox_of(who, None, after, limit)
+		}
+
+		fn contacts(who: AccountId, after: Option<AccountId>, limit: u32) -> Vec<AccountId> {
+			Messaging::contacts_of(who, after, limit)
+		}
+
+		fn conversation(
+			a: AccountId,
+			b: AccountId,
+			from_seq: u64,
+			limit: u32,
+		) -> ConversationPage<AccountId, H256, BlockNumber> {
+			Messaging::conversation_page(a, b, from_seq, limit)
+		}
+
+		fn event_privacy() -> EventPrivacyMode {
+			Messaging::parameters().event_privacy
+		}
+
+		fn has_profile(who: AccountId) -> bool {
+			Messaging::has_profile(who)
+		}
+
+		fn can_message(sender: AccountId, recipient: AccountId) -> Result<(), CanMessageError> {
+			Messaging::can_message(sender, recipient)
+		}
+
+		fn profile_activity(who: AccountId) -> Option<ProfileActivity<BlockNumber>> {
+			Messaging::profile_activity(who)
+		}
+
+		fn message_by_hash(
+			hash: H256,
+		) -> Option<(MessageId, MessageRecordView<AccountId, H256, BlockNumber>)> {
+			Messaging::message_by_hash(hash)
+		}
+
+		fn inbox_revision(who: AccountId) -> u64 {
+			Messaging::inbox_revision(who)
+		}
+
+		fn conversation_summary(
+			a: AccountId,
+			b: AccountId,
+		) -> Option<ConversationSummary<BlockNumber>> {
+			Messaging::conversation_summary(a, b)
+		}
+
+		fn message_state(message_id: MessageId) -> MessageState<BlockNumber> {
+			Messaging::message_state(message_id)
+		}
+
+		fn verify_messages(batch: VerificationBatch<H256>) -> Vec<VerificationResult> {
+			Messaging::verify_message_batch(batch)
+		}
+	}
+
+	impl pallet_messaging_runtime_api::MessagingStandingApi<Block, AccountId, Balance, BlockNumber>
+		for Test
+	{
+		fn account_standing(who: AccountId) -> AccountStanding<Balance, BlockNumber> {
+			Messaging::standing(who)
+		}
+	}
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1000), (2, 1000), (3, 1000)],
+		dev_accounts: None,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		// Genesis records the in-code storage version, as on a real chain
+		<Messaging as OnGenesis>::on_genesis();
+	});
+	ext
+}
+
+/// Call `method` of `MessagingApi` with SCALE-encoded `args` and decode the result.
+fn call_api<R: Decode>(method: &str, args: impl Encode) -> R {
+	let output = api::dispatch(&format!("MessagingApi_{method}"), &args.encode())
+		.expect("method is implemented");
+	R::decode(&mut &output[..]).expect("output decodes")
+}
+
+fn send_message(hash: H256) {
+	let origin = RuntimeOrigin::signed;
+	assert_ok!(Messaging::register_profile(origin(1), vec![1, 2, 3, 4]));
+	assert_ok!(Messaging::register_profile(origin(2), vec![5, 6, 7, 8]));
+	assert_ok!(Messaging::approve_contact(origin(2), 1));
+	assert_ok!(Messaging::send_message_hash(origin(1), 2, hash));
+}
+
+#[test]
+fn api_is_at_version_twelve() {
+	type Api = dyn MessagingApi<Block, AccountId, H256, BlockNumber>;
+	assert_eq!(<Api as RuntimeApiInfo>::VERSION, 12);
+	assert!(RUNTIME_API_VERSIONS.contains(&(<Api as RuntimeApiInfo>::ID, 12)));
+}
+
+#[test]
+fn verify_message_reports_each_outcome() {
+	new_test_ext().execute_with(|| {
+		let hash = H256::from([1; 32]);
+		let verify = |id: MessageId, hash: H256| -> VerificationResult {
+			call_api("verify_message", (id, hash))
+		};
+		assert_eq!(verify(0, hash), VerificationResult::NotFound);
+
+		send_message(hash);
+		assert_eq!(verify(0, hash), VerificationResult::Valid);
+		assert_eq!(verify(0, H256::from([2; 32])), VerificationResult::Mismatch);
+
+		System::set_block_number(1002);
+		assert_eq!(verify(0, hash), VerificationResult::Expired);
+	});
+}
+
+#[test]
+fn verify_message_reports_takedowns() {
+	new_test_ext().execute_with(|| {
+		let hash = H256::from([1; 32]);
+		send_message(hash);
+		assert_ok!(Messaging::force_remove_message(
+			RuntimeOrigin::root(),
+			0,
+			vec![1; 8].try_into().unwrap()
+		));
+
+		let result: VerificationResult = call_api("verify_message", (0u64, hash));
+		assert_eq!(result, VerificationResult::RemovedByGovernance);
+	});
+}
+
+#[test]
+fn verify_messages_reports_each_entry() {
+	new_test_ext().execute_with(|| {
+		let hash = H256::from([1; 32]);
+		send_message(hash);
+		let batch = vec![(5, hash), (0, H256::from([2; 32])), (0, hash)];
+
+		let results: Vec<VerificationResult> = call_api("verify_messages", batch);
+		assert_eq!(
+			results,
+			[VerificationResult::NotFound, VerificationResult::Mismatch, VerificationResult::Valid]
+		);
+	});
+}
+
+#[test]
+#[should_panic(expected = "Bad input data")]
+fn verify_messages_rejects_oversized_batches() {
+	new_test_ext().execute_with(|| {
+		let batch = vec![(0u64, H256::zero()); MAX_VERIFICATION_BATCH as usize + 1];
+		let _: Vec<VerificationResult> = call_api("verify_messages", batch);
+	});
+}
+
+#[test]
+fn message_info_and_expiry() {
+	new_test_ext().execute_with(|| {
+		let hash = H256::from([1; 32]);
+		let info: Option<MessageRecordView<AccountId, H256, BlockNumber>> =
+			call_api("message_info", 0u64);
+		assert_eq!(info, None);
+		assert!(call_api::<bool>("is_expired", 0u64));
+
+		send_message(hash);
+		let info: Option<MessageRecordView<AccountId, H256, BlockNumber>> =
+			call_api("message_info", 0u64);
+		assert_eq!(
+			info,
+			Some(MessageRecordView { hash, sender: 1, recipient: 2, sent_at: 1, expires_at: 1001 })
+		);
+		assert!(!call_api::<bool>("is_expired", 0u64));
+
+		System::set_block_number(1002);
+		assert!(call_api::<bool>("is_expired", 0u64));
+	});
+}
+
+#[test]
+fn message_by_hash_finds_the_record() {
+	new_test_ext().execute_with(|| {
+		type Found = Option<(MessageId, MessageRecordView<AccountId, H256, BlockNumber>)>;
+		let hash = H256::from([1; 32]);
+		assert_eq!(call_api::<Found>("message_by_hash", hash), None);
+
+		send_message(hash);
+		assert_eq!(
+			call_api::<Found>("message_by_hash", hash),
+			Some((
+				0,
+				MessageRecordView { hash, sender: 1, recipient: 2, sent_at: 1, expires_at: 1001 }
+			))
+		);
+	});
+}
+
+#[test]
+fn inbox_revision_moves_with_the_inbox() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(call_api::<u64>("inbox_revision", 2u64), 0);
+
+		send_message(H256::from([1; 32]));
+		assert_eq!(call_api::<u64>("inbox_revision", 2u64), 1);
+		assert_eq!(call_api::<u64>("inbox_revision", 1u64), 0);
+	});
+}
+
+#[test]
+fn conversation_summary_is_served() {
+	new_test_ext().execute_with(|| {
+		type Summary = Option<ConversationSummary<BlockNumber>>;
+		assert_eq!(call_api::<Summary>("conversation_summary", (1u64, 2u64)), None);
+
+		send_message(H256::from([1; 32]));
+		let summary = ConversationSummary {
+			messages: 1,
+			unacknowledged_sent: 0,
+			unacknowledged_received: 0,
+			last_message_id: 0,
+			last_activity: 1,
+		};
+		assert_eq!(call_api::<Summary>("conversation_summary", (2u64, 1u64)), Some(summary));
+	});
+}
+
+#[test]
+fn message_state_is_served() {
+	new_test_ext().execute_with(|| {
+		type State = MessageState<BlockNumber>;
+		assert_eq!(call_api::<State>("message_state", 0u64), MessageState::NotFound);
+
+		send_message(H256::from([1; 32]));
+		assert_eq!(
+			call_api::<State>("message_state", 0u64),
+			MessageState::Active { expires_at: 1001 }
+		);
+	});
+}
+
+#[test]
+fn profile_reflects_key_and_bond() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(call_api::<Option<ProfileView>>("profile", 1u64), None);
+
+		send_message(H256::from([1; 32]));
+		assert_ok!(Messaging::force_reset_key(
+			RuntimeOrigin::root(),
+			1,
+			vec![9; 32],
+			KeyAlgorithm::Ed25519
+		));
+
+		assert_eq!(
+			call_api::<Option<ProfileView>>("profile", 1u64),
+			Some(ProfileView {
+				public_key: vec![9; 32],
+				algorithm: KeyAlgorithm::Ed25519,
+				key_version: 1,
+				bonded: true,
+				banned: false,
+			})
+		);
+	});
+}
+
+#[test]
+fn admin_audit_log_is_still_served() {
+	new_test_ext().execute_with(|| {
+		send_message(H256::from([1; 32]));
+		assert_ok!(Messaging::force_reset_key(
+			RuntimeOrigin::root(),
+			1,
+			vec![9; 32],
+			KeyAlgorithm::X25519
+		));
+
+		let log: Vec<pallet_messaging::AuditEntry<BlockNumber, H256>> =
+			call_api("admin_audit_log", ());
+		assert_eq!(log, Messaging::audit_log());
+		assert_eq!(log.len(), 1);
+	});
+}
+
+type Page = ConversationPage<AccountId, H256, BlockNumber>;
+
+/// Have 1 approve 2 back, so that 2 can reply to the message of `send_message`.
+fn approve_back() {
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+}
+
+/// Send one message per block so neither rate limit is hit.
+fn reply(from: AccountId, to: AccountId, hash: H256) {
+	System::set_block_number(System::block_number() + 1);
+	assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(from), to, hash));
+}
+
+fn seqs_and_senders(page: &Page) -> Vec<(u64, AccountId)> {
+	page.messages.iter().map(|message| (message.seq, message.sender)).collect()
+}
+
+#[test]
+fn conversation_lists_both_directions() {
+	new_test_ext().execute_with(|| {
+		send_message(H256::from([1; 32]));
+		approve_back();
+		reply(2, 1, H256::from([2; 32]));
+
+		let page: Page = call_api("conversation", (2u64, 1u64, 0u64, 10u32));
+		assert_eq!(page, call_api::<Page>("conversation", (1u64, 2u64, 0u64, 10u32)));
+		assert_eq!((page.lowest_retained_seq, page.next_seq), (0, 2));
+		assert_eq!(
+			page.messages[1],
+			ConversationMessage {
+				message_id: 1,
+				seq: 1,
+				sender: 2,
+				recipient: 1,
+				hash: H256::from([2; 32]),
+				sent_at: 2,
+				expires_at: 1002,
+				status: MessageStatus::Active,
+			}
+		);
+		assert_eq!(seqs_and_senders(&page), vec![(0, 1), (1, 2)]);
+	});
+}
+
+#[test]
+fn conversation_keeps_interleaved_messages_in_order() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![9, 9, 9, 9]));
+		send_message(H256::from([1; 32]));
+		approve_back();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 3));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(3), 1));
+		reply(1, 3, H256::from([2; 32]));
+		reply(2, 1, H256::from([3; 32]));
+		reply(3, 1, H256::from([4; 32]));
+		reply(1, 2, H256::from([5; 32]));
+
+		// Messages with account 3 neither show up nor take a sequence number
+		let page: Page = call_api("conversation", (1u64, 2u64, 0u64, 10u32));
+		assert_eq!(seqs_and_senders(&page), vec![(0, 1), (1, 2), (2, 1)]);
+		let ids: Vec<_> = page.messages.iter().map(|message| message.message_id).collect();
+		assert_eq!(ids, vec![0, 2, 4]);
+
+		let page: Page = call_api("conversation", (3u64, 1u64, 0u64, 10u32));
+		assert_eq!(seqs_and_senders(&page), vec![(0, 1), (1, 3)]);
+	});
+}
+
+#[test]
+fn conversation_pages_by_sequence_number() {
+	new_test_ext().execute_with(|| {
+		send_message(H256::from([1; 32]));
+		approve_back();
+		reply(2, 1, H256::from([2; 32]));
+		reply(1, 2, H256::from([3; 32]));
+
+		let page: Page = call_api("conversation", (1u64, 2u64, 0u64, 2u32));
+		assert_eq!(seqs_and_senders(&page), vec![(0, 1), (1, 2)]);
+		let page: Page = call_api("conversation", (1u64, 2u64, 2u64, 2u32));
+		assert_eq!(seqs_and_senders(&page), vec![(2, 1)]);
+		let page: Page = call_api("conversation", (1u64, 2u64, 3u64, 2u32));
+		assert!(page.messages.is_empty());
+		assert_eq!(page.next_seq, 3);
+	});
+}
+
+#[test]
+fn conversation_pages_across_a_pruning_boundary() {
+	new_test_ext().execute_with(|| {
+		send_message(H256::from([1; 32]));
+		approve_back();
+		reply(2, 1, H256::from([2; 32]));
+		reply(1, 2, H256::from([3; 32]));
+
+		// Only the first message, sent at block 1, has expired by block 1002
+		System::set_block_number(1002);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+
+		// A client resuming below the retained history gets what is left
+		let page: Page = call_api("conversation", (2u64, 1u64, 0u64, 1u32));
+		assert_eq!(page.lowest_retained_seq, 1);
+		assert_eq!(seqs_and_senders(&page), vec![(1, 2)]);
+		let page: Page = call_api("conversation", (2u64, 1u64, 2u64, 1u32));
+		assert_eq!(seqs_and_senders(&page), vec![(2, 1)]);
+		assert_eq!(page.lowest_retained_seq, 1);
+	});
+}
+
+#[test]
+fn can_message_is_served() {
+	new_test_ext().execute_with(|| {
+		assert!(!call_api::<bool>("has_profile", 1u64));
+		let check: Result<(), CanMessageError> = call_api("can_message", (1u64, 2u64));
+		assert_eq!(check, Err(CanMessageError::NoSenderProfile));
+
+		send_message(H256::from([1; 32]));
+		assert!(call_api::<bool>("has_profile", 1u64));
+		let check: Result<(), CanMessageError> = call_api("can_message", (1u64, 2u64));
+		assert_eq!(check, Ok(()));
+		let check: Result<(), CanMessageError> = call_api("can_message", (1u64, 3u64));
+		assert_eq!(check, Err(CanMessageError::NoRecipientProfile));
+	});
+}
+
+#[test]
+fn profile_activity_is_served() {
+	new_test_ext().execute_with(|| {
+		type Activity = Option<ProfileActivity<BlockNumber>>;
+		assert_eq!(call_api::<Activity>("profile_activity", 1u64), None);
+
+		send_message(H256::from([1; 32]));
+		System::set_block_number(201);
+		assert_ok!(Messaging::flag_inactive(RuntimeOrigin::signed(3), 2));
+		assert_eq!(
+			call_api::<Activity>("profile_activity", 2u64),
+			Some(ProfileActivity { last_activity: 1, dormant_since: Some(201) })
+		);
+	});
+}
+
+#[test]
+fn contacts_are_served() {
+	new_test_ext().execute_with(|| {
+		send_message(H256::from([1; 32]));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+
+		let contacts: Vec<AccountId> = call_api("contacts", (1u64, None::<AccountId>, 10u32));
+		assert_eq!(contacts, vec![2]);
+	});
+}
+
+#[test]
+fn message_info_serves_minimal_events() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(call_api::<EventPrivacyMode>("event_privacy", ()), EventPrivacyMode::Full);
+		let parameters = pallet_messaging::PalletParameters {
+			event_privacy: EventPrivacyMode::Minimal,
+			..Messaging::parameters()
+		};
+		assert_ok!(Messaging::update_parameters(RuntimeOrigin::root(), parameters));
+		assert_eq!(call_api::<EventPrivacyMode>("event_privacy", ()), EventPrivacyMode::Minimal);
+
+		let hash = H256::from([1; 32]);
+		send_message(hash);
+		let info: Option<MessageRecordView<AccountId, H256, BlockNumber>> =
+			call_api("message_info", 0u64);
+		assert_eq!(
+			info,
+			Some(MessageRecordView { hash, sender: 1, recipient: 2, sent_at: 1, expires_at: 1001 })
+		);
+	});
+}
+
+
+#[test]
+fn account_standing_reports_bond_and_rate_limit() {
+	new_test_ext().execute_with(|| {
+		type Api = dyn MessagingStandingApi<Block, AccountId, Balance, BlockNumber>;
+		assert_eq!(Api::VERSION, 1);
+
+		send_message(H256::from([1; 32]));
+		let output = api::dispatch("MessagingStandingApi_account_standing", &1u64.encode())
+			.expect("method is implemented");
+		let standing = AccountStanding::<Balance, BlockNumber>::decode(&mut &output[..])
+			.expect("output decodes");
+		assert!(standing.registered);
+		assert!(!standing.banned);
+		assert_eq!(standing.bonded, standing.required_bond);
+		assert_eq!(standing.rate_limit_remaining, 9);
+	});
+}
@@ -4,23 +4,246 @@
 
 use super::*;
 use crate::Pallet as Messaging;
+use crate::membership::VerifyMembershipProof;
 use frame_benchmarking::v2::*;
-use frame_support::traits::Currency;
+use frame_support::{
+	migrations::SteppedMigration,
+	traits::{
+		fungible::{Mutate, MutateHold},
+		tokens::Precision,
+	},
+	weights::WeightMeter,
+};
 use frame_system::RawOrigin;
+use sp_runtime::traits::Hash;
 use sp_std::vec;
 
+/// Longest public key a profile can have, so that benchmarks read and write profiles at
+/// their largest.
+fn max_key() -> Vec<u8> {
+	vec![1u8; 256]
+}
+
+/// Register a profile for `who` with `n` devices, all with the longest key and attestation.
+fn register_with_devices<T: Config>(who: &T::AccountId, n: u32) {
+	// Enough for the deposits of a full set of devices
+	let deposits = T::DeviceDeposit::get() * T::MaxDevicesPerAccount::get().into();
+	let _ = T::Currency::set_balance(who, T::SpamBond::get() * 10u32.into() + deposits);
+	let _ = Messaging::<T>::register_profile(RawOrigin::Signed(who.clone()).into(), max_key());
+	for _ in 0..n {
+		let _ = Messaging::<T>::add_device_key(
+			RawOrigin::Signed(who.clone()).into(),
+			max_key(),
+			max_attestation(),
+		);
+	}
+}
+
+/// Charge a protocol fee on every send, contacts included, so that it is withdrawn and
+/// routed.
+fn charge_protocol_fee<T: Config>() {
+	Parameters::<T>::put(PalletParameters {
+		message_protocol_fee: 1u32.into(),
+		exempt_mutual_contacts: false,
+		..Messaging::<T>::parameters()
+	});
+}
+
+/// Longest device key attestation.
+fn max_attestation() -> DeviceAttestation {
+	vec![1u8; MAX_DEVICE_ATTESTATION_LEN as usize].try_into().unwrap()
+}
+
+/// Register a sender and recipient, send a message and open a challenge against it.
+///
+/// Returns the sender of the challenged message, which has ID 0.
+fn open_challenge<T: Config>(challenger: &T::AccountId) -> T::AccountId {
+	let sender: T::AccountId = account("sender", 0, 0);
+	let recipient: T::AccountId = account("recipient", 0, 0);
+	let public_key = max_key();
+	let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+	let bond_amount = T::SpamBond::get();
+	let _ = T::Currency::set_balance(&sender, bond_amount * 10u32.into());
+	let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
+	let _ = T::Currency::set_balance(challenger, T::AppealBond::get() * 10u32.into());
+	let _ = Messaging::<T>::register_profile(RawOrigin::Signed(sender.clone()).into(), public_key.clone());
+	let _ = Messaging::<T>::register_profile(RawOrigin::Signed(recipient.clone()).into(), public_key);
+	let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(recipient.clone()).into(), sender.clone());
+	let _ = Messaging::<T>::send_message_hash(RawOrigin::Signed(sender.clone()).into(), recipient, message_hash);
+	let _ = Messaging::<T>::challenge_spam(
+		RawOrigin::Signed(challenger.clone()).into(),
+		0,
+		T::Hash::default(),
+	);
+
+	sender
+}
+
+/// Register a sender and recipient, set the recipient's inbox price and send it a paid
+/// message.
+///
+/// Returns the sender and recipient of the paid message, which has ID 0.
+fn send_paid_message<T: Config>() -> (T::AccountId, T::AccountId) {
+	let sender: T::AccountId = account("sender", 0, 0);
+	let recipient: T::AccountId = account("recipient", 0, 0);
+	let public_key = max_key();
+	let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+	let bond_amount = T::SpamBond::get();
+	let _ = T::Currency::set_balance(&sender, bond_amount * 10u32.into());
+	let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
+	let _ = Messaging::<T>::register_profile(RawOrigin::Signed(sender.clone()).into(), public_key.clone());
+	let _ = Messaging::<T>::register_profile(RawOrigin::Signed(recipient.clone()).into(), public_key);
+	let _ = Messaging::<T>::set_inbox_price(RawOrigin::Signed(recipient.clone()).into(), Some(bond_amount));
+	let _ = Messaging::<T>::send_paid_message_hash(RawOrigin::Signed(sender.clone()).into(), recipient.clone(), message_hash);
+
+	(sender, recipient)
+}
+
+/// Add `count` placeholder accounts to the contact list of `who`.
+fn fill_contacts<T: Config>(who: &T::AccountId, count: u32) {
+	for i in 0..count {
+		let filler: T::AccountId = account("filler", i, 0);
+		ApprovedContacts::<T>::insert(who, &filler, true);
+	}
+	ContactCount::<T>::mutate(who, |contacts| *contacts = contacts.saturating_add(count));
+}
+
+/// Fill the outgoing contact requests of `requester` and the incoming ones of `target`, so a
+/// request between them drops the oldest of each.
+fn fill_contact_requests<T: Config>(requester: &T::AccountId, target: &T::AccountId) {
+	let now = frame_system::Pallet::<T>::block_number();
+	for i in 0..T::MaxPendingContactRequests::get() {
+		let filler: T::AccountId = account("request_filler", i, 0);
+		let _ = OutgoingContactRequests::<T>::try_append(requester, (filler.clone(), now));
+		let _ = IncomingContactRequests::<T>::try_append(&filler, (requester.clone(), now));
+		let _ = IncomingContactRequests::<T>::try_append(target, (filler.clone(), now));
+		let _ = OutgoingContactRequests::<T>::try_append(&filler, (target.clone(), now));
+	}
+}
+
+/// Deposit held for a group of `members` members.
+fn group_deposit<T: Config>(members: u32) -> BalanceOf<T> {
+	T::GroupBaseDeposit::get() + T::GroupPerMemberDeposit::get() * members.into()
+}
+
+/// Register an owner and a member, and create group 0 with both of them in it.
+fn create_group_with_member<T: Config>() -> (T::AccountId, T::AccountId) {
+	let owner: T::AccountId = account("owner", 0, 0);
+	let member: T::AccountId = account("member", 0, 0);
+	let funds = T::SpamBond::get() * 10u32.into() + group_deposit::<T>(T::MaxGroupMembers::get());
+	for who in [&owner, &member] {
+		let _ = T::Currency::set_balance(who, funds);
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(who.clone()).into(),
+			max_key(),
+		);
+	}
+
+	let group_key: GroupKey = vec![1u8; 256].try_into().unwrap();
+	let _ = Messaging::<T>::create_group(RawOrigin::Signed(owner.clone()).into(), group_key, 2);
+	let _ = Messaging::<T>::add_group_member(RawOrigin::Signed(owner.clone()).into(), 0, member.clone());
+	(owner, member)
+}
+
+/// Register an account and have the owner of group 0 invite it.
+fn invite_to_group_0<T: Config>(owner: &T::AccountId) -> T::AccountId {
+	let invitee: T::AccountId = account("invitee", 0, 0);
+	let _ = T::Currency::set_balance(&invitee, T::SpamBond::get() * 10u32.into());
+	let _ = Messaging::<T>::register_profile(
+		RawOrigin::Signed(invitee.clone()).into(),
+		max_key(),
+	);
+	let _ = Messaging::<T>::invite_to_group(RawOrigin::Signed(owner.clone()).into(), 0, invitee.clone());
+	invitee
+}
+
+/// Let the owner of group 0 list it for discovery, with the longest description CID.
+fn list_group_0<T: Config>(owner: &T::AccountId) {
+	let description_cid: DescriptionCid = vec![1u8; 128].try_into().unwrap();
+	let _ = Messaging::<T>::set_group_metadata(
+		RawOrigin::Signed(owner.clone()).into(),
+		0,
+		T::Hashing::hash_of(&[1u8; 32]),
+		description_cid,
+		true,
+	);
+}
+
+/// Register an account and have it ask to join group 0, listing the group first.
+fn request_to_join_group_0<T: Config>(owner: &T::AccountId) -> T::AccountId {
+	list_group_0::<T>(owner);
+	let requester: T::AccountId = account("requester", 0, 0);
+	let _ = T::Currency::set_balance(&requester, T::SpamBond::get() * 10u32.into());
+	let _ = Messaging::<T>::register_profile(
+		RawOrigin::Signed(requester.clone()).into(),
+		max_key(),
+	);
+	let _ = Messaging::<T>::request_to_join_group(RawOrigin::Signed(requester.clone()).into(), 0);
+	requester
+}
+
+/// Fill the moderation log of group 0, so that the next action evicts the oldest entry.
+fn fill_moderation_log_0<T: Config>(by: &T::AccountId) {
+	let entry = GroupModerationEntry {
+		by: by.clone(),
+		action: GroupModerationAction::Unmute { who: by.clone() },
+		at: frame_system::Pallet::<T>::block_number(),
+	};
+	let log = vec![entry; T::MaxGroupModerationLog::get() as usize];
+	GroupModerationLogs::<T>::insert(0, BoundedVec::truncate_from(log));
+}
+
+/// Register `relay` as a relay with the full `RelayBond`.
+fn register_relay_account<T: Config>(relay: &T::AccountId) {
+	let _ = T::Currency::set_balance(relay, T::RelayBond::get() * 2u32.into());
+	let _ = Messaging::<T>::register_relay(RawOrigin::Signed(relay.clone()).into());
+}
+
+/// Register the whitelisted caller and `n` recipients that approved it as a contact.
+fn recipient_set<T: Config>(n: u32) -> Result<RecipientSet<T::AccountId>, BenchmarkError> {
+	let caller: T::AccountId = whitelisted_caller();
+	let funds = T::SpamBond::get() * 10u32.into() + T::RecipientSetDeposit::get() * n.into();
+	let _ = T::Currency::set_balance(&caller, funds);
+	let _ = Messaging::<T>::register_profile(
+		RawOrigin::Signed(caller.clone()).into(),
+		max_key(),
+	);
+
+	let mut recipients = vec![];
+	for i in 0..n {
+		let recipient: T::AccountId = account("recipient", i, 0);
+		let _ = T::Currency::set_balance(&recipient, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(recipient.clone()).into(),
+			max_key(),
+		);
+		let _ = Messaging::<T>::approve_contact(
+			RawOrigin::Signed(recipient.clone()).into(),
+			caller.clone(),
+		);
+		recipients.push(recipient);
+	}
+	recipients.try_into().map_err(|_| BenchmarkError::Weightless)
+}
+
 #[benchmarks]
 mod benchmarks {
 	use super::*;
 
 	#[benchmark]
-	fn register_profile() {
+	fn register_profile(k: Linear<1, 256>) {
 		let caller: T::AccountId = whitelisted_caller();
-		let public_key = vec![1u8; 32];
+		let public_key = vec![1u8; k as usize];
 		
 		// Fund the caller
 		let bond_amount = T::SpamBond::get();
-		let _ = T::Currency::make_free_balance_be(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+
+		// Worst case: the whitelist is consulted
+		CurrentRegistrationMode::<T>::put(RegistrationMode::Whitelisted);
+		RegistrationWhitelist::<T>::insert(&caller, ());
 
 		#[extrinsic_call]
 		register_profile(RawOrigin::Signed(caller.clone()), public_key);
@@ -29,14 +252,14 @@ mod benchmarks {
 	}
 
 	#[benchmark]
-	fn update_profile() {
+	fn update_profile(k: Linear<1, 256>) {
 		let caller: T::AccountId = whitelisted_caller();
-		let public_key1 = vec![1u8; 32];
-		let public_key2 = vec![2u8; 32];
+		let public_key1 = max_key();
+		let public_key2 = vec![2u8; k as usize];
 		
 		// Setup: register profile first
 		let bond_amount = T::SpamBond::get();
-		let _ = T::Currency::make_free_balance_be(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key1);
 
 		#[extrinsic_call]
@@ -49,59 +272,166 @@ mod benchmarks {
 	fn send_message_hash() {
 		let caller: T::AccountId = whitelisted_caller();
 		let recipient: T::AccountId = account("recipient", 0, 0);
-		let public_key = vec![1u8; 32];
+		let public_key = max_key();
 		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
 		
-		// Setup: register both users
+		// Setup: register both users as mutual contacts
 		let bond_amount = T::SpamBond::get();
-		let _ = T::Currency::make_free_balance_be(&caller, bond_amount * 10u32.into());
-		let _ = T::Currency::make_free_balance_be(&recipient, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key.clone());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(recipient.clone()).into(), public_key);
+		let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(caller.clone()).into(), recipient.clone());
+		let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(recipient.clone()).into(), caller.clone());
+
+		// Worst case: the rate-limit window and the conversation are already in use, so both
+		// are updated rather than created
+		let earlier_hash = T::Hashing::hash_of(&[0u8; 32]);
+		let _ = Messaging::<T>::send_message_hash(RawOrigin::Signed(caller.clone()).into(), recipient.clone(), earlier_hash);
+		let conversation_id = Messaging::<T>::conversation_id(&caller, &recipient);
+
+		// A protocol fee is withdrawn and routed
+		charge_protocol_fee::<T>();
+
+		// The recipient opted in to push notifications
+		NotificationHooks::<T>::insert(&recipient, NotificationHint::truncate_from(vec![1u8; 64]));
+
+		// The recipient requires a session, which it initiated, so both directions are read
+		UserProfiles::<T>::mutate(&recipient, |profile| {
+			if let Some(profile) = profile {
+				profile.session_required = true;
+			}
+		});
+		Sessions::<T>::insert(
+			&recipient,
+			&caller,
+			SessionInfo {
+				commitment: T::Hashing::hash_of(&[2u8; 32]),
+				prekey_index: Some(0),
+				established_at: BlockNumberFor::<T>::zero(),
+				epoch: 0,
+				status: SessionStatus::Confirmed,
+			},
+		);
+
+		// The sender was flagged dormant, so sending writes its profile back
+		UserProfiles::<T>::mutate(&caller, |profile| {
+			if let Some(profile) = profile {
+				profile.dormant_since = Some(BlockNumberFor::<T>::zero());
+			}
+		});
 
 		#[extrinsic_call]
-		send_message_hash(RawOrigin::Signed(caller), recipient, message_hash);
+		send_message_hash(RawOrigin::Signed(caller.clone()), recipient.clone(), message_hash);
 
-		assert_eq!(NextMessageId::<T>::get(), 1);
+		assert_eq!(NextMessageId::<T>::get(), 2);
+		assert_eq!(ConversationStats::<T>::get(conversation_id).next_seq, 2);
+		assert_eq!(Messaging::<T>::profile_activity(caller.clone()).unwrap().dormant_since, None);
+		assert_eq!(
+			MessageHashes::<T>::get(1).map(|record| (record.sender, record.recipient)),
+			Some((caller, recipient))
+		);
+	}
+
+	#[benchmark]
+	fn send_message_hash_batch(
+		n: Linear<1, { T::MaxSendBatch::get() }>,
+	) -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), max_key());
+		charge_protocol_fee::<T>();
+
+		// Worst case for each recipient, as in `send_message_hash`: mutual contacts with a
+		// notification hint, requiring a session that they initiated
+		let mut messages = Vec::new();
+		for i in 0..n {
+			let recipient: T::AccountId = account("recipient", i, 0);
+			let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
+			let _ = Messaging::<T>::register_profile(RawOrigin::Signed(recipient.clone()).into(), max_key());
+			let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(caller.clone()).into(), recipient.clone());
+			let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(recipient.clone()).into(), caller.clone());
+			NotificationHooks::<T>::insert(&recipient, NotificationHint::truncate_from(vec![1u8; 64]));
+			UserProfiles::<T>::mutate(&recipient, |profile| {
+				if let Some(profile) = profile {
+					profile.session_required = true;
+				}
+			});
+			Sessions::<T>::insert(
+				&recipient,
+				&caller,
+				SessionInfo {
+					commitment: T::Hashing::hash_of(&[2u8; 32]),
+					prekey_index: Some(0),
+					established_at: BlockNumberFor::<T>::zero(),
+					epoch: 0,
+					status: SessionStatus::Confirmed,
+				},
+			);
+			messages.push((recipient, T::Hashing::hash_of(&i)));
+		}
+		let messages: SendBatchOf<T> =
+			messages.try_into().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		send_message_hash_batch(RawOrigin::Signed(caller), messages);
+
+		assert_eq!(NextMessageId::<T>::get(), u64::from(n));
+		Ok(())
 	}
 
 	#[benchmark]
 	fn approve_contact() {
 		let caller: T::AccountId = whitelisted_caller();
 		let contact: T::AccountId = account("contact", 0, 0);
-		let public_key = vec![1u8; 32];
+		let public_key = max_key();
 		
 		// Setup: register both users
 		let bond_amount = T::SpamBond::get();
-		let _ = T::Currency::make_free_balance_be(&caller, bond_amount * 10u32.into());
-		let _ = T::Currency::make_free_balance_be(&contact, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&contact, bond_amount * 10u32.into());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key.clone());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(contact.clone()).into(), public_key);
 
+		// Worst case: the new contact takes the last free slot, and the request drops the
+		// oldest of full request indexes
+		let max_contacts = T::MaxContactsPerUser::get();
+		fill_contacts::<T>(&caller, max_contacts.saturating_sub(1));
+		fill_contact_requests::<T>(&caller, &contact);
+
 		#[extrinsic_call]
 		approve_contact(RawOrigin::Signed(caller.clone()), contact.clone());
 
 		assert!(ApprovedContacts::<T>::get(&caller, &contact));
+		assert_eq!(ContactCount::<T>::get(&caller), max_contacts);
+		assert!(IncomingContactRequests::<T>::get(&contact).iter().any(|(who, _)| *who == caller));
 	}
 
 	#[benchmark]
 	fn remove_contact() {
 		let caller: T::AccountId = whitelisted_caller();
 		let contact: T::AccountId = account("contact", 0, 0);
-		let public_key = vec![1u8; 32];
+		let public_key = max_key();
 		
 		// Setup: register both users and approve contact
 		let bond_amount = T::SpamBond::get();
-		let _ = T::Currency::make_free_balance_be(&caller, bond_amount * 10u32.into());
-		let _ = T::Currency::make_free_balance_be(&contact, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&contact, bond_amount * 10u32.into());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key.clone());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(contact.clone()).into(), public_key);
+
+		// Worst case: the contact is removed from a full list
+		let max_contacts = T::MaxContactsPerUser::get();
+		fill_contacts::<T>(&caller, max_contacts.saturating_sub(1));
 		let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(caller.clone()).into(), contact.clone());
 
 		#[extrinsic_call]
 		remove_contact(RawOrigin::Signed(caller.clone()), contact.clone());
 
 		assert!(!ApprovedContacts::<T>::get(&caller, &contact));
+		assert_eq!(ContactCount::<T>::get(&caller), max_contacts.saturating_sub(1));
+		assert!(IncomingContactRequests::<T>::get(&contact).is_empty());
 	}
 
 	#[benchmark]
@@ -109,38 +439,2335 @@ mod benchmarks {
 		let sender: T::AccountId = account("sender", 0, 0);
 		let recipient: T::AccountId = account("recipient", 0, 0);
 		let challenger: T::AccountId = whitelisted_caller();
-		let public_key = vec![1u8; 32];
+		let public_key = max_key();
 		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
 		
 		// Setup: register users and send message
 		let bond_amount = T::SpamBond::get();
-		let _ = T::Currency::make_free_balance_be(&sender, bond_amount * 10u32.into());
-		let _ = T::Currency::make_free_balance_be(&recipient, bond_amount * 10u32.into());
-		let _ = T::Currency::make_free_balance_be(&challenger, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&sender, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&challenger, T::ChallengeBond::get() * 10u32.into());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(sender.clone()).into(), public_key.clone());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(recipient.clone()).into(), public_key);
+		let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(recipient.clone()).into(), sender.clone());
 		let _ = Messaging::<T>::send_message_hash(RawOrigin::Signed(sender).into(), recipient, message_hash);
 		
 		let message_id = 0;
 
 		#[extrinsic_call]
-		challenge_spam(RawOrigin::Signed(challenger), message_id);
+		challenge_spam(RawOrigin::Signed(challenger), message_id, T::Hash::default());
 	}
 
 	#[benchmark]
 	fn refund_bond() {
 		let caller: T::AccountId = whitelisted_caller();
-		let public_key = vec![1u8; 32];
+		let public_key = max_key();
 		
 		// Setup: register profile
 		let bond_amount = T::SpamBond::get();
-		let _ = T::Currency::make_free_balance_be(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
 		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key);
 
 		#[extrinsic_call]
 		refund_bond(RawOrigin::Signed(caller.clone()));
 
-		assert_eq!(SpamBonds::<T>::get(&caller), BalanceOf::<T>::default());
+		assert_eq!(SpamBonds::<T>::get(&caller).amount, BalanceOf::<T>::default());
+	}
+
+	#[benchmark]
+	fn resolve_challenge() -> Result<(), BenchmarkError> {
+		let challenger: T::AccountId = whitelisted_caller();
+
+		// Setup: register users, send message and open a challenge
+		let sender = open_challenge::<T>(&challenger);
+
+		let origin =
+			T::ResolutionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		resolve_challenge(origin as T::RuntimeOrigin, 0, true);
+
+		assert!(matches!(
+			Challenges::<T>::get(0).map(|c| c.status),
+			Some(ChallengeStatus::Resolved { upheld: true, .. })
+		));
+		assert_eq!(Reputation::<T>::get(&sender).strikes, 1);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn top_up_bond() {
+		let caller: T::AccountId = whitelisted_caller();
+		let public_key = max_key();
+
+		// Setup: register profile
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key);
+
+		#[extrinsic_call]
+		top_up_bond(RawOrigin::Signed(caller.clone()), bond_amount);
+
+		assert_eq!(SpamBonds::<T>::get(&caller).amount, bond_amount * 2u32.into());
+	}
+
+	#[benchmark]
+	fn withdraw_excess_bond() {
+		let caller: T::AccountId = whitelisted_caller();
+		let public_key = max_key();
+
+		// Setup: register profile and top up the bond
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key);
+		let _ = Messaging::<T>::top_up_bond(RawOrigin::Signed(caller.clone()).into(), bond_amount);
+
+		#[extrinsic_call]
+		withdraw_excess_bond(RawOrigin::Signed(caller.clone()));
+
+		assert_eq!(SpamBonds::<T>::get(&caller).amount, bond_amount);
+	}
+
+	#[benchmark]
+	fn appeal_challenge() -> Result<(), BenchmarkError> {
+		let challenger: T::AccountId = account("challenger", 0, 0);
+
+		// Setup: open a challenge and uphold it
+		let sender = open_challenge::<T>(&challenger);
+		let _ = T::Currency::set_balance(&sender, T::AppealBond::get() * 10u32.into());
+		let origin =
+			T::ResolutionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let _ = Messaging::<T>::resolve_challenge(origin, 0, true);
+
+		#[extrinsic_call]
+		appeal_challenge(RawOrigin::Signed(sender), 0);
+
+		assert!(matches!(
+			Challenges::<T>::get(0).map(|c| c.status),
+			Some(ChallengeStatus::Appealed { .. })
+		));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn resolve_appeal() -> Result<(), BenchmarkError> {
+		let challenger: T::AccountId = account("challenger", 0, 0);
+
+		// Setup: uphold a challenge and have the sender appeal it
+		let sender = open_challenge::<T>(&challenger);
+		let _ = T::Currency::set_balance(&sender, T::AppealBond::get() * 10u32.into());
+		let origin =
+			T::ResolutionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let _ = Messaging::<T>::resolve_challenge(origin, 0, true);
+		let _ = Messaging::<T>::appeal_challenge(RawOrigin::Signed(sender.clone()).into(), 0);
+
+		let origin =
+			T::AppealOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		// Worst case: the verdict is overturned and the slash reversed
+		#[extrinsic_call]
+		resolve_appeal(origin as T::RuntimeOrigin, 0, false);
+
+		assert!(!Challenges::<T>::contains_key(0));
+		assert_eq!(Reputation::<T>::get(&sender).strikes, 0);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn finalize_challenge() -> Result<(), BenchmarkError> {
+		let challenger: T::AccountId = whitelisted_caller();
+
+		// Setup: uphold a challenge and let the appeal window pass, with a funded reward pool
+		open_challenge::<T>(&challenger);
+		let _ = T::Currency::set_balance(
+			&Messaging::<T>::reward_pool_account(),
+			T::BaseChallengeReward::get() * 10u32.into(),
+		);
+		let origin =
+			T::ResolutionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let _ = Messaging::<T>::resolve_challenge(origin, 0, true);
+		let now = frame_system::Pallet::<T>::block_number();
+		frame_system::Pallet::<T>::set_block_number(
+			now.saturating_add(T::AppealWindow::get()).saturating_add(1u32.into()),
+		);
+
+		#[extrinsic_call]
+		finalize_challenge(RawOrigin::Signed(challenger), 0);
+
+		assert!(!Challenges::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn report_spam() {
+		let sender: T::AccountId = account("sender", 0, 0);
+		let reporter: T::AccountId = whitelisted_caller();
+		let public_key = max_key();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Setup: register users, send message and bring the sender to the report threshold
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&sender, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&reporter, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(sender.clone()).into(), public_key.clone());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(reporter.clone()).into(), public_key);
+		let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(reporter.clone()).into(), sender.clone());
+		let _ = Messaging::<T>::send_message_hash(RawOrigin::Signed(sender.clone()).into(), reporter.clone(), message_hash);
+		SpamReports::<T>::insert(
+			&sender,
+			SpamReportRecord {
+				reports: T::AutoChallengeThreshold::get().saturating_sub(1),
+				last_decay: frame_system::Pallet::<T>::block_number(),
+			},
+		);
+
+		#[extrinsic_call]
+		report_spam(RawOrigin::Signed(reporter), 0);
+
+		assert!(Challenges::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn expire_challenge() {
+		let challenger: T::AccountId = whitelisted_caller();
+
+		// Setup: open a challenge and let it outlive its maximum duration
+		open_challenge::<T>(&challenger);
+		let now = frame_system::Pallet::<T>::block_number();
+		frame_system::Pallet::<T>::set_block_number(
+			now.saturating_add(T::ChallengeMaxDuration::get()).saturating_add(1u32.into()),
+		);
+
+		#[extrinsic_call]
+		expire_challenge(RawOrigin::Signed(challenger), 0);
+
+		assert!(!Challenges::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn set_inbox_price() {
+		let caller: T::AccountId = whitelisted_caller();
+		let public_key = max_key();
+
+		// Setup: register profile
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key);
+
+		#[extrinsic_call]
+		set_inbox_price(RawOrigin::Signed(caller.clone()), Some(bond_amount));
+
+		assert_eq!(InboxPrices::<T>::get(&caller), Some(bond_amount));
+	}
+
+	#[benchmark]
+	fn send_paid_message_hash() {
+		let caller: T::AccountId = whitelisted_caller();
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		let public_key = max_key();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Setup: register both users and set the recipient's inbox price
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key.clone());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(recipient.clone()).into(), public_key);
+		let _ = Messaging::<T>::set_inbox_price(RawOrigin::Signed(recipient.clone()).into(), Some(bond_amount));
+		charge_protocol_fee::<T>();
+
+		#[extrinsic_call]
+		send_paid_message_hash(RawOrigin::Signed(caller), recipient, message_hash);
+
+		assert!(InboxPayments::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn acknowledge_message() {
+		// Setup: send a paid message
+		let (sender, recipient) = send_paid_message::<T>();
+
+		// Worst case: a pass is granted as well
+		#[extrinsic_call]
+		acknowledge_message(RawOrigin::Signed(recipient.clone()), 0, true);
+
+		assert!(!InboxPayments::<T>::contains_key(0));
+		assert!(InboxPasses::<T>::contains_key(&recipient, &sender));
+	}
+
+	#[benchmark]
+	fn retract_paid_message() {
+		// Setup: send a paid message
+		let (sender, _) = send_paid_message::<T>();
+
+		#[extrinsic_call]
+		retract_paid_message(RawOrigin::Signed(sender), 0);
+
+		assert!(!InboxPayments::<T>::contains_key(0));
+		assert!(!MessageHashes::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn refund_inbox_payment() {
+		let caller: T::AccountId = whitelisted_caller();
+
+		// Setup: send a paid message and let it expire
+		send_paid_message::<T>();
+		let now = frame_system::Pallet::<T>::block_number();
+		frame_system::Pallet::<T>::set_block_number(
+			now.saturating_add(T::MessageHashExpiry::get()).saturating_add(1u32.into()),
+		);
+
+		#[extrinsic_call]
+		refund_inbox_payment(RawOrigin::Signed(caller), 0);
+
+		assert!(!InboxPayments::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn pause() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		pause(origin as T::RuntimeOrigin, PausedCalls::ALL);
+
+		assert_eq!(Paused::<T>::get(), PausedCalls::ALL);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn unpause() -> Result<(), BenchmarkError> {
+		Paused::<T>::put(PausedCalls::ALL);
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		unpause(origin as T::RuntimeOrigin, PausedCalls::ALL);
+
+		assert!(Paused::<T>::get().is_empty());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn force_remove_message() -> Result<(), BenchmarkError> {
+		// Setup: send a paid message so its payment is refunded as well
+		send_paid_message::<T>();
+		let origin =
+			T::ForceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let order_reference: OrderReference = vec![1u8; 64].try_into().unwrap();
+
+		#[extrinsic_call]
+		force_remove_message(origin as T::RuntimeOrigin, 0, order_reference);
+
+		assert!(!MessageHashes::<T>::contains_key(0));
+		assert!(!InboxPayments::<T>::contains_key(0));
+		assert!(Takedowns::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn force_remove_group_message() -> Result<(), BenchmarkError> {
+		let (_, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::send_group_message_hash(
+			RawOrigin::Signed(member).into(),
+			0,
+			T::Hashing::hash_of(&[1u8; 32]),
+		);
+		let origin =
+			T::ForceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let order_reference: OrderReference = vec![1u8; 64].try_into().unwrap();
+
+		#[extrinsic_call]
+		force_remove_group_message(origin as T::RuntimeOrigin, 0, order_reference);
+
+		assert!(!GroupMessages::<T>::contains_key(0, 0));
+		assert!(!GroupMessagesBySeq::<T>::contains_key(0, 0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn update_parameters() -> Result<(), BenchmarkError> {
+		let mut new = Messaging::<T>::parameters();
+		new.max_contacts = new.max_contacts.saturating_add(1);
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		update_parameters(origin as T::RuntimeOrigin, new.clone());
+
+		assert_eq!(Parameters::<T>::get(), Some(new));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn add_moderator() -> Result<(), BenchmarkError> {
+		let moderator: T::AccountId = account("moderator", 0, 0);
+
+		// Worst case: the set is one short of full
+		for i in 1..32 {
+			let _ = Moderators::<T>::try_append(account::<T::AccountId>("moderator", i, 0));
+		}
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		add_moderator(origin as T::RuntimeOrigin, moderator.clone());
+
+		assert!(Messaging::<T>::is_moderator(&moderator));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_moderator() -> Result<(), BenchmarkError> {
+		let moderator: T::AccountId = account("moderator", 0, 0);
+
+		// Worst case: the moderator is last in a full set
+		for i in 1..32 {
+			let _ = Moderators::<T>::try_append(account::<T::AccountId>("moderator", i, 0));
+		}
+		let _ = Moderators::<T>::try_append(moderator.clone());
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		remove_moderator(origin as T::RuntimeOrigin, moderator.clone());
+
+		assert!(!Messaging::<T>::is_moderator(&moderator));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn moderator_challenge() {
+		let moderator: T::AccountId = whitelisted_caller();
+		let sender: T::AccountId = account("sender", 0, 0);
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		let public_key = max_key();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Setup: register users, send message and appoint the moderator
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&sender, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(sender.clone()).into(), public_key.clone());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(recipient.clone()).into(), public_key);
+		let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(recipient.clone()).into(), sender.clone());
+		let _ = Messaging::<T>::send_message_hash(RawOrigin::Signed(sender).into(), recipient, message_hash);
+		let _ = Moderators::<T>::try_append(moderator.clone());
+
+		#[extrinsic_call]
+		moderator_challenge(RawOrigin::Signed(moderator), 0);
+
+		assert!(Challenges::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn fast_track_challenge() {
+		let moderator: T::AccountId = whitelisted_caller();
+		let sender: T::AccountId = account("sender", 0, 0);
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		let public_key = max_key();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Setup: open a challenge through the moderator
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&sender, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(sender.clone()).into(), public_key.clone());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(recipient.clone()).into(), public_key);
+		let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(recipient.clone()).into(), sender.clone());
+		let _ = Messaging::<T>::send_message_hash(RawOrigin::Signed(sender.clone()).into(), recipient, message_hash);
+		let _ = Moderators::<T>::try_append(moderator.clone());
+		let _ = Messaging::<T>::moderator_challenge(RawOrigin::Signed(moderator.clone()).into(), 0);
+
+		#[extrinsic_call]
+		fast_track_challenge(RawOrigin::Signed(moderator), 0, true);
+
+		assert_eq!(Reputation::<T>::get(&sender).strikes, 1);
+	}
+
+	#[benchmark]
+	fn freeze_sender() {
+		let moderator: T::AccountId = whitelisted_caller();
+		let target: T::AccountId = account("target", 0, 0);
+		let _ = Moderators::<T>::try_append(moderator.clone());
+
+		#[extrinsic_call]
+		freeze_sender(RawOrigin::Signed(moderator), target.clone(), T::MaxFreezeDuration::get());
+
+		assert!(Messaging::<T>::is_frozen(&target));
+	}
+
+	#[benchmark]
+	fn unfreeze_sender() {
+		let moderator: T::AccountId = whitelisted_caller();
+		let target: T::AccountId = account("target", 0, 0);
+		let _ = Moderators::<T>::try_append(moderator.clone());
+		let _ = Messaging::<T>::freeze_sender(
+			RawOrigin::Signed(moderator.clone()).into(),
+			target.clone(),
+			T::MaxFreezeDuration::get(),
+		);
+
+		#[extrinsic_call]
+		unfreeze_sender(RawOrigin::Signed(moderator), target.clone());
+
+		assert!(!Messaging::<T>::is_frozen(&target));
+	}
+
+	#[benchmark]
+	fn ban_account() -> Result<(), BenchmarkError> {
+		let target: T::AccountId = account("target", 0, 0);
+		let public_key = max_key();
+
+		// Setup: register a profile with an inbox price
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&target, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(target.clone()).into(), public_key);
+		let _ = Messaging::<T>::set_inbox_price(RawOrigin::Signed(target.clone()).into(), Some(bond_amount));
+		let origin =
+			T::ForceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		ban_account(origin as T::RuntimeOrigin, target.clone(), T::Hashing::hash_of(&[1u8; 32]));
+
+		assert!(BannedAccounts::<T>::contains_key(&target));
+		assert!(!UserProfiles::<T>::contains_key(&target));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn unban_account() -> Result<(), BenchmarkError> {
+		let target: T::AccountId = account("target", 0, 0);
+		BannedAccounts::<T>::insert(
+			&target,
+			BanRecord {
+				at: frame_system::Pallet::<T>::block_number(),
+				reason: T::Hashing::hash_of(&[1u8; 32]),
+			},
+		);
+		let origin =
+			T::ForceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		unban_account(origin as T::RuntimeOrigin, target.clone());
+
+		assert!(!BannedAccounts::<T>::contains_key(&target));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn reset_income() -> Result<(), BenchmarkError> {
+		PalletIncome::<T>::insert(IncomeCategory::Slash, T::SpamBond::get());
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		reset_income(origin as T::RuntimeOrigin, IncomeCategory::Slash);
+
+		assert!(PalletIncome::<T>::get(IncomeCategory::Slash).is_zero());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn force_reset_key(n: Linear<0, 100>) -> Result<(), BenchmarkError> {
+		let target: T::AccountId = account("target", 0, 0);
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&target, bond_amount * 10u32.into());
+		let public_key = max_key();
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(target.clone()).into(), public_key);
+
+		// Setup: `n` mutual contacts to notify
+		for i in 0..n {
+			let contact: T::AccountId = account("contact", i, 0);
+			ApprovedContacts::<T>::insert(&target, &contact, true);
+			ApprovedContacts::<T>::insert(&contact, &target, true);
+		}
+		let origin =
+			T::ForceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		force_reset_key(
+			origin as T::RuntimeOrigin,
+			target.clone(),
+			vec![2u8; 256],
+			KeyAlgorithm::X25519,
+		);
+
+		assert_eq!(KeyVersions::<T>::get(&target), 1);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn continue_key_reset_notices(n: Linear<0, 100>) {
+		let caller: T::AccountId = whitelisted_caller();
+		let target: T::AccountId = account("target", 0, 0);
+
+		// Setup: `n` mutual contacts still to notify
+		for i in 0..n {
+			let contact: T::AccountId = account("contact", i, 0);
+			ApprovedContacts::<T>::insert(&target, &contact, true);
+			ApprovedContacts::<T>::insert(&contact, &target, true);
+		}
+		KeyResetCursors::<T>::insert(&target, BoundedVec::default());
+
+		#[extrinsic_call]
+		continue_key_reset_notices(RawOrigin::Signed(caller), target.clone());
+	}
+
+	#[benchmark]
+	fn prune_takedown() {
+		let caller: T::AccountId = whitelisted_caller();
+		let order_reference: OrderReference = vec![1u8; 64].try_into().unwrap();
+		Takedowns::<T>::insert(
+			0,
+			TakedownRecord {
+				sender: account("sender", 0, 0),
+				recipient: account("recipient", 0, 0),
+				removed_at: frame_system::Pallet::<T>::block_number(),
+				order_reference,
+			},
+		);
+		frame_system::Pallet::<T>::set_block_number(
+			frame_system::Pallet::<T>::block_number() + T::TakedownRetention::get() + 1u32.into(),
+		);
+
+		#[extrinsic_call]
+		prune_takedown(RawOrigin::Signed(caller), 0);
+
+		assert!(!Takedowns::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn set_registration_mode() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		set_registration_mode(origin as T::RuntimeOrigin, RegistrationMode::Whitelisted);
+
+		assert_eq!(CurrentRegistrationMode::<T>::get(), RegistrationMode::Whitelisted);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn whitelist_account() -> Result<(), BenchmarkError> {
+		let target: T::AccountId = account("target", 0, 0);
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		whitelist_account(origin as T::RuntimeOrigin, target.clone());
+
+		assert!(RegistrationWhitelist::<T>::contains_key(&target));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_from_whitelist() -> Result<(), BenchmarkError> {
+		let target: T::AccountId = account("target", 0, 0);
+		RegistrationWhitelist::<T>::insert(&target, ());
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		remove_from_whitelist(origin as T::RuntimeOrigin, target.clone());
+
+		assert!(!RegistrationWhitelist::<T>::contains_key(&target));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn force_register_profile(k: Linear<1, 256>) -> Result<(), BenchmarkError> {
+		let target: T::AccountId = account("target", 0, 0);
+		let sponsor: T::AccountId = account("sponsor", 0, 0);
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&sponsor, bond_amount * 10u32.into());
+		let origin =
+			T::ForceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		force_register_profile(
+			origin as T::RuntimeOrigin,
+			target.clone(),
+			vec![1u8; k as usize],
+			KeyAlgorithm::X25519,
+			Some(sponsor),
+		);
+
+		assert!(UserProfiles::<T>::contains_key(&target));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn prune_expired_unsigned(n: Linear<1, { T::MaxPrunesPerCall::get() }>) {
+		let sender: T::AccountId = account("sender", 0, 0);
+		let recipient: T::AccountId = account("recipient", 0, 0);
+
+		// Setup: `n` messages, all expired, split between reported and indexed direct messages
+		// and group messages
+		let direct = (n / 2) as MessageId;
+		for id in 0..direct {
+			let hash = T::Hashing::hash_of(&id);
+			MessageHashes::<T>::insert(
+				id,
+				MessageRecord {
+					sender: sender.clone(),
+					recipient: recipient.clone(),
+					hash,
+					sent_at: BlockNumberFor::<T>::zero(),
+				},
+			);
+			MessageIdByHash::<T>::insert(hash, id);
+			ReportedMessages::<T>::insert(id, &recipient, true);
+		}
+		let group = n as MessageId - direct;
+		for id in 0..group {
+			GroupMessages::<T>::insert(
+				0,
+				id,
+				GroupMessageRecord {
+					hash: T::Hash::default(),
+					sender: sender.clone(),
+					sent_at: BlockNumberFor::<T>::zero(),
+					key_version: 0,
+					expires_at: T::GroupMessageExpiry::get(),
+					seq: id,
+				},
+			);
+			GroupMessageIds::<T>::insert(id, 0);
+			GroupMessagesBySeq::<T>::insert(0, id, id);
+		}
+		NextMessageId::<T>::put(direct);
+		NextGroupMessageId::<T>::put(group);
+		let now = Messaging::<T>::parameters().message_expiry.max(T::GroupMessageExpiry::get()) +
+			1u32.into();
+		frame_system::Pallet::<T>::set_block_number(now);
+
+		#[extrinsic_call]
+		prune_expired_unsigned(RawOrigin::None, now);
+
+		assert_eq!(PruneCursor::<T>::get(), direct);
+		assert_eq!(GroupPruneCursor::<T>::get(), group);
+	}
+
+	#[benchmark]
+	fn prune_direct_messages(n: Linear<0, { T::MaxPrunesPerCall::get() }>) {
+		let sender: T::AccountId = account("sender", 0, 0);
+		let recipient: T::AccountId = account("recipient", 0, 0);
+
+		// Setup: `n` expired, reported and indexed direct messages
+		for id in 0..n as MessageId {
+			let hash = T::Hashing::hash_of(&id);
+			MessageHashes::<T>::insert(
+				id,
+				MessageRecord {
+					sender: sender.clone(),
+					recipient: recipient.clone(),
+					hash,
+					sent_at: BlockNumberFor::<T>::zero(),
+				},
+			);
+			MessageIdByHash::<T>::insert(hash, id);
+			ReportedMessages::<T>::insert(id, &recipient, true);
+		}
+		NextMessageId::<T>::put(n as MessageId);
+		let now = Messaging::<T>::parameters().message_expiry + 1u32.into();
+		frame_system::Pallet::<T>::set_block_number(now);
+
+		#[block]
+		{
+			Messaging::<T>::prune_expired(now, n, &mut WeightMeter::new());
+		}
+
+		assert_eq!(PruneCursor::<T>::get(), n as MessageId);
+	}
+
+	#[benchmark]
+	fn prune_group_messages(n: Linear<0, { T::MaxPrunesPerCall::get() }>) {
+		let sender: T::AccountId = account("sender", 0, 0);
+
+		// Setup: `n` expired group messages
+		for id in 0..n as MessageId {
+			GroupMessages::<T>::insert(
+				0,
+				id,
+				GroupMessageRecord {
+					hash: T::Hash::default(),
+					sender: sender.clone(),
+					sent_at: BlockNumberFor::<T>::zero(),
+					key_version: 0,
+					expires_at: T::GroupMessageExpiry::get(),
+					seq: id,
+				},
+			);
+			GroupMessageIds::<T>::insert(id, 0);
+			GroupMessagesBySeq::<T>::insert(0, id, id);
+		}
+		NextGroupMessageId::<T>::put(n as MessageId);
+		let now = T::GroupMessageExpiry::get() + 1u32.into();
+		frame_system::Pallet::<T>::set_block_number(now);
+
+		#[block]
+		{
+			Messaging::<T>::prune_expired_group_messages(now, n, &mut WeightMeter::new());
+		}
+
+		assert_eq!(GroupPruneCursor::<T>::get(), n as MessageId);
+	}
+
+	#[benchmark]
+	fn prune_index_entries(n: Linear<0, { T::MaxPrunesPerCall::get() }>) {
+		// Setup: `n` expired markers of retracted messages, found after the message maps
+		// were searched in vain
+		for id in 0..n as MessageId {
+			RetractedMessages::<T>::insert(id, BlockNumberFor::<T>::zero());
+		}
+		NextMessageId::<T>::put(n as MessageId);
+		let now = Messaging::<T>::parameters().message_expiry + 1u32.into();
+		frame_system::Pallet::<T>::set_block_number(now);
+
+		#[block]
+		{
+			Messaging::<T>::prune_expired(now, n, &mut WeightMeter::new());
+		}
+
+		assert_eq!(PruneCursor::<T>::get(), n as MessageId);
+		assert_eq!(RetractedMessages::<T>::iter_keys().count(), 0);
+	}
+
+	#[benchmark]
+	fn receive_profile() -> Result<(), BenchmarkError> {
+		let origin =
+			T::XcmOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let dest = T::XcmOrigin::ensure_origin(origin.clone())
+			.map_err(|_| BenchmarkError::Weightless)?;
+		let who: T::AccountId = account("who", 0, 0);
+		ProfileQueries::<T>::insert(
+			0,
+			ProfileQuery {
+				dest: dest.clone(),
+				who: who.clone(),
+				expires_at: frame_system::Pallet::<T>::block_number(),
+			},
+		);
+
+		// Worst case: the longest key is cached
+		let profile = RemoteProfileV1 {
+			public_key: vec![1u8; 256].try_into().unwrap(),
+			algorithm: KeyAlgorithm::X25519,
+			key_version: 0,
+		};
+
+		#[extrinsic_call]
+		receive_profile(origin as T::RuntimeOrigin, 0, VersionedRemoteProfile::V1(Some(profile)));
+
+		assert!(RemoteProfiles::<T>::contains_key(&dest, &who));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn expire_profile_query() {
+		let caller: T::AccountId = whitelisted_caller();
+		let now = frame_system::Pallet::<T>::block_number();
+		ProfileQueries::<T>::insert(
+			0,
+			ProfileQuery { dest: Location::here(), who: account("who", 0, 0), expires_at: now },
+		);
+		frame_system::Pallet::<T>::set_block_number(now + 1u32.into());
+
+		#[extrinsic_call]
+		expire_profile_query(RawOrigin::Signed(caller), 0);
+
+		assert!(!ProfileQueries::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn accept_and_send() {
+		let caller: T::AccountId = whitelisted_caller();
+		let requester: T::AccountId = account("requester", 0, 0);
+		let public_key = max_key();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Setup: register both users and request the contact
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&requester, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(caller.clone()).into(), public_key.clone());
+		let _ = Messaging::<T>::register_profile(RawOrigin::Signed(requester.clone()).into(), public_key);
+		let _ = Messaging::<T>::approve_contact(RawOrigin::Signed(requester.clone()).into(), caller.clone());
+		charge_protocol_fee::<T>();
+
+		#[extrinsic_call]
+		accept_and_send(RawOrigin::Signed(caller.clone()), requester.clone(), message_hash);
+
+		assert!(ApprovedContacts::<T>::get(&caller, &requester));
+		assert_eq!(NextMessageId::<T>::get(), 1);
+	}
+
+	#[benchmark]
+	fn register_and_approve(k: Linear<1, 256>) {
+		let caller: T::AccountId = whitelisted_caller();
+		let contact: T::AccountId = account("contact", 0, 0);
+		let public_key = vec![1u8; k as usize];
+
+		// Setup: register the contact and fund the caller
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&contact, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(contact.clone()).into(),
+			max_key(),
+		);
+
+		// Worst case: the whitelist is consulted
+		CurrentRegistrationMode::<T>::put(RegistrationMode::Whitelisted);
+		RegistrationWhitelist::<T>::insert(&caller, ());
+
+		#[extrinsic_call]
+		register_and_approve(RawOrigin::Signed(caller.clone()), public_key, KeyAlgorithm::X25519, contact.clone());
+
+		assert!(UserProfiles::<T>::contains_key(&caller));
+		assert!(ApprovedContacts::<T>::get(&caller, &contact));
+	}
+
+	#[benchmark]
+	fn migrate_message_record() {
+		let sender: T::AccountId = account("sender", 0, 0);
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Setup: two messages in the pre-v2 layout, and weight for rewriting one of them
+		StorageVersion::new(1).put::<Messaging<T>>();
+		for message_id in 0..2 {
+			migrations::v2::MessageHashes::<T>::insert(
+				message_id,
+				(message_hash, BlockNumberFor::<T>::zero(), sender.clone(), recipient.clone()),
+			);
+		}
+		let mut meter = WeightMeter::with_limit(T::WeightInfo::migrate_message_record());
+
+		#[block]
+		{
+			let _ = migrations::v2::LazyMigrateV1ToV2::<T>::step(None, &mut meter);
+		}
+
+		assert!(MessageMigrationCursor::<T>::get().is_some());
+		assert_eq!(Messaging::<T>::message_count(), 1);
+	}
+
+	#[benchmark]
+	fn create_group() {
+		let caller: T::AccountId = whitelisted_caller();
+		let group_key: GroupKey = vec![1u8; 256].try_into().unwrap();
+
+		// Setup: register the caller and fund the group deposit
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(
+			&caller,
+			bond_amount * 10u32.into() + group_deposit::<T>(1),
+		);
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+
+		#[extrinsic_call]
+		create_group(RawOrigin::Signed(caller.clone()), group_key, T::MaxGroupMembers::get());
+
+		assert!(Groups::<T>::contains_key(0));
+		assert_eq!(GroupCount::<T>::get(&caller), 1);
+	}
+
+	#[benchmark]
+	fn add_group_member() {
+		let (owner, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::remove_group_member(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			member.clone(),
+		);
+
+		#[extrinsic_call]
+		add_group_member(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert!(GroupMembers::<T>::contains_key(0, &member));
+	}
+
+	#[benchmark]
+	fn remove_group_member() {
+		let (owner, member) = create_group_with_member::<T>();
+
+		// Worst case: the member is an admin with a pending ownership offer
+		let _ = Messaging::<T>::promote_member(RawOrigin::Signed(owner.clone()).into(), 0, member.clone());
+		let _ = Messaging::<T>::transfer_group_ownership(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			member.clone(),
+		);
+
+		#[extrinsic_call]
+		remove_group_member(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert!(!GroupMembers::<T>::contains_key(0, &member));
+		assert!(!PendingGroupOwners::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn promote_member() {
+		let (owner, member) = create_group_with_member::<T>();
+
+		#[extrinsic_call]
+		promote_member(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert_eq!(Messaging::<T>::group_role(0, &member), Some(GroupRole::Admin));
+	}
+
+	#[benchmark]
+	fn demote_member() {
+		let (owner, member) = create_group_with_member::<T>();
+
+		// Worst case: the admin has a pending ownership offer
+		let _ = Messaging::<T>::promote_member(RawOrigin::Signed(owner.clone()).into(), 0, member.clone());
+		let _ = Messaging::<T>::transfer_group_ownership(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			member.clone(),
+		);
+
+		#[extrinsic_call]
+		demote_member(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert_eq!(Messaging::<T>::group_role(0, &member), Some(GroupRole::Member));
+		assert!(!PendingGroupOwners::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn transfer_group_ownership() {
+		let (owner, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::promote_member(RawOrigin::Signed(owner.clone()).into(), 0, member.clone());
+
+		#[extrinsic_call]
+		transfer_group_ownership(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert_eq!(PendingGroupOwners::<T>::get(0), Some(member));
+	}
+
+	#[benchmark]
+	fn accept_group_ownership() {
+		let (owner, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::promote_member(RawOrigin::Signed(owner.clone()).into(), 0, member.clone());
+		let _ = Messaging::<T>::transfer_group_ownership(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			member.clone(),
+		);
+
+		#[extrinsic_call]
+		accept_group_ownership(RawOrigin::Signed(member.clone()), 0);
+
+		assert_eq!(Messaging::<T>::group_role(0, &member), Some(GroupRole::Owner));
+		assert_eq!(Messaging::<T>::group_role(0, &owner), Some(GroupRole::Admin));
+	}
+
+	#[benchmark]
+	fn rotate_group_key() {
+		let (owner, _) = create_group_with_member::<T>();
+		let group_key: GroupKey = vec![2u8; 256].try_into().unwrap();
+
+		// Worst case: the key history is full
+		for version in 1..=MAX_KEY_HISTORY {
+			let _ = Messaging::<T>::rotate_group_key(
+				RawOrigin::Signed(owner.clone()).into(),
+				0,
+				group_key.clone(),
+				version,
+			);
+		}
+
+		#[extrinsic_call]
+		rotate_group_key(RawOrigin::Signed(owner), 0, group_key, MAX_KEY_HISTORY + 1);
+
+		assert_eq!(Groups::<T>::get(0).map(|group| group.key_version), Some(MAX_KEY_HISTORY + 1));
+	}
+
+	#[benchmark]
+	fn send_group_message_hash() {
+		let (_, member) = create_group_with_member::<T>();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Worst case: an expired mute is removed
+		GroupMutes::<T>::insert(0, &member, frame_system::Pallet::<T>::block_number());
+
+		#[extrinsic_call]
+		send_group_message_hash(RawOrigin::Signed(member.clone()), 0, message_hash);
+
+		assert!(GroupMessages::<T>::contains_key(0, 0));
+		assert!(!GroupMutes::<T>::contains_key(0, &member));
+	}
+
+	#[benchmark]
+	fn set_group_retention() {
+		let (owner, _) = create_group_with_member::<T>();
+
+		#[extrinsic_call]
+		set_group_retention(RawOrigin::Signed(owner), 0, 1u32.into());
+
+		assert_eq!(Messaging::<T>::group_retention(0), 1u32.into());
+	}
+
+	#[benchmark]
+	fn leave_group(m: Linear<1, { T::MaxGroupMembers::get() - 1 }>) {
+		let (owner, admin) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::promote_member(RawOrigin::Signed(owner.clone()).into(), 0, admin.clone());
+
+		// Worst case: the owner leaves and the remaining `m` members are scanned for admins
+		let joined_at = frame_system::Pallet::<T>::block_number();
+		for i in 1..m {
+			let filler: T::AccountId = account("filler", i, 0);
+			GroupMembers::<T>::insert(0, &filler, MemberInfo { joined_at, role: GroupRole::Member });
+		}
+		Groups::<T>::mutate(0, |group| {
+			if let Some(group) = group {
+				group.member_count = m + 1;
+			}
+		});
+
+		#[extrinsic_call]
+		leave_group(RawOrigin::Signed(owner), 0);
+
+		assert_eq!(Groups::<T>::get(0).map(|group| group.owner), Some(admin));
+	}
+
+	#[benchmark]
+	fn invite_to_group() {
+		let (owner, _) = create_group_with_member::<T>();
+		let invitee: T::AccountId = account("invitee", 0, 0);
+		let _ = T::Currency::set_balance(&invitee, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(invitee.clone()).into(),
+			max_key(),
+		);
+
+		#[extrinsic_call]
+		invite_to_group(RawOrigin::Signed(owner), 0, invitee.clone());
+
+		assert!(GroupInvites::<T>::contains_key(0, &invitee));
+	}
+
+	#[benchmark]
+	fn accept_group_invite() {
+		let (owner, _) = create_group_with_member::<T>();
+		let invitee = invite_to_group_0::<T>(&owner);
+
+		#[extrinsic_call]
+		accept_group_invite(RawOrigin::Signed(invitee.clone()), 0);
+
+		assert!(GroupMembers::<T>::contains_key(0, &invitee));
+	}
+
+	#[benchmark]
+	fn decline_group_invite() {
+		let (owner, _) = create_group_with_member::<T>();
+		let invitee = invite_to_group_0::<T>(&owner);
+
+		#[extrinsic_call]
+		decline_group_invite(RawOrigin::Signed(invitee.clone()), 0);
+
+		assert!(!GroupInvites::<T>::contains_key(0, &invitee));
+	}
+
+	#[benchmark]
+	fn remove_expired_group_invite() {
+		let (owner, _) = create_group_with_member::<T>();
+		let invitee = invite_to_group_0::<T>(&owner);
+		let now = frame_system::Pallet::<T>::block_number();
+		frame_system::Pallet::<T>::set_block_number(now + T::GroupInviteExpiry::get() + 1u32.into());
+		let caller: T::AccountId = whitelisted_caller();
+
+		#[extrinsic_call]
+		remove_expired_group_invite(RawOrigin::Signed(caller), 0, invitee.clone());
+
+		assert!(!GroupInvites::<T>::contains_key(0, &invitee));
+	}
+
+	#[benchmark]
+	fn set_group_invite_only() {
+		let (owner, _) = create_group_with_member::<T>();
+
+		#[extrinsic_call]
+		set_group_invite_only(RawOrigin::Signed(owner), 0, true);
+
+		assert_eq!(Groups::<T>::get(0).map(|group| group.invite_only), Some(true));
+	}
+
+	#[benchmark]
+	fn mute_member() {
+		let (owner, member) = create_group_with_member::<T>();
+
+		// Worst case: the moderation log is full
+		fill_moderation_log_0::<T>(&owner);
+
+		#[extrinsic_call]
+		mute_member(RawOrigin::Signed(owner), 0, member.clone(), 10u32.into());
+
+		assert!(Messaging::<T>::muted_until(0, &member).is_some());
+	}
+
+	#[benchmark]
+	fn unmute_member() {
+		let (owner, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::mute_member(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			member.clone(),
+			10u32.into(),
+		);
+		fill_moderation_log_0::<T>(&owner);
+
+		#[extrinsic_call]
+		unmute_member(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert!(!GroupMutes::<T>::contains_key(0, &member));
+	}
+
+	#[benchmark]
+	fn set_posting_policy() {
+		let (owner, _) = create_group_with_member::<T>();
+
+		#[extrinsic_call]
+		set_posting_policy(RawOrigin::Signed(owner), 0, PostingPolicy::AdminsOnly);
+
+		assert_eq!(Messaging::<T>::posting_policy(0), PostingPolicy::AdminsOnly);
+	}
+
+	#[benchmark]
+	fn mute_group() {
+		let (_, member) = create_group_with_member::<T>();
+
+		#[extrinsic_call]
+		mute_group(RawOrigin::Signed(member.clone()), 0);
+
+		assert!(MutedGroups::<T>::contains_key(0, &member));
+	}
+
+	#[benchmark]
+	fn unmute_group() {
+		let (_, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::mute_group(RawOrigin::Signed(member.clone()).into(), 0);
+
+		#[extrinsic_call]
+		unmute_group(RawOrigin::Signed(member.clone()), 0);
+
+		assert!(!MutedGroups::<T>::contains_key(0, &member));
+	}
+
+	#[benchmark]
+	fn ban_from_group() {
+		let (owner, admin) = create_group_with_member::<T>();
+		let reason_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Worst case: an admin with a pending ownership offer is removed
+		let _ = Messaging::<T>::promote_member(RawOrigin::Signed(owner.clone()).into(), 0, admin.clone());
+		let _ = Messaging::<T>::transfer_group_ownership(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			admin.clone(),
+		);
+
+		#[extrinsic_call]
+		ban_from_group(RawOrigin::Signed(owner), 0, admin.clone(), reason_hash);
+
+		assert!(GroupBans::<T>::contains_key(0, &admin));
+		assert!(!GroupMembers::<T>::contains_key(0, &admin));
+		assert!(!PendingGroupOwners::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn lift_group_ban() {
+		let (owner, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::ban_from_group(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			member.clone(),
+			T::Hashing::hash_of(&[1u8; 32]),
+		);
+
+		#[extrinsic_call]
+		lift_group_ban(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert!(!GroupBans::<T>::contains_key(0, &member));
+	}
+
+	#[benchmark]
+	fn disband_group(
+		m: Linear<2, { T::MaxDisbandRemovalsPerCall::get() }>,
+		g: Linear<0, { T::MaxDisbandRemovalsPerCall::get() }>,
+	) {
+		let (owner, member) = create_group_with_member::<T>();
+
+		// Worst case: both budgets are used up and the group is still deleted, along with its
+		// metadata
+		list_group_0::<T>(&owner);
+		let joined_at = frame_system::Pallet::<T>::block_number();
+		for i in 2..m {
+			let filler: T::AccountId = account("filler", i, 0);
+			GroupMembers::<T>::insert(0, &filler, MemberInfo { joined_at, role: GroupRole::Member });
+		}
+		Groups::<T>::mutate(0, |group| {
+			if let Some(group) = group {
+				group.member_count = m;
+				group.next_seq = g.into();
+			}
+		});
+		for message_id in 0..g as MessageId {
+			GroupMessages::<T>::insert(
+				0,
+				message_id,
+				GroupMessageRecord {
+					hash: T::Hash::default(),
+					sender: member.clone(),
+					sent_at: joined_at,
+					key_version: 0,
+					expires_at: joined_at,
+					seq: message_id,
+				},
+			);
+			GroupMessageIds::<T>::insert(message_id, 0);
+			GroupMessagesBySeq::<T>::insert(0, message_id, message_id);
+		}
+
+		#[extrinsic_call]
+		disband_group(RawOrigin::Signed(owner), 0);
+
+		assert!(!Groups::<T>::contains_key(0));
+		assert!(GroupMembers::<T>::iter_key_prefix(0).next().is_none());
+		assert!(GroupMessages::<T>::iter_key_prefix(0).next().is_none());
+		assert!(!GroupsMetadata::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn set_group_metadata() {
+		let (owner, _) = create_group_with_member::<T>();
+		let description_cid: DescriptionCid = vec![1u8; 128].try_into().unwrap();
+
+		// Worst case: earlier metadata is replaced and the group listed
+		let _ = Messaging::<T>::set_group_metadata(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			T::Hash::default(),
+			DescriptionCid::default(),
+			false,
+		);
+
+		#[extrinsic_call]
+		set_group_metadata(
+			RawOrigin::Signed(owner),
+			0,
+			T::Hashing::hash_of(&[1u8; 32]),
+			description_cid,
+			true,
+		);
+
+		assert!(DiscoverableGroups::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn clear_group_metadata() {
+		let (owner, _) = create_group_with_member::<T>();
+		list_group_0::<T>(&owner);
+
+		#[extrinsic_call]
+		clear_group_metadata(RawOrigin::Signed(owner), 0);
+
+		assert!(!GroupsMetadata::<T>::contains_key(0));
+		assert!(!DiscoverableGroups::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn request_to_join_group() {
+		let (owner, _) = create_group_with_member::<T>();
+		list_group_0::<T>(&owner);
+		let requester: T::AccountId = account("requester", 0, 0);
+		let _ = T::Currency::set_balance(&requester, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(requester.clone()).into(),
+			max_key(),
+		);
+
+		#[extrinsic_call]
+		request_to_join_group(RawOrigin::Signed(requester.clone()), 0);
+
+		assert!(GroupJoinRequests::<T>::contains_key(0, &requester));
+	}
+
+	#[benchmark]
+	fn approve_join_request() {
+		let (owner, _) = create_group_with_member::<T>();
+		let requester = request_to_join_group_0::<T>(&owner);
+		fill_moderation_log_0::<T>(&owner);
+
+		#[extrinsic_call]
+		approve_join_request(RawOrigin::Signed(owner), 0, requester.clone());
+
+		assert!(GroupMembers::<T>::contains_key(0, &requester));
+		assert!(!GroupJoinRequests::<T>::contains_key(0, &requester));
+	}
+
+	#[benchmark]
+	fn cancel_join_request() {
+		let (owner, _) = create_group_with_member::<T>();
+		let requester = request_to_join_group_0::<T>(&owner);
+		fill_moderation_log_0::<T>(&owner);
+
+		// Worst case: the request has not expired and is rejected by the owner, with the
+		// moderation log full
+		#[extrinsic_call]
+		cancel_join_request(RawOrigin::Signed(owner), 0, requester.clone());
+
+		assert!(!GroupJoinRequests::<T>::contains_key(0, &requester));
+	}
+
+	#[benchmark]
+	fn appoint_moderator() {
+		let (owner, member) = create_group_with_member::<T>();
+
+		#[extrinsic_call]
+		appoint_moderator(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert_eq!(Messaging::<T>::group_role(0, &member), Some(GroupRole::Moderator));
+	}
+
+	#[benchmark]
+	fn dismiss_moderator() {
+		let (owner, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::appoint_moderator(RawOrigin::Signed(owner.clone()).into(), 0, member.clone());
+
+		#[extrinsic_call]
+		dismiss_moderator(RawOrigin::Signed(owner), 0, member.clone());
+
+		assert_eq!(Messaging::<T>::group_role(0, &member), Some(GroupRole::Member));
+	}
+
+	#[benchmark]
+	fn remove_group_message() {
+		let (owner, member) = create_group_with_member::<T>();
+		let _ = Messaging::<T>::send_group_message_hash(
+			RawOrigin::Signed(member).into(),
+			0,
+			T::Hashing::hash_of(&[1u8; 32]),
+		);
+
+		// Worst case: the moderation log is full
+		fill_moderation_log_0::<T>(&owner);
+
+		#[extrinsic_call]
+		remove_group_message(RawOrigin::Signed(owner), 0, 0);
+
+		assert!(!GroupMessages::<T>::contains_key(0, 0));
+		assert!(GroupMessageTombstones::<T>::contains_key(0, 0));
+	}
+
+	#[benchmark]
+	fn send_stealth_message_hash() {
+		let caller: T::AccountId = whitelisted_caller();
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(
+			&caller,
+			(bond_amount + T::StealthMessageDeposit::get()) * 10u32.into(),
+		);
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+		let recipient_tag = T::Hashing::hash_of(&[2u8; 32]);
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Worst case: the rate-limit window is already in use and the deposit hold exists
+		let _ = Messaging::<T>::send_stealth_message_hash(
+			RawOrigin::Signed(caller.clone()).into(),
+			recipient_tag,
+			message_hash,
+		);
+
+		#[extrinsic_call]
+		send_stealth_message_hash(RawOrigin::Signed(caller.clone()), recipient_tag, message_hash);
+
+		assert_eq!(NextMessageId::<T>::get(), 2);
+		assert_eq!(StealthMessages::<T>::get(1).map(|message| message.sender), Some(caller));
+		assert!(StealthMessagesByPrefix::<T>::contains_key(
+			crate::stealth::prefix_of(recipient_tag.as_ref()),
+			1,
+		));
+	}
+
+	#[benchmark]
+	fn register_relay() {
+		let caller: T::AccountId = whitelisted_caller();
+		let _ = T::Currency::set_balance(&caller, T::RelayBond::get() * 2u32.into());
+
+		#[extrinsic_call]
+		register_relay(RawOrigin::Signed(caller.clone()));
+
+		assert_eq!(
+			RelayAccounts::<T>::get(&caller).map(|relay| relay.bond),
+			Some(T::RelayBond::get())
+		);
+	}
+
+	#[benchmark]
+	fn deregister_relay() {
+		let caller: T::AccountId = whitelisted_caller();
+		register_relay_account::<T>(&caller);
+
+		#[extrinsic_call]
+		deregister_relay(RawOrigin::Signed(caller.clone()));
+
+		assert!(!RelayAccounts::<T>::contains_key(&caller));
+	}
+
+	#[benchmark]
+	fn send_sealed_message_hash() {
+		let caller: T::AccountId = whitelisted_caller();
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		register_relay_account::<T>(&caller);
+		let _ = T::Currency::set_balance(&recipient, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(recipient.clone()).into(),
+			max_key(),
+		);
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Worst case: the relay already carried a message in this block
+		let _ = Messaging::<T>::send_sealed_message_hash(
+			RawOrigin::Signed(caller.clone()).into(),
+			recipient.clone(),
+			T::Hashing::hash_of(&[0u8; 32]),
+		);
+
+		#[extrinsic_call]
+		send_sealed_message_hash(
+			RawOrigin::Signed(caller.clone()),
+			recipient.clone(),
+			message_hash,
+		);
+
+		assert_eq!(
+			MessageHashes::<T>::get(1).map(|record| (record.sender, record.recipient)),
+			Some((caller, recipient))
+		);
+	}
+
+	#[benchmark]
+	fn send_to_unregistered() {
+		let caller: T::AccountId = whitelisted_caller();
+		let _ = T::Currency::set_balance(
+			&caller,
+			(T::SpamBond::get() + T::PendingMessageDeposit::get()) * 10u32.into(),
+		);
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+		let account_hash = Messaging::<T>::account_hash(&account("recipient", 0, 0));
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Worst case: the rate-limit window is already in use and the deposit hold exists
+		let _ = Messaging::<T>::send_to_unregistered(
+			RawOrigin::Signed(caller.clone()).into(),
+			account_hash,
+			message_hash,
+		);
+
+		#[extrinsic_call]
+		send_to_unregistered(RawOrigin::Signed(caller.clone()), account_hash, message_hash);
+
+		assert_eq!(
+			PendingMessages::<T>::get(account_hash, 1).map(|message| message.sender),
+			Some(caller)
+		);
+	}
+
+	#[benchmark]
+	fn claim_pending_messages(n: Linear<1, { T::MaxClaimsPerCall::get() }>) {
+		let sender: T::AccountId = account("sender", 0, 0);
+		let caller: T::AccountId = whitelisted_caller();
+		let deposit = T::PendingMessageDeposit::get();
+		let _ = T::Currency::set_balance(&sender, deposit * (n + 1).into());
+		let _ = T::Currency::set_balance(&caller, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+
+		// Setup: `n` pending messages, none expired, with distinct hashes so each is indexed
+		let account_hash = Messaging::<T>::account_hash(&caller);
+		for pending_id in 0..n as MessageId {
+			let _ = T::Currency::hold(&HoldReason::MessageDeposit.into(), &sender, deposit);
+			PendingMessages::<T>::insert(
+				account_hash,
+				pending_id,
+				PendingMessageRecord {
+					sender: sender.clone(),
+					hash: T::Hashing::hash_of(&pending_id),
+					sent_at: frame_system::Pallet::<T>::block_number(),
+					deposit,
+				},
+			);
+			PendingMessageIds::<T>::insert(pending_id, account_hash);
+		}
+
+		#[extrinsic_call]
+		claim_pending_messages(RawOrigin::Signed(caller.clone()));
+
+		assert!(PendingMessages::<T>::iter_prefix(account_hash).next().is_none());
+		assert_eq!(NextMessageId::<T>::get(), n as MessageId);
+	}
+
+	#[benchmark]
+	fn contest_challenge() {
+		let challenger: T::AccountId = whitelisted_caller();
+		let sender = open_challenge::<T>(&challenger);
+
+		#[extrinsic_call]
+		contest_challenge(RawOrigin::Signed(sender), 0);
+
+		assert!(ChallengeEvidence::<T>::get(0).is_some_and(|record| record.reveal_by.is_some()));
+	}
+
+	#[benchmark]
+	fn reveal_evidence(e: Linear<1, MAX_EVIDENCE_LEN>) -> Result<(), BenchmarkError> {
+		let challenger: T::AccountId = whitelisted_caller();
+		let sender = open_challenge::<T>(&challenger);
+
+		// Setup: commit to `e` bytes of evidence and contest the challenge
+		let evidence: Evidence =
+			vec![1u8; e as usize].try_into().map_err(|_| BenchmarkError::Weightless)?;
+		let salt = T::Hash::default();
+		ChallengeEvidence::<T>::mutate(0, |record| {
+			if let Some(record) = record {
+				record.commitment = Messaging::<T>::evidence_commitment(&evidence, &salt);
+			}
+		});
+		let _ = Messaging::<T>::contest_challenge(RawOrigin::Signed(sender).into(), 0);
+
+		#[extrinsic_call]
+		reveal_evidence(RawOrigin::Signed(challenger), 0, evidence.clone(), salt);
+
+		assert_eq!(Messaging::<T>::challenge_evidence(0), Some(evidence));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn send_anonymous_contact_message(
+		p: Linear<1, MAX_MEMBERSHIP_PROOF_LEN>,
+	) -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		register_relay_account::<T>(&caller);
+		let _ = T::Currency::set_balance(&recipient, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(recipient.clone()).into(),
+			max_key(),
+		);
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+		let proof: MembershipProof = T::MembershipProofVerifier::valid_proof(
+			&recipient,
+			&ContactAccumulators::<T>::get(&recipient),
+			&message_hash,
+			p,
+		)
+		.ok_or(BenchmarkError::Skip)?
+		.try_into()
+		.map_err(|_| BenchmarkError::Weightless)?;
+
+		// Worst case: the relay already carried a message in this block
+		let _ = Messaging::<T>::send_sealed_message_hash(
+			RawOrigin::Signed(caller.clone()).into(),
+			recipient.clone(),
+			T::Hashing::hash_of(&[0u8; 32]),
+		);
+
+		#[extrinsic_call]
+		send_anonymous_contact_message(
+			RawOrigin::Signed(caller.clone()),
+			recipient.clone(),
+			message_hash,
+			proof,
+		);
+
+		assert_eq!(
+			MessageHashes::<T>::get(1).map(|record| (record.sender, record.recipient)),
+			Some((caller, recipient))
+		);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn publish_ephemeral_key(n: Linear<1, { T::MaxEphemeralKeys::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		let _ = T::Currency::set_balance(&caller, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+		let key: EphemeralKey = vec![1u8; MAX_EPHEMERAL_KEY_LEN as usize].try_into().unwrap();
+
+		// Setup: `n` keys already published, so the full set evicts the oldest
+		for index in 0..n {
+			let _ = Messaging::<T>::publish_ephemeral_key(
+				RawOrigin::Signed(caller.clone()).into(),
+				index,
+				key.clone(),
+			);
+		}
+
+		#[extrinsic_call]
+		publish_ephemeral_key(RawOrigin::Signed(caller.clone()), n, key.clone());
+
+		assert_eq!(Messaging::<T>::ephemeral_key(&caller, n), Some(key));
+	}
+
+	#[benchmark]
+	fn send_message_hash_with_ephemeral_key() {
+		let caller: T::AccountId = whitelisted_caller();
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		let public_key = max_key();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Setup: register both users as mutual contacts and publish a key
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&recipient, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			public_key.clone(),
+		);
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(recipient.clone()).into(),
+			public_key,
+		);
+		let _ = Messaging::<T>::approve_contact(
+			RawOrigin::Signed(caller.clone()).into(),
+			recipient.clone(),
+		);
+		let _ = Messaging::<T>::approve_contact(
+			RawOrigin::Signed(recipient.clone()).into(),
+			caller.clone(),
+		);
+		let _ = Messaging::<T>::publish_ephemeral_key(
+			RawOrigin::Signed(caller.clone()).into(),
+			0,
+			vec![1u8; MAX_EPHEMERAL_KEY_LEN as usize].try_into().unwrap(),
+		);
+
+		// Worst case: the rate-limit window and the conversation are already in use
+		let _ = Messaging::<T>::send_message_hash(
+			RawOrigin::Signed(caller.clone()).into(),
+			recipient.clone(),
+			T::Hashing::hash_of(&[0u8; 32]),
+		);
+		charge_protocol_fee::<T>();
+
+		#[extrinsic_call]
+		send_message_hash_with_ephemeral_key(
+			RawOrigin::Signed(caller.clone()),
+			recipient.clone(),
+			message_hash,
+			0,
+		);
+
+		assert_eq!(MessageEphemeralKeys::<T>::get(1), Some(0));
+	}
+
+	#[benchmark]
+	fn send_to_recipient_set(n: Linear<1, MAX_RECIPIENT_SET_LEN>) -> Result<(), BenchmarkError> {
+		let recipients = recipient_set::<T>(n)?;
+		let caller: T::AccountId = whitelisted_caller();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		#[extrinsic_call]
+		send_to_recipient_set(RawOrigin::Signed(caller.clone()), recipients.clone(), message_hash);
+
+		assert_eq!(
+			RecipientSetMessages::<T>::get(0).map(|record| (record.sender, record.recipients)),
+			Some((caller, recipients))
+		);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn acknowledge_recipient_set_message() -> Result<(), BenchmarkError> {
+		let recipients = recipient_set::<T>(MAX_RECIPIENT_SET_LEN)?;
+		let caller: T::AccountId = whitelisted_caller();
+		let _ = Messaging::<T>::send_to_recipient_set(
+			RawOrigin::Signed(caller).into(),
+			recipients.clone(),
+			T::Hashing::hash_of(&[1u8; 32]),
+		);
+		let member = recipients[0].clone();
+
+		#[extrinsic_call]
+		acknowledge_recipient_set_message(RawOrigin::Signed(member), 0);
+
+		assert!(RecipientSetMessages::<T>::get(0).is_some_and(|record| record.deposit.is_zero()));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn commit_backup() {
+		let caller: T::AccountId = whitelisted_caller();
+		let _ = T::Currency::set_balance(&caller, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+		let backup_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Worst case: the history is full, so the oldest commitment is refunded
+		for version in 0..MAX_BACKUP_HISTORY {
+			let _ = Messaging::<T>::commit_backup(
+				RawOrigin::Signed(caller.clone()).into(),
+				backup_hash,
+				version,
+			);
+		}
+
+		#[extrinsic_call]
+		commit_backup(RawOrigin::Signed(caller.clone()), backup_hash, MAX_BACKUP_HISTORY);
+
+		assert_eq!(
+			Messaging::<T>::latest_backup(&caller).map(|backup| backup.version),
+			Some(MAX_BACKUP_HISTORY)
+		);
+	}
+
+	#[benchmark]
+	fn set_inbox_pricing() {
+		let caller: T::AccountId = whitelisted_caller();
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+		let pricing = InboxPricing { verified: Some(bond_amount), others: Some(bond_amount) };
+
+		#[extrinsic_call]
+		set_inbox_pricing(RawOrigin::Signed(caller.clone()), pricing.clone());
+
+		assert_eq!(InboxPricings::<T>::get(&caller), Some(pricing));
+	}
+
+	#[benchmark]
+	fn refund_deposits_on_reply(
+		n: Linear<0, { T::MaxAutoRefundsPerReply::get() }>,
+	) -> Result<(), BenchmarkError> {
+		let recipients = recipient_set::<T>(MAX_RECIPIENT_SET_LEN)?;
+		let caller: T::AccountId = whitelisted_caller();
+		let deposit = T::RecipientSetDeposit::get() * MAX_RECIPIENT_SET_LEN.into();
+		let _ = T::Currency::set_balance(
+			&caller,
+			T::SpamBond::get() * 10u32.into() + deposit * n.into(),
+		);
+
+		// Worst case: every message lists all members, each of which is dropped from the index
+		for i in 0..n {
+			let _ = Messaging::<T>::send_to_recipient_set(
+				RawOrigin::Signed(caller.clone()).into(),
+				recipients.clone(),
+				T::Hashing::hash_of(&i),
+			);
+		}
+		let member = recipients[0].clone();
+		assert_eq!(ReplyRefundableDeposits::<T>::get(&caller, &member).len(), n as usize);
+
+		#[block]
+		{
+			Messaging::<T>::refund_deposits_on_reply(&member, &caller, n);
+		}
+
+		assert!(!ReplyRefundableDeposits::<T>::contains_key(&caller, &member));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn purchase_credits() {
+		let caller: T::AccountId = whitelisted_caller();
+		let count = T::MaxCreditsPerAccount::get();
+		let _ = T::Currency::set_balance(
+			&caller,
+			T::SpamBond::get() * 10u32.into() + T::CreditPrice::get() * count.into(),
+		);
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+
+		#[extrinsic_call]
+		purchase_credits(RawOrigin::Signed(caller.clone()), count);
+
+		assert_eq!(MessageCredits::<T>::get(&caller), count);
+	}
+
+	#[benchmark]
+	fn refund_unused_credits() {
+		let caller: T::AccountId = whitelisted_caller();
+		let count = T::MaxCreditsPerAccount::get();
+		let _ = T::Currency::set_balance(
+			&caller,
+			T::SpamBond::get() * 10u32.into() + T::CreditPrice::get() * count.into(),
+		);
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+		let _ = Messaging::<T>::purchase_credits(RawOrigin::Signed(caller.clone()).into(), count);
+
+		#[extrinsic_call]
+		refund_unused_credits(RawOrigin::Signed(caller.clone()));
+
+		assert!(!MessageCredits::<T>::contains_key(&caller));
+	}
+
+	#[benchmark]
+	fn sponsor_registration(k: Linear<1, 256>) -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		let public_key = vec![1u8; k as usize];
+		let algorithm = KeyAlgorithm::X25519;
+		let _ = T::Currency::set_balance(&caller, T::SpamBond::get() * 10u32.into());
+
+		// Setup: consent of the sponsored account to the caller's sponsorship
+		let sponsorship = (&caller, &public_key, algorithm).encode();
+		let payload = [SPONSORSHIP_CONTEXT, &sponsorship[..]].concat();
+		let (who, proof_of_possession) =
+			T::BenchmarkHelper::sign(&payload).ok_or(BenchmarkError::Skip)?;
+
+		// Worst case: the whitelist is consulted
+		CurrentRegistrationMode::<T>::put(RegistrationMode::Whitelisted);
+		RegistrationWhitelist::<T>::insert(&who, ());
+
+		#[extrinsic_call]
+		sponsor_registration(
+			RawOrigin::Signed(caller.clone()),
+			who.clone(),
+			public_key,
+			algorithm,
+			proof_of_possession,
+		);
+
+		assert_eq!(SpamBonds::<T>::get(&who).sponsor, Some(caller));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn assume_own_bond() {
+		let caller: T::AccountId = whitelisted_caller();
+		let sponsor: T::AccountId = account("sponsor", 0, 0);
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let _ = T::Currency::set_balance(&sponsor, bond_amount * 10u32.into());
+
+		// Setup: a registered caller whose bond is held from the sponsor
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+		let reason = HoldReason::SpamBond.into();
+		let _ = T::Currency::release(&reason, &caller, bond_amount, Precision::Exact);
+		let _ = T::Currency::hold(&reason, &sponsor, bond_amount);
+		SpamBonds::<T>::mutate(&caller, |bond| bond.sponsor = Some(sponsor.clone()));
+
+		#[extrinsic_call]
+		assume_own_bond(RawOrigin::Signed(caller.clone()));
+
+		assert_eq!(SpamBonds::<T>::get(&caller).sponsor, None);
+	}
+
+	#[benchmark]
+	fn migrate_spam_bond() {
+		let bond_amount = T::SpamBond::get();
+
+		// Setup: two bonds in the pre-v4 layout, and weight for rewriting one of them
+		StorageVersion::new(3).put::<Messaging<T>>();
+		for index in 0..2 {
+			let who: T::AccountId = account("bonded", index, 0);
+			migrations::v4::SpamBonds::<T>::insert(&who, bond_amount);
+		}
+		let mut meter = WeightMeter::with_limit(T::WeightInfo::migrate_spam_bond());
+
+		#[block]
+		{
+			let _ = migrations::v4::LazyMigrateV3ToV4::<T>::step(None, &mut meter);
+		}
+
+		assert_eq!(meter.consumed(), T::WeightInfo::migrate_spam_bond());
+	}
+
+	#[benchmark]
+	fn rekey_entry() {
+		let sender: T::AccountId = account("sender", 0, 0);
+		let refunds = u64::from(T::MaxAutoRefundsPerReply::get());
+		let message_ids = BoundedVec::truncate_from((0..refunds).collect::<Vec<_>>());
+
+		// Setup: two full reply refund entries in the pre-v9 layout, and weight for moving one
+		StorageVersion::new(8).put::<Messaging<T>>();
+		for index in 0..2 {
+			let member: T::AccountId = account("member", index, 0);
+			migrations::v9::ReplyRefundableDeposits::<T>::insert(
+				&sender,
+				&member,
+				message_ids.clone(),
+			);
+		}
+		let mut meter = WeightMeter::with_limit(T::WeightInfo::rekey_entry());
+
+		#[block]
+		{
+			let _ = migrations::rekey::move_entries::<
+				migrations::v9::ReplyRefundableDeposits<T>,
+				ReplyRefundableDeposits<T>,
+				_,
+				_,
+				_,
+			>(None, T::WeightInfo::rekey_entry(), &mut meter);
+		}
+
+		assert_eq!(meter.consumed(), T::WeightInfo::rekey_entry());
+		let moved = (0..2)
+			.filter(|index| {
+				let member: T::AccountId = account("member", *index, 0);
+				ReplyRefundableDeposits::<T>::contains_key(&sender, &member)
+			})
+			.count();
+		assert_eq!(moved, 1);
+	}
+
+	#[benchmark]
+	fn fund_reward_pool() {
+		let caller: T::AccountId = whitelisted_caller();
+		let amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, amount * 10u32.into());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), amount);
+
+		assert_eq!(Messaging::<T>::reward_pool_balance(), amount);
+	}
+
+	#[benchmark]
+	fn flag_inactive() {
+		let caller: T::AccountId = whitelisted_caller();
+		let target: T::AccountId = account("target", 0, 0);
+
+		// Setup: a profile inactive since registration
+		let _ = T::Currency::set_balance(&target, T::SpamBond::get() * 10u32.into());
+		let owner = RawOrigin::Signed(target.clone());
+		let _ = Messaging::<T>::register_profile(owner.into(), max_key());
+		let now = frame_system::Pallet::<T>::block_number();
+		frame_system::Pallet::<T>::set_block_number(now + T::InactivityThreshold::get());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), target.clone());
+
+		assert!(Messaging::<T>::profile_activity(target).unwrap().dormant_since.is_some());
+	}
+
+	#[benchmark]
+	fn sweep_dormant_profile() -> Result<(), BenchmarkError> {
+		let target: T::AccountId = account("target", 0, 0);
+
+		// Setup: a dormant profile inactive since registration, with an inbox price
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&target, bond_amount * 10u32.into());
+		let owner = RawOrigin::Signed(target.clone());
+		let _ = Messaging::<T>::register_profile(owner.clone().into(), max_key());
+		let _ = Messaging::<T>::set_inbox_price(owner.into(), Some(bond_amount));
+		let now = frame_system::Pallet::<T>::block_number();
+		UserProfiles::<T>::mutate(&target, |profile| {
+			if let Some(profile) = profile {
+				profile.dormant_since = Some(now);
+			}
+		});
+		frame_system::Pallet::<T>::set_block_number(now + T::DormantSweepThreshold::get());
+		let origin =
+			T::ForceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, target.clone());
+
+		assert!(!UserProfiles::<T>::contains_key(&target));
+		assert!(SpamBonds::<T>::get(&target).amount.is_zero());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_notification_hint() {
+		let caller: T::AccountId = whitelisted_caller();
+		let _ = T::Currency::set_balance(&caller, T::SpamBond::get() * 10u32.into());
+		let owner = RawOrigin::Signed(caller.clone());
+		let _ = Messaging::<T>::register_profile(owner.into(), max_key());
+		let hint = NotificationHint::truncate_from(vec![1u8; 64]);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), Some(hint.clone()));
+
+		assert_eq!(NotificationHooks::<T>::get(&caller), Some(hint));
+	}
+
+	#[benchmark]
+	fn set_compact_events() {
+		let caller: T::AccountId = whitelisted_caller();
+		let _ = T::Currency::set_balance(&caller, T::SpamBond::get() * 10u32.into());
+		let owner = RawOrigin::Signed(caller.clone());
+		let _ = Messaging::<T>::register_profile(owner.into(), max_key());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), true);
+
+		assert!(CompactEventSenders::<T>::contains_key(&caller));
+	}
+
+	#[benchmark]
+	fn record_session() {
+		let caller: T::AccountId = whitelisted_caller();
+		let peer: T::AccountId = account("peer", 0, 0);
+		for who in [&caller, &peer] {
+			let _ = T::Currency::set_balance(who, T::SpamBond::get() * 10u32.into());
+			let _ = Messaging::<T>::register_profile(
+				RawOrigin::Signed(who.clone()).into(),
+				max_key(),
+			);
+		}
+		let key: EphemeralKey = vec![1u8; MAX_EPHEMERAL_KEY_LEN as usize].try_into().unwrap();
+		let _ =
+			Messaging::<T>::publish_ephemeral_key(RawOrigin::Signed(peer.clone()).into(), 0, key);
+
+		// Worst case: an earlier session is replaced under the next epoch
+		let earlier = T::Hashing::hash_of(&[1u8; 32]);
+		let _ = Messaging::<T>::record_session(
+			RawOrigin::Signed(caller.clone()).into(),
+			peer.clone(),
+			earlier,
+			None,
+		);
+		let commitment = T::Hashing::hash_of(&[2u8; 32]);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), peer.clone(), commitment, Some(0));
+
+		assert_eq!(Sessions::<T>::get(&caller, &peer).map(|session| session.epoch), Some(1));
+	}
+
+	#[benchmark]
+	fn confirm_session() {
+		let initiator: T::AccountId = account("initiator", 0, 0);
+		let caller: T::AccountId = whitelisted_caller();
+		for who in [&initiator, &caller] {
+			let _ = T::Currency::set_balance(who, T::SpamBond::get() * 10u32.into());
+			let _ = Messaging::<T>::register_profile(
+				RawOrigin::Signed(who.clone()).into(),
+				max_key(),
+			);
+		}
+		let commitment = T::Hashing::hash_of(&[1u8; 32]);
+		let _ = Messaging::<T>::record_session(
+			RawOrigin::Signed(initiator.clone()).into(),
+			caller.clone(),
+			commitment,
+			None,
+		);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), initiator.clone(), commitment);
+
+		assert!(Messaging::<T>::has_confirmed_session(&initiator, &caller));
+	}
+
+	#[benchmark]
+	fn terminate_session() {
+		let initiator: T::AccountId = account("initiator", 0, 0);
+		let caller: T::AccountId = whitelisted_caller();
+		for who in [&initiator, &caller] {
+			let _ = T::Currency::set_balance(who, T::SpamBond::get() * 10u32.into());
+			let _ = Messaging::<T>::register_profile(
+				RawOrigin::Signed(who.clone()).into(),
+				max_key(),
+			);
+		}
+		let commitment = T::Hashing::hash_of(&[1u8; 32]);
+		let _ = Messaging::<T>::record_session(
+			RawOrigin::Signed(initiator.clone()).into(),
+			caller.clone(),
+			commitment,
+			None,
+		);
+		let _ = Messaging::<T>::confirm_session(
+			RawOrigin::Signed(caller.clone()).into(),
+			initiator.clone(),
+			commitment,
+		);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), initiator.clone(), caller.clone());
+
+		assert!(!Messaging::<T>::has_confirmed_session(&initiator, &caller));
+	}
+
+	#[benchmark]
+	fn set_session_requirement() {
+		let caller: T::AccountId = whitelisted_caller();
+		let _ = T::Currency::set_balance(&caller, T::SpamBond::get() * 10u32.into());
+		let _ = Messaging::<T>::register_profile(
+			RawOrigin::Signed(caller.clone()).into(),
+			max_key(),
+		);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), true);
+
+		assert!(UserProfiles::<T>::get(&caller).is_some_and(|profile| profile.session_required));
+	}
+
+	#[benchmark]
+	fn add_device_key() {
+		let caller: T::AccountId = whitelisted_caller();
+		// Worst case: the last free slot is taken
+		let max = T::MaxDevicesPerAccount::get();
+		register_with_devices::<T>(&caller, max - 1);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), max_key(), max_attestation());
+
+		assert_eq!(Messaging::<T>::devices_revision(caller), max);
+	}
+
+	#[benchmark]
+	fn replace_device_key() {
+		let caller: T::AccountId = whitelisted_caller();
+		// Worst case: the last device of a full set is replaced
+		let max = T::MaxDevicesPerAccount::get();
+		register_with_devices::<T>(&caller, max);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), max - 1, vec![2u8; 256], max_attestation());
+
+		assert_eq!(Messaging::<T>::devices_revision(caller), max + 1);
+	}
+
+	#[benchmark]
+	fn remove_device_key() {
+		let caller: T::AccountId = whitelisted_caller();
+		// Worst case: the last device of a full set is removed
+		let max = T::MaxDevicesPerAccount::get();
+		register_with_devices::<T>(&caller, max);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), max - 1);
+
+		assert_eq!(Messaging::<T>::devices_revision(caller), max + 1);
+	}
+
+	#[benchmark]
+	fn verify_message_on_chain() {
+		let sender: T::AccountId = account("sender", 0, 0);
+		let recipient: T::AccountId = whitelisted_caller();
+		let message_hash = T::Hashing::hash_of(&[1u8; 32]);
+
+		// Worst case: a mismatch brings the sender to the report threshold
+		let bond_amount = T::SpamBond::get();
+		for who in [&sender, &recipient] {
+			let _ = T::Currency::set_balance(who, bond_amount * 10u32.into());
+			let _ = Messaging::<T>::register_profile(
+				RawOrigin::Signed(who.clone()).into(),
+				max_key(),
+			);
+		}
+		let _ = Messaging::<T>::approve_contact(
+			RawOrigin::Signed(recipient.clone()).into(),
+			sender.clone(),
+		);
+		let origin = RawOrigin::Signed(sender.clone());
+		let _ = Messaging::<T>::send_message_hash(origin.into(), recipient.clone(), message_hash);
+		SpamReports::<T>::insert(
+			&sender,
+			SpamReportRecord {
+				reports: T::AutoChallengeThreshold::get().saturating_sub(1),
+				last_decay: frame_system::Pallet::<T>::block_number(),
+			},
+		);
+		let claimed_hash = T::Hashing::hash_of(&[2u8; 32]);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(recipient), 0, claimed_hash);
+
+		assert_eq!(MessageVerifications::<T>::get(0).map(|record| record.matched), Some(false));
+		assert!(Challenges::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn deregister_profile() {
+		let caller: T::AccountId = whitelisted_caller();
+
+		// Worst case: the bond is released and every profile setting cleared
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&caller, bond_amount * 10u32.into());
+		let owner = RawOrigin::Signed(caller.clone());
+		let _ = Messaging::<T>::register_profile(owner.clone().into(), max_key());
+		let _ = Messaging::<T>::set_inbox_price(owner.clone().into(), Some(bond_amount));
+		let hint = NotificationHint::truncate_from(vec![1u8; 64]);
+		let _ = Messaging::<T>::set_notification_hint(owner.into(), Some(hint));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()));
+
+		assert!(!UserProfiles::<T>::contains_key(&caller));
+		assert!(SpamBonds::<T>::get(&caller).amount.is_zero());
+		assert!(DepartedSenders::<T>::contains_key(&caller));
+	}
+
+	#[benchmark]
+	fn force_deregister_profile() -> Result<(), BenchmarkError> {
+		let target: T::AccountId = account("target", 0, 0);
+
+		// Setup: a profile with an inbox price and a notification hint
+		let bond_amount = T::SpamBond::get();
+		let _ = T::Currency::set_balance(&target, bond_amount * 10u32.into());
+		let owner = RawOrigin::Signed(target.clone());
+		let _ = Messaging::<T>::register_profile(owner.clone().into(), max_key());
+		let _ = Messaging::<T>::set_inbox_price(owner.clone().into(), Some(bond_amount));
+		let hint = NotificationHint::truncate_from(vec![1u8; 64]);
+		let _ = Messaging::<T>::set_notification_hint(owner.into(), Some(hint));
+		let origin =
+			T::ForceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, target.clone());
+
+		assert!(!UserProfiles::<T>::contains_key(&target));
+		assert!(DepartedSenders::<T>::contains_key(&target));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn verify_messages(n: Linear<1, MAX_VERIFICATION_BATCH>) {
+		// Worst case: distinct IDs in descending order, none of them stored, so each takes a
+		// read of the message, its takedown and its retraction
+		let batch: Vec<_> = (0..n as MessageId)
+			.rev()
+			.map(|message_id| (message_id, T::Hashing::hash_of(&message_id)))
+			.collect();
+		let results;
+
+		#[block]
+		{
+			results = Messaging::<T>::verify_messages(batch);
+		}
+
+		assert_eq!(results.len(), n as usize);
+		assert!(results.iter().all(|result| *result == VerificationResult::NotFound));
 	}
 
 	impl_benchmark_test_suite!(Messaging, crate::mock::new_test_ext(), crate::mock::Test);
@@ -0,0 +1,337 @@
+//! Transaction extensions for pallet-messaging.
+//!
+//! ## Fee-free messaging between contacts
+//!
+//! [`SkipFeeForContacts`] wraps the runtime's payment extension and refunds its fee for
+//! `send_message_hash` calls between mutual contacts whose bonds are active. Spam is
+//! already priced in by the bonds, so contacts can talk without paying per message. The
+//! exemption is bounded by the sender's rate-limit window and by
+//! [`Config::MaxFreeMessagesPerBlock`], both read from `SendRateLimits` during validation;
+//! every other call, and any send over those bounds, is charged by the wrapped extension
+//! as usual.
+//!
+//! The wrapped extension always validates and withdraws the fee, and only a send that
+//! succeeds is refunded. A failed send rolls back its use of the rate limit, so refunding
+//! it too would let contacts fill blocks with free sends bound to fail.
+//!
+//! To enable it, wrap `ChargeTransactionPayment` in the runtime's `TxExtension` tuple:
+//!
+//! ```ignore
+//! pub type TxExtension = (
+//!     frame_system::CheckNonZeroSender<Runtime>,
+//!     // ...
+//!     frame_system::CheckWeight<Runtime>,
+//!     pallet_messaging::extensions::SkipFeeForContacts<
+//!         Runtime,
+//!         pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+//!     >,
+//!     // ...
+//! );
+//! ```
+//!
+//! The wrapper encodes exactly like the extension it wraps and reports the same
+//! identifier, so clients building transactions need no changes. Nodes constructing the
+//! tuple convert the payment extension with `.into()`.
+//!
+//! ## Rejecting sends from unregistered accounts
+//!
+//! [`CheckRegisteredSender`] keeps sends that are bound to fail out of the transaction
+//! pool. For `send_message_hash`, `send_paid_message_hash` and `accept_and_send` it checks
+//! that sends are not paused and that both the sender and the recipient have a profile,
+//! rejecting the transaction with an [`InvalidSend`] code otherwise. The checks only read
+//! storage and are repeated when the transaction is included in a block, since a profile
+//! may be removed while the transaction waits in the pool. The calls themselves still
+//! perform every check, so the extension is an optimization rather than a guard.
+//!
+//! It encodes to nothing and is added to the runtime's `TxExtension` tuple like the
+//! `frame_system` checks:
+//!
+//! ```ignore
+//! pub type TxExtension = (
+//!     // ...
+//!     frame_system::CheckNonce<Runtime>,
+//!     pallet_messaging::extensions::CheckRegisteredSender<Runtime>,
+//!     frame_system::CheckWeight<Runtime>,
+//!     // ...
+//! );
+//! ```
+
+use crate::{Call, Config, Pallet, PausedCalls, UserProfiles};
+use codec::{Decode, DecodeWithMemTracking, Encode};
+use core::marker::PhantomData;
+use frame_support::{
+	dispatch::{Pays, PostDispatchInfo},
+	pallet_prelude::*,
+	traits::{IsSubType, OriginTrait},
+};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{
+		DispatchInfoOf, DispatchOriginOf, Dispatchable, Implication, PostDispatchInfoOf,
+		TransactionExtension, TransactionExtensionMetadata, ValidateResult,
+	},
+	transaction_validity::{InvalidTransaction, TransactionSource},
+};
+use sp_std::vec::Vec;
+
+/// Refunds the fee of the wrapped payment extension for messages between mutual contacts.
+///
+/// See the [module documentation](self) for the exemption rules and runtime wiring.
+#[derive(Encode, Decode, DecodeWithMemTracking, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct SkipFeeForContacts<T, S>(pub S, PhantomData<T>);
+
+impl<T, S: Encode> core::fmt::Debug for SkipFeeForContacts<T, S> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "SkipFeeForContacts<{:?}>", self.0.encode())
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut core::fmt::Formatter) -> core::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T, S> From<S> for SkipFeeForContacts<T, S> {
+	fn from(s: S) -> Self {
+		Self(s, PhantomData)
+	}
+}
+
+/// State of the wrapped extension, and whether its fee is refunded.
+pub enum Intermediate<T> {
+	/// The wrapped extension charges the fee as usual.
+	Charge(T),
+	/// The wrapped extension charges the fee, which is refunded if the send succeeds.
+	RefundOnSuccess(T),
+}
+
+impl<T: Config, S> SkipFeeForContacts<T, S>
+where
+	T::RuntimeCall: IsSubType<Call<T>>,
+{
+	/// Whether `call`, dispatched from `origin`, qualifies for a fee-free send.
+	fn is_exempt(origin: &DispatchOriginOf<T::RuntimeCall>, call: &T::RuntimeCall) -> bool {
+		let Some(sender) = origin.as_signer() else { return false };
+		match call.is_sub_type() {
+			Some(Call::send_message_hash { recipient, .. }) =>
+				Pallet::<T>::is_fee_exempt(sender, recipient),
+			_ => false,
+		}
+	}
+}
+
+impl<T, S> TransactionExtension<T::RuntimeCall> for SkipFeeForContacts<T, S>
+where
+	T: Config + Send + Sync,
+	T::RuntimeCall: IsSubType<Call<T>> + Dispatchable<PostInfo = PostDispatchInfo>,
+	S: TransactionExtension<T::RuntimeCall>,
+{
+	const IDENTIFIER: &'static str = S::IDENTIFIER;
+	type Implicit = S::Implicit;
+
+	fn metadata() -> Vec<TransactionExtensionMetadata> {
+		S::metadata()
+	}
+
+	fn implicit(&self) -> Result<Self::Implicit, TransactionValidityError> {
+		self.0.implicit()
+	}
+
+	type Val = Intermediate<S::Val>;
+	type Pre = Intermediate<S::Pre>;
+
+	fn weight(&self, call: &T::RuntimeCall) -> Weight {
+		let inner = self.0.weight(call);
+		match call.is_sub_type() {
+			// Paused (r:1), FrozenSenders (r:1), BannedAccounts (r:2), ApprovedContacts (r:2),
+			// SpamBonds (r:2), UserProfiles (r:1), Reputation (r:1), SendRateLimits (r:1)
+			Some(Call::send_message_hash { .. }) =>
+				inner.saturating_add(T::DbWeight::get().reads(11)),
+			_ => inner,
+		}
+	}
+
+	fn validate(
+		&self,
+		origin: DispatchOriginOf<T::RuntimeCall>,
+		call: &T::RuntimeCall,
+		info: &DispatchInfoOf<T::RuntimeCall>,
+		len: usize,
+		self_implicit: Self::Implicit,
+		inherited_implication: &impl Implication,
+		source: TransactionSource,
+	) -> ValidateResult<Self::Val, T::RuntimeCall> {
+		let exempt = Self::is_exempt(&origin, call);
+		let (valid, val, origin) = self.0.validate(
+			origin,
+			call,
+			info,
+			len,
+			self_implicit,
+			inherited_implication,
+			source,
+		)?;
+		let val =
+			if exempt { Intermediate::RefundOnSuccess(val) } else { Intermediate::Charge(val) };
+		Ok((valid, val, origin))
+	}
+
+	fn prepare(
+		self,
+		val: Self::Val,
+		origin: &DispatchOriginOf<T::RuntimeCall>,
+		call: &T::RuntimeCall,
+		info: &DispatchInfoOf<T::RuntimeCall>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		match val {
+			Intermediate::Charge(val) =>
+				self.0.prepare(val, origin, call, info, len).map(Intermediate::Charge),
+			Intermediate::RefundOnSuccess(val) =>
+				self.0.prepare(val, origin, call, info, len).map(Intermediate::RefundOnSuccess),
+		}
+	}
+
+	fn post_dispatch_details(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<T::RuntimeCall>,
+		post_info: &PostDispatchInfoOf<T::RuntimeCall>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<Weight, TransactionValidityError> {
+		match pre {
+			Intermediate::RefundOnSuccess(pre) if result.is_ok() => {
+				// The wrapped extension refunds what a call that does not pay was charged
+				let post_info = PostDispatchInfo { pays_fee: Pays::No, ..*post_info };
+				S::post_dispatch_details(pre, info, &post_info, len, result)
+			},
+			Intermediate::Charge(pre) | Intermediate::RefundOnSuccess(pre) =>
+				S::post_dispatch_details(pre, info, post_info, len, result),
+		}
+	}
+}
+
+/// Reasons [`CheckRegisteredSender`] rejects a send, reported as
+/// `InvalidTransaction::Custom` codes.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[repr(u8)]
+pub enum InvalidSend {
+	/// The sender has no profile.
+	UnregisteredSender = 1,
+	/// The recipient has no profile.
+	UnknownRecipient = 2,
+	/// Sends are paused.
+	SendsPaused = 3,
+}
+
+impl From<InvalidSend> for TransactionValidityError {
+	fn from(reason: InvalidSend) -> Self {
+		InvalidTransaction::Custom(reason as u8).into()
+	}
+}
+
+/// Rejects sends from unregistered accounts before they enter the transaction pool.
+///
+/// See the [module documentation](self) for the checks and runtime wiring.
+#[derive(Encode, Decode, DecodeWithMemTracking, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckRegisteredSender<T>(PhantomData<T>);
+
+impl<T> CheckRegisteredSender<T> {
+	/// Create the extension.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T> Default for CheckRegisteredSender<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> core::fmt::Debug for CheckRegisteredSender<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "CheckRegisteredSender")
+	}
+}
+
+impl<T: Config> CheckRegisteredSender<T>
+where
+	T::RuntimeCall: IsSubType<Call<T>>,
+{
+	/// Recipient of `call`, if it sends a message.
+	fn recipient_of(call: &T::RuntimeCall) -> Option<&T::AccountId> {
+		match call.is_sub_type()? {
+			Call::send_message_hash { recipient, .. } |
+			Call::send_paid_message_hash { recipient, .. } => Some(recipient),
+			Call::accept_and_send { requester, .. } => Some(requester),
+			_ => None,
+		}
+	}
+
+	/// Check that a send from `origin` can succeed, reading storage only.
+	fn check(
+		origin: &DispatchOriginOf<T::RuntimeCall>,
+		call: &T::RuntimeCall,
+	) -> Result<(), TransactionValidityError> {
+		let Some(recipient) = Self::recipient_of(call) else { return Ok(()) };
+		let Some(sender) = origin.as_signer() else { return Ok(()) };
+		if Pallet::<T>::is_paused(PausedCalls::SENDS) {
+			return Err(InvalidSend::SendsPaused.into());
+		}
+		if !UserProfiles::<T>::contains_key(sender) {
+			return Err(InvalidSend::UnregisteredSender.into());
+		}
+		if !UserProfiles::<T>::contains_key(recipient) {
+			return Err(InvalidSend::UnknownRecipient.into());
+		}
+		Ok(())
+	}
+}
+
+impl<T> TransactionExtension<T::RuntimeCall> for CheckRegisteredSender<T>
+where
+	T: Config + Send + Sync,
+	T::RuntimeCall: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckRegisteredSender";
+	type Implicit = ();
+	type Val = ();
+	type Pre = ();
+
+	fn weight(&self, call: &T::RuntimeCall) -> Weight {
+		match Self::recipient_of(call) {
+			// Paused (r:1), UserProfiles (r:2)
+			Some(_) => T::DbWeight::get().reads(3),
+			None => Weight::zero(),
+		}
+	}
+
+	fn validate(
+		&self,
+		origin: DispatchOriginOf<T::RuntimeCall>,
+		call: &T::RuntimeCall,
+		_info: &DispatchInfoOf<T::RuntimeCall>,
+		_len: usize,
+		_self_implicit: Self::Implicit,
+		_inherited_implication: &impl Implication,
+		_source: TransactionSource,
+	) -> ValidateResult<Self::Val, T::RuntimeCall> {
+		Self::check(&origin, call)?;
+		Ok((ValidTransaction::default(), (), origin))
+	}
+
+	fn prepare(
+		self,
+		_val: Self::Val,
+		origin: &DispatchOriginOf<T::RuntimeCall>,
+		call: &T::RuntimeCall,
+		_info: &DispatchInfoOf<T::RuntimeCall>,
+		_len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		// The profiles may have changed since the transaction entered the pool
+		Self::check(origin, call)
+	}
+}
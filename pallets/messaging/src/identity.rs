@@ -0,0 +1,65 @@
+//! Registration gate backed by `pallet-identity` judgements.
+//!
+//! [`IdentityJudgementGate`] admits accounts that at least one registrar has judged
+//! `Reasonable`, or `KnownGood` if configured so. Plug it into
+//! [`Config::RegistrationGate`](crate::Config) to keep sybil accounts from registering
+//! profiles:
+//!
+//! ```ignore
+//! parameter_types! {
+//!     pub const MinRegistrationJudgement: JudgementLevel = JudgementLevel::KnownGood;
+//! }
+//!
+//! impl pallet_messaging::Config for Runtime {
+//!     // ...
+//!     type RegistrationGate = IdentityJudgementGate<Runtime, MinRegistrationJudgement>;
+//! }
+//! ```
+//!
+//! Only available with the `identity` feature.
+
+use core::marker::PhantomData;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{Contains, Get},
+};
+use pallet_identity::{IdentityOf, Judgement};
+
+/// Registrar judgements accepted by [`IdentityJudgementGate`], from weakest to strongest.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, RuntimeDebug, TypeInfo)]
+pub enum JudgementLevel {
+	/// The registrar found the identity reasonable.
+	Reasonable,
+	/// The registrar verified the identity.
+	KnownGood,
+}
+
+impl JudgementLevel {
+	/// Level of a registrar judgement, or `None` if it does not vouch for the identity.
+	pub fn of<Balance>(judgement: &Judgement<Balance>) -> Option<Self> {
+		match judgement {
+			Judgement::Reasonable => Some(Self::Reasonable),
+			Judgement::KnownGood => Some(Self::KnownGood),
+			_ => None,
+		}
+	}
+}
+
+/// Admits accounts with a registrar judgement of at least `MinJudgement`.
+pub struct IdentityJudgementGate<T, MinJudgement>(PhantomData<(T, MinJudgement)>);
+
+impl<T, MinJudgement> Contains<T::AccountId> for IdentityJudgementGate<T, MinJudgement>
+where
+	T: pallet_identity::Config,
+	MinJudgement: Get<JudgementLevel>,
+{
+	fn contains(who: &T::AccountId) -> bool {
+		IdentityOf::<T>::get(who).is_some_and(|registration| {
+			registration
+				.judgements
+				.iter()
+				.filter_map(|(_, judgement)| JudgementLevel::of(judgement))
+				.any(|level| level >= MinJudgement::get())
+		})
+	}
+}
@@ -36,6 +36,272 @@
 //! - Message hashes expire to prevent chain bloat
 //! - Public keys are validated on registration
 //! - All extrinsics require proper origin verification
+//!
+//! ### Message Events
+//!
+//! Since storage version 1, `MessageSent` carries everything an indexer needs to track a
+//! message without further storage queries:
+//!
+//! ```ignore
+//! MessageSent {
+//!     message_id: MessageId,
+//!     from: AccountId,
+//!     to: AccountId,
+//!     hash: Hash,
+//!     expires_at: BlockNumber,
+//!     sequence: u64,
+//!     conversation_id: Hash,
+//! }
+//! ```
+//!
+//! `expires_at` is the last block at which the hash verifies. `conversation_id` is
+//! [`Pallet::conversation_id`] of the two accounts and identical in both directions;
+//! `sequence` numbers the messages of a conversation from 0. `MessageAcknowledged` gained
+//! a trailing `latency` field holding the blocks between sending and acknowledgement.
+//! Events emitted before the upgrade use the old layout without these fields, so
+//! indexers should pick the decoder by the runtime version of the block.
+//!
+//! ### Profile Events
+//!
+//! Since storage version 3, `ProfileRegistered` and `ProfileUpdated` commit to the new
+//! public key instead of carrying it:
+//!
+//! ```ignore
+//! ProfileRegistered { who: AccountId, key_hash: Hash, version: u32 }
+//! ProfileUpdated { who: AccountId, key_hash: Hash, version: u32 }
+//! ```
+//!
+//! Clients that need the key read it from `UserProfiles` under `who`, or through the
+//! `profile` runtime API, and check it with [`Pallet::key_hash`]. `version` matches
+//! `KeyVersions`, so a client can also tell whether the stored key has since been
+//! replaced.
+//!
+//! ### Stealth Messages
+//!
+//! `send_stealth_message_hash` records a message under a recipient tag instead of the
+//! recipient's account. See the [`stealth`] module for how tags are derived and scanned.
+//!
+//! ### Event Privacy
+//!
+//! With `event_privacy` set to [`EventPrivacyMode::Minimal`] in the pallet parameters,
+//! messages and contact changes no longer put account IDs in public events:
+//!
+//! ```ignore
+//! MessageSentMinimal { message_id: MessageId, conversation_id: Hash }
+//! ContactApprovedMinimal { pair: Hash }
+//! ContactRemovedMinimal { pair: Hash }
+//! ```
+//!
+//! `pair` is [`Pallet::contact_pair_hash`] of the acting account and the contact. The
+//! parties read the rest through the `message_info` and `is_contact` view functions.
+//! Parameters stored before the field existed decode as [`EventPrivacyMode::Full`], so
+//! switching modes needs no migration.
+//!
+//! ### Anonymous Contact Messages
+//!
+//! `send_anonymous_contact_message` is a sealed-sender send whose sender additionally proves
+//! to be an approved contact of the recipient. See the [`membership`] module for the
+//! accumulator and verifier hooks the runtime provides.
+//!
+//! ### Ephemeral Keys
+//!
+//! Senders publish per-message public keys with `publish_ephemeral_key`, keeping at most
+//! `MaxEphemeralKeys` at once, and name the key a message was encrypted under with
+//! `send_message_hash_with_ephemeral_key`. Keys expire and are pruned like messages.
+//!
+//! ### Recipient Sets
+//!
+//! `send_to_recipient_set` hides the true recipient of a message among up to seven decoys.
+//! Every listed account must accept messages from the sender, and the deposit scales with
+//! the size of the set. Any member can acknowledge the message to refund the deposit, and a
+//! message from a member back to the sender refunds its `MaxAutoRefundsPerReply` most recent
+//! unsettled deposits for sets listing that member. A batch of replies shares one such
+//! allowance.
+//!
+//! ### Contact-List Backups
+//!
+//! Accounts anchor encrypted backups of their contact list with `commit_backup`. Versions
+//! must increase, and the last four commitments are kept, each backed by `BackupDeposit`.
+//!
+//! ### Protocol Fee
+//!
+//! `send_message_hash` withdraws `message_protocol_fee` from the sender, separately from
+//! transaction fees, and hands it to `ProtocolFeeIncome`, which burns it by default. The
+//! parameters can exempt messages between mutual contacts.
+//!
+//! ### Inbox Pricing Tiers
+//!
+//! Recipients can price paid messages by sender class with `set_inbox_pricing`: one price for
+//! senders whose identity `IdentityVerifier` vouches for and another for everyone else, while
+//! contacts send for free. The tier charged is reported in `InboxPaymentHeld`.
+//!
+//! ### Account Standing
+//!
+//! The `standing` view function, also served by the `MessagingStandingApi` runtime API, reports
+//! everything that decides whether an account can send: its bond against the strike-adjusted
+//! requirement, remaining rate limit, bans, freezes, pauses, graylisting and whether it can pay
+//! the protocol fee, as well as whether its bond is refundable yet.
+//!
+//! For a specific recipient, `can_message` returns the first check `send_message_hash` would
+//! fail as a [`CanMessageError`]. `send_message_hash` runs the same function before anything
+//! else, so the pre-flight check and dispatch cannot disagree.
+//!
+//! ### Message Credits
+//!
+//! Frequent senders can prepay protocol fees with `purchase_credits`. Each message that would
+//! be charged the fee uses a credit instead; rate limits, bonds and deposits still apply. The
+//! `CreditRefund` portion of the price stays on hold until a credit is used and is returned
+//! for unused credits by `refund_unused_credits`. The rest is collected on purchase.
+//!
+//! ### Sponsored Registration
+//!
+//! Onboarding services can pay the spam bond of new users with `sponsor_registration`, given
+//! the user's signature consenting to the sponsor and key. The bond stays held from the
+//! sponsor, who gets it back on refund and loses it to slashes and bans, keeping sponsors
+//! accountable for the accounts they onboard. The user can take the bond over with
+//! `assume_own_bond`.
+//!
+//! ### Bond Assets
+//!
+//! Bonds, deposits and fees are held in `Currency`, the native currency by default. Runtimes
+//! can denominate them in an asset instead by setting `Currency` to [`BondCurrency`] over an
+//! assets pallet with holds. Amounts in the parameters are then fixed amounts of that asset;
+//! no price conversion takes place.
+//!
+//! ### Challenger Reward Pool
+//!
+//! Slash shares alone are worth little when spam bonds are small, so upheld challengers are
+//! also paid a base reward from a reward pool held by a pallet sub-account. Anyone can
+//! fund the pool with `fund_reward_pool`, and `RewardPoolFeeShare` of every protocol fee is
+//! paid into it. A pool short of the reward pays what it holds above the existential deposit;
+//! a dry pool pays nothing and never holds up the verdict.
+//!
+//! ### Slash Parameters
+//!
+//! The challenger's share of a slash, the auto-slash percentage, the base challenge reward
+//! and where the rest of a slash goes (`SlashIncome`, burned, or the reward pool) are
+//! [`PalletParameters`] that `AdminOrigin` can change, defaulting to the corresponding
+//! config constants. A challenge keeps the values in force when it was opened, so changes
+//! only apply to challenges opened afterwards.
+//!
+//! ### Conversation History
+//!
+//! Messages between two accounts are indexed by their `sequence` number, so the
+//! `conversation` runtime API can page through a conversation in both directions at once,
+//! up to `MAX_CONVERSATION_PAGE_SIZE` messages per call. Pruning raises the conversation's
+//! floor and the API reports the lowest sequence number still stored; takedowns and
+//! retractions leave gaps. Messages sent before the index existed are not indexed and read
+//! as pruned history.
+//!
+//! ### Contact Requests
+//!
+//! Approving an account that has not approved the caller sends it a contact request. Each
+//! account's incoming and outgoing requests are indexed, oldest first, for the
+//! `incoming_requests` and `outgoing_requests` view functions. Approving back accepts a
+//! request, and `remove_contact` by either side withdraws or rejects it. The index keeps
+//! the latest `MaxPendingContactRequests` requests per account and direction, and approvals
+//! made before it existed are not listed.
+//!
+//! ### Dormant Profiles
+//!
+//! Sending or acknowledging a message, approving or removing a contact and registering or
+//! updating a profile count as activity, reported as `last_activity` by the
+//! `profile_activity` view function. Anyone can `flag_inactive` a profile without activity
+//! for `InactivityThreshold` blocks. Sends to a dormant profile fail with `RecipientDormant`
+//! until its owner is active again, which clears the flag. After `DormantSweepThreshold`
+//! blocks without activity, `ForceOrigin` can remove a dormant profile with
+//! `sweep_dormant_profile`, refunding its bond. Profiles without recorded activity count as
+//! inactive since genesis.
+//!
+//! ### Deregistration
+//!
+//! Owners remove their profile with `deregister_profile`, which fails while spam challenges
+//! against them are open. `ForceOrigin` can do the same with `force_deregister_profile` even
+//! then. Either way the bond stays held while challenges or recent messages still need it and
+//! is claimed later with `refund_bond`. Messages sent before leaving stay verifiable until
+//! they expire and report `MessageStatus::SenderDeparted`. Their inbox payments can be
+//! refunded to the sender at once.
+//!
+//! ### Expiry Events
+//!
+//! Pruning reports each expired message in a `MessageExpired` event, up to
+//! `MaxExpiryEventsPerBlock` per block. Each pruning call then reports the rest in one
+//! `MessagesExpiredBatch` covering every message from `first_id` to `last_id` that was still
+//! stored, so indexers can tell pruned messages from ones that never existed or were removed
+//! earlier, which have events of their own.
+//!
+//! ### Lookup by Hash
+//!
+//! Direct messages are indexed by their hash, so a recipient holding only the ciphertext can
+//! find the on-chain record through `message_by_hash`. A hash identifies at most one live
+//! message: sending it again fails with `DuplicateMessageHash` until the earlier message has
+//! expired. Retraction, takedown and pruning drop the index entry with the message.
+//!
+//! ### Message States
+//!
+//! `message_state` tells apart a message that is active, under challenge, expired,
+//! retracted, taken down or not stored at all. Verification and the extrinsics acting on a
+//! message derive their errors from it, so every caller sees the same reason. A retraction
+//! is remembered until the message would have been pruned; pruned messages are not.
+//!
+//! ### Inbox Sync
+//!
+//! Every account has an inbox revision, bumped whenever a message to it is delivered,
+//! acknowledged, refunded, retracted, removed or pruned. Clients read `inbox_revision` and
+//! only page through `inbox_of` once it has moved, passing the revision they last synced at
+//! as `since_revision`.
+//!
+//! ### Conversation Summaries
+//!
+//! `conversation_summary` gives a chat list what it needs per thread without paging through
+//! messages: how many are still stored, how many paid messages await acknowledgement in
+//! each direction, and the last message and activity. The pallet updates the summary as
+//! messages are sent, acknowledged, refunded, retracted, taken down and pruned.
+//!
+//! ### Push Notifications
+//!
+//! Recipients can opt in to push notifications by registering an opaque hint naming their
+//! push relay with `set_notification_hint`. Every direct message to them then also emits
+//! `NotifyRelay` with the hash of the hint bytes, the recipient and the message ID, so a relay
+//! only watches for its own hint hash. The event carries neither the sender nor the
+//! content, but names the recipient even under `EventPrivacyMode::Minimal`.
+//!
+//! ### On-Chain Verification
+//!
+//! `verify_message_hash` only answers a runtime query. A recipient that needs evidence of
+//! the check, e.g. for a dispute or a delivery agreement, calls `verify_message_on_chain`
+//! instead, which records whether the claimed hash matched and at which block. Each
+//! message can be verified once, and the record is dropped with the message. A mismatch
+//! counts as a spam report against the sender, unless the recipient already reported it.
+//!
+//! Relays checking a backlog pass up to `MAX_VERIFICATION_BATCH` ID and hash pairs at once to
+//! the `verify_message_batch` view function or the `verify_messages` runtime API. Each pair
+//! gets its own `VerificationResult`, so one missing message does not fail the rest.
+//!
+//! ### Compact Send Events
+//!
+//! `send_message_hash_batch` sends up to `MaxSendBatch` messages in one call. Senders that
+//! enable `compact_events` with `set_compact_events` get one `MessagesSentCompact` event per
+//! batch, committing to its messages in a merkle root, instead of a `MessageSent` event each.
+//! See the [`compact_events`] module for the tree and how recipients and indexers use it.
+//!
+//! ### Sessions
+//!
+//! After an off-chain handshake, the initiator commits to the session with `record_session`,
+//! optionally naming the peer's ephemeral key it used as a prekey, and the peer confirms it
+//! with `confirm_session` and the same commitment. Either party can `terminate_session`.
+//! Recording a session again replaces the previous one under the next epoch, and needs a
+//! fresh confirmation. Accounts that enable `set_session_requirement` only receive direct
+//! messages from senders they share a confirmed session with, in either direction.
+//!
+//! ### Device Keys
+//!
+//! Besides its profile key, an account registers a key per device with `add_device_key`, up
+//! to `MaxDevicesPerAccount`, each with an attestation counterparties check off-chain and a
+//! `DeviceDeposit` held until the device is removed. `replace_device_key` rolls a device to a
+//! new key under the same device ID, and `remove_device_key` frees its slot. Removing the
+//! profile removes its devices. Every change bumps the account's `devices_revision`, which
+//! clients caching device keys compare through the `devices_revision` view function.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -47,460 +313,12682 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod xcm_mock;
+
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub mod compact_events;
+pub mod extensions;
+#[cfg(feature = "identity")]
+pub mod identity;
+pub mod membership;
+pub mod migrations;
+pub mod offchain_index;
+pub mod stealth;
 pub mod weights;
 pub use weights::*;
 
 use frame_support::pallet_prelude::*;
 use frame_system::pallet_prelude::*;
-use sp_runtime::traits::{Hash, Saturating};
-use sp_std::vec::Vec;
+use sp_runtime::{
+	traits::{
+		AccountIdConversion, Hash as HashT, IdentifyAccount, Saturating, UniqueSaturatedInto,
+		Verify, Zero,
+	},
+	Perbill,
+};
+use sp_std::{boxed::Box, vec, vec::Vec};
+use xcm::latest::Location;
 
 pub type BalanceOf<T> =
-	<<T as Config>::Currency as frame_support::traits::Currency<
+	<<T as Config>::Currency as frame_support::traits::fungible::Inspect<
 		<T as frame_system::Config>::AccountId,
 	>>::Balance;
 
 pub type MessageId = u64;
 
-#[frame_support::pallet]
-pub mod pallet {
-	use super::*;
-	use frame_support::traits::{Currency, ReservableCurrency, Time};
+/// A message stored on-chain.
+///
+/// Replaced the positional `(hash, sent_at, sender, recipient)` tuple in storage version 2.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct MessageRecord<AccountId, Hash, BlockNumber> {
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Account the message was sent to.
+	pub recipient: AccountId,
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+}
 
-	#[pallet::pallet]
-	pub struct Pallet<T>(_);
+/// Encryption public key registered with a profile.
+pub type PublicKey = BoundedVec<u8, ConstU32<256>>;
 
-	#[pallet::config]
-	pub trait Config: frame_system::Config {
-		/// The overarching event type.
-		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+/// A registered profile, as stored in `UserProfiles`.
+///
+/// Holds what the send path checks for an account with a profile, so the sender and the
+/// recipient each cost a single read.
+#[derive(Encode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ProfileRecord<Balance, BlockNumber> {
+	/// Key messages to the account are encrypted to.
+	pub public_key: PublicKey,
+	/// Block at which the profile was flagged dormant, while it is dormant.
+	pub dormant_since: Option<BlockNumber>,
+	/// Spam bond the account was required to post when it registered, which stays its
+	/// requirement if the parameter changes. `None` for accounts registered before bonds
+	/// were recorded, which posted `SpamBond`.
+	pub registration_bond: Option<Balance>,
+	/// Whether the account only receives direct messages over a confirmed session with the
+	/// sender.
+	pub session_required: bool,
+}
+
+// Profiles stored before version 8 are a bare public key and decode as active, and profiles
+// stored before version 10 decode without a recorded bond or session requirement, so they
+// stay readable while `migrations::v8` and `migrations::v10` rewrite them.
+impl<Balance: Decode, BlockNumber: Decode> Decode for ProfileRecord<Balance, BlockNumber> {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let public_key = PublicKey::decode(input)?;
+		let mut profile = Self {
+			public_key,
+			dormant_since: None,
+			registration_bond: None,
+			session_required: false,
+		};
+		if input.remaining_len()? != Some(0) {
+			profile.dormant_since = Option::<BlockNumber>::decode(input)?;
+		}
+		if input.remaining_len()? != Some(0) {
+			profile.registration_bond = Option::<Balance>::decode(input)?;
+			profile.session_required = bool::decode(input)?;
+		}
+		Ok(profile)
+	}
+}
 
-		/// Currency type for handling bonds.
-		type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+/// Profile record as stored by the pallet.
+pub type ProfileRecordOf<T> =
+	ProfileRecord<BalanceOf<T>, frame_system::pallet_prelude::BlockNumberFor<T>>;
 
-		/// Time provider for expiry checks.
-		type Time: Time;
+pub type MessageRecordOf<T> = MessageRecord<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	frame_system::pallet_prelude::BlockNumberFor<T>,
+>;
 
-		/// Weight information for extrinsics.
-		type WeightInfo: WeightInfo;
+/// A message stored under a recipient tag instead of the recipient's account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct StealthMessageRecord<AccountId, Hash, BlockNumber, Balance> {
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Tag the recipient derives from its key, see [`stealth`].
+	pub recipient_tag: Hash,
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Deposit held from the sender until the message is pruned.
+	pub deposit: Balance,
+}
 
-		/// The amount required as a spam prevention bond.
-		#[pallet::constant]
-		type SpamBond: Get<BalanceOf<Self>>;
+/// Stealth message as stored by the pallet.
+pub type StealthMessageRecordOf<T> = StealthMessageRecord<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	frame_system::pallet_prelude::BlockNumberFor<T>,
+	BalanceOf<T>,
+>;
 
-		/// Maximum number of contacts a user can have.
-		#[pallet::constant]
-		type MaxContactsPerUser: Get<u32>;
+/// Maximum number of accounts a message can be sent to as a recipient set.
+pub const MAX_RECIPIENT_SET_LEN: u32 = 8;
 
-		/// Number of blocks after which message hashes expire.
-		#[pallet::constant]
-		type MessageHashExpiry: Get<BlockNumberFor<Self>>;
+/// Accounts a message is sent to, only one of which can decrypt it.
+pub type RecipientSet<AccountId> = BoundedVec<AccountId, ConstU32<MAX_RECIPIENT_SET_LEN>>;
+
+/// Recipients and message hashes, as passed to `send_message_hash_batch`.
+pub type SendBatchOf<T> = BoundedVec<
+	(<T as frame_system::Config>::AccountId, <T as frame_system::Config>::Hash),
+	<T as Config>::MaxSendBatch,
+>;
+
+/// Most storage proof, in bytes, a single call may need at the configured bounds.
+///
+/// A fifth of the 5 MiB proof a parachain block can carry, so that a few worst-case calls
+/// still fit beside everything else in the block. `integrity_test` checks the worst case of
+/// every bounded call against it.
+pub const MAX_CALL_PROOF_SIZE: u64 = 1024 * 1024;
+
+/// Why one item of a multi-item call was rejected, as carried by `Error::ItemFailed`.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	TypeInfo,
+	frame_support::PalletError,
+)]
+pub enum ItemError {
+	/// The item repeats an earlier one, or names the caller itself.
+	Duplicate,
+	/// The account named by the item is banned.
+	Banned,
+	/// The account named by the item has no profile.
+	NotFound,
+	/// The account named by the item has not approved the caller.
+	NotApproved,
+	/// The message of the item cannot be sent, as `can_message` would report.
+	CannotMessage(CanMessageError),
+	/// The message hash of the item is the default hash.
+	InvalidHash,
+	/// The message hash of the item identifies a live message.
+	DuplicateHash,
+}
+
+/// A message sent to a set of accounts hiding its true recipient.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RecipientSetMessageRecord<AccountId, Hash, BlockNumber, Balance> {
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Accounts the message was sent to, including the true recipient.
+	pub recipients: RecipientSet<AccountId>,
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Deposit held from the sender until a recipient acknowledges the message or it is
+	/// pruned. Zero once acknowledged.
+	pub deposit: Balance,
+}
+
+/// Recipient-set message as stored by the pallet.
+pub type RecipientSetMessageRecordOf<T> = RecipientSetMessageRecord<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	frame_system::pallet_prelude::BlockNumberFor<T>,
+	BalanceOf<T>,
+>;
+
+/// A message left for an account that has not registered a profile yet.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct PendingMessageRecord<AccountId, Hash, BlockNumber, Balance> {
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Deposit held from the sender until the message is claimed or pruned.
+	pub deposit: Balance,
+}
+
+/// Pending message as stored by the pallet.
+pub type PendingMessageRecordOf<T> = PendingMessageRecord<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	frame_system::pallet_prelude::BlockNumberFor<T>,
+	BalanceOf<T>,
+>;
+
+/// Registration of a relay carrying sealed-sender messages.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RelayInfo<Balance, BlockNumber> {
+	/// Relay bond held, less what was slashed since.
+	pub bond: Balance,
+	/// Block at which the relay last carried a message, if ever.
+	pub last_carried_at: Option<BlockNumber>,
+	/// Number of messages carried at `last_carried_at`.
+	pub carried_in_block: u32,
+}
+
+/// Relay registration as stored by the pallet.
+pub type RelayInfoOf<T> =
+	RelayInfo<BalanceOf<T>, frame_system::pallet_prelude::BlockNumberFor<T>>;
+
+/// Number of backup commitments kept per account, including the latest.
+pub const MAX_BACKUP_HISTORY: u32 = 4;
+
+/// Commitment to an encrypted backup of an account's contact list.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BackupCommitment<Hash, BlockNumber, Balance> {
+	/// Hash of the encrypted backup.
+	pub backup_hash: Hash,
+	/// Version chosen by the account, increasing with every backup.
+	pub version: u32,
+	/// Block at which the backup was committed.
+	pub committed_at: BlockNumber,
+	/// Deposit held until the commitment leaves the history.
+	pub deposit: Balance,
+}
+
+/// Backup commitment as stored by the pallet.
+pub type BackupCommitmentOf<T> = BackupCommitment<
+	<T as frame_system::Config>::Hash,
+	frame_system::pallet_prelude::BlockNumberFor<T>,
+	BalanceOf<T>,
+>;
+
+/// Every `CREDIT_MILESTONE` credits used, the pallet reports how many an account has left.
+pub const CREDIT_MILESTONE: u32 = 10;
+
+/// Reputation record tracking upheld spam challenges against an account.
+///
+/// Strikes decay lazily: one strike is forgiven for every `StrikeDecayPeriod` blocks
+/// elapsed since `last_decay`, applied whenever the account interacts with the pallet.
+#[derive(
+	Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, Default, TypeInfo, MaxEncodedLen,
+)]
+pub struct ReputationRecord<BlockNumber> {
+	/// Number of strikes currently held.
+	pub strikes: u32,
+	/// Block from which the next strike decay is counted.
+	pub last_decay: BlockNumber,
+}
+
+/// Per-account send counter for the current rate-limit window.
+#[derive(
+	Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, Default, TypeInfo, MaxEncodedLen,
+)]
+pub struct RateLimitState<BlockNumber> {
+	/// Block at which the current window started.
+	pub window_start: BlockNumber,
+	/// Messages sent within the current window.
+	pub sent: u32,
+	/// Block of the most recently counted message.
+	pub last_block: BlockNumber,
+	/// Messages counted within `last_block`.
+	pub sent_in_block: u32,
+}
+
+/// Cumulative messaging activity of an account.
+#[derive(
+	Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, Default, TypeInfo, MaxEncodedLen,
+)]
+pub struct AccountStatistics<BlockNumber> {
+	/// Messages sent.
+	pub sent: u64,
+	/// Messages received.
+	pub received: u64,
+	/// Paid messages acknowledged.
+	pub acknowledged: u64,
+	/// Conversations started, by either side.
+	pub conversations: u32,
+	/// Block of the account's last recorded activity: a message sent or acknowledged, a
+	/// contact approved or removed, or a profile registered or updated.
+	pub last_active: BlockNumber,
+}
+
+/// Account statistics as stored by the pallet.
+pub type AccountStatisticsOf<T> =
+	AccountStatistics<frame_system::pallet_prelude::BlockNumberFor<T>>;
+
+/// Cumulative activity across the pallet, as stored by the pallet.
+#[derive(
+	Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, Default, TypeInfo, MaxEncodedLen,
+)]
+pub struct CumulativeStatistics {
+	/// Messages sent.
+	pub messages: u64,
+	/// Profiles registered.
+	pub profiles: u64,
+	/// Groups created.
+	pub groups: u64,
+}
+
+/// Activity across the pallet: what was ever created, and what is stored now.
+#[derive(
+	Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, Default, TypeInfo, MaxEncodedLen,
+)]
+pub struct GlobalStatistics {
+	/// Messages sent.
+	pub messages: u64,
+	/// Profiles registered.
+	pub profiles: u64,
+	/// Groups created.
+	pub groups: u64,
+	/// Direct messages stored, until they are pruned or removed.
+	pub stored_messages: u32,
+	/// Profiles currently registered.
+	pub stored_profiles: u32,
+}
+
+/// Running summary of the conversation between two accounts.
+#[derive(Encode, Clone, PartialEq, Eq, RuntimeDebug, Default, TypeInfo, MaxEncodedLen)]
+pub struct ConversationStatistics<BlockNumber> {
+	/// Messages of the conversation still stored.
+	pub messages: u32,
+	/// Paid messages awaiting acknowledgement, sent by the account of the pair that sorts
+	/// first and by the other one.
+	pub unacknowledged: (u32, u32),
+	/// ID of the last message sent in the conversation, which may since have been removed.
+	pub last_message_id: MessageId,
+	/// Block of the last message sent or acknowledged in the conversation.
+	pub last_activity: BlockNumber,
+	/// Sequence number of the next message sent in the conversation, which is also the
+	/// number of messages ever sent in it.
+	pub next_seq: u64,
+}
+
+// Statistics stored before version 8 decode with no message numbered yet, so they stay
+// readable while `migrations::v8` moves the sequence numbers in.
+impl<BlockNumber: Decode> Decode for ConversationStatistics<BlockNumber> {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let messages = u32::decode(input)?;
+		let unacknowledged = <(u32, u32)>::decode(input)?;
+		let last_message_id = MessageId::decode(input)?;
+		let last_activity = BlockNumber::decode(input)?;
+		let next_seq = match input.remaining_len()? {
+			Some(0) => 0,
+			_ => u64::decode(input)?,
+		};
+		Ok(Self { messages, unacknowledged, last_message_id, last_activity, next_seq })
 	}
+}
 
-	/// User profiles containing public keys for encryption.
-	/// Maps AccountId => PublicKey (as Vec<u8>)
-	#[pallet::storage]
-	#[pallet::getter(fn user_profiles)]
-	pub type UserProfiles<T: Config> =
-		StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<u8, ConstU32<256>>, OptionQuery>;
+/// Conversation statistics as stored by the pallet.
+pub type ConversationStatisticsOf<T> =
+	ConversationStatistics<frame_system::pallet_prelude::BlockNumberFor<T>>;
 
-	/// Message hashes for verification.
-	/// Maps MessageId => (Hash, BlockNumber, Sender, Recipient)
-	#[pallet::storage]
-	#[pallet::getter(fn message_hashes)]
-	pub type MessageHashes<T: Config> = StorageMap<
-		_,
-		Blake2_128Concat,
-		MessageId,
-		(T::Hash, BlockNumberFor<T>, T::AccountId, T::AccountId),
-		OptionQuery,
-	>;
+/// Outcome of a recipient verifying a message on-chain.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct MessageVerification<BlockNumber> {
+	/// Whether the claimed hash matched the stored one.
+	pub matched: bool,
+	/// Block at which the verification was recorded.
+	pub at: BlockNumber,
+}
 
-	/// Anti-spam bonds deposited by users.
-	/// Maps AccountId => Balance
-	#[pallet::storage]
-	#[pallet::getter(fn spam_bonds)]
-	pub type SpamBonds<T: Config> =
-		StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+/// Decaying count of spam reports filed against a sender.
+///
+/// Like strikes, one report is forgiven for every `ReportDecayPeriod` blocks elapsed since
+/// `last_decay`.
+#[derive(
+	Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, Default, TypeInfo, MaxEncodedLen,
+)]
+pub struct SpamReportRecord<BlockNumber> {
+	/// Number of reports currently counted.
+	pub reports: u32,
+	/// Block from which the next report decay is counted.
+	pub last_decay: BlockNumber,
+}
 
-	/// Approved contacts list (bidirectional).
-	/// Maps (AccountId, AccountId) => bool
-	#[pallet::storage]
-	#[pallet::getter(fn approved_contacts)]
-	pub type ApprovedContacts<T: Config> = StorageDoubleMap<
-		_,
-		Blake2_128Concat,
-		T::AccountId,
-		Blake2_128Concat,
-		T::AccountId,
-		bool,
-		ValueQuery,
-	>;
+/// Spam bond held for an account.
+#[derive(Encode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BondRecord<Balance, AccountId> {
+	/// Amount held.
+	pub amount: Balance,
+	/// Account the bond is held from, if a sponsor registered the bonded account.
+	pub sponsor: Option<AccountId>,
+}
 
-	/// Count of contacts per user.
-	/// Maps AccountId => u32
-	#[pallet::storage]
-	#[pallet::getter(fn contact_count)]
-	pub type ContactCount<T: Config> =
-		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+// Bonds stored before version 4 are a bare amount and decode as held from the bonded account,
+// so they stay readable while `migrations::v4` rewrites them.
+impl<Balance: Decode, AccountId: Decode> Decode for BondRecord<Balance, AccountId> {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let amount = Balance::decode(input)?;
+		let sponsor = match input.remaining_len()? {
+			Some(0) => None,
+			_ => Option::<AccountId>::decode(input)?,
+		};
+		Ok(Self { amount, sponsor })
+	}
+}
 
-	/// Message ID counter.
-	#[pallet::storage]
-	#[pallet::getter(fn next_message_id)]
-	pub type NextMessageId<T: Config> = StorageValue<_, MessageId, ValueQuery>;
+impl<Balance: Default, AccountId> Default for BondRecord<Balance, AccountId> {
+	fn default() -> Self {
+		Self { amount: Balance::default(), sponsor: None }
+	}
+}
 
-	#[pallet::event]
-	#[pallet::generate_deposit(pub(super) fn deposit_event)]
-	pub enum Event<T: Config> {
-		/// A user profile was registered.
-		ProfileRegistered { who: T::AccountId, public_key: Vec<u8> },
-		/// A message hash was recorded on-chain.
-		MessageSent { message_id: MessageId, from: T::AccountId, to: T::AccountId, hash: T::Hash },
-		/// A contact was approved.
-		ContactApproved { approver: T::AccountId, contact: T::AccountId },
-		/// A contact was removed.
-		ContactRemoved { remover: T::AccountId, contact: T::AccountId },
-		/// A message was challenged as spam.
-		SpamChallenged { message_id: MessageId, challenger: T::AccountId },
-		/// A spam bond was refunded.
-		BondRefunded { who: T::AccountId, amount: BalanceOf<T> },
-		/// A user profile was updated.
-		ProfileUpdated { who: T::AccountId, public_key: Vec<u8> },
+impl<Balance, AccountId> BondRecord<Balance, AccountId> {
+	/// Account the bond of `who` is held from: its sponsor, or `who` itself.
+	pub fn payer<'a>(&'a self, who: &'a AccountId) -> &'a AccountId {
+		self.sponsor.as_ref().unwrap_or(who)
 	}
+}
 
-	#[pallet::error]
-	pub enum Error<T> {
-		/// Profile already exists for this account.
-		ProfileAlreadyExists,
-		/// No profile found for this account.
-		ProfileNotFound,
-		/// Insufficient balance for spam bond.
-		InsufficientBond,
-		/// Invalid public key format.
-		InvalidPublicKey,
-		/// Recipient doesn't have a profile.
-		RecipientNotFound,
-		/// Maximum number of contacts reached.
-		MaxContactsReached,
-		/// Contact not in approved list.
-		ContactNotApproved,
-		/// Message not found in storage.
-		MessageNotFound,
-		/// Message hash has expired.
-		MessageExpired,
-		/// Not authorized to perform this action.
-		NotAuthorized,
-		/// Bond has already been refunded.
-		BondAlreadyRefunded,
-		/// Cannot add yourself as a contact.
-		CannotAddSelf,
-		/// Public key too large.
-		PublicKeyTooLarge,
+/// Spam bond record as stored by the pallet.
+pub type BondRecordOf<T> = BondRecord<BalanceOf<T>, <T as frame_system::Config>::AccountId>;
+
+/// Context signed by an account consenting to a sponsored registration, followed by the
+/// sponsor, public key and key algorithm.
+pub const SPONSORSHIP_CONTEXT: &[u8] = b"messaging/sponsor";
+
+/// Produces sponsorship proofs when benchmarking `sponsor_registration`.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait SponsorshipBenchmarkHelper<AccountId, Signature> {
+	/// Returns a fresh account and its signature over `payload`, or `None` if the runtime
+	/// cannot sign.
+	fn sign(payload: &[u8]) -> Option<(AccountId, Signature)>;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<AccountId, Signature> SponsorshipBenchmarkHelper<AccountId, Signature> for () {
+	fn sign(_: &[u8]) -> Option<(AccountId, Signature)> {
+		None
 	}
+}
 
-	#[pallet::call]
-	impl<T: Config> Pallet<T> {
-		/// Register a user profile with a public key.
-		///
-		/// The caller must have sufficient balance for the spam bond.
-		/// The public key will be stored on-chain for others to use for encryption.
-		///
-		/// Parameters:
-		/// - `public_key`: The user's public key (max 256 bytes)
-		///
-		/// Emits `ProfileRegistered` event on success.
-		#[pallet::call_index(0)]
-		#[pallet::weight(T::WeightInfo::register_profile())]
-		pub fn register_profile(
-			origin: OriginFor<T>,
-			public_key: Vec<u8>,
-		) -> DispatchResult {
-			let who = ensure_signed(origin)?;
+/// Portion of a spam bond that secured recently sent messages.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BondLock<Balance, BlockNumber> {
+	/// Bond amount held when the last message was sent.
+	pub amount: Balance,
+	/// Block until which the amount cannot be withdrawn.
+	pub until: BlockNumber,
+}
 
-			// Check if profile already exists
-			ensure!(!UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileAlreadyExists);
+/// Stage of a spam challenge on its way to a final verdict.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum ChallengeStatus<AccountId, Balance, BlockNumber> {
+	/// Awaiting a verdict from `ResolutionOrigin`.
+	Open,
+	/// Resolved at block `at`; the losing party may appeal for `AppealWindow` blocks.
+	Resolved { upheld: bool, at: BlockNumber },
+	/// Appealed by `appellant`, who holds `bond`; awaiting a final verdict from `AppealOrigin`.
+	Appealed { upheld: bool, appellant: AccountId, bond: Balance },
+}
 
-			// Validate public key
-			ensure!(!public_key.is_empty(), Error::<T>::InvalidPublicKey);
-			ensure!(public_key.len() <= 256, Error::<T>::PublicKeyTooLarge);
+/// A spam challenge against a message.
+///
+/// Challenges stay in storage until their verdict is final, so that bonds and slashed funds
+/// can still be redistributed if the verdict is overturned on appeal.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ChallengeInfo<AccountId, Balance, BlockNumber> {
+	/// Account that opened the challenge, or `None` for challenges opened automatically
+	/// from aggregated spam reports.
+	pub challenger: Option<AccountId>,
+	/// Sender of the challenged message.
+	pub sender: AccountId,
+	/// Block at which the challenge was opened.
+	pub opened_at: BlockNumber,
+	/// Bond held from the challenger.
+	pub bond: Balance,
+	/// Sender bond slashed into escrow by an upheld verdict.
+	pub escrowed: Balance,
+	/// Current stage of the challenge.
+	pub status: ChallengeStatus<AccountId, Balance, BlockNumber>,
+	/// Slash parameters in force when the challenge was opened.
+	pub terms: SlashTerms<Balance>,
+}
 
-			// Reserve spam bond
-			let bond_amount = T::SpamBond::get();
-			T::Currency::reserve(&who, bond_amount)
-				.map_err(|_| Error::<T>::InsufficientBond)?;
+/// Challenge record as stored by the pallet.
+pub type ChallengeInfoOf<T> = ChallengeInfo<
+	<T as frame_system::Config>::AccountId,
+	BalanceOf<T>,
+	frame_system::pallet_prelude::BlockNumberFor<T>,
+>;
 
-			// Store bond amount
-			SpamBonds::<T>::insert(&who, bond_amount);
+/// Maximum length of the evidence revealed for a challenge.
+pub const MAX_EVIDENCE_LEN: u32 = 1024;
 
-			// Convert to BoundedVec
-			let bounded_key: BoundedVec<u8, ConstU32<256>> =
-				public_key.clone().try_into().map_err(|_| Error::<T>::PublicKeyTooLarge)?;
+/// Evidence revealed by a challenger.
+pub type Evidence = BoundedVec<u8, ConstU32<MAX_EVIDENCE_LEN>>;
+
+/// Maximum length of a contact membership proof.
+pub const MAX_MEMBERSHIP_PROOF_LEN: u32 = 2048;
+
+/// Proof passed to `Config::MembershipProofVerifier`.
+pub type MembershipProof = BoundedVec<u8, ConstU32<MAX_MEMBERSHIP_PROOF_LEN>>;
+
+/// Maximum length of an ephemeral sender key.
+pub const MAX_EPHEMERAL_KEY_LEN: u32 = 32;
+
+/// Public key a sender publishes for a single message.
+pub type EphemeralKey = BoundedVec<u8, ConstU32<MAX_EPHEMERAL_KEY_LEN>>;
+
+/// Where a session stands.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum SessionStatus {
+	/// Recorded by the initiator and awaiting the peer's confirmation.
+	Pending,
+	/// Confirmed by the peer.
+	Confirmed,
+	/// Terminated by either party.
+	Terminated,
+}
+
+/// Session an initiator recorded with a peer, as stored in `Sessions`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct SessionInfo<Hash, BlockNumber> {
+	/// Commitment to the handshake, which the peer confirms.
+	pub commitment: Hash,
+	/// Index of the peer's ephemeral key the handshake used, if any.
+	pub prekey_index: Option<u32>,
+	/// Block at which the session was recorded.
+	pub established_at: BlockNumber,
+	/// Number of sessions the initiator recorded with the peer before this one.
+	pub epoch: u32,
+	/// Whether the session awaits confirmation, is confirmed or was terminated.
+	pub status: SessionStatus,
+}
+
+/// Session record as stored by the pallet.
+pub type SessionInfoOf<T> = SessionInfo<
+	<T as frame_system::Config>::Hash,
+	frame_system::pallet_prelude::BlockNumberFor<T>,
+>;
+
+/// Identifier of one of an account's devices, kept when its key is replaced.
+pub type DeviceId = u32;
+
+/// Most devices any runtime can let an account register. `MaxDevicesPerAccount` must not
+/// exceed it.
+pub const MAX_DEVICE_SLOTS: u32 = 16;
+
+/// Maximum length of a device key attestation.
+pub const MAX_DEVICE_ATTESTATION_LEN: u32 = 128;
+
+/// Proof that a device key belongs to the account, typically a signature over it by the
+/// profile key. The pallet stores it for counterparties to check.
+pub type DeviceAttestation = BoundedVec<u8, ConstU32<MAX_DEVICE_ATTESTATION_LEN>>;
+
+/// Key of one of an account's devices.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct DeviceKeyRecord<Balance, BlockNumber> {
+	/// Device the key belongs to.
+	pub device_id: DeviceId,
+	/// Key messages to the device are encrypted to.
+	pub public_key: PublicKey,
+	/// Attestation of the key by the account.
+	pub attestation: DeviceAttestation,
+	/// Deposit held from the account until the device is removed.
+	pub deposit: Balance,
+	/// Block at which the key was added or last replaced.
+	pub updated_at: BlockNumber,
+}
+
+/// Devices of an account, as stored in `DeviceSets`.
+#[derive(
+	Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, Default, TypeInfo, MaxEncodedLen,
+)]
+pub struct DeviceSet<Balance, BlockNumber> {
+	/// Keys of the registered devices, in the order they were added.
+	pub devices: BoundedVec<DeviceKeyRecord<Balance, BlockNumber>, ConstU32<MAX_DEVICE_SLOTS>>,
+	/// ID the next added device gets. IDs are not reused while the profile exists.
+	pub next_device_id: DeviceId,
+}
+
+/// Device set as stored by the pallet.
+pub type DeviceSetOf<T> =
+	DeviceSet<BalanceOf<T>, frame_system::pallet_prelude::BlockNumberFor<T>>;
+
+/// Evidence backing a challenge, committed to when the challenge is opened and revealed
+/// only if the sender contests it.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct EvidenceRecord<Hash, BlockNumber> {
+	/// `Pallet::evidence_commitment` of the evidence and a salt chosen by the challenger.
+	pub commitment: Hash,
+	/// Last block at which the evidence can be revealed, set once the sender contests.
+	pub reveal_by: Option<BlockNumber>,
+	/// The evidence, once revealed.
+	pub evidence: Option<Evidence>,
+}
+
+/// Evidence record as stored by the pallet.
+pub type EvidenceRecordOf<T> = EvidenceRecord<
+	<T as frame_system::Config>::Hash,
+	frame_system::pallet_prelude::BlockNumberFor<T>,
+>;
+
+/// Inbox price paid for a message to a recipient that has not approved the sender.
+///
+/// The amount stays on hold in the sender's account until the recipient acknowledges the
+/// message, the sender retracts it, or it expires.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct InboxPayment<AccountId, Balance> {
+	/// Sender of the paid message.
+	pub sender: AccountId,
+	/// Recipient the payment is owed to.
+	pub recipient: AccountId,
+	/// Amount held from the sender.
+	pub amount: Balance,
+}
+
+/// Inbox prices a recipient asks of senders it has not approved, by sender class.
+///
+/// A tier left at `None` closes the inbox to that class. Approved senders and holders of an
+/// inbox pass are never charged.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	Default,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub struct InboxPricing<Balance> {
+	/// Price for senders whose identity is verified by `IdentityVerifier`.
+	pub verified: Option<Balance>,
+	/// Price for every other sender.
+	pub others: Option<Balance>,
+}
+
+impl<Balance> InboxPricing<Balance> {
+	/// Whether no tier accepts paid messages.
+	pub fn is_closed(&self) -> bool {
+		self.verified.is_none() && self.others.is_none()
+	}
+}
+
+/// Sender class an inbox price was resolved for.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub enum InboxTier {
+	/// Sender approved by the recipient or holding an inbox pass.
+	Contact,
+	/// Sender with a verified identity.
+	Verified,
+	/// Any other sender, also used for the flat price set by `set_inbox_price`.
+	Others,
+}
+
+/// Where the part of a slashed bond not paid to the challenger goes.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	Default,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub enum SlashDestination {
+	/// Routed to `SlashIncome`, usually the treasury.
+	#[default]
+	Treasury,
+	/// Burned, reducing total issuance.
+	Burn,
+	/// Paid into the challenge reward pool, or routed to `SlashIncome` if too small to open
+	/// an empty pool.
+	RewardPool,
+}
+
+/// Slash parameters a challenge is settled with, taken from [`PalletParameters`] when it is
+/// opened.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	Default,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub struct SlashTerms<Balance> {
+	/// Portion of the escrowed slash paid to the challenger of an upheld challenge.
+	pub challenger_reward_percent: Perbill,
+	/// Portion of the remaining bond slashed when the sender reaches `AutoSlashThreshold`.
+	pub auto_slash_percent: Perbill,
+	/// Reward paid to the challenger of an upheld challenge from the challenge reward pool.
+	pub base_challenge_reward: Balance,
+	/// Where the rest of the slash goes.
+	pub slash_destination: SlashDestination,
+}
+
+/// How much of a message or contact change the pallet's events reveal.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	Default,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub enum EventPrivacyMode {
+	/// Events name the accounts involved.
+	#[default]
+	Full,
+	/// `MessageSentMinimal`, `ContactApprovedMinimal` and `ContactRemovedMinimal` are emitted
+	/// instead of their full counterparts, leaving the accounts involved to be read from
+	/// storage by the parties.
+	Minimal,
+}
+
+/// Pallet parameters adjustable by `AdminOrigin` without a runtime upgrade.
+#[derive(Encode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct PalletParameters<Balance, BlockNumber> {
+	/// Spam bond held from newly registered profiles.
+	pub spam_bond: Balance,
+	/// Number of blocks after which message hashes expire.
+	pub message_expiry: BlockNumber,
+	/// Maximum number of contacts a user can have.
+	pub max_contacts: u32,
+	/// How much the pallet's events reveal.
+	pub event_privacy: EventPrivacyMode,
+	/// Protocol fee withdrawn from the sender of every `send_message_hash` and routed to
+	/// `ProtocolFeeIncome`, on top of transaction fees.
+	pub message_protocol_fee: Balance,
+	/// Whether senders messaging a mutual contact are exempt from the protocol fee.
+	pub exempt_mutual_contacts: bool,
+	/// Portion of the escrowed slash paid to the challenger of an upheld challenge.
+	pub challenger_reward_percent: Perbill,
+	/// Portion of the remaining bond slashed when the sender reaches `AutoSlashThreshold`.
+	pub auto_slash_percent: Perbill,
+	/// Reward paid to the challenger of an upheld challenge from the challenge reward pool.
+	pub base_challenge_reward: Balance,
+	/// Where the part of a slashed bond not paid to the challenger goes.
+	pub slash_destination: SlashDestination,
+}
+
+// Parameters stored before `event_privacy` was added end after `max_contacts`, and those
+// stored before the protocol fee was added end after `event_privacy`. Missing trailing fields
+// decode with their defaults, so adding them needs no migration. The slash parameters have no
+// sensible default and are filled in from the config by `migrations::v6`.
+impl<Balance: Decode + Default, BlockNumber: Decode> Decode
+	for PalletParameters<Balance, BlockNumber>
+{
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let spam_bond = Balance::decode(input)?;
+		let message_expiry = BlockNumber::decode(input)?;
+		let max_contacts = u32::decode(input)?;
+		let event_privacy = match input.remaining_len()? {
+			Some(0) => EventPrivacyMode::default(),
+			_ => EventPrivacyMode::decode(input)?,
+		};
+		let (message_protocol_fee, exempt_mutual_contacts) = match input.remaining_len()? {
+			Some(0) => (Balance::default(), false),
+			_ => (Balance::decode(input)?, bool::decode(input)?),
+		};
+		let terms = match input.remaining_len()? {
+			Some(0) => SlashTerms::default(),
+			_ => SlashTerms::decode(input)?,
+		};
+		Ok(Self {
+			spam_bond,
+			message_expiry,
+			max_contacts,
+			event_privacy,
+			message_protocol_fee,
+			exempt_mutual_contacts,
+			challenger_reward_percent: terms.challenger_reward_percent,
+			auto_slash_percent: terms.auto_slash_percent,
+			base_challenge_reward: terms.base_challenge_reward,
+			slash_destination: terms.slash_destination,
+		})
+	}
+}
+
+impl<Balance: Clone, BlockNumber> PalletParameters<Balance, BlockNumber> {
+	/// The slash parameters, as snapshotted into new challenges.
+	pub fn slash_terms(&self) -> SlashTerms<Balance> {
+		SlashTerms {
+			challenger_reward_percent: self.challenger_reward_percent,
+			auto_slash_percent: self.auto_slash_percent,
+			base_challenge_reward: self.base_challenge_reward.clone(),
+			slash_destination: self.slash_destination,
+		}
+	}
+}
+
+impl<Balance, BlockNumber> DecodeWithMemTracking for PalletParameters<Balance, BlockNumber>
+where
+	Balance: DecodeWithMemTracking + Default,
+	BlockNumber: DecodeWithMemTracking,
+{
+}
+
+/// Parameters as stored by the pallet.
+pub type PalletParametersOf<T> =
+	PalletParameters<BalanceOf<T>, frame_system::pallet_prelude::BlockNumberFor<T>>;
+
+/// Set of call groups that can be paused independently.
+///
+/// Calls outside these groups, such as acknowledgements, bond refunds, challenge housekeeping
+/// and governance calls, are never paused.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	Default,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub struct PausedCalls(u8);
+
+impl PausedCalls {
+	/// No calls paused.
+	pub const NONE: Self = Self(0);
+	/// Sending messages.
+	pub const SENDS: Self = Self(1 << 0);
+	/// Registering and updating profiles.
+	pub const REGISTRATIONS: Self = Self(1 << 1);
+	/// Approving new contacts.
+	pub const CONTACTS: Self = Self(1 << 2);
+	/// Every pausable call group.
+	pub const ALL: Self = Self(Self::SENDS.0 | Self::REGISTRATIONS.0 | Self::CONTACTS.0);
+
+	/// Whether every group in `other` is in this set.
+	pub fn contains(self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	/// Whether any group in `other` is in this set.
+	pub fn intersects(self, other: Self) -> bool {
+		self.0 & other.0 & Self::ALL.0 != 0
+	}
+
+	/// Whether no group is in this set.
+	pub fn is_empty(self) -> bool {
+		self.0 & Self::ALL.0 == 0
+	}
+
+	/// Groups in either set.
+	pub fn union(self, other: Self) -> Self {
+		Self((self.0 | other.0) & Self::ALL.0)
+	}
+
+	/// Groups in this set but not in `other`.
+	pub fn difference(self, other: Self) -> Self {
+		Self(self.0 & !other.0 & Self::ALL.0)
+	}
+}
+
+/// Record of an account banned by `ForceOrigin`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BanRecord<Hash, BlockNumber> {
+	/// Block at which the ban was imposed.
+	pub at: BlockNumber,
+	/// Hash of the off-chain reason for the ban.
+	pub reason: Hash,
+}
+
+/// Action taken by a moderator, recorded in `ModeratorAction` events.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub enum ModeratorActionKind<BlockNumber> {
+	/// Opened a challenge against one of the target's messages.
+	OpenChallenge { message_id: MessageId },
+	/// Resolved a challenge against the target that was opened without a challenger.
+	FastTrackChallenge { message_id: MessageId, upheld: bool },
+	/// Blocked the target's sends until `until`.
+	FreezeSender { until: BlockNumber },
+	/// Lifted a freeze on the target's sends.
+	UnfreezeSender,
+}
+
+/// Ensures the origin is a signed account in the on-chain moderator set.
+///
+/// Succeeds with the moderator's account, so other calls can be gated on the set.
+pub struct EnsureModerator<T>(PhantomData<T>);
+
+impl<T: Config> EnsureOrigin<OriginFor<T>> for EnsureModerator<T> {
+	type Success = T::AccountId;
+
+	fn try_origin(o: OriginFor<T>) -> Result<Self::Success, OriginFor<T>> {
+		match o.clone().into() {
+			Ok(frame_system::RawOrigin::Signed(who)) if Pallet::<T>::is_moderator(&who) => Ok(who),
+			_ => Err(o),
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin() -> Result<OriginFor<T>, ()> {
+		Moderators::<T>::get()
+			.first()
+			.map(|who| frame_system::RawOrigin::Signed(who.clone()).into())
+			.ok_or(())
+	}
+}
+
+/// Inbox payment record as stored by the pallet.
+pub type InboxPaymentOf<T> = InboxPayment<<T as frame_system::Config>::AccountId, BalanceOf<T>>;
+
+/// Tiered inbox pricing as stored by the pallet.
+pub type InboxPricingOf<T> = InboxPricing<BalanceOf<T>>;
+
+/// Encryption scheme of a profile's public key.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	Default,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub enum KeyAlgorithm {
+	/// X25519 Diffie-Hellman key.
+	#[default]
+	X25519,
+	/// Ed25519 key converted for encryption by the client.
+	Ed25519,
+	/// Secp256k1 ECIES key.
+	Secp256k1,
+}
+
+/// A public key or group key commitment replaced by a later version.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RevokedKey<BlockNumber> {
+	/// The revoked public key.
+	pub public_key: BoundedVec<u8, ConstU32<256>>,
+	/// Version the key had while it was current.
+	pub version: u32,
+	/// Block at which the key was replaced.
+	pub revoked_at: BlockNumber,
+}
+
+/// Reference to the legal order behind a message takedown.
+pub type OrderReference = BoundedVec<u8, ConstU32<64>>;
+
+/// Opaque identifier of the push relay an account receives notifications through.
+pub type NotificationHint = BoundedVec<u8, ConstU32<64>>;
+
+/// Tombstone left in place of a message removed by governance.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct TakedownRecord<AccountId, BlockNumber> {
+	/// Sender of the removed message.
+	pub sender: AccountId,
+	/// Recipient of the removed message.
+	pub recipient: AccountId,
+	/// Block at which the message was removed.
+	pub removed_at: BlockNumber,
+	/// Reference to the order requiring the removal.
+	pub order_reference: OrderReference,
+}
+
+/// Takedown record as stored by the pallet.
+pub type TakedownRecordOf<T> =
+	TakedownRecord<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>;
+
+/// Privileged action recorded in the admin audit log.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub enum AuditAction {
+	/// `resolve_challenge`, targeting the message ID.
+	ResolveChallenge,
+	/// `resolve_appeal`, targeting the message ID.
+	ResolveAppeal,
+	/// `pause`, targeting the paused call groups.
+	Pause,
+	/// `unpause`, targeting the unpaused call groups.
+	Unpause,
+	/// `force_remove_message`, targeting the message ID.
+	ForceRemoveMessage,
+	/// `update_parameters`, targeting the new parameters.
+	UpdateParameters,
+	/// `add_moderator`, targeting the account.
+	AddModerator,
+	/// `remove_moderator`, targeting the account.
+	RemoveModerator,
+	/// `moderator_challenge`, targeting the message ID.
+	ModeratorChallenge,
+	/// `fast_track_challenge`, targeting the message ID.
+	FastTrackChallenge,
+	/// `freeze_sender`, targeting the account.
+	FreezeSender,
+	/// `unfreeze_sender`, targeting the account.
+	UnfreezeSender,
+	/// `ban_account`, targeting the account.
+	BanAccount,
+	/// `unban_account`, targeting the account.
+	UnbanAccount,
+	/// `reset_income`, targeting the income category.
+	ResetIncome,
+	/// `force_reset_key`, targeting the account.
+	ForceResetKey,
+	/// `set_registration_mode`, targeting the new mode.
+	SetRegistrationMode,
+	/// `whitelist_account`, targeting the account.
+	WhitelistAccount,
+	/// `remove_from_whitelist`, targeting the account.
+	RemoveFromWhitelist,
+	/// `force_register_profile`, targeting the account.
+	ForceRegisterProfile,
+	/// `force_remove_group_message`, targeting the group message ID.
+	ForceRemoveGroupMessage,
+	/// `sweep_dormant_profile`, targeting the account.
+	SweepDormantProfile,
+	/// `force_deregister_profile`, targeting the account.
+	ForceDeregisterProfile,
+}
+
+/// Who may register a profile.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	Default,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub enum RegistrationMode {
+	/// Any account able to post the spam bond.
+	#[default]
+	Open,
+	/// Only accounts in `RegistrationWhitelist`.
+	Whitelisted,
+}
+
+/// Entry of the admin audit log.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct AuditEntry<BlockNumber, Hash> {
+	/// Block at which the action was taken.
+	pub block: BlockNumber,
+	/// The action taken.
+	pub action: AuditAction,
+	/// Hash of the SCALE-encoded target of the action, e.g. an account or message ID.
+	pub target_hash: Hash,
+}
+
+/// Audit log entry as stored by the pallet.
+pub type AuditEntryOf<T> =
+	AuditEntry<BlockNumberFor<T>, <T as frame_system::Config>::Hash>;
+
+/// Number of entries kept in the admin audit log; the oldest is evicted first.
+pub const MAX_AUDIT_LOG_ENTRIES: u32 = 128;
+
+/// Maximum number of revoked keys kept per account; the oldest is dropped first.
+pub const MAX_KEY_HISTORY: u32 = 8;
+
+/// Outcome of checking a hash against a stored message.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum VerificationResult {
+	/// The hash matches the stored message.
+	Valid,
+	/// The hash differs from the stored message.
+	Mismatch,
+	/// No message is stored under the ID.
+	NotFound,
+	/// The message exists but has expired.
+	Expired,
+	/// The message was removed by governance.
+	RemovedByGovernance,
+	/// The sender retracted the message.
+	Retracted,
+}
+
+/// Maximum number of hashes checked by one call of `verify_message_batch` or the runtime
+/// API's `verify_messages`.
+pub const MAX_VERIFICATION_BATCH: u32 = 256;
+
+/// Message IDs and the hashes to check against them, as passed to `verify_message_batch`.
+pub type VerificationBatch<Hash> = BoundedVec<(MessageId, Hash), ConstU32<MAX_VERIFICATION_BATCH>>;
+
+/// Where a direct message stands, as returned by [`Pallet::message_state`].
+///
+/// Every check of a message against its lifecycle goes through this, so queries and
+/// extrinsics report the same reason for a message that cannot be used.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum MessageState<BlockNumber> {
+	/// The message is stored and verifies until `expires_at`.
+	Active { expires_at: BlockNumber },
+	/// The message is stored and under a spam challenge without a final verdict. It still
+	/// verifies.
+	Challenged,
+	/// The message is stored but has expired and will be pruned.
+	Expired,
+	/// No message is stored under the ID, either because none was sent or because it was
+	/// already pruned.
+	NotFound,
+	/// The sender retracted the message. Reported until the message would have been pruned.
+	Retracted,
+	/// The message was removed by governance. Reported for `TakedownRetention` blocks.
+	TakenDown,
+}
+
+impl<BlockNumber> MessageState<BlockNumber> {
+	/// Whether the message is stored and has not expired.
+	pub fn is_live(&self) -> bool {
+		matches!(self, MessageState::Active { .. } | MessageState::Challenged)
+	}
+}
+
+/// Message state as returned by the pallet.
+pub type MessageStateOf<T> = MessageState<frame_system::pallet_prelude::BlockNumberFor<T>>;
+
+/// A stored message as seen by off-chain clients.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct MessageRecordView<AccountId, Hash, BlockNumber> {
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Account the message was sent to.
+	pub recipient: AccountId,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Last block at which the message can still be verified.
+	pub expires_at: BlockNumber,
+}
+
+/// Maximum number of entries returned by one page of a paginated view function.
+pub const MAX_VIEW_PAGE_SIZE: u32 = 100;
+
+/// A message in a recipient's inbox, as returned by the `inbox_of` view function.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct MessageSummary<AccountId, Hash, BlockNumber> {
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Whether the message has expired.
+	pub expired: bool,
+	/// Whether a payment for the message is awaiting acknowledgement.
+	pub paid: bool,
+}
+
+/// Inbox entry as returned by the pallet.
+pub type MessageSummaryOf<T> = MessageSummary<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	BlockNumberFor<T>,
+>;
+
+/// Spam bond of an account, as returned by the `bond_status` view function.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct BondStatus<Balance, BlockNumber> {
+	/// Bond currently held.
+	pub bonded: Balance,
+	/// Bond the account must hold to send messages.
+	pub required: Balance,
+	/// Part of the bond that cannot be withdrawn yet.
+	pub locked: Balance,
+	/// Block until which `locked` cannot be withdrawn, if anything is locked.
+	pub locked_until: Option<BlockNumber>,
+	/// Current strike count.
+	pub strikes: u32,
+}
+
+/// Bond status as returned by the pallet.
+pub type BondStatusOf<T> = BondStatus<BalanceOf<T>, BlockNumberFor<T>>;
+
+/// Everything that decides whether an account can send messages, as returned by the
+/// `standing` view function.
+///
+/// Together with the recipient's profile, contacts and ban status, these fields predict the
+/// outcome of `send_message_hash`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct AccountStanding<Balance, BlockNumber> {
+	/// Whether the account has registered a profile.
+	pub registered: bool,
+	/// Whether the account is banned.
+	pub banned: bool,
+	/// Block until which a moderator froze the account's sends, if it is frozen.
+	pub frozen_until: Option<BlockNumber>,
+	/// Whether sends are paused for every account.
+	pub sends_paused: bool,
+	/// Current strike count.
+	pub strikes: u32,
+	/// Whether the account may only message mutual contacts because of its strikes.
+	pub graylisted: bool,
+	/// Spam bond currently held.
+	pub bonded: Balance,
+	/// Spam bond the account must hold to send messages.
+	pub required_bond: Balance,
+	/// Messages the account may still send in the current rate-limit window.
+	pub rate_limit_remaining: u32,
+	/// Whether the account can pay the message protocol fee where it is not exempt, from its
+	/// balance or with a prepaid credit.
+	pub can_pay_protocol_fee: bool,
+	/// Unused prepaid message credits.
+	pub message_credits: u32,
+	/// Open spam challenges against the account, which keep its bond from being refunded.
+	pub open_challenges: u32,
+	/// Block until which recent messages lock the bond, if it is locked.
+	pub bond_locked_until: Option<BlockNumber>,
+	/// Whether `refund_bond` would succeed.
+	pub can_refund_bond: bool,
+}
+
+/// Account standing as returned by the pallet.
+pub type AccountStandingOf<T> = AccountStanding<BalanceOf<T>, BlockNumberFor<T>>;
+
+/// Why `send_message_hash` would fail, as returned by [`Pallet::can_message`].
+///
+/// Variants are listed in the order the send path checks them.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	TypeInfo,
+	frame_support::PalletError,
+)]
+pub enum CanMessageError {
+	/// Sends are paused.
+	Paused,
+	/// The sender is banned.
+	SenderBanned,
+	/// The recipient is banned.
+	RecipientBanned,
+	/// A moderator froze the sender's sends.
+	SenderFrozen,
+	/// The sender has no profile.
+	NoSenderProfile,
+	/// The recipient has no profile.
+	NoRecipientProfile,
+	/// The recipient's profile was flagged dormant.
+	RecipientDormant,
+	/// The sender is graylisted and the recipient is not a mutual contact.
+	SenderGraylisted,
+	/// The sender's bond is below its strike-adjusted requirement.
+	InsufficientBond,
+	/// The sender has used up its current rate-limit window.
+	RateLimited,
+	/// The sender can pay the protocol fee neither from its balance nor with a credit.
+	InsufficientProtocolFee,
+	/// The recipient is the sender. Checked right after `Paused`.
+	MessageToSelf,
+	/// The recipient is one of the pallet's own accounts. Checked right after `Paused`.
+	ReservedRecipient,
+	/// The recipient only receives messages over a confirmed session with the sender.
+	/// Checked right after `NotApproved`.
+	SessionRequired,
+	/// The recipient has neither approved the sender nor granted it an inbox pass, so only
+	/// `send_paid_message_hash` reaches it. Checked right after `RecipientDormant`.
+	NotApproved,
+}
+
+/// What the checks of the send path read about the sender, for the send to reuse instead of
+/// reading it again.
+pub(crate) struct SendCheck<Balance, BlockNumber> {
+	/// Profile of the sender.
+	pub sender_profile: ProfileRecord<Balance, BlockNumber>,
+	/// Reputation of the sender as stored, without the pending decay.
+	pub reputation: ReputationRecord<BlockNumber>,
+	/// Spam bond of the sender.
+	pub bond: Balance,
+	/// Rate-limit window of the sender with the send counted.
+	pub rate_limit: RateLimitState<BlockNumber>,
+	/// Protocol fee the send owes.
+	pub fee: Balance,
+}
+
+/// Send check as made by the pallet.
+pub(crate) type SendCheckOf<T> =
+	SendCheck<BalanceOf<T>, frame_system::pallet_prelude::BlockNumberFor<T>>;
+
+/// Activity of a profile, as returned by the `profile_activity` view function.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct ProfileActivity<BlockNumber> {
+	/// Block of the profile's last recorded activity.
+	pub last_activity: BlockNumber,
+	/// Block at which the profile was flagged dormant, if it is dormant.
+	pub dormant_since: Option<BlockNumber>,
+}
+
+/// Profile activity as returned by the pallet.
+pub type ProfileActivityOf<T> = ProfileActivity<BlockNumberFor<T>>;
+
+/// Summary of a conversation as seen by one of its parties, as returned by the
+/// `conversation_summary` view function.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct ConversationSummary<BlockNumber> {
+	/// Messages of the conversation still stored, in either direction.
+	pub messages: u32,
+	/// Paid messages sent by this party that the other has not acknowledged yet.
+	pub unacknowledged_sent: u32,
+	/// Paid messages received by this party that it has not acknowledged yet.
+	pub unacknowledged_received: u32,
+	/// ID of the last message sent in the conversation, which may since have been removed.
+	pub last_message_id: MessageId,
+	/// Block of the last message sent or acknowledged in the conversation.
+	pub last_activity: BlockNumber,
+}
+
+/// Conversation summary as returned by the pallet.
+pub type ConversationSummaryOf<T> = ConversationSummary<BlockNumberFor<T>>;
+
+/// Messages of a group, as returned by the `group_messages_after` view function.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct GroupMessagesPage<AccountId, Hash, BlockNumber> {
+	/// Lowest sequence number of a message still stored, or `next_seq` if none is. Clients
+	/// cannot sync messages below it.
+	pub lowest_retained_seq: u64,
+	/// Sequence number the next message sent to the group gets.
+	pub next_seq: u64,
+	/// Messages in sequence order, with their IDs. Sequence numbers of messages taken down
+	/// are skipped.
+	pub messages: Vec<(MessageId, GroupMessageRecord<AccountId, Hash, BlockNumber>)>,
+}
+
+/// Page of group messages as returned by the pallet.
+pub type GroupMessagesPageOf<T> = GroupMessagesPage<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	BlockNumberFor<T>,
+>;
+
+/// Maximum number of messages returned by one page of a conversation.
+pub const MAX_CONVERSATION_PAGE_SIZE: u32 = 128;
+
+/// Where a message of a conversation stands.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum MessageStatus {
+	/// The hash verifies and nothing is pending.
+	Active,
+	/// A payment for the message is awaiting acknowledgement by the recipient.
+	AwaitingAcknowledgement,
+	/// A spam challenge against the message has no final verdict yet.
+	Challenged,
+	/// The message has expired and will be pruned.
+	Expired,
+	/// The sender deregistered after sending the message. Its hash still verifies until
+	/// expiry, and any inbox payment can be refunded to the sender.
+	SenderDeparted,
+}
+
+/// A message of a conversation, as returned by the `conversation` runtime API.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct ConversationMessage<AccountId, Hash, BlockNumber> {
+	/// ID of the message.
+	pub message_id: MessageId,
+	/// Sequence number of the message within the conversation.
+	pub seq: u64,
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Account the message was sent to.
+	pub recipient: AccountId,
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Last block at which the message can still be verified.
+	pub expires_at: BlockNumber,
+	/// Where the message stands.
+	pub status: MessageStatus,
+}
+
+/// Messages of a conversation, as returned by the `conversation` runtime API.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct ConversationPage<AccountId, Hash, BlockNumber> {
+	/// Lowest sequence number of a message still stored, or `next_seq` if none is. Clients
+	/// cannot load history below it.
+	pub lowest_retained_seq: u64,
+	/// Sequence number the next message of the conversation gets.
+	pub next_seq: u64,
+	/// Messages in sequence order. Sequence numbers of messages taken down or retracted
+	/// are skipped.
+	pub messages: Vec<ConversationMessage<AccountId, Hash, BlockNumber>>,
+}
+
+/// Page of a conversation as returned by the pallet.
+pub type ConversationPageOf<T> = ConversationPage<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	BlockNumberFor<T>,
+>;
+
+/// A registered profile as seen by off-chain clients.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct ProfileView {
+	/// The current public key.
+	pub public_key: Vec<u8>,
+	/// Algorithm of the current public key.
+	pub algorithm: KeyAlgorithm,
+	/// Number of times the key was replaced.
+	pub key_version: u32,
+	/// Whether the account holds at least its required spam bond.
+	pub bonded: bool,
+	/// Whether the account is banned.
+	pub banned: bool,
+}
+
+/// Public key of a profile registered on another chain, as exchanged over XCM.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RemoteProfileV1 {
+	/// The current public key.
+	pub public_key: BoundedVec<u8, ConstU32<256>>,
+	/// Algorithm of the current public key.
+	pub algorithm: KeyAlgorithm,
+	/// Number of times the key was replaced.
+	pub key_version: u32,
+}
+
+/// Answer to a remote profile lookup.
+///
+/// Requesters state the newest version they understand and responders answer with the newest
+/// version both sides support, so either chain can upgrade first.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum VersionedRemoteProfile {
+	/// The profile, or `None` if the account has no profile on the responding chain.
+	#[codec(index = 1)]
+	V1(Option<RemoteProfileV1>),
+}
+
+/// Newest `VersionedRemoteProfile` version understood by this pallet.
+pub const REMOTE_PROFILE_VERSION: u32 = 1;
+
+/// Identifier of a remote profile lookup.
+pub type ProfileQueryId = u64;
+
+/// A remote profile lookup awaiting its answer.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ProfileQuery<AccountId, BlockNumber> {
+	/// Chain the lookup was sent to.
+	pub dest: Location,
+	/// Account whose profile was requested.
+	pub who: AccountId,
+	/// Last block at which an answer is accepted.
+	pub expires_at: BlockNumber,
+}
+
+/// Remote profile lookup as stored by the pallet.
+pub type ProfileQueryOf<T> =
+	ProfileQuery<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>;
+
+/// Identifier of a group chat.
+pub type GroupId = u64;
+
+/// Commitment to the encrypted group key that members share off-chain.
+pub type GroupKey = BoundedVec<u8, ConstU32<256>>;
+
+/// Content identifier of a group description stored off-chain, e.g. on IPFS.
+pub type DescriptionCid = BoundedVec<u8, ConstU32<128>>;
+
+/// Lifecycle state of a group.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum GroupStatus {
+	/// Members can send messages and the group can be managed.
+	Active,
+	/// The owner left and no admin could take over. Members can only leave.
+	Frozen,
+	/// The group is being disbanded over several `disband_group` calls, and only those can
+	/// change it.
+	Disbanding,
+}
+
+/// Which members of a group can send group messages.
+#[derive(
+	Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+)]
+pub enum PostingPolicy {
+	/// Every member can send.
+	#[default]
+	Everyone,
+	/// Only the owner and admins can send.
+	AdminsOnly,
+	/// Only the owner can send.
+	OwnerOnly,
+}
+
+impl PostingPolicy {
+	/// Whether a member with `role` can send under this policy.
+	pub fn allows(&self, role: GroupRole) -> bool {
+		match self {
+			Self::Everyone => true,
+			Self::AdminsOnly => matches!(role, GroupRole::Owner | GroupRole::Admin),
+			Self::OwnerOnly => role == GroupRole::Owner,
+		}
+	}
+}
+
+/// A group chat.
+///
+/// The owner's `deposit` stays on hold for as long as the group exists, and grows and shrinks
+/// with its membership.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct GroupInfo<AccountId, Balance, BlockNumber> {
+	/// Account holding the group deposit. Of a frozen group, the owner that left it.
+	pub owner: AccountId,
+	/// Whether the group is active or frozen.
+	pub status: GroupStatus,
+	/// Whether accounts can only join by accepting an invite.
+	pub invite_only: bool,
+	/// Commitment to the current group key.
+	pub key: GroupKey,
+	/// Version of the current group key, starting at 0 and bumped by every rotation.
+	pub key_version: u32,
+	/// Whether a member left since the last key rotation. Group messages cannot be sent
+	/// until the key is rotated again.
+	pub rotation_required: bool,
+	/// Block at which the group was created.
+	pub created_at: BlockNumber,
+	/// Number of members, the owner included.
+	pub member_count: u32,
+	/// Number of members with the `Admin` role.
+	pub admin_count: u32,
+	/// Number of members the owner expected the group to grow to when creating it.
+	pub max_members: u32,
+	/// Deposit held from the owner: `GroupBaseDeposit` plus `GroupPerMemberDeposit` per
+	/// member.
+	pub deposit: Balance,
+	/// Sequence number the next message sent to the group gets, starting at 0.
+	pub next_seq: u64,
+}
+
+/// Group as stored by the pallet.
+pub type GroupInfoOf<T> =
+	GroupInfo<<T as frame_system::Config>::AccountId, BalanceOf<T>, BlockNumberFor<T>>;
+
+/// A message sent to a group.
+///
+/// Stored once per group rather than per member; members discover messages through
+/// `GroupMessageSent` events.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct GroupMessageRecord<AccountId, Hash, BlockNumber> {
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Member that sent the message.
+	pub sender: AccountId,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Version of the group key the message was encrypted under.
+	pub key_version: u32,
+	/// Last block at which the message hash verifies, unless the group retention was
+	/// shortened since.
+	pub expires_at: BlockNumber,
+	/// Position of the message among the messages sent to the group.
+	pub seq: u64,
+}
+
+/// Group message as stored by the pallet.
+pub type GroupMessageRecordOf<T> = GroupMessageRecord<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	BlockNumberFor<T>,
+>;
+
+/// Role of a member within a group.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum GroupRole {
+	/// Holds the group deposit and manages admins. Each group has exactly one owner.
+	Owner,
+	/// Manages ordinary members on behalf of the owner.
+	Admin,
+	/// Ordinary member.
+	Member,
+	/// Mutes members, removes their messages and handles join requests, without managing
+	/// members or the group itself.
+	Moderator,
+}
+
+impl GroupRole {
+	/// Whether a member with this role can act on members with role `other`, e.g. mute
+	/// them or remove their messages.
+	///
+	/// The owner outranks admins, admins outrank moderators and moderators outrank
+	/// ordinary members.
+	pub fn outranks(self, other: Self) -> bool {
+		self.rank() > other.rank()
+	}
+
+	fn rank(self) -> u8 {
+		match self {
+			Self::Member => 0,
+			Self::Moderator => 1,
+			Self::Admin => 2,
+			Self::Owner => 3,
+		}
+	}
+}
+
+/// Group message removed by a moderator, kept until the message would have expired so that
+/// syncing members can tell the gap in the sequence numbers apart from a takedown.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct GroupMessageTombstone<AccountId, BlockNumber> {
+	/// Sequence number of the removed message.
+	pub seq: u64,
+	/// Moderator, admin or owner that removed the message.
+	pub removed_by: AccountId,
+	/// Block at which the message was removed.
+	pub removed_at: BlockNumber,
+	/// Last block at which the message would have verified.
+	pub expires_at: BlockNumber,
+}
+
+/// Group message tombstone as stored by the pallet.
+pub type GroupMessageTombstoneOf<T> =
+	GroupMessageTombstone<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>;
+
+/// Moderation action recorded in the log of a group.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum GroupModerationAction<AccountId, BlockNumber> {
+	/// `who` was muted until block `until`.
+	Mute { who: AccountId, until: BlockNumber },
+	/// The mute of `who` was lifted.
+	Unmute { who: AccountId },
+	/// A group message was removed, leaving a tombstone.
+	RemoveMessage { message_id: MessageId },
+	/// The join request of `who` was approved.
+	ApproveJoinRequest { who: AccountId },
+	/// The join request of `who` was rejected.
+	RejectJoinRequest { who: AccountId },
+}
+
+/// Group moderation action as stored by the pallet.
+pub type GroupModerationActionOf<T> =
+	GroupModerationAction<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>;
+
+/// Entry of the moderation log of a group.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct GroupModerationEntry<AccountId, BlockNumber> {
+	/// Moderator, admin or owner that took the action.
+	pub by: AccountId,
+	/// The action taken.
+	pub action: GroupModerationAction<AccountId, BlockNumber>,
+	/// Block at which the action was taken.
+	pub at: BlockNumber,
+}
+
+/// Group moderation log entry as stored by the pallet.
+pub type GroupModerationEntryOf<T> =
+	GroupModerationEntry<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>;
+
+/// Pending invitation of an account to a group.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct GroupInvite<AccountId, BlockNumber> {
+	/// Owner or admin that sent the invite.
+	pub invited_by: AccountId,
+	/// Last block at which the invite can be accepted.
+	pub expires_at: BlockNumber,
+}
+
+/// Ban of an account from a group.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BanInfo<AccountId, Hash, BlockNumber> {
+	/// Owner or admin that imposed the ban.
+	pub banned_by: AccountId,
+	/// Hash of the off-chain reason for the ban.
+	pub reason: Hash,
+	/// Block at which the ban was imposed.
+	pub at: BlockNumber,
+}
+
+/// Group ban as stored by the pallet.
+pub type BanInfoOf<T> = BanInfo<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	BlockNumberFor<T>,
+>;
+
+/// Public description of a group, set by its owner.
+///
+/// The deposit is held from `depositor` until the metadata is replaced or cleared, even if
+/// group ownership changes in the meantime.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct GroupMetadata<Hash, AccountId, Balance> {
+	/// Hash of the group name.
+	pub name_hash: Hash,
+	/// Where the group description is stored off-chain.
+	pub description_cid: DescriptionCid,
+	/// Whether the group is listed in `DiscoverableGroups` and accepts join requests.
+	pub is_discoverable: bool,
+	/// Account the deposit is held from.
+	pub depositor: AccountId,
+	/// Deposit held for the metadata bytes.
+	pub deposit: Balance,
+}
+
+/// Group metadata as stored by the pallet.
+pub type GroupMetadataOf<T> = GroupMetadata<
+	<T as frame_system::Config>::Hash,
+	<T as frame_system::Config>::AccountId,
+	BalanceOf<T>,
+>;
+
+/// Membership of an account in a group.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct MemberInfo<BlockNumber> {
+	/// Block at which the account joined the group.
+	pub joined_at: BlockNumber,
+	/// Role of the account within the group.
+	pub role: GroupRole,
+}
+
+/// Currency holding bonds, deposits and fees in the asset of `BondAssets` that `BondAssetId`
+/// selects, or in `Native` while it selects `NativeOrWithId::Native`.
+///
+/// Set as `Config::Currency` to denominate bonds in an asset. `BondAssetId` must not change
+/// while funds are held, since they are released in whichever currency it selects.
+pub type BondCurrency<Native, BondAssets, BondAssetId, AccountId> =
+	frame_support::traits::fungible::ItemOf<
+		frame_support::traits::fungible::UnionOf<
+			Native,
+			BondAssets,
+			frame_support::traits::fungible::NativeFromLeft,
+			frame_support::traits::fungible::NativeOrWithId<
+				<BondAssets as frame_support::traits::fungibles::Inspect<AccountId>>::AssetId,
+			>,
+			AccountId,
+		>,
+		BondAssetId,
+		AccountId,
+	>;
+
+/// Funds taken out of circulation by the pallet, awaiting an income handler.
+pub type CreditOf<T> = frame_support::traits::fungible::Credit<
+	<T as frame_system::Config>::AccountId,
+	<T as Config>::Currency,
+>;
+
+/// Category of pallet income, each routed to its own handler.
+#[derive(
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub enum IncomeCategory {
+	/// Slashed spam bonds not paid out as challenger rewards.
+	Slash,
+	/// Fees collected by the pallet.
+	Fee,
+	/// Deposits forfeited with no counterparty to receive them.
+	ForfeitedDeposit,
+	/// Per-message protocol fees.
+	ProtocolFee,
+}
+
+/// Measured cost of the items expiry pruning removes, charged item by item so pruning can
+/// stop wherever the weight it was given runs out.
+///
+/// Derived from the marginal weight of one item in the `prune_*` benchmarks. Each ID visited
+/// costs `index_entry`; removing a message costs its kind's weight on top of that.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct PruneCosts {
+	/// Visiting an ID and removing the index entries left under it.
+	pub index_entry: Weight,
+	/// Removing a direct, stealth or pending message, on top of `index_entry`.
+	pub direct_message: Weight,
+	/// Removing a group message or its tombstone, on top of `index_entry`.
+	pub group_message: Weight,
+}
+
+impl PruneCosts {
+	/// Costs measured by the benchmarks of `T`.
+	pub fn of<T: Config>() -> Self {
+		let marginal = |weight: fn(u32) -> Weight| weight(1).saturating_sub(weight(0));
+		let index_entry = marginal(T::WeightInfo::prune_index_entries);
+		Self {
+			index_entry,
+			direct_message: marginal(T::WeightInfo::prune_direct_messages)
+				.saturating_sub(index_entry),
+			group_message: marginal(T::WeightInfo::prune_group_messages)
+				.saturating_sub(index_entry),
+		}
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::traits::{
+		fungible::{Balanced, BalancedHold, Inspect, InspectHold, Mutate, MutateHold},
+		tokens::{Fortitude, Precision, Preservation, Restriction},
+		Contains, Imbalance, OnUnbalanced, Time,
+	};
+	use frame_support::{dispatch::PostDispatchInfo, weights::WeightMeter, PalletId};
+	use frame_system::offchain::{CreateBare, CreateTransactionBase, SubmitTransaction};
+	use crate::membership::{AccumulatorUpdater as _, VerifyMembershipProof as _};
+	use sp_runtime::offchain::storage::{StorageRetrievalError, StorageValueRef};
+	use xcm::{
+		latest::{
+			send_xcm,
+			Instruction::{Transact, UnpaidExecution},
+			OriginKind, SendXcm, WeightLimit, Xcm,
+		},
+		VersionedLocation,
+	};
+
+	/// Offchain storage key holding the block of the last pruning transaction submitted by
+	/// this node.
+	const PRUNE_LOCK_KEY: &[u8] = b"pallet-messaging::last-prune";
+
+	/// The in-code storage version.
+	///
+	/// Version 1 introduced the enriched `MessageSent` and `MessageAcknowledged` events,
+	/// version 2 stores messages as [`MessageRecord`]s, version 3 replaces public keys in
+	/// profile events with their hash, version 4 stores spam bonds as [`BondRecord`]s, version
+	/// 5 has profiles hold a reference on their account. See [`crate::migrations`].
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(11);
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + CreateBare<Call<Self>> {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The overarching hold reason.
+		type RuntimeHoldReason: From<HoldReason>;
+
+		/// Currency type for handling bonds, deposits and fees.
+		///
+		/// Use [`BondCurrency`] to hold them in an asset instead of the native currency.
+		type Currency: Inspect<Self::AccountId>
+			+ Mutate<Self::AccountId>
+			+ InspectHold<Self::AccountId, Reason = Self::RuntimeHoldReason>
+			+ MutateHold<Self::AccountId, Reason = Self::RuntimeHoldReason>
+			+ Balanced<Self::AccountId>
+			+ BalancedHold<Self::AccountId>;
+
+		/// Time provider for expiry checks.
+		type Time: Time;
+
+		/// Weight information for extrinsics.
+		type WeightInfo: WeightInfo;
+
+		/// The amount required as a spam prevention bond, until overridden by
+		/// `update_parameters`.
+		#[pallet::constant]
+		type SpamBond: Get<BalanceOf<Self>>;
+
+		/// Maximum number of contacts a user can have, until overridden by
+		/// `update_parameters`.
+		#[pallet::constant]
+		type MaxContactsPerUser: Get<u32>;
+
+		/// Maximum number of pending contact requests indexed per account and direction.
+		///
+		/// Once full, the oldest request drops out of the index; it stays approved.
+		#[pallet::constant]
+		type MaxPendingContactRequests: Get<u32>;
+
+		/// Number of blocks after which message hashes expire, until overridden by
+		/// `update_parameters`.
+		#[pallet::constant]
+		type MessageHashExpiry: Get<BlockNumberFor<Self>>;
+
+		/// Origin allowed to resolve spam challenges.
+		type ResolutionOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Maximum number of messages an account may send per rate-limit window.
+		#[pallet::constant]
+		type MaxMessagesPerWindow: Get<u32>;
+
+		/// Length of the send rate-limit window in blocks.
+		#[pallet::constant]
+		type RateLimitWindow: Get<BlockNumberFor<Self>>;
+
+		/// Number of strikes after which the sender's rate limit is halved.
+		#[pallet::constant]
+		type RateLimitPenaltyStrikes: Get<u32>;
+
+		/// Number of strikes after which the required spam bond is doubled.
+		#[pallet::constant]
+		type BondPenaltyStrikes: Get<u32>;
+
+		/// Number of strikes after which an account is graylisted.
+		///
+		/// Accounts are graylisted earlier if they are auto-slashed at `AutoSlashThreshold`.
+		#[pallet::constant]
+		type MaxStrikes: Get<u32>;
+
+		/// Number of blocks after which a single strike decays.
+		#[pallet::constant]
+		type StrikeDecayPeriod: Get<BlockNumberFor<Self>>;
+
+		/// Minimum amount accepted by a single bond top-up.
+		#[pallet::constant]
+		type MinBondIncrement: Get<BalanceOf<Self>>;
+
+		/// Number of blocks a bond stays locked after securing a message.
+		#[pallet::constant]
+		type BondUnbondingPeriod: Get<BlockNumberFor<Self>>;
+
+		/// Number of blocks without activity after which anyone may flag a profile dormant.
+		#[pallet::constant]
+		type InactivityThreshold: Get<BlockNumberFor<Self>>;
+
+		/// Number of blocks without activity after which `ForceOrigin` may remove a dormant
+		/// profile. Should be much larger than `InactivityThreshold`.
+		#[pallet::constant]
+		type DormantSweepThreshold: Get<BlockNumberFor<Self>>;
+
+		/// Pallet identifier from which the slash escrow account is derived.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The amount a challenger must bond to open a spam challenge.
+		#[pallet::constant]
+		type ChallengeBond: Get<BalanceOf<Self>>;
+
+		/// Portion of the sender's spam bond slashed when a challenge is upheld.
+		#[pallet::constant]
+		type ChallengeSlashPercent: Get<Perbill>;
+
+		/// Portion of a slashed bond paid to the challenger once the verdict is final.
+		///
+		/// The remainder goes to the slash destination, `SlashIncome` by default. This is the
+		/// default of the `challenger_reward_percent` parameter.
+		#[pallet::constant]
+		type ChallengerRewardPercent: Get<Perbill>;
+
+		/// Reward paid from the challenge reward pool to a challenger on top of their slash
+		/// share once an upheld verdict is final, as far as the pool can cover it.
+		///
+		/// This is the default of the `base_challenge_reward` parameter.
+		#[pallet::constant]
+		type BaseChallengeReward: Get<BalanceOf<Self>>;
+
+		/// Portion of every protocol fee paid into the challenge reward pool.
+		///
+		/// The remainder is routed to `ProtocolFeeIncome`.
+		#[pallet::constant]
+		type RewardPoolFeeShare: Get<Perbill>;
+
+		/// Number of blocks after a challenge is resolved during which it can be appealed.
+		#[pallet::constant]
+		type AppealWindow: Get<BlockNumberFor<Self>>;
+
+		/// Number of blocks after a message is sent during which it can be challenged as spam.
+		#[pallet::constant]
+		type ChallengeWindow: Get<BlockNumberFor<Self>>;
+
+		/// The amount an appellant must bond. Must exceed `ChallengeBond`.
+		#[pallet::constant]
+		type AppealBond: Get<BalanceOf<Self>>;
+
+		/// Origin allowed to give final verdicts on appealed challenges.
+		type AppealOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Origin allowed to perform operational toggles, such as pausing groups of calls.
+		///
+		/// Admin calls never modify user state.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Origin allowed to make destructive changes to user state, such as removing
+		/// messages.
+		type ForceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Maximum number of blocks a moderator may freeze a sender for.
+		#[pallet::constant]
+		type MaxFreezeDuration: Get<BlockNumberFor<Self>>;
+
+		/// Maximum number of spam reports an account may file per rate-limit window.
+		#[pallet::constant]
+		type MaxReportsPerWindow: Get<u32>;
+
+		/// Number of blocks after which a single spam report against a sender decays.
+		#[pallet::constant]
+		type ReportDecayPeriod: Get<BlockNumberFor<Self>>;
+
+		/// Number of outstanding spam reports at which a challenge is opened automatically.
+		#[pallet::constant]
+		type AutoChallengeThreshold: Get<u32>;
+
+		/// Whether reaching `AutoSlashThreshold` strikes slashes the sender automatically.
+		///
+		/// Chains preferring purely governance-driven slashes can disable this.
+		#[pallet::constant]
+		type AutoSlashEnabled: Get<bool>;
+
+		/// Number of strikes at which the sender's bond is slashed and the account graylisted.
+		#[pallet::constant]
+		type AutoSlashThreshold: Get<u32>;
+
+		/// Portion of the sender's remaining spam bond slashed at `AutoSlashThreshold`.
+		///
+		/// This is the default of the `auto_slash_percent` parameter.
+		#[pallet::constant]
+		type AutoSlashPercent: Get<Perbill>;
+
+		/// Number of blocks after which an unresolved challenge may be expired by anyone.
+		#[pallet::constant]
+		type ChallengeMaxDuration: Get<BlockNumberFor<Self>>;
+
+		/// Fee-free sends an account may make per block through
+		/// [`SkipFeeForContacts`](crate::extensions::SkipFeeForContacts).
+		#[pallet::constant]
+		type MaxFreeMessagesPerBlock: Get<u32>;
+
+		/// Number of blocks a sender may message a recipient without paying after the
+		/// recipient acknowledges one of its paid messages with a pass.
+		#[pallet::constant]
+		type InboxPassDuration: Get<BlockNumberFor<Self>>;
+
+		/// Handler for slashed spam bonds left after challenger rewards.
+		type SlashIncome: OnUnbalanced<CreditOf<Self>>;
+
+		/// Handler for fees collected by the pallet.
+		type FeeIncome: OnUnbalanced<CreditOf<Self>>;
+
+		/// Handler for deposits forfeited with no counterparty, such as failed appeal bonds
+		/// on system challenges.
+		type ForfeitedDeposits: OnUnbalanced<CreditOf<Self>>;
+
+		/// Handler for per-message protocol fees. `()` burns them.
+		type ProtocolFeeIncome: OnUnbalanced<CreditOf<Self>>;
+
+		/// Maximum number of contact relationships scanned for `ContactKeyReset` events in
+		/// one call. Larger contact lists are finished by `continue_key_reset_notices`.
+		#[pallet::constant]
+		type MaxKeyResetNotifications: Get<u32>;
+
+		/// Number of blocks a takedown record is kept before it may be pruned.
+		#[pallet::constant]
+		type TakedownRetention: Get<BlockNumberFor<Self>>;
+
+		/// Maximum number of message IDs one `prune_expired_unsigned` call advances over.
+		#[pallet::constant]
+		type MaxPrunesPerCall: Get<u32>;
+
+		/// Maximum number of `MessageExpired` events emitted per block. Messages pruned beyond
+		/// it are reported together in `MessagesExpiredBatch`.
+		#[pallet::constant]
+		type MaxExpiryEventsPerBlock: Get<u32>;
+
+		/// Number of blocks an offchain worker waits before submitting another pruning
+		/// transaction. Each transaction stays valid for the same number of blocks.
+		#[pallet::constant]
+		type PruneInterval: Get<BlockNumberFor<Self>>;
+
+		/// Priority of unsigned pruning transactions.
+		#[pallet::constant]
+		type PruneUnsignedPriority: Get<TransactionPriority>;
+
+		/// Whether stored messages are mirrored into the offchain index.
+		///
+		/// See [`offchain_index`](crate::offchain_index) for the layout. Index writes are free
+		/// for the runtime; nodes only persist them when started with offchain indexing.
+		#[pallet::constant]
+		type OffchainIndexing: Get<bool>;
+
+		/// Router used to send remote profile lookups and their answers to other chains.
+		///
+		/// Lookups are dispatched with `Transact`, which assumes this pallet sits at the same
+		/// index on both chains.
+		type XcmSender: SendXcm;
+
+		/// Origin of calls dispatched over XCM by another chain, resolving to its location.
+		type XcmOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Location>;
+
+		/// Number of blocks a fetched remote profile stays fresh.
+		#[pallet::constant]
+		type RemoteProfileTtl: Get<BlockNumberFor<Self>>;
+
+		/// Number of blocks a remote profile lookup waits for its answer.
+		#[pallet::constant]
+		type ProfileQueryTimeout: Get<BlockNumberFor<Self>>;
+
+		/// Accounts allowed to register a profile themselves, checked on top of the
+		/// registration mode.
+		///
+		/// Use `Everything` to admit anyone, or
+		/// [`IdentityJudgementGate`](crate::identity::IdentityJudgementGate) to require an
+		/// identity judgement.
+		type RegistrationGate: Contains<Self::AccountId>;
+
+		/// Accounts with a verified identity, charged the `verified` tier of a recipient's
+		/// [`InboxPricing`].
+		///
+		/// Use `Nothing` to treat every sender alike, or
+		/// [`IdentityJudgementGate`](crate::identity::IdentityJudgementGate) to rely on
+		/// identity judgements.
+		type IdentityVerifier: Contains<Self::AccountId>;
+
+		/// Deposit held from the owner of a group, regardless of its size.
+		#[pallet::constant]
+		type GroupBaseDeposit: Get<BalanceOf<Self>>;
+
+		/// Deposit held from the owner of a group for each member, the owner included.
+		#[pallet::constant]
+		type GroupPerMemberDeposit: Get<BalanceOf<Self>>;
+
+		/// Maximum number of groups an account can be a member of, its own groups included.
+		#[pallet::constant]
+		type MaxGroupsPerAccount: Get<u32>;
+
+		/// Maximum number of members of a group, its owner included.
+		#[pallet::constant]
+		type MaxGroupMembers: Get<u32>;
+
+		/// Maximum number of admins of a group, its owner not included.
+		#[pallet::constant]
+		type MaxGroupAdmins: Get<u32>;
+
+		/// Maximum number of messages sent to a group, by all members together, within a
+		/// `RateLimitWindow`.
+		#[pallet::constant]
+		type MaxGroupMessagesPerWindow: Get<u32>;
+
+		/// Number of blocks for which group message hashes verify, unless the group owner
+		/// chose a shorter retention.
+		#[pallet::constant]
+		type GroupMessageExpiry: Get<BlockNumberFor<Self>>;
+
+		/// Number of blocks for which a group invite can be accepted.
+		#[pallet::constant]
+		type GroupInviteExpiry: Get<BlockNumberFor<Self>>;
+
+		/// Maximum number of pending invites of a group.
+		#[pallet::constant]
+		type MaxGroupInvites: Get<u32>;
+
+		/// Maximum number of pending group invites of an account.
+		#[pallet::constant]
+		type MaxPendingGroupInvites: Get<u32>;
+
+		/// Maximum number of accounts banned from a group.
+		#[pallet::constant]
+		type MaxGroupBans: Get<u32>;
+
+		/// Maximum number of messages, and separately of members, invites, join requests and
+		/// bans, one `disband_group` call removes.
+		#[pallet::constant]
+		type MaxDisbandRemovalsPerCall: Get<u32>;
+
+		/// Deposit held per byte of group metadata.
+		#[pallet::constant]
+		type GroupMetadataDepositPerByte: Get<BalanceOf<Self>>;
+
+		/// Maximum number of groups listed in `DiscoverableGroups`.
+		#[pallet::constant]
+		type MaxDiscoverableGroups: Get<u32>;
+
+		/// Maximum number of pending join requests of a group.
+		#[pallet::constant]
+		type MaxGroupJoinRequests: Get<u32>;
+
+		/// Maximum number of entries kept in the moderation log of a group. The oldest entry
+		/// is evicted once the log is full.
+		#[pallet::constant]
+		type MaxGroupModerationLog: Get<u32>;
+
+		/// Deposit held per stealth message until it is pruned. Stealth messages bypass
+		/// contact approval and inbox caps, so this should well exceed the cost of a direct
+		/// message.
+		#[pallet::constant]
+		type StealthMessageDeposit: Get<BalanceOf<Self>>;
+
+		/// Bond held from each relay account. Relays answer for every sealed message they
+		/// carry, so this should well exceed `SpamBond`.
+		#[pallet::constant]
+		type RelayBond: Get<BalanceOf<Self>>;
+
+		/// Maximum number of sealed messages a relay can carry per block.
+		#[pallet::constant]
+		type MaxRelayCarriesPerBlock: Get<u32>;
+
+		/// Deposit held per message left for an unregistered account until it is claimed or
+		/// pruned.
+		#[pallet::constant]
+		type PendingMessageDeposit: Get<BalanceOf<Self>>;
+
+		/// Number of blocks a message left for an unregistered account can be claimed.
+		#[pallet::constant]
+		type PendingMessageExpiry: Get<BlockNumberFor<Self>>;
+
+		/// Portion of the deposit of a pending message refunded to its sender once claimed.
+		/// The rest is collected as a fee.
+		#[pallet::constant]
+		type PendingMessageRefund: Get<Perbill>;
+
+		/// Maximum number of pending messages claimed per call.
+		#[pallet::constant]
+		type MaxClaimsPerCall: Get<u32>;
+
+		/// Number of blocks a challenger has to reveal their evidence once the sender
+		/// contests a challenge.
+		#[pallet::constant]
+		type EvidenceRevealPeriod: Get<BlockNumberFor<Self>>;
+
+		/// Verifies that the sender of an anonymous contact message is an approved contact
+		/// of the recipient. `()` rejects every proof.
+		type MembershipProofVerifier: membership::VerifyMembershipProof<
+			Self::AccountId,
+			Self::Hash,
+		>;
+
+		/// Folds approved and removed contacts into each account's `ContactAccumulators`
+		/// entry. Must produce the accumulators `MembershipProofVerifier` expects.
+		type AccumulatorUpdater: membership::AccumulatorUpdater<Self::AccountId, Self::Hash>;
+
+		/// Maximum number of ephemeral keys an account has published at once. Publishing
+		/// another evicts the oldest.
+		#[pallet::constant]
+		type MaxEphemeralKeys: Get<u32>;
+
+		/// Deposit held per listed account for each message sent to a recipient set, so
+		/// decoys cost as much as the true recipient.
+		#[pallet::constant]
+		type RecipientSetDeposit: Get<BalanceOf<Self>>;
+
+		/// Deposit held per backup commitment kept in an account's history.
+		#[pallet::constant]
+		type BackupDeposit: Get<BalanceOf<Self>>;
+
+		/// Maximum number of recipient-set deposits refunded when a member of the set replies
+		/// to their sender, most recent first. Older deposits wait for acknowledgement or
+		/// expiry.
+		#[pallet::constant]
+		type MaxAutoRefundsPerReply: Get<u32>;
+
+		/// Maximum number of messages sent by one call of `send_message_hash_batch`. A batch
+		/// refunds at most `MaxAutoRefundsPerReply` deposits in all, so the proof size of a
+		/// batch grows by the accesses of one message per message.
+		#[pallet::constant]
+		type MaxSendBatch: Get<u32>;
+
+		/// Maximum number of device keys an account has registered at once. At most
+		/// `MAX_DEVICE_SLOTS`.
+		#[pallet::constant]
+		type MaxDevicesPerAccount: Get<u32>;
+
+		/// Deposit held per registered device key until the device or the profile is removed.
+		#[pallet::constant]
+		type DeviceDeposit: Get<BalanceOf<Self>>;
+
+		/// Price of one prepaid message credit.
+		#[pallet::constant]
+		type CreditPrice: Get<BalanceOf<Self>>;
+
+		/// Maximum number of unused message credits an account can hold.
+		#[pallet::constant]
+		type MaxCreditsPerAccount: Get<u32>;
+
+		/// Portion of the price of a credit held until it is used and returned if it is
+		/// refunded unused. The rest is collected as a fee on purchase.
+		#[pallet::constant]
+		type CreditRefund: Get<Perbill>;
+
+		/// Signature with which an account consents to a registration sponsored by another.
+		type OffchainSignature: Verify<Signer = Self::SigningPublicKey> + Parameter;
+
+		/// Public key that verifies an `OffchainSignature`, identifying the signing account.
+		type SigningPublicKey: IdentifyAccount<AccountId = Self::AccountId>;
+
+		/// Signs sponsorship proofs for benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: SponsorshipBenchmarkHelper<Self::AccountId, Self::OffchainSignature>;
+	}
+
+	/// Reasons for this pallet placing a hold on funds.
+	#[pallet::composite_enum]
+	pub enum HoldReason {
+		/// Anti-spam bond backing a registered profile.
+		SpamBond,
+		/// Deposit backing an individual message.
+		MessageDeposit,
+		/// Bond posted by a spam challenger.
+		ChallengeBond,
+		/// Deposit backing a pending contact request.
+		ContactRequestDeposit,
+		/// Bond posted by the party appealing a challenge verdict.
+		AppealBond,
+		/// Inbox price paid for a message, held until the recipient acknowledges it.
+		InboxPayment,
+		/// Deposit backing a group chat.
+		GroupDeposit,
+		/// Deposit backing the metadata of a group.
+		GroupMetadataDeposit,
+		/// Bond backing a relay account.
+		RelayBond,
+		/// Deposit backing a contact-list backup commitment.
+		BackupDeposit,
+		/// Refundable part of the price of unused message credits.
+		MessageCredits,
+		/// Deposit backing a registered device key.
+		DeviceDeposit,
+	}
+
+	/// User profiles containing public keys for encryption, the dormant flag, the bond posted
+	/// at registration and the session requirement.
+	/// Maps AccountId => ProfileRecord
+	///
+	/// Counted, see [`Pallet::profile_count`].
+	#[pallet::storage]
+	pub type UserProfiles<T: Config> =
+		CountedStorageMap<_, Blake2_128Concat, T::AccountId, ProfileRecordOf<T>, OptionQuery>;
+
+	/// Message hashes for verification.
+	/// Maps MessageId => MessageRecord
+	///
+	/// Counted, see [`Pallet::message_count`].
+	#[pallet::storage]
+	pub type MessageHashes<T: Config> =
+		CountedStorageMap<_, Blake2_128Concat, MessageId, MessageRecordOf<T>, OptionQuery>;
+
+	/// Message stored under each hash, so a received ciphertext can be matched to its record.
+	/// Maps Hash => MessageId
+	///
+	/// A hash is only re-indexed once its previous message has expired or been removed.
+	#[pallet::storage]
+	pub type MessageIdByHash<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::Hash, MessageId, OptionQuery>;
+
+	/// Revision of each account's inbox, bumped whenever a message in it is delivered,
+	/// acknowledged, refunded, retracted, removed or pruned.
+	/// Maps AccountId => u64
+	#[pallet::storage]
+	pub type InboxRevisions<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
+
+	/// Anti-spam bonds deposited by users or their sponsors.
+	/// Maps AccountId => BondRecord
+	#[pallet::storage]
+	pub type SpamBonds<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BondRecordOf<T>, ValueQuery>;
+
+	/// Approved contacts list (bidirectional).
+	///
+	/// Read twice per send. Contacts are hashed with `Twox64Concat`: only the first account
+	/// approves entries under its own key, at most `MaxContactsPerUser` of them, so accounts
+	/// ground to collide only deepen the approver's own subtree. The first key stays
+	/// `Blake2_128Concat`, as any account can add itself at the top of the map.
+	/// Maps (AccountId, AccountId) => bool
+	#[pallet::storage]
+	pub type ApprovedContacts<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Twox64Concat,
+		T::AccountId,
+		bool,
+		ValueQuery,
+	>;
+
+	/// Count of contacts per user.
+	/// Maps AccountId => u32
+	#[pallet::storage]
+	pub type ContactCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// Contact requests waiting for each account's decision, oldest first.
+	///
+	/// A request is an approval the other account has not returned. Accepting it with
+	/// `approve_contact`, or removing the requester or target with `remove_contact`, drops
+	/// it from both this and `OutgoingContactRequests`.
+	/// Maps AccountId => BoundedVec<(requester, requested at)>
+	#[pallet::storage]
+	pub type IncomingContactRequests<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		BoundedVec<(T::AccountId, BlockNumberFor<T>), T::MaxPendingContactRequests>,
+		ValueQuery,
+	>;
+
+	/// Contact requests each account sent that are still pending, oldest first.
+	/// Maps AccountId => BoundedVec<(target, requested at)>
+	#[pallet::storage]
+	pub type OutgoingContactRequests<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		BoundedVec<(T::AccountId, BlockNumberFor<T>), T::MaxPendingContactRequests>,
+		ValueQuery,
+	>;
+
+	/// Message ID counter.
+	#[pallet::storage]
+	pub type NextMessageId<T: Config> = StorageValue<_, MessageId, ValueQuery>;
+
+	/// ID of each stored message by its sequence number within its conversation.
+	///
+	/// Only messages numbered through `ConversationStats` are indexed; messages delivered
+	/// through relays or claimed from pending storage are not part of a conversation.
+	///
+	/// Written on every send. Sequence numbers are hashed with `Twox64Concat`: the pallet
+	/// assigns them consecutively, so nobody can choose keys that collide.
+	/// Maps (conversation ID, u64) => MessageId
+	#[pallet::storage]
+	pub type ConversationMessages<T: Config> = StorageDoubleMap<
+		_,
+		Identity,
+		T::Hash,
+		Twox64Concat,
+		u64,
+		MessageId,
+		OptionQuery,
+	>;
+
+	/// Sequence number of each message indexed in `ConversationMessages`.
+	/// Maps MessageId => u64
+	#[pallet::storage]
+	pub type MessageSequences<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, u64, OptionQuery>;
+
+	/// Sequence number below which all messages of a conversation were pruned.
+	///
+	/// Messages are pruned in ID order, and so in sequence order within a conversation; only
+	/// takedowns and retractions leave gaps above it.
+	/// Maps conversation ID => u64
+	#[pallet::storage]
+	pub type ConversationSeqFloors<T: Config> =
+		StorageMap<_, Identity, T::Hash, u64, ValueQuery>;
+
+	/// Spam challenges that have not reached a final verdict.
+	/// Maps MessageId => ChallengeInfo
+	#[pallet::storage]
+	pub type Challenges<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, ChallengeInfoOf<T>, OptionQuery>;
+
+	/// Strike records of accounts with upheld spam challenges.
+	/// Maps AccountId => ReputationRecord
+	#[pallet::storage]
+	pub type Reputation<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		ReputationRecord<BlockNumberFor<T>>,
+		ValueQuery,
+	>;
+
+	/// Accounts currently graylisted, with the block they were graylisted at.
+	///
+	/// Entries are refreshed lazily when the account interacts with the pallet, so clients
+	/// should prefer [`Pallet::is_graylisted`] which accounts for pending strike decay.
+	#[pallet::storage]
+	pub type GraylistedAccounts<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BlockNumberFor<T>, OptionQuery>;
+
+	/// Send counters for the current rate-limit window.
+	/// Maps AccountId => RateLimitState
+	#[pallet::storage]
+	pub type SendRateLimits<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		RateLimitState<BlockNumberFor<T>>,
+		ValueQuery,
+	>;
+
+	/// Cumulative messaging activity of each account.
+	/// Maps AccountId => AccountStatistics
+	#[pallet::storage]
+	pub type AccountStats<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, AccountStatisticsOf<T>, ValueQuery>;
+
+	/// Cumulative activity across the pallet.
+	#[pallet::storage]
+	pub type GlobalStats<T: Config> = StorageValue<_, CumulativeStatistics, ValueQuery>;
+
+	/// Running summary of each conversation, kept up to date as its messages are sent,
+	/// acknowledged and removed, with the sequence number its next message gets.
+	///
+	/// Covers the messages numbered by `next_seq`, so a send reads one value per pair.
+	/// Maps conversation ID => ConversationStatistics
+	#[pallet::storage]
+	pub type ConversationStats<T: Config> =
+		StorageMap<_, Identity, T::Hash, ConversationStatisticsOf<T>, ValueQuery>;
+
+	/// Accounts that deregistered their profile, with the block they left at. Cleared when the
+	/// account registers again.
+	/// Maps AccountId => BlockNumber
+	#[pallet::storage]
+	pub type DepartedSenders<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BlockNumberFor<T>, OptionQuery>;
+
+	/// Profiles registered before version 11 on an account without funds, which keep the
+	/// sufficient reference they held instead of a consumer reference.
+	/// Maps AccountId => ()
+	#[pallet::storage]
+	pub type SufficientProfileRefs<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	/// Number of spam challenges against each sender that are not yet final.
+	/// Maps AccountId => u32
+	#[pallet::storage]
+	pub type OpenChallengeCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// Bond amounts locked by recently sent messages.
+	/// Maps AccountId => BondLock
+	#[pallet::storage]
+	pub type BondLocks<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		BondLock<BalanceOf<T>, BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// Decaying spam report counts per sender.
+	/// Maps AccountId => SpamReportRecord
+	#[pallet::storage]
+	pub type SpamReports<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		SpamReportRecord<BlockNumberFor<T>>,
+		ValueQuery,
+	>;
+
+	/// Messages already reported as spam by their recipient.
+	/// Maps (MessageId, AccountId) => bool
+	#[pallet::storage]
+	pub type ReportedMessages<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		MessageId,
+		Blake2_128Concat,
+		T::AccountId,
+		bool,
+		ValueQuery,
+	>;
+
+	/// Outcomes recorded by `verify_message_on_chain`, kept until the message is removed.
+	/// Maps MessageId => MessageVerification
+	#[pallet::storage]
+	pub type MessageVerifications<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		MessageId,
+		MessageVerification<BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// Block at which each paid message was acknowledged, kept until the message is removed.
+	/// Maps MessageId => BlockNumber
+	#[pallet::storage]
+	pub type AcknowledgedMessages<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, BlockNumberFor<T>, OptionQuery>;
+
+	/// Report counters for the current rate-limit window.
+	/// Maps AccountId => RateLimitState
+	#[pallet::storage]
+	pub type ReportRateLimits<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		RateLimitState<BlockNumberFor<T>>,
+		ValueQuery,
+	>;
+
+	/// Asking price for messages from senders the account has not approved.
+	/// Maps AccountId => Balance
+	#[pallet::storage]
+	pub type InboxPrices<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, OptionQuery>;
+
+	/// Push relay hint of each account that opted in to notifications.
+	/// Maps AccountId => NotificationHint
+	#[pallet::storage]
+	pub type NotificationHooks<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, NotificationHint, OptionQuery>;
+
+	/// Accounts whose batch sends are reported in one `MessagesSentCompact` event.
+	/// Maps AccountId => ()
+	#[pallet::storage]
+	pub type CompactEventSenders<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	/// Tiered inbox prices, taking precedence over the account's flat inbox price.
+	/// Maps AccountId => InboxPricing
+	#[pallet::storage]
+	pub type InboxPricings<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, InboxPricingOf<T>, OptionQuery>;
+
+	/// Inbox payments awaiting acknowledgement by the recipient.
+	/// Maps MessageId => InboxPayment
+	#[pallet::storage]
+	pub type InboxPayments<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, InboxPaymentOf<T>, OptionQuery>;
+
+	/// Temporary passes letting a sender message a recipient without paying.
+	///
+	/// Read on every paid send. Senders are hashed with `Twox64Concat`: a pass is only
+	/// stored when the recipient grants it while acknowledging a paid message, so senders
+	/// cannot fill a recipient's subtree with colliding accounts on their own.
+	/// Maps (Recipient, Sender) => BlockNumber the pass lasts until
+	#[pallet::storage]
+	pub type InboxPasses<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Twox64Concat,
+		T::AccountId,
+		BlockNumberFor<T>,
+		OptionQuery,
+	>;
+
+	/// Parameters set by `AdminOrigin`, overriding the configured constants.
+	///
+	/// Read through [`Pallet::parameters`], which falls back to the constants while unset.
+	#[pallet::storage]
+	pub type Parameters<T: Config> = StorageValue<_, PalletParametersOf<T>, OptionQuery>;
+
+	/// Accounts allowed to triage challenges and freeze senders.
+	#[pallet::storage]
+	pub type Moderators<T: Config> =
+		StorageValue<_, BoundedVec<T::AccountId, ConstU32<32>>, ValueQuery>;
+
+	/// Senders frozen by a moderator pending review.
+	/// Maps AccountId => BlockNumber the freeze lasts until
+	#[pallet::storage]
+	pub type FrozenSenders<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BlockNumberFor<T>, OptionQuery>;
+
+	/// Accounts banned from the messaging system.
+	/// Maps AccountId => BanRecord
+	#[pallet::storage]
+	pub type BannedAccounts<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		BanRecord<T::Hash, BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// Income routed to the income handlers since the last reset, per category.
+	/// Maps IncomeCategory => Balance
+	#[pallet::storage]
+	pub type PalletIncome<T: Config> =
+		StorageMap<_, Twox64Concat, IncomeCategory, BalanceOf<T>, ValueQuery>;
+
+	/// Version of each account's current public key, bumped on every key change.
+	/// Maps AccountId => u32
+	#[pallet::storage]
+	pub type KeyVersions<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// Algorithm of each account's current public key.
+	/// Maps AccountId => KeyAlgorithm
+	#[pallet::storage]
+	pub type KeyAlgorithms<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, KeyAlgorithm, ValueQuery>;
+
+	/// Recently revoked public keys, oldest first.
+	/// Maps AccountId => Vec<RevokedKey>
+	#[pallet::storage]
+	pub type KeyHistory<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		BoundedVec<RevokedKey<BlockNumberFor<T>>, ConstU32<MAX_KEY_HISTORY>>,
+		ValueQuery,
+	>;
+
+	/// Accounts whose contacts have not all been notified of a forced key reset, with the
+	/// raw `ApprovedContacts` key to resume after. An empty cursor starts from the beginning.
+	/// Maps AccountId => raw storage key
+	#[pallet::storage]
+	pub type KeyResetCursors<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<u8, ConstU32<256>>, OptionQuery>;
+
+	/// Tombstones of messages removed by governance.
+	/// Maps MessageId => TakedownRecord
+	#[pallet::storage]
+	pub type Takedowns<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, TakedownRecordOf<T>, OptionQuery>;
+
+	/// Block at which each retracted message was sent, kept until it would have been pruned.
+	/// Maps MessageId => BlockNumber
+	#[pallet::storage]
+	pub type RetractedMessages<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, BlockNumberFor<T>, OptionQuery>;
+
+	/// Who may currently register a profile.
+	#[pallet::storage]
+	pub type CurrentRegistrationMode<T: Config> = StorageValue<_, RegistrationMode, ValueQuery>;
+
+	/// Accounts allowed to register while the pallet is in whitelisted mode.
+	/// Maps AccountId => ()
+	#[pallet::storage]
+	pub type RegistrationWhitelist<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	/// Most recent privileged actions, oldest first.
+	#[pallet::storage]
+	pub type AdminAuditLog<T: Config> =
+		StorageValue<_, BoundedVec<AuditEntryOf<T>, ConstU32<MAX_AUDIT_LOG_ENTRIES>>, ValueQuery>;
+
+	/// Call groups currently paused.
+	#[pallet::storage]
+	pub type Paused<T: Config> = StorageValue<_, PausedCalls, ValueQuery>;
+
+	/// Lowest message ID that may still await pruning.
+	///
+	/// Messages expire in ID order, so everything below the cursor has been pruned or
+	/// removed otherwise.
+	#[pallet::storage]
+	pub type PruneCursor<T: Config> = StorageValue<_, MessageId, ValueQuery>;
+
+	/// Block of the last `MessageExpired` event and the number emitted in that block.
+	#[pallet::storage]
+	pub type ExpiryEventCount<T: Config> =
+		StorageValue<_, (BlockNumberFor<T>, u32), ValueQuery>;
+
+	/// Profiles fetched from other chains, with the block they were fetched at.
+	/// Maps (Location, AccountId) => (RemoteProfileV1, BlockNumber)
+	#[pallet::storage]
+	pub type RemoteProfiles<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		Location,
+		Blake2_128Concat,
+		T::AccountId,
+		(RemoteProfileV1, BlockNumberFor<T>),
+		OptionQuery,
+	>;
+
+	/// Remote profile lookups awaiting their answer.
+	/// Maps ProfileQueryId => ProfileQuery
+	#[pallet::storage]
+	pub type ProfileQueries<T: Config> =
+		StorageMap<_, Blake2_128Concat, ProfileQueryId, ProfileQueryOf<T>, OptionQuery>;
+
+	/// Identifier of the next remote profile lookup.
+	#[pallet::storage]
+	pub type NextProfileQueryId<T: Config> = StorageValue<_, ProfileQueryId, ValueQuery>;
+
+	/// Block at which each account was last auto-slashed.
+	/// Maps AccountId => BlockNumber
+	#[pallet::storage]
+	pub type LastAutoSlash<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BlockNumberFor<T>, OptionQuery>;
+
+	/// Last message rewritten by a running multi-block v2 migration.
+	///
+	/// Messages after it in storage order still use the old layout. See
+	/// [`migrations::v2::LazyMigrateV1ToV2`](crate::migrations::v2::LazyMigrateV1ToV2).
+	#[pallet::storage]
+	pub type MessageMigrationCursor<T: Config> = StorageValue<_, MessageId, OptionQuery>;
+
+	/// Group chats.
+	/// Maps GroupId => GroupInfo
+	#[pallet::storage]
+	pub type Groups<T: Config> =
+		StorageMap<_, Blake2_128Concat, GroupId, GroupInfoOf<T>, OptionQuery>;
+
+	/// Members of each group.
+	/// Maps (GroupId, AccountId) => MemberInfo
+	#[pallet::storage]
+	pub type GroupMembers<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		T::AccountId,
+		MemberInfo<BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// Number of groups each account is a member of.
+	/// Maps AccountId => u32
+	#[pallet::storage]
+	pub type GroupCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// Group ID counter.
+	#[pallet::storage]
+	pub type NextGroupId<T: Config> = StorageValue<_, GroupId, ValueQuery>;
+
+	/// Recently replaced key commitments of each group, oldest first.
+	/// Maps GroupId => Vec<RevokedKey>
+	#[pallet::storage]
+	pub type GroupKeyHistory<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		BoundedVec<RevokedKey<BlockNumberFor<T>>, ConstU32<MAX_KEY_HISTORY>>,
+		ValueQuery,
+	>;
+
+	/// Messages sent to each group.
+	/// Maps (GroupId, MessageId) => GroupMessageRecord
+	#[pallet::storage]
+	pub type GroupMessages<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		MessageId,
+		GroupMessageRecordOf<T>,
+		OptionQuery,
+	>;
+
+	/// Group each group message was sent to, in ID order for pruning.
+	///
+	/// Group messages are numbered apart from direct messages, so that each kind is pruned
+	/// under its own expiry without walking over the other.
+	/// Maps MessageId => GroupId
+	#[pallet::storage]
+	pub type GroupMessageIds<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, GroupId, OptionQuery>;
+
+	/// ID of each stored group message by its sequence number within the group.
+	/// Maps (GroupId, u64) => MessageId
+	#[pallet::storage]
+	pub type GroupMessagesBySeq<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		u64,
+		MessageId,
+		OptionQuery,
+	>;
+
+	/// Sequence number below which all messages of a group were pruned.
+	///
+	/// Messages of a group are pruned in sequence order, so only takedowns and removals by
+	/// moderators leave gaps above it.
+	/// Maps GroupId => u64
+	#[pallet::storage]
+	pub type GroupSeqFloors<T: Config> = StorageMap<_, Blake2_128Concat, GroupId, u64, ValueQuery>;
+
+	/// Group messages removed by moderators, until they would have expired.
+	///
+	/// The ID of a removed message stays in `GroupMessageIds` so that the tombstone is pruned
+	/// in order.
+	/// Maps (GroupId, MessageId) => GroupMessageTombstone
+	#[pallet::storage]
+	pub type GroupMessageTombstones<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		MessageId,
+		GroupMessageTombstoneOf<T>,
+		OptionQuery,
+	>;
+
+	/// Group message ID counter.
+	#[pallet::storage]
+	pub type NextGroupMessageId<T: Config> = StorageValue<_, MessageId, ValueQuery>;
+
+	/// Lowest group message ID that may still await pruning.
+	///
+	/// Group messages are pruned in ID order. With retentions differing between groups, a
+	/// message can expire before earlier ones and wait for them, but never longer than
+	/// `GroupMessageExpiry` after it was sent.
+	#[pallet::storage]
+	pub type GroupPruneCursor<T: Config> = StorageValue<_, MessageId, ValueQuery>;
+
+	/// Retention chosen by group owners, shorter than `GroupMessageExpiry`.
+	/// Maps GroupId => BlockNumber
+	#[pallet::storage]
+	pub type GroupRetentions<T: Config> =
+		StorageMap<_, Blake2_128Concat, GroupId, BlockNumberFor<T>, OptionQuery>;
+
+	/// Posting policy of groups not open to every member.
+	/// Maps GroupId => PostingPolicy
+	#[pallet::storage]
+	pub type GroupPostingPolicies<T: Config> =
+		StorageMap<_, Blake2_128Concat, GroupId, PostingPolicy, OptionQuery>;
+
+	/// Members muted by a group owner or admin, until the given block.
+	/// Maps (GroupId, AccountId) => BlockNumber
+	#[pallet::storage]
+	pub type GroupMutes<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		T::AccountId,
+		BlockNumberFor<T>,
+		OptionQuery,
+	>;
+
+	/// Groups members muted for themselves. Clients leave muted groups out of their inbox and
+	/// unread counts.
+	/// Maps (GroupId, AccountId) => ()
+	#[pallet::storage]
+	pub type MutedGroups<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		T::AccountId,
+		(),
+		OptionQuery,
+	>;
+
+	/// Per-group send counter for the current rate-limit window.
+	/// Maps GroupId => RateLimitState
+	#[pallet::storage]
+	pub type GroupRateLimits<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		RateLimitState<BlockNumberFor<T>>,
+		ValueQuery,
+	>;
+
+	/// Pending group invites, until accepted, declined or removed after expiring.
+	/// Maps (GroupId, AccountId) => GroupInvite
+	#[pallet::storage]
+	pub type GroupInvites<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		T::AccountId,
+		GroupInvite<T::AccountId, BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// Number of pending invites of each group.
+	/// Maps GroupId => u32
+	#[pallet::storage]
+	pub type GroupInviteCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, GroupId, u32, ValueQuery>;
+
+	/// Number of pending group invites of each account.
+	/// Maps AccountId => u32
+	#[pallet::storage]
+	pub type PendingGroupInviteCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// Accounts banned from each group, which can neither join nor be invited.
+	/// Maps (GroupId, AccountId) => BanInfo
+	#[pallet::storage]
+	pub type GroupBans<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		T::AccountId,
+		BanInfoOf<T>,
+		OptionQuery,
+	>;
+
+	/// Number of accounts banned from each group.
+	/// Maps GroupId => u32
+	#[pallet::storage]
+	pub type GroupBanCount<T: Config> = StorageMap<_, Blake2_128Concat, GroupId, u32, ValueQuery>;
+
+	/// Metadata of groups whose owner set one.
+	/// Maps GroupId => GroupMetadata
+	#[pallet::storage]
+	pub type GroupsMetadata<T: Config> =
+		StorageMap<_, Blake2_128Concat, GroupId, GroupMetadataOf<T>, OptionQuery>;
+
+	/// Groups listed for public discovery.
+	/// Maps GroupId => ()
+	#[pallet::storage]
+	pub type DiscoverableGroups<T: Config> =
+		StorageMap<_, Blake2_128Concat, GroupId, (), OptionQuery>;
+
+	/// Number of groups listed in `DiscoverableGroups`.
+	#[pallet::storage]
+	pub type DiscoverableGroupCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// Pending requests of accounts to join discoverable groups, until the given block.
+	/// Maps (GroupId, AccountId) => BlockNumber
+	#[pallet::storage]
+	pub type GroupJoinRequests<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		Blake2_128Concat,
+		T::AccountId,
+		BlockNumberFor<T>,
+		OptionQuery,
+	>;
+
+	/// Number of pending join requests of each group.
+	/// Maps GroupId => u32
+	#[pallet::storage]
+	pub type GroupJoinRequestCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, GroupId, u32, ValueQuery>;
+
+	/// Latest moderation actions taken in each group, oldest first.
+	/// Maps GroupId => BoundedVec<GroupModerationEntry>
+	#[pallet::storage]
+	pub type GroupModerationLogs<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		GroupId,
+		BoundedVec<GroupModerationEntryOf<T>, T::MaxGroupModerationLog>,
+		ValueQuery,
+	>;
+
+	/// Messages sent under a recipient tag. IDs are shared with `MessageHashes`.
+	/// Maps MessageId => StealthMessageRecord
+	#[pallet::storage]
+	pub type StealthMessages<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, StealthMessageRecordOf<T>, OptionQuery>;
+
+	/// Messages sent to recipient sets. IDs are shared with `MessageHashes`.
+	/// Maps MessageId => RecipientSetMessageRecord
+	#[pallet::storage]
+	pub type RecipientSetMessages<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, RecipientSetMessageRecordOf<T>, OptionQuery>;
+
+	/// Unsettled recipient-set messages from a sender that list a member, oldest first,
+	/// keeping the last `MaxAutoRefundsPerReply`. A message from the member to the sender
+	/// refunds their deposits.
+	///
+	/// Read on every send. Members are hashed with `Twox64Concat`: entries under a sender
+	/// are only created by its own recipient-set messages, which pay a deposit per member,
+	/// so colliding members only deepen the sender's own subtree.
+	/// Maps (Sender, Member) => [MessageId]
+	#[pallet::storage]
+	pub type ReplyRefundableDeposits<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Twox64Concat,
+		T::AccountId,
+		BoundedVec<MessageId, T::MaxAutoRefundsPerReply>,
+		ValueQuery,
+	>;
+
+	/// Contact-list backup commitments of each account, oldest first, keeping the last
+	/// `MAX_BACKUP_HISTORY`.
+	/// Maps AccountId => [BackupCommitment]
+	#[pallet::storage]
+	pub type BackupCommitments<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		BoundedVec<BackupCommitmentOf<T>, ConstU32<MAX_BACKUP_HISTORY>>,
+		ValueQuery,
+	>;
+
+	/// Unused prepaid message credits of each account.
+	/// Maps AccountId => u32
+	#[pallet::storage]
+	pub type MessageCredits<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// Stealth messages indexed by the prefix of their recipient tag.
+	/// Maps (TagPrefix, MessageId) => ()
+	#[pallet::storage]
+	pub type StealthMessagesByPrefix<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		stealth::TagPrefix,
+		Blake2_128Concat,
+		MessageId,
+		(),
+		OptionQuery,
+	>;
+
+	/// Messages left for accounts without a profile, by the hash of the recipient account.
+	/// IDs are counted separately from other messages.
+	/// Maps (Hash, MessageId) => PendingMessageRecord
+	#[pallet::storage]
+	pub type PendingMessages<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::Hash,
+		Blake2_128Concat,
+		MessageId,
+		PendingMessageRecordOf<T>,
+		OptionQuery,
+	>;
+
+	/// Recipient account hash of each pending message, so pending messages can be pruned
+	/// in ID order.
+	/// Maps MessageId => Hash
+	#[pallet::storage]
+	pub type PendingMessageIds<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, T::Hash, OptionQuery>;
+
+	/// Pending message ID counter.
+	#[pallet::storage]
+	pub type NextPendingMessageId<T: Config> = StorageValue<_, MessageId, ValueQuery>;
+
+	/// Lowest pending message ID that may still await pruning.
+	#[pallet::storage]
+	pub type PendingPruneCursor<T: Config> = StorageValue<_, MessageId, ValueQuery>;
+
+	/// Evidence committed to by the challenger of each open user challenge. Removed with
+	/// the challenge.
+	/// Maps MessageId => EvidenceRecord
+	#[pallet::storage]
+	pub type ChallengeEvidence<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, EvidenceRecordOf<T>, OptionQuery>;
+
+	/// Accounts registered to carry sealed-sender messages.
+	/// Maps AccountId => RelayInfo
+	#[pallet::storage]
+	pub type RelayAccounts<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, RelayInfoOf<T>, OptionQuery>;
+
+	/// Accumulator over the contacts each account has approved, maintained by
+	/// `Config::AccumulatorUpdater`.
+	/// Maps AccountId => Hash
+	#[pallet::storage]
+	pub type ContactAccumulators<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, T::Hash, ValueQuery>;
+
+	/// Ephemeral keys published by each sender, with the block they were published at.
+	/// Expire with the messages sent in the same block.
+	/// Maps (AccountId, u32) => (EphemeralKey, BlockNumber)
+	#[pallet::storage]
+	pub type EphemeralKeys<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Twox64Concat,
+		u32,
+		(EphemeralKey, BlockNumberFor<T>),
+		OptionQuery,
+	>;
+
+	/// Publisher, index and publication block of each published ephemeral key, so keys can
+	/// be pruned in publication order.
+	/// Maps u64 => (AccountId, u32, BlockNumber)
+	#[pallet::storage]
+	pub type EphemeralKeyIds<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		u64,
+		(T::AccountId, u32, BlockNumberFor<T>),
+		OptionQuery,
+	>;
+
+	/// Ephemeral key publication counter.
+	#[pallet::storage]
+	pub type NextEphemeralKeyId<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	/// Lowest ephemeral key publication that may still await pruning.
+	#[pallet::storage]
+	pub type EphemeralPruneCursor<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	/// Index of the sender's ephemeral key used for each message that names one.
+	/// Removed with the message.
+	/// Maps MessageId => u32
+	#[pallet::storage]
+	pub type MessageEphemeralKeys<T: Config> =
+		StorageMap<_, Blake2_128Concat, MessageId, u32, OptionQuery>;
+
+	/// Admin each group owner offered ownership to, until the admin accepts.
+	/// Maps GroupId => AccountId
+	#[pallet::storage]
+	pub type PendingGroupOwners<T: Config> =
+		StorageMap<_, Blake2_128Concat, GroupId, T::AccountId, OptionQuery>;
+
+	/// Latest session each initiator recorded with each peer. Terminated sessions are kept
+	/// so the next one continues their epochs, and outlive the profiles of their parties.
+	/// Maps (initiator, peer) => SessionInfo
+	#[pallet::storage]
+	pub type Sessions<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::AccountId,
+		SessionInfoOf<T>,
+		OptionQuery,
+	>;
+
+	/// Device keys of each account, removed with the profile.
+	/// Maps AccountId => DeviceSet
+	#[pallet::storage]
+	pub type DeviceSets<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, DeviceSetOf<T>, OptionQuery>;
+
+	/// Revision of each account's device keys, bumped whenever a device is added, replaced or
+	/// removed. Kept when the profile is removed, so cached keys of the old profile never
+	/// look current.
+	/// Maps AccountId => u32
+	#[pallet::storage]
+	pub type DevicesRevisions<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// Profiles registered at genesis as `(account, public key)`. Each account's spam bond
+		/// is held from its genesis endowment.
+		pub profiles: Vec<(T::AccountId, Vec<u8>)>,
+		/// Account pairs approved as contacts of each other at genesis.
+		pub initial_contacts: Vec<(T::AccountId, T::AccountId)>,
+		/// ID assigned to the first message sent after genesis, so chains forked from
+		/// existing state do not reuse message IDs.
+		pub initial_next_message_id: MessageId,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			let bond = Pallet::<T>::parameters().spam_bond;
+			for (who, public_key) in &self.profiles {
+				assert!(
+					!UserProfiles::<T>::contains_key(who),
+					"genesis profile of {:?} is listed twice",
+					who
+				);
+				assert!(
+					!public_key.is_empty(),
+					"genesis profile of {:?} has an empty public key",
+					who
+				);
+				let public_key: PublicKey = public_key.clone().try_into().unwrap_or_else(|_| {
+					panic!("genesis profile of {:?} has a public key over 256 bytes", who)
+				});
+				T::Currency::hold(&HoldReason::SpamBond.into(), who, bond).unwrap_or_else(|_| {
+					panic!("genesis account {:?} cannot cover its spam bond of {:?}", who, bond)
+				});
+				Pallet::<T>::inc_profile_ref(who).unwrap_or_else(|_| {
+					panic!("genesis account {:?} cannot keep its profile alive", who)
+				});
+
+				SpamBonds::<T>::insert(who, BondRecord { amount: bond, sponsor: None });
+				UserProfiles::<T>::insert(
+					who,
+					ProfileRecord {
+						public_key,
+						dormant_since: None,
+						registration_bond: Some(bond),
+						session_required: false,
+					},
+				);
+				GlobalStats::<T>::mutate(|stats| stats.profiles.saturating_inc());
+			}
+
+			let max_contacts = Pallet::<T>::parameters().max_contacts;
+			for (a, b) in &self.initial_contacts {
+				assert!(a != b, "genesis contact pair of {:?} with itself", a);
+				for (who, contact) in [(a, b), (b, a)] {
+					assert!(
+						UserProfiles::<T>::contains_key(who),
+						"genesis contact {:?} has no genesis profile",
+						who
+					);
+					if ApprovedContacts::<T>::get(who, contact) {
+						continue;
+					}
+					let count = ContactCount::<T>::get(who).saturating_add(1);
+					assert!(
+						count <= max_contacts,
+						"genesis contacts of {:?} exceed the limit of {}",
+						who,
+						max_contacts
+					);
+					ApprovedContacts::<T>::insert(who, contact, true);
+					ContactCount::<T>::insert(who, count);
+					Pallet::<T>::accumulate_contact(who, contact, true);
+					Pallet::<T>::index_contact_request(who, contact);
+				}
+			}
+
+			NextMessageId::<T>::put(self.initial_next_message_id);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A user profile was registered.
+		ProfileRegistered {
+			who: T::AccountId,
+			/// Hash of the registered public key, see `Pallet::key_hash`.
+			key_hash: T::Hash,
+			/// Version of the registered public key.
+			version: u32,
+		},
+		/// A message hash was recorded on-chain.
+		MessageSent {
+			message_id: MessageId,
+			from: T::AccountId,
+			to: T::AccountId,
+			hash: T::Hash,
+			/// Last block at which the message hash is still valid.
+			expires_at: BlockNumberFor<T>,
+			/// Position of the message in its conversation, starting at 0.
+			sequence: u64,
+			/// Conversation between `from` and `to`, see `Pallet::conversation_id`.
+			conversation_id: T::Hash,
+		},
+		/// A contact was approved.
+		ContactApproved { approver: T::AccountId, contact: T::AccountId },
+		/// A contact was removed.
+		ContactRemoved { remover: T::AccountId, contact: T::AccountId },
+		/// A message was challenged as spam.
+		SpamChallenged { message_id: MessageId, challenger: T::AccountId },
+		/// A spam bond was refunded.
+		BondRefunded { who: T::AccountId, amount: BalanceOf<T> },
+		/// A user profile was updated.
+		ProfileUpdated {
+			who: T::AccountId,
+			/// Hash of the new public key, see `Pallet::key_hash`.
+			key_hash: T::Hash,
+			/// Version of the new public key.
+			version: u32,
+		},
+		/// A spam challenge was resolved.
+		ChallengeResolved { message_id: MessageId, upheld: bool },
+		/// A strike was recorded against an account.
+		StrikeRecorded { who: T::AccountId, strikes: u32 },
+		/// An account reached the strike limit and was graylisted.
+		AccountGraylisted { who: T::AccountId, strikes: u32 },
+		/// A graylisted account's strikes decayed below the limit.
+		AccountRestored { who: T::AccountId },
+		/// A spam bond was topped up.
+		BondIncreased { who: T::AccountId, amount: BalanceOf<T>, total: BalanceOf<T> },
+		/// Excess spam bond was withdrawn.
+		BondDecreased { who: T::AccountId, amount: BalanceOf<T>, total: BalanceOf<T> },
+		/// Part of a spam bond was slashed into escrow.
+		BondSlashed { who: T::AccountId, amount: BalanceOf<T> },
+		/// An escrowed slash was returned to the sender's bond.
+		SlashReversed { who: T::AccountId, amount: BalanceOf<T> },
+		/// A strike was withdrawn after a verdict was overturned.
+		StrikeRevoked { who: T::AccountId, strikes: u32 },
+		/// A challenge verdict was appealed.
+		ChallengeAppealed { message_id: MessageId, appellant: T::AccountId },
+		/// An appealed challenge received its final verdict.
+		AppealResolved { message_id: MessageId, upheld: bool },
+		/// A challenge verdict became final and its bonds were settled.
+		ChallengeFinalized { message_id: MessageId, upheld: bool },
+		/// A recipient reported a message as spam.
+		SpamReported {
+			message_id: MessageId,
+			reporter: T::AccountId,
+			sender: T::AccountId,
+			reports: u32,
+		},
+		/// A challenge was opened automatically after a sender reached `AutoChallengeThreshold`.
+		AutoChallengeOpened { message_id: MessageId, sender: T::AccountId },
+		/// A spam bond was slashed into escrow after reaching `AutoSlashThreshold` strikes.
+		BondAutoSlashed { who: T::AccountId, amount: BalanceOf<T>, strikes: u32 },
+		/// An unresolved challenge expired and the challenger's bond was refunded.
+		ChallengeExpired { message_id: MessageId },
+		/// An account set or cleared its inbox price.
+		InboxPriceSet { who: T::AccountId, price: Option<BalanceOf<T>> },
+		/// The inbox price for a message was placed in escrow.
+		InboxPaymentHeld {
+			message_id: MessageId,
+			sender: T::AccountId,
+			recipient: T::AccountId,
+			amount: BalanceOf<T>,
+			tier: InboxTier,
+		},
+		/// A recipient acknowledged a paid message and received its payment.
+		MessageAcknowledged {
+			message_id: MessageId,
+			recipient: T::AccountId,
+			amount: BalanceOf<T>,
+			/// Blocks between the message being sent and acknowledged.
+			latency: BlockNumberFor<T>,
+		},
+		/// A recipient granted a sender a pass to message it without paying.
+		InboxPassGranted { recipient: T::AccountId, sender: T::AccountId, until: BlockNumberFor<T> },
+		/// A sender retracted a paid message before it was acknowledged.
+		PaidMessageRetracted { message_id: MessageId, sender: T::AccountId, amount: BalanceOf<T> },
+		/// The payment for an expired, unacknowledged message was refunded to its sender.
+		InboxPaymentRefunded { message_id: MessageId, sender: T::AccountId, amount: BalanceOf<T> },
+		/// Call groups were paused; `paused` is the full set now paused.
+		PalletPaused { calls: PausedCalls, paused: PausedCalls },
+		/// Call groups were unpaused; `paused` is the full set still paused.
+		PalletUnpaused { calls: PausedCalls, paused: PausedCalls },
+		/// A message was taken down by `ForceOrigin`.
+		MessageTakenDown {
+			message_id: MessageId,
+			sender: T::AccountId,
+			recipient: T::AccountId,
+			order_reference: OrderReference,
+		},
+		/// A takedown record was pruned after its retention period.
+		TakedownPruned { message_id: MessageId },
+		/// The registration mode was changed.
+		RegistrationModeSet { mode: RegistrationMode },
+		/// An account was added to the registration whitelist.
+		AccountWhitelisted { who: T::AccountId },
+		/// An account was removed from the registration whitelist.
+		AccountRemovedFromWhitelist { who: T::AccountId },
+		/// Pallet parameters were updated.
+		ParametersUpdated { old: PalletParametersOf<T>, new: PalletParametersOf<T> },
+		/// An account was added to the moderator set.
+		ModeratorAdded { who: T::AccountId },
+		/// An account was removed from the moderator set.
+		ModeratorRemoved { who: T::AccountId },
+		/// A moderator acted against `target`.
+		ModeratorAction {
+			who: T::AccountId,
+			action: ModeratorActionKind<BlockNumberFor<T>>,
+			target: T::AccountId,
+		},
+		/// An account was banned, its profile removed and its bond slashed.
+		AccountBanned { who: T::AccountId, reason_hash: T::Hash, slashed: BalanceOf<T> },
+		/// A ban was lifted.
+		AccountUnbanned { who: T::AccountId },
+		/// An income counter was reset after reconciliation.
+		IncomeReset { category: IncomeCategory, amount: BalanceOf<T> },
+		/// Governance replaced a compromised key.
+		KeyReset { who: T::AccountId, version: u32, algorithm: KeyAlgorithm },
+		/// `who`, a mutual contact of `contact`, had its key reset by governance.
+		ContactKeyReset { contact: T::AccountId, who: T::AccountId },
+		/// All contacts of `who` have been notified of its key reset.
+		KeyResetNotificationsCompleted { who: T::AccountId },
+		/// Expired message hashes were pruned; `next` is the lowest ID still awaiting pruning.
+		ExpiredMessagesPruned { count: u32, next: MessageId },
+		/// The hash of an expired message was pruned. `conversation_id` is `None` for stealth
+		/// and recipient-set messages.
+		MessageExpired { message_id: MessageId, conversation_id: Option<T::Hash> },
+		/// `count` more expired messages were pruned after `MaxExpiryEventsPerBlock`
+		/// `MessageExpired` events in this block: every message between `first_id` and
+		/// `last_id` that was still stored.
+		MessagesExpiredBatch { count: u32, first_id: MessageId, last_id: MessageId },
+		/// Expired group message hashes were pruned; `next` is the lowest group message ID
+		/// still awaiting pruning.
+		ExpiredGroupMessagesPruned { count: u32, next: MessageId },
+		/// A group owner changed how long group message hashes verify.
+		GroupRetentionSet { group_id: GroupId, blocks: BlockNumberFor<T> },
+		/// A group owner or admin muted a member.
+		MemberMuted {
+			group_id: GroupId,
+			who: T::AccountId,
+			until: BlockNumberFor<T>,
+			muted_by: T::AccountId,
+		},
+		/// A group owner or admin lifted the mute of a member.
+		MemberUnmuted { group_id: GroupId, who: T::AccountId, unmuted_by: T::AccountId },
+		/// A group owner changed who can send group messages.
+		GroupPostingPolicySet { group_id: GroupId, policy: PostingPolicy },
+		/// A member muted a group for themselves.
+		GroupMuted { group_id: GroupId, who: T::AccountId },
+		/// A member unmuted a group for themselves.
+		GroupUnmuted { group_id: GroupId, who: T::AccountId },
+		/// A group owner or admin banned an account from the group.
+		BannedFromGroup {
+			group_id: GroupId,
+			who: T::AccountId,
+			banned_by: T::AccountId,
+			reason_hash: T::Hash,
+		},
+		/// A group owner or admin lifted the ban of an account from the group.
+		GroupBanLifted { group_id: GroupId, who: T::AccountId, lifted_by: T::AccountId },
+		/// A group was disbanded and its deposit released to its owner.
+		GroupDisbanded { group_id: GroupId, owner: T::AccountId, deposit: BalanceOf<T> },
+		/// A group owner set the metadata of a group.
+		GroupMetadataSet { group_id: GroupId, is_discoverable: bool, deposit: BalanceOf<T> },
+		/// The metadata of a group was cleared and its deposit refunded.
+		GroupMetadataCleared { group_id: GroupId, depositor: T::AccountId, refunded: BalanceOf<T> },
+		/// An account asked to join a discoverable group.
+		GroupJoinRequested { group_id: GroupId, who: T::AccountId, expires_at: BlockNumberFor<T> },
+		/// A group owner or admin let an account that asked to join into the group.
+		GroupJoinRequestApproved {
+			group_id: GroupId,
+			who: T::AccountId,
+			approved_by: T::AccountId,
+		},
+		/// A join request was withdrawn, rejected or removed after expiring.
+		GroupJoinRequestCancelled { group_id: GroupId, who: T::AccountId },
+		/// Governance removed a group message.
+		GroupMessageTakenDown {
+			group_id: GroupId,
+			message_id: MessageId,
+			seq: u64,
+			order_reference: OrderReference,
+		},
+		/// The owner appointed a group moderator.
+		ModeratorAppointed { group_id: GroupId, who: T::AccountId },
+		/// The owner made a group moderator an ordinary member again.
+		ModeratorDismissed { group_id: GroupId, who: T::AccountId },
+		/// A moderator, admin or the owner removed a group message, leaving a tombstone.
+		GroupMessageRemoved {
+			group_id: GroupId,
+			message_id: MessageId,
+			seq: u64,
+			removed_by: T::AccountId,
+		},
+		/// A message hash was recorded under a recipient tag.
+		StealthMessageSent {
+			message_id: MessageId,
+			from: T::AccountId,
+			recipient_tag: T::Hash,
+			hash: T::Hash,
+			expires_at: BlockNumberFor<T>,
+		},
+		/// An account registered as a relay or restored its relay bond.
+		RelayRegistered { relay: T::AccountId, held: BalanceOf<T> },
+		/// A relay deregistered and got its bond back.
+		RelayDeregistered { relay: T::AccountId, refunded: BalanceOf<T> },
+		/// A relay recorded a message hash on behalf of a sender it does not reveal.
+		SealedMessageSent {
+			message_id: MessageId,
+			relay: T::AccountId,
+			to: T::AccountId,
+			hash: T::Hash,
+			expires_at: BlockNumberFor<T>,
+		},
+		/// A message hash was left for the account hashing to `recipient_account_hash`.
+		PendingMessageSent {
+			pending_id: MessageId,
+			from: T::AccountId,
+			recipient_account_hash: T::Hash,
+			hash: T::Hash,
+			expires_at: BlockNumberFor<T>,
+		},
+		/// A newly registered account claimed a pending message into its inbox.
+		PendingMessageClaimed {
+			pending_id: MessageId,
+			message_id: MessageId,
+			from: T::AccountId,
+			to: T::AccountId,
+			refunded: BalanceOf<T>,
+		},
+		/// Expired pending messages were pruned; `next` is the lowest pending message ID still
+		/// awaiting pruning.
+		ExpiredPendingMessagesPruned { count: u32, next: MessageId },
+		/// A message hash was recorded on-chain, emitted instead of `MessageSent` under
+		/// `EventPrivacyMode::Minimal`. The message is readable with `Pallet::message_info`.
+		MessageSentMinimal { message_id: MessageId, conversation_id: T::Hash },
+		/// A contact was approved, emitted instead of `ContactApproved` under
+		/// `EventPrivacyMode::Minimal`.
+		ContactApprovedMinimal {
+			/// `Pallet::contact_pair_hash` of the approver and the contact.
+			pair: T::Hash,
+		},
+		/// A contact was removed, emitted instead of `ContactRemoved` under
+		/// `EventPrivacyMode::Minimal`.
+		ContactRemovedMinimal {
+			/// `Pallet::contact_pair_hash` of the remover and the contact.
+			pair: T::Hash,
+		},
+		/// The sender contested a challenge; the challenger must reveal their evidence by
+		/// block `reveal_by`.
+		ChallengeContested { message_id: MessageId, reveal_by: BlockNumberFor<T> },
+		/// The challenger revealed evidence matching their commitment.
+		EvidenceRevealed { message_id: MessageId },
+		/// Revealed evidence did not match the commitment; the challenge was closed and the
+		/// challenger's bond forfeited.
+		EvidenceMismatch {
+			message_id: MessageId,
+			challenger: T::AccountId,
+			forfeited: BalanceOf<T>,
+		},
+		/// A relay carried a message whose hidden sender proved to be an approved contact.
+		AnonymousContactMessageSent {
+			message_id: MessageId,
+			relay: T::AccountId,
+			to: T::AccountId,
+			hash: T::Hash,
+			expires_at: BlockNumberFor<T>,
+		},
+		/// An ephemeral key was published under `index`, evicting the key under `evicted` if
+		/// the sender already had `MaxEphemeralKeys`.
+		EphemeralKeyPublished { who: T::AccountId, index: u32, evicted: Option<u32> },
+		/// A message was sent under the sender's ephemeral key at `index`.
+		EphemeralKeyReferenced { message_id: MessageId, index: u32 },
+		/// Expired ephemeral keys were pruned; `next` is the lowest publication ID still
+		/// awaiting pruning.
+		ExpiredEphemeralKeysPruned { count: u32, next: u64 },
+		/// A message hash was sent to a recipient set, holding `deposit` from the sender.
+		RecipientSetMessageSent {
+			message_id: MessageId,
+			from: T::AccountId,
+			recipients: RecipientSet<T::AccountId>,
+			hash: T::Hash,
+			expires_at: BlockNumberFor<T>,
+			deposit: BalanceOf<T>,
+		},
+		/// A member of its recipient set acknowledged a message, refunding its deposit.
+		RecipientSetMessageAcknowledged {
+			message_id: MessageId,
+			by: T::AccountId,
+			refunded: BalanceOf<T>,
+		},
+		/// An account committed to a new backup of its contact list.
+		BackupCommitted { who: T::AccountId, backup_hash: T::Hash, version: u32 },
+		/// A protocol fee was withdrawn from the sender of a message.
+		ProtocolFeeCharged { who: T::AccountId, amount: BalanceOf<T> },
+		/// An account set or cleared its tiered inbox prices.
+		InboxPricingSet { who: T::AccountId, pricing: InboxPricingOf<T> },
+		/// A member of a recipient set replied to its sender, refunding the deposit of a
+		/// message sent to the set.
+		DepositReleasedOnReply {
+			message_id: MessageId,
+			sender: T::AccountId,
+			replier: T::AccountId,
+			refunded: BalanceOf<T>,
+		},
+		/// An account bought prepaid message credits.
+		CreditsPurchased { who: T::AccountId, count: u32, paid: BalanceOf<T> },
+		/// An account used a multiple of `CREDIT_MILESTONE` message credits, or its last one.
+		CreditsRemaining { who: T::AccountId, remaining: u32 },
+		/// An account returned its unused message credits.
+		CreditsRefunded { who: T::AccountId, count: u32, refunded: BalanceOf<T> },
+		/// A sponsor registered an account, holding its spam bond.
+		RegistrationSponsored { who: T::AccountId, sponsor: T::AccountId, amount: BalanceOf<T> },
+		/// An account took over its sponsored spam bond, which was released to the sponsor.
+		BondAssumed { who: T::AccountId, sponsor: T::AccountId, amount: BalanceOf<T> },
+		/// An account paid into the challenge reward pool.
+		RewardPoolFunded { who: T::AccountId, amount: BalanceOf<T> },
+		/// A challenger was paid from the challenge reward pool for an upheld challenge.
+		ChallengeRewardPaid {
+			message_id: MessageId,
+			challenger: T::AccountId,
+			amount: BalanceOf<T>,
+		},
+		/// A profile lookup was sent to another chain.
+		RemoteProfileRequested { query_id: ProfileQueryId, dest: Location, who: T::AccountId },
+		/// A profile lookup from another chain was answered.
+		ProfileLookupAnswered {
+			query_id: ProfileQueryId,
+			requester: Location,
+			who: T::AccountId,
+			found: bool,
+		},
+		/// A profile was fetched from another chain and cached.
+		RemoteProfileFetched { query_id: ProfileQueryId, dest: Location, who: T::AccountId },
+		/// Another chain reported that the account has no profile.
+		RemoteProfileNotFound { query_id: ProfileQueryId, dest: Location, who: T::AccountId },
+		/// A profile lookup expired without an answer.
+		ProfileQueryTimedOut { query_id: ProfileQueryId, dest: Location, who: T::AccountId },
+		/// A step of the multi-block message migration rewrote `migrated` messages, up to
+		/// and including `last`, for `total` so far.
+		MessageMigrationAdvanced { migrated: u32, total: u32, last: Option<MessageId> },
+		/// The multi-block message migration rewrote every message.
+		MessageMigrationCompleted,
+		/// A group chat was created.
+		GroupCreated {
+			group_id: GroupId,
+			owner: T::AccountId,
+			/// Hash of the group key commitment, see `Pallet::key_hash`.
+			key_hash: T::Hash,
+			/// Deposit held from the owner.
+			deposit: BalanceOf<T>,
+		},
+		/// An account was added to a group.
+		MemberAdded { group_id: GroupId, who: T::AccountId },
+		/// An account was removed from a group, by an owner, an admin or itself.
+		MemberRemoved { group_id: GroupId, who: T::AccountId, removed_by: T::AccountId },
+		/// A member left a group.
+		MemberLeft { group_id: GroupId, who: T::AccountId },
+		/// The owner of a group left without an admin to take over.
+		GroupFrozen { group_id: GroupId },
+		/// An account was invited to a group.
+		GroupInviteSent {
+			group_id: GroupId,
+			who: T::AccountId,
+			invited_by: T::AccountId,
+			/// Last block at which the invite can be accepted.
+			expires_at: BlockNumberFor<T>,
+		},
+		/// An account accepted an invite and joined the group.
+		GroupInviteAccepted { group_id: GroupId, who: T::AccountId },
+		/// An account declined an invite.
+		GroupInviteDeclined { group_id: GroupId, who: T::AccountId },
+		/// An invite expired without an answer and was removed.
+		GroupInviteExpired { group_id: GroupId, who: T::AccountId },
+		/// A group owner changed whether accounts can only join by invite.
+		GroupInviteOnlySet { group_id: GroupId, invite_only: bool },
+		/// A group member was made an admin.
+		MemberPromoted { group_id: GroupId, who: T::AccountId },
+		/// A group admin was made an ordinary member.
+		MemberDemoted { group_id: GroupId, who: T::AccountId },
+		/// A group owner offered ownership of the group to an admin.
+		GroupOwnershipOffered { group_id: GroupId, owner: T::AccountId, new_owner: T::AccountId },
+		/// An admin accepted ownership of a group. The previous owner is now an admin.
+		GroupOwnershipTransferred {
+			group_id: GroupId,
+			old_owner: T::AccountId,
+			new_owner: T::AccountId,
+		},
+		/// A message hash was recorded for a group.
+		GroupMessageSent {
+			group_id: GroupId,
+			message_id: MessageId,
+			sender: T::AccountId,
+			hash: T::Hash,
+			/// Version of the group key the message was encrypted under.
+			key_version: u32,
+			/// Sequence number of the message within the group.
+			seq: u64,
+		},
+		/// The key of a group was rotated.
+		GroupKeyRotated {
+			group_id: GroupId,
+			/// Version of the new group key.
+			version: u32,
+			/// Hash of the new group key commitment, see `Pallet::key_hash`.
+			key_hash: T::Hash,
+		},
+		/// An inactive profile was flagged dormant and stops receiving messages.
+		ProfileFlaggedDormant { who: T::AccountId, flagged_by: T::AccountId },
+		/// The owner of a dormant profile became active again.
+		ProfileReactivated { who: T::AccountId },
+		/// A dormant profile was removed and its bond refunded to whoever it was held from.
+		DormantProfileSwept { who: T::AccountId, refunded: BalanceOf<T> },
+		/// An account set or cleared its notification hint; `hint_hash` is the hash relays
+		/// filter `NotifyRelay` events on.
+		NotificationHintSet { who: T::AccountId, hint_hash: Option<T::Hash> },
+		/// A message was sent to a recipient that opted in to push notifications.
+		NotifyRelay { hint_hash: T::Hash, recipient: T::AccountId, message_id: MessageId },
+		/// The recipient recorded whether a claimed hash matched the message.
+		MessageVerificationRecorded { message_id: MessageId, matched: bool },
+		/// A profile was removed, by its owner or by governance if `forced`. `refunded` is the
+		/// part of the bond released straight away.
+		ProfileDeregistered { who: T::AccountId, refunded: BalanceOf<T>, forced: bool },
+		/// An account chose whether its batch sends are reported in one `MessagesSentCompact`
+		/// event.
+		CompactEventsSet { who: T::AccountId, enabled: bool },
+		/// `count` messages with the consecutive IDs from `first_id` were sent by `from` in one
+		/// batch, emitted instead of a `MessageSent` event each. `ids_root` commits to their
+		/// IDs, recipients and hashes, see the [`crate::compact_events`] module.
+		MessagesSentCompact {
+			from: T::AccountId,
+			count: u32,
+			first_id: MessageId,
+			ids_root: T::Hash,
+		},
+		/// `initiator` recorded a session with `peer`, awaiting the peer's confirmation.
+		SessionRecorded {
+			initiator: T::AccountId,
+			peer: T::AccountId,
+			commitment: T::Hash,
+			prekey_index: Option<u32>,
+			epoch: u32,
+		},
+		/// `peer` confirmed the session `initiator` recorded.
+		SessionConfirmed { initiator: T::AccountId, peer: T::AccountId, epoch: u32 },
+		/// `by`, one of its parties, terminated the session `initiator` recorded with `peer`.
+		SessionTerminated {
+			initiator: T::AccountId,
+			peer: T::AccountId,
+			epoch: u32,
+			by: T::AccountId,
+		},
+		/// An account chose whether it only receives direct messages over confirmed sessions.
+		SessionRequirementSet { who: T::AccountId, required: bool },
+		/// An account registered a key for a new device.
+		DeviceKeyAdded {
+			who: T::AccountId,
+			device_id: DeviceId,
+			key_hash: T::Hash,
+			devices_revision: u32,
+		},
+		/// An account replaced the key of one of its devices, which keeps its ID.
+		DeviceKeyReplaced {
+			who: T::AccountId,
+			device_id: DeviceId,
+			old_key_hash: T::Hash,
+			new_key_hash: T::Hash,
+			devices_revision: u32,
+		},
+		/// An account removed one of its devices.
+		DeviceKeyRemoved { who: T::AccountId, device_id: DeviceId, devices_revision: u32 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Profile already exists for this account.
+		ProfileAlreadyExists,
+		/// No profile found for this account.
+		ProfileNotFound,
+		/// Insufficient balance for spam bond.
+		InsufficientBond,
+		/// Invalid public key format.
+		InvalidPublicKey,
+		/// Recipient doesn't have a profile.
+		RecipientNotFound,
+		/// Maximum number of contacts reached.
+		MaxContactsReached,
+		/// Contact not in approved list.
+		ContactNotApproved,
+		/// Message not found in storage.
+		MessageNotFound,
+		/// Message hash has expired.
+		MessageExpired,
+		/// Not authorized to perform this action.
+		NotAuthorized,
+		/// Bond has already been refunded.
+		BondAlreadyRefunded,
+		/// Cannot add yourself as a contact.
+		CannotAddSelf,
+		/// Public key too large.
+		PublicKeyTooLarge,
+		/// Message already has an open challenge.
+		AlreadyChallenged,
+		/// No open challenge for this message.
+		ChallengeNotFound,
+		/// Send rate limit for the current window exceeded.
+		RateLimitExceeded,
+		/// Account is graylisted and may only interact with mutual contacts.
+		Graylisted,
+		/// Bond top-up is below the minimum increment.
+		BondIncrementTooSmall,
+		/// Bond does not exceed the required amount.
+		NoExcessBond,
+		/// Bond is still securing recently sent messages.
+		BondLocked,
+		/// Account has open spam challenges against it.
+		OpenChallengesPending,
+		/// Insufficient balance for challenge bond.
+		InsufficientChallengeBond,
+		/// Insufficient balance for appeal bond.
+		InsufficientAppealBond,
+		/// Challenge has already been resolved.
+		ChallengeNotOpen,
+		/// Challenge is not awaiting appeal or finalization.
+		ChallengeNotResolved,
+		/// Appeal window for this challenge has closed.
+		AppealWindowClosed,
+		/// Appeal window for this challenge is still open.
+		AppealWindowOpen,
+		/// Challenge has not been appealed.
+		AppealNotFound,
+		/// Message has already been reported by this account.
+		AlreadyReported,
+		/// Challenge has not been open for `ChallengeMaxDuration` blocks.
+		ChallengeNotExpired,
+		/// Recipient does not accept paid messages.
+		InboxClosed,
+		/// Insufficient balance to pay the recipient's inbox price.
+		InsufficientInboxPayment,
+		/// Message has no inbox payment awaiting acknowledgement.
+		PaymentNotFound,
+		/// Message has not expired yet.
+		MessageNotExpired,
+		/// This call is paused.
+		PalletPaused,
+		/// Message expiry must be at least one block.
+		ZeroMessageExpiry,
+		/// Spam bond must not be below the existential deposit.
+		SpamBondBelowMinimum,
+		/// Upheld challengers must get a share of the slash or a base reward.
+		ChallengerUnrewarded,
+		/// The auto-slash percentage must not be zero; disable auto-slashing instead.
+		ZeroAutoSlashPercent,
+		/// The challenge and auto-slash percentages together exceed the whole bond.
+		SlashPercentagesTooHigh,
+		/// Account is already a moderator.
+		AlreadyModerator,
+		/// Account is not a moderator.
+		NotModerator,
+		/// Moderator set is full.
+		TooManyModerators,
+		/// Only challenges opened without a challenger can be fast-tracked.
+		NotSystemChallenge,
+		/// Freeze duration must be between one block and `MaxFreezeDuration`.
+		InvalidFreezeDuration,
+		/// Sender is frozen pending moderator review.
+		SenderFrozen,
+		/// Sender is not frozen.
+		SenderNotFrozen,
+		/// Account is banned from the messaging system.
+		AccountBanned,
+		/// Account is not banned.
+		AccountNotBanned,
+		/// No key reset notifications are pending for the account.
+		NoPendingKeyResetNotices,
+		/// Message was removed by governance.
+		MessageRemovedByGovernance,
+		/// No takedown record exists for the message.
+		TakedownNotFound,
+		/// Takedown record is still within its retention period.
+		TakedownRetained,
+		/// Account is not on the registration whitelist.
+		NotWhitelisted,
+		/// Account is already on the registration whitelist.
+		AlreadyWhitelisted,
+		/// No message expired by the given block is awaiting pruning.
+		NothingToPrune,
+		/// The destination location uses an unsupported XCM version.
+		BadVersion,
+		/// The XCM message could not be sent.
+		XcmSendFailed,
+		/// No profile lookup with this ID is pending.
+		UnknownProfileQuery,
+		/// The answer did not come from the chain the lookup was sent to.
+		UnexpectedResponder,
+		/// The profile lookup timed out before it was answered.
+		ProfileQueryExpired,
+		/// The profile lookup has not timed out yet.
+		ProfileQueryNotExpired,
+		/// No profile version is understood by both chains.
+		UnsupportedProfileVersion,
+		/// Account is not admitted by the registration gate.
+		RegistrationGateFailed,
+		/// The other account has not approved the caller as a contact.
+		NoContactRequest,
+		/// Group key commitment is empty.
+		InvalidGroupKey,
+		/// A group must have room for at least its owner.
+		InvalidGroupSize,
+		/// Account is a member of the maximum number of groups.
+		TooManyGroups,
+		/// Insufficient balance for the group deposit.
+		InsufficientGroupDeposit,
+		/// No group exists with this ID.
+		GroupNotFound,
+		/// Group has the maximum number of members.
+		GroupFull,
+		/// Account is not a member of the group.
+		NotGroupMember,
+		/// Account is already a member of the group.
+		AlreadyGroupMember,
+		/// Only the group owner can do this.
+		NotGroupOwner,
+		/// The group owner cannot be removed from its group.
+		CannotRemoveGroupOwner,
+		/// Only the group owner or an admin can do this.
+		NotGroupAdmin,
+		/// Member is not an admin of the group.
+		MemberNotAdmin,
+		/// Member is already an admin or the owner of the group.
+		AlreadyGroupAdmin,
+		/// Group has the maximum number of admins.
+		TooManyGroupAdmins,
+		/// Group ownership has not been offered to the caller.
+		NoPendingOwnershipTransfer,
+		/// Group key version does not follow the current one.
+		InvalidGroupKeyVersion,
+		/// A member left the group and its key must be rotated first.
+		GroupKeyRotationPending,
+		/// The group is frozen since its owner left.
+		GroupFrozen,
+		/// The group is being disbanded.
+		GroupDisbanding,
+		/// The group only admits members through invites.
+		GroupInviteRequired,
+		/// The account already has a pending invite to the group.
+		AlreadyGroupInvited,
+		/// The group has too many pending invites.
+		TooManyGroupInvites,
+		/// The account has too many pending group invites.
+		TooManyPendingGroupInvites,
+		/// No invite to the group is pending for the account.
+		GroupInviteNotFound,
+		/// The invite can no longer be accepted.
+		GroupInviteExpired,
+		/// The invite can still be accepted.
+		GroupInviteNotExpired,
+		/// Group retention must be non-zero and at most `GroupMessageExpiry`.
+		InvalidGroupRetention,
+		/// The sender is muted in the group.
+		MemberMuted,
+		/// The member is not muted in the group.
+		MemberNotMuted,
+		/// Mutes must last at least one block.
+		ZeroMuteDuration,
+		/// The group owner cannot be muted.
+		CannotMuteGroupOwner,
+		/// The group posting policy does not let the sender post.
+		PostingRestricted,
+		/// The group is already muted by the caller.
+		GroupAlreadyMuted,
+		/// The group is not muted by the caller.
+		GroupNotMuted,
+		/// The account is banned from the group.
+		BannedFromGroup,
+		/// The account is not banned from the group.
+		NotBannedFromGroup,
+		/// The group owner cannot be banned from its group.
+		CannotBanGroupOwner,
+		/// The group has banned `MaxGroupBans` accounts already.
+		TooManyGroupBans,
+		/// `MaxDiscoverableGroups` groups are listed for discovery already.
+		TooManyDiscoverableGroups,
+		/// Insufficient balance for the group metadata deposit.
+		InsufficientMetadataDeposit,
+		/// The group has no metadata.
+		GroupMetadataNotFound,
+		/// The group does not accept join requests.
+		GroupNotDiscoverable,
+		/// The account already asked to join the group.
+		AlreadyRequestedToJoin,
+		/// The group has `MaxGroupJoinRequests` pending join requests already.
+		TooManyJoinRequests,
+		/// No join request of the account to the group.
+		JoinRequestNotFound,
+		/// The join request expired.
+		JoinRequestExpired,
+		/// Only the requester can withdraw a join request that has not expired, or an owner,
+		/// admin or moderator reject it.
+		JoinRequestNotExpired,
+		/// Caller is not the owner, an admin or a moderator of the group.
+		NotGroupModerator,
+		/// Member is already a moderator of the group.
+		AlreadyGroupModerator,
+		/// Member is not a moderator of the group.
+		MemberNotModerator,
+		/// Sender cannot cover the deposit of a stealth message.
+		InsufficientStealthDeposit,
+		/// Account is not a registered relay.
+		NotRelay,
+		/// Account is already a relay with its full bond.
+		AlreadyRelay,
+		/// Insufficient balance for the relay bond, or the relay bond was slashed below
+		/// `RelayBond`.
+		InsufficientRelayBond,
+		/// Relay already carried `MaxRelayCarriesPerBlock` messages in this block.
+		RelayCarryLimitReached,
+		/// Membership proof was rejected by `MembershipProofVerifier`.
+		InvalidMembershipProof,
+		/// Sender cannot cover the deposit of a pending message.
+		InsufficientPendingDeposit,
+		/// No messages are pending for the caller.
+		NoPendingMessages,
+		/// Challenge was opened without a challenger and has no evidence to reveal.
+		NoEvidenceCommitment,
+		/// Challenge was already contested.
+		AlreadyContested,
+		/// Challenge has not been contested by the sender.
+		ChallengeNotContested,
+		/// The period for revealing evidence has ended.
+		EvidenceRevealClosed,
+		/// Evidence was already revealed.
+		EvidenceAlreadyRevealed,
+		/// Ephemeral key is empty.
+		EmptyEphemeralKey,
+		/// Sender has no unexpired ephemeral key under this index.
+		EphemeralKeyNotFound,
+		/// Recipient set is empty.
+		EmptyRecipientSet,
+		/// Sender cannot cover the deposit of a recipient-set message.
+		InsufficientRecipientSetDeposit,
+		/// The message was already acknowledged.
+		AlreadyAcknowledged,
+		/// Backup version does not exceed the latest committed version.
+		StaleBackupVersion,
+		/// Insufficient balance for the backup deposit.
+		InsufficientBackupDeposit,
+		/// Insufficient balance for the message protocol fee.
+		InsufficientProtocolFee,
+		/// Credit purchases must buy at least one credit.
+		ZeroCreditPurchase,
+		/// The purchase would take the account above `MaxCreditsPerAccount` unused credits.
+		CreditLimitExceeded,
+		/// Insufficient balance for the price of the credits.
+		InsufficientCreditPayment,
+		/// The account has no unused message credits.
+		NoUnusedCredits,
+		/// The signature does not prove the account consented to the sponsored registration.
+		InvalidSponsorshipProof,
+		/// Accounts cannot sponsor their own registration.
+		CannotSponsorSelf,
+		/// The spam bond is held from a sponsor and has to be assumed first.
+		BondSponsored,
+		/// The spam bond is not held from a sponsor.
+		BondNotSponsored,
+		/// Cannot fund the challenge reward pool with nothing.
+		ZeroRewardPoolFunding,
+		/// The recipient's profile is dormant until its owner becomes active again.
+		RecipientDormant,
+		/// The account was active within the last `InactivityThreshold` blocks.
+		AccountNotInactive,
+		/// The profile is already flagged dormant.
+		AlreadyDormant,
+		/// The profile is not flagged dormant.
+		ProfileNotDormant,
+		/// The account was active within the last `DormantSweepThreshold` blocks.
+		SweepTooEarly,
+		/// A live message with the same hash has already been sent.
+		DuplicateMessageHash,
+		/// The account is neither an approved contact of the caller nor requesting to be one.
+		ContactNotFound,
+		/// The account is already an approved contact of the caller.
+		AlreadyApproved,
+		/// The new public key is the one already registered.
+		KeyUnchanged,
+		/// The notification hint is already set to this value.
+		NotificationHintUnchanged,
+		/// The message was already verified on-chain.
+		AlreadyVerified,
+		/// The sender retracted the message.
+		MessageRetracted,
+		/// Item `index` of a multi-item call was rejected. Nothing done for earlier items
+		/// was kept.
+		ItemFailed { index: u8, error: ItemError },
+		/// Senders cannot challenge their own messages.
+		CannotChallengeOwnMessage,
+		/// The message was sent more than `ChallengeWindow` blocks ago.
+		ChallengeWindowClosed,
+		/// Cannot send a message to yourself.
+		CannotMessageSelf,
+		/// The message hash is the default hash, which no message hashes to.
+		InvalidMessageHash,
+		/// The recipient is one of the pallet's own accounts.
+		ReservedRecipient,
+		/// The bond of the account's removed profile is still held. Claim it with
+		/// `refund_bond` before registering again.
+		UnrefundedBond,
+		/// Compact events are already enabled or disabled as requested.
+		CompactEventsUnchanged,
+		/// A batch send lists no messages.
+		EmptySendBatch,
+		/// No session awaiting confirmation or confirmed between these accounts.
+		SessionNotFound,
+		/// The session was already confirmed.
+		SessionAlreadyConfirmed,
+		/// The commitment differs from the one the initiator recorded.
+		SessionCommitmentMismatch,
+		/// The recipient only receives direct messages over a confirmed session with the
+		/// sender.
+		SessionRequired,
+		/// Sessions are already required or not required as requested.
+		SessionRequirementUnchanged,
+		/// The account already has `MaxDevicesPerAccount` devices.
+		TooManyDevices,
+		/// The account has no device with this ID.
+		DeviceNotFound,
+		/// A device key comes without an attestation.
+		EmptyDeviceAttestation,
+		/// Insufficient balance for the deposit of a device key.
+		InsufficientDeviceDeposit,
+		/// The account cannot take the reference its profile keeps on it, as it lacks the
+		/// existential deposit.
+		AccountNotFunded,
+	}
+
+	impl<T> From<CanMessageError> for Error<T> {
+		fn from(error: CanMessageError) -> Self {
+			match error {
+				CanMessageError::Paused => Error::<T>::PalletPaused,
+				CanMessageError::SenderBanned | CanMessageError::RecipientBanned =>
+					Error::<T>::AccountBanned,
+				CanMessageError::SenderFrozen => Error::<T>::SenderFrozen,
+				CanMessageError::NoSenderProfile => Error::<T>::ProfileNotFound,
+				CanMessageError::NoRecipientProfile => Error::<T>::RecipientNotFound,
+				CanMessageError::RecipientDormant => Error::<T>::RecipientDormant,
+				CanMessageError::SenderGraylisted => Error::<T>::Graylisted,
+				CanMessageError::InsufficientBond => Error::<T>::InsufficientBond,
+				CanMessageError::RateLimited => Error::<T>::RateLimitExceeded,
+				CanMessageError::InsufficientProtocolFee => Error::<T>::InsufficientProtocolFee,
+				CanMessageError::MessageToSelf => Error::<T>::CannotMessageSelf,
+				CanMessageError::ReservedRecipient => Error::<T>::ReservedRecipient,
+				CanMessageError::SessionRequired => Error::<T>::SessionRequired,
+				CanMessageError::NotApproved => Error::<T>::ContactNotApproved,
+			}
+		}
+	}
+
+	impl<T> From<ItemError> for Error<T> {
+		fn from(error: ItemError) -> Self {
+			match error {
+				ItemError::CannotMessage(error) => error.into(),
+				ItemError::InvalidHash => Error::<T>::InvalidMessageHash,
+				ItemError::DuplicateHash => Error::<T>::DuplicateMessageHash,
+				// Only recipient sets reject these, and report the position with them
+				error => Error::<T>::ItemFailed { index: 0, error },
+			}
+		}
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn integrity_test() {
+			// Appeals must cost more than challenges, or challengers could appeal their own
+			// dismissed challenges for less than a fresh one
+			assert!(
+				T::AppealBond::get() > T::ChallengeBond::get(),
+				"`AppealBond` must exceed `ChallengeBond`"
+			);
+
+			// Same bounds `update_parameters` enforces on the stored parameters, which start
+			// from these defaults. A zero expiry makes every message expire on arrival, and a
+			// bond below the existential deposit cannot be held.
+			assert!(!T::MessageHashExpiry::get().is_zero(), "`MessageHashExpiry` must be non-zero");
+			assert!(
+				T::SpamBond::get() >= T::Currency::minimum_balance(),
+				"`SpamBond` must be at least the existential deposit"
+			);
+			assert!(T::MaxContactsPerUser::get() > 0, "`MaxContactsPerUser` must be non-zero");
+
+			// With an empty window or quota, nobody could ever send a message
+			assert!(!T::RateLimitWindow::get().is_zero(), "`RateLimitWindow` must be non-zero");
+			assert!(T::MaxMessagesPerWindow::get() > 0, "`MaxMessagesPerWindow` must be non-zero");
+
+			// Pruning transactions are valid for `PruneInterval` blocks, and must make progress
+			assert!(!T::PruneInterval::get().is_zero(), "`PruneInterval` must be non-zero");
+			assert!(T::MaxPrunesPerCall::get() > 0, "`MaxPrunesPerCall` must be non-zero");
+
+			// Key reset notices would otherwise never finish a contact list
+			assert!(
+				T::MaxKeyResetNotifications::get() > 0,
+				"`MaxKeyResetNotifications` must be non-zero"
+			);
+
+			// Nobody could create a group, as owners count as members
+			assert!(T::MaxGroupsPerAccount::get() > 0, "`MaxGroupsPerAccount` must be non-zero");
+			assert!(T::MaxGroupMembers::get() > 0, "`MaxGroupMembers` must be non-zero");
+
+			// Invites would expire before they could be accepted
+			assert!(!T::GroupInviteExpiry::get().is_zero(), "`GroupInviteExpiry` must be non-zero");
+			assert!(
+				!T::GroupMessageExpiry::get().is_zero(),
+				"`GroupMessageExpiry` must be non-zero"
+			);
+			assert!(
+				T::MaxGroupMessagesPerWindow::get() > 0,
+				"`MaxGroupMessagesPerWindow` must be non-zero"
+			);
+
+			// Disbanding groups would never make progress
+			assert!(
+				T::MaxDisbandRemovalsPerCall::get() > 0,
+				"`MaxDisbandRemovalsPerCall` must be non-zero"
+			);
+
+			// A relay answers for many senders, so it must stake more than any one of them
+			assert!(T::RelayBond::get() > T::SpamBond::get(), "`RelayBond` must exceed `SpamBond`");
+			assert!(
+				T::MaxRelayCarriesPerBlock::get() > 0,
+				"`MaxRelayCarriesPerBlock` must be non-zero"
+			);
+
+			// Pending messages could never be claimed
+			assert!(
+				!T::PendingMessageExpiry::get().is_zero(),
+				"`PendingMessageExpiry` must be non-zero"
+			);
+			assert!(T::MaxClaimsPerCall::get() > 0, "`MaxClaimsPerCall` must be non-zero");
+			// Contested challenges could never have their evidence revealed
+			assert!(
+				!T::EvidenceRevealPeriod::get().is_zero(),
+				"`EvidenceRevealPeriod` must be non-zero"
+			);
+			assert!(T::MaxEphemeralKeys::get() > 0, "`MaxEphemeralKeys` must be non-zero");
+			// A zero deposit would mark recipient-set messages acknowledged on arrival
+			assert!(
+				!T::RecipientSetDeposit::get().is_zero(),
+				"`RecipientSetDeposit` must be non-zero"
+			);
+			// Profiles must be flaggable dormant before they can be swept
+			assert!(
+				!T::InactivityThreshold::get().is_zero(),
+				"`InactivityThreshold` must be non-zero"
+			);
+			assert!(
+				T::DormantSweepThreshold::get() > T::InactivityThreshold::get(),
+				"`DormantSweepThreshold` must exceed `InactivityThreshold`"
+			);
+			assert!(T::MaxSendBatch::get() > 0, "`MaxSendBatch` must be non-zero");
+			assert!(
+				T::MaxDevicesPerAccount::get() <= MAX_DEVICE_SLOTS,
+				"`MaxDevicesPerAccount` must not exceed `MAX_DEVICE_SLOTS`"
+			);
+
+			// A bound too large for a parachain block fails here rather than in production
+			for (call, weight) in Self::worst_case_weights() {
+				assert!(
+					weight.proof_size() <= MAX_CALL_PROOF_SIZE,
+					"worst-case proof of `{}` is {} bytes, over `MAX_CALL_PROOF_SIZE`",
+					call,
+					weight.proof_size(),
+				);
+			}
+		}
+
+		/// Records the number of stored messages once every migration has run.
+		///
+		/// Checks specific to one migration live on the migration itself and run as part of
+		/// [`crate::migrations::Migrations`]; these hooks check what must hold after any upgrade.
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+			Ok((MessageHashes::<T>::iter_keys().count() as u64).encode())
+		}
+
+		/// Checks that no message was lost and every message decodes in the current layout.
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+			let count = u64::decode(&mut &state[..])
+				.map_err(|_| "messaging: undecodable pre-upgrade state")?;
+
+			let mut stored = 0u64;
+			for message_id in MessageHashes::<T>::iter_keys() {
+				ensure!(
+					MessageHashes::<T>::get(message_id).is_some(),
+					"messaging: message does not decode in the current layout"
+				);
+				stored.saturating_inc();
+			}
+			ensure!(stored == count, "messaging: message count changed during upgrade");
+			Ok(())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+			Self::do_try_state()
+		}
+
+		/// Prunes expired messages and keys with the weight left over at the end of the block.
+		///
+		/// Items are charged at their measured `PruneCosts`, so pruning stops before it would
+		/// exceed `remaining_weight`; the offchain worker's transactions cover the rest.
+		fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			let mut meter = WeightMeter::with_limit(remaining_weight);
+			if meter.try_consume(T::WeightInfo::prune_expired_unsigned(0)).is_err() ||
+				!Self::has_overdue_messages(now)
+			{
+				return meter.consumed();
+			}
+			Self::prune_overdue(now, u32::MAX, &mut meter);
+			meter.consumed()
+		}
+
+		fn offchain_worker(now: BlockNumberFor<T>) {
+			if !Self::has_overdue_messages(now) {
+				return;
+			}
+
+			// Submit at most one pruning transaction per `PruneInterval`, so the pool is not
+			// flooded with duplicates while an earlier one is still pending
+			let last_submitted = StorageValueRef::persistent(PRUNE_LOCK_KEY);
+			let claimed = last_submitted.mutate(
+				|last: Result<Option<BlockNumberFor<T>>, StorageRetrievalError>| match last {
+					Ok(Some(last)) if now < last.saturating_add(T::PruneInterval::get()) => Err(()),
+					_ => Ok(now),
+				},
+			);
+			if claimed.is_err() {
+				return;
+			}
+
+			let call = Call::prune_expired_unsigned { up_to_block: now };
+			let _ = SubmitTransaction::<T, Call<T>>::submit_transaction(T::create_bare(call.into()));
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let Call::prune_expired_unsigned { up_to_block } = call else {
+				return InvalidTransaction::Call.into();
+			};
+			if *up_to_block > frame_system::Pallet::<T>::block_number() {
+				return InvalidTransaction::Future.into();
+			}
+			if !Self::has_overdue_messages(*up_to_block) {
+				return InvalidTransaction::Stale.into();
+			}
+
+			ValidTransaction::with_tag_prefix("MessagingPrune")
+				.priority(T::PruneUnsignedPriority::get())
+				// Pruning transactions starting from the same cursors are duplicates
+				.and_provides((PruneCursor::<T>::get(), GroupPruneCursor::<T>::get()))
+				.longevity(T::PruneInterval::get().unique_saturated_into())
+				.propagate(true)
+				.build()
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register a user profile with a public key.
+		///
+		/// The caller must have sufficient balance for the spam bond.
+		/// The public key will be stored on-chain for others to use for encryption.
+		///
+		/// Parameters:
+		/// - `public_key`: The user's public key (max 256 bytes)
+		///
+		/// Emits `ProfileRegistered` event on success.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::register_profile(public_key.len() as u32))]
+		pub fn register_profile(
+			origin: OriginFor<T>,
+			public_key: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_register_profile(who, public_key, None)
+		}
+
+		/// Update an existing user profile with a new public key.
+		///
+		/// Fails with `KeyUnchanged` if the key is the one already registered, which would
+		/// otherwise revoke it in favour of itself.
+		///
+		/// Parameters:
+		/// - `public_key`: The new public key (max 256 bytes)
+		///
+		/// Emits `ProfileUpdated` event on success.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::update_profile(public_key.len() as u32))]
+		pub fn update_profile(
+			origin: OriginFor<T>,
+			public_key: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			Self::ensure_not_banned(&who)?;
+
+			// Check if profile exists
+			let current = UserProfiles::<T>::get(&who).ok_or(Error::<T>::ProfileNotFound)?;
+
+			// Validate public key
+			ensure!(!public_key.is_empty(), Error::<T>::InvalidPublicKey);
+			ensure!(public_key.len() <= 256, Error::<T>::PublicKeyTooLarge);
+			ensure!(public_key != *current.public_key, Error::<T>::KeyUnchanged);
+
+			// Convert to BoundedVec
+			let key_hash = Self::key_hash(&public_key);
+			let bounded_key: BoundedVec<u8, ConstU32<256>> =
+				public_key.try_into().map_err(|_| Error::<T>::PublicKeyTooLarge)?;
+
+			// Update profile, revoking the previous key
+			let version = Self::rotate_key(&who, bounded_key);
+			Self::note_activity(&who);
+
+			// Emit event
+			Self::deposit_event(Event::ProfileUpdated { who, key_hash, version });
+
+			Ok(())
+		}
+
+		/// Send a message by recording its hash on-chain.
+		///
+		/// The actual encrypted message should be transmitted off-chain.
+		/// Only the hash is stored for verification purposes.
+		///
+		/// Parameters:
+		/// - `recipient`: The account ID of the message recipient
+		/// - `message_hash`: The hash of the encrypted message, which must not be shared with
+		///   another live message
+		///
+		/// Fails with `CannotMessageSelf` if `recipient` is the sender, `ReservedRecipient`
+		/// if it is one of the pallet's own accounts, `ContactNotApproved` if the recipient
+		/// has neither approved the sender nor granted it an inbox pass, and
+		/// `InvalidMessageHash` if `message_hash` is the default hash. Other send paths apply
+		/// the same checks, except that `send_paid_message_hash` needs no approval.
+		///
+		/// The message protocol fee is withdrawn from the sender before the message is
+		/// recorded, unless the recipient is a mutual contact and the parameters exempt
+		/// contacts. Every other send path charges it the same way.
+		///
+		/// Emits `MessageSent` event on success, preceded by `ProtocolFeeCharged` when a fee
+		/// is charged.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::send_message_hash().saturating_add(
+			T::WeightInfo::refund_deposits_on_reply(T::MaxAutoRefundsPerReply::get())
+		))]
+		pub fn send_message_hash(
+			origin: OriginFor<T>,
+			recipient: T::AccountId,
+			message_hash: T::Hash,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+			let (_, refunded) = Self::do_send_message(
+				sender,
+				recipient,
+				message_hash,
+				false,
+				false,
+				T::MaxAutoRefundsPerReply::get(),
+			)
+			.map_err(Error::<T>::from)?;
+
+			Ok(Some(
+				T::WeightInfo::send_message_hash()
+					.saturating_add(T::WeightInfo::refund_deposits_on_reply(refunded)),
+			)
+			.into())
+		}
+
+		/// Approve a contact for messaging.
+		///
+		/// Both parties must approve each other to establish a bidirectional contact.
+		/// Approving an account that is already approved fails with `AlreadyApproved`.
+		///
+		/// Parameters:
+		/// - `contact`: The account ID to approve
+		///
+		/// Emits `ContactApproved` event on success.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::approve_contact())]
+		pub fn approve_contact(
+			origin: OriginFor<T>,
+			contact: T::AccountId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_approve_contact(who, contact)
+		}
+
+		/// Remove a contact from approved list.
+		///
+		/// This is a unilateral action that doesn't require the other party's consent. Also
+		/// rejects a pending contact request from the account. Fails with `ContactNotFound`
+		/// if there is neither an approval nor a request to remove.
+		///
+		/// Deliberately not pausable: pausing `CONTACTS` only stops new approvals, so users
+		/// can still cut off an abusive contact while the pallet is paused during an incident.
+		///
+		/// Parameters:
+		/// - `contact`: The account ID to remove
+		///
+		/// Emits `ContactRemoved` event on success.
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::WeightInfo::remove_contact())]
+		pub fn remove_contact(
+			origin: OriginFor<T>,
+			contact: T::AccountId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let approved = ApprovedContacts::<T>::get(&who, &contact);
+			let requested = IncomingContactRequests::<T>::get(&who)
+				.iter()
+				.any(|(requester, _)| *requester == contact);
+			ensure!(approved || requested, Error::<T>::ContactNotFound);
+
+			// Remove from approved contacts
+			if approved {
+				ApprovedContacts::<T>::remove(&who, &contact);
+				ContactCount::<T>::mutate(&who, |count| *count = count.saturating_sub(1));
+				Self::accumulate_contact(&who, &contact, false);
+			}
+
+			// Withdraw a request to the contact, or reject one from it
+			Self::unindex_contact_request(&who, &contact);
+			Self::unindex_contact_request(&contact, &who);
+			Self::note_activity(&who);
+
+			// Emit event
+			Self::deposit_event(match Self::parameters().event_privacy {
+				EventPrivacyMode::Full => Event::ContactRemoved { remover: who, contact },
+				EventPrivacyMode::Minimal =>
+					Event::ContactRemovedMinimal { pair: Self::contact_pair_hash(&who, &contact) },
+			});
+
+			Ok(())
+		}
+
+		/// Challenge a message as spam.
+		///
+		/// Opens a challenge against the message's sender which is later resolved by
+		/// `ResolutionOrigin`. The challenger must hold `ChallengeBond` until the verdict is
+		/// final: it is returned with a reward if the challenge is upheld and paid to the
+		/// sender otherwise.
+		///
+		/// The decrypted message is not submitted here. The challenger commits to it and
+		/// reveals it with `reveal_evidence` only if the sender contests the challenge.
+		///
+		/// Only live messages can be challenged, by someone other than their sender, up to
+		/// `ChallengeWindow` blocks after they were sent. Acknowledged messages are settled
+		/// and cannot be challenged.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the message to challenge
+		/// - `evidence_commitment`: `Pallet::evidence_commitment` of the evidence and a salt
+		///
+		/// Emits `SpamChallenged` event on success.
+		#[pallet::call_index(5)]
+		#[pallet::weight(T::WeightInfo::challenge_spam())]
+		pub fn challenge_spam(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			evidence_commitment: T::Hash,
+		) -> DispatchResult {
+			let challenger = ensure_signed(origin)?;
+			Self::ensure_not_banned(&challenger)?;
+
+			// Verify the message is still open to challenge
+			let MessageRecord { sender, sent_at, .. } = Self::live_message(message_id)?;
+			ensure!(challenger != sender, Error::<T>::CannotChallengeOwnMessage);
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <=
+					sent_at.saturating_add(T::ChallengeWindow::get()),
+				Error::<T>::ChallengeWindowClosed
+			);
+			ensure!(
+				!AcknowledgedMessages::<T>::contains_key(message_id),
+				Error::<T>::AlreadyAcknowledged
+			);
+
+			// Only one open challenge per message
+			ensure!(!Challenges::<T>::contains_key(message_id), Error::<T>::AlreadyChallenged);
+
+			// Hold challenge bond
+			let bond = T::ChallengeBond::get();
+			T::Currency::hold(&HoldReason::ChallengeBond.into(), &challenger, bond)
+				.map_err(|_| Error::<T>::InsufficientChallengeBond)?;
+
+			// Record the open challenge
+			Self::open_challenge(message_id, Some(challenger.clone()), sender, bond);
+			ChallengeEvidence::<T>::insert(
+				message_id,
+				EvidenceRecord { commitment: evidence_commitment, reveal_by: None, evidence: None },
+			);
+
+			// Emit event
+			Self::deposit_event(Event::SpamChallenged { message_id, challenger });
+
+			Ok(())
+		}
+
+		/// Claim back the spam bond, which is returned to whoever it is held from.
+		///
+		/// Fails while spam challenges against the caller are open or while the bond is
+		/// still securing messages sent within the last `BondUnbondingPeriod` blocks.
+		///
+		/// Emits `BondRefunded` event on success.
+		#[pallet::call_index(6)]
+		#[pallet::weight(T::WeightInfo::refund_bond())]
+		pub fn refund_bond(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			// Check if bond exists
+			let bond = SpamBonds::<T>::get(&who);
+			let bond_amount = bond.amount;
+			ensure!(bond_amount > BalanceOf::<T>::default(), Error::<T>::BondAlreadyRefunded);
+
+			// Bond must not be needed for pending challenges or recent messages
+			ensure!(
+				OpenChallengeCount::<T>::get(&who).is_zero(),
+				Error::<T>::OpenChallengesPending
+			);
+			ensure!(Self::locked_bond(&who).is_zero(), Error::<T>::BondLocked);
+
+			// Release the held bond
+			T::Currency::release(
+				&HoldReason::SpamBond.into(),
+				bond.payer(&who),
+				bond_amount,
+				Precision::BestEffort,
+			)?;
+
+			// Remove bond record
+			SpamBonds::<T>::remove(&who);
+			BondLocks::<T>::remove(&who);
+
+			// Emit event
+			Self::deposit_event(Event::BondRefunded { who, amount: bond_amount });
+
+			Ok(())
+		}
+
+		/// Resolve an open spam challenge.
+		///
+		/// An upheld challenge records a strike against the message sender and slashes
+		/// `ChallengeSlashPercent` of their spam bond into the pallet escrow account. Strikes
+		/// escalate: the sender's rate limit halves after `RateLimitPenaltyStrikes`, the required
+		/// bond doubles after `BondPenaltyStrikes`, and the account is graylisted at `MaxStrikes`.
+		/// Reaching `AutoSlashThreshold` additionally slashes the challenge's auto-slash
+		/// percentage of the remaining bond into escrow and graylists the account.
+		///
+		/// The losing party may appeal the verdict for `AppealWindow` blocks; bonds and slashed
+		/// funds are only settled once the verdict is final.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the challenged message
+		/// - `upheld`: Whether the message was found to be spam
+		///
+		/// Emits `ChallengeResolved` event on success.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::resolve_challenge())]
+		pub fn resolve_challenge(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			upheld: bool,
+		) -> DispatchResult {
+			T::ResolutionOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ResolveChallenge, &message_id);
+
+			// Check the challenge awaits a verdict
+			let challenge =
+				Challenges::<T>::get(message_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			Self::do_resolve_challenge(message_id, challenge, upheld)
+		}
+
+		/// Add funds to the caller's spam bond.
+		///
+		/// A sponsored bond has to be taken over with `assume_own_bond` first.
+		///
+		/// Parameters:
+		/// - `amount`: Amount to hold in addition to the current bond (at least `MinBondIncrement`)
+		///
+		/// Emits `BondIncreased` event on success.
+		#[pallet::call_index(8)]
+		#[pallet::weight(T::WeightInfo::top_up_bond())]
+		pub fn top_up_bond(origin: OriginFor<T>, amount: BalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+
+			// Check if profile exists
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			// Validate amount
+			ensure!(amount >= T::MinBondIncrement::get(), Error::<T>::BondIncrementTooSmall);
+
+			// Bonds are held from a single account
+			let mut bond = SpamBonds::<T>::get(&who);
+			ensure!(bond.sponsor.is_none(), Error::<T>::BondSponsored);
+
+			// Hold the additional funds
+			T::Currency::hold(&HoldReason::SpamBond.into(), &who, amount)
+				.map_err(|_| Error::<T>::InsufficientBond)?;
+
+			// Update bond record
+			bond.amount = bond.amount.saturating_add(amount);
+			let total = bond.amount;
+			SpamBonds::<T>::insert(&who, bond);
+
+			// Emit event
+			Self::deposit_event(Event::BondIncreased { who, amount, total });
+
+			Ok(())
+		}
+
+		/// Withdraw the part of the spam bond above the currently required amount, returning it
+		/// to whoever the bond is held from.
+		///
+		/// The portion that secured messages sent within the last `BondUnbondingPeriod`
+		/// blocks stays held, and nothing can be withdrawn while challenges are open.
+		///
+		/// Emits `BondDecreased` event on success.
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::withdraw_excess_bond())]
+		pub fn withdraw_excess_bond(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			// Bond must not be needed for pending challenges
+			ensure!(
+				OpenChallengeCount::<T>::get(&who).is_zero(),
+				Error::<T>::OpenChallengesPending
+			);
+
+			// Determine the amount above the strike-adjusted requirement
+			let mut bond = SpamBonds::<T>::get(&who);
+			let required =
+				Self::required_bond_for(&who, Self::refresh_reputation(&who).strikes);
+			ensure!(bond.amount > required, Error::<T>::NoExcessBond);
+
+			// Keep the portion securing recent messages
+			let amount = bond.amount.saturating_sub(required.max(Self::locked_bond(&who)));
+			ensure!(!amount.is_zero(), Error::<T>::BondLocked);
+
+			// Release the excess
+			let reason: T::RuntimeHoldReason = HoldReason::SpamBond.into();
+			T::Currency::release(&reason, bond.payer(&who), amount, Precision::Exact)?;
+
+			// Update bond record
+			bond.amount = bond.amount.saturating_sub(amount);
+			let total = bond.amount;
+			SpamBonds::<T>::insert(&who, bond);
+
+			// Emit event
+			Self::deposit_event(Event::BondDecreased { who, amount, total });
+
+			Ok(())
+		}
+
+		/// Appeal the verdict of a resolved spam challenge.
+		///
+		/// Only the losing party may appeal: the sender of an upheld challenge or the
+		/// challenger of a dismissed one. The appellant must hold `AppealBond`, which is
+		/// returned if the verdict is overturned and paid to the other party otherwise.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the challenged message
+		///
+		/// Emits `ChallengeAppealed` event on success.
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::appeal_challenge())]
+		pub fn appeal_challenge(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			let appellant = ensure_signed(origin)?;
+
+			// Check the challenge is resolved and still appealable
+			let mut challenge =
+				Challenges::<T>::get(message_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			let ChallengeStatus::Resolved { upheld, at } = challenge.status else {
+				return Err(Error::<T>::ChallengeNotResolved.into());
+			};
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= at.saturating_add(T::AppealWindow::get()),
+				Error::<T>::AppealWindowClosed
+			);
+
+			// Only the losing party may appeal
+			let loser = if upheld { Some(&challenge.sender) } else { challenge.challenger.as_ref() };
+			ensure!(loser == Some(&appellant), Error::<T>::NotAuthorized);
+
+			// Hold appeal bond
+			let bond = T::AppealBond::get();
+			T::Currency::hold(&HoldReason::AppealBond.into(), &appellant, bond)
+				.map_err(|_| Error::<T>::InsufficientAppealBond)?;
+
+			// Reopen the case for `AppealOrigin`
+			challenge.status =
+				ChallengeStatus::Appealed { upheld, appellant: appellant.clone(), bond };
+			Challenges::<T>::insert(message_id, challenge);
+
+			// Emit event
+			Self::deposit_event(Event::ChallengeAppealed { message_id, appellant });
+
+			Ok(())
+		}
+
+		/// Give the final verdict on an appealed spam challenge.
+		///
+		/// Overturning an upheld verdict revokes the sender's strike and returns the escrowed
+		/// slash to their bond; overturning a dismissal records the strike and slashes the
+		/// sender. All bonds are then settled as for `finalize_challenge`.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the challenged message
+		/// - `upheld`: Whether the message was found to be spam
+		///
+		/// Emits `AppealResolved` and `ChallengeFinalized` events on success.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::resolve_appeal())]
+		pub fn resolve_appeal(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			upheld: bool,
+		) -> DispatchResult {
+			T::AppealOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ResolveAppeal, &message_id);
+
+			// Check the challenge was appealed
+			let mut challenge =
+				Challenges::<T>::get(message_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			let ChallengeStatus::Appealed { upheld: original, appellant, bond } =
+				challenge.status.clone()
+			else {
+				return Err(Error::<T>::AppealNotFound.into());
+			};
+
+			// Return the appeal bond if the verdict is overturned, otherwise pay it to the winner
+			if upheld != original {
+				T::Currency::release(
+					&HoldReason::AppealBond.into(),
+					&appellant,
+					bond,
+					Precision::BestEffort,
+				)?;
+			} else {
+				let winner = if appellant == challenge.sender {
+					challenge.challenger.as_ref()
+				} else {
+					Some(&challenge.sender)
+				};
+				match winner {
+					Some(winner) => {
+						T::Currency::transfer_on_hold(
+							&HoldReason::AppealBond.into(),
+							&appellant,
+							winner,
+							bond,
+							Precision::BestEffort,
+							Restriction::Free,
+							Fortitude::Force,
+						)?;
+					},
+					// System challenges have no challenger to pay
+					None => {
+						let (credit, _) =
+							T::Currency::slash(&HoldReason::AppealBond.into(), &appellant, bond);
+						Self::route_income(IncomeCategory::ForfeitedDeposit, credit);
+					},
+				}
+			}
+
+			// Apply or reverse the sender's penalty
+			match (original, upheld) {
+				(true, false) => {
+					Self::revoke_strike(&challenge.sender);
+					Self::return_from_escrow(&challenge.sender, challenge.escrowed)?;
+					challenge.escrowed = Zero::zero();
+				},
+				(false, true) => {
+					challenge.escrowed = Self::penalize(&challenge.sender, &challenge.terms)?;
+				},
+				_ => {},
+			}
+
+			// Emit event
+			Self::deposit_event(Event::AppealResolved { message_id, upheld });
+
+			// The appeal verdict is final
+			Self::settle_challenge(message_id, challenge, upheld)
+		}
+
+		/// Settle a resolved spam challenge whose appeal window has closed.
+		///
+		/// If the challenge was upheld the challenger's bond is returned together with
+		/// the challenger's share of the escrowed slash and the rest of the slash goes to the
+		/// slash destination, all as set when the challenge was opened. The challenger is also
+		/// paid the base challenge reward from the challenge reward pool, or what the pool can
+		/// cover of it.
+		/// If it was dismissed the challenger's bond is paid to the sender.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the challenged message
+		///
+		/// Emits `ChallengeFinalized` event, and `ChallengeRewardPaid` if the pool paid a reward,
+		/// on success.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::finalize_challenge())]
+		pub fn finalize_challenge(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			// Check the appeal window has closed
+			let challenge =
+				Challenges::<T>::get(message_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			let ChallengeStatus::Resolved { upheld, at } = challenge.status else {
+				return Err(Error::<T>::ChallengeNotResolved.into());
+			};
+			ensure!(
+				frame_system::Pallet::<T>::block_number() > at.saturating_add(T::AppealWindow::get()),
+				Error::<T>::AppealWindowOpen
+			);
+
+			Self::settle_challenge(message_id, challenge, upheld)
+		}
+
+		/// Report a received message as spam.
+		///
+		/// Reports need no bond but are rate-limited to `MaxReportsPerWindow` per reporter.
+		/// They count against the sender with decay, and once a sender's outstanding reports
+		/// reach `AutoChallengeThreshold` a system challenge is opened against the reported
+		/// message and resolved through the normal flow.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of a message addressed to the caller
+		///
+		/// Emits `SpamReported` event on success, and `AutoChallengeOpened` when a challenge
+		/// is opened.
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::report_spam())]
+		pub fn report_spam(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			let reporter = ensure_signed(origin)?;
+			Self::ensure_not_banned(&reporter)?;
+
+			// Verify message exists
+			let MessageRecord { sender, recipient, .. } =
+				MessageHashes::<T>::get(message_id).ok_or(Error::<T>::MessageNotFound)?;
+
+			// Only the recipient may report, once per message
+			ensure!(recipient == reporter, Error::<T>::NotAuthorized);
+			ensure!(
+				!ReportedMessages::<T>::get(message_id, &reporter),
+				Error::<T>::AlreadyReported
+			);
+
+			// Enforce the report rate limit
+			let current_block = frame_system::Pallet::<T>::block_number();
+			ReportRateLimits::<T>::try_mutate(&reporter, |state| {
+				Self::consume_window(state, current_block, T::MaxReportsPerWindow::get())
+			})?;
+			Self::count_spam_report(message_id, reporter, sender);
+
+			Ok(())
+		}
+
+		/// Close a challenge left unresolved for longer than `ChallengeMaxDuration`.
+		///
+		/// The challenger's bond is refunded in full. Expired challenges count as neither
+		/// upheld nor dismissed, so the sender's reputation is unaffected.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the challenged message
+		///
+		/// Emits `ChallengeExpired` event on success.
+		#[pallet::call_index(14)]
+		#[pallet::weight(T::WeightInfo::expire_challenge())]
+		pub fn expire_challenge(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			// Check the challenge is still awaiting a verdict past its maximum duration
+			let challenge =
+				Challenges::<T>::get(message_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			ensure!(challenge.status == ChallengeStatus::Open, Error::<T>::ChallengeNotOpen);
+			ensure!(
+				frame_system::Pallet::<T>::block_number() >
+					challenge.opened_at.saturating_add(T::ChallengeMaxDuration::get()),
+				Error::<T>::ChallengeNotExpired
+			);
+
+			// Refund the challenger
+			if let Some(challenger) = &challenge.challenger {
+				T::Currency::release(
+					&HoldReason::ChallengeBond.into(),
+					challenger,
+					challenge.bond,
+					Precision::BestEffort,
+				)?;
+			}
+
+			// Close the challenge
+			Self::close_challenge(message_id, &challenge.sender);
+
+			// Emit event
+			Self::deposit_event(Event::ChallengeExpired { message_id });
+
+			Ok(())
+		}
+
+		/// Set or clear the price senders must pay to message the caller without approval.
+		///
+		/// Parameters:
+		/// - `price`: The asking price, or `None` to stop accepting paid messages
+		///
+		/// Emits `InboxPriceSet` event on success.
+		#[pallet::call_index(15)]
+		#[pallet::weight(T::WeightInfo::set_inbox_price())]
+		pub fn set_inbox_price(
+			origin: OriginFor<T>,
+			price: Option<BalanceOf<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			Self::ensure_not_banned(&who)?;
+
+			// Check if profile exists
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			// Store the price
+			InboxPrices::<T>::set(&who, price);
+
+			// Emit event
+			Self::deposit_event(Event::InboxPriceSet { who, price });
+
+			Ok(())
+		}
+
+		/// Send a message to a recipient that has not approved the sender by paying the
+		/// recipient's inbox price.
+		///
+		/// The price is held from the sender until the recipient acknowledges the message,
+		/// the sender retracts it, or it expires. Senders approved by the recipient, or
+		/// holding an active inbox pass, send without paying. Otherwise the tier of the
+		/// recipient's [`InboxPricing`] matching the sender applies, or its flat inbox price
+		/// if it has no tiered pricing. The message protocol fee is charged on top, as for
+		/// `send_message_hash`.
+		///
+		/// Parameters:
+		/// - `recipient`: The account ID of the message recipient
+		/// - `message_hash`: The hash of the encrypted message
+		///
+		/// Emits `MessageSent` event, and `InboxPaymentHeld` if a payment was taken, preceded
+		/// by `ProtocolFeeCharged` when a fee is charged.
+		#[pallet::call_index(16)]
+		#[pallet::weight(T::WeightInfo::send_paid_message_hash().saturating_add(
+			T::WeightInfo::refund_deposits_on_reply(T::MaxAutoRefundsPerReply::get())
+		))]
+		pub fn send_paid_message_hash(
+			origin: OriginFor<T>,
+			recipient: T::AccountId,
+			message_hash: T::Hash,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+
+			// Resolve the price for the sender's class
+			let (tier, price) = Self::inbox_price_for(&recipient, &sender)?;
+
+			// Record the message
+			let (message_id, refunded) = Self::do_send_message(
+				sender.clone(),
+				recipient.clone(),
+				message_hash,
+				false,
+				true,
+				T::MaxAutoRefundsPerReply::get(),
+			)
+			.map_err(Error::<T>::from)?;
+			let actual_weight = T::WeightInfo::send_paid_message_hash()
+				.saturating_add(T::WeightInfo::refund_deposits_on_reply(refunded));
+			if price.is_zero() {
+				return Ok(Some(actual_weight).into());
+			}
+
+			// Hold the payment until the recipient acknowledges the message
+			T::Currency::hold(&HoldReason::InboxPayment.into(), &sender, price)
+				.map_err(|_| Error::<T>::InsufficientInboxPayment)?;
+			InboxPayments::<T>::insert(
+				message_id,
+				InboxPayment { sender: sender.clone(), recipient: recipient.clone(), amount: price },
+			);
+			Self::track_unacknowledged(&sender, &recipient, true);
+
+			// Emit event
+			Self::deposit_event(Event::InboxPaymentHeld {
+				message_id,
+				sender,
+				recipient,
+				amount: price,
+				tier,
+			});
+
+			Ok(Some(actual_weight).into())
+		}
+
+		/// Acknowledge a paid message, releasing its payment to the caller.
+		///
+		/// Only the recipient may acknowledge, and only before the message expires.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the paid message
+		/// - `grant_pass`: Whether to let the sender message the caller without paying for
+		///   `InboxPassDuration` blocks
+		///
+		/// Emits `MessageAcknowledged` event, and `InboxPassGranted` if a pass was granted.
+		#[pallet::call_index(17)]
+		#[pallet::weight(T::WeightInfo::acknowledge_message())]
+		pub fn acknowledge_message(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			grant_pass: bool,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			// Check the caller is owed an outstanding payment
+			let payment =
+				InboxPayments::<T>::get(message_id).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(who == payment.recipient, Error::<T>::NotAuthorized);
+			let message = Self::live_message(message_id)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			let latency = now.saturating_sub(message.sent_at);
+
+			// Pay the recipient
+			T::Currency::transfer_on_hold(
+				&HoldReason::InboxPayment.into(),
+				&payment.sender,
+				&who,
+				payment.amount,
+				Precision::Exact,
+				Restriction::Free,
+				Fortitude::Polite,
+			)?;
+			InboxPayments::<T>::remove(message_id);
+			AcknowledgedMessages::<T>::insert(message_id, now);
+			Self::track_unacknowledged(&payment.sender, &who, false);
+			ConversationStats::<T>::mutate(Self::conversation_id(&payment.sender, &who), |stats| {
+				stats.last_activity = now
+			});
+			Self::touch_inbox(&who);
+			offchain_index::acknowledge::<T>(message_id);
+			AccountStats::<T>::mutate(&who, |stats| {
+				stats.acknowledged.saturating_inc();
+				stats.last_active = now;
+			});
+			Self::reactivate(&who);
+
+			// Emit event
+			Self::deposit_event(Event::MessageAcknowledged {
+				message_id,
+				recipient: who.clone(),
+				amount: payment.amount,
+				latency,
+			});
+
+			// Let the sender reach the recipient for free for a while
+			if grant_pass {
+				let until = now.saturating_add(T::InboxPassDuration::get());
+				InboxPasses::<T>::insert(&who, &payment.sender, until);
+				Self::deposit_event(Event::InboxPassGranted {
+					recipient: who,
+					sender: payment.sender,
+					until,
+				});
+			}
+
+			Ok(())
+		}
+
+		/// Retract a paid message before the recipient acknowledges it.
+		///
+		/// The payment is refunded and the message hash removed. Messages under challenge
+		/// cannot be retracted.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the paid message
+		///
+		/// Emits `PaidMessageRetracted` event on success.
+		#[pallet::call_index(18)]
+		#[pallet::weight(T::WeightInfo::retract_paid_message())]
+		pub fn retract_paid_message(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			// Check the caller paid for the message
+			let payment =
+				InboxPayments::<T>::get(message_id).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(who == payment.sender, Error::<T>::NotAuthorized);
+			ensure!(!Challenges::<T>::contains_key(message_id), Error::<T>::AlreadyChallenged);
+
+			// Refund the sender and withdraw the message
+			T::Currency::release(
+				&HoldReason::InboxPayment.into(),
+				&who,
+				payment.amount,
+				Precision::BestEffort,
+			)?;
+			InboxPayments::<T>::remove(message_id);
+			Self::track_unacknowledged(&who, &payment.recipient, false);
+			if let Some(message) = MessageHashes::<T>::take(message_id) {
+				Self::unindex_message_hash(message_id, &message.hash);
+				RetractedMessages::<T>::insert(message_id, message.sent_at);
+			}
+			Self::unindex_conversation_message(message_id, &who, &payment.recipient, false);
+			Self::touch_inbox(&payment.recipient);
+			offchain_index::clear::<T>(&payment.recipient, message_id);
+
+			// Emit event
+			Self::deposit_event(Event::PaidMessageRetracted {
+				message_id,
+				sender: who,
+				amount: payment.amount,
+			});
+
+			Ok(())
+		}
+
+		/// Refund the payment for a paid message that expired without being acknowledged, or
+		/// whose sender deregistered after sending it and is not challenged.
+		///
+		/// Can be called by anyone.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the expired message
+		///
+		/// Emits `InboxPaymentRefunded` event on success.
+		#[pallet::call_index(19)]
+		#[pallet::weight(T::WeightInfo::refund_inbox_payment())]
+		pub fn refund_inbox_payment(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			// Check the payment is still outstanding past the message's expiry
+			let payment =
+				InboxPayments::<T>::get(message_id).ok_or(Error::<T>::PaymentNotFound)?;
+			let refundable = match Self::message_state(message_id) {
+				MessageState::Active { .. } => Self::sender_departed(message_id),
+				MessageState::Challenged => false,
+				_ => true,
+			};
+			ensure!(refundable, Error::<T>::MessageNotExpired);
+
+			// Refund the sender
+			T::Currency::release(
+				&HoldReason::InboxPayment.into(),
+				&payment.sender,
+				payment.amount,
+				Precision::BestEffort,
+			)?;
+			InboxPayments::<T>::remove(message_id);
+			Self::track_unacknowledged(&payment.sender, &payment.recipient, false);
+			Self::touch_inbox(&payment.recipient);
+
+			// Emit event
+			Self::deposit_event(Event::InboxPaymentRefunded {
+				message_id,
+				sender: payment.sender,
+				amount: payment.amount,
+			});
+
+			Ok(())
+		}
+
+		/// Pause groups of user calls, e.g. during an incident.
+		///
+		/// Groups already paused stay paused.
+		///
+		/// Parameters:
+		/// - `calls`: The call groups to pause
+		///
+		/// Emits `PalletPaused` event on success.
+		#[pallet::call_index(20)]
+		#[pallet::weight(T::WeightInfo::pause())]
+		pub fn pause(origin: OriginFor<T>, calls: PausedCalls) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::Pause, &calls);
+
+			let paused = Paused::<T>::get().union(calls);
+			Paused::<T>::put(paused);
+
+			Self::deposit_event(Event::PalletPaused { calls, paused });
+
+			Ok(())
+		}
+
+		/// Resume groups of paused user calls.
+		///
+		/// Parameters:
+		/// - `calls`: The call groups to resume
+		///
+		/// Emits `PalletUnpaused` event on success.
+		#[pallet::call_index(21)]
+		#[pallet::weight(T::WeightInfo::unpause())]
+		pub fn unpause(origin: OriginFor<T>, calls: PausedCalls) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::Unpause, &calls);
+
+			let paused = Paused::<T>::get().difference(calls);
+			Paused::<T>::put(paused);
+
+			Self::deposit_event(Event::PalletUnpaused { calls, paused });
+
+			Ok(())
+		}
+
+		/// Take down a message hash, e.g. one referring to illegal content.
+		///
+		/// The hash and its report index are removed, but a `TakedownRecord` is kept for
+		/// `TakedownRetention` blocks so the removal stays auditable. Any inbox payment still
+		/// held for the message is refunded to the sender. The sender is not penalized;
+		/// challenges against the message are unaffected and run their course.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the message to remove
+		/// - `order_reference`: Reference to the order requiring the removal
+		///
+		/// Emits `MessageTakenDown` event on success.
+		#[pallet::call_index(22)]
+		#[pallet::weight(T::WeightInfo::force_remove_message())]
+		pub fn force_remove_message(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			order_reference: OrderReference,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ForceRemoveMessage, &message_id);
+
+			// Remove the message and its report and conversation indexes
+			let MessageRecord { sender, recipient, hash, .. } =
+				MessageHashes::<T>::take(message_id).ok_or(Error::<T>::MessageNotFound)?;
+			Self::unindex_message_hash(message_id, &hash);
+			ReportedMessages::<T>::remove(message_id, &recipient);
+			MessageVerifications::<T>::remove(message_id);
+			AcknowledgedMessages::<T>::remove(message_id);
+			Self::unindex_conversation_message(message_id, &sender, &recipient, false);
+			Self::touch_inbox(&recipient);
+			offchain_index::clear::<T>(&recipient, message_id);
+
+			// Refund any unacknowledged payment
+			if let Some(payment) = InboxPayments::<T>::take(message_id) {
+				T::Currency::release(
+					&HoldReason::InboxPayment.into(),
+					&payment.sender,
+					payment.amount,
+					Precision::BestEffort,
+				)?;
+				Self::track_unacknowledged(&payment.sender, &payment.recipient, false);
+			}
+
+			// Leave a tombstone
+			Takedowns::<T>::insert(
+				message_id,
+				TakedownRecord {
+					sender: sender.clone(),
+					recipient: recipient.clone(),
+					removed_at: frame_system::Pallet::<T>::block_number(),
+					order_reference: order_reference.clone(),
+				},
+			);
+
+			// Emit event
+			Self::deposit_event(Event::MessageTakenDown {
+				message_id,
+				sender,
+				recipient,
+				order_reference,
+			});
+
+			Ok(())
+		}
+
+		/// Update the pallet parameters.
+		///
+		/// A new spam bond applies to registrations made afterwards; existing accounts keep
+		/// the requirement they registered under. New slash parameters apply to challenges
+		/// opened afterwards; open challenges keep the ones they were opened under.
+		///
+		/// Parameters:
+		/// - `new`: The parameters to apply
+		///
+		/// Emits `ParametersUpdated` event on success.
+		#[pallet::call_index(23)]
+		#[pallet::weight(T::WeightInfo::update_parameters())]
+		pub fn update_parameters(
+			origin: OriginFor<T>,
+			new: PalletParametersOf<T>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::UpdateParameters, &new);
+
+			// Sanity check the new values
+			ensure!(!new.message_expiry.is_zero(), Error::<T>::ZeroMessageExpiry);
+			ensure!(
+				new.spam_bond >= T::Currency::minimum_balance(),
+				Error::<T>::SpamBondBelowMinimum
+			);
+			ensure!(
+				!new.challenger_reward_percent.is_zero() || !new.base_challenge_reward.is_zero(),
+				Error::<T>::ChallengerUnrewarded
+			);
+			ensure!(!new.auto_slash_percent.is_zero(), Error::<T>::ZeroAutoSlashPercent);
+			ensure!(
+				T::ChallengeSlashPercent::get().deconstruct() <=
+					Perbill::one().deconstruct() - new.auto_slash_percent.deconstruct(),
+				Error::<T>::SlashPercentagesTooHigh
+			);
+
+			// Store the parameters
+			let old = Self::parameters();
+			Parameters::<T>::put(new.clone());
+
+			// Emit event
+			Self::deposit_event(Event::ParametersUpdated { old, new });
+
+			Ok(())
+		}
+
+		/// Add an account to the moderator set.
+		///
+		/// Parameters:
+		/// - `who`: The account to add
+		///
+		/// Emits `ModeratorAdded` event on success.
+		#[pallet::call_index(24)]
+		#[pallet::weight(T::WeightInfo::add_moderator())]
+		pub fn add_moderator(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::AddModerator, &who);
+
+			Moderators::<T>::try_mutate(|moderators| {
+				ensure!(!moderators.contains(&who), Error::<T>::AlreadyModerator);
+				moderators.try_push(who.clone()).map_err(|_| Error::<T>::TooManyModerators)
+			})?;
+
+			Self::deposit_event(Event::ModeratorAdded { who });
+
+			Ok(())
+		}
+
+		/// Remove an account from the moderator set.
+		///
+		/// Freezes placed by the moderator stay in effect.
+		///
+		/// Parameters:
+		/// - `who`: The account to remove
+		///
+		/// Emits `ModeratorRemoved` event on success.
+		#[pallet::call_index(25)]
+		#[pallet::weight(T::WeightInfo::remove_moderator())]
+		pub fn remove_moderator(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::RemoveModerator, &who);
+
+			Moderators::<T>::try_mutate(|moderators| {
+				let index =
+					moderators.iter().position(|m| m == &who).ok_or(Error::<T>::NotModerator)?;
+				moderators.remove(index);
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::ModeratorRemoved { who });
+
+			Ok(())
+		}
+
+		/// Open a challenge against a message on behalf of the system.
+		///
+		/// Unlike `challenge_spam`, no challenger bond is held; the challenge is settled like
+		/// one opened from aggregated spam reports.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the message to challenge
+		///
+		/// Emits `ModeratorAction` event on success.
+		#[pallet::call_index(26)]
+		#[pallet::weight(T::WeightInfo::moderator_challenge())]
+		pub fn moderator_challenge(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			let who = EnsureModerator::<T>::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ModeratorChallenge, &message_id);
+
+			// Verify message exists
+			let MessageRecord { sender, .. } =
+				MessageHashes::<T>::get(message_id).ok_or(Error::<T>::MessageNotFound)?;
+
+			// Only one open challenge per message
+			ensure!(!Challenges::<T>::contains_key(message_id), Error::<T>::AlreadyChallenged);
+
+			// Record the open challenge
+			Self::open_challenge(message_id, None, sender.clone(), Zero::zero());
+
+			// Emit event
+			Self::deposit_event(Event::ModeratorAction {
+				who,
+				action: ModeratorActionKind::OpenChallenge { message_id },
+				target: sender,
+			});
+
+			Ok(())
+		}
+
+		/// Resolve a challenge that was opened without a challenger.
+		///
+		/// Covers challenges opened from aggregated spam reports or by a moderator. The
+		/// verdict can be appealed like one from `ResolutionOrigin`.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the challenged message
+		/// - `upheld`: Whether the message is spam
+		///
+		/// Emits `ChallengeResolved` and `ModeratorAction` events on success.
+		#[pallet::call_index(27)]
+		#[pallet::weight(T::WeightInfo::fast_track_challenge())]
+		pub fn fast_track_challenge(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			upheld: bool,
+		) -> DispatchResult {
+			let who = EnsureModerator::<T>::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::FastTrackChallenge, &message_id);
+
+			// User challenges are left to `ResolutionOrigin`
+			let challenge =
+				Challenges::<T>::get(message_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			ensure!(challenge.challenger.is_none(), Error::<T>::NotSystemChallenge);
+
+			// Resolve the challenge
+			let sender = challenge.sender.clone();
+			Self::do_resolve_challenge(message_id, challenge, upheld)?;
+
+			// Emit event
+			Self::deposit_event(Event::ModeratorAction {
+				who,
+				action: ModeratorActionKind::FastTrackChallenge { message_id, upheld },
+				target: sender,
+			});
+
+			Ok(())
+		}
+
+		/// Block an account from sending messages pending review.
+		///
+		/// A new freeze replaces any existing one.
+		///
+		/// Parameters:
+		/// - `who`: The account to freeze
+		/// - `duration`: Number of blocks to freeze for, at most `MaxFreezeDuration`
+		///
+		/// Emits `ModeratorAction` event on success.
+		#[pallet::call_index(28)]
+		#[pallet::weight(T::WeightInfo::freeze_sender())]
+		pub fn freeze_sender(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			duration: BlockNumberFor<T>,
+		) -> DispatchResult {
+			let moderator = EnsureModerator::<T>::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::FreezeSender, &who);
+
+			// Validate duration
+			ensure!(
+				!duration.is_zero() && duration <= T::MaxFreezeDuration::get(),
+				Error::<T>::InvalidFreezeDuration
+			);
+
+			// Record the freeze
+			let until = frame_system::Pallet::<T>::block_number().saturating_add(duration);
+			FrozenSenders::<T>::insert(&who, until);
+
+			// Emit event
+			Self::deposit_event(Event::ModeratorAction {
+				who: moderator,
+				action: ModeratorActionKind::FreezeSender { until },
+				target: who,
+			});
+
+			Ok(())
+		}
+
+		/// Lift a freeze on an account's sends.
+		///
+		/// Parameters:
+		/// - `who`: The frozen account
+		///
+		/// Emits `ModeratorAction` event on success.
+		#[pallet::call_index(29)]
+		#[pallet::weight(T::WeightInfo::unfreeze_sender())]
+		pub fn unfreeze_sender(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			let moderator = EnsureModerator::<T>::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::UnfreezeSender, &who);
+
+			// Lift the freeze
+			ensure!(FrozenSenders::<T>::take(&who).is_some(), Error::<T>::SenderNotFrozen);
+
+			// Emit event
+			Self::deposit_event(Event::ModeratorAction {
+				who: moderator,
+				action: ModeratorActionKind::UnfreezeSender,
+				target: who,
+			});
+
+			Ok(())
+		}
+
+		/// Ban an account from registering, sending, approving contacts and receiving
+		/// messages.
+		///
+		/// The account's profile is removed and its whole spam bond slashed to the slash
+		/// destination.
+		/// Contacts and messages already recorded are left in place.
+		///
+		/// Parameters:
+		/// - `who`: The account to ban
+		/// - `reason_hash`: Hash of the off-chain reason for the ban
+		///
+		/// Emits `AccountBanned` event on success.
+		#[pallet::call_index(30)]
+		#[pallet::weight(T::WeightInfo::ban_account())]
+		pub fn ban_account(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			reason_hash: T::Hash,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::BanAccount, &who);
+			Self::ensure_not_banned(&who)?;
+
+			// Remove the profile
+			Self::remove_profile(&who);
+			BondLocks::<T>::remove(&who);
+
+			// Slash the whole bond, from the sponsor of a sponsored account
+			let bond = SpamBonds::<T>::take(&who);
+			let reason: T::RuntimeHoldReason = HoldReason::SpamBond.into();
+			let (credit, _) = T::Currency::slash(&reason, bond.payer(&who), bond.amount);
+			let slashed = credit.peek();
+			Self::route_slash(Self::parameters().slash_destination, credit);
+
+			// Record the ban
+			BannedAccounts::<T>::insert(
+				&who,
+				BanRecord { at: frame_system::Pallet::<T>::block_number(), reason: reason_hash },
+			);
+
+			// Emit event
+			Self::deposit_event(Event::AccountBanned { who, reason_hash, slashed });
+
+			Ok(())
+		}
+
+		/// Lift a ban.
+		///
+		/// State removed by the ban is not restored; the account has to register again.
+		///
+		/// Parameters:
+		/// - `who`: The banned account
+		///
+		/// Emits `AccountUnbanned` event on success.
+		#[pallet::call_index(31)]
+		#[pallet::weight(T::WeightInfo::unban_account())]
+		pub fn unban_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::UnbanAccount, &who);
+
+			ensure!(BannedAccounts::<T>::take(&who).is_some(), Error::<T>::AccountNotBanned);
+
+			Self::deposit_event(Event::AccountUnbanned { who });
+
+			Ok(())
+		}
+
+		/// Reset an income counter once the treasury has reconciled it.
+		///
+		/// Only the counter is reset; funds already routed stay with their handler.
+		///
+		/// Parameters:
+		/// - `category`: The income category to reset
+		///
+		/// Emits `IncomeReset` event with the amount cleared.
+		#[pallet::call_index(32)]
+		#[pallet::weight(T::WeightInfo::reset_income())]
+		pub fn reset_income(origin: OriginFor<T>, category: IncomeCategory) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ResetIncome, &category);
+
+			let amount = PalletIncome::<T>::take(category);
+
+			Self::deposit_event(Event::IncomeReset { category, amount });
+
+			Ok(())
+		}
+
+		/// Replace the key of an account that proved out-of-band that its key is compromised.
+		///
+		/// The old key is revoked into `KeyHistory` and the key version bumped. Every mutual
+		/// contact is notified with a `ContactKeyReset` event; at most
+		/// `MaxKeyResetNotifications` relationships are scanned here and the rest are left to
+		/// `continue_key_reset_notices`.
+		///
+		/// Parameters:
+		/// - `who`: The account whose key is reset
+		/// - `new_public_key`: The replacement public key (max 256 bytes)
+		/// - `algorithm`: The algorithm of the replacement key
+		///
+		/// Emits `KeyReset` event on success.
+		#[pallet::call_index(33)]
+		#[pallet::weight(T::WeightInfo::force_reset_key(T::MaxKeyResetNotifications::get()))]
+		pub fn force_reset_key(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			new_public_key: Vec<u8>,
+			algorithm: KeyAlgorithm,
+		) -> DispatchResultWithPostInfo {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ForceResetKey, &who);
+
+			// Check if profile exists
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			// Validate public key
+			ensure!(!new_public_key.is_empty(), Error::<T>::InvalidPublicKey);
+			let bounded_key: BoundedVec<u8, ConstU32<256>> =
+				new_public_key.try_into().map_err(|_| Error::<T>::PublicKeyTooLarge)?;
+
+			// Replace the key
+			let version = Self::rotate_key(&who, bounded_key);
+			KeyAlgorithms::<T>::insert(&who, algorithm);
+
+			// Emit event
+			Self::deposit_event(Event::KeyReset { who: who.clone(), version, algorithm });
+
+			// Notify contacts, restarting any notification round still in progress
+			KeyResetCursors::<T>::insert(&who, BoundedVec::default());
+			let scanned = Self::notify_key_reset(&who);
+
+			Ok(Some(T::WeightInfo::force_reset_key(scanned)).into())
+		}
+
+		/// Continue notifying the contacts of an account whose key was reset.
+		///
+		/// Callable by anyone while notifications are pending.
+		///
+		/// Parameters:
+		/// - `who`: The account whose key was reset
+		///
+		/// Emits `KeyResetNotificationsCompleted` once every contact has been notified.
+		#[pallet::call_index(34)]
+		#[pallet::weight(T::WeightInfo::continue_key_reset_notices(
+			T::MaxKeyResetNotifications::get()
+		))]
+		pub fn continue_key_reset_notices(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+
+			ensure!(
+				KeyResetCursors::<T>::contains_key(&who),
+				Error::<T>::NoPendingKeyResetNotices
+			);
+			let scanned = Self::notify_key_reset(&who);
+
+			Ok(Some(T::WeightInfo::continue_key_reset_notices(scanned)).into())
+		}
+
+		/// Prune a takedown record whose retention period has passed.
+		///
+		/// Callable by anyone.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the removed message
+		///
+		/// Emits `TakedownPruned` event on success.
+		#[pallet::call_index(35)]
+		#[pallet::weight(T::WeightInfo::prune_takedown())]
+		pub fn prune_takedown(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			// Check the retention period has passed
+			let record = Takedowns::<T>::get(message_id).ok_or(Error::<T>::TakedownNotFound)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() >
+					record.removed_at.saturating_add(T::TakedownRetention::get()),
+				Error::<T>::TakedownRetained
+			);
+
+			// Remove the record
+			Takedowns::<T>::remove(message_id);
+
+			// Emit event
+			Self::deposit_event(Event::TakedownPruned { message_id });
+
+			Ok(())
+		}
+
+		/// Switch between open and whitelisted registration.
+		///
+		/// Profiles registered before the switch keep working.
+		///
+		/// Parameters:
+		/// - `mode`: The registration mode to apply
+		///
+		/// Emits `RegistrationModeSet` event on success.
+		#[pallet::call_index(36)]
+		#[pallet::weight(T::WeightInfo::set_registration_mode())]
+		pub fn set_registration_mode(
+			origin: OriginFor<T>,
+			mode: RegistrationMode,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::SetRegistrationMode, &mode);
+
+			CurrentRegistrationMode::<T>::put(mode);
+
+			Self::deposit_event(Event::RegistrationModeSet { mode });
+
+			Ok(())
+		}
+
+		/// Allow an account to register while registration is whitelisted.
+		///
+		/// Parameters:
+		/// - `who`: The account to whitelist
+		///
+		/// Emits `AccountWhitelisted` event on success.
+		#[pallet::call_index(37)]
+		#[pallet::weight(T::WeightInfo::whitelist_account())]
+		pub fn whitelist_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::WhitelistAccount, &who);
+
+			ensure!(
+				!RegistrationWhitelist::<T>::contains_key(&who),
+				Error::<T>::AlreadyWhitelisted
+			);
+			RegistrationWhitelist::<T>::insert(&who, ());
+
+			Self::deposit_event(Event::AccountWhitelisted { who });
+
+			Ok(())
+		}
+
+		/// Remove an account from the registration whitelist.
+		///
+		/// An existing profile of the account is unaffected.
+		///
+		/// Parameters:
+		/// - `who`: The account to remove
+		///
+		/// Emits `AccountRemovedFromWhitelist` event on success.
+		#[pallet::call_index(38)]
+		#[pallet::weight(T::WeightInfo::remove_from_whitelist())]
+		pub fn remove_from_whitelist(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::RemoveFromWhitelist, &who);
+
+			ensure!(
+				RegistrationWhitelist::<T>::take(&who).is_some(),
+				Error::<T>::NotWhitelisted
+			);
+
+			Self::deposit_event(Event::AccountRemovedFromWhitelist { who });
+
+			Ok(())
+		}
+
+		/// Register a profile on behalf of a vetted user, regardless of registration mode.
+		///
+		/// Without a sponsor the profile is registered without a bond, on an account that must
+		/// already hold the existential deposit. With a sponsor the current spam bond is
+		/// transferred from the sponsor and held on the new profile. The sponsor also tops the
+		/// account up to the existential deposit, so it needs no funds of its own.
+		///
+		/// Parameters:
+		/// - `who`: The account to register
+		/// - `public_key`: The user's public key (max 256 bytes)
+		/// - `algorithm`: The algorithm of the public key
+		/// - `sponsor`: Account funding the spam bond, if any
+		///
+		/// Emits `ProfileRegistered` event on success.
+		#[pallet::call_index(39)]
+		#[pallet::weight(T::WeightInfo::force_register_profile(public_key.len() as u32))]
+		pub fn force_register_profile(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			public_key: Vec<u8>,
+			algorithm: KeyAlgorithm,
+			sponsor: Option<T::AccountId>,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ForceRegisterProfile, &who);
+			Self::ensure_not_banned(&who)?;
+
+			// Check if profile already exists
+			ensure!(!UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileAlreadyExists);
+			ensure!(SpamBonds::<T>::get(&who).amount.is_zero(), Error::<T>::UnrefundedBond);
+
+			// Validate public key
+			ensure!(!public_key.is_empty(), Error::<T>::InvalidPublicKey);
+			let key_hash = Self::key_hash(&public_key);
+			let bounded_key: BoundedVec<u8, ConstU32<256>> =
+				public_key.try_into().map_err(|_| Error::<T>::PublicKeyTooLarge)?;
+
+			// Fund and hold the sponsored bond
+			let bond_amount = match sponsor {
+				Some(sponsor) => {
+					let bond_amount = Self::parameters().spam_bond;
+					// The sponsor also covers any existential deposit the account lacks
+					let shortfall = T::Currency::minimum_balance()
+						.saturating_sub(T::Currency::balance(&who));
+					T::Currency::transfer(
+						&sponsor,
+						&who,
+						bond_amount.saturating_add(shortfall),
+						Preservation::Preserve,
+					)
+					.map_err(|_| Error::<T>::InsufficientBond)?;
+					T::Currency::hold(&HoldReason::SpamBond.into(), &who, bond_amount)
+						.map_err(|_| Error::<T>::InsufficientBond)?;
+					bond_amount
+				},
+				None => Zero::zero(),
+			};
+			Self::inc_profile_ref(&who)?;
+			SpamBonds::<T>::insert(&who, BondRecord { amount: bond_amount, sponsor: None });
+			DepartedSenders::<T>::remove(&who);
+
+			// Store profile
+			UserProfiles::<T>::insert(
+				&who,
+				ProfileRecord {
+					public_key: bounded_key,
+					dormant_since: None,
+					registration_bond: Some(bond_amount),
+					session_required: false,
+				},
+			);
+			KeyAlgorithms::<T>::insert(&who, algorithm);
+			GlobalStats::<T>::mutate(|stats| stats.profiles.saturating_inc());
+			Self::note_activity(&who);
+
+			// Emit event
+			let version = KeyVersions::<T>::get(&who);
+			Self::deposit_event(Event::ProfileRegistered { who, key_hash, version });
+
+			Ok(())
+		}
+
+		/// Remove the hashes of direct, group and pending messages, and the ephemeral keys,
+		/// that had expired by `up_to_block`.
+		///
+		/// Submitted unsigned by offchain workers and only valid while such messages or keys
+		/// exist. Each kind is pruned in ID order, advancing over at most `MaxPrunesPerCall`
+		/// IDs in total.
+		/// Inbox payments and challenges of pruned messages are left to their own settlement
+		/// calls.
+		///
+		/// Parameters:
+		/// - `up_to_block`: Block by which the pruned messages must have expired
+		///
+		/// Emits `ExpiredMessagesPruned` event on success, preceded by
+		/// `ExpiredGroupMessagesPruned`, `ExpiredPendingMessagesPruned` and
+		/// `ExpiredEphemeralKeysPruned` if group messages, pending messages or ephemeral keys
+		/// were inspected. Each pruned message hash is reported in a `MessageExpired` event, or
+		/// in the `MessagesExpiredBatch` event that follows them once `MaxExpiryEventsPerBlock`
+		/// were emitted in the block.
+		#[pallet::call_index(40)]
+		#[pallet::weight((
+			T::WeightInfo::prune_expired_unsigned(T::MaxPrunesPerCall::get()),
+			Pays::No
+		))]
+		pub fn prune_expired_unsigned(
+			origin: OriginFor<T>,
+			up_to_block: BlockNumberFor<T>,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+
+			ensure!(
+				up_to_block <= frame_system::Pallet::<T>::block_number() &&
+					Self::has_overdue_messages(up_to_block),
+				Error::<T>::NothingToPrune
+			);
+			// Bounded by `MaxPrunesPerCall` alone, which the call's weight already covers
+			let advanced = Self::prune_overdue(
+				up_to_block,
+				T::MaxPrunesPerCall::get(),
+				&mut WeightMeter::new(),
+			);
+
+			Ok(PostDispatchInfo {
+				actual_weight: Some(T::WeightInfo::prune_expired_unsigned(advanced)),
+				pays_fee: Pays::No,
+			})
+		}
+
+		/// Look up the profile of `who` on another chain.
+		///
+		/// Sends an XCM `Transact` of `respond_profile` to `dest`, whose answer arrives as
+		/// `receive_profile` and is cached in `RemoteProfiles`. Lookups not answered within
+		/// `ProfileQueryTimeout` blocks can be expired with `expire_profile_query`.
+		///
+		/// Parameters:
+		/// - `dest`: The chain holding the profile
+		/// - `who`: The account whose profile to fetch
+		///
+		/// Emits `RemoteProfileRequested` event on success.
+		#[pallet::call_index(41)]
+		#[pallet::weight(T::WeightInfo::request_remote_profile())]
+		pub fn request_remote_profile(
+			origin: OriginFor<T>,
+			dest: Box<VersionedLocation>,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let requester = ensure_signed(origin)?;
+			Self::ensure_not_banned(&requester)?;
+			let dest: Location = (*dest).try_into().map_err(|()| Error::<T>::BadVersion)?;
+
+			let query_id = NextProfileQueryId::<T>::get();
+			NextProfileQueryId::<T>::put(query_id.saturating_add(1));
+			let expires_at = frame_system::Pallet::<T>::block_number()
+				.saturating_add(T::ProfileQueryTimeout::get());
+			ProfileQueries::<T>::insert(
+				query_id,
+				ProfileQuery { dest: dest.clone(), who: who.clone(), expires_at },
+			);
+
+			Self::send_transact(
+				dest.clone(),
+				Call::respond_profile {
+					query_id,
+					who: who.clone(),
+					version: REMOTE_PROFILE_VERSION,
+				},
+			)?;
+
+			// Emit event
+			Self::deposit_event(Event::RemoteProfileRequested { query_id, dest, who });
+
+			Ok(())
+		}
+
+		/// Answer a profile lookup from another chain.
+		///
+		/// Dispatched over XCM by `request_remote_profile`. Replies with the profile of `who`,
+		/// or with `None` if it has none, in the newest version both chains understand.
+		///
+		/// Parameters:
+		/// - `query_id`: The requester's ID of the lookup
+		/// - `who`: The account whose profile was requested
+		/// - `version`: Newest profile version the requester understands
+		///
+		/// Emits `ProfileLookupAnswered` event on success.
+		#[pallet::call_index(42)]
+		#[pallet::weight(T::WeightInfo::respond_profile())]
+		pub fn respond_profile(
+			origin: OriginFor<T>,
+			query_id: ProfileQueryId,
+			who: T::AccountId,
+			version: u32,
+		) -> DispatchResult {
+			let requester = T::XcmOrigin::ensure_origin(origin)?;
+
+			let profile = UserProfiles::<T>::get(&who).map(|profile| RemoteProfileV1 {
+				public_key: profile.public_key,
+				algorithm: KeyAlgorithms::<T>::get(&who),
+				key_version: KeyVersions::<T>::get(&who),
+			});
+			let found = profile.is_some();
+			let profile = match version.min(REMOTE_PROFILE_VERSION) {
+				1 => VersionedRemoteProfile::V1(profile),
+				_ => return Err(Error::<T>::UnsupportedProfileVersion.into()),
+			};
+
+			Self::send_transact(requester.clone(), Call::receive_profile { query_id, profile })?;
+
+			// Emit event
+			Self::deposit_event(Event::ProfileLookupAnswered { query_id, requester, who, found });
+
+			Ok(())
+		}
+
+		/// Record the answer to a remote profile lookup.
+		///
+		/// Dispatched over XCM by `respond_profile` on the chain the lookup was sent to. A
+		/// missing profile also evicts any stale copy from `RemoteProfiles`.
+		///
+		/// Parameters:
+		/// - `query_id`: The ID of the lookup
+		/// - `profile`: The profile returned by the other chain
+		///
+		/// Emits `RemoteProfileFetched` or `RemoteProfileNotFound` event on success.
+		#[pallet::call_index(43)]
+		#[pallet::weight(T::WeightInfo::receive_profile())]
+		pub fn receive_profile(
+			origin: OriginFor<T>,
+			query_id: ProfileQueryId,
+			profile: VersionedRemoteProfile,
+		) -> DispatchResult {
+			let responder = T::XcmOrigin::ensure_origin(origin)?;
+
+			let query = ProfileQueries::<T>::get(query_id).ok_or(Error::<T>::UnknownProfileQuery)?;
+			ensure!(query.dest == responder, Error::<T>::UnexpectedResponder);
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(now <= query.expires_at, Error::<T>::ProfileQueryExpired);
+			ProfileQueries::<T>::remove(query_id);
+
+			let ProfileQuery { dest, who, .. } = query;
+			let VersionedRemoteProfile::V1(profile) = profile;
+			if let Some(profile) = profile {
+				RemoteProfiles::<T>::insert(&dest, &who, (profile, now));
+				Self::deposit_event(Event::RemoteProfileFetched { query_id, dest, who });
+			} else {
+				RemoteProfiles::<T>::remove(&dest, &who);
+				Self::deposit_event(Event::RemoteProfileNotFound { query_id, dest, who });
+			}
+
+			Ok(())
+		}
+
+		/// Drop a remote profile lookup that was not answered in time.
+		///
+		/// Can be called by anyone once `ProfileQueryTimeout` blocks have passed.
+		///
+		/// Parameters:
+		/// - `query_id`: The ID of the lookup
+		///
+		/// Emits `ProfileQueryTimedOut` event on success.
+		#[pallet::call_index(44)]
+		#[pallet::weight(T::WeightInfo::expire_profile_query())]
+		pub fn expire_profile_query(origin: OriginFor<T>, query_id: ProfileQueryId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			let query = ProfileQueries::<T>::get(query_id).ok_or(Error::<T>::UnknownProfileQuery)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() > query.expires_at,
+				Error::<T>::ProfileQueryNotExpired
+			);
+			ProfileQueries::<T>::remove(query_id);
+
+			// Emit event
+			Self::deposit_event(Event::ProfileQueryTimedOut {
+				query_id,
+				dest: query.dest,
+				who: query.who,
+			});
+
+			Ok(())
+		}
+
+		/// Accept a contact request and send the requester a first message in one go.
+		///
+		/// `requester` must already have approved the caller. Approves it back like
+		/// `approve_contact`, then records the message like `send_message_hash`. Nothing is
+		/// stored if either step fails.
+		///
+		/// Parameters:
+		/// - `requester`: The account whose contact request is accepted
+		/// - `message_hash`: The hash of the encrypted message
+		///
+		/// Emits `ContactApproved` and `MessageSent` events on success, with
+		/// `ProtocolFeeCharged` in between when a fee is charged.
+		#[pallet::call_index(45)]
+		#[pallet::weight(T::WeightInfo::accept_and_send().saturating_add(
+			T::WeightInfo::refund_deposits_on_reply(T::MaxAutoRefundsPerReply::get())
+		))]
+		pub fn accept_and_send(
+			origin: OriginFor<T>,
+			requester: T::AccountId,
+			message_hash: T::Hash,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::CONTACTS.union(PausedCalls::SENDS))?;
+
+			// Only pending requests can be accepted
+			ensure!(who != requester, Error::<T>::CannotAddSelf);
+			ensure!(
+				ApprovedContacts::<T>::get(&requester, &who),
+				Error::<T>::NoContactRequest
+			);
+
+			let refunded = frame_support::storage::with_storage_layer(|| {
+				Self::do_approve_contact(who.clone(), requester.clone())?;
+				let max_refunds = T::MaxAutoRefundsPerReply::get();
+				Self::do_send_message(who, requester, message_hash, false, false, max_refunds)
+					.map(|(_, refunded)| refunded)
+					.map_err(|error| DispatchError::from(Error::<T>::from(error)))
+			})?;
+
+			Ok(Some(
+				T::WeightInfo::accept_and_send()
+					.saturating_add(T::WeightInfo::refund_deposits_on_reply(refunded)),
+			)
+			.into())
+		}
+
+		/// Register a profile and approve a first contact in one go.
+		///
+		/// Registers like `register_profile`, records the key algorithm, then approves
+		/// `first_contact` like `approve_contact`. Nothing is stored if either step fails.
+		///
+		/// Parameters:
+		/// - `public_key`: The user's public key (max 256 bytes)
+		/// - `algorithm`: The algorithm of the public key
+		/// - `first_contact`: The account ID to approve
+		///
+		/// Emits `ProfileRegistered` and `ContactApproved` events on success.
+		#[pallet::call_index(46)]
+		#[pallet::weight(T::WeightInfo::register_and_approve(public_key.len() as u32))]
+		pub fn register_and_approve(
+			origin: OriginFor<T>,
+			public_key: Vec<u8>,
+			algorithm: KeyAlgorithm,
+			first_contact: T::AccountId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS.union(PausedCalls::CONTACTS))?;
+
+			// Check the contact before any bond is held
+			ensure!(who != first_contact, Error::<T>::CannotAddSelf);
+			ensure!(
+				UserProfiles::<T>::contains_key(&first_contact),
+				Error::<T>::RecipientNotFound
+			);
+
+			frame_support::storage::with_storage_layer(|| {
+				Self::do_register_profile(who.clone(), public_key, None)?;
+				KeyAlgorithms::<T>::insert(&who, algorithm);
+				Self::do_approve_contact(who, first_contact)
+			})
+		}
+
+		/// Create a group chat owned by the caller.
+		///
+		/// The caller must have a profile and sufficient balance for the group deposit, which
+		/// stays on hold for as long as the group exists. The caller becomes the first member,
+		/// so the deposit starts at `GroupBaseDeposit` plus one `GroupPerMemberDeposit`.
+		///
+		/// Parameters:
+		/// - `group_key`: Commitment to the encrypted group key shared by the members
+		/// - `max_members_hint`: Number of members the group is expected to grow to, at most
+		///   `MaxGroupMembers`
+		///
+		/// Emits `GroupCreated` event on success.
+		#[pallet::call_index(47)]
+		#[pallet::weight(T::WeightInfo::create_group())]
+		pub fn create_group(
+			origin: OriginFor<T>,
+			group_key: GroupKey,
+			max_members_hint: u32,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			Self::ensure_not_banned(&owner)?;
+
+			ensure!(UserProfiles::<T>::contains_key(&owner), Error::<T>::ProfileNotFound);
+			ensure!(!group_key.is_empty(), Error::<T>::InvalidGroupKey);
+			ensure!(
+				max_members_hint > 0 && max_members_hint <= T::MaxGroupMembers::get(),
+				Error::<T>::InvalidGroupSize
+			);
+			ensure!(
+				GroupCount::<T>::get(&owner) < T::MaxGroupsPerAccount::get(),
+				Error::<T>::TooManyGroups
+			);
+
+			// Hold group deposit
+			let deposit = T::GroupBaseDeposit::get().saturating_add(T::GroupPerMemberDeposit::get());
+			T::Currency::hold(&HoldReason::GroupDeposit.into(), &owner, deposit)
+				.map_err(|_| Error::<T>::InsufficientGroupDeposit)?;
+
+			let group_id = NextGroupId::<T>::get();
+			NextGroupId::<T>::put(group_id.saturating_add(1));
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let key_hash = Self::key_hash(&group_key);
+			Groups::<T>::insert(
+				group_id,
+				GroupInfo {
+					owner: owner.clone(),
+					status: GroupStatus::Active,
+					invite_only: false,
+					key: group_key,
+					key_version: 0,
+					rotation_required: false,
+					created_at: now,
+					member_count: 1,
+					admin_count: 0,
+					max_members: max_members_hint,
+					deposit,
+					next_seq: 0,
+				},
+			);
+			GroupMembers::<T>::insert(
+				group_id,
+				&owner,
+				MemberInfo { joined_at: now, role: GroupRole::Owner },
+			);
+			GroupCount::<T>::mutate(&owner, |count| count.saturating_inc());
+			GlobalStats::<T>::mutate(|stats| stats.groups.saturating_inc());
+
+			Self::deposit_event(Event::GroupCreated { group_id, owner, key_hash, deposit });
+
+			Ok(())
+		}
+
+		/// Add an account to a group.
+		///
+		/// Only the group owner and admins can add members, and only to groups that are not
+		/// invite-only. The new member must have a profile, and joins as an ordinary member.
+		/// A pending invite of the new member is removed. The owner must cover another
+		/// `GroupPerMemberDeposit`.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to add the account to
+		/// - `who`: The account to add
+		///
+		/// Emits `MemberAdded` event on success.
+		#[pallet::call_index(48)]
+		#[pallet::weight(T::WeightInfo::add_group_member())]
+		pub fn add_group_member(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+
+			let mut group = Self::ensure_group_admin(group_id, &caller)?;
+			ensure!(!group.invite_only, Error::<T>::GroupInviteRequired);
+
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::RecipientNotFound);
+			ensure!(
+				!GroupMembers::<T>::contains_key(group_id, &who),
+				Error::<T>::AlreadyGroupMember
+			);
+			Self::join_group(group_id, &mut group, &who)?;
+			Groups::<T>::insert(group_id, group);
+
+			Self::deposit_event(Event::MemberAdded { group_id, who });
+
+			Ok(())
+		}
+
+		/// Remove an account from a group.
+		///
+		/// The group owner can remove any other member, admins can remove ordinary members, and
+		/// members can remove themselves. The owner cannot be removed.
+		///
+		/// The group key must be rotated with `rotate_group_key` before the next group message,
+		/// so the removed member cannot read it. Removed accounts can be added again unless
+		/// banned with `ban_from_group`.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to remove the account from
+		/// - `who`: The member to remove
+		///
+		/// Emits `MemberRemoved` event on success.
+		#[pallet::call_index(49)]
+		#[pallet::weight(T::WeightInfo::remove_group_member())]
+		pub fn remove_group_member(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+
+			let mut group = if caller == who {
+				let group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+				ensure!(group.status != GroupStatus::Disbanding, Error::<T>::GroupDisbanding);
+				group
+			} else {
+				Self::ensure_group_admin(group_id, &caller)?
+			};
+			ensure!(who != group.owner, Error::<T>::CannotRemoveGroupOwner);
+			let member =
+				GroupMembers::<T>::get(group_id, &who).ok_or(Error::<T>::NotGroupMember)?;
+
+			// Only the owner can remove admins, and admins moderators
+			if caller != who {
+				Self::ensure_outranks(group_id, &caller, member.role)?;
+			}
+
+			Self::remove_member(group_id, &mut group, &who, member.role)?;
+			Groups::<T>::insert(group_id, group);
+
+			Self::deposit_event(Event::MemberRemoved { group_id, who, removed_by: caller });
+
+			Ok(())
+		}
+
+		/// Make an ordinary group member or a moderator an admin.
+		///
+		/// Only the group owner can promote members, up to `MaxGroupAdmins` admins.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The member to promote
+		///
+		/// Emits `MemberPromoted` event on success.
+		#[pallet::call_index(50)]
+		#[pallet::weight(T::WeightInfo::promote_member())]
+		pub fn promote_member(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let mut group = Self::ensure_group_owner(group_id, &caller)?;
+
+			let mut member =
+				GroupMembers::<T>::get(group_id, &who).ok_or(Error::<T>::NotGroupMember)?;
+			ensure!(
+				matches!(member.role, GroupRole::Member | GroupRole::Moderator),
+				Error::<T>::AlreadyGroupAdmin
+			);
+			ensure!(
+				group.admin_count < T::MaxGroupAdmins::get(),
+				Error::<T>::TooManyGroupAdmins
+			);
+
+			member.role = GroupRole::Admin;
+			GroupMembers::<T>::insert(group_id, &who, member);
+			group.admin_count.saturating_inc();
+			Groups::<T>::insert(group_id, group);
+
+			Self::deposit_event(Event::MemberPromoted { group_id, who });
+
+			Ok(())
+		}
+
+		/// Make a group admin an ordinary member.
+		///
+		/// Only the group owner can demote admins. A pending ownership offer to the admin is
+		/// withdrawn.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The admin to demote
+		///
+		/// Emits `MemberDemoted` event on success.
+		#[pallet::call_index(51)]
+		#[pallet::weight(T::WeightInfo::demote_member())]
+		pub fn demote_member(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let mut group = Self::ensure_group_owner(group_id, &caller)?;
+
+			let mut member =
+				GroupMembers::<T>::get(group_id, &who).ok_or(Error::<T>::NotGroupMember)?;
+			ensure!(member.role == GroupRole::Admin, Error::<T>::MemberNotAdmin);
+
+			member.role = GroupRole::Member;
+			GroupMembers::<T>::insert(group_id, &who, member);
+			group.admin_count.saturating_dec();
+			Groups::<T>::insert(group_id, group);
+			Self::withdraw_ownership_offer(group_id, &who);
+
+			Self::deposit_event(Event::MemberDemoted { group_id, who });
+
+			Ok(())
+		}
+
+		/// Offer ownership of a group to one of its admins.
+		///
+		/// Ownership only changes once the admin calls `accept_group_ownership`. A new offer
+		/// replaces any earlier one.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `new_owner`: The admin to hand the group to
+		///
+		/// Emits `GroupOwnershipOffered` event on success.
+		#[pallet::call_index(52)]
+		#[pallet::weight(T::WeightInfo::transfer_group_ownership())]
+		pub fn transfer_group_ownership(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			new_owner: T::AccountId,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			Self::ensure_group_owner(group_id, &owner)?;
+
+			let member =
+				GroupMembers::<T>::get(group_id, &new_owner).ok_or(Error::<T>::NotGroupMember)?;
+			ensure!(member.role == GroupRole::Admin, Error::<T>::MemberNotAdmin);
+
+			PendingGroupOwners::<T>::insert(group_id, &new_owner);
+
+			Self::deposit_event(Event::GroupOwnershipOffered { group_id, owner, new_owner });
+
+			Ok(())
+		}
+
+		/// Accept ownership of a group offered to the caller.
+		///
+		/// The group deposit moves from the previous owner to the caller, who must be able to
+		/// cover it. The previous owner stays in the group as an admin.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		///
+		/// Emits `GroupOwnershipTransferred` event on success.
+		#[pallet::call_index(53)]
+		#[pallet::weight(T::WeightInfo::accept_group_ownership())]
+		pub fn accept_group_ownership(origin: OriginFor<T>, group_id: GroupId) -> DispatchResult {
+			let new_owner = ensure_signed(origin)?;
+			let mut group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			ensure!(
+				PendingGroupOwners::<T>::get(group_id).as_ref() == Some(&new_owner),
+				Error::<T>::NoPendingOwnershipTransfer
+			);
+			PendingGroupOwners::<T>::remove(group_id);
+
+			// Move the group deposit
+			T::Currency::hold(&HoldReason::GroupDeposit.into(), &new_owner, group.deposit)
+				.map_err(|_| Error::<T>::InsufficientGroupDeposit)?;
+			T::Currency::release(
+				&HoldReason::GroupDeposit.into(),
+				&group.owner,
+				group.deposit,
+				Precision::Exact,
+			)?;
+
+			// Swap roles, which leaves the number of admins unchanged
+			GroupMembers::<T>::mutate(group_id, &new_owner, |member| {
+				if let Some(member) = member {
+					member.role = GroupRole::Owner;
+				}
+			});
+			GroupMembers::<T>::mutate(group_id, &group.owner, |member| {
+				if let Some(member) = member {
+					member.role = GroupRole::Admin;
+				}
+			});
+			let old_owner = core::mem::replace(&mut group.owner, new_owner.clone());
+			Groups::<T>::insert(group_id, group);
+
+			Self::deposit_event(Event::GroupOwnershipTransferred {
+				group_id,
+				old_owner,
+				new_owner,
+			});
+
+			Ok(())
+		}
+
+		/// Replace the key commitment of a group.
+		///
+		/// Callable by the group owner and admins. The previous commitment is kept in
+		/// `GroupKeyHistory`. `key_version` must be the current version plus one, so that
+		/// concurrent rotations by different admins cannot overwrite each other.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `new_group_key`: Commitment to the new encrypted group key
+		/// - `key_version`: Version of the new key
+		///
+		/// Emits `GroupKeyRotated` event on success.
+		#[pallet::call_index(54)]
+		#[pallet::weight(T::WeightInfo::rotate_group_key())]
+		pub fn rotate_group_key(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			new_group_key: GroupKey,
+			key_version: u32,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let mut group = Self::ensure_group_admin(group_id, &caller)?;
+
+			ensure!(!new_group_key.is_empty(), Error::<T>::InvalidGroupKey);
+			ensure!(
+				key_version == group.key_version.saturating_add(1),
+				Error::<T>::InvalidGroupKeyVersion
+			);
+
+			let key_hash = Self::key_hash(&new_group_key);
+			let old_key = core::mem::replace(&mut group.key, new_group_key);
+			GroupKeyHistory::<T>::mutate(group_id, |history| {
+				if history.is_full() {
+					history.remove(0);
+				}
+				let _ = history.try_push(RevokedKey {
+					public_key: old_key,
+					version: group.key_version,
+					revoked_at: frame_system::Pallet::<T>::block_number(),
+				});
+			});
+			group.key_version = key_version;
+			group.rotation_required = false;
+			Groups::<T>::insert(group_id, group);
+
+			Self::deposit_event(Event::GroupKeyRotated { group_id, version: key_version, key_hash });
+
+			Ok(())
+		}
+
+		/// Send a message to a group by recording its hash on-chain.
+		///
+		/// Any member allowed by the group posting policy can send, unless muted. The hash is
+		/// stored once for the whole group, under the current group key version, and expires
+		/// after the group retention. All members together can send at most
+		/// `MaxGroupMessagesPerWindow` messages per `RateLimitWindow`.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to send to
+		/// - `message_hash`: The hash of the message encrypted under the group key
+		///
+		/// Emits `GroupMessageSent` event on success.
+		#[pallet::call_index(55)]
+		#[pallet::weight(T::WeightInfo::send_group_message_hash())]
+		pub fn send_group_message_hash(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			message_hash: T::Hash,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::SENDS)?;
+			Self::ensure_valid_hash(&message_hash)?;
+			Self::ensure_not_banned(&sender)?;
+			ensure!(!Self::is_frozen(&sender), Error::<T>::SenderFrozen);
+
+			let mut group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			Self::ensure_group_active(&group)?;
+			let member =
+				GroupMembers::<T>::get(group_id, &sender).ok_or(Error::<T>::NotGroupMember)?;
+			ensure!(
+				Self::posting_policy(group_id).allows(member.role),
+				Error::<T>::PostingRestricted
+			);
+
+			// Expired mutes are removed on the next send
+			let now = frame_system::Pallet::<T>::block_number();
+			if let Some(until) = GroupMutes::<T>::get(group_id, &sender) {
+				ensure!(now >= until, Error::<T>::MemberMuted);
+				GroupMutes::<T>::remove(group_id, &sender);
+			}
+
+			// A departed member still knows the current key
+			ensure!(!group.rotation_required, Error::<T>::GroupKeyRotationPending);
+
+			GroupRateLimits::<T>::try_mutate(group_id, |state| {
+				Self::consume_window(state, now, T::MaxGroupMessagesPerWindow::get())
+			})?;
+
+			let message_id = NextGroupMessageId::<T>::get();
+			NextGroupMessageId::<T>::put(message_id.saturating_add(1));
+			let seq = group.next_seq;
+			group.next_seq.saturating_inc();
+			let key_version = group.key_version;
+			Groups::<T>::insert(group_id, group);
+
+			GroupMessages::<T>::insert(
+				group_id,
+				message_id,
+				GroupMessageRecord {
+					hash: message_hash,
+					sender: sender.clone(),
+					sent_at: now,
+					key_version,
+					expires_at: now.saturating_add(Self::group_retention(group_id)),
+					seq,
+				},
+			);
+			GroupMessageIds::<T>::insert(message_id, group_id);
+			GroupMessagesBySeq::<T>::insert(group_id, seq, message_id);
+
+			Self::deposit_event(Event::GroupMessageSent {
+				group_id,
+				message_id,
+				sender,
+				hash: message_hash,
+				key_version,
+				seq,
+			});
+
+			Ok(())
+		}
+
+		/// Leave a group.
+		///
+		/// The group key must be rotated before the next group message, and the owner gets one
+		/// `GroupPerMemberDeposit` back. When the owner leaves, ownership and the group deposit
+		/// pass to the longest-standing admin that can cover the deposit. Without such an admin
+		/// the group is frozen, and the deposit stays on hold from the owner that left until it
+		/// disbands the group.
+		///
+		/// Members can also leave frozen groups.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to leave
+		///
+		/// Emits `MemberLeft` event on success, followed by `GroupOwnershipTransferred` or
+		/// `GroupFrozen` if the owner left.
+		#[pallet::call_index(56)]
+		#[pallet::weight(T::WeightInfo::leave_group(T::MaxGroupMembers::get()))]
+		pub fn leave_group(origin: OriginFor<T>, group_id: GroupId) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let mut group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			ensure!(group.status != GroupStatus::Disbanding, Error::<T>::GroupDisbanding);
+			let member =
+				GroupMembers::<T>::get(group_id, &who).ok_or(Error::<T>::NotGroupMember)?;
+
+			Self::drop_membership(group_id, &who);
+			group.member_count.saturating_dec();
+			group.rotation_required = true;
+			Self::release_member_deposit(&mut group)?;
+			Self::deposit_event(Event::MemberLeft { group_id, who: who.clone() });
+
+			let mut scanned = 0u32;
+			match member.role {
+				GroupRole::Member | GroupRole::Moderator => {},
+				GroupRole::Admin => {
+					group.admin_count.saturating_dec();
+					Self::withdraw_ownership_offer(group_id, &who);
+				},
+				GroupRole::Owner => {
+					PendingGroupOwners::<T>::remove(group_id);
+
+					let mut admins: Vec<_> = GroupMembers::<T>::iter_prefix(group_id)
+						.inspect(|_| scanned.saturating_inc())
+						.filter(|(_, member)| member.role == GroupRole::Admin)
+						.map(|(admin, member)| (member.joined_at, admin))
+						.collect();
+					admins.sort_by_key(|(joined_at, _)| *joined_at);
+					let successor = admins.into_iter().map(|(_, admin)| admin).find(|admin| {
+						T::Currency::hold(&HoldReason::GroupDeposit.into(), admin, group.deposit)
+							.is_ok()
+					});
+
+					match successor {
+						Some(new_owner) => {
+							T::Currency::release(
+								&HoldReason::GroupDeposit.into(),
+								&who,
+								group.deposit,
+								Precision::Exact,
+							)?;
+							GroupMembers::<T>::mutate(group_id, &new_owner, |member| {
+								if let Some(member) = member {
+									member.role = GroupRole::Owner;
+								}
+							});
+							group.admin_count.saturating_dec();
+							group.owner = new_owner.clone();
+							Self::deposit_event(Event::GroupOwnershipTransferred {
+								group_id,
+								old_owner: who,
+								new_owner,
+							});
+						},
+						None => {
+							group.status = GroupStatus::Frozen;
+							Self::deposit_event(Event::GroupFrozen { group_id });
+						},
+					}
+				},
+			}
+			Groups::<T>::insert(group_id, group);
+
+			Ok(Some(T::WeightInfo::leave_group(scanned)).into())
+		}
+
+		/// Shorten how long the hashes of messages sent to a group verify.
+		///
+		/// Only the group owner can change this, to at most `GroupMessageExpiry` blocks. A
+		/// shorter retention also applies to messages already sent, a longer one only to
+		/// messages sent from now on.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `blocks`: Number of blocks for which message hashes verify
+		///
+		/// Emits `GroupRetentionSet` event on success.
+		#[pallet::call_index(62)]
+		#[pallet::weight(T::WeightInfo::set_group_retention())]
+		pub fn set_group_retention(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			blocks: BlockNumberFor<T>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_group_owner(group_id, &caller)?;
+			ensure!(
+				!blocks.is_zero() && blocks <= T::GroupMessageExpiry::get(),
+				Error::<T>::InvalidGroupRetention
+			);
+
+			if blocks == T::GroupMessageExpiry::get() {
+				GroupRetentions::<T>::remove(group_id);
+			} else {
+				GroupRetentions::<T>::insert(group_id, blocks);
+			}
+
+			Self::deposit_event(Event::GroupRetentionSet { group_id, blocks });
+
+			Ok(())
+		}
+
+		/// Invite an account to a group.
+		///
+		/// Callable by the group owner and admins. The invitee must have a profile, and joins
+		/// by accepting the invite within `GroupInviteExpiry` blocks. An expired invite of the
+		/// same account is replaced.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to invite to
+		/// - `who`: The account to invite
+		///
+		/// Emits `GroupInviteSent` event on success.
+		#[pallet::call_index(57)]
+		#[pallet::weight(T::WeightInfo::invite_to_group())]
+		pub fn invite_to_group(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+			Self::ensure_group_admin(group_id, &caller)?;
+			ensure!(!GroupBans::<T>::contains_key(group_id, &who), Error::<T>::BannedFromGroup);
+
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::RecipientNotFound);
+			ensure!(
+				!GroupMembers::<T>::contains_key(group_id, &who),
+				Error::<T>::AlreadyGroupMember
+			);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			if let Some(invite) = GroupInvites::<T>::get(group_id, &who) {
+				ensure!(now > invite.expires_at, Error::<T>::AlreadyGroupInvited);
+				Self::remove_group_invite(group_id, &who);
+				Self::deposit_event(Event::GroupInviteExpired { group_id, who: who.clone() });
+			}
+			ensure!(
+				GroupInviteCount::<T>::get(group_id) < T::MaxGroupInvites::get(),
+				Error::<T>::TooManyGroupInvites
+			);
+			ensure!(
+				PendingGroupInviteCount::<T>::get(&who) < T::MaxPendingGroupInvites::get(),
+				Error::<T>::TooManyPendingGroupInvites
+			);
+
+			let expires_at = now.saturating_add(T::GroupInviteExpiry::get());
+			GroupInvites::<T>::insert(
+				group_id,
+				&who,
+				GroupInvite { invited_by: caller.clone(), expires_at },
+			);
+			GroupInviteCount::<T>::mutate(group_id, |count| count.saturating_inc());
+			PendingGroupInviteCount::<T>::mutate(&who, |count| count.saturating_inc());
+
+			Self::deposit_event(Event::GroupInviteSent {
+				group_id,
+				who,
+				invited_by: caller,
+				expires_at,
+			});
+
+			Ok(())
+		}
+
+		/// Accept a pending invite and join the group as an ordinary member.
+		///
+		/// Parameters:
+		/// - `group_id`: The group the caller was invited to
+		///
+		/// Emits `GroupInviteAccepted` and `MemberAdded` events on success.
+		#[pallet::call_index(58)]
+		#[pallet::weight(T::WeightInfo::accept_group_invite())]
+		pub fn accept_group_invite(origin: OriginFor<T>, group_id: GroupId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+
+			let invite =
+				GroupInvites::<T>::get(group_id, &who).ok_or(Error::<T>::GroupInviteNotFound)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= invite.expires_at,
+				Error::<T>::GroupInviteExpired
+			);
+			let mut group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			Self::ensure_group_active(&group)?;
+
+			Self::join_group(group_id, &mut group, &who)?;
+			Groups::<T>::insert(group_id, group);
+
+			Self::deposit_event(Event::GroupInviteAccepted { group_id, who: who.clone() });
+			Self::deposit_event(Event::MemberAdded { group_id, who });
+
+			Ok(())
+		}
+
+		/// Decline a pending invite, expired or not.
+		///
+		/// Parameters:
+		/// - `group_id`: The group the caller was invited to
+		///
+		/// Emits `GroupInviteDeclined` event on success.
+		#[pallet::call_index(59)]
+		#[pallet::weight(T::WeightInfo::decline_group_invite())]
+		pub fn decline_group_invite(origin: OriginFor<T>, group_id: GroupId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				Self::remove_group_invite(group_id, &who),
+				Error::<T>::GroupInviteNotFound
+			);
+
+			Self::deposit_event(Event::GroupInviteDeclined { group_id, who });
+
+			Ok(())
+		}
+
+		/// Remove an expired invite, freeing its slot in the limits of the group and the
+		/// invitee.
+		///
+		/// Callable by anyone.
+		///
+		/// Parameters:
+		/// - `group_id`: The group of the invite
+		/// - `who`: The invited account
+		///
+		/// Emits `GroupInviteExpired` event on success.
+		#[pallet::call_index(60)]
+		#[pallet::weight(T::WeightInfo::remove_expired_group_invite())]
+		pub fn remove_expired_group_invite(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			let invite =
+				GroupInvites::<T>::get(group_id, &who).ok_or(Error::<T>::GroupInviteNotFound)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() > invite.expires_at,
+				Error::<T>::GroupInviteNotExpired
+			);
+			Self::remove_group_invite(group_id, &who);
+
+			Self::deposit_event(Event::GroupInviteExpired { group_id, who });
+
+			Ok(())
+		}
+
+		/// Set whether accounts can only join a group by accepting an invite.
+		///
+		/// Only the group owner can change this. Pending invites are unaffected.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `invite_only`: Whether `add_group_member` is disabled for the group
+		///
+		/// Emits `GroupInviteOnlySet` event on success.
+		#[pallet::call_index(61)]
+		#[pallet::weight(T::WeightInfo::set_group_invite_only())]
+		pub fn set_group_invite_only(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			invite_only: bool,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let mut group = Self::ensure_group_owner(group_id, &caller)?;
+
+			group.invite_only = invite_only;
+			Groups::<T>::insert(group_id, group);
+
+			Self::deposit_event(Event::GroupInviteOnlySet { group_id, invite_only });
+
+			Ok(())
+		}
+
+		/// Keep a group member from sending group messages for a while.
+		///
+		/// Callable by the group owner, admins and moderators, on members they outrank: only
+		/// the owner can mute admins, and only the owner and admins moderators. The owner
+		/// cannot be muted. The member can send again from `duration_blocks` blocks after this
+		/// call. Muting a muted member replaces the mute. The mute is recorded in the
+		/// moderation log of the group.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The member to mute
+		/// - `duration_blocks`: Number of blocks the mute lasts
+		///
+		/// Emits `MemberMuted` event on success.
+		#[pallet::call_index(63)]
+		#[pallet::weight(T::WeightInfo::mute_member())]
+		pub fn mute_member(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+			duration_blocks: BlockNumberFor<T>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let group = Self::ensure_group_moderator(group_id, &caller)?;
+			Self::ensure_can_mute(group_id, &group, &caller, &who)?;
+			ensure!(!duration_blocks.is_zero(), Error::<T>::ZeroMuteDuration);
+
+			let until = frame_system::Pallet::<T>::block_number().saturating_add(duration_blocks);
+			GroupMutes::<T>::insert(group_id, &who, until);
+			Self::record_moderation(
+				group_id,
+				&caller,
+				GroupModerationAction::Mute { who: who.clone(), until },
+			);
+
+			Self::deposit_event(Event::MemberMuted { group_id, who, until, muted_by: caller });
+
+			Ok(())
+		}
+
+		/// Lift the mute of a group member.
+		///
+		/// Callable by the group owner, admins and moderators, on members they outrank. The
+		/// unmute is recorded in the moderation log of the group.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The muted member
+		///
+		/// Emits `MemberUnmuted` event on success.
+		#[pallet::call_index(64)]
+		#[pallet::weight(T::WeightInfo::unmute_member())]
+		pub fn unmute_member(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let group = Self::ensure_group_moderator(group_id, &caller)?;
+			Self::ensure_can_mute(group_id, &group, &caller, &who)?;
+			ensure!(Self::muted_until(group_id, &who).is_some(), Error::<T>::MemberNotMuted);
+
+			GroupMutes::<T>::remove(group_id, &who);
+			Self::record_moderation(
+				group_id,
+				&caller,
+				GroupModerationAction::Unmute { who: who.clone() },
+			);
+
+			Self::deposit_event(Event::MemberUnmuted { group_id, who, unmuted_by: caller });
+
+			Ok(())
+		}
+
+		/// Set which members of a group can send group messages.
+		///
+		/// Only the group owner can change this.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `policy`: The new posting policy
+		///
+		/// Emits `GroupPostingPolicySet` event on success.
+		#[pallet::call_index(65)]
+		#[pallet::weight(T::WeightInfo::set_posting_policy())]
+		pub fn set_posting_policy(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			policy: PostingPolicy,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_group_owner(group_id, &caller)?;
+
+			if policy == PostingPolicy::default() {
+				GroupPostingPolicies::<T>::remove(group_id);
+			} else {
+				GroupPostingPolicies::<T>::insert(group_id, policy);
+			}
+
+			Self::deposit_event(Event::GroupPostingPolicySet { group_id, policy });
+
+			Ok(())
+		}
+
+		/// Mute a group for the caller only.
+		///
+		/// Does not affect sending. Clients leave muted groups out of the caller's inbox and
+		/// unread counts. Members can also mute frozen groups.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to mute
+		///
+		/// Emits `GroupMuted` event on success.
+		#[pallet::call_index(66)]
+		#[pallet::weight(T::WeightInfo::mute_group())]
+		pub fn mute_group(origin: OriginFor<T>, group_id: GroupId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(GroupMembers::<T>::contains_key(group_id, &who), Error::<T>::NotGroupMember);
+			ensure!(!MutedGroups::<T>::contains_key(group_id, &who), Error::<T>::GroupAlreadyMuted);
+
+			MutedGroups::<T>::insert(group_id, &who, ());
+
+			Self::deposit_event(Event::GroupMuted { group_id, who });
+
+			Ok(())
+		}
+
+		/// Unmute a group the caller muted with `mute_group`.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to unmute
+		///
+		/// Emits `GroupUnmuted` event on success.
+		#[pallet::call_index(67)]
+		#[pallet::weight(T::WeightInfo::unmute_group())]
+		pub fn unmute_group(origin: OriginFor<T>, group_id: GroupId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(MutedGroups::<T>::take(group_id, &who).is_some(), Error::<T>::GroupNotMuted);
+
+			Self::deposit_event(Event::GroupUnmuted { group_id, who });
+
+			Ok(())
+		}
+
+		/// Ban an account from a group.
+		///
+		/// Callable by the group owner and admins, for members and non-members alike. A
+		/// banned member is removed as by `remove_group_member`, and any pending invite or
+		/// join request is dropped. Banned accounts cannot be invited or join until the ban is
+		/// lifted, even after re-registering their profile. Only the owner can ban admins, and
+		/// the owner cannot be banned. A group can ban at most `MaxGroupBans` accounts.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The account to ban
+		/// - `reason_hash`: Hash of the off-chain reason for the ban
+		///
+		/// Emits `BannedFromGroup` event on success, preceded by `MemberRemoved` if `who` was
+		/// a member.
+		#[pallet::call_index(68)]
+		#[pallet::weight(T::WeightInfo::ban_from_group())]
+		pub fn ban_from_group(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+			reason_hash: T::Hash,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let mut group = Self::ensure_group_admin(group_id, &caller)?;
+			ensure!(who != group.owner, Error::<T>::CannotBanGroupOwner);
+			ensure!(!GroupBans::<T>::contains_key(group_id, &who), Error::<T>::BannedFromGroup);
+			ensure!(
+				GroupBanCount::<T>::get(group_id) < T::MaxGroupBans::get(),
+				Error::<T>::TooManyGroupBans
+			);
+
+			if let Some(member) = GroupMembers::<T>::get(group_id, &who) {
+				// Only the owner can ban admins, and admins moderators
+				Self::ensure_outranks(group_id, &caller, member.role)?;
+				Self::remove_member(group_id, &mut group, &who, member.role)?;
+				Groups::<T>::insert(group_id, group);
+				Self::deposit_event(Event::MemberRemoved {
+					group_id,
+					who: who.clone(),
+					removed_by: caller.clone(),
+				});
+			}
+			Self::remove_group_invite(group_id, &who);
+			Self::remove_join_request(group_id, &who);
+
+			GroupBans::<T>::insert(
+				group_id,
+				&who,
+				BanInfo {
+					banned_by: caller.clone(),
+					reason: reason_hash,
+					at: frame_system::Pallet::<T>::block_number(),
+				},
+			);
+			GroupBanCount::<T>::mutate(group_id, |count| count.saturating_inc());
+
+			Self::deposit_event(Event::BannedFromGroup {
+				group_id,
+				who,
+				banned_by: caller,
+				reason_hash,
+			});
+
+			Ok(())
+		}
+
+		/// Lift the ban of an account from a group.
+		///
+		/// Callable by the group owner and admins. The account can be invited or added again
+		/// afterwards.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The banned account
+		///
+		/// Emits `GroupBanLifted` event on success.
+		#[pallet::call_index(69)]
+		#[pallet::weight(T::WeightInfo::lift_group_ban())]
+		pub fn lift_group_ban(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_group_admin(group_id, &caller)?;
+			ensure!(
+				GroupBans::<T>::take(group_id, &who).is_some(),
+				Error::<T>::NotBannedFromGroup
+			);
+			GroupBanCount::<T>::mutate(group_id, |count| count.saturating_dec());
+
+			Self::deposit_event(Event::GroupBanLifted { group_id, who, lifted_by: caller });
+
+			Ok(())
+		}
+
+		/// Disband a group, deleting everything stored for it.
+		///
+		/// Callable by the group owner, including the departed owner of a frozen group, or by
+		/// `ForceOrigin`. Each call removes up to `MaxDisbandRemovalsPerCall` group messages
+		/// and tombstones, and as many members, invites, join requests and bans. A group with
+		/// more left enters the `Disbanding` state, in which it accepts no new messages or
+		/// members, and further calls continue where the last one stopped. The call that
+		/// empties the group deletes it, releases the whole group deposit to the owner and
+		/// refunds any metadata deposit.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to disband
+		///
+		/// Emits `GroupDisbanded` event once the group is deleted, preceded by
+		/// `GroupMetadataCleared` if the group had metadata.
+		#[pallet::call_index(70)]
+		#[pallet::weight(T::WeightInfo::disband_group(
+			T::MaxDisbandRemovalsPerCall::get(),
+			T::MaxDisbandRemovalsPerCall::get(),
+		))]
+		pub fn disband_group(origin: OriginFor<T>, group_id: GroupId) -> DispatchResultWithPostInfo {
+			let caller = match T::ForceOrigin::try_origin(origin) {
+				Ok(_) => None,
+				Err(origin) => Some(ensure_signed(origin)?),
+			};
+			let mut group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			if let Some(caller) = caller {
+				ensure!(caller == group.owner, Error::<T>::NotGroupOwner);
+			}
+
+			if group.status != GroupStatus::Disbanding {
+				group.status = GroupStatus::Disbanding;
+				PendingGroupOwners::<T>::remove(group_id);
+			}
+
+			let limit = T::MaxDisbandRemovalsPerCall::get();
+			let messages = Self::remove_group_messages(group_id, limit);
+			let entries = Self::remove_group_entries(group_id, &mut group, limit);
+
+			// Only an exhausted budget can leave anything behind
+			let messages_left = messages == limit &&
+				(GroupMessages::<T>::iter_key_prefix(group_id).next().is_some() ||
+					GroupMessageTombstones::<T>::iter_key_prefix(group_id).next().is_some());
+			let entries_left = entries == limit && Self::has_group_entries(group_id);
+			if messages_left || entries_left {
+				Groups::<T>::insert(group_id, group);
+				return Ok(Some(T::WeightInfo::disband_group(entries, messages)).into());
+			}
+
+			T::Currency::release(
+				&HoldReason::GroupDeposit.into(),
+				&group.owner,
+				group.deposit,
+				Precision::Exact,
+			)?;
+			if let Ok(metadata) = Self::remove_group_metadata(group_id) {
+				Self::deposit_event(Event::GroupMetadataCleared {
+					group_id,
+					depositor: metadata.depositor,
+					refunded: metadata.deposit,
+				});
+			}
+			Groups::<T>::remove(group_id);
+			GroupKeyHistory::<T>::remove(group_id);
+			GroupRetentions::<T>::remove(group_id);
+			GroupPostingPolicies::<T>::remove(group_id);
+			GroupRateLimits::<T>::remove(group_id);
+			GroupInviteCount::<T>::remove(group_id);
+			GroupBanCount::<T>::remove(group_id);
+			GroupJoinRequestCount::<T>::remove(group_id);
+			GroupSeqFloors::<T>::remove(group_id);
+			GroupModerationLogs::<T>::remove(group_id);
+
+			Self::deposit_event(Event::GroupDisbanded {
+				group_id,
+				owner: group.owner,
+				deposit: group.deposit,
+			});
+
+			Ok(Some(T::WeightInfo::disband_group(entries, messages)).into())
+		}
+
+		/// Set the metadata of a group, replacing any previous metadata.
+		///
+		/// Only the group owner can set it. A deposit of `GroupMetadataDepositPerByte` for
+		/// each encoded byte of the metadata is held from the owner, and any deposit for the
+		/// previous metadata released. Discoverable groups are listed in `DiscoverableGroups`,
+		/// of which there can be at most `MaxDiscoverableGroups`, and accept join requests.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `name_hash`: Hash of the group name
+		/// - `description_cid`: Where the group description is stored off-chain
+		/// - `is_discoverable`: Whether to list the group for discovery
+		///
+		/// Emits `GroupMetadataSet` event on success.
+		#[pallet::call_index(71)]
+		#[pallet::weight(T::WeightInfo::set_group_metadata())]
+		pub fn set_group_metadata(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			name_hash: T::Hash,
+			description_cid: DescriptionCid,
+			is_discoverable: bool,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_group_owner(group_id, &caller)?;
+
+			let listed = DiscoverableGroups::<T>::contains_key(group_id);
+			if is_discoverable && !listed {
+				ensure!(
+					DiscoverableGroupCount::<T>::get() < T::MaxDiscoverableGroups::get(),
+					Error::<T>::TooManyDiscoverableGroups
+				);
+			}
+
+			if GroupsMetadata::<T>::contains_key(group_id) {
+				Self::remove_group_metadata(group_id)?;
+			}
+			let bytes = (&name_hash, &description_cid, is_discoverable).encoded_size() as u32;
+			let deposit = T::GroupMetadataDepositPerByte::get().saturating_mul(bytes.into());
+			T::Currency::hold(&HoldReason::GroupMetadataDeposit.into(), &caller, deposit)
+				.map_err(|_| Error::<T>::InsufficientMetadataDeposit)?;
+
+			if is_discoverable {
+				DiscoverableGroups::<T>::insert(group_id, ());
+				DiscoverableGroupCount::<T>::mutate(|count| count.saturating_inc());
+			}
+			GroupsMetadata::<T>::insert(
+				group_id,
+				GroupMetadata {
+					name_hash,
+					description_cid,
+					is_discoverable,
+					depositor: caller,
+					deposit,
+				},
+			);
+
+			Self::deposit_event(Event::GroupMetadataSet { group_id, is_discoverable, deposit });
+
+			Ok(())
+		}
+
+		/// Clear the metadata of a group, refunding its deposit and removing the group from
+		/// discovery.
+		///
+		/// Only the group owner can clear it. Pending join requests stay until approved,
+		/// cancelled or expired.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		///
+		/// Emits `GroupMetadataCleared` event on success.
+		#[pallet::call_index(72)]
+		#[pallet::weight(T::WeightInfo::clear_group_metadata())]
+		pub fn clear_group_metadata(origin: OriginFor<T>, group_id: GroupId) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_group_owner(group_id, &caller)?;
+
+			let metadata = Self::remove_group_metadata(group_id)?;
+
+			Self::deposit_event(Event::GroupMetadataCleared {
+				group_id,
+				depositor: metadata.depositor,
+				refunded: metadata.deposit,
+			});
+
+			Ok(())
+		}
+
+		/// Ask to join a discoverable group.
+		///
+		/// The request expires after `GroupInviteExpiry` blocks, and until then an owner or
+		/// admin can approve it with `approve_join_request`. A group can have at most
+		/// `MaxGroupJoinRequests` pending requests. An expired request of the caller is
+		/// replaced.
+		///
+		/// Parameters:
+		/// - `group_id`: The group to join
+		///
+		/// Emits `GroupJoinRequested` event on success.
+		#[pallet::call_index(73)]
+		#[pallet::weight(T::WeightInfo::request_to_join_group())]
+		pub fn request_to_join_group(origin: OriginFor<T>, group_id: GroupId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			let group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			Self::ensure_group_active(&group)?;
+			ensure!(
+				DiscoverableGroups::<T>::contains_key(group_id),
+				Error::<T>::GroupNotDiscoverable
+			);
+			ensure!(!GroupBans::<T>::contains_key(group_id, &who), Error::<T>::BannedFromGroup);
+			ensure!(
+				!GroupMembers::<T>::contains_key(group_id, &who),
+				Error::<T>::AlreadyGroupMember
+			);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			if let Some(expires_at) = GroupJoinRequests::<T>::get(group_id, &who) {
+				ensure!(now > expires_at, Error::<T>::AlreadyRequestedToJoin);
+				Self::remove_join_request(group_id, &who);
+			}
+			ensure!(
+				GroupJoinRequestCount::<T>::get(group_id) < T::MaxGroupJoinRequests::get(),
+				Error::<T>::TooManyJoinRequests
+			);
+
+			let expires_at = now.saturating_add(T::GroupInviteExpiry::get());
+			GroupJoinRequests::<T>::insert(group_id, &who, expires_at);
+			GroupJoinRequestCount::<T>::mutate(group_id, |count| count.saturating_inc());
+
+			Self::deposit_event(Event::GroupJoinRequested { group_id, who, expires_at });
+
+			Ok(())
+		}
+
+		/// Approve a pending join request, adding the requester as an ordinary member.
+		///
+		/// Callable by the group owner, admins and moderators, also for invite-only groups and
+		/// groups no longer listed for discovery. The approval is recorded in the moderation
+		/// log of the group.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The account that asked to join
+		///
+		/// Emits `GroupJoinRequestApproved` and `MemberAdded` events on success.
+		#[pallet::call_index(74)]
+		#[pallet::weight(T::WeightInfo::approve_join_request())]
+		pub fn approve_join_request(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let mut group = Self::ensure_group_moderator(group_id, &caller)?;
+			Self::ensure_not_banned(&who)?;
+
+			let expires_at =
+				GroupJoinRequests::<T>::get(group_id, &who).ok_or(Error::<T>::JoinRequestNotFound)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= expires_at,
+				Error::<T>::JoinRequestExpired
+			);
+
+			Self::join_group(group_id, &mut group, &who)?;
+			Groups::<T>::insert(group_id, group);
+			Self::record_moderation(
+				group_id,
+				&caller,
+				GroupModerationAction::ApproveJoinRequest { who: who.clone() },
+			);
+
+			Self::deposit_event(Event::GroupJoinRequestApproved {
+				group_id,
+				who: who.clone(),
+				approved_by: caller,
+			});
+			Self::deposit_event(Event::MemberAdded { group_id, who });
+
+			Ok(())
+		}
+
+		/// Cancel a pending join request.
+		///
+		/// The requester can withdraw its request and an owner, admin or moderator reject it at
+		/// any time. Anyone can remove an expired request, freeing its slot. Rejections are
+		/// recorded in the moderation log of the group.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The account that asked to join
+		///
+		/// Emits `GroupJoinRequestCancelled` event on success.
+		#[pallet::call_index(75)]
+		#[pallet::weight(T::WeightInfo::cancel_join_request())]
+		pub fn cancel_join_request(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+
+			let expires_at =
+				GroupJoinRequests::<T>::get(group_id, &who).ok_or(Error::<T>::JoinRequestNotFound)?;
+			let rejected = caller != who &&
+				matches!(
+					Self::group_role(group_id, &caller),
+					Some(GroupRole::Owner | GroupRole::Admin | GroupRole::Moderator)
+				);
+			ensure!(
+				caller == who || rejected || frame_system::Pallet::<T>::block_number() > expires_at,
+				Error::<T>::JoinRequestNotExpired
+			);
+			Self::remove_join_request(group_id, &who);
+			if rejected {
+				Self::record_moderation(
+					group_id,
+					&caller,
+					GroupModerationAction::RejectJoinRequest { who: who.clone() },
+				);
+			}
+
+			Self::deposit_event(Event::GroupJoinRequestCancelled { group_id, who });
+
+			Ok(())
+		}
+
+		/// Take down a group message hash, e.g. one referring to illegal content.
+		///
+		/// Group counterpart of `force_remove_message`. The sequence number of the message
+		/// is left as a gap, so syncing members see that something was removed. The removal
+		/// is recorded in the audit log and the event, and the sender is not penalized.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the group message to remove
+		/// - `order_reference`: Reference to the order requiring the removal
+		///
+		/// Emits `GroupMessageTakenDown` event on success.
+		#[pallet::call_index(76)]
+		#[pallet::weight(T::WeightInfo::force_remove_group_message())]
+		pub fn force_remove_group_message(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			order_reference: OrderReference,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ForceRemoveGroupMessage, &message_id);
+
+			let group_id =
+				GroupMessageIds::<T>::take(message_id).ok_or(Error::<T>::MessageNotFound)?;
+			let message = GroupMessages::<T>::take(group_id, message_id)
+				.ok_or(Error::<T>::MessageNotFound)?;
+			GroupMessagesBySeq::<T>::remove(group_id, message.seq);
+
+			Self::deposit_event(Event::GroupMessageTakenDown {
+				group_id,
+				message_id,
+				seq: message.seq,
+				order_reference,
+			});
+
+			Ok(())
+		}
+
+		/// Make an ordinary group member a moderator.
+		///
+		/// Only the group owner can appoint moderators. Moderators can mute members, remove
+		/// their messages and handle join requests, but cannot manage members, roles or the
+		/// group itself.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The member to appoint
+		///
+		/// Emits `ModeratorAppointed` event on success.
+		#[pallet::call_index(77)]
+		#[pallet::weight(T::WeightInfo::appoint_moderator())]
+		pub fn appoint_moderator(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_group_owner(group_id, &caller)?;
+
+			let mut member =
+				GroupMembers::<T>::get(group_id, &who).ok_or(Error::<T>::NotGroupMember)?;
+			ensure!(member.role != GroupRole::Moderator, Error::<T>::AlreadyGroupModerator);
+			ensure!(member.role == GroupRole::Member, Error::<T>::AlreadyGroupAdmin);
+
+			member.role = GroupRole::Moderator;
+			GroupMembers::<T>::insert(group_id, &who, member);
+
+			Self::deposit_event(Event::ModeratorAppointed { group_id, who });
+
+			Ok(())
+		}
+
+		/// Make a group moderator an ordinary member.
+		///
+		/// Only the group owner can dismiss moderators.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `who`: The moderator to dismiss
+		///
+		/// Emits `ModeratorDismissed` event on success.
+		#[pallet::call_index(78)]
+		#[pallet::weight(T::WeightInfo::dismiss_moderator())]
+		pub fn dismiss_moderator(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			who: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_group_owner(group_id, &caller)?;
+
+			let mut member =
+				GroupMembers::<T>::get(group_id, &who).ok_or(Error::<T>::NotGroupMember)?;
+			ensure!(member.role == GroupRole::Moderator, Error::<T>::MemberNotModerator);
+
+			member.role = GroupRole::Member;
+			GroupMembers::<T>::insert(group_id, &who, member);
+
+			Self::deposit_event(Event::ModeratorDismissed { group_id, who });
+
+			Ok(())
+		}
+
+		/// Remove a group message, leaving a tombstone in its place.
+		///
+		/// Callable by the group owner, admins and moderators, on messages of senders they
+		/// outrank. Senders that left the group count as ordinary members. The tombstone
+		/// records who removed the message and is pruned once the message would have expired.
+		/// The removal is recorded in the moderation log of the group.
+		///
+		/// Parameters:
+		/// - `group_id`: The group
+		/// - `message_id`: The ID of the group message to remove
+		///
+		/// Emits `GroupMessageRemoved` event on success.
+		#[pallet::call_index(79)]
+		#[pallet::weight(T::WeightInfo::remove_group_message())]
+		pub fn remove_group_message(
+			origin: OriginFor<T>,
+			group_id: GroupId,
+			message_id: MessageId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			Self::ensure_group_moderator(group_id, &caller)?;
+
+			let message = GroupMessages::<T>::get(group_id, message_id)
+				.ok_or(Error::<T>::MessageNotFound)?;
+			let sender_role =
+				Self::group_role(group_id, &message.sender).unwrap_or(GroupRole::Member);
+			Self::ensure_outranks(group_id, &caller, sender_role)?;
+
+			GroupMessages::<T>::remove(group_id, message_id);
+			GroupMessagesBySeq::<T>::remove(group_id, message.seq);
+			GroupMessageTombstones::<T>::insert(
+				group_id,
+				message_id,
+				GroupMessageTombstone {
+					seq: message.seq,
+					removed_by: caller.clone(),
+					removed_at: frame_system::Pallet::<T>::block_number(),
+					expires_at: Self::effective_expiry(group_id, &message),
+				},
+			);
+			Self::record_moderation(
+				group_id,
+				&caller,
+				GroupModerationAction::RemoveMessage { message_id },
+			);
+
+			Self::deposit_event(Event::GroupMessageRemoved {
+				group_id,
+				message_id,
+				seq: message.seq,
+				removed_by: caller,
+			});
+
+			Ok(())
+		}
+
+		/// Send a message hash under a recipient tag instead of the recipient's account.
+		///
+		/// The tag is derived off-chain from the recipient's public key as described in the
+		/// [`stealth`] module. Since the recipient is unknown on-chain, contact approval and
+		/// inbox caps cannot apply: instead `StealthMessageDeposit` is held from the sender
+		/// until the message is pruned, and graylisted senders cannot send stealth messages.
+		/// The sender's bond and rate limit apply as for direct messages.
+		///
+		/// Parameters:
+		/// - `recipient_tag`: Tag derived from the recipient's key and an ephemeral value
+		/// - `message_hash`: The hash of the encrypted message
+		///
+		/// Emits `StealthMessageSent` event on success.
+		#[pallet::call_index(80)]
+		#[pallet::weight(T::WeightInfo::send_stealth_message_hash())]
+		pub fn send_stealth_message_hash(
+			origin: OriginFor<T>,
+			recipient_tag: T::Hash,
+			message_hash: T::Hash,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::SENDS)?;
+			Self::ensure_valid_hash(&message_hash)?;
+			Self::ensure_not_banned(&sender)?;
+			ensure!(!Self::is_frozen(&sender), Error::<T>::SenderFrozen);
+			ensure!(UserProfiles::<T>::contains_key(&sender), Error::<T>::ProfileNotFound);
+
+			// Mutual contacts cannot be checked without the recipient
+			let strikes = Self::refresh_reputation(&sender).strikes;
+			ensure!(strikes < Self::graylist_threshold(), Error::<T>::Graylisted);
+			ensure!(
+				SpamBonds::<T>::get(&sender).amount >= Self::required_bond_for(&sender, strikes),
+				Error::<T>::InsufficientBond
+			);
+
+			let current_block = frame_system::Pallet::<T>::block_number();
+			Self::consume_rate_limit(&sender, strikes, current_block)?;
+
+			let deposit = T::StealthMessageDeposit::get();
+			T::Currency::hold(&HoldReason::MessageDeposit.into(), &sender, deposit)
+				.map_err(|_| Error::<T>::InsufficientStealthDeposit)?;
+
+			let message_id = NextMessageId::<T>::get();
+			NextMessageId::<T>::put(message_id.saturating_add(1));
+			StealthMessages::<T>::insert(
+				message_id,
+				StealthMessageRecord {
+					sender: sender.clone(),
+					recipient_tag,
+					hash: message_hash,
+					sent_at: current_block,
+					deposit,
+				},
+			);
+			StealthMessagesByPrefix::<T>::insert(
+				stealth::prefix_of(recipient_tag.as_ref()),
+				message_id,
+				(),
+			);
+
+			Self::deposit_event(Event::StealthMessageSent {
+				message_id,
+				from: sender,
+				recipient_tag,
+				hash: message_hash,
+				expires_at: current_block.saturating_add(Self::parameters().message_expiry),
+			});
+
+			Ok(())
+		}
+
+		/// Register the caller as a relay for sealed-sender messages, or restore the bond of a
+		/// relay that was slashed.
+		///
+		/// Holds `RelayBond`, or what the relay bond lacks of it. Upheld spam challenges
+		/// against any message sent from a relay account slash the relay bond instead of the
+		/// spam bond.
+		///
+		/// Emits `RelayRegistered` event on success.
+		#[pallet::call_index(81)]
+		#[pallet::weight(T::WeightInfo::register_relay())]
+		pub fn register_relay(origin: OriginFor<T>) -> DispatchResult {
+			let relay = ensure_signed(origin)?;
+			Self::ensure_not_banned(&relay)?;
+
+			let mut info = match RelayAccounts::<T>::get(&relay) {
+				Some(info) => info,
+				None => {
+					// Slashes taken so far came from the spam bond and are reversed into it
+					ensure!(
+						OpenChallengeCount::<T>::get(&relay).is_zero(),
+						Error::<T>::OpenChallengesPending
+					);
+					RelayInfo { bond: Zero::zero(), last_carried_at: None, carried_in_block: 0 }
+				},
+			};
+			let held = T::RelayBond::get().saturating_sub(info.bond);
+			ensure!(!held.is_zero(), Error::<T>::AlreadyRelay);
+			T::Currency::hold(&HoldReason::RelayBond.into(), &relay, held)
+				.map_err(|_| Error::<T>::InsufficientRelayBond)?;
+			info.bond = T::RelayBond::get();
+			RelayAccounts::<T>::insert(&relay, info);
+
+			Self::deposit_event(Event::RelayRegistered { relay, held });
+
+			Ok(())
+		}
+
+		/// Stop relaying and claim back the relay bond.
+		///
+		/// Fails while spam challenges against the relay are open or within
+		/// `BondUnbondingPeriod` blocks of the last message it carried.
+		///
+		/// Emits `RelayDeregistered` event on success.
+		#[pallet::call_index(82)]
+		#[pallet::weight(T::WeightInfo::deregister_relay())]
+		pub fn deregister_relay(origin: OriginFor<T>) -> DispatchResult {
+			let relay = ensure_signed(origin)?;
+			let info = RelayAccounts::<T>::get(&relay).ok_or(Error::<T>::NotRelay)?;
+			ensure!(
+				OpenChallengeCount::<T>::get(&relay).is_zero(),
+				Error::<T>::OpenChallengesPending
+			);
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				info.last_carried_at
+					.is_none_or(|at| now >= at.saturating_add(T::BondUnbondingPeriod::get())),
+				Error::<T>::BondLocked
+			);
+
+			let refunded = T::Currency::release(
+				&HoldReason::RelayBond.into(),
+				&relay,
+				info.bond,
+				Precision::BestEffort,
+			)?;
+			RelayAccounts::<T>::remove(&relay);
+
+			Self::deposit_event(Event::RelayDeregistered { relay, refunded });
+
+			Ok(())
+		}
+
+		/// Send a message hash on behalf of a sender that stays hidden.
+		///
+		/// Callable by relays only. The stored message names the relay as its sender; the true
+		/// sender is only present inside the encrypted off-chain payload. The relay answers for
+		/// the message: spam challenges against it slash the relay bond, and relays that are
+		/// graylisted or whose bond was slashed below `RelayBond` cannot carry messages. Each
+		/// relay carries at most `MaxRelayCarriesPerBlock` messages per block. Contact approval
+		/// and the send rate limit of the true sender cannot apply.
+		///
+		/// Parameters:
+		/// - `recipient`: The account ID of the message recipient
+		/// - `message_hash`: The hash of the encrypted message
+		///
+		/// Emits `SealedMessageSent` event on success.
+		#[pallet::call_index(83)]
+		#[pallet::weight(T::WeightInfo::send_sealed_message_hash())]
+		pub fn send_sealed_message_hash(
+			origin: OriginFor<T>,
+			recipient: T::AccountId,
+			message_hash: T::Hash,
+		) -> DispatchResult {
+			let relay = ensure_signed(origin)?;
+			let (message_id, expires_at) =
+				Self::carry_sealed_message(&relay, &recipient, message_hash)?;
+
+			Self::deposit_event(Event::SealedMessageSent {
+				message_id,
+				relay,
+				to: recipient,
+				hash: message_hash,
+				expires_at,
+			});
+
+			Ok(())
+		}
+
+		/// Leave a message hash for an account that has not registered a profile yet.
+		///
+		/// The message is stored under `recipient_account_hash`, which is
+		/// [`Pallet::account_hash`] of the recipient, until the recipient registers and claims
+		/// it with `claim_pending_messages` or `PendingMessageExpiry` blocks pass.
+		/// `PendingMessageDeposit` is held from the sender meanwhile. Since the recipient is
+		/// unknown on-chain, contact approval cannot apply and graylisted senders cannot leave
+		/// pending messages. The sender's bond and rate limit apply as for direct messages.
+		///
+		/// Parameters:
+		/// - `recipient_account_hash`: Hash of the recipient's account ID
+		/// - `message_hash`: The hash of the encrypted message
+		///
+		/// Emits `PendingMessageSent` event on success.
+		#[pallet::call_index(84)]
+		#[pallet::weight(T::WeightInfo::send_to_unregistered())]
+		pub fn send_to_unregistered(
+			origin: OriginFor<T>,
+			recipient_account_hash: T::Hash,
+			message_hash: T::Hash,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::SENDS)?;
+			Self::ensure_valid_hash(&message_hash)?;
+			Self::ensure_not_banned(&sender)?;
+			ensure!(!Self::is_frozen(&sender), Error::<T>::SenderFrozen);
+			ensure!(UserProfiles::<T>::contains_key(&sender), Error::<T>::ProfileNotFound);
+
+			// Mutual contacts cannot be checked without the recipient
+			let strikes = Self::refresh_reputation(&sender).strikes;
+			ensure!(strikes < Self::graylist_threshold(), Error::<T>::Graylisted);
+			ensure!(
+				SpamBonds::<T>::get(&sender).amount >= Self::required_bond_for(&sender, strikes),
+				Error::<T>::InsufficientBond
+			);
+
+			let current_block = frame_system::Pallet::<T>::block_number();
+			Self::consume_rate_limit(&sender, strikes, current_block)?;
+
+			let deposit = T::PendingMessageDeposit::get();
+			T::Currency::hold(&HoldReason::MessageDeposit.into(), &sender, deposit)
+				.map_err(|_| Error::<T>::InsufficientPendingDeposit)?;
+
+			let pending_id = NextPendingMessageId::<T>::get();
+			NextPendingMessageId::<T>::put(pending_id.saturating_add(1));
+			PendingMessages::<T>::insert(
+				recipient_account_hash,
+				pending_id,
+				PendingMessageRecord {
+					sender: sender.clone(),
+					hash: message_hash,
+					sent_at: current_block,
+					deposit,
+				},
+			);
+			PendingMessageIds::<T>::insert(pending_id, recipient_account_hash);
+
+			Self::deposit_event(Event::PendingMessageSent {
+				pending_id,
+				from: sender,
+				recipient_account_hash,
+				hash: message_hash,
+				expires_at: current_block.saturating_add(T::PendingMessageExpiry::get()),
+			});
+
+			Ok(())
+		}
+
+		/// Move messages left for the caller before it registered into its inbox.
+		///
+		/// Claims at most `MaxClaimsPerCall` pending messages; call again for the rest. Each
+		/// claimed message is stored as a direct message with a new ID, sent at the current
+		/// block, and `PendingMessageRefund` of its deposit is returned to the sender.
+		/// Expired messages found along the way are pruned and count towards the limit.
+		///
+		/// Emits `PendingMessageClaimed` event for each claimed message.
+		#[pallet::call_index(85)]
+		#[pallet::weight(T::WeightInfo::claim_pending_messages(T::MaxClaimsPerCall::get()))]
+		pub fn claim_pending_messages(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			let account_hash = Self::account_hash(&who);
+			let pending: Vec<_> = PendingMessages::<T>::iter_prefix(account_hash)
+				.take(T::MaxClaimsPerCall::get() as usize)
+				.collect();
+			ensure!(!pending.is_empty(), Error::<T>::NoPendingMessages);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			for (pending_id, record) in &pending {
+				PendingMessages::<T>::remove(account_hash, pending_id);
+				PendingMessageIds::<T>::remove(pending_id);
+				if now > record.sent_at.saturating_add(T::PendingMessageExpiry::get()) {
+					Self::release_message_deposit(&record.sender, record.deposit);
+					continue;
+				}
+
+				let refunded = T::PendingMessageRefund::get().mul_floor(record.deposit);
+				Self::release_message_deposit(&record.sender, refunded);
+				let (fee, _) = T::Currency::slash(
+					&HoldReason::MessageDeposit.into(),
+					&record.sender,
+					record.deposit.saturating_sub(refunded),
+				);
+				Self::route_income(IncomeCategory::Fee, fee);
+
+				let message_id = NextMessageId::<T>::get();
+				NextMessageId::<T>::put(message_id.saturating_add(1));
+				MessageHashes::<T>::insert(
+					message_id,
+					MessageRecord {
+						sender: record.sender.clone(),
+						recipient: who.clone(),
+						hash: record.hash,
+						sent_at: now,
+					},
+				);
+				// The deposit is already paid, so a hash in use only stays unindexed
+				if Self::ensure_hash_unused(&record.hash).is_ok() {
+					MessageIdByHash::<T>::insert(record.hash, message_id);
+				}
+				Self::touch_inbox(&who);
+				offchain_index::set::<T>(
+					&who,
+					message_id,
+					&offchain_index::IndexEntry {
+						sender: record.sender.clone(),
+						hash: record.hash,
+						sent_at: now,
+						acknowledged: false,
+					},
+				);
+
+				Self::deposit_event(Event::PendingMessageClaimed {
+					pending_id: *pending_id,
+					message_id,
+					from: record.sender.clone(),
+					to: who.clone(),
+					refunded,
+				});
+			}
+
+			Ok(Some(T::WeightInfo::claim_pending_messages(pending.len() as u32)).into())
+		}
+
+		/// Contest a challenge against one of the caller's messages.
+		///
+		/// The challenger then has `EvidenceRevealPeriod` blocks to reveal the evidence they
+		/// committed to in `challenge_spam`.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the challenged message
+		///
+		/// Emits `ChallengeContested` event on success.
+		#[pallet::call_index(86)]
+		#[pallet::weight(T::WeightInfo::contest_challenge())]
+		pub fn contest_challenge(origin: OriginFor<T>, message_id: MessageId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			// Only the sender may contest a challenge awaiting a verdict
+			let challenge =
+				Challenges::<T>::get(message_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			ensure!(challenge.sender == who, Error::<T>::NotAuthorized);
+			ensure!(challenge.status == ChallengeStatus::Open, Error::<T>::ChallengeNotOpen);
+			let mut record =
+				ChallengeEvidence::<T>::get(message_id).ok_or(Error::<T>::NoEvidenceCommitment)?;
+			ensure!(record.reveal_by.is_none(), Error::<T>::AlreadyContested);
+
+			// Open the reveal period
+			let reveal_by = frame_system::Pallet::<T>::block_number()
+				.saturating_add(T::EvidenceRevealPeriod::get());
+			record.reveal_by = Some(reveal_by);
+			ChallengeEvidence::<T>::insert(message_id, record);
+
+			// Emit event
+			Self::deposit_event(Event::ChallengeContested { message_id, reveal_by });
+
+			Ok(())
+		}
+
+		/// Reveal the evidence committed to when challenging a message.
+		///
+		/// Only possible once the sender has contested the challenge, until the end of the
+		/// reveal period or an earlier verdict. Matching evidence is stored for
+		/// `ResolutionOrigin` until the challenge closes. Evidence that does not match the
+		/// commitment closes the challenge and forfeits the challenger's bond.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the challenged message
+		/// - `evidence`: The evidence committed to
+		/// - `salt`: The salt committed to with the evidence
+		///
+		/// Emits `EvidenceRevealed` or `EvidenceMismatch` event on success.
+		#[pallet::call_index(87)]
+		#[pallet::weight(T::WeightInfo::reveal_evidence(evidence.len() as u32))]
+		pub fn reveal_evidence(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			evidence: Evidence,
+			salt: T::Hash,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			// Only the challenger may reveal, within the reveal period
+			let challenge =
+				Challenges::<T>::get(message_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			ensure!(challenge.challenger.as_ref() == Some(&who), Error::<T>::NotAuthorized);
+			ensure!(challenge.status == ChallengeStatus::Open, Error::<T>::ChallengeNotOpen);
+			let mut record =
+				ChallengeEvidence::<T>::get(message_id).ok_or(Error::<T>::NoEvidenceCommitment)?;
+			let reveal_by = record.reveal_by.ok_or(Error::<T>::ChallengeNotContested)?;
+			ensure!(record.evidence.is_none(), Error::<T>::EvidenceAlreadyRevealed);
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= reveal_by,
+				Error::<T>::EvidenceRevealClosed
+			);
+
+			// A mismatch ends the challenge without a verdict
+			if Self::evidence_commitment(&evidence, &salt) != record.commitment {
+				let (credit, _) =
+					T::Currency::slash(&HoldReason::ChallengeBond.into(), &who, challenge.bond);
+				Self::route_income(IncomeCategory::ForfeitedDeposit, credit);
+				Self::close_challenge(message_id, &challenge.sender);
+				Self::deposit_event(Event::EvidenceMismatch {
+					message_id,
+					challenger: who,
+					forfeited: challenge.bond,
+				});
+				return Ok(());
+			}
+
+			// Store the evidence for the resolver
+			record.evidence = Some(evidence);
+			ChallengeEvidence::<T>::insert(message_id, record);
+
+			// Emit event
+			Self::deposit_event(Event::EvidenceRevealed { message_id });
+
+			Ok(())
+		}
+
+		/// Send a sealed message hash whose hidden sender proves to be an approved contact.
+		///
+		/// Callable by relays under the same rules as `send_sealed_message_hash`. `proof` must
+		/// convince `Config::MembershipProofVerifier` that the sender of `message_hash` is in
+		/// the recipient's contact set, as committed to by the recipient's current
+		/// `ContactAccumulators` entry. The proof does not reveal which contact sent it.
+		///
+		/// Parameters:
+		/// - `recipient`: The account ID of the message recipient
+		/// - `message_hash`: The hash of the encrypted message
+		/// - `proof`: Membership proof for the verifier configured by the runtime
+		///
+		/// Emits `AnonymousContactMessageSent` event on success.
+		#[pallet::call_index(88)]
+		#[pallet::weight(
+			T::WeightInfo::send_anonymous_contact_message(proof.len() as u32)
+				.saturating_add(T::MembershipProofVerifier::verification_weight(proof.len() as u32))
+		)]
+		pub fn send_anonymous_contact_message(
+			origin: OriginFor<T>,
+			recipient: T::AccountId,
+			message_hash: T::Hash,
+			proof: MembershipProof,
+		) -> DispatchResult {
+			let relay = ensure_signed(origin)?;
+			ensure!(
+				T::MembershipProofVerifier::verify(
+					&recipient,
+					&ContactAccumulators::<T>::get(&recipient),
+					&message_hash,
+					&proof,
+				),
+				Error::<T>::InvalidMembershipProof
+			);
+			let (message_id, expires_at) =
+				Self::carry_sealed_message(&relay, &recipient, message_hash)?;
+
+			Self::deposit_event(Event::AnonymousContactMessageSent {
+				message_id,
+				relay,
+				to: recipient,
+				hash: message_hash,
+				expires_at,
+			});
+
+			Ok(())
+		}
+
+		/// Publish an ephemeral public key for a single message.
+		///
+		/// The key is authenticated by the signed origin and stored under `index`, replacing
+		/// any key already there. An account keeps at most `MaxEphemeralKeys` keys; publishing
+		/// under a new index beyond that evicts the oldest. Keys expire like messages sent in
+		/// the same block and are pruned by `prune_expired_unsigned`.
+		///
+		/// Parameters:
+		/// - `index`: Index the sender refers to the key by
+		/// - `key`: The ephemeral public key
+		///
+		/// Emits `EphemeralKeyPublished` event on success.
+		#[pallet::call_index(89)]
+		#[pallet::weight(T::WeightInfo::publish_ephemeral_key(T::MaxEphemeralKeys::get()))]
+		pub fn publish_ephemeral_key(
+			origin: OriginFor<T>,
+			index: u32,
+			key: EphemeralKey,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(!key.is_empty(), Error::<T>::EmptyEphemeralKey);
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			// Make room under a new index by evicting the oldest key
+			let mut evicted = None;
+			let mut count = 0u32;
+			if !EphemeralKeys::<T>::contains_key(&who, index) {
+				let mut oldest: Option<(BlockNumberFor<T>, u32)> = None;
+				for (stored_index, (_, published_at)) in EphemeralKeys::<T>::iter_prefix(&who) {
+					count.saturating_inc();
+					if oldest.is_none_or(|oldest| (published_at, stored_index) < oldest) {
+						oldest = Some((published_at, stored_index));
+					}
+				}
+				if count >= T::MaxEphemeralKeys::get() {
+					if let Some((_, oldest_index)) = oldest {
+						EphemeralKeys::<T>::remove(&who, oldest_index);
+						evicted = Some(oldest_index);
+					}
+				}
+			}
+
+			// Store the key and queue it for pruning
+			let current_block = frame_system::Pallet::<T>::block_number();
+			EphemeralKeys::<T>::insert(&who, index, (key, current_block));
+			let id = NextEphemeralKeyId::<T>::get();
+			NextEphemeralKeyId::<T>::put(id.saturating_add(1));
+			EphemeralKeyIds::<T>::insert(id, (who.clone(), index, current_block));
+
+			// Emit event
+			Self::deposit_event(Event::EphemeralKeyPublished { who, index, evicted });
+
+			Ok(Some(T::WeightInfo::publish_ephemeral_key(count)).into())
+		}
+
+		/// Send a message hash encrypted to the recipient under one of the sender's
+		/// ephemeral keys.
+		///
+		/// Behaves like `send_message_hash`, and additionally records `key_index` in
+		/// `MessageEphemeralKeys` so the recipient can bind the off-chain ciphertext to the
+		/// published key. The key must be published and unexpired.
+		///
+		/// Parameters:
+		/// - `recipient`: The account ID of the message recipient
+		/// - `message_hash`: The hash of the encrypted message
+		/// - `key_index`: Index of the sender's ephemeral key used for the message
+		///
+		/// Emits `MessageSent` and `EphemeralKeyReferenced` events on success.
+		#[pallet::call_index(90)]
+		#[pallet::weight(T::WeightInfo::send_message_hash_with_ephemeral_key().saturating_add(
+			T::WeightInfo::refund_deposits_on_reply(T::MaxAutoRefundsPerReply::get())
+		))]
+		pub fn send_message_hash_with_ephemeral_key(
+			origin: OriginFor<T>,
+			recipient: T::AccountId,
+			message_hash: T::Hash,
+			key_index: u32,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+			ensure!(
+				Self::ephemeral_key(&sender, key_index).is_some(),
+				Error::<T>::EphemeralKeyNotFound
+			);
+
+			let (message_id, refunded) = Self::do_send_message(
+				sender,
+				recipient,
+				message_hash,
+				false,
+				false,
+				T::MaxAutoRefundsPerReply::get(),
+			)
+			.map_err(Error::<T>::from)?;
+			MessageEphemeralKeys::<T>::insert(message_id, key_index);
+
+			Self::deposit_event(Event::EphemeralKeyReferenced { message_id, index: key_index });
+
+			Ok(Some(
+				T::WeightInfo::send_message_hash_with_ephemeral_key()
+					.saturating_add(T::WeightInfo::refund_deposits_on_reply(refunded)),
+			)
+			.into())
+		}
+
+		/// Send a message hash to a set of accounts, only one of which can decrypt it.
+		///
+		/// The message is stored once with the whole set and indexed in the inbox of every
+		/// listed account, hiding the true recipient among decoys. Every listed account must
+		/// have a profile and have approved the sender or granted it an inbox pass.
+		/// `RecipientSetDeposit` per listed account is held from the sender until any member
+		/// acknowledges the message with `acknowledge_recipient_set_message` or sends the
+		/// sender a message, or it is pruned. The sender's bond and rate limit apply as for
+		/// direct messages.
+		///
+		/// Parameters:
+		/// - `recipients`: The true recipient and its decoys, in any order
+		/// - `message_hash`: The hash of the encrypted message
+		///
+		/// A listed account that cannot be messaged fails the whole call with `ItemFailed`,
+		/// carrying its position in `recipients`.
+		///
+		/// Emits `RecipientSetMessageSent` event on success.
+		#[pallet::call_index(91)]
+		#[pallet::weight(T::WeightInfo::send_to_recipient_set(recipients.len() as u32))]
+		pub fn send_to_recipient_set(
+			origin: OriginFor<T>,
+			recipients: RecipientSet<T::AccountId>,
+			message_hash: T::Hash,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::SENDS)?;
+			Self::ensure_valid_hash(&message_hash)?;
+			Self::ensure_not_banned(&sender)?;
+			ensure!(!Self::is_frozen(&sender), Error::<T>::SenderFrozen);
+			ensure!(UserProfiles::<T>::contains_key(&sender), Error::<T>::ProfileNotFound);
+			ensure!(!recipients.is_empty(), Error::<T>::EmptyRecipientSet);
+
+			// Every listed account must accept messages from the sender
+			Self::try_each_item(recipients.iter().enumerate(), |(i, recipient)| {
+				if *recipient == sender || recipients[..i].contains(recipient) {
+					return Err(ItemError::Duplicate);
+				}
+				if Self::is_banned(recipient) {
+					return Err(ItemError::Banned);
+				}
+				if !UserProfiles::<T>::contains_key(recipient) {
+					return Err(ItemError::NotFound);
+				}
+				if !Self::has_inbox_access(recipient, &sender) {
+					return Err(ItemError::NotApproved);
+				}
+				Ok(())
+			})?;
+
+			let strikes = Self::refresh_reputation(&sender).strikes;
+			ensure!(
+				SpamBonds::<T>::get(&sender).amount >= Self::required_bond_for(&sender, strikes),
+				Error::<T>::InsufficientBond
+			);
+			let current_block = frame_system::Pallet::<T>::block_number();
+			Self::consume_rate_limit(&sender, strikes, current_block)?;
+
+			// Decoys cost as much as the true recipient
+			let deposit =
+				T::RecipientSetDeposit::get().saturating_mul((recipients.len() as u32).into());
+			T::Currency::hold(&HoldReason::MessageDeposit.into(), &sender, deposit)
+				.map_err(|_| Error::<T>::InsufficientRecipientSetDeposit)?;
+
+			let message_id = NextMessageId::<T>::get();
+			NextMessageId::<T>::put(message_id.saturating_add(1));
+			for recipient in &recipients {
+				offchain_index::set::<T>(
+					recipient,
+					message_id,
+					&offchain_index::IndexEntry {
+						sender: sender.clone(),
+						hash: message_hash,
+						sent_at: current_block,
+						acknowledged: false,
+					},
+				);
+				// A reply refunds the most recent deposits only
+				ReplyRefundableDeposits::<T>::mutate(&sender, recipient, |ids| {
+					if ids.is_full() && !ids.is_empty() {
+						ids.remove(0);
+					}
+					let _ = ids.try_push(message_id);
+				});
+			}
+			RecipientSetMessages::<T>::insert(
+				message_id,
+				RecipientSetMessageRecord {
+					sender: sender.clone(),
+					recipients: recipients.clone(),
+					hash: message_hash,
+					sent_at: current_block,
+					deposit,
+				},
+			);
+
+			Self::deposit_event(Event::RecipientSetMessageSent {
+				message_id,
+				from: sender,
+				recipients,
+				hash: message_hash,
+				expires_at: current_block.saturating_add(Self::parameters().message_expiry),
+				deposit,
+			});
+
+			Ok(())
+		}
+
+		/// Acknowledge a message sent to a recipient set the caller is listed in.
+		///
+		/// Any member of the set can acknowledge, since the chain cannot tell which one is the
+		/// true recipient. Refunds the sender's deposit and marks the message acknowledged in
+		/// the inbox index of every member.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of the recipient-set message
+		///
+		/// Emits `RecipientSetMessageAcknowledged` event on success.
+		#[pallet::call_index(92)]
+		#[pallet::weight(T::WeightInfo::acknowledge_recipient_set_message())]
+		pub fn acknowledge_recipient_set_message(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let message =
+				RecipientSetMessages::<T>::get(message_id).ok_or(Error::<T>::MessageNotFound)?;
+			ensure!(message.recipients.contains(&who), Error::<T>::NotAuthorized);
+			ensure!(!message.deposit.is_zero(), Error::<T>::AlreadyAcknowledged);
+			let expires_at = message.sent_at.saturating_add(Self::parameters().message_expiry);
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= expires_at,
+				Error::<T>::MessageExpired
+			);
+
+			// Settle the deposit
+			let refunded = Self::settle_recipient_set_message(message_id, message);
+
+			Self::deposit_event(Event::RecipientSetMessageAcknowledged {
+				message_id,
+				by: who,
+				refunded,
+			});
+
+			Ok(())
+		}
+
+		/// Commit to an encrypted backup of the caller's contact list.
+		///
+		/// The backup itself is stored off-chain, encrypted to the caller's own key. Devices
+		/// restoring it compare its hash and version with `latest_backup` to detect tampering
+		/// or a stale copy. The last `MAX_BACKUP_HISTORY` commitments are kept, each holding
+		/// `BackupDeposit`; the deposit of the oldest is refunded once a new commitment pushes
+		/// it out.
+		///
+		/// Parameters:
+		/// - `backup_hash`: The hash of the encrypted backup
+		/// - `backup_version`: The backup version, above every version committed so far
+		///
+		/// Emits `BackupCommitted` event on success.
+		#[pallet::call_index(93)]
+		#[pallet::weight(T::WeightInfo::commit_backup())]
+		pub fn commit_backup(
+			origin: OriginFor<T>,
+			backup_hash: T::Hash,
+			backup_version: u32,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			let mut history = BackupCommitments::<T>::get(&who);
+			ensure!(
+				history.last().is_none_or(|latest| backup_version > latest.version),
+				Error::<T>::StaleBackupVersion
+			);
+
+			// Hold the deposit, refunding the commitment pushed out of the history
+			let deposit = T::BackupDeposit::get();
+			T::Currency::hold(&HoldReason::BackupDeposit.into(), &who, deposit)
+				.map_err(|_| Error::<T>::InsufficientBackupDeposit)?;
+			if history.is_full() {
+				let oldest = history.remove(0);
+				T::Currency::release(
+					&HoldReason::BackupDeposit.into(),
+					&who,
+					oldest.deposit,
+					Precision::BestEffort,
+				)?;
+			}
+			let _ = history.try_push(BackupCommitment {
+				backup_hash,
+				version: backup_version,
+				committed_at: frame_system::Pallet::<T>::block_number(),
+				deposit,
+			});
+			BackupCommitments::<T>::insert(&who, history);
+
+			// Emit event
+			Self::deposit_event(Event::BackupCommitted {
+				who,
+				backup_hash,
+				version: backup_version,
+			});
+
+			Ok(())
+		}
+
+		/// Set the caller's inbox prices by sender class, or clear them by leaving every tier
+		/// at `None`.
+		///
+		/// Tiered pricing takes precedence over the flat price set by `set_inbox_price`.
+		/// Payments already held keep the amount they were taken at.
+		///
+		/// Parameters:
+		/// - `pricing`: The price for each sender class
+		///
+		/// Emits `InboxPricingSet` event on success.
+		#[pallet::call_index(94)]
+		#[pallet::weight(T::WeightInfo::set_inbox_pricing())]
+		pub fn set_inbox_pricing(
+			origin: OriginFor<T>,
+			pricing: InboxPricingOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			Self::ensure_not_banned(&who)?;
+
+			// Check if profile exists
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			// Store the pricing
+			if pricing.is_closed() {
+				InboxPricings::<T>::remove(&who);
+			} else {
+				InboxPricings::<T>::insert(&who, &pricing);
+			}
+
+			// Emit event
+			Self::deposit_event(Event::InboxPricingSet { who, pricing });
+
+			Ok(())
+		}
+
+		/// Buy `count` prepaid message credits at `CreditPrice` each.
+		///
+		/// Messages that would be charged the protocol fee use a credit instead. The
+		/// `CreditRefund` portion of the price is held until the credit is used; the rest is
+		/// collected as a fee.
+		///
+		/// Parameters:
+		/// - `count`: The number of credits to buy
+		///
+		/// Emits `CreditsPurchased` event on success.
+		#[pallet::call_index(95)]
+		#[pallet::weight(T::WeightInfo::purchase_credits())]
+		pub fn purchase_credits(origin: OriginFor<T>, count: u32) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+			ensure!(!count.is_zero(), Error::<T>::ZeroCreditPurchase);
+
+			let credits = MessageCredits::<T>::get(&who)
+				.checked_add(count)
+				.filter(|credits| *credits <= T::MaxCreditsPerAccount::get())
+				.ok_or(Error::<T>::CreditLimitExceeded)?;
+
+			// Hold the refundable part of the price and collect the rest
+			let paid = T::CreditPrice::get().saturating_mul(count.into());
+			let refundable = T::CreditRefund::get().mul_floor(paid);
+			T::Currency::hold(&HoldReason::MessageCredits.into(), &who, refundable)
+				.map_err(|_| Error::<T>::InsufficientCreditPayment)?;
+			let fee = T::Currency::withdraw(
+				&who,
+				paid.saturating_sub(refundable),
+				Precision::Exact,
+				Preservation::Preserve,
+				Fortitude::Polite,
+			)
+			.map_err(|_| Error::<T>::InsufficientCreditPayment)?;
+			Self::route_income(IncomeCategory::Fee, fee);
+
+			MessageCredits::<T>::insert(&who, credits);
+
+			// Emit event
+			Self::deposit_event(Event::CreditsPurchased { who, count, paid });
+
+			Ok(())
+		}
+
+		/// Return every unused message credit of the caller, releasing the held
+		/// `CreditRefund` portion of their price.
+		///
+		/// Emits `CreditsRefunded` event on success.
+		#[pallet::call_index(96)]
+		#[pallet::weight(T::WeightInfo::refund_unused_credits())]
+		pub fn refund_unused_credits(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let count = MessageCredits::<T>::take(&who);
+			ensure!(!count.is_zero(), Error::<T>::NoUnusedCredits);
+
+			let refunded = T::Currency::release_all(
+				&HoldReason::MessageCredits.into(),
+				&who,
+				Precision::BestEffort,
+			)?;
+
+			// Emit event
+			Self::deposit_event(Event::CreditsRefunded { who, count, refunded });
+
+			Ok(())
+		}
+
+		/// Register a profile for `who`, holding its spam bond from the caller.
+		///
+		/// `who` consents by signing `SPONSORSHIP_CONTEXT` followed by the SCALE-encoded
+		/// sponsor, public key and key algorithm. Until `who` calls `assume_own_bond`, refunds
+		/// of the bond go back to the sponsor and slashes of it are taken from the sponsor.
+		/// The caller also tops `who` up to the existential deposit, which its profile keeps
+		/// in place.
+		///
+		/// Parameters:
+		/// - `who`: The account to register
+		/// - `public_key`: The account's public key (max 256 bytes)
+		/// - `algorithm`: The algorithm of the public key
+		/// - `proof_of_possession`: The signature of `who` over the sponsorship
+		///
+		/// Emits `ProfileRegistered` and `RegistrationSponsored` events on success.
+		#[pallet::call_index(97)]
+		#[pallet::weight(T::WeightInfo::sponsor_registration(public_key.len() as u32))]
+		pub fn sponsor_registration(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			public_key: Vec<u8>,
+			algorithm: KeyAlgorithm,
+			proof_of_possession: T::OffchainSignature,
+		) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			Self::ensure_not_banned(&sponsor)?;
+			ensure!(sponsor != who, Error::<T>::CannotSponsorSelf);
+
+			// Check the account consented to this sponsor and key
+			let sponsorship = (&sponsor, &public_key, algorithm).encode();
+			let payload = [SPONSORSHIP_CONTEXT, &sponsorship[..]].concat();
+			ensure!(
+				proof_of_possession.verify(&payload[..], &who),
+				Error::<T>::InvalidSponsorshipProof
+			);
+
+			Self::do_register_profile(who.clone(), public_key, Some(sponsor.clone()))?;
+			KeyAlgorithms::<T>::insert(&who, algorithm);
+
+			// Emit event
+			let amount = SpamBonds::<T>::get(&who).amount;
+			Self::deposit_event(Event::RegistrationSponsored { who, sponsor, amount });
+
+			Ok(())
+		}
+
+		/// Take over the caller's sponsored spam bond, holding the same amount from the caller
+		/// and releasing the sponsor's.
+		///
+		/// Fails while spam challenges against the caller are not final, since slashes they
+		/// reverse are returned to whoever the bond is held from.
+		///
+		/// Emits `BondAssumed` event on success.
+		#[pallet::call_index(98)]
+		#[pallet::weight(T::WeightInfo::assume_own_bond())]
+		pub fn assume_own_bond(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut bond = SpamBonds::<T>::get(&who);
+			let sponsor = bond.sponsor.take().ok_or(Error::<T>::BondNotSponsored)?;
+			ensure!(
+				OpenChallengeCount::<T>::get(&who).is_zero(),
+				Error::<T>::OpenChallengesPending
+			);
+
+			// Hold the bond from the caller before releasing the sponsor's
+			let reason: T::RuntimeHoldReason = HoldReason::SpamBond.into();
+			T::Currency::hold(&reason, &who, bond.amount)
+				.map_err(|_| Error::<T>::InsufficientBond)?;
+			T::Currency::release(&reason, &sponsor, bond.amount, Precision::BestEffort)?;
+
+			let amount = bond.amount;
+			SpamBonds::<T>::insert(&who, bond);
+
+			// Emit event
+			Self::deposit_event(Event::BondAssumed { who, sponsor, amount });
+
+			Ok(())
+		}
+
+		/// Pay `amount` into the challenge reward pool, from which upheld challengers receive
+		/// the base challenge reward.
+		///
+		/// Emits `RewardPoolFunded` event on success.
+		#[pallet::call_index(99)]
+		#[pallet::weight(T::WeightInfo::fund_reward_pool())]
+		pub fn fund_reward_pool(origin: OriginFor<T>, amount: BalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!amount.is_zero(), Error::<T>::ZeroRewardPoolFunding);
+
+			T::Currency::transfer(
+				&who,
+				&Self::reward_pool_account(),
+				amount,
+				Preservation::Preserve,
+			)?;
+
+			// Emit event
+			Self::deposit_event(Event::RewardPoolFunded { who, amount });
+
+			Ok(())
+		}
+
+		/// Flag the profile of an account without activity for `InactivityThreshold` blocks as
+		/// dormant.
+		///
+		/// Nobody can message a dormant profile until its owner sends or acknowledges a message,
+		/// approves or removes a contact, or updates the profile, which reactivates it.
+		///
+		/// Parameters:
+		/// - `who`: The inactive account
+		///
+		/// Emits `ProfileFlaggedDormant` event on success.
+		#[pallet::call_index(100)]
+		#[pallet::weight(T::WeightInfo::flag_inactive())]
+		pub fn flag_inactive(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			let flagged_by = ensure_signed(origin)?;
+			let mut profile = UserProfiles::<T>::get(&who).ok_or(Error::<T>::ProfileNotFound)?;
+			ensure!(profile.dormant_since.is_none(), Error::<T>::AlreadyDormant);
+
+			// Profiles without recorded activity count as inactive since genesis
+			let now = frame_system::Pallet::<T>::block_number();
+			let last_active = AccountStats::<T>::get(&who).last_active;
+			ensure!(
+				now >= last_active.saturating_add(T::InactivityThreshold::get()),
+				Error::<T>::AccountNotInactive
+			);
+
+			profile.dormant_since = Some(now);
+			UserProfiles::<T>::insert(&who, profile);
+
+			// Emit event
+			Self::deposit_event(Event::ProfileFlaggedDormant { who, flagged_by });
+
+			Ok(())
+		}
+
+		/// Remove a dormant profile without activity for `DormantSweepThreshold` blocks,
+		/// refunding its spam bond to whoever it is held from.
+		///
+		/// Like `refund_bond`, fails while spam challenges against the account are open or its
+		/// bond is still locked. Contacts and messages already recorded are left in place.
+		///
+		/// Parameters:
+		/// - `who`: The dormant account
+		///
+		/// Emits `DormantProfileSwept` event on success.
+		#[pallet::call_index(101)]
+		#[pallet::weight(T::WeightInfo::sweep_dormant_profile())]
+		pub fn sweep_dormant_profile(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::SweepDormantProfile, &who);
+			ensure!(
+				UserProfiles::<T>::get(&who).is_some_and(|profile| profile.dormant_since.is_some()),
+				Error::<T>::ProfileNotDormant
+			);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let last_active = AccountStats::<T>::get(&who).last_active;
+			ensure!(
+				now >= last_active.saturating_add(T::DormantSweepThreshold::get()),
+				Error::<T>::SweepTooEarly
+			);
+			ensure!(
+				OpenChallengeCount::<T>::get(&who).is_zero(),
+				Error::<T>::OpenChallengesPending
+			);
+			ensure!(Self::locked_bond(&who).is_zero(), Error::<T>::BondLocked);
+
+			// Refund the bond to whoever it is held from
+			let bond = SpamBonds::<T>::take(&who);
+			let refunded = if bond.amount.is_zero() {
+				Zero::zero()
+			} else {
+				T::Currency::release(
+					&HoldReason::SpamBond.into(),
+					bond.payer(&who),
+					bond.amount,
+					Precision::BestEffort,
+				)?
+			};
+
+			// Remove the profile
+			Self::remove_profile(&who);
+			BondLocks::<T>::remove(&who);
+
+			// Emit event
+			Self::deposit_event(Event::DormantProfileSwept { who, refunded });
+
+			Ok(())
+		}
+
+		/// Set or clear the hint naming the push relay that notifies the caller of new
+		/// messages.
+		///
+		/// While a hint is set, every direct message to the caller also emits `NotifyRelay`
+		/// with the hash of the hint. The hint is dropped if the profile is removed. Setting
+		/// the hint already in place, or clearing an unset one, fails with
+		/// `NotificationHintUnchanged`.
+		///
+		/// Parameters:
+		/// - `hint`: Opaque identifier agreed with the relay, or `None` to stop notifications
+		///
+		/// Emits `NotificationHintSet` event on success.
+		#[pallet::call_index(102)]
+		#[pallet::weight(T::WeightInfo::set_notification_hint())]
+		pub fn set_notification_hint(
+			origin: OriginFor<T>,
+			hint: Option<NotificationHint>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			ensure!(
+				NotificationHooks::<T>::get(&who) != hint,
+				Error::<T>::NotificationHintUnchanged
+			);
+
+			let hint_hash = hint.as_ref().map(|hint| T::Hashing::hash(hint));
+			NotificationHooks::<T>::set(&who, hint);
+
+			Self::deposit_event(Event::NotificationHintSet { who, hint_hash });
+
+			Ok(())
+		}
+
+		/// Verify a received message on-chain and record the outcome.
+		///
+		/// Runs the same check as `verify_message_hash` and stores whether `claimed_hash`
+		/// matched, and at which block, until the message is removed. A message can be
+		/// verified once. A mismatch counts as a spam report against the sender unless the
+		/// caller already reported the message, but is not limited by `MaxReportsPerWindow`.
+		///
+		/// Parameters:
+		/// - `message_id`: The ID of a message addressed to the caller
+		/// - `claimed_hash`: The hash of the message as received off-chain
+		///
+		/// Emits `MessageVerificationRecorded` event on success, followed by `SpamReported`
+		/// on a mismatch.
+		#[pallet::call_index(103)]
+		#[pallet::weight(T::WeightInfo::verify_message_on_chain())]
+		pub fn verify_message_on_chain(
+			origin: OriginFor<T>,
+			message_id: MessageId,
+			claimed_hash: T::Hash,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+
+			// Only the recipient may verify, once per message
+			let matched = Self::verify_message_hash(message_id, claimed_hash)?;
+			let MessageRecord { sender, recipient, .. } =
+				MessageHashes::<T>::get(message_id).ok_or(Error::<T>::MessageNotFound)?;
+			ensure!(recipient == who, Error::<T>::NotAuthorized);
+			ensure!(
+				!MessageVerifications::<T>::contains_key(message_id),
+				Error::<T>::AlreadyVerified
+			);
+
+			let at = frame_system::Pallet::<T>::block_number();
+			MessageVerifications::<T>::insert(message_id, MessageVerification { matched, at });
+			Self::deposit_event(Event::MessageVerificationRecorded { message_id, matched });
+
+			if !matched && !ReportedMessages::<T>::get(message_id, &who) {
+				Self::count_spam_report(message_id, who, sender);
+			}
+
+			Ok(())
+		}
+
+		/// Remove the caller's profile.
+		///
+		/// Fails while spam challenges against the caller are open. The bond is refunded to
+		/// whoever it is held from, unless it still secures messages sent within the last
+		/// `BondUnbondingPeriod` blocks, in which case `refund_bond` releases it once unlocked.
+		///
+		/// Messages already sent stay verifiable until they expire and report
+		/// `MessageStatus::SenderDeparted`. Their inbox payments can be refunded to the caller
+		/// straight away with `refund_inbox_payment`.
+		///
+		/// Emits `ProfileDeregistered` event on success.
+		#[pallet::call_index(104)]
+		#[pallet::weight(T::WeightInfo::deregister_profile())]
+		pub fn deregister_profile(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+			ensure!(
+				OpenChallengeCount::<T>::get(&who).is_zero(),
+				Error::<T>::OpenChallengesPending
+			);
+
+			let refunded = Self::depart(&who)?;
+
+			Self::deposit_event(Event::ProfileDeregistered { who, refunded, forced: false });
+
+			Ok(())
+		}
+
+		/// Remove a profile as `deregister_profile` does, even while spam challenges against
+		/// it are open.
+		///
+		/// The bond then stays held, so challenges upheld later can still slash it, and the
+		/// account claims what is left with `refund_bond` once they are final.
+		///
+		/// Parameters:
+		/// - `who`: The account to deregister
+		///
+		/// Emits `ProfileDeregistered` event on success.
+		#[pallet::call_index(105)]
+		#[pallet::weight(T::WeightInfo::force_deregister_profile())]
+		pub fn force_deregister_profile(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::record_audit(AuditAction::ForceDeregisterProfile, &who);
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			let refunded = Self::depart(&who)?;
+
+			Self::deposit_event(Event::ProfileDeregistered { who, refunded, forced: true });
+
+			Ok(())
+		}
+
+		/// Choose whether the caller's batch sends are reported in one `MessagesSentCompact`
+		/// event or in a `MessageSent` event per message.
+		///
+		/// The choice is dropped if the profile is removed. Enabling compact events while
+		/// enabled, or disabling them while disabled, fails with `CompactEventsUnchanged`.
+		///
+		/// Parameters:
+		/// - `enabled`: Whether to compact the events of batch sends
+		///
+		/// Emits `CompactEventsSet` event on success.
+		#[pallet::call_index(106)]
+		#[pallet::weight(T::WeightInfo::set_compact_events())]
+		pub fn set_compact_events(origin: OriginFor<T>, enabled: bool) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			ensure!(
+				CompactEventSenders::<T>::contains_key(&who) != enabled,
+				Error::<T>::CompactEventsUnchanged
+			);
+			CompactEventSenders::<T>::set(&who, enabled.then_some(()));
+
+			Self::deposit_event(Event::CompactEventsSet { who, enabled });
+
+			Ok(())
+		}
+
+		/// Send up to `MaxSendBatch` messages, each as `send_message_hash` would.
+		///
+		/// The messages get consecutive IDs in the order listed, and the call fails as a whole
+		/// with `ItemFailed`, carrying the position of the message, if any of them cannot be
+		/// sent. Each is charged the protocol fee and counts against the rate limit on its own.
+		/// Replies refund at most `MaxAutoRefundsPerReply` recipient-set deposits across the
+		/// batch, leaving older ones for a later reply.
+		///
+		/// Parameters:
+		/// - `messages`: The recipient and message hash of each message
+		///
+		/// Emits `MessagesSentCompact` event on success if the caller enabled compact events
+		/// and `event_privacy` is `Full`, and `MessageSent` event for every message otherwise.
+		/// `ProtocolFeeCharged` and `NotifyRelay` events are emitted per message either way.
+		#[pallet::call_index(107)]
+		#[pallet::weight(
+			T::WeightInfo::send_message_hash_batch(messages.len() as u32).saturating_add(
+				T::WeightInfo::refund_deposits_on_reply(T::MaxAutoRefundsPerReply::get())
+			)
+		)]
+		pub fn send_message_hash_batch(
+			origin: OriginFor<T>,
+			messages: SendBatchOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+			ensure!(!messages.is_empty(), Error::<T>::EmptySendBatch);
+
+			// Compact events name the sender, which `Minimal` keeps out of events
+			let compact = CompactEventSenders::<T>::contains_key(&sender) &&
+				Self::parameters().event_privacy == EventPrivacyMode::Full;
+			let first_id = NextMessageId::<T>::get();
+			let count = messages.len() as u32;
+			let mut leaves = Vec::new();
+			// The messages share one budget of refunds on reply
+			let mut refunds_left = T::MaxAutoRefundsPerReply::get();
+			Self::try_each_item(messages, |(recipient, message_hash)| {
+				let (message_id, refunded) = Self::do_send_message(
+					sender.clone(),
+					recipient.clone(),
+					message_hash,
+					compact,
+					false,
+					refunds_left,
+				)?;
+				refunds_left.saturating_reduce(refunded);
+				if compact {
+					leaves.push(compact_events::leaf::<T::Hashing, _>(
+						message_id,
+						&recipient,
+						&message_hash,
+					));
+				}
+				Ok(())
+			})?;
+			let refunded = T::MaxAutoRefundsPerReply::get().saturating_sub(refunds_left);
+
+			if compact {
+				Self::deposit_event(Event::MessagesSentCompact {
+					from: sender,
+					count: leaves.len() as u32,
+					first_id,
+					ids_root: compact_events::root::<T::Hashing>(leaves),
+				});
+			}
+
+			Ok(Some(
+				T::WeightInfo::send_message_hash_batch(count)
+					.saturating_add(T::WeightInfo::refund_deposits_on_reply(refunded)),
+			)
+			.into())
+		}
+
+		/// Record a session established with `peer` in an off-chain handshake.
+		///
+		/// Replaces any earlier session the caller recorded with `peer`, under the next epoch.
+		/// The session counts once the peer confirms it with `confirm_session`.
+		///
+		/// Parameters:
+		/// - `peer`: The account the handshake was with
+		/// - `session_commitment`: Commitment to the handshake, which the peer confirms
+		/// - `prekey_index`: Index of the peer's ephemeral key the handshake used, if any
+		///
+		/// Emits `SessionRecorded` event on success.
+		#[pallet::call_index(108)]
+		#[pallet::weight(T::WeightInfo::record_session())]
+		pub fn record_session(
+			origin: OriginFor<T>,
+			peer: T::AccountId,
+			session_commitment: T::Hash,
+			prekey_index: Option<u32>,
+		) -> DispatchResult {
+			let initiator = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::SENDS)?;
+			Self::ensure_not_banned(&initiator)?;
+			ensure!(initiator != peer, Error::<T>::CannotMessageSelf);
+			ensure!(UserProfiles::<T>::contains_key(&initiator), Error::<T>::ProfileNotFound);
+			ensure!(UserProfiles::<T>::contains_key(&peer), Error::<T>::RecipientNotFound);
+			if let Some(index) = prekey_index {
+				ensure!(
+					Self::ephemeral_key(&peer, index).is_some(),
+					Error::<T>::EphemeralKeyNotFound
+				);
+			}
+
+			let epoch = Sessions::<T>::get(&initiator, &peer)
+				.map_or(0, |session| session.epoch.saturating_add(1));
+			Sessions::<T>::insert(
+				&initiator,
+				&peer,
+				SessionInfo {
+					commitment: session_commitment,
+					prekey_index,
+					established_at: frame_system::Pallet::<T>::block_number(),
+					epoch,
+					status: SessionStatus::Pending,
+				},
+			);
+
+			Self::deposit_event(Event::SessionRecorded {
+				initiator,
+				peer,
+				commitment: session_commitment,
+				prekey_index,
+				epoch,
+			});
+
+			Ok(())
+		}
+
+		/// Confirm the session `initiator` recorded with the caller.
+		///
+		/// Parameters:
+		/// - `initiator`: The account that recorded the session
+		/// - `commitment`: The commitment the initiator recorded, which must match
+		///
+		/// Emits `SessionConfirmed` event on success.
+		#[pallet::call_index(109)]
+		#[pallet::weight(T::WeightInfo::confirm_session())]
+		pub fn confirm_session(
+			origin: OriginFor<T>,
+			initiator: T::AccountId,
+			commitment: T::Hash,
+		) -> DispatchResult {
+			let peer = ensure_signed(origin)?;
+			Self::ensure_not_banned(&peer)?;
+
+			let epoch = Sessions::<T>::try_mutate(&initiator, &peer, |session| {
+				let session = session.as_mut().ok_or(Error::<T>::SessionNotFound)?;
+				match session.status {
+					SessionStatus::Pending => {},
+					SessionStatus::Confirmed => return Err(Error::<T>::SessionAlreadyConfirmed),
+					SessionStatus::Terminated => return Err(Error::<T>::SessionNotFound),
+				}
+				ensure!(session.commitment == commitment, Error::<T>::SessionCommitmentMismatch);
+				session.status = SessionStatus::Confirmed;
+				Ok(session.epoch)
+			})?;
+
+			Self::deposit_event(Event::SessionConfirmed { initiator, peer, epoch });
+
+			Ok(())
+		}
+
+		/// Terminate the session `initiator` recorded with `peer`, as either of them.
+		///
+		/// The record is kept, so a session recorded later continues its epochs.
+		///
+		/// Parameters:
+		/// - `initiator`: The account that recorded the session
+		/// - `peer`: The account the session was recorded with
+		///
+		/// Emits `SessionTerminated` event on success.
+		#[pallet::call_index(110)]
+		#[pallet::weight(T::WeightInfo::terminate_session())]
+		pub fn terminate_session(
+			origin: OriginFor<T>,
+			initiator: T::AccountId,
+			peer: T::AccountId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(who == initiator || who == peer, Error::<T>::NotAuthorized);
+
+			let epoch = Sessions::<T>::try_mutate(&initiator, &peer, |session| {
+				let session = session
+					.as_mut()
+					.filter(|session| session.status != SessionStatus::Terminated)
+					.ok_or(Error::<T>::SessionNotFound)?;
+				session.status = SessionStatus::Terminated;
+				Ok::<_, Error<T>>(session.epoch)
+			})?;
+
+			Self::deposit_event(Event::SessionTerminated { initiator, peer, epoch, by: who });
+
+			Ok(())
+		}
+
+		/// Choose whether the caller only receives direct messages from senders it shares a
+		/// confirmed session with.
+		///
+		/// Sessions count in either direction, and the requirement holds for every direct send
+		/// path, paid sends included. The choice is dropped if the profile is removed.
+		/// Requiring sessions while required, or lifting the requirement while lifted, fails
+		/// with `SessionRequirementUnchanged`.
+		///
+		/// Parameters:
+		/// - `required`: Whether to require a confirmed session
+		///
+		/// Emits `SessionRequirementSet` event on success.
+		#[pallet::call_index(111)]
+		#[pallet::weight(T::WeightInfo::set_session_requirement())]
+		pub fn set_session_requirement(origin: OriginFor<T>, required: bool) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_banned(&who)?;
+			let mut profile = UserProfiles::<T>::get(&who).ok_or(Error::<T>::ProfileNotFound)?;
+
+			ensure!(
+				profile.session_required != required,
+				Error::<T>::SessionRequirementUnchanged
+			);
+			profile.session_required = required;
+			UserProfiles::<T>::insert(&who, profile);
+
+			Self::deposit_event(Event::SessionRequirementSet { who, required });
+
+			Ok(())
+		}
+
+		/// Register the key of a new device of the caller.
+		///
+		/// The device gets the next unused device ID. An account keeps at most
+		/// `MaxDevicesPerAccount` devices; remove or replace one to make room. `DeviceDeposit`
+		/// is held from the caller until the device is removed.
+		///
+		/// Parameters:
+		/// - `key`: The device's public key (max 256 bytes)
+		/// - `attestation`: Proof that the key belongs to the caller, for counterparties
+		///
+		/// Emits `DeviceKeyAdded` event on success.
+		#[pallet::call_index(112)]
+		#[pallet::weight(T::WeightInfo::add_device_key())]
+		pub fn add_device_key(
+			origin: OriginFor<T>,
+			key: Vec<u8>,
+			attestation: DeviceAttestation,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+			ensure!(!key.is_empty(), Error::<T>::InvalidPublicKey);
+			ensure!(!attestation.is_empty(), Error::<T>::EmptyDeviceAttestation);
+			let key_hash = Self::key_hash(&key);
+			let public_key: PublicKey =
+				key.try_into().map_err(|_| Error::<T>::PublicKeyTooLarge)?;
+
+			let updated_at = frame_system::Pallet::<T>::block_number();
+			let deposit = T::DeviceDeposit::get();
+			let device_id = DeviceSets::<T>::try_mutate(&who, |set| {
+				let set = set.get_or_insert_with(Default::default);
+				ensure!(
+					(set.devices.len() as u32) < T::MaxDevicesPerAccount::get(),
+					Error::<T>::TooManyDevices
+				);
+				T::Currency::hold(&HoldReason::DeviceDeposit.into(), &who, deposit)
+					.map_err(|_| Error::<T>::InsufficientDeviceDeposit)?;
+				let device_id = set.next_device_id;
+				set.devices
+					.try_push(DeviceKeyRecord {
+						device_id,
+						public_key,
+						attestation,
+						deposit,
+						updated_at,
+					})
+					.map_err(|_| Error::<T>::TooManyDevices)?;
+				set.next_device_id.saturating_inc();
+				Ok::<_, Error<T>>(device_id)
+			})?;
+			let devices_revision = Self::bump_devices_revision(&who);
+
+			Self::deposit_event(Event::DeviceKeyAdded {
+				who,
+				device_id,
+				key_hash,
+				devices_revision,
+			});
+
+			Ok(())
+		}
+
+		/// Replace the key of one of the caller's devices, keeping its device ID.
+		///
+		/// Parameters:
+		/// - `device_id`: The device whose key to replace
+		/// - `new_key`: The device's new public key (max 256 bytes)
+		/// - `attestation`: Proof that the new key belongs to the caller
+		///
+		/// Emits `DeviceKeyReplaced` event on success.
+		#[pallet::call_index(113)]
+		#[pallet::weight(T::WeightInfo::replace_device_key())]
+		pub fn replace_device_key(
+			origin: OriginFor<T>,
+			device_id: DeviceId,
+			new_key: Vec<u8>,
+			attestation: DeviceAttestation,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			Self::ensure_not_banned(&who)?;
+			ensure!(!new_key.is_empty(), Error::<T>::InvalidPublicKey);
+			ensure!(!attestation.is_empty(), Error::<T>::EmptyDeviceAttestation);
+			let new_key_hash = Self::key_hash(&new_key);
+			let public_key: PublicKey =
+				new_key.try_into().map_err(|_| Error::<T>::PublicKeyTooLarge)?;
+
+			let updated_at = frame_system::Pallet::<T>::block_number();
+			let old_key_hash = DeviceSets::<T>::try_mutate(&who, |set| {
+				let set = set.as_mut().ok_or(Error::<T>::DeviceNotFound)?;
+				let device = set
+					.devices
+					.iter_mut()
+					.find(|device| device.device_id == device_id)
+					.ok_or(Error::<T>::DeviceNotFound)?;
+				let old_key_hash = Self::key_hash(&device.public_key);
+				device.public_key = public_key;
+				device.attestation = attestation;
+				device.updated_at = updated_at;
+				Ok::<_, Error<T>>(old_key_hash)
+			})?;
+			let devices_revision = Self::bump_devices_revision(&who);
+
+			Self::deposit_event(Event::DeviceKeyReplaced {
+				who,
+				device_id,
+				old_key_hash,
+				new_key_hash,
+				devices_revision,
+			});
+
+			Ok(())
+		}
+
+		/// Remove one of the caller's devices, freeing its slot and releasing its deposit. Its
+		/// device ID is not reused.
+		///
+		/// Parameters:
+		/// - `device_id`: The device to remove
+		///
+		/// Emits `DeviceKeyRemoved` event on success.
+		#[pallet::call_index(114)]
+		#[pallet::weight(T::WeightInfo::remove_device_key())]
+		pub fn remove_device_key(origin: OriginFor<T>, device_id: DeviceId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let device = DeviceSets::<T>::try_mutate(&who, |set| {
+				let devices = &mut set.as_mut().ok_or(Error::<T>::DeviceNotFound)?.devices;
+				let index = devices
+					.iter()
+					.position(|device| device.device_id == device_id)
+					.ok_or(Error::<T>::DeviceNotFound)?;
+				Ok::<_, Error<T>>(devices.remove(index))
+			})?;
+			Self::release_device_deposit(&who, device.deposit);
+			let devices_revision = Self::bump_devices_revision(&who);
+
+			Self::deposit_event(Event::DeviceKeyRemoved { who, device_id, devices_revision });
+
+			Ok(())
+		}
+	}
+
+	#[pallet::view_functions_experimental]
+	impl<T: Config> Pallet<T> {
+		/// Accounts approved as contacts by `who`, starting after `after`.
+		///
+		/// Returns at most `limit` accounts, capped at `MAX_VIEW_PAGE_SIZE`. Pass the last
+		/// account of a page as `after` to fetch the next one.
+		pub fn contacts_of(
+			who: T::AccountId,
+			after: Option<T::AccountId>,
+			limit: u32,
+		) -> Vec<T::AccountId> {
+			let contacts = match after {
+				Some(after) => ApprovedContacts::<T>::iter_prefix_from(
+					&who,
+					ApprovedContacts::<T>::hashed_key_for(&who, &after),
+				),
+				None => ApprovedContacts::<T>::iter_prefix(&who),
+			};
+			contacts
+				.filter_map(|(contact, approved)| approved.then_some(contact))
+				.take(limit.min(MAX_VIEW_PAGE_SIZE) as usize)
+				.collect()
+		}
+
+		/// Whether `who` has registered a profile.
+		pub fn has_profile(who: T::AccountId) -> bool {
+			UserProfiles::<T>::contains_key(who)
+		}
+
+		/// Cumulative messaging activity of `who`.
+		pub fn account_stats(who: T::AccountId) -> AccountStatisticsOf<T> {
+			AccountStats::<T>::get(who)
+		}
+
+		/// Activity across the pallet.
+		pub fn global_stats() -> GlobalStatistics {
+			let CumulativeStatistics { messages, profiles, groups } = GlobalStats::<T>::get();
+			GlobalStatistics {
+				messages,
+				profiles,
+				groups,
+				stored_messages: Self::message_count(),
+				stored_profiles: Self::profile_count(),
+			}
+		}
+
+		/// Last activity of `who` and whether its profile is dormant, or `None` if it has no
+		/// profile.
+		pub fn profile_activity(who: T::AccountId) -> Option<ProfileActivityOf<T>> {
+			UserProfiles::<T>::get(&who).map(|profile| ProfileActivity {
+				last_activity: AccountStats::<T>::get(&who).last_active,
+				dormant_since: profile.dormant_since,
+			})
+		}
+
+		/// Whether `sender` can message `recipient` with `send_message_hash` now, or the first
+		/// check it would fail.
+		///
+		/// Every send path runs the same checks before anything else, so the two agree.
+		pub fn can_message(
+			sender: T::AccountId,
+			recipient: T::AccountId,
+		) -> Result<(), CanMessageError> {
+			Self::ensure_can_message(&sender, &recipient, false).map(|_| ())
+		}
+
+		/// Contact requests waiting for the decision of `who`, oldest first, as the requester
+		/// and the block it was sent at.
+		///
+		/// Returns at most `limit` requests; requests carry no expiry or introduction.
+		pub fn incoming_requests(
+			who: T::AccountId,
+			limit: u32,
+		) -> Vec<(T::AccountId, BlockNumberFor<T>)> {
+			let mut requests = IncomingContactRequests::<T>::get(who).into_inner();
+			requests.truncate(limit as usize);
+			requests
+		}
+
+		/// Contact requests sent by `who` that are still pending, oldest first, as the target
+		/// and the block it was sent at.
+		///
+		/// Returns at most `limit` requests.
+		pub fn outgoing_requests(
+			who: T::AccountId,
+			limit: u32,
+		) -> Vec<(T::AccountId, BlockNumberFor<T>)> {
+			let mut requests = OutgoingContactRequests::<T>::get(who).into_inner();
+			requests.truncate(limit as usize);
+			requests
+		}
+
+		/// Messages sent to `who` with an ID greater than `after`, in ID order.
+		///
+		/// Returns at most `limit` messages, capped at `MAX_VIEW_PAGE_SIZE`. Pass the last
+		/// ID of a page as `after` to fetch the next one. Messages removed by governance
+		/// are not listed.
+		///
+		/// Returns nothing if `since_revision` is given and the inbox has not changed since,
+		/// so a client can pass the `inbox_revision` it last synced at.
+		pub fn inbox_of(
+			who: T::AccountId,
+			since_revision: Option<u64>,
+			after: Option<MessageId>,
+			limit: u32,
+		) -> Vec<(MessageId, MessageSummaryOf<T>)> {
+			if since_revision.is_some_and(|since| InboxRevisions::<T>::get(&who) <= since) {
+				return Vec::new();
+			}
+			let start = after.map_or(0, |id| id.saturating_add(1));
+			(start..NextMessageId::<T>::get())
+				.filter_map(|message_id| {
+					let MessageRecord { sender, recipient, hash, sent_at } =
+						Self::message(message_id)?;
+					if recipient != who {
+						return None;
+					}
+					Some((message_id, Self::message_summary(message_id, sender, hash, sent_at)))
+				})
+				.take(limit.min(MAX_VIEW_PAGE_SIZE) as usize)
+				.collect()
+		}
+
+		/// Revision of the inbox of `who`, bumped on every change to the messages listed by
+		/// `inbox_of`.
+		///
+		/// A message expiring leaves the revision alone, as clients can tell from `sent_at`;
+		/// pruning it bumps the revision.
+		pub fn inbox_revision(who: T::AccountId) -> u64 {
+			InboxRevisions::<T>::get(who)
+		}
+
+		/// Messages exchanged between `a` and `b` in either direction, starting at ID `from`.
+		///
+		/// Returns at most `limit` messages in ID order, capped at `MAX_VIEW_PAGE_SIZE`.
+		pub fn conversation(
+			a: T::AccountId,
+			b: T::AccountId,
+			from: MessageId,
+			limit: u32,
+		) -> Vec<(MessageId, MessageSummaryOf<T>)> {
+			(from..NextMessageId::<T>::get())
+				.filter_map(|message_id| {
+					let MessageRecord { sender, recipient, hash, sent_at } =
+						Self::message(message_id)?;
+					if !(sender == a && recipient == b || sender == b && recipient == a) {
+						return None;
+					}
+					Some((message_id, Self::message_summary(message_id, sender, hash, sent_at)))
+				})
+				.take(limit.min(MAX_VIEW_PAGE_SIZE) as usize)
+				.collect()
+		}
+
+		/// Messages between `a` and `b` with a sequence number of at least `from_seq`, in
+		/// sequence order, whichever of them sent each message.
+		///
+		/// Returns at most `limit` messages, capped at `MAX_CONVERSATION_PAGE_SIZE`. Pass one
+		/// more than the sequence number of the last message of a page as `from_seq` to fetch
+		/// the next one. Messages below `lowest_retained_seq` were pruned and cannot be loaded.
+		pub fn conversation_page(
+			a: T::AccountId,
+			b: T::AccountId,
+			from_seq: u64,
+			limit: u32,
+		) -> ConversationPageOf<T> {
+			let conversation_id = Self::conversation_id(&a, &b);
+			let next_seq = ConversationStats::<T>::get(conversation_id).next_seq;
+			let lowest_retained_seq = Self::lowest_conversation_seq(conversation_id, next_seq);
+			let expiry = Self::parameters().message_expiry;
+			let messages = (from_seq.max(lowest_retained_seq)..next_seq)
+				.filter_map(|seq| {
+					let message_id = ConversationMessages::<T>::get(conversation_id, seq)?;
+					let MessageRecord { sender, recipient, hash, sent_at } =
+						MessageHashes::<T>::get(message_id)?;
+					Some(ConversationMessage {
+						message_id,
+						seq,
+						sender,
+						recipient,
+						hash,
+						sent_at,
+						expires_at: sent_at.saturating_add(expiry),
+						status: Self::message_status(message_id),
+					})
+				})
+				.take(limit.min(MAX_CONVERSATION_PAGE_SIZE) as usize)
+				.collect();
+			ConversationPage { lowest_retained_seq, next_seq, messages }
+		}
+
+		/// Summary of the conversation between `a` and `b`, seen from `a`, or `None` if neither
+		/// has sent the other a message.
+		///
+		/// Messages carried by relays or claimed from pending storage are not part of a
+		/// conversation and are not counted. Like all chain state, the summary can be read by
+		/// anyone, not only by `a` and `b`.
+		pub fn conversation_summary(
+			a: T::AccountId,
+			b: T::AccountId,
+		) -> Option<ConversationSummaryOf<T>> {
+			let stats = ConversationStats::<T>::try_get(Self::conversation_id(&a, &b)).ok()?;
+			let (first, second) = stats.unacknowledged;
+			let (unacknowledged_sent, unacknowledged_received) =
+				if a <= b { (first, second) } else { (second, first) };
+			Some(ConversationSummary {
+				messages: stats.messages,
+				unacknowledged_sent,
+				unacknowledged_received,
+				last_message_id: stats.last_message_id,
+				last_activity: stats.last_activity,
+			})
+		}
+
+		/// Spam bond held by `who` and what it must hold to keep sending.
+		pub fn bond_status(who: T::AccountId) -> BondStatusOf<T> {
+			let now = frame_system::Pallet::<T>::block_number();
+			let lock = BondLocks::<T>::get(&who).filter(|lock| lock.until > now);
+			BondStatus {
+				bonded: Self::bond(&who).unwrap_or_else(Zero::zero),
+				required: Self::required_bond(&who),
+				locked: lock.as_ref().map_or_else(Zero::zero, |lock| lock.amount),
+				locked_until: lock.map(|lock| lock.until),
+				strikes: Self::current_strikes(&who),
+			}
+		}
+
+		/// Whether `a` has approved `b` as a contact.
+		pub fn is_contact(a: T::AccountId, b: T::AccountId) -> bool {
+			ApprovedContacts::<T>::get(&a, &b)
+		}
+
+		/// The message stored under `message_id`, if any.
+		///
+		/// Recovers what `MessageSentMinimal` leaves out of the event.
+		pub fn message_info(
+			message_id: MessageId,
+		) -> Option<MessageRecordView<T::AccountId, T::Hash, BlockNumberFor<T>>> {
+			Self::message_view(message_id)
+		}
+
+		/// The message sent with `hash` and its ID, if it has not been pruned or removed.
+		///
+		/// Lets a recipient holding a ciphertext find its record without knowing the ID.
+		pub fn message_by_hash(
+			hash: T::Hash,
+		) -> Option<(MessageId, MessageRecordView<T::AccountId, T::Hash, BlockNumberFor<T>>)> {
+			let message_id = MessageIdByHash::<T>::get(hash)?;
+			Self::message_view(message_id).map(|view| (message_id, view))
+		}
+
+		/// Groups listed for discovery, starting after `after`.
+		///
+		/// Returns at most `limit` groups, capped at `MAX_VIEW_PAGE_SIZE`, in storage order.
+		/// Pass the last group of a page as `after` to fetch the next one.
+		pub fn discoverable_groups(after: Option<GroupId>, limit: u32) -> Vec<GroupId> {
+			let groups = match after {
+				Some(after) => DiscoverableGroups::<T>::iter_keys_from(
+					DiscoverableGroups::<T>::hashed_key_for(after),
+				),
+				None => DiscoverableGroups::<T>::iter_keys(),
+			};
+			groups.take(limit.min(MAX_VIEW_PAGE_SIZE) as usize).collect()
+		}
+
+		/// Messages of a group with a sequence number greater than `after_seq`, in sequence
+		/// order, or `None` if the group does not exist.
+		///
+		/// Returns at most `limit` messages, capped at `MAX_VIEW_PAGE_SIZE`. Pass the sequence
+		/// number of the last message of a page as `after_seq` to fetch the next one. Messages
+		/// below `lowest_retained_seq` were pruned and cannot be synced.
+		pub fn group_messages_after(
+			group_id: GroupId,
+			after_seq: Option<u64>,
+			limit: u32,
+		) -> Option<GroupMessagesPageOf<T>> {
+			let next_seq = Groups::<T>::get(group_id)?.next_seq;
+			let lowest_retained_seq = Self::lowest_retained_seq(group_id, next_seq);
+			let start = after_seq.map_or(0, |seq| seq.saturating_add(1)).max(lowest_retained_seq);
+			let messages = (start..next_seq)
+				.filter_map(|seq| {
+					let message_id = GroupMessagesBySeq::<T>::get(group_id, seq)?;
+					Some((message_id, GroupMessages::<T>::get(group_id, message_id)?))
+				})
+				.take(limit.min(MAX_VIEW_PAGE_SIZE) as usize)
+				.collect();
+			Some(GroupMessagesPage { lowest_retained_seq, next_seq, messages })
+		}
+
+		/// Stealth messages whose recipient tag starts with `prefix`, starting after `after`.
+		///
+		/// Returns at most `limit` messages, capped at `MAX_VIEW_PAGE_SIZE`, in storage order.
+		/// Pass the last ID of a page as `after` to fetch the next one.
+		pub fn messages_by_tag_prefix(
+			prefix: stealth::TagPrefix,
+			after: Option<MessageId>,
+			limit: u32,
+		) -> Vec<(MessageId, StealthMessageRecordOf<T>)> {
+			let message_ids = match after {
+				Some(after) => StealthMessagesByPrefix::<T>::iter_key_prefix_from(
+					prefix,
+					StealthMessagesByPrefix::<T>::hashed_key_for(prefix, after),
+				),
+				None => StealthMessagesByPrefix::<T>::iter_key_prefix(prefix),
+			};
+			message_ids
+				.filter_map(|message_id| Some((message_id, StealthMessages::<T>::get(message_id)?)))
+				.take(limit.min(MAX_VIEW_PAGE_SIZE) as usize)
+				.collect()
+		}
+
+		/// Evidence revealed for the open challenge against `message_id`, if any.
+		pub fn challenge_evidence(message_id: MessageId) -> Option<Evidence> {
+			ChallengeEvidence::<T>::get(message_id)?.evidence
+		}
+
+		/// Bond, strikes, rate limit and restrictions deciding whether `who` can send.
+		///
+		/// Reads a fixed number of storage items and writes nothing; pending strike decay and
+		/// elapsed rate-limit windows are applied to the values returned.
+		pub fn standing(who: T::AccountId) -> AccountStandingOf<T> {
+			let now = frame_system::Pallet::<T>::block_number();
+			let strikes = Self::current_strikes(&who);
+			let bonded = SpamBonds::<T>::get(&who).amount;
+
+			let rate_limit = SendRateLimits::<T>::get(&who);
+			let sent = if now >= rate_limit.window_start.saturating_add(T::RateLimitWindow::get())
+			{
+				0
+			} else {
+				rate_limit.sent
+			};
+
+			let fee = Self::parameters().message_protocol_fee;
+			let spendable =
+				T::Currency::reducible_balance(&who, Preservation::Preserve, Fortitude::Polite);
+			let message_credits = MessageCredits::<T>::get(&who);
+
+			let open_challenges = OpenChallengeCount::<T>::get(&who);
+			let bond_locked_until = BondLocks::<T>::get(&who)
+				.filter(|lock| lock.until > now && !lock.amount.is_zero())
+				.map(|lock| lock.until);
+
+			AccountStanding {
+				registered: UserProfiles::<T>::contains_key(&who),
+				banned: Self::is_banned(&who),
+				frozen_until: FrozenSenders::<T>::get(&who).filter(|until| now < *until),
+				sends_paused: Self::is_paused(PausedCalls::SENDS),
+				strikes,
+				graylisted: strikes >= Self::graylist_threshold(),
+				bonded,
+				required_bond: Self::required_bond_for(&who, strikes),
+				rate_limit_remaining: Self::rate_limit_for(strikes).saturating_sub(sent),
+				can_pay_protocol_fee: fee.is_zero() || message_credits > 0 || spendable >= fee,
+				message_credits,
+				open_challenges,
+				bond_locked_until,
+				can_refund_bond: !bonded.is_zero() &&
+					open_challenges.is_zero() &&
+					bond_locked_until.is_none(),
+			}
+		}
+
+		/// Check each hash in `batch` against the message stored under its ID.
+		///
+		/// Results are returned in the order of `batch`. A message listed more than once is
+		/// looked up once.
+		pub fn verify_message_batch(batch: VerificationBatch<T::Hash>) -> Vec<VerificationResult> {
+			Self::verify_messages(batch.into_inner())
+		}
+
+		/// Revision of the device keys of `who`, bumped whenever a device is added, replaced
+		/// or removed.
+		pub fn devices_revision(who: T::AccountId) -> u32 {
+			DevicesRevisions::<T>::get(who)
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Public key registered by `who`, or `None` if it has no profile.
+		pub fn profile(who: &T::AccountId) -> Option<PublicKey> {
+			UserProfiles::<T>::get(who).map(|profile| profile.public_key)
+		}
+
+		/// Number of registered profiles.
+		pub fn profile_count() -> u32 {
+			UserProfiles::<T>::count()
+		}
+
+		/// Number of stored direct messages.
+		///
+		/// Only kept from storage version 7 on, see [`crate::migrations::v7`].
+		pub fn message_count() -> u32 {
+			MessageHashes::<T>::count()
+		}
+
+		/// Role of `who` in group `group_id`, or `None` if it is not a member.
+		pub fn group_role(group_id: GroupId, who: &T::AccountId) -> Option<GroupRole> {
+			GroupMembers::<T>::get(group_id, who).map(|member| member.role)
+		}
+
+		/// Latest moderation actions taken in group `group_id`, oldest first.
+		pub fn group_moderation_log(group_id: GroupId) -> Vec<GroupModerationEntryOf<T>> {
+			GroupModerationLogs::<T>::get(group_id).into_inner()
+		}
+
+		/// Spam bond deposited by `who`, or `None` if it has none.
+		pub fn bond(who: &T::AccountId) -> Option<BalanceOf<T>> {
+			let bond = SpamBonds::<T>::get(who).amount;
+			(!bond.is_zero()).then_some(bond)
+		}
+
+		/// The message stored under `message_id`, or `None` if it was never sent or has been
+		/// pruned.
+		///
+		/// Also reads messages not yet rewritten by a running multi-block v2 migration.
+		pub fn message(message_id: MessageId) -> Option<MessageRecordOf<T>> {
+			if crate::migrations::v2::is_migrated::<T>(message_id) {
+				return MessageHashes::<T>::get(message_id);
+			}
+			let (hash, sent_at, sender, recipient) =
+				crate::migrations::v2::MessageHashes::<T>::get(message_id)?;
+			Some(MessageRecord { sender, recipient, hash, sent_at })
+		}
+
+		/// ID of the live or not yet pruned message sent with `hash`, or `None` if there is none.
+		pub fn message_id_by_hash(hash: T::Hash) -> Option<MessageId> {
+			MessageIdByHash::<T>::get(hash)
+		}
+
+		/// Administrative actions recorded in the audit log, oldest first.
+		pub fn audit_log() -> Vec<AuditEntryOf<T>> {
+			AdminAuditLog::<T>::get().into_inner()
+		}
+
+		/// Strike count of an account after applying pending decay, without writing to storage.
+		pub fn current_strikes(who: &T::AccountId) -> u32 {
+			Self::decayed(Reputation::<T>::get(who)).strikes
+		}
+
+		/// Whether an account is graylisted, accounting for pending strike decay.
+		pub fn is_graylisted(who: &T::AccountId) -> bool {
+			Self::current_strikes(who) >= Self::graylist_threshold()
+		}
+
+		/// Spam bond an account must hold to send messages.
+		pub fn required_bond(who: &T::AccountId) -> BalanceOf<T> {
+			Self::required_bond_for(who, Self::current_strikes(who))
+		}
+
+		/// Messages an account may send per rate-limit window.
+		pub fn rate_limit(who: &T::AccountId) -> u32 {
+			Self::rate_limit_for(Self::current_strikes(who))
+		}
+
+		/// Bond amount still securing recently sent messages.
+		pub fn locked_bond(who: &T::AccountId) -> BalanceOf<T> {
+			BondLocks::<T>::get(who)
+				.filter(|lock| lock.until > frame_system::Pallet::<T>::block_number())
+				.map_or_else(Zero::zero, |lock| lock.amount)
+		}
+
+		/// Account holding slashed bonds until the challenge that slashed them is final.
+		pub fn escrow_account() -> T::AccountId {
+			T::PalletId::get().into_sub_account_truncating(b"escrow")
+		}
+
+		/// Account holding the challenge reward pool.
+		pub fn reward_pool_account() -> T::AccountId {
+			T::PalletId::get().into_sub_account_truncating(b"rewards")
+		}
+
+		/// Balance of the challenge reward pool, including its existential deposit.
+		pub fn reward_pool_balance() -> BalanceOf<T> {
+			T::Currency::balance(&Self::reward_pool_account())
+		}
+
+		/// Outstanding spam reports against an account after applying pending decay.
+		pub fn current_reports(who: &T::AccountId) -> u32 {
+			Self::decayed_reports(SpamReports::<T>::get(who)).reports
+		}
+
+		/// Whether either account recorded a session with the other that the other confirmed.
+		pub fn has_confirmed_session(a: &T::AccountId, b: &T::AccountId) -> bool {
+			let confirmed = |session: Option<SessionInfoOf<T>>| {
+				session.is_some_and(|session| session.status == SessionStatus::Confirmed)
+			};
+			confirmed(Sessions::<T>::get(a, b)) || confirmed(Sessions::<T>::get(b, a))
+		}
+
+		/// Whether both accounts have approved each other.
+		pub fn are_mutual_contacts(a: &T::AccountId, b: &T::AccountId) -> bool {
+			ApprovedContacts::<T>::get(a, b) && ApprovedContacts::<T>::get(b, a)
+		}
+
+		/// Current pallet parameters, falling back to the configured constants while unset.
+		pub fn parameters() -> PalletParametersOf<T> {
+			Parameters::<T>::get().unwrap_or_else(|| PalletParameters {
+				spam_bond: T::SpamBond::get(),
+				message_expiry: T::MessageHashExpiry::get(),
+				max_contacts: T::MaxContactsPerUser::get(),
+				event_privacy: EventPrivacyMode::Full,
+				message_protocol_fee: Zero::zero(),
+				exempt_mutual_contacts: false,
+				challenger_reward_percent: T::ChallengerRewardPercent::get(),
+				auto_slash_percent: T::AutoSlashPercent::get(),
+				base_challenge_reward: T::BaseChallengeReward::get(),
+				slash_destination: SlashDestination::Treasury,
+			})
+		}
+
+		/// Whether an account is in the moderator set.
+		pub fn is_moderator(who: &T::AccountId) -> bool {
+			Moderators::<T>::get().contains(who)
+		}
+
+		/// Whether a sender is currently frozen by a moderator.
+		pub fn is_frozen(who: &T::AccountId) -> bool {
+			FrozenSenders::<T>::get(who)
+				.is_some_and(|until| frame_system::Pallet::<T>::block_number() < until)
+		}
+
+		/// Whether any of the given call groups is paused.
+		pub fn is_paused(calls: PausedCalls) -> bool {
+			Paused::<T>::get().intersects(calls)
+		}
+
+		/// Whether `sender` may message `recipient` without paying its inbox price.
+		pub fn has_inbox_access(recipient: &T::AccountId, sender: &T::AccountId) -> bool {
+			ApprovedContacts::<T>::get(recipient, sender) || Self::has_inbox_pass(recipient, sender)
+		}
+
+		/// Whether `recipient` granted `sender` an inbox pass that has not run out.
+		fn has_inbox_pass(recipient: &T::AccountId, sender: &T::AccountId) -> bool {
+			InboxPasses::<T>::get(recipient, sender)
+				.is_some_and(|until| frame_system::Pallet::<T>::block_number() < until)
+		}
+
+		/// Tier and inbox price `sender` must pay to message `recipient`, or `InboxClosed` if
+		/// the recipient does not accept paid messages from the sender's class.
+		pub fn inbox_price_for(
+			recipient: &T::AccountId,
+			sender: &T::AccountId,
+		) -> Result<(InboxTier, BalanceOf<T>), DispatchError> {
+			// Approved senders reach the recipient without paying
+			if Self::has_inbox_access(recipient, sender) {
+				return Ok((InboxTier::Contact, Zero::zero()));
+			}
+			let (tier, price) = match InboxPricings::<T>::get(recipient) {
+				Some(pricing) if T::IdentityVerifier::contains(sender) =>
+					(InboxTier::Verified, pricing.verified),
+				Some(pricing) => (InboxTier::Others, pricing.others),
+				None => (InboxTier::Others, InboxPrices::<T>::get(recipient)),
+			};
+			Ok((tier, price.ok_or(Error::<T>::InboxClosed)?))
+		}
+
+		/// Whether a message from `sender` to `recipient` may be sent without a fee.
+		///
+		/// Both accounts must be mutual contacts with active bonds, and the sender must
+		/// have quota left both in its rate-limit window and within the current block.
+		pub fn is_fee_exempt(sender: &T::AccountId, recipient: &T::AccountId) -> bool {
+			if Self::is_paused(PausedCalls::SENDS) || Self::is_frozen(sender) {
+				return false;
+			}
+			if Self::is_banned(sender) || Self::is_banned(recipient) {
+				return false;
+			}
+			if sender == recipient || !Self::are_mutual_contacts(sender, recipient) {
+				return false;
+			}
+			if SpamBonds::<T>::get(sender).amount < Self::required_bond(sender) ||
+				SpamBonds::<T>::get(recipient).amount.is_zero()
+			{
+				return false;
+			}
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let state = SendRateLimits::<T>::get(sender);
+			let sent = if now >= state.window_start.saturating_add(T::RateLimitWindow::get()) {
+				0
+			} else {
+				state.sent
+			};
+			let sent_in_block = if state.last_block == now { state.sent_in_block } else { 0 };
+			sent < Self::rate_limit(sender) && sent_in_block < T::MaxFreeMessagesPerBlock::get()
+		}
+
+		fn required_bond_for(who: &T::AccountId, strikes: u32) -> BalanceOf<T> {
+			let registration_bond =
+				UserProfiles::<T>::get(who).and_then(|profile| profile.registration_bond);
+			Self::bond_requirement(registration_bond, strikes)
+		}
+
+		/// Bond required of an account that posted `registration_bond` when it registered and
+		/// has `strikes` strikes.
+		fn bond_requirement(registration_bond: Option<BalanceOf<T>>, strikes: u32) -> BalanceOf<T> {
+			// Accounts registered before bonds were recorded posted the configured constant
+			let bond = registration_bond.unwrap_or_else(T::SpamBond::get);
+			if strikes >= T::BondPenaltyStrikes::get() {
+				bond.saturating_add(bond)
+			} else {
+				bond
+			}
+		}
+
+		fn rate_limit_for(strikes: u32) -> u32 {
+			let limit = T::MaxMessagesPerWindow::get();
+			if strikes >= T::RateLimitPenaltyStrikes::get() {
+				limit / 2
+			} else {
+				limit
+			}
+		}
+
+		/// Apply elapsed strike decay to a reputation record.
+		fn decayed(
+			mut record: ReputationRecord<BlockNumberFor<T>>,
+		) -> ReputationRecord<BlockNumberFor<T>> {
+			Self::decay(&mut record.strikes, &mut record.last_decay, T::StrikeDecayPeriod::get());
+			record
+		}
+
+		/// Apply elapsed report decay to a spam report record.
+		fn decayed_reports(
+			mut record: SpamReportRecord<BlockNumberFor<T>>,
+		) -> SpamReportRecord<BlockNumberFor<T>> {
+			Self::decay(&mut record.reports, &mut record.last_decay, T::ReportDecayPeriod::get());
+			record
+		}
+
+		/// Forgive one unit of `count` for every `period` blocks elapsed since `last_decay`.
+		fn decay(
+			count: &mut u32,
+			last_decay: &mut BlockNumberFor<T>,
+			period: BlockNumberFor<T>,
+		) {
+			if count.is_zero() || period.is_zero() {
+				return;
+			}
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let elapsed_periods: u32 =
+				(now.saturating_sub(*last_decay) / period).unique_saturated_into();
+			let forgiven = elapsed_periods.min(*count);
+			*count = count.saturating_sub(forgiven);
+			*last_decay = last_decay.saturating_add(period.saturating_mul(forgiven.into()));
+		}
+
+		/// Persist pending strike decay for an account and sync its graylist status.
+		fn refresh_reputation(who: &T::AccountId) -> ReputationRecord<BlockNumberFor<T>> {
+			Self::store_decay(who, Reputation::<T>::get(who))
+		}
+
+		/// Write back the decay pending on `stored`, the reputation of `who` as stored,
+		/// returning the decayed record.
+		fn store_decay(
+			who: &T::AccountId,
+			stored: ReputationRecord<BlockNumberFor<T>>,
+		) -> ReputationRecord<BlockNumberFor<T>> {
+			let record = Self::decayed(stored.clone());
+			if record != stored {
+				if record.strikes.is_zero() {
+					Reputation::<T>::remove(who);
+				} else {
+					Reputation::<T>::insert(who, &record);
+				}
+				Self::sync_graylist(who, record.strikes);
+			}
+			record
+		}
+
+		/// Strike count at which an account is graylisted.
+		fn graylist_threshold() -> u32 {
+			if T::AutoSlashEnabled::get() {
+				T::MaxStrikes::get().min(T::AutoSlashThreshold::get())
+			} else {
+				T::MaxStrikes::get()
+			}
+		}
+
+		/// Add a strike to an account, graylisting it once the graylist threshold is reached.
+		///
+		/// Returns the account's new strike count.
+		fn record_strike(who: &T::AccountId) -> u32 {
+			let mut record = Self::refresh_reputation(who);
+			if record.strikes.is_zero() {
+				record.last_decay = frame_system::Pallet::<T>::block_number();
+			}
+			record.strikes = record.strikes.saturating_add(1);
+			Reputation::<T>::insert(who, &record);
+
+			Self::deposit_event(Event::StrikeRecorded { who: who.clone(), strikes: record.strikes });
+			Self::sync_graylist(who, record.strikes);
+			record.strikes
+		}
+
+		/// Withdraw a strike after the verdict that recorded it was overturned.
+		fn revoke_strike(who: &T::AccountId) {
+			let mut record = Self::refresh_reputation(who);
+			if record.strikes.is_zero() {
+				return;
+			}
+			record.strikes = record.strikes.saturating_sub(1);
+			if record.strikes.is_zero() {
+				Reputation::<T>::remove(who);
+			} else {
+				Reputation::<T>::insert(who, &record);
+			}
+
+			Self::deposit_event(Event::StrikeRevoked { who: who.clone(), strikes: record.strikes });
+			Self::sync_graylist(who, record.strikes);
+		}
+
+		fn sync_graylist(who: &T::AccountId, strikes: u32) {
+			let graylisted = GraylistedAccounts::<T>::contains_key(who);
+			let threshold = Self::graylist_threshold();
+			if strikes >= threshold && !graylisted {
+				GraylistedAccounts::<T>::insert(who, frame_system::Pallet::<T>::block_number());
+				Self::deposit_event(Event::AccountGraylisted { who: who.clone(), strikes });
+			} else if strikes < threshold && graylisted {
+				GraylistedAccounts::<T>::remove(who);
+				Self::deposit_event(Event::AccountRestored { who: who.clone() });
+			}
+		}
+
+		/// Apply the penalties of an upheld challenge to its sender.
+		///
+		/// Records a strike and slashes `ChallengeSlashPercent` of the sender's bond into escrow,
+		/// plus the auto-slash percentage of `terms` of the remainder when the strike reaches
+		/// `AutoSlashThreshold`. Returns the total amount moved into escrow.
+		fn penalize(
+			who: &T::AccountId,
+			terms: &SlashTerms<BalanceOf<T>>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let strikes = Self::record_strike(who);
+
+			let slashed = Self::slash_into_escrow(who, T::ChallengeSlashPercent::get())?;
+			if !slashed.is_zero() {
+				Self::deposit_event(Event::BondSlashed { who: who.clone(), amount: slashed });
+			}
+
+			let auto_slashed = Self::maybe_auto_slash(who, strikes, terms.auto_slash_percent)?;
+			Ok(slashed.saturating_add(auto_slashed))
+		}
+
+		/// Slash `percent` of the bond when `strikes` has just reached the threshold.
+		///
+		/// At most one auto-slash applies per account per block, so challenges resolving in the
+		/// same block cannot slash the account repeatedly.
+		fn maybe_auto_slash(
+			who: &T::AccountId,
+			strikes: u32,
+			percent: Perbill,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let now = frame_system::Pallet::<T>::block_number();
+			if !T::AutoSlashEnabled::get() ||
+				strikes != T::AutoSlashThreshold::get() ||
+				LastAutoSlash::<T>::get(who) == Some(now)
+			{
+				return Ok(Zero::zero());
+			}
+			LastAutoSlash::<T>::insert(who, now);
+
+			let amount = Self::slash_into_escrow(who, percent)?;
+			Self::deposit_event(Event::BondAutoSlashed { who: who.clone(), amount, strikes });
+			Ok(amount)
+		}
+
+		/// Append a privileged action to the admin audit log, evicting the oldest entry if
+		/// the log is full.
+		///
+		/// Every call gated by a privileged origin records itself through this helper.
+		fn record_audit(action: AuditAction, target: &impl Encode) {
+			let entry = AuditEntry {
+				block: frame_system::Pallet::<T>::block_number(),
+				action,
+				target_hash: T::Hashing::hash_of(target),
+			};
+			AdminAuditLog::<T>::mutate(|log| {
+				if log.is_full() {
+					log.remove(0);
+				}
+				let _ = log.try_push(entry);
+			});
+		}
+
+		/// Replace `who`'s public key, moving the current one into `KeyHistory`.
+		///
+		/// Returns the new key version.
+		fn rotate_key(who: &T::AccountId, public_key: BoundedVec<u8, ConstU32<256>>) -> u32 {
+			let version = KeyVersions::<T>::get(who);
+			let old = UserProfiles::<T>::mutate(who, |profile| {
+				let profile = profile.as_mut()?;
+				Some(core::mem::replace(&mut profile.public_key, public_key))
+			});
+			if let Some(old) = old {
+				KeyHistory::<T>::mutate(who, |history| {
+					if history.is_full() {
+						history.remove(0);
+					}
+					let _ = history.try_push(RevokedKey {
+						public_key: old,
+						version,
+						revoked_at: frame_system::Pallet::<T>::block_number(),
+					});
+				});
+			}
+
+			let version = version.saturating_add(1);
+			KeyVersions::<T>::insert(who, version);
+			version
+		}
+
+		/// Emit `ContactKeyReset` for the next batch of `who`'s mutual contacts.
+		///
+		/// Scans at most `MaxKeyResetNotifications` relationships from the stored cursor and
+		/// clears the cursor once the contact list is exhausted. Returns the number of
+		/// relationships scanned.
+		fn notify_key_reset(who: &T::AccountId) -> u32 {
+			let Some(cursor) = KeyResetCursors::<T>::get(who) else { return 0 };
+			let mut contacts = if cursor.is_empty() {
+				ApprovedContacts::<T>::iter_prefix(who)
+			} else {
+				ApprovedContacts::<T>::iter_prefix_from(who, cursor.into_inner())
+			};
+
+			let mut scanned = 0u32;
+			while scanned < T::MaxKeyResetNotifications::get() {
+				let Some((contact, approved)) = contacts.next() else {
+					KeyResetCursors::<T>::remove(who);
+					Self::deposit_event(Event::KeyResetNotificationsCompleted { who: who.clone() });
+					return scanned;
+				};
+				scanned.saturating_inc();
+				if approved && ApprovedContacts::<T>::get(&contact, who) {
+					Self::deposit_event(Event::ContactKeyReset { contact, who: who.clone() });
+				}
+			}
+
+			// Budget exhausted, resume after the last relationship scanned
+			match BoundedVec::try_from(contacts.last_raw_key().to_vec()) {
+				Ok(cursor) => KeyResetCursors::<T>::insert(who, cursor),
+				Err(_) => KeyResetCursors::<T>::remove(who),
+			}
+			scanned
+		}
+
+		/// Fail with the first check of the send path `sender` does not pass for a message to
+		/// `recipient`, without changing any state, or return what the checks read.
+		///
+		/// A `paid` message needs no approval or inbox pass, as its price stands in for them.
+		///
+		/// The sender's spam bond and reputation are read from their own maps rather than from
+		/// its profile. Both outlive the profile: a bond stays held for refund or slashing after
+		/// deregistration, and strikes carry over to a new registration. Strikes also decay with
+		/// the block number, so a graylist flag stored in the profile would go stale. Each is
+		/// read once and handed to the send through [`SendCheck`].
+		fn ensure_can_message(
+			sender: &T::AccountId,
+			recipient: &T::AccountId,
+			paid: bool,
+		) -> Result<SendCheckOf<T>, CanMessageError> {
+			ensure!(!Self::is_paused(PausedCalls::SENDS), CanMessageError::Paused);
+			ensure!(sender != recipient, CanMessageError::MessageToSelf);
+			ensure!(!Self::is_reserved_account(recipient), CanMessageError::ReservedRecipient);
+			ensure!(!Self::is_banned(sender), CanMessageError::SenderBanned);
+			ensure!(!Self::is_banned(recipient), CanMessageError::RecipientBanned);
+			ensure!(!Self::is_frozen(sender), CanMessageError::SenderFrozen);
+			let sender_profile =
+				UserProfiles::<T>::get(sender).ok_or(CanMessageError::NoSenderProfile)?;
+			let recipient_profile =
+				UserProfiles::<T>::get(recipient).ok_or(CanMessageError::NoRecipientProfile)?;
+			ensure!(recipient_profile.dormant_since.is_none(), CanMessageError::RecipientDormant);
+			// Read both approvals once for access, the graylist and the fee
+			let approved = ApprovedContacts::<T>::get(recipient, sender);
+			ensure!(
+				paid || approved || Self::has_inbox_pass(recipient, sender),
+				CanMessageError::NotApproved
+			);
+			let mutual_contacts = approved && ApprovedContacts::<T>::get(sender, recipient);
+			ensure!(
+				!recipient_profile.session_required ||
+					Self::has_confirmed_session(sender, recipient),
+				CanMessageError::SessionRequired
+			);
+
+			// Graylisted senders may only reach mutual contacts
+			let reputation = Reputation::<T>::get(sender);
+			let strikes = Self::decayed(reputation.clone()).strikes;
+			ensure!(
+				strikes < Self::graylist_threshold() || mutual_contacts,
+				CanMessageError::SenderGraylisted
+			);
+			let bond = SpamBonds::<T>::get(sender).amount;
+			ensure!(
+				bond >= Self::bond_requirement(sender_profile.registration_bond, strikes),
+				CanMessageError::InsufficientBond
+			);
+
+			// Count the send against a copy of the window
+			let mut rate_limit = SendRateLimits::<T>::get(sender);
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				Self::consume_window(&mut rate_limit, now, Self::rate_limit_for(strikes)).is_ok(),
+				CanMessageError::RateLimited
+			);
+
+			let fee = Self::protocol_fee_due(mutual_contacts);
+			ensure!(
+				fee.is_zero() ||
+					!MessageCredits::<T>::get(sender).is_zero() ||
+					T::Currency::reducible_balance(
+						sender,
+						Preservation::Preserve,
+						Fortitude::Polite,
+					) >= fee,
+				CanMessageError::InsufficientProtocolFee
+			);
+			Ok(SendCheck { sender_profile, reputation, bond, rate_limit, fee })
+		}
+
+		/// Protocol fee owed for a message between accounts that are `mutual_contacts` or not,
+		/// zero if it is exempt.
+		fn protocol_fee_due(mutual_contacts: bool) -> BalanceOf<T> {
+			let parameters = Self::parameters();
+			if parameters.exempt_mutual_contacts && mutual_contacts {
+				return Zero::zero();
+			}
+			parameters.message_protocol_fee
+		}
+
+		/// Withdraw `fee`, the message protocol fee due from `sender`, paying
+		/// `RewardPoolFeeShare` of it into the challenge reward pool and routing the rest as
+		/// protocol fee income.
+		fn charge_protocol_fee(sender: &T::AccountId, fee: BalanceOf<T>) -> DispatchResult {
+			if fee.is_zero() {
+				return Ok(());
+			}
+
+			// A prepaid credit covers the fee
+			if Self::use_message_credit(sender) {
+				return Ok(());
+			}
+
+			let credit = T::Currency::withdraw(
+				sender,
+				fee,
+				Precision::Exact,
+				Preservation::Preserve,
+				Fortitude::Polite,
+			)
+			.map_err(|_| Error::<T>::InsufficientProtocolFee)?;
+
+			// A share below the existential deposit of an empty pool goes to income instead
+			let (share, income) = credit.split(T::RewardPoolFeeShare::get().mul_floor(fee));
+			let income = match T::Currency::resolve(&Self::reward_pool_account(), share) {
+				Ok(()) => income,
+				Err(share) => income.merge(share),
+			};
+			Self::route_income(IncomeCategory::ProtocolFee, income);
+
+			Self::deposit_event(Event::ProtocolFeeCharged { who: sender.clone(), amount: fee });
+			Ok(())
+		}
+
+		/// Use one of `who`'s message credits, collecting its share of the held price.
+		///
+		/// Returns `false` if `who` has no credits left.
+		fn use_message_credit(who: &T::AccountId) -> bool {
+			let credits = MessageCredits::<T>::get(who);
+			if credits.is_zero() {
+				return false;
+			}
+
+			let reason: T::RuntimeHoldReason = HoldReason::MessageCredits.into();
+			let share = T::Currency::balance_on_hold(&reason, who) / BalanceOf::<T>::from(credits);
+			let (fee, _) = T::Currency::slash(&reason, who, share);
+			Self::route_income(IncomeCategory::Fee, fee);
+
+			let remaining = credits - 1;
+			if remaining.is_zero() {
+				MessageCredits::<T>::remove(who);
+			} else {
+				MessageCredits::<T>::insert(who, remaining);
+			}
+			if remaining % CREDIT_MILESTONE == 0 {
+				Self::deposit_event(Event::CreditsRemaining { who: who.clone(), remaining });
+			}
+			true
+		}
+
+		/// Record `credit` as income of `category` and hand it to the category's handler.
+		fn route_income(category: IncomeCategory, credit: CreditOf<T>) {
+			let amount = credit.peek();
+			if amount.is_zero() {
+				return;
+			}
+			PalletIncome::<T>::mutate(category, |total| *total = total.saturating_add(amount));
+			match category {
+				IncomeCategory::Slash => T::SlashIncome::on_unbalanced(credit),
+				IncomeCategory::Fee => T::FeeIncome::on_unbalanced(credit),
+				IncomeCategory::ForfeitedDeposit => T::ForfeitedDeposits::on_unbalanced(credit),
+				IncomeCategory::ProtocolFee => T::ProtocolFeeIncome::on_unbalanced(credit),
+			}
+		}
+
+		/// Send the part of a slash not paid to the challenger to `destination`.
+		fn route_slash(destination: SlashDestination, credit: CreditOf<T>) {
+			match destination {
+				SlashDestination::Treasury => Self::route_income(IncomeCategory::Slash, credit),
+				SlashDestination::Burn => drop(credit),
+				SlashDestination::RewardPool => {
+					let pool = Self::reward_pool_account();
+					if let Err(credit) = T::Currency::resolve(&pool, credit) {
+						Self::route_income(IncomeCategory::Slash, credit);
+					}
+				},
+			}
+		}
+
+		/// Move `percent` of an account's spam bond, or of its relay bond if it is a relay,
+		/// into the escrow account. A sponsored spam bond is slashed from the sponsor.
+		///
+		/// Only the bond actually held is moved if it is smaller than recorded.
+		fn slash_into_escrow(
+			who: &T::AccountId,
+			percent: Perbill,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let relay = RelayAccounts::<T>::get(who);
+			let spam_bond = SpamBonds::<T>::get(who);
+			let (reason, bond, payer) = match &relay {
+				Some(relay) => (HoldReason::RelayBond, relay.bond, who),
+				None => (HoldReason::SpamBond, spam_bond.amount, spam_bond.payer(who)),
+			};
+			let amount = percent.mul_floor(bond);
+			if amount.is_zero() {
+				return Ok(amount);
+			}
+
+			let slashed = T::Currency::transfer_on_hold(
+				&reason.into(),
+				payer,
+				&Self::escrow_account(),
+				amount,
+				Precision::BestEffort,
+				Restriction::Free,
+				Fortitude::Force,
+			)?;
+			match relay {
+				Some(mut relay) => {
+					relay.bond = relay.bond.saturating_sub(slashed);
+					RelayAccounts::<T>::insert(who, relay);
+				},
+				None => SpamBonds::<T>::mutate(who, |bond| {
+					bond.amount = bond.amount.saturating_sub(slashed)
+				}),
+			}
+			Ok(slashed)
+		}
+
+		/// Return an escrowed slash to the account's spam bond, or to its relay bond if it is
+		/// a relay. A sponsored spam bond is returned to the sponsor.
+		fn return_from_escrow(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			if amount.is_zero() {
+				return Ok(());
+			}
+
+			let escrow = Self::escrow_account();
+			match RelayAccounts::<T>::get(who) {
+				Some(mut relay) => {
+					T::Currency::transfer(&escrow, who, amount, Preservation::Expendable)?;
+					T::Currency::hold(&HoldReason::RelayBond.into(), who, amount)?;
+					relay.bond = relay.bond.saturating_add(amount);
+					RelayAccounts::<T>::insert(who, relay);
+				},
+				None => {
+					let mut bond = SpamBonds::<T>::get(who);
+					let payer = bond.payer(who);
+					T::Currency::transfer(&escrow, payer, amount, Preservation::Expendable)?;
+					T::Currency::hold(&HoldReason::SpamBond.into(), payer, amount)?;
+					bond.amount = bond.amount.saturating_add(amount);
+					SpamBonds::<T>::insert(who, bond);
+				},
+			}
+
+			Self::deposit_event(Event::SlashReversed { who: who.clone(), amount });
+			Ok(())
+		}
+
+		/// Redistribute the bonds and escrowed slash of a challenge with a final verdict.
+		fn settle_challenge(
+			message_id: MessageId,
+			challenge: ChallengeInfoOf<T>,
+			upheld: bool,
+		) -> DispatchResult {
+			if upheld {
+				// Return the challenger's bond with their share of the slash, route the rest
+				let escrow = Self::escrow_account();
+				let mut reward = Zero::zero();
+				if let Some(challenger) = &challenge.challenger {
+					T::Currency::release(
+						&HoldReason::ChallengeBond.into(),
+						challenger,
+						challenge.bond,
+						Precision::BestEffort,
+					)?;
+
+					let terms = &challenge.terms;
+					reward = terms.challenger_reward_percent.mul_floor(challenge.escrowed);
+					if !reward.is_zero() {
+						T::Currency::transfer(&escrow, challenger, reward, Preservation::Expendable)?;
+					}
+
+					Self::pay_challenge_reward(message_id, challenger, terms.base_challenge_reward);
+				}
+				let income = challenge.escrowed.saturating_sub(reward);
+				if !income.is_zero() {
+					let credit = T::Currency::withdraw(
+						&escrow,
+						income,
+						Precision::BestEffort,
+						Preservation::Expendable,
+						Fortitude::Force,
+					)?;
+					Self::route_slash(challenge.terms.slash_destination, credit);
+				}
+			} else if let Some(challenger) = &challenge.challenger {
+				// Compensate the sender with the challenger's bond
+				T::Currency::transfer_on_hold(
+					&HoldReason::ChallengeBond.into(),
+					challenger,
+					&challenge.sender,
+					challenge.bond,
+					Precision::BestEffort,
+					Restriction::Free,
+					Fortitude::Force,
+				)?;
+			}
+
+			// Close the challenge
+			Self::close_challenge(message_id, &challenge.sender);
+
+			// Emit event
+			Self::deposit_event(Event::ChallengeFinalized { message_id, upheld });
+
+			Ok(())
+		}
+
+		/// Pay `reward` to `challenger` from the challenge reward pool, or as much of it as the
+		/// pool holds above its existential deposit.
+		///
+		/// Never fails, so a dry pool does not hold up the verdict.
+		fn pay_challenge_reward(
+			message_id: MessageId,
+			challenger: &T::AccountId,
+			reward: BalanceOf<T>,
+		) {
+			let pool = Self::reward_pool_account();
+			let available =
+				T::Currency::reducible_balance(&pool, Preservation::Preserve, Fortitude::Polite);
+			let amount = reward.min(available);
+			if amount.is_zero() ||
+				T::Currency::transfer(&pool, challenger, amount, Preservation::Preserve).is_err()
+			{
+				return;
+			}
+
+			Self::deposit_event(Event::ChallengeRewardPaid {
+				message_id,
+				challenger: challenger.clone(),
+				amount,
+			});
+		}
+
+		/// Record activity by `who` at the current block, reactivating its profile if it was
+		/// flagged dormant.
+		fn note_activity(who: &T::AccountId) {
+			let now = frame_system::Pallet::<T>::block_number();
+			AccountStats::<T>::mutate(who, |stats| stats.last_active = now);
+			Self::reactivate(who);
+		}
+
+		/// Clear the dormant flag of `who`, if it is set.
+		///
+		/// Only writes the profile back when the flag was set.
+		fn reactivate(who: &T::AccountId) {
+			if let Some(profile) = UserProfiles::<T>::get(who) {
+				Self::reactivate_profile(who, profile);
+			}
+		}
+
+		/// Clear the dormant flag of `profile`, the profile of `who` as stored, writing it back
+		/// only when the flag was set.
+		fn reactivate_profile(who: &T::AccountId, mut profile: ProfileRecordOf<T>) {
+			if profile.dormant_since.take().is_some() {
+				UserProfiles::<T>::insert(who, profile);
+				Self::deposit_event(Event::ProfileReactivated { who: who.clone() });
+			}
+		}
+
+		/// Take the reference that a profile keeps on its account.
+		///
+		/// Every profile holds one consumer reference on its account, from registration
+		/// until the profile is removed. A consumer reference makes the currency keep the
+		/// existential deposit in place, so a registered account is never reaped, not even
+		/// after transferring out all free funds once its bond is refunded. This is separate
+		/// from the consumer reference the currency takes while the bond is held, which is
+		/// taken on the sponsor for a sponsored bond. A consumer reference needs a provider,
+		/// so the account must hold the existential deposit when it registers.
+		///
+		/// Profiles registered before version 11 on an account without funds keep the
+		/// sufficient reference they held, as listed in [`SufficientProfileRefs`].
+		pub(crate) fn inc_profile_ref(who: &T::AccountId) -> DispatchResult {
+			frame_system::Pallet::<T>::inc_consumers(who)
+				.map_err(|_| Error::<T>::AccountNotFunded.into())
+		}
+
+		/// Drop the reference taken by [`Self::inc_profile_ref`] once the profile is removed.
+		fn dec_profile_ref(who: &T::AccountId) {
+			if SufficientProfileRefs::<T>::take(who).is_some() {
+				frame_system::Pallet::<T>::dec_sufficients(who);
+			} else {
+				frame_system::Pallet::<T>::dec_consumers(who);
+			}
+		}
+
+		/// Register a profile for `who`, holding the spam bond from `sponsor` if given and from
+		/// `who` otherwise.
+		fn do_register_profile(
+			who: T::AccountId,
+			public_key: Vec<u8>,
+			sponsor: Option<T::AccountId>,
+		) -> DispatchResult {
+			Self::ensure_not_paused(PausedCalls::REGISTRATIONS)?;
+			Self::ensure_not_banned(&who)?;
+
+			// Whitelisted chains only admit vetted accounts
+			ensure!(
+				CurrentRegistrationMode::<T>::get() == RegistrationMode::Open ||
+					RegistrationWhitelist::<T>::contains_key(&who),
+				Error::<T>::NotWhitelisted
+			);
+			ensure!(T::RegistrationGate::contains(&who), Error::<T>::RegistrationGateFailed);
+
+			// Check if profile already exists
+			ensure!(!UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileAlreadyExists);
+			ensure!(SpamBonds::<T>::get(&who).amount.is_zero(), Error::<T>::UnrefundedBond);
+
+			// Validate public key
+			ensure!(!public_key.is_empty(), Error::<T>::InvalidPublicKey);
+			ensure!(public_key.len() <= 256, Error::<T>::PublicKeyTooLarge);
+
+			// A sponsor covers any existential deposit the account lacks
+			if let Some(sponsor) = &sponsor {
+				let shortfall =
+					T::Currency::minimum_balance().saturating_sub(T::Currency::balance(&who));
+				if !shortfall.is_zero() {
+					T::Currency::transfer(sponsor, &who, shortfall, Preservation::Preserve)
+						.map_err(|_| Error::<T>::InsufficientBond)?;
+				}
+			}
+
+			// Keep the account alive while it is registered
+			Self::inc_profile_ref(&who)?;
+
+			// Hold spam bond
+			let bond_amount = Self::parameters().spam_bond;
+			let bond = BondRecord { amount: bond_amount, sponsor };
+			T::Currency::hold(&HoldReason::SpamBond.into(), bond.payer(&who), bond_amount)
+				.map_err(|_| Error::<T>::InsufficientBond)?;
+
+			SpamBonds::<T>::insert(&who, bond);
+			DepartedSenders::<T>::remove(&who);
+
+			// Convert to BoundedVec
+			let key_hash = Self::key_hash(&public_key);
+			let bounded_key: BoundedVec<u8, ConstU32<256>> =
+				public_key.try_into().map_err(|_| Error::<T>::PublicKeyTooLarge)?;
+
+			// Store profile with the bond amount, which stays the account's requirement if the
+			// parameter changes
+			UserProfiles::<T>::insert(
+				&who,
+				ProfileRecord {
+					public_key: bounded_key,
+					dormant_since: None,
+					registration_bond: Some(bond_amount),
+					session_required: false,
+				},
+			);
+			GlobalStats::<T>::mutate(|stats| stats.profiles.saturating_inc());
+			Self::note_activity(&who);
+
+			// Emit event
+			let version = KeyVersions::<T>::get(&who);
+			Self::deposit_event(Event::ProfileRegistered { who, key_hash, version });
+
+			Ok(())
+		}
+
+		/// Store a message hash carried by `relay` for a hidden sender.
+		///
+		/// Checks that `relay` may carry it and counts it against the relay's per-block
+		/// limit. Returns the new message ID and the block at which the message expires.
+		fn carry_sealed_message(
+			relay: &T::AccountId,
+			recipient: &T::AccountId,
+			message_hash: T::Hash,
+		) -> Result<(MessageId, BlockNumberFor<T>), DispatchError> {
+			Self::ensure_not_paused(PausedCalls::SENDS)?;
+			ensure!(relay != recipient, Error::<T>::CannotMessageSelf);
+			ensure!(!Self::is_reserved_account(recipient), Error::<T>::ReservedRecipient);
+			Self::ensure_valid_hash(&message_hash)?;
+			Self::ensure_not_banned(relay)?;
+			Self::ensure_not_banned(recipient)?;
+			ensure!(!Self::is_frozen(relay), Error::<T>::SenderFrozen);
+			ensure!(UserProfiles::<T>::contains_key(recipient), Error::<T>::RecipientNotFound);
+
+			let mut info = RelayAccounts::<T>::get(relay).ok_or(Error::<T>::NotRelay)?;
+			ensure!(info.bond >= T::RelayBond::get(), Error::<T>::InsufficientRelayBond);
+			let strikes = Self::refresh_reputation(relay).strikes;
+			ensure!(strikes < Self::graylist_threshold(), Error::<T>::Graylisted);
+
+			// Enforce the per-block carry limit
+			let current_block = frame_system::Pallet::<T>::block_number();
+			if info.last_carried_at != Some(current_block) {
+				info.last_carried_at = Some(current_block);
+				info.carried_in_block = 0;
+			}
+			ensure!(
+				info.carried_in_block < T::MaxRelayCarriesPerBlock::get(),
+				Error::<T>::RelayCarryLimitReached
+			);
+			Self::ensure_hash_unused(&message_hash)?;
+			info.carried_in_block.saturating_inc();
+			RelayAccounts::<T>::insert(relay, info);
+
+			let message_id = NextMessageId::<T>::get();
+			NextMessageId::<T>::put(message_id.saturating_add(1));
+			MessageHashes::<T>::insert(
+				message_id,
+				MessageRecord {
+					sender: relay.clone(),
+					recipient: recipient.clone(),
+					hash: message_hash,
+					sent_at: current_block,
+				},
+			);
+			MessageIdByHash::<T>::insert(message_hash, message_id);
+			Self::touch_inbox(recipient);
+			offchain_index::set::<T>(
+				recipient,
+				message_id,
+				&offchain_index::IndexEntry {
+					sender: relay.clone(),
+					hash: message_hash,
+					sent_at: current_block,
+					acknowledged: false,
+				},
+			);
+
+			Ok((message_id, current_block.saturating_add(Self::parameters().message_expiry)))
+		}
+
+		/// Fold an approved (`added`) or removed contact into the accumulator of `who`.
+		fn accumulate_contact(who: &T::AccountId, contact: &T::AccountId, added: bool) {
+			ContactAccumulators::<T>::mutate(who, |accumulator| {
+				*accumulator = if added {
+					T::AccumulatorUpdater::add(accumulator, contact)
+				} else {
+					T::AccumulatorUpdater::remove(accumulator, contact)
+				};
+			});
+		}
+
+		/// Index the approval of `contact` by `who` as a pending request, or accept the request
+		/// of `contact` if it approved `who` first.
+		///
+		/// Full indexes drop their oldest request, from both directions.
+		fn index_contact_request(who: &T::AccountId, contact: &T::AccountId) {
+			if ApprovedContacts::<T>::get(contact, who) {
+				Self::unindex_contact_request(contact, who);
+				return;
+			}
+			let outgoing = OutgoingContactRequests::<T>::get(who);
+			if let (true, Some((target, _))) = (outgoing.is_full(), outgoing.first()) {
+				Self::unindex_contact_request(who, target);
+			}
+			let incoming = IncomingContactRequests::<T>::get(contact);
+			if let (true, Some((requester, _))) = (incoming.is_full(), incoming.first()) {
+				Self::unindex_contact_request(requester, contact);
+			}
+
+			let now = frame_system::Pallet::<T>::block_number();
+			OutgoingContactRequests::<T>::mutate(who, |requests| {
+				let _ = requests.try_push((contact.clone(), now));
+			});
+			IncomingContactRequests::<T>::mutate(contact, |requests| {
+				let _ = requests.try_push((who.clone(), now));
+			});
+		}
+
+		/// Drop the request of `requester` to `target` from the pending request indexes.
+		fn unindex_contact_request(requester: &T::AccountId, target: &T::AccountId) {
+			OutgoingContactRequests::<T>::mutate_exists(requester, |requests| {
+				if let Some(outgoing) = requests {
+					outgoing.retain(|(account, _)| account != target);
+					if outgoing.is_empty() {
+						*requests = None;
+					}
+				}
+			});
+			IncomingContactRequests::<T>::mutate_exists(target, |requests| {
+				if let Some(incoming) = requests {
+					incoming.retain(|(account, _)| account != requester);
+					if incoming.is_empty() {
+						*requests = None;
+					}
+				}
+			});
+		}
+
+		/// Add `contact` to the approved contacts of `who`.
+		fn do_approve_contact(who: T::AccountId, contact: T::AccountId) -> DispatchResult {
+			Self::ensure_not_paused(PausedCalls::CONTACTS)?;
+			Self::ensure_not_banned(&who)?;
+			Self::ensure_not_banned(&contact)?;
+
+			// Cannot add yourself
+			ensure!(who != contact, Error::<T>::CannotAddSelf);
+
+			// Check if profile exists
+			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+
+			// Check contact exists
+			ensure!(UserProfiles::<T>::contains_key(&contact), Error::<T>::RecipientNotFound);
+			ensure!(!ApprovedContacts::<T>::get(&who, &contact), Error::<T>::AlreadyApproved);
+
+			// Graylisted accounts cannot approve new contacts
+			ensure!(
+				Self::refresh_reputation(&who).strikes < Self::graylist_threshold(),
+				Error::<T>::Graylisted
+			);
+
+			// Check max contacts limit
+			let current_count = ContactCount::<T>::get(&who);
+			ensure!(
+				current_count < Self::parameters().max_contacts,
+				Error::<T>::MaxContactsReached
+			);
+
+			// Add to approved contacts
+			ApprovedContacts::<T>::insert(&who, &contact, true);
+			ContactCount::<T>::mutate(&who, |count| *count = count.saturating_add(1));
+			Self::accumulate_contact(&who, &contact, true);
+			Self::index_contact_request(&who, &contact);
+			Self::note_activity(&who);
+
+			// Emit event
+			Self::deposit_event(match Self::parameters().event_privacy {
+				EventPrivacyMode::Full => Event::ContactApproved { approver: who, contact },
+				EventPrivacyMode::Minimal =>
+					Event::ContactApprovedMinimal { pair: Self::contact_pair_hash(&who, &contact) },
+			});
+
+			Ok(())
+		}
+
+		/// Record a message from `sender` to `recipient` after enforcing the checks of
+		/// [`Self::ensure_can_message`] and the hash checks, and charging the protocol fee.
+		///
+		/// Every send path goes through here, so [`Self::can_message`] predicts all of them.
+		///
+		/// The `MessageSent` event is left out if `compact`, for the caller to report the
+		/// message in a `MessagesSentCompact` event. Only `paid` messages reach recipients
+		/// that have not approved the sender. As a reply, the message refunds up to
+		/// `max_refunds` recipient-set deposits.
+		///
+		/// Returns the ID of the message and the number of recipient-set messages whose
+		/// deposits the reply settled, see [`Self::refund_deposits_on_reply`]. Fails with the
+		/// error a batch reports the message with.
+		fn do_send_message(
+			sender: T::AccountId,
+			recipient: T::AccountId,
+			message_hash: T::Hash,
+			compact: bool,
+			paid: bool,
+			max_refunds: u32,
+		) -> Result<(MessageId, u32), ItemError> {
+			let SendCheck { sender_profile, reputation, bond, rate_limit, fee } =
+				Self::ensure_can_message(&sender, &recipient, paid)
+					.map_err(ItemError::CannotMessage)?;
+			Self::ensure_valid_hash(&message_hash).map_err(|_| ItemError::InvalidHash)?;
+
+			// Refuse a hash that still identifies a live message
+			Self::ensure_hash_unused(&message_hash).map_err(|_| ItemError::DuplicateHash)?;
+
+			// Charge the fee before any state change, so a failure leaves none behind
+			Self::charge_protocol_fee(&sender, fee)
+				.map_err(|_| ItemError::CannotMessage(CanMessageError::InsufficientProtocolFee))?;
+
+			// Apply the pending strike decay and count the send, which the checks above only
+			// computed
+			Self::store_decay(&sender, reputation);
+			SendRateLimits::<T>::insert(&sender, rate_limit);
+
+			// Get current block number
+			let current_block = frame_system::Pallet::<T>::block_number();
+
+			// Lock the bond securing this message for the unbonding period
+			BondLocks::<T>::insert(
+				&sender,
+				BondLock {
+					amount: bond,
+					until: current_block.saturating_add(T::BondUnbondingPeriod::get()),
+				},
+			);
+
+			// Get next message ID
+			let message_id = NextMessageId::<T>::get();
+			let next_id = message_id.saturating_add(1);
+			NextMessageId::<T>::put(next_id);
+
+			// Store message hash with metadata
+			MessageHashes::<T>::insert(
+				message_id,
+				MessageRecord {
+					sender: sender.clone(),
+					recipient: recipient.clone(),
+					hash: message_hash,
+					sent_at: current_block,
+				},
+			);
+			MessageIdByHash::<T>::insert(message_hash, message_id);
+			Self::touch_inbox(&recipient);
+			offchain_index::set::<T>(
+				&recipient,
+				message_id,
+				&offchain_index::IndexEntry {
+					sender: sender.clone(),
+					hash: message_hash,
+					sent_at: current_block,
+					acknowledged: false,
+				},
+			);
+
+			// Number the message within its conversation
+			let conversation_id = Self::conversation_id(&sender, &recipient);
+			let sequence = ConversationStats::<T>::mutate(conversation_id, |stats| {
+				stats.messages.saturating_inc();
+				stats.last_message_id = message_id;
+				stats.last_activity = current_block;
+				let sequence = stats.next_seq;
+				stats.next_seq = sequence.saturating_add(1);
+				sequence
+			});
+			ConversationMessages::<T>::insert(conversation_id, sequence, message_id);
+			MessageSequences::<T>::insert(message_id, sequence);
+
+			// Count the message, and the conversation if it is the first one
+			AccountStats::<T>::mutate(&sender, |stats| {
+				stats.sent.saturating_inc();
+				stats.last_active = current_block;
+				if sequence == 0 {
+					stats.conversations.saturating_inc();
+				}
+			});
+			AccountStats::<T>::mutate(&recipient, |stats| {
+				stats.received.saturating_inc();
+				if sequence == 0 {
+					stats.conversations.saturating_inc();
+				}
+			});
+			GlobalStats::<T>::mutate(|stats| stats.messages.saturating_inc());
+			Self::reactivate_profile(&sender, sender_profile);
+
+			// A reply settles the recipient's deposits for messages to sets listing the sender
+			let refunded = Self::refund_deposits_on_reply(&sender, &recipient, max_refunds);
+
+			// Notify the recipient's push relay, if it opted in
+			if let Some(hint) = NotificationHooks::<T>::get(&recipient) {
+				Self::deposit_event(Event::NotifyRelay {
+					hint_hash: T::Hashing::hash(&hint),
+					recipient: recipient.clone(),
+					message_id,
+				});
+			}
+
+			if compact {
+				return Ok((message_id, refunded));
+			}
+
+			// Emit event
+			let parameters = Self::parameters();
+			Self::deposit_event(match parameters.event_privacy {
+				EventPrivacyMode::Full => Event::MessageSent {
+					message_id,
+					from: sender,
+					to: recipient,
+					hash: message_hash,
+					expires_at: current_block.saturating_add(parameters.message_expiry),
+					sequence,
+					conversation_id,
+				},
+				EventPrivacyMode::Minimal =>
+					Event::MessageSentMinimal { message_id, conversation_id },
+			});
+
+			Ok((message_id, refunded))
+		}
+
+		/// Refund the deposits of the most recent unexpired recipient-set messages `sender`
+		/// sent to sets listing `replier`, up to `limit` of them. Older messages wait for the
+		/// next reply.
+		///
+		/// Returns the number of messages looked up.
+		pub(crate) fn refund_deposits_on_reply(
+			replier: &T::AccountId,
+			sender: &T::AccountId,
+			limit: u32,
+		) -> u32 {
+			if limit.is_zero() {
+				return 0;
+			}
+			let mut message_ids = ReplyRefundableDeposits::<T>::take(sender, replier);
+			if message_ids.is_empty() {
+				return 0;
+			}
+			let older = message_ids.len().saturating_sub(limit as usize);
+			let newest = message_ids[older..].to_vec();
+			message_ids.truncate(older);
+			if !message_ids.is_empty() {
+				ReplyRefundableDeposits::<T>::insert(sender, replier, message_ids);
+			}
+			let now = frame_system::Pallet::<T>::block_number();
+			let expiry = Self::parameters().message_expiry;
+			let mut inspected = 0u32;
+			for message_id in newest.into_iter().rev() {
+				inspected.saturating_inc();
+				let Some(message) = RecipientSetMessages::<T>::get(message_id) else {
+					continue;
+				};
+				// Older messages expired too and are settled by pruning
+				if now > message.sent_at.saturating_add(expiry) {
+					break;
+				}
+				let refunded = Self::settle_recipient_set_message(message_id, message);
+				Self::deposit_event(Event::DepositReleasedOnReply {
+					message_id,
+					sender: sender.clone(),
+					replier: replier.clone(),
+					refunded,
+				});
+			}
+			inspected
+		}
+
+		/// Refund the deposit of a recipient-set message and mark it acknowledged in the inbox
+		/// index of every member, returning the amount refunded.
+		fn settle_recipient_set_message(
+			message_id: MessageId,
+			mut message: RecipientSetMessageRecordOf<T>,
+		) -> BalanceOf<T> {
+			let refunded = message.deposit;
+			Self::release_message_deposit(&message.sender, refunded);
+			message.deposit = Zero::zero();
+			Self::forget_reply_refundable(message_id, &message);
+			for recipient in &message.recipients {
+				offchain_index::set::<T>(
+					recipient,
+					message_id,
+					&offchain_index::IndexEntry {
+						sender: message.sender.clone(),
+						hash: message.hash,
+						sent_at: message.sent_at,
+						acknowledged: true,
+					},
+				);
+			}
+			RecipientSetMessages::<T>::insert(message_id, message);
+			refunded
+		}
+
+		/// Drop a settled recipient-set message from `ReplyRefundableDeposits`.
+		fn forget_reply_refundable(
+			message_id: MessageId,
+			message: &RecipientSetMessageRecordOf<T>,
+		) {
+			for recipient in &message.recipients {
+				ReplyRefundableDeposits::<T>::mutate_exists(&message.sender, recipient, |ids| {
+					if let Some(list) = ids {
+						list.retain(|id| *id != message_id);
+						if list.is_empty() {
+							*ids = None;
+						}
+					}
+				});
+			}
+		}
+
+		/// Whether the account is banned from the messaging system.
+		pub fn is_banned(who: &T::AccountId) -> bool {
+			BannedAccounts::<T>::contains_key(who)
+		}
+
+		/// Fail with `AccountBanned` if the account is banned.
+		fn ensure_not_banned(who: &T::AccountId) -> DispatchResult {
+			ensure!(!Self::is_banned(who), Error::<T>::AccountBanned);
+			Ok(())
+		}
+
+		/// Apply `f` to each of `items` in its own storage layer.
+		///
+		/// The first item `f` rejects fails the whole run with `ItemFailed` carrying the
+		/// item's position, and every change made for the items before it is rolled back.
+		/// Positions past `u8::MAX` are reported as `u8::MAX`.
+		pub(crate) fn try_each_item<I>(
+			items: I,
+			mut f: impl FnMut(I::Item) -> Result<(), ItemError>,
+		) -> DispatchResult
+		where
+			I: IntoIterator,
+		{
+			frame_support::storage::with_storage_layer(|| {
+				for (index, item) in items.into_iter().enumerate() {
+					f(item).map_err(|error| Error::<T>::ItemFailed {
+						index: index.try_into().unwrap_or(u8::MAX),
+						error,
+					})?;
+				}
+				Ok(())
+			})
+		}
+
+		/// Fail with `PalletPaused` if any of the given call groups is paused.
+		fn ensure_not_paused(calls: PausedCalls) -> DispatchResult {
+			ensure!(!Self::is_paused(calls), Error::<T>::PalletPaused);
+			Ok(())
+		}
+
+		/// Group `group_id`, or `NotGroupOwner` if `who` does not own it.
+		fn ensure_group_owner(
+			group_id: GroupId,
+			who: &T::AccountId,
+		) -> Result<GroupInfoOf<T>, DispatchError> {
+			let group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			Self::ensure_group_active(&group)?;
+			ensure!(group.owner == *who, Error::<T>::NotGroupOwner);
+			Ok(group)
+		}
+
+		/// `GroupFrozen` or `GroupDisbanding` unless `group` is active.
+		fn ensure_group_active(group: &GroupInfoOf<T>) -> DispatchResult {
+			match group.status {
+				GroupStatus::Active => Ok(()),
+				GroupStatus::Frozen => Err(Error::<T>::GroupFrozen.into()),
+				GroupStatus::Disbanding => Err(Error::<T>::GroupDisbanding.into()),
+			}
+		}
+
+		/// Group `group_id`, or `NotGroupAdmin` if `who` neither owns it nor is an admin.
+		fn ensure_group_admin(
+			group_id: GroupId,
+			who: &T::AccountId,
+		) -> Result<GroupInfoOf<T>, DispatchError> {
+			let group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			Self::ensure_group_active(&group)?;
+			ensure!(
+				matches!(Self::group_role(group_id, who), Some(GroupRole::Owner | GroupRole::Admin)),
+				Error::<T>::NotGroupAdmin
+			);
+			Ok(group)
+		}
+
+		/// Group `group_id`, or `NotGroupModerator` if `who` is neither its owner, an admin nor
+		/// a moderator.
+		fn ensure_group_moderator(
+			group_id: GroupId,
+			who: &T::AccountId,
+		) -> Result<GroupInfoOf<T>, DispatchError> {
+			let group = Groups::<T>::get(group_id).ok_or(Error::<T>::GroupNotFound)?;
+			Self::ensure_group_active(&group)?;
+			ensure!(
+				Self::group_role(group_id, who).is_some_and(|role| role != GroupRole::Member),
+				Error::<T>::NotGroupModerator
+			);
+			Ok(group)
+		}
+
+		/// Add `who` to `group` as an ordinary member, leaving `group` for the caller to store.
+		///
+		/// Holds the deposit for the new member from the owner, and removes any pending invite
+		/// or join request of `who` to the group.
+		fn join_group(
+			group_id: GroupId,
+			group: &mut GroupInfoOf<T>,
+			who: &T::AccountId,
+		) -> DispatchResult {
+			ensure!(!GroupBans::<T>::contains_key(group_id, who), Error::<T>::BannedFromGroup);
+			ensure!(group.member_count < T::MaxGroupMembers::get(), Error::<T>::GroupFull);
+			ensure!(
+				GroupCount::<T>::get(who) < T::MaxGroupsPerAccount::get(),
+				Error::<T>::TooManyGroups
+			);
+
+			let amount = T::GroupPerMemberDeposit::get();
+			T::Currency::hold(&HoldReason::GroupDeposit.into(), &group.owner, amount)
+				.map_err(|_| Error::<T>::InsufficientGroupDeposit)?;
+			group.deposit.saturating_accrue(amount);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			GroupMembers::<T>::insert(
+				group_id,
+				who,
+				MemberInfo { joined_at: now, role: GroupRole::Member },
+			);
+			GroupCount::<T>::mutate(who, |count| count.saturating_inc());
+			group.member_count.saturating_inc();
+			Self::remove_group_invite(group_id, who);
+			Self::remove_join_request(group_id, who);
+			Ok(())
+		}
+
+		/// Remove the join request of `who` to a group, returning whether one was pending.
+		fn remove_join_request(group_id: GroupId, who: &T::AccountId) -> bool {
+			if GroupJoinRequests::<T>::take(group_id, who).is_none() {
+				return false;
+			}
+			GroupJoinRequestCount::<T>::mutate(group_id, |count| count.saturating_dec());
+			true
+		}
+
+		/// Release the deposit of the metadata of a group and remove it from discovery,
+		/// returning the removed metadata.
+		fn remove_group_metadata(group_id: GroupId) -> Result<GroupMetadataOf<T>, DispatchError> {
+			let metadata =
+				GroupsMetadata::<T>::take(group_id).ok_or(Error::<T>::GroupMetadataNotFound)?;
+			T::Currency::release(
+				&HoldReason::GroupMetadataDeposit.into(),
+				&metadata.depositor,
+				metadata.deposit,
+				Precision::Exact,
+			)?;
+			if DiscoverableGroups::<T>::take(group_id).is_some() {
+				DiscoverableGroupCount::<T>::mutate(|count| count.saturating_dec());
+			}
+			Ok(metadata)
+		}
+
+		/// Release the deposit for one member that left `group` to its owner, leaving `group` for
+		/// the caller to store.
+		fn release_member_deposit(group: &mut GroupInfoOf<T>) -> DispatchResult {
+			let amount = T::GroupPerMemberDeposit::get().min(group.deposit);
+			T::Currency::release(
+				&HoldReason::GroupDeposit.into(),
+				&group.owner,
+				amount,
+				Precision::Exact,
+			)?;
+			group.deposit.saturating_reduce(amount);
+			Ok(())
+		}
+
+		/// Remove `who` from the members of a group, along with its mutes, leaving the group
+		/// itself for the caller to update.
+		fn drop_membership(group_id: GroupId, who: &T::AccountId) {
+			GroupMembers::<T>::remove(group_id, who);
+			GroupCount::<T>::mutate(who, |count| count.saturating_dec());
+			GroupMutes::<T>::remove(group_id, who);
+			MutedGroups::<T>::remove(group_id, who);
+		}
+
+		/// Remove member `who` with `role` from a group other than its owner, leaving `group`
+		/// for the caller to store.
+		///
+		/// Releases the deposit for the member, withdraws any ownership offer to it and
+		/// requires a key rotation.
+		fn remove_member(
+			group_id: GroupId,
+			group: &mut GroupInfoOf<T>,
+			who: &T::AccountId,
+			role: GroupRole,
+		) -> DispatchResult {
+			Self::drop_membership(group_id, who);
+			group.member_count.saturating_dec();
+			Self::release_member_deposit(group)?;
+			if role == GroupRole::Admin {
+				group.admin_count.saturating_dec();
+				Self::withdraw_ownership_offer(group_id, who);
+			}
+			group.rotation_required = true;
+			Ok(())
+		}
+
+		/// Remove up to `limit` messages and tombstones of a disbanding group, returning how
+		/// many were removed.
+		fn remove_group_messages(group_id: GroupId, limit: u32) -> u32 {
+			let message_ids: Vec<MessageId> =
+				GroupMessages::<T>::iter_key_prefix(group_id).take(limit as usize).collect();
+			for message_id in &message_ids {
+				if let Some(message) = GroupMessages::<T>::take(group_id, message_id) {
+					GroupMessagesBySeq::<T>::remove(group_id, message.seq);
+				}
+				GroupMessageIds::<T>::remove(message_id);
+			}
+			let mut removed = message_ids.len() as u32;
+
+			let tombstones: Vec<MessageId> = GroupMessageTombstones::<T>::iter_key_prefix(group_id)
+				.take(limit.saturating_sub(removed) as usize)
+				.collect();
+			for message_id in &tombstones {
+				GroupMessageTombstones::<T>::remove(group_id, message_id);
+				GroupMessageIds::<T>::remove(message_id);
+			}
+			removed.saturating_accrue(tombstones.len() as u32);
+
+			removed
+		}
+
+		/// Remove up to `limit` invites, join requests, bans and members of a disbanding group,
+		/// in that order, returning how many were removed.
+		///
+		/// Keeps the counts in `group` and the per-group counters in step, leaving `group` for
+		/// the caller to store.
+		fn remove_group_entries(group_id: GroupId, group: &mut GroupInfoOf<T>, limit: u32) -> u32 {
+			let mut removed = 0u32;
+
+			let invitees: Vec<T::AccountId> =
+				GroupInvites::<T>::iter_key_prefix(group_id).take(limit as usize).collect();
+			for who in &invitees {
+				Self::remove_group_invite(group_id, who);
+			}
+			removed.saturating_accrue(invitees.len() as u32);
+
+			let requesters: Vec<T::AccountId> = GroupJoinRequests::<T>::iter_key_prefix(group_id)
+				.take(limit.saturating_sub(removed) as usize)
+				.collect();
+			for who in &requesters {
+				Self::remove_join_request(group_id, who);
+			}
+			removed.saturating_accrue(requesters.len() as u32);
+
+			let banned: Vec<T::AccountId> = GroupBans::<T>::iter_key_prefix(group_id)
+				.take(limit.saturating_sub(removed) as usize)
+				.collect();
+			for who in &banned {
+				GroupBans::<T>::remove(group_id, who);
+				GroupBanCount::<T>::mutate(group_id, |count| count.saturating_dec());
+			}
+			removed.saturating_accrue(banned.len() as u32);
+
+			let members: Vec<(T::AccountId, MemberInfo<BlockNumberFor<T>>)> =
+				GroupMembers::<T>::iter_prefix(group_id)
+					.take(limit.saturating_sub(removed) as usize)
+					.collect();
+			for (who, member) in &members {
+				Self::drop_membership(group_id, who);
+				group.member_count.saturating_dec();
+				if member.role == GroupRole::Admin {
+					group.admin_count.saturating_dec();
+				}
+			}
+			removed.saturating_accrue(members.len() as u32);
+
+			removed
+		}
+
+		/// Whether a group has any invites, join requests, bans or members left.
+		fn has_group_entries(group_id: GroupId) -> bool {
+			GroupInvites::<T>::iter_key_prefix(group_id).next().is_some() ||
+				GroupJoinRequests::<T>::iter_key_prefix(group_id).next().is_some() ||
+				GroupBans::<T>::iter_key_prefix(group_id).next().is_some() ||
+				GroupMembers::<T>::iter_key_prefix(group_id).next().is_some()
+		}
+
+		/// Check that group counters match what is stored for each group, and that nothing is
+		/// stored for deleted groups.
+		///
+		/// Also holds between the calls disbanding a group.
+		#[cfg(any(feature = "try-runtime", test))]
+		pub fn do_try_state() -> Result<(), sp_runtime::TryRuntimeError> {
+			for (group_id, group) in Groups::<T>::iter() {
+				let (mut members, mut admins, mut has_owner) = (0u32, 0u32, false);
+				for (who, member) in GroupMembers::<T>::iter_prefix(group_id) {
+					members.saturating_inc();
+					match member.role {
+						GroupRole::Owner => {
+							ensure!(who == group.owner, "messaging: group owner is not its owner");
+							has_owner = true;
+						},
+						GroupRole::Admin => admins.saturating_inc(),
+						GroupRole::Member | GroupRole::Moderator => {},
+					}
+				}
+				ensure!(members == group.member_count, "messaging: wrong group member count");
+				ensure!(admins == group.admin_count, "messaging: wrong group admin count");
+				ensure!(
+					has_owner || group.status != GroupStatus::Active,
+					"messaging: active group without its owner"
+				);
+				ensure!(
+					GroupInvites::<T>::iter_key_prefix(group_id).count() as u32 ==
+						GroupInviteCount::<T>::get(group_id),
+					"messaging: wrong group invite count"
+				);
+				ensure!(
+					GroupBans::<T>::iter_key_prefix(group_id).count() as u32 ==
+						GroupBanCount::<T>::get(group_id),
+					"messaging: wrong group ban count"
+				);
+				ensure!(
+					GroupJoinRequests::<T>::iter_key_prefix(group_id).count() as u32 ==
+						GroupJoinRequestCount::<T>::get(group_id),
+					"messaging: wrong group join request count"
+				);
+			}
+			ensure!(
+				DiscoverableGroups::<T>::iter_keys().count() as u32 ==
+					DiscoverableGroupCount::<T>::get(),
+				"messaging: wrong discoverable group count"
+			);
+			for (group_id, ()) in DiscoverableGroups::<T>::iter() {
+				ensure!(
+					GroupsMetadata::<T>::get(group_id).is_some_and(|m| m.is_discoverable),
+					"messaging: discoverable group without discoverable metadata"
+				);
+			}
+
+			let group_ids = GroupMembers::<T>::iter_keys()
+				.map(|(group_id, _)| group_id)
+				.chain(GroupInvites::<T>::iter_keys().map(|(group_id, _)| group_id))
+				.chain(GroupBans::<T>::iter_keys().map(|(group_id, _)| group_id))
+				.chain(GroupJoinRequests::<T>::iter_keys().map(|(group_id, _)| group_id))
+				.chain(GroupsMetadata::<T>::iter_keys())
+				.chain(GroupSeqFloors::<T>::iter_keys())
+				.chain(GroupModerationLogs::<T>::iter_keys())
+				.chain(GroupMessageTombstones::<T>::iter_keys().map(|(group_id, _)| group_id))
+				.chain(GroupMessages::<T>::iter_keys().map(|(group_id, _)| group_id));
+			for group_id in group_ids {
+				ensure!(Groups::<T>::contains_key(group_id), "messaging: entry of a deleted group");
+			}
+
+			for (group_id, message_id, message) in GroupMessages::<T>::iter() {
+				ensure!(
+					GroupMessageIds::<T>::get(message_id) == Some(group_id),
+					"messaging: group message without its index"
+				);
+				ensure!(
+					GroupMessagesBySeq::<T>::get(group_id, message.seq) == Some(message_id),
+					"messaging: group message without its sequence number"
+				);
+				ensure!(
+					Groups::<T>::get(group_id).is_some_and(|group| message.seq < group.next_seq),
+					"messaging: group message sequence number not yet assigned"
+				);
+			}
+			for (group_id, seq, message_id) in GroupMessagesBySeq::<T>::iter() {
+				ensure!(
+					GroupMessages::<T>::get(group_id, message_id)
+						.is_some_and(|message| message.seq == seq),
+					"messaging: sequence number of a missing group message"
+				);
+			}
+			for (group_id, message_id, tombstone) in GroupMessageTombstones::<T>::iter() {
+				ensure!(
+					GroupMessageIds::<T>::get(message_id) == Some(group_id),
+					"messaging: group message tombstone without its index"
+				);
+				ensure!(
+					!GroupMessages::<T>::contains_key(group_id, message_id) &&
+						!GroupMessagesBySeq::<T>::contains_key(group_id, tombstone.seq),
+					"messaging: tombstone of a stored group message"
+				);
+			}
+			for (message_id, group_id) in GroupMessageIds::<T>::iter() {
+				ensure!(
+					GroupMessages::<T>::contains_key(group_id, message_id) ||
+						GroupMessageTombstones::<T>::contains_key(group_id, message_id),
+					"messaging: index of a missing group message"
+				);
+			}
+
+			for (requester, requests) in OutgoingContactRequests::<T>::iter() {
+				ensure!(!requests.is_empty(), "messaging: empty outgoing contact requests");
+				for (target, requested_at) in requests {
+					ensure!(
+						ApprovedContacts::<T>::get(&requester, &target) &&
+							!ApprovedContacts::<T>::get(&target, &requester),
+						"messaging: pending contact request that is not pending"
+					);
+					ensure!(
+						IncomingContactRequests::<T>::get(&target)
+							.contains(&(requester.clone(), requested_at)),
+						"messaging: outgoing contact request without its incoming entry"
+					);
+				}
+			}
+			for (target, requests) in IncomingContactRequests::<T>::iter() {
+				ensure!(!requests.is_empty(), "messaging: empty incoming contact requests");
+				for (requester, requested_at) in requests {
+					ensure!(
+						OutgoingContactRequests::<T>::get(&requester)
+							.contains(&(target.clone(), requested_at)),
+						"messaging: incoming contact request without its outgoing entry"
+					);
+				}
+			}
+
+			for (message_id, seq) in MessageSequences::<T>::iter() {
+				let message = MessageHashes::<T>::get(message_id)
+					.ok_or("messaging: sequence number of a missing message")?;
+				let conversation_id = Self::conversation_id(&message.sender, &message.recipient);
+				ensure!(
+					ConversationMessages::<T>::get(conversation_id, seq) == Some(message_id),
+					"messaging: conversation message without its index"
+				);
+				ensure!(
+					seq < ConversationStats::<T>::get(conversation_id).next_seq,
+					"messaging: conversation sequence number not yet assigned"
+				);
+			}
+			for (_, _, message_id) in ConversationMessages::<T>::iter() {
+				ensure!(
+					MessageSequences::<T>::contains_key(message_id),
+					"messaging: conversation index of an unnumbered message"
+				);
+			}
+			for (hash, message_id) in MessageIdByHash::<T>::iter() {
+				ensure!(
+					MessageHashes::<T>::get(message_id).is_some_and(|message| message.hash == hash),
+					"messaging: hash index of a missing or different message"
+				);
+			}
+
+			for (message_id, message) in StealthMessages::<T>::iter() {
+				ensure!(
+					StealthMessagesByPrefix::<T>::contains_key(
+						stealth::prefix_of(message.recipient_tag.as_ref()),
+						message_id,
+					),
+					"messaging: stealth message without its index"
+				);
+				ensure!(
+					!MessageHashes::<T>::contains_key(message_id),
+					"messaging: stealth message sharing its ID with a direct message"
+				);
+			}
+			ensure!(
+				StealthMessagesByPrefix::<T>::iter_keys().all(|(prefix, message_id)| {
+					StealthMessages::<T>::get(message_id).is_some_and(|message| {
+						stealth::prefix_of(message.recipient_tag.as_ref()) == prefix
+					})
+				}),
+				"messaging: index of a missing stealth message"
+			);
+			for (account_hash, pending_id, _) in PendingMessages::<T>::iter() {
+				ensure!(
+					PendingMessageIds::<T>::get(pending_id) == Some(account_hash),
+					"messaging: pending message without its index"
+				);
+			}
+			for (pending_id, account_hash) in PendingMessageIds::<T>::iter() {
+				ensure!(
+					PendingMessages::<T>::contains_key(account_hash, pending_id),
+					"messaging: index of a missing pending message"
+				);
+			}
+
+			// Evidence is removed with its challenge
+			for message_id in ChallengeEvidence::<T>::iter_keys() {
+				ensure!(
+					Challenges::<T>::contains_key(message_id),
+					"messaging: evidence of a closed challenge"
+				);
+			}
+
+			// Publishing beyond `MaxEphemeralKeys` evicts the oldest key
+			for who in EphemeralKeys::<T>::iter_keys().map(|(who, _)| who) {
+				ensure!(
+					EphemeralKeys::<T>::iter_prefix(&who).count() <=
+						T::MaxEphemeralKeys::get() as usize,
+					"messaging: too many ephemeral keys"
+				);
+			}
+
+			// Settled messages are dropped from the reply refund index
+			for (sender, member, message_ids) in ReplyRefundableDeposits::<T>::iter() {
+				for message_id in message_ids {
+					ensure!(
+						RecipientSetMessages::<T>::get(message_id).is_some_and(|message| {
+							message.sender == sender &&
+								message.recipients.contains(&member) &&
+								!message.deposit.is_zero()
+						}),
+						"messaging: reply refund index of a settled message"
+					);
+				}
+			}
+
+			// Counted maps agree with what they store
+			ensure!(
+				MessageHashes::<T>::iter_keys().count() as u32 == MessageHashes::<T>::count(),
+				"messaging: wrong message count"
+			);
+			ensure!(
+				UserProfiles::<T>::iter_keys().count() as u32 == UserProfiles::<T>::count(),
+				"messaging: wrong profile count"
+			);
+
+			// Registered accounts are kept alive by their profile
+			for who in UserProfiles::<T>::iter_keys() {
+				let referenced = if SufficientProfileRefs::<T>::contains_key(&who) {
+					frame_system::Pallet::<T>::sufficients(&who) > 0
+				} else {
+					frame_system::Pallet::<T>::consumers(&who) > 0
+				};
+				ensure!(referenced, "messaging: profile without a reference on its account");
+			}
+
+			// Sponsors are other accounts
+			for (who, bond) in SpamBonds::<T>::iter() {
+				ensure!(
+					bond.sponsor.as_ref() != Some(&who),
+					"messaging: spam bond sponsored by the bonded account"
+				);
+			}
+
+			// Credit balances stay within the cap
+			for (_, credits) in MessageCredits::<T>::iter() {
+				ensure!(
+					credits > 0 && credits <= T::MaxCreditsPerAccount::get(),
+					"messaging: message credits outside the cap"
+				);
+			}
+
+			Ok(())
+		}
+
+		/// Check that `caller` can mute or unmute member `who` of `group`.
+		///
+		/// Nobody can mute the owner, and callers can only mute members they outrank.
+		fn ensure_can_mute(
+			group_id: GroupId,
+			group: &GroupInfoOf<T>,
+			caller: &T::AccountId,
+			who: &T::AccountId,
+		) -> DispatchResult {
+			ensure!(*who != group.owner, Error::<T>::CannotMuteGroupOwner);
+			let role = Self::group_role(group_id, who).ok_or(Error::<T>::NotGroupMember)?;
+			Self::ensure_outranks(group_id, caller, role)
+		}
+
+		/// Check that `caller` outranks members with `role` in a group, as it must to act on
+		/// them.
+		///
+		/// Fails with the error of the least privileged check `caller` would have to pass.
+		fn ensure_outranks(
+			group_id: GroupId,
+			caller: &T::AccountId,
+			role: GroupRole,
+		) -> DispatchResult {
+			let outranks = Self::group_role(group_id, caller).is_some_and(|own| own.outranks(role));
+			ensure!(
+				outranks,
+				match role {
+					GroupRole::Owner | GroupRole::Admin => Error::<T>::NotGroupOwner,
+					GroupRole::Moderator => Error::<T>::NotGroupAdmin,
+					GroupRole::Member => Error::<T>::NotGroupModerator,
+				}
+			);
+			Ok(())
+		}
+
+		/// Append an action to the moderation log of a group, evicting the oldest entry if the
+		/// log is full.
+		fn record_moderation(
+			group_id: GroupId,
+			by: &T::AccountId,
+			action: GroupModerationActionOf<T>,
+		) {
+			let entry = GroupModerationEntry {
+				by: by.clone(),
+				action,
+				at: frame_system::Pallet::<T>::block_number(),
+			};
+			GroupModerationLogs::<T>::mutate(group_id, |log| {
+				if log.is_full() {
+					log.remove(0);
+				}
+				let _ = log.try_push(entry);
+			});
+		}
+
+		/// Remove the invite of `who` to a group, returning whether one was pending.
+		fn remove_group_invite(group_id: GroupId, who: &T::AccountId) -> bool {
+			if GroupInvites::<T>::take(group_id, who).is_none() {
+				return false;
+			}
+			GroupInviteCount::<T>::mutate(group_id, |count| count.saturating_dec());
+			PendingGroupInviteCount::<T>::mutate(who, |count| count.saturating_dec());
+			true
+		}
+
+		/// Withdraw the ownership offer of a group, if it was made to `who`.
+		fn withdraw_ownership_offer(group_id: GroupId, who: &T::AccountId) {
+			if PendingGroupOwners::<T>::get(group_id).as_ref() == Some(who) {
+				PendingGroupOwners::<T>::remove(group_id);
+			}
+		}
+
+		/// Count a send against the account's rate-limit window.
+		fn consume_rate_limit(
+			who: &T::AccountId,
+			strikes: u32,
+			now: BlockNumberFor<T>,
+		) -> DispatchResult {
+			SendRateLimits::<T>::try_mutate(who, |state| {
+				Self::consume_window(state, now, Self::rate_limit_for(strikes))
+			})
+		}
+
+		/// Give an open challenge its verdict and open the appeal window.
+		fn do_resolve_challenge(
+			message_id: MessageId,
+			mut challenge: ChallengeInfoOf<T>,
+			upheld: bool,
+		) -> DispatchResult {
+			ensure!(challenge.status == ChallengeStatus::Open, Error::<T>::ChallengeNotOpen);
+
+			// Penalize the sender
+			if upheld {
+				challenge.escrowed = Self::penalize(&challenge.sender, &challenge.terms)?;
+			}
+
+			// Open the appeal window
+			challenge.status =
+				ChallengeStatus::Resolved { upheld, at: frame_system::Pallet::<T>::block_number() };
+			Challenges::<T>::insert(message_id, challenge);
+
+			// Emit event
+			Self::deposit_event(Event::ChallengeResolved { message_id, upheld });
+
+			Ok(())
+		}
+
+		/// Remove a challenge and its evidence and release its hold on the sender's bond.
+		fn close_challenge(message_id: MessageId, sender: &T::AccountId) {
+			OpenChallengeCount::<T>::mutate_exists(sender, |count| {
+				*count = count.map(|c| c.saturating_sub(1)).filter(|c| !c.is_zero());
+			});
+			Challenges::<T>::remove(message_id);
+			ChallengeEvidence::<T>::remove(message_id);
+		}
+
+		/// Count an action against a rate-limit window, starting a new window once the
+		/// current one has elapsed.
+		fn consume_window(
+			state: &mut RateLimitState<BlockNumberFor<T>>,
+			now: BlockNumberFor<T>,
+			limit: u32,
+		) -> DispatchResult {
+			if now >= state.window_start.saturating_add(T::RateLimitWindow::get()) {
+				state.window_start = now;
+				state.sent = 0;
+			}
+			ensure!(state.sent < limit, Error::<T>::RateLimitExceeded);
+			state.sent = state.sent.saturating_add(1);
+			if state.last_block != now {
+				state.last_block = now;
+				state.sent_in_block = 0;
+			}
+			state.sent_in_block = state.sent_in_block.saturating_add(1);
+			Ok(())
+		}
+
+		/// Count a report of `message_id` by its recipient against `sender`.
+		///
+		/// Marks the message reported and opens a system challenge once the sender's
+		/// outstanding reports reach `AutoChallengeThreshold`.
+		fn count_spam_report(
+			message_id: MessageId,
+			reporter: T::AccountId,
+			sender: T::AccountId,
+		) {
+			let current_block = frame_system::Pallet::<T>::block_number();
+			ReportedMessages::<T>::insert(message_id, &reporter, true);
 
-			// Store profile
-			UserProfiles::<T>::insert(&who, bounded_key);
+			// Count the report against the sender
+			let mut record = Self::decayed_reports(SpamReports::<T>::get(&sender));
+			if record.reports.is_zero() {
+				record.last_decay = current_block;
+			}
+			record.reports = record.reports.saturating_add(1);
+			let reports = record.reports;
 
 			// Emit event
-			Self::deposit_event(Event::ProfileRegistered { who, public_key });
+			Self::deposit_event(Event::SpamReported {
+				message_id,
+				reporter,
+				sender: sender.clone(),
+				reports,
+			});
 
-			Ok(())
+			// Open a system challenge once enough reports have accumulated
+			if reports >= T::AutoChallengeThreshold::get() &&
+				!Challenges::<T>::contains_key(message_id)
+			{
+				SpamReports::<T>::remove(&sender);
+				Self::open_challenge(message_id, None, sender.clone(), Zero::zero());
+				Self::deposit_event(Event::AutoChallengeOpened { message_id, sender });
+			} else {
+				SpamReports::<T>::insert(&sender, record);
+			}
 		}
 
-		/// Update an existing user profile with a new public key.
+		/// Record a new open challenge against a message.
+		fn open_challenge(
+			message_id: MessageId,
+			challenger: Option<T::AccountId>,
+			sender: T::AccountId,
+			bond: BalanceOf<T>,
+		) {
+			OpenChallengeCount::<T>::mutate(&sender, |count| *count = count.saturating_add(1));
+			Challenges::<T>::insert(
+				message_id,
+				ChallengeInfo {
+					challenger,
+					sender,
+					opened_at: frame_system::Pallet::<T>::block_number(),
+					bond,
+					escrowed: Zero::zero(),
+					status: ChallengeStatus::Open,
+					terms: Self::parameters().slash_terms(),
+				},
+			);
+		}
+
+		/// Check if a message hash has expired.
 		///
-		/// Parameters:
-		/// - `public_key`: The new public key (max 256 bytes)
+		/// Also `true` for a message that does not exist, was retracted or was taken down.
+		#[deprecated(note = "use `message_state`, which tells these cases apart")]
+		pub fn is_message_expired(message_id: MessageId) -> bool {
+			!Self::message_state(message_id).is_live()
+		}
+
+		/// Where the direct message `message_id` stands.
+		pub fn message_state(message_id: MessageId) -> MessageStateOf<T> {
+			// Retraction and takedown remove the message, so a stored one is neither
+			let Some(message) = Self::message(message_id) else {
+				return if Takedowns::<T>::contains_key(message_id) {
+					MessageState::TakenDown
+				} else if RetractedMessages::<T>::contains_key(message_id) {
+					MessageState::Retracted
+				} else {
+					MessageState::NotFound
+				};
+			};
+			let expires_at = message.sent_at.saturating_add(Self::parameters().message_expiry);
+			if frame_system::Pallet::<T>::block_number() > expires_at {
+				MessageState::Expired
+			} else if Challenges::<T>::contains_key(message_id) {
+				MessageState::Challenged
+			} else {
+				MessageState::Active { expires_at }
+			}
+		}
+
+		/// The stored message `message_id`, or the error matching its state if it is not live.
+		fn live_message(message_id: MessageId) -> Result<MessageRecordOf<T>, DispatchError> {
+			let error = match Self::message_state(message_id) {
+				MessageState::Active { .. } | MessageState::Challenged =>
+					return Self::message(message_id).ok_or(Error::<T>::MessageNotFound.into()),
+				MessageState::Expired => Error::<T>::MessageExpired,
+				MessageState::NotFound => Error::<T>::MessageNotFound,
+				MessageState::Retracted => Error::<T>::MessageRetracted,
+				MessageState::TakenDown => Error::<T>::MessageRemovedByGovernance,
+			};
+			Err(error.into())
+		}
+
+		/// Hash committed to by profile and group events for `public_key`.
 		///
-		/// Emits `ProfileUpdated` event on success.
-		#[pallet::call_index(1)]
-		#[pallet::weight(T::WeightInfo::update_profile())]
-		pub fn update_profile(
-			origin: OriginFor<T>,
-			public_key: Vec<u8>,
-		) -> DispatchResult {
-			let who = ensure_signed(origin)?;
+		/// Clients recompute it over a key fetched from storage to check that it is the key
+		/// announced by a `ProfileRegistered`, `ProfileUpdated`, `GroupCreated` or
+		/// `GroupKeyRotated` event.
+		pub fn key_hash(public_key: &[u8]) -> T::Hash {
+			T::Hashing::hash(public_key)
+		}
 
-			// Check if profile exists
-			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+		/// Commitment to challenge evidence and the salt hiding it, as passed to
+		/// `challenge_spam` and checked by `reveal_evidence`.
+		pub fn evidence_commitment(evidence: &[u8], salt: &T::Hash) -> T::Hash {
+			T::Hashing::hash_of(&(evidence, salt))
+		}
 
-			// Validate public key
-			ensure!(!public_key.is_empty(), Error::<T>::InvalidPublicKey);
-			ensure!(public_key.len() <= 256, Error::<T>::PublicKeyTooLarge);
+		/// Latest contact-list backup commitment of `who`, if any.
+		pub fn latest_backup(who: &T::AccountId) -> Option<BackupCommitmentOf<T>> {
+			BackupCommitments::<T>::get(who).last().cloned()
+		}
 
-			// Convert to BoundedVec
-			let bounded_key: BoundedVec<u8, ConstU32<256>> =
-				public_key.clone().try_into().map_err(|_| Error::<T>::PublicKeyTooLarge)?;
+		/// Ephemeral key `who` published under `index`, if it has not expired.
+		pub fn ephemeral_key(who: &T::AccountId, index: u32) -> Option<EphemeralKey> {
+			let (key, published_at) = EphemeralKeys::<T>::get(who, index)?;
+			let expires_at = published_at.saturating_add(Self::parameters().message_expiry);
+			(frame_system::Pallet::<T>::block_number() <= expires_at).then_some(key)
+		}
 
-			// Update profile
-			UserProfiles::<T>::insert(&who, bounded_key);
+		/// Hash identifying a contact change made by `who` about `contact` in
+		/// `ContactApprovedMinimal` and `ContactRemovedMinimal` events.
+		pub fn contact_pair_hash(who: &T::AccountId, contact: &T::AccountId) -> T::Hash {
+			T::Hashing::hash_of(&(who, contact))
+		}
 
-			// Emit event
-			Self::deposit_event(Event::ProfileUpdated { who, public_key });
+		/// ID of the conversation between `a` and `b`, the same in both directions.
+		pub fn conversation_id(a: &T::AccountId, b: &T::AccountId) -> T::Hash {
+			if a <= b {
+				T::Hashing::hash_of(&(a, b))
+			} else {
+				T::Hashing::hash_of(&(b, a))
+			}
+		}
+
+		/// Profile of `who` fetched from `dest`, if it is still fresh.
+		pub fn remote_profile(dest: &Location, who: &T::AccountId) -> Option<RemoteProfileV1> {
+			let (profile, fetched_at) = RemoteProfiles::<T>::get(dest, who)?;
+			let fresh_until = fetched_at.saturating_add(T::RemoteProfileTtl::get());
+			(frame_system::Pallet::<T>::block_number() <= fresh_until).then_some(profile)
+		}
 
+		/// Dispatch `call` on the same pallet of the chain at `dest`.
+		fn send_transact(dest: Location, call: Call<T>) -> DispatchResult {
+			let call = <T as CreateTransactionBase<Call<T>>>::RuntimeCall::from(call);
+			let message = Xcm(vec![
+				UnpaidExecution { weight_limit: WeightLimit::Unlimited, check_origin: None },
+				Transact {
+					origin_kind: OriginKind::Xcm,
+					fallback_max_weight: None,
+					call: call.encode().into(),
+				},
+			]);
+			send_xcm::<T::XcmSender>(dest, message).map_err(|_| Error::<T>::XcmSendFailed)?;
 			Ok(())
 		}
 
-		/// Send a message by recording its hash on-chain.
-		///
-		/// The actual encrypted message should be transmitted off-chain.
-		/// Only the hash is stored for verification purposes.
-		///
-		/// Parameters:
-		/// - `recipient`: The account ID of the message recipient
-		/// - `message_hash`: The hash of the encrypted message
+		/// Weight of each call whose cost grows with a configured bound, at that bound, by
+		/// call name.
+		pub fn worst_case_weights() -> Vec<(&'static str, Weight)> {
+			let refunds = T::WeightInfo::refund_deposits_on_reply(T::MaxAutoRefundsPerReply::get());
+			let removals = T::MaxDisbandRemovalsPerCall::get();
+			let notices = T::MaxKeyResetNotifications::get();
+			vec![
+				(
+					"send_message_hash_batch",
+					T::WeightInfo::send_message_hash_batch(T::MaxSendBatch::get())
+						.saturating_add(refunds),
+				),
+				(
+					"prune_expired_unsigned",
+					T::WeightInfo::prune_expired_unsigned(T::MaxPrunesPerCall::get()),
+				),
+				("disband_group", T::WeightInfo::disband_group(removals, removals)),
+				("leave_group", T::WeightInfo::leave_group(T::MaxGroupMembers::get())),
+				("force_reset_key", T::WeightInfo::force_reset_key(notices)),
+				("continue_key_reset_notices", T::WeightInfo::continue_key_reset_notices(notices)),
+				(
+					"claim_pending_messages",
+					T::WeightInfo::claim_pending_messages(T::MaxClaimsPerCall::get()),
+				),
+				("accept_and_send", T::WeightInfo::accept_and_send().saturating_add(refunds)),
+				("send_message_hash", T::WeightInfo::send_message_hash().saturating_add(refunds)),
+				(
+					"send_to_recipient_set",
+					T::WeightInfo::send_to_recipient_set(MAX_RECIPIENT_SET_LEN),
+				),
+				(
+					"publish_ephemeral_key",
+					T::WeightInfo::publish_ephemeral_key(T::MaxEphemeralKeys::get()),
+				),
+			]
+		}
+
+		/// Whether a direct, group or pending message or an ephemeral key awaiting pruning had
+		/// expired by block `at`.
 		///
-		/// Emits `MessageSent` event on success.
-		#[pallet::call_index(2)]
-		#[pallet::weight(T::WeightInfo::send_message_hash())]
-		pub fn send_message_hash(
-			origin: OriginFor<T>,
-			recipient: T::AccountId,
-			message_hash: T::Hash,
-		) -> DispatchResult {
-			let sender = ensure_signed(origin)?;
+		/// Only the next `MaxPrunesPerCall` IDs from `PruneCursor`, `GroupPruneCursor`,
+		/// `PendingPruneCursor` and `EphemeralPruneCursor` are inspected; since messages and
+		/// keys are pruned in ID order, the first one still stored decides.
+		pub fn has_overdue_messages(at: BlockNumberFor<T>) -> bool {
+			let limit: MessageId = T::MaxPrunesPerCall::get().into();
+			let start = PruneCursor::<T>::get();
+			let end = NextMessageId::<T>::get().min(start.saturating_add(limit));
+			let expiry = Self::parameters().message_expiry;
+			let direct = (start..end)
+				.find_map(|message_id| {
+					Self::message(message_id)
+						.map(|message| message.sent_at)
+						.or_else(|| {
+							StealthMessages::<T>::get(message_id).map(|message| message.sent_at)
+						})
+						.or_else(|| {
+							RecipientSetMessages::<T>::get(message_id)
+								.map(|message| message.sent_at)
+						})
+						.or_else(|| RetractedMessages::<T>::get(message_id))
+				})
+				.is_some_and(|sent_at| at > sent_at.saturating_add(expiry));
 
-			// Verify sender has profile and bond
-			ensure!(UserProfiles::<T>::contains_key(&sender), Error::<T>::ProfileNotFound);
-			ensure!(SpamBonds::<T>::contains_key(&sender), Error::<T>::InsufficientBond);
+			let start = GroupPruneCursor::<T>::get();
+			let end = NextGroupMessageId::<T>::get().min(start.saturating_add(limit));
+			let group = (start..end)
+				.find_map(Self::group_message_expires_at)
+				.is_some_and(|expires_at| at > expires_at);
 
-			// Verify recipient exists
-			ensure!(
-				UserProfiles::<T>::contains_key(&recipient),
-				Error::<T>::RecipientNotFound
-			);
+			let start = PendingPruneCursor::<T>::get();
+			let end = NextPendingMessageId::<T>::get().min(start.saturating_add(limit));
+			let pending = (start..end)
+				.find_map(|pending_id| {
+					let account_hash = PendingMessageIds::<T>::get(pending_id)?;
+					PendingMessages::<T>::get(account_hash, pending_id)
+				})
+				.is_some_and(|message| {
+					at > message.sent_at.saturating_add(T::PendingMessageExpiry::get())
+				});
 
-			// Get current block number
-			let current_block = frame_system::Pallet::<T>::block_number();
+			let start = EphemeralPruneCursor::<T>::get();
+			let end = NextEphemeralKeyId::<T>::get().min(start.saturating_add(limit));
+			let keys = (start..end)
+				.find_map(EphemeralKeyIds::<T>::get)
+				.is_some_and(|(_, _, published_at)| at > published_at.saturating_add(expiry));
+			direct || group || pending || keys
+		}
 
-			// Get next message ID
-			let message_id = NextMessageId::<T>::get();
-			let next_id = message_id.saturating_add(1);
-			NextMessageId::<T>::put(next_id);
+		/// Which members of a group can send group messages.
+		pub fn posting_policy(group_id: GroupId) -> PostingPolicy {
+			GroupPostingPolicies::<T>::get(group_id).unwrap_or_default()
+		}
 
-			// Store message hash with metadata
-			MessageHashes::<T>::insert(
-				message_id,
-				(message_hash, current_block, sender.clone(), recipient.clone()),
-			);
+		/// Block until which `who` is muted in a group, or `None` if it can send.
+		pub fn muted_until(group_id: GroupId, who: &T::AccountId) -> Option<BlockNumberFor<T>> {
+			GroupMutes::<T>::get(group_id, who)
+				.filter(|until| frame_system::Pallet::<T>::block_number() < *until)
+		}
 
-			// Emit event
-			Self::deposit_event(Event::MessageSent {
-				message_id,
-				from: sender,
-				to: recipient,
-				hash: message_hash,
-			});
+		/// Lowest sequence number of a message of a conversation still stored, or `next_seq` if
+		/// none is.
+		pub fn lowest_conversation_seq(conversation_id: T::Hash, next_seq: u64) -> u64 {
+			(ConversationSeqFloors::<T>::get(conversation_id)..next_seq)
+				.find(|seq| ConversationMessages::<T>::contains_key(conversation_id, seq))
+				.unwrap_or(next_seq)
+		}
 
-			Ok(())
+		/// Where the stored message `message_id` stands.
+		fn message_status(message_id: MessageId) -> MessageStatus {
+			match Self::message_state(message_id) {
+				MessageState::Challenged => MessageStatus::Challenged,
+				MessageState::Active { .. } if Self::sender_departed(message_id) =>
+					MessageStatus::SenderDeparted,
+				MessageState::Active { .. } if InboxPayments::<T>::contains_key(message_id) =>
+					MessageStatus::AwaitingAcknowledgement,
+				MessageState::Active { .. } => MessageStatus::Active,
+				_ => MessageStatus::Expired,
+			}
 		}
 
-		/// Approve a contact for messaging.
-		///
-		/// Both parties must approve each other to establish a bidirectional contact.
-		///
-		/// Parameters:
-		/// - `contact`: The account ID to approve
+		/// Whether the sender of `message_id` deregistered after sending it.
+		fn sender_departed(message_id: MessageId) -> bool {
+			Self::message(message_id).is_some_and(|message| {
+				DepartedSenders::<T>::get(&message.sender)
+					.is_some_and(|departed_at| message.sent_at <= departed_at)
+			})
+		}
+
+		/// Remove the profile of `who` and the settings that only apply while it is registered.
+		fn remove_profile(who: &T::AccountId) {
+			if UserProfiles::<T>::take(who).is_some() {
+				Self::dec_profile_ref(who);
+			}
+			InboxPrices::<T>::remove(who);
+			InboxPricings::<T>::remove(who);
+			NotificationHooks::<T>::remove(who);
+			CompactEventSenders::<T>::remove(who);
+			if let Some(set) = DeviceSets::<T>::take(who).filter(|set| !set.devices.is_empty()) {
+				let deposit = set.devices.iter().fold(BalanceOf::<T>::zero(), |total, device| {
+					total.saturating_add(device.deposit)
+				});
+				Self::release_device_deposit(who, deposit);
+				Self::bump_devices_revision(who);
+			}
+		}
+
+		/// Remove the profile of `who` and record its departure.
 		///
-		/// Emits `ContactApproved` event on success.
-		#[pallet::call_index(3)]
-		#[pallet::weight(T::WeightInfo::approve_contact())]
-		pub fn approve_contact(
-			origin: OriginFor<T>,
-			contact: T::AccountId,
-		) -> DispatchResult {
-			let who = ensure_signed(origin)?;
+		/// The bond is released to whoever it is held from, unless open challenges or
+		/// messages sent within the last `BondUnbondingPeriod` blocks still need it. It then
+		/// stays held until `refund_bond` succeeds. Returns the amount released.
+		fn depart(who: &T::AccountId) -> Result<BalanceOf<T>, DispatchError> {
+			let bond_needed =
+				!OpenChallengeCount::<T>::get(who).is_zero() || !Self::locked_bond(who).is_zero();
+			let bond = SpamBonds::<T>::get(who);
+			let refunded = if bond_needed || bond.amount.is_zero() {
+				Zero::zero()
+			} else {
+				SpamBonds::<T>::remove(who);
+				BondLocks::<T>::remove(who);
+				T::Currency::release(
+					&HoldReason::SpamBond.into(),
+					bond.payer(who),
+					bond.amount,
+					Precision::BestEffort,
+				)?
+			};
 
-			// Cannot add yourself
-			ensure!(who != contact, Error::<T>::CannotAddSelf);
+			Self::remove_profile(who);
+			DepartedSenders::<T>::insert(who, frame_system::Pallet::<T>::block_number());
+			Ok(refunded)
+		}
 
-			// Check if profile exists
-			ensure!(UserProfiles::<T>::contains_key(&who), Error::<T>::ProfileNotFound);
+		/// Fail with `InvalidMessageHash` if `hash` is the default hash.
+		///
+		/// Hashes are never checked against the message itself, but the default hash is what
+		/// a client sends when it forgot to fill one in.
+		fn ensure_valid_hash(hash: &T::Hash) -> DispatchResult {
+			ensure!(*hash != T::Hash::default(), Error::<T>::InvalidMessageHash);
+			Ok(())
+		}
 
-			// Check contact exists
-			ensure!(UserProfiles::<T>::contains_key(&contact), Error::<T>::RecipientNotFound);
+		/// Whether `who` is the pallet account or one of its sub-accounts, none of which can
+		/// read messages.
+		pub fn is_reserved_account(who: &T::AccountId) -> bool {
+			let pallet_account: T::AccountId = T::PalletId::get().into_account_truncating();
+			*who == pallet_account ||
+				*who == Self::escrow_account() ||
+				*who == Self::reward_pool_account()
+		}
 
-			// Check max contacts limit
-			let current_count = ContactCount::<T>::get(&who);
-			ensure!(
-				current_count < T::MaxContactsPerUser::get(),
-				Error::<T>::MaxContactsReached
-			);
+		/// Fail with `DuplicateMessageHash` if `hash` is indexed to a message that has not
+		/// expired yet.
+		fn ensure_hash_unused(hash: &T::Hash) -> DispatchResult {
+			if let Some(message_id) = MessageIdByHash::<T>::get(hash) {
+				ensure!(
+					!Self::message_state(message_id).is_live(),
+					Error::<T>::DuplicateMessageHash
+				);
+			}
+			Ok(())
+		}
 
-			// Add to approved contacts
-			if !ApprovedContacts::<T>::get(&who, &contact) {
-				ApprovedContacts::<T>::insert(&who, &contact, true);
-				ContactCount::<T>::mutate(&who, |count| *count = count.saturating_add(1));
+		/// Drop the hash index entry of a removed message, unless the hash was re-indexed to a
+		/// later message after this one expired.
+		fn unindex_message_hash(message_id: MessageId, hash: &T::Hash) {
+			if MessageIdByHash::<T>::get(hash) == Some(message_id) {
+				MessageIdByHash::<T>::remove(hash);
 			}
+		}
 
-			// Emit event
-			Self::deposit_event(Event::ContactApproved { approver: who, contact });
+		/// Bump the inbox revision of `who` after a change to its inbox.
+		fn touch_inbox(who: &T::AccountId) {
+			InboxRevisions::<T>::mutate(who, |revision| revision.saturating_inc());
+		}
 
-			Ok(())
+		/// Count a paid message from `sender` to `recipient` as awaiting acknowledgement in
+		/// the statistics of their conversation, or as no longer awaiting it.
+		fn track_unacknowledged(sender: &T::AccountId, recipient: &T::AccountId, awaiting: bool) {
+			ConversationStats::<T>::mutate(Self::conversation_id(sender, recipient), |stats| {
+				let count = if sender <= recipient {
+					&mut stats.unacknowledged.0
+				} else {
+					&mut stats.unacknowledged.1
+				};
+				if awaiting {
+					count.saturating_inc();
+				} else {
+					count.saturating_dec();
+				}
+			});
 		}
 
-		/// Remove a contact from approved list.
-		///
-		/// This is a unilateral action that doesn't require the other party's consent.
-		///
-		/// Parameters:
-		/// - `contact`: The account ID to remove
+		/// Remove a message between `sender` and `recipient` from the conversation index.
 		///
-		/// Emits `ContactRemoved` event on success.
-		#[pallet::call_index(4)]
-		#[pallet::weight(T::WeightInfo::remove_contact())]
-		pub fn remove_contact(
-			origin: OriginFor<T>,
-			contact: T::AccountId,
-		) -> DispatchResult {
-			let who = ensure_signed(origin)?;
+		/// Pruned messages raise the conversation's floor; messages removed early leave a gap.
+		fn unindex_conversation_message(
+			message_id: MessageId,
+			sender: &T::AccountId,
+			recipient: &T::AccountId,
+			pruned: bool,
+		) {
+			let Some(seq) = MessageSequences::<T>::take(message_id) else { return };
+			let conversation_id = Self::conversation_id(sender, recipient);
+			ConversationMessages::<T>::remove(conversation_id, seq);
+			ConversationStats::<T>::mutate(conversation_id, |stats| {
+				stats.messages.saturating_dec()
+			});
+			if pruned {
+				ConversationSeqFloors::<T>::mutate(conversation_id, |floor| {
+					*floor = (*floor).max(seq.saturating_add(1))
+				});
+			}
+		}
 
-			// Remove from approved contacts
-			if ApprovedContacts::<T>::take(&who, &contact) {
-				ContactCount::<T>::mutate(&who, |count| *count = count.saturating_sub(1));
+		/// Lowest sequence number of a message of a group still stored, or `next_seq` if none
+		/// is.
+		pub fn lowest_retained_seq(group_id: GroupId, next_seq: u64) -> u64 {
+			(GroupSeqFloors::<T>::get(group_id)..next_seq)
+				.find(|seq| GroupMessagesBySeq::<T>::contains_key(group_id, seq))
+				.unwrap_or(next_seq)
+		}
+
+		/// Number of blocks for which the hashes of messages sent to a group verify.
+		pub fn group_retention(group_id: GroupId) -> BlockNumberFor<T> {
+			let max = T::GroupMessageExpiry::get();
+			GroupRetentions::<T>::get(group_id).map_or(max, |blocks| blocks.min(max))
+		}
+
+		/// Last block at which group message `message_id`, or its tombstone, is kept, or
+		/// `None` if neither is stored.
+		fn group_message_expires_at(message_id: MessageId) -> Option<BlockNumberFor<T>> {
+			let group_id = GroupMessageIds::<T>::get(message_id)?;
+			match GroupMessages::<T>::get(group_id, message_id) {
+				Some(message) => Some(Self::effective_expiry(group_id, &message)),
+				None => GroupMessageTombstones::<T>::get(group_id, message_id)
+					.map(|tombstone| tombstone.expires_at),
 			}
+		}
 
-			// Emit event
-			Self::deposit_event(Event::ContactRemoved { remover: who, contact });
+		/// Last block at which a group message verifies, honouring a retention shortened after
+		/// it was sent.
+		fn effective_expiry(
+			group_id: GroupId,
+			message: &GroupMessageRecordOf<T>,
+		) -> BlockNumberFor<T> {
+			message
+				.expires_at
+				.min(message.sent_at.saturating_add(Self::group_retention(group_id)))
+		}
 
-			Ok(())
+		/// Prune direct, group and pending messages and ephemeral keys that had expired by
+		/// block `up_to_block`, advancing over at most `limit` IDs in total and stopping once
+		/// `meter` cannot cover the next item.
+		///
+		/// Emits the pruning events of `prune_expired_unsigned` and returns the number of IDs
+		/// advanced over.
+		fn prune_overdue(
+			up_to_block: BlockNumberFor<T>,
+			limit: u32,
+			meter: &mut WeightMeter,
+		) -> u32 {
+			let (advanced, count) = Self::prune_expired(up_to_block, limit, meter);
+			let (group_advanced, group_count) = Self::prune_expired_group_messages(
+				up_to_block,
+				limit.saturating_sub(advanced),
+				meter,
+			);
+			if group_advanced > 0 {
+				Self::deposit_event(Event::ExpiredGroupMessagesPruned {
+					count: group_count,
+					next: GroupPruneCursor::<T>::get(),
+				});
+			}
+			let (pending_advanced, pending_count) = Self::prune_expired_pending_messages(
+				up_to_block,
+				limit.saturating_sub(advanced).saturating_sub(group_advanced),
+				meter,
+			);
+			if pending_advanced > 0 {
+				Self::deposit_event(Event::ExpiredPendingMessagesPruned {
+					count: pending_count,
+					next: PendingPruneCursor::<T>::get(),
+				});
+			}
+			let advanced = advanced.saturating_add(group_advanced).saturating_add(pending_advanced);
+			let (key_advanced, key_count) = Self::prune_expired_ephemeral_keys(
+				up_to_block,
+				limit.saturating_sub(advanced),
+				meter,
+			);
+			if key_advanced > 0 {
+				Self::deposit_event(Event::ExpiredEphemeralKeysPruned {
+					count: key_count,
+					next: EphemeralPruneCursor::<T>::get(),
+				});
+			}
+
+			Self::deposit_event(Event::ExpiredMessagesPruned {
+				count,
+				next: PruneCursor::<T>::get(),
+			});
+			advanced.saturating_add(key_advanced)
 		}
 
-		/// Challenge a message as spam.
+		/// Remove messages that had expired by block `at`, advancing `PruneCursor` over at
+		/// most `limit` IDs and charging each to `meter`.
 		///
-		/// This is a placeholder for governance-based spam challenges.
-		/// In a production system, this would integrate with governance or reputation systems.
+		/// Returns the number of IDs advanced over and the number of messages removed.
+		pub(crate) fn prune_expired(
+			at: BlockNumberFor<T>,
+			limit: u32,
+			meter: &mut WeightMeter,
+		) -> (u32, u32) {
+			let costs = PruneCosts::of::<T>();
+			let expiry = Self::parameters().message_expiry;
+			let end = NextMessageId::<T>::get();
+			let mut cursor = PruneCursor::<T>::get();
+			let (mut advanced, mut count) = (0u32, 0u32);
+			let now = frame_system::Pallet::<T>::block_number();
+			let mut reported = match ExpiryEventCount::<T>::get() {
+				(block, reported) if block == now => reported,
+				_ => 0,
+			};
+			let mut batch = None;
+			while advanced < limit && cursor < end {
+				if meter.try_consume(costs.index_entry).is_err() {
+					break;
+				}
+				// IDs without a stored message were already removed, e.g. by a takedown
+				if let Some(MessageRecord { sent_at, sender, recipient, hash }) =
+					MessageHashes::<T>::get(cursor)
+				{
+					if at <= sent_at.saturating_add(expiry) ||
+						meter.try_consume(costs.direct_message).is_err()
+					{
+						break;
+					}
+					MessageHashes::<T>::remove(cursor);
+					Self::unindex_message_hash(cursor, &hash);
+					Self::unindex_conversation_message(cursor, &sender, &recipient, true);
+					MessageEphemeralKeys::<T>::remove(cursor);
+					ReportedMessages::<T>::remove(cursor, &recipient);
+					MessageVerifications::<T>::remove(cursor);
+					AcknowledgedMessages::<T>::remove(cursor);
+					Self::touch_inbox(&recipient);
+					offchain_index::clear::<T>(&recipient, cursor);
+					let conversation_id = Self::conversation_id(&sender, &recipient);
+					Self::report_expired(cursor, Some(conversation_id), &mut reported, &mut batch);
+					count.saturating_inc();
+				} else if let Some(message) = StealthMessages::<T>::get(cursor) {
+					if at <= message.sent_at.saturating_add(expiry) ||
+						meter.try_consume(costs.direct_message).is_err()
+					{
+						break;
+					}
+					StealthMessages::<T>::remove(cursor);
+					StealthMessagesByPrefix::<T>::remove(
+						stealth::prefix_of(message.recipient_tag.as_ref()),
+						cursor,
+					);
+					Self::release_message_deposit(&message.sender, message.deposit);
+					Self::report_expired(cursor, None, &mut reported, &mut batch);
+					count.saturating_inc();
+				} else if let Some(sent_at) = RetractedMessages::<T>::get(cursor) {
+					if at <= sent_at.saturating_add(expiry) {
+						break;
+					}
+					RetractedMessages::<T>::remove(cursor);
+				} else if let Some(message) = RecipientSetMessages::<T>::get(cursor) {
+					// Each recipient's offchain index entry is cleared on top of the message
+					let recipients = message.recipients.len() as u64;
+					let cost = costs
+						.direct_message
+						.saturating_add(costs.index_entry.saturating_mul(recipients));
+					if at <= message.sent_at.saturating_add(expiry) ||
+						meter.try_consume(cost).is_err()
+					{
+						break;
+					}
+					RecipientSetMessages::<T>::remove(cursor);
+					Self::forget_reply_refundable(cursor, &message);
+					for recipient in &message.recipients {
+						offchain_index::clear::<T>(recipient, cursor);
+					}
+					Self::release_message_deposit(&message.sender, message.deposit);
+					Self::report_expired(cursor, None, &mut reported, &mut batch);
+					count.saturating_inc();
+				}
+				cursor.saturating_inc();
+				advanced.saturating_inc();
+			}
+			PruneCursor::<T>::put(cursor);
+			if let Some((batched, first_id, last_id)) = batch {
+				Self::deposit_event(Event::MessagesExpiredBatch {
+					count: batched,
+					first_id,
+					last_id,
+				});
+			}
+			if count > 0 {
+				ExpiryEventCount::<T>::put((now, reported));
+			}
+			(advanced, count)
+		}
+
+		/// Report the pruning of `message_id` in a `MessageExpired` event while fewer than
+		/// `MaxExpiryEventsPerBlock` were emitted in this block, and add it to `batch`, the
+		/// count and ID range of a `MessagesExpiredBatch` event, otherwise.
+		fn report_expired(
+			message_id: MessageId,
+			conversation_id: Option<T::Hash>,
+			reported: &mut u32,
+			batch: &mut Option<(u32, MessageId, MessageId)>,
+		) {
+			if *reported < T::MaxExpiryEventsPerBlock::get() {
+				reported.saturating_inc();
+				Self::deposit_event(Event::MessageExpired { message_id, conversation_id });
+				return;
+			}
+			let (count, _, last_id) = batch.get_or_insert((0, message_id, message_id));
+			count.saturating_inc();
+			*last_id = message_id;
+		}
+
+		/// Remove group messages that had expired by block `at`, advancing `GroupPruneCursor`
+		/// over at most `limit` IDs and charging each to `meter`.
 		///
-		/// Parameters:
-		/// - `message_id`: The ID of the message to challenge
+		/// Returns the number of IDs advanced over and the number of messages removed.
+		pub(crate) fn prune_expired_group_messages(
+			at: BlockNumberFor<T>,
+			limit: u32,
+			meter: &mut WeightMeter,
+		) -> (u32, u32) {
+			let costs = PruneCosts::of::<T>();
+			let end = NextGroupMessageId::<T>::get();
+			let mut cursor = GroupPruneCursor::<T>::get();
+			let (mut advanced, mut count) = (0u32, 0u32);
+			while advanced < limit && cursor < end {
+				if meter.try_consume(costs.index_entry).is_err() {
+					break;
+				}
+				if let Some(group_id) = GroupMessageIds::<T>::get(cursor) {
+					if let Some(message) = GroupMessages::<T>::get(group_id, cursor) {
+						if at <= Self::effective_expiry(group_id, &message) ||
+							meter.try_consume(costs.group_message).is_err()
+						{
+							break;
+						}
+						GroupMessages::<T>::remove(group_id, cursor);
+						GroupMessagesBySeq::<T>::remove(group_id, message.seq);
+						GroupSeqFloors::<T>::insert(group_id, message.seq.saturating_add(1));
+						count.saturating_inc();
+					} else if let Some(tombstone) =
+						GroupMessageTombstones::<T>::get(group_id, cursor)
+					{
+						if at <= tombstone.expires_at ||
+							meter.try_consume(costs.group_message).is_err()
+						{
+							break;
+						}
+						GroupMessageTombstones::<T>::remove(group_id, cursor);
+						GroupSeqFloors::<T>::insert(group_id, tombstone.seq.saturating_add(1));
+					}
+					GroupMessageIds::<T>::remove(cursor);
+				}
+				cursor.saturating_inc();
+				advanced.saturating_inc();
+			}
+			GroupPruneCursor::<T>::put(cursor);
+			(advanced, count)
+		}
+
+		/// Remove pending messages that had expired by block `at`, advancing
+		/// `PendingPruneCursor` over at most `limit` IDs and charging each to `meter`.
 		///
-		/// Emits `SpamChallenged` event on success.
-		#[pallet::call_index(5)]
-		#[pallet::weight(T::WeightInfo::challenge_spam())]
-		pub fn challenge_spam(
-			origin: OriginFor<T>,
-			message_id: MessageId,
-		) -> DispatchResult {
-			let challenger = ensure_signed(origin)?;
+		/// Returns the number of IDs advanced over and the number of messages removed.
+		fn prune_expired_pending_messages(
+			at: BlockNumberFor<T>,
+			limit: u32,
+			meter: &mut WeightMeter,
+		) -> (u32, u32) {
+			let costs = PruneCosts::of::<T>();
+			let expiry = T::PendingMessageExpiry::get();
+			let end = NextPendingMessageId::<T>::get();
+			let mut cursor = PendingPruneCursor::<T>::get();
+			let (mut advanced, mut count) = (0u32, 0u32);
+			while advanced < limit && cursor < end {
+				if meter.try_consume(costs.index_entry).is_err() {
+					break;
+				}
+				// IDs without an index entry were already claimed
+				if let Some(account_hash) = PendingMessageIds::<T>::get(cursor) {
+					if let Some(message) = PendingMessages::<T>::get(account_hash, cursor) {
+						if at <= message.sent_at.saturating_add(expiry) ||
+							meter.try_consume(costs.direct_message).is_err()
+						{
+							break;
+						}
+						PendingMessages::<T>::remove(account_hash, cursor);
+						Self::release_message_deposit(&message.sender, message.deposit);
+						count.saturating_inc();
+					}
+					PendingMessageIds::<T>::remove(cursor);
+				}
+				cursor.saturating_inc();
+				advanced.saturating_inc();
+			}
+			PendingPruneCursor::<T>::put(cursor);
+			(advanced, count)
+		}
 
-			// Verify message exists
-			let message_data =
-				MessageHashes::<T>::get(message_id).ok_or(Error::<T>::MessageNotFound)?;
-			let (_hash, _block, sender, _recipient) = message_data;
+		/// Remove ephemeral keys that had expired by block `at`, advancing
+		/// `EphemeralPruneCursor` over at most `limit` publications and charging each to
+		/// `meter`.
+		///
+		/// Returns the number of publications advanced over and the number of keys removed.
+		fn prune_expired_ephemeral_keys(
+			at: BlockNumberFor<T>,
+			limit: u32,
+			meter: &mut WeightMeter,
+		) -> (u32, u32) {
+			let costs = PruneCosts::of::<T>();
+			let expiry = Self::parameters().message_expiry;
+			let end = NextEphemeralKeyId::<T>::get();
+			let mut cursor = EphemeralPruneCursor::<T>::get();
+			let (mut advanced, mut count) = (0u32, 0u32);
+			while advanced < limit && cursor < end {
+				if meter.try_consume(costs.index_entry).is_err() {
+					break;
+				}
+				if let Some((who, index, published_at)) = EphemeralKeyIds::<T>::get(cursor) {
+					// The key itself is a second index entry to remove
+					if at <= published_at.saturating_add(expiry) ||
+						meter.try_consume(costs.index_entry).is_err()
+					{
+						break;
+					}
+					// Keys republished under the same index since are left for their own entry
+					if EphemeralKeys::<T>::get(&who, index)
+						.is_some_and(|(_, stored_at)| stored_at == published_at)
+					{
+						EphemeralKeys::<T>::remove(&who, index);
+						count.saturating_inc();
+					}
+					EphemeralKeyIds::<T>::remove(cursor);
+				}
+				cursor.saturating_inc();
+				advanced.saturating_inc();
+			}
+			EphemeralPruneCursor::<T>::put(cursor);
+			(advanced, count)
+		}
 
-			// In production, this would involve:
-			// 1. Governance vote
-			// 2. Reputation check
-			// 3. Challenge bond from challenger
-			// 4. Slash sender's bond if challenge succeeds
+		/// Return `amount` of the device deposit held from `who`.
+		fn release_device_deposit(who: &T::AccountId, amount: BalanceOf<T>) {
+			let _ = T::Currency::release(
+				&HoldReason::DeviceDeposit.into(),
+				who,
+				amount,
+				Precision::BestEffort,
+			);
+		}
 
-			// For now, just emit event
-			Self::deposit_event(Event::SpamChallenged { message_id, challenger });
+		/// Bump the device revision of `who` after a change to its devices, returning the new
+		/// revision.
+		fn bump_devices_revision(who: &T::AccountId) -> u32 {
+			DevicesRevisions::<T>::mutate(who, |revision| {
+				revision.saturating_inc();
+				*revision
+			})
+		}
 
-			Ok(())
+		/// Return `amount` of the message deposit held from `sender`.
+		fn release_message_deposit(sender: &T::AccountId, amount: BalanceOf<T>) {
+			let _ = T::Currency::release(
+				&HoldReason::MessageDeposit.into(),
+				sender,
+				amount,
+				Precision::BestEffort,
+			);
 		}
 
-		/// Claim back the spam bond.
-		///
-		/// Requires a clean history with no successful spam challenges.
-		/// This is a simplified version - production would include time locks.
-		///
-		/// Emits `BondRefunded` event on success.
-		#[pallet::call_index(6)]
-		#[pallet::weight(T::WeightInfo::refund_bond())]
-		pub fn refund_bond(origin: OriginFor<T>) -> DispatchResult {
-			let who = ensure_signed(origin)?;
+		/// Hash of `who`'s account ID, under which messages are left for it before it
+		/// registers.
+		pub fn account_hash(who: &T::AccountId) -> T::Hash {
+			T::Hashing::hash_of(who)
+		}
 
-			// Check if bond exists
-			let bond_amount = SpamBonds::<T>::get(&who);
-			ensure!(bond_amount > BalanceOf::<T>::default(), Error::<T>::BondAlreadyRefunded);
+		/// Check a hash against a stored message without failing.
+		pub fn verification_result(message_id: MessageId, hash: T::Hash) -> VerificationResult {
+			Self::check_hash(Self::verifiable_hash(message_id), hash)
+		}
 
-			// In production, add checks for:
-			// 1. Time lock (e.g., must wait 30 days)
-			// 2. No recent spam challenges
-			// 3. Account closure requirements
+		/// Check many hashes against stored messages without failing, in the order given.
+		///
+		/// IDs are looked up in ascending order and each only once, however often it is listed.
+		/// A bad entry only affects its own result. Callers outside the runtime go through
+		/// `verify_message_batch` or the runtime API, which bound the batch to
+		/// [`MAX_VERIFICATION_BATCH`].
+		pub fn verify_messages(batch: Vec<(MessageId, T::Hash)>) -> Vec<VerificationResult> {
+			let mut ids: Vec<MessageId> = batch.iter().map(|(id, _)| *id).collect();
+			ids.sort_unstable();
+			ids.dedup();
+			let stored: Vec<_> =
+				ids.into_iter().map(|id| (id, Self::verifiable_hash(id))).collect();
 
-			// Unreserve the bond
-			T::Currency::unreserve(&who, bond_amount);
+			batch
+				.into_iter()
+				.map(|(message_id, hash)| {
+					let stored = stored
+						.binary_search_by_key(&message_id, |(id, _)| *id)
+						.map_or(Err(VerificationResult::NotFound), |index| stored[index].1);
+					Self::check_hash(stored, hash)
+				})
+				.collect()
+		}
 
-			// Remove bond record
-			SpamBonds::<T>::remove(&who);
+		/// Hash of the message under `message_id` if it is live, or why it cannot be verified.
+		fn verifiable_hash(message_id: MessageId) -> Result<T::Hash, VerificationResult> {
+			match Self::message_state(message_id) {
+				MessageState::Active { .. } | MessageState::Challenged => Self::message(message_id)
+					.map(|message| message.hash)
+					.ok_or(VerificationResult::NotFound),
+				MessageState::Expired => Err(VerificationResult::Expired),
+				MessageState::NotFound => Err(VerificationResult::NotFound),
+				MessageState::Retracted => Err(VerificationResult::Retracted),
+				MessageState::TakenDown => Err(VerificationResult::RemovedByGovernance),
+			}
+		}
 
-			// Emit event
-			Self::deposit_event(Event::BondRefunded { who, amount: bond_amount });
+		fn check_hash(
+			stored: Result<T::Hash, VerificationResult>,
+			hash: T::Hash,
+		) -> VerificationResult {
+			match stored {
+				Ok(stored_hash) if stored_hash == hash => VerificationResult::Valid,
+				Ok(_) => VerificationResult::Mismatch,
+				Err(result) => result,
+			}
+		}
 
-			Ok(())
+		/// The stored message under `message_id`, if any.
+		pub fn message_view(
+			message_id: MessageId,
+		) -> Option<MessageRecordView<T::AccountId, T::Hash, BlockNumberFor<T>>> {
+			let MessageRecord { sender, recipient, hash, sent_at } = Self::message(message_id)?;
+			Some(MessageRecordView {
+				hash,
+				sender,
+				recipient,
+				sent_at,
+				expires_at: sent_at.saturating_add(Self::parameters().message_expiry),
+			})
 		}
-	}
 
-	impl<T: Config> Pallet<T> {
-		/// Check if a message hash has expired.
-		pub fn is_message_expired(message_id: MessageId) -> bool {
-			if let Some((_hash, block_number, _sender, _recipient)) =
-				MessageHashes::<T>::get(message_id)
-			{
-				let current_block = frame_system::Pallet::<T>::block_number();
-				let expiry = block_number.saturating_add(T::MessageHashExpiry::get());
-				current_block > expiry
-			} else {
-				true
+		fn message_summary(
+			message_id: MessageId,
+			sender: T::AccountId,
+			hash: T::Hash,
+			sent_at: BlockNumberFor<T>,
+		) -> MessageSummaryOf<T> {
+			MessageSummary {
+				sender,
+				hash,
+				sent_at,
+				expired: !Self::message_state(message_id).is_live(),
+				paid: InboxPayments::<T>::contains_key(message_id),
 			}
 		}
 
+		/// The profile registered by `who`, if any.
+		pub fn profile_view(who: &T::AccountId) -> Option<ProfileView> {
+			let public_key = Self::profile(who)?;
+			Some(ProfileView {
+				public_key: public_key.into_inner(),
+				algorithm: KeyAlgorithms::<T>::get(who),
+				key_version: KeyVersions::<T>::get(who),
+				bonded: Self::bond(who).unwrap_or_else(Zero::zero) >= Self::required_bond(who),
+				banned: Self::is_banned(who),
+			})
+		}
+
 		/// Verify a message hash matches what's stored on-chain.
+		///
+		/// Fails with the error matching the message's [`MessageState`] unless it is live.
 		pub fn verify_message_hash(
 			message_id: MessageId,
 			hash: T::Hash,
 		) -> Result<bool, DispatchError> {
-			let message_data =
-				MessageHashes::<T>::get(message_id).ok_or(Error::<T>::MessageNotFound)?;
-			let (stored_hash, _block, _sender, _recipient) = message_data;
+			Ok(Self::live_message(message_id)?.hash == hash)
+		}
+
+		/// Verify a hash against a message sent to a group.
+		///
+		/// Group counterpart of [`Self::verify_message_hash`].
+		pub fn verify_group_message(
+			group_id: GroupId,
+			message_id: MessageId,
+			hash: T::Hash,
+		) -> Result<bool, DispatchError> {
+			let message =
+				GroupMessages::<T>::get(group_id, message_id).ok_or(Error::<T>::MessageNotFound)?;
 
 			// Check if expired
-			ensure!(!Self::is_message_expired(message_id), Error::<T>::MessageExpired);
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <=
+					Self::effective_expiry(group_id, &message),
+				Error::<T>::MessageExpired
+			);
 
-			Ok(stored_hash == hash)
+			Ok(message.hash == hash)
 		}
 	}
 }
\ No newline at end of file
@@ -0,0 +1,83 @@
+//! Hooks for proving contact membership without naming the contact.
+//!
+//! `send_anonymous_contact_message` lets a relay carry a message whose sender proves it is
+//! an approved contact of the recipient without revealing which one. The pallet does not
+//! embed a proof system. It keeps one accumulator per account in `ContactAccumulators` and
+//! folds every contact the account approves or removes into it through the runtime's
+//! [`AccumulatorUpdater`]. Proofs are checked against the recipient's current accumulator by
+//! the runtime's [`VerifyMembershipProof`].
+//!
+//! An updater and a verifier only work as a pair: the verifier must understand the
+//! accumulators the updater produces. [`HashChain`] commits to the full history of contact
+//! changes and suits tests; a real deployment pairs an algebraic accumulator with a verifier
+//! for its membership proofs. The default verifier, `()`, rejects every proof.
+//!
+//! Accumulators start from the zero hash and only reflect contacts approved or removed
+//! while an updater was configured. Contacts approved before then can be removed and
+//! approved again to fold them in.
+
+use codec::Encode;
+use frame_support::weights::Weight;
+use sp_runtime::traits::Hash;
+use sp_std::marker::PhantomData;
+#[cfg(feature = "runtime-benchmarks")]
+use sp_std::vec::Vec;
+
+/// Checks proofs that the sender of a message is an approved contact of its recipient.
+pub trait VerifyMembershipProof<AccountId, Hash> {
+	/// Whether `proof` shows that the sender of `message_hash` is in the contact set of
+	/// `recipient` committed to by `accumulator`.
+	fn verify(recipient: &AccountId, accumulator: &Hash, message_hash: &Hash, proof: &[u8])
+		-> bool;
+
+	/// Weight of verifying a proof of `proof_len` bytes, charged on top of the call.
+	fn verification_weight(proof_len: u32) -> Weight;
+
+	/// A proof of `proof_len` bytes that `verify` accepts, if the verifier can produce one.
+	/// Benchmarks are skipped when it returns `None`.
+	#[cfg(feature = "runtime-benchmarks")]
+	fn valid_proof(
+		_recipient: &AccountId,
+		_accumulator: &Hash,
+		_message_hash: &Hash,
+		_proof_len: u32,
+	) -> Option<Vec<u8>> {
+		None
+	}
+}
+
+/// Rejects every proof.
+impl<AccountId, Hash> VerifyMembershipProof<AccountId, Hash> for () {
+	fn verify(_: &AccountId, _: &Hash, _: &Hash, _: &[u8]) -> bool {
+		false
+	}
+
+	fn verification_weight(_: u32) -> Weight {
+		Weight::zero()
+	}
+}
+
+/// Folds changes to an account's contact set into its accumulator.
+pub trait AccumulatorUpdater<AccountId, Hash> {
+	/// Accumulator after `contact` is approved.
+	fn add(accumulator: &Hash, contact: &AccountId) -> Hash;
+
+	/// Accumulator after `contact` is removed.
+	fn remove(accumulator: &Hash, contact: &AccountId) -> Hash;
+}
+
+/// Hashes each change onto the previous accumulator with `H`.
+///
+/// Two contact sets reached through different histories have different accumulators, so
+/// this suits tests and verifiers that replay the history rather than succinct proofs.
+pub struct HashChain<H>(PhantomData<H>);
+
+impl<AccountId: Encode, H: Hash> AccumulatorUpdater<AccountId, H::Output> for HashChain<H> {
+	fn add(accumulator: &H::Output, contact: &AccountId) -> H::Output {
+		H::hash_of(&(accumulator, true, contact))
+	}
+
+	fn remove(accumulator: &H::Output, contact: &AccountId) -> H::Output {
+		H::hash_of(&(accumulator, false, contact))
+	}
+}
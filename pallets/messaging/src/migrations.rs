@@ -0,0 +1,1870 @@
+//! Storage migrations for pallet-messaging.
+//!
+//! Every layout change bumps the pallet's storage version and ships a module `vN` holding
+//! a `MigrateV{N-1}ToVN` migration. It wraps an unversioned `UncheckedOnRuntimeUpgrade`
+//! body in `VersionedMigration`, so it only runs on chains at the previous version and
+//! bumps the version afterwards. Bodies follow the same pattern:
+//!
+//! - old layouts are read through `storage_alias` types kept next to the migration;
+//! - the returned weight is computed from the number of items actually migrated;
+//! - with `try-runtime`, `pre_upgrade` records item counts and a sample of entries, and
+//!   `post_upgrade` checks them against the migrated storage.
+//!
+//! Versioned migrations are collected in order in [`Migrations`], which runtimes add to
+//! their `Executive` migrations tuple. Adding version N means implementing
+//! `UncheckedOnRuntimeUpgrade` in a new `vN` module and appending `vN::MigrateV{N-1}ToVN` to
+//! [`Migrations`]. Since each step is guarded by the on-chain storage version, running the
+//! tuple again is a no-op, and a chain at any earlier version is brought up to date in one
+//! upgrade.
+//!
+//! ## Multi-block migrations
+//!
+//! Chains with too many messages to rewrite in one block run the layout changes from
+//! version 1 on as [`MultiBlockMigrations`] through `pallet-migrations` instead, keeping
+//! only the earlier ones in `Executive`:
+//!
+//! ```ignore
+//! pub type Executive = frame_executive::Executive<
+//!     // ...
+//!     pallet_messaging::migrations::v1::MigrateV0ToV1<Runtime>,
+//! >;
+//!
+//! impl pallet_migrations::Config for Runtime {
+//!     // ...
+//!     type Migrations = pallet_messaging::migrations::MultiBlockMigrations<Runtime>;
+//! }
+//! ```
+//!
+//! Chains whose spam bonds predate holds reach version 1 with [`v1::MigrateReservesToHolds`]
+//! in place of `v1::MigrateV0ToV1`, or with [`v1::LazyMigrateReservesToHolds`] ahead of
+//! [`MultiBlockMigrations`] when they hold too many bonds to move in one block.
+//!
+//! `pallet-migrations` blocks transactions until the migrations complete. Runtime APIs and
+//! offchain workers keep working: [`Pallet::message`] reads messages in either layout.
+
+use super::*;
+use frame_support::{
+	migrations::{MigrationId, SteppedMigration, SteppedMigrationError, VersionedMigration},
+	traits::{
+		fungible::{InspectHold, MutateHold},
+		GetStorageVersion, ReservableCurrency, UncheckedOnRuntimeUpgrade,
+	},
+	weights::WeightMeter,
+};
+use sp_std::marker::PhantomData;
+
+/// Prefix of the identifiers of the pallet's multi-block migrations.
+const PALLET_MIGRATIONS_ID: &[u8; 16] = b"pallet-messaging";
+
+/// Counter of [`MessageHashes`](crate::MessageHashes), for migrations that count messages
+/// themselves.
+#[frame_support::storage_alias]
+pub type CounterForMessageHashes<T: Config> = StorageValue<Pallet<T>, u32, ValueQuery>;
+
+/// Counter of [`UserProfiles`](crate::UserProfiles), for migrations that count profiles
+/// themselves.
+#[frame_support::storage_alias]
+pub type CounterForUserProfiles<T: Config> = StorageValue<Pallet<T>, u32, ValueQuery>;
+
+/// Moving the entries of double maps whose key hashers changed.
+///
+/// A map keeps its storage prefix when its hashers change, so entries under the old and the
+/// new hashers sit side by side until every entry is moved. [`rekey::move_entries`] tells
+/// them apart by hashing the keys it decodes again: only an entry stored under the old
+/// hashers sits at the raw key those keys hash to.
+pub mod rekey {
+	use super::*;
+	use codec::FullCodec;
+	use frame_support::storage::{unhashed, IterableStorageDoubleMap, PrefixIterator};
+
+	/// Raw storage key of the last entry a running re-keying visited.
+	pub type RawKey = BoundedVec<u8, ConstU32<256>>;
+
+	/// Moves the entries of `Old`, an alias of a map under its previous hashers, to `New`,
+	/// the map under its current ones, in storage order after the raw key `cursor`.
+	///
+	/// Visits entries while `meter` has `per_entry` left for each, and skips those already
+	/// under the new hashers. Returns the raw key of the last entry visited, or `None` once
+	/// no entry is left under the old hashers.
+	pub fn move_entries<Old, New, K1, K2, V>(
+		mut cursor: Option<RawKey>,
+		per_entry: Weight,
+		meter: &mut WeightMeter,
+	) -> Result<Option<RawKey>, SteppedMigrationError>
+	where
+		Old: IterableStorageDoubleMap<K1, K2, V, Iterator = PrefixIterator<(K1, K2, V)>>,
+		New: frame_support::storage::StorageDoubleMap<K1, K2, V>,
+		K1: FullCodec,
+		K2: FullCodec,
+		V: FullCodec,
+	{
+		if !meter.can_consume(per_entry) {
+			return Err(SteppedMigrationError::InsufficientWeight { required: per_entry });
+		}
+
+		let mut entries = match cursor.clone() {
+			Some(last) => Old::iter_from(last.into_inner()),
+			None => Old::iter(),
+		};
+		while meter.try_consume(per_entry).is_ok() {
+			let Some((key1, key2, value)) = entries.next() else { return Ok(None) };
+			let raw_key = entries.last_raw_key().to_vec();
+			// Entries under the new hashers decode to other keys, if at all
+			if Old::hashed_key_for(&key1, &key2) == raw_key {
+				unhashed::kill(&raw_key);
+				New::insert(key1, key2, value);
+			}
+			cursor = Some(raw_key.try_into().map_err(|_| SteppedMigrationError::Failed)?);
+		}
+
+		Ok(cursor)
+	}
+}
+
+/// Every versioned migration of the pallet, oldest first.
+pub type Migrations<T> = (
+	v1::MigrateV0ToV1<T>,
+	v2::MigrateV1ToV2<T>,
+	v3::MigrateV2ToV3<T>,
+	v4::MigrateV3ToV4<T>,
+	v5::MigrateV4ToV5<T>,
+	v6::MigrateV5ToV6<T>,
+	v7::MigrateV6ToV7<T>,
+	v8::MigrateV7ToV8<T>,
+	v9::MigrateV8ToV9<T>,
+	v10::MigrateV9ToV10<T>,
+	v11::MigrateV10ToV11<T>,
+);
+
+/// Multi-block versions of the migrations from version 1 on, oldest first.
+pub type MultiBlockMigrations<T> = (
+	v2::LazyMigrateV1ToV2<T>,
+	v3::LazyMigrateV2ToV3<T>,
+	v4::LazyMigrateV3ToV4<T>,
+	v5::LazyMigrateV4ToV5<T>,
+	v6::LazyMigrateV5ToV6<T>,
+	v7::LazyMigrateV6ToV7<T>,
+	v8::LazyMigrateV7ToV8<T>,
+	v9::LazyMigrateV8ToV9<T>,
+	v10::LazyMigrateV9ToV10<T>,
+	v11::LazyMigrateV10ToV11<T>,
+);
+
+/// Version 1: enriched message events, and spam bonds held under a named reason.
+pub mod v1 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// Marks the switch to the enriched `MessageSent` and `MessageAcknowledged` events.
+	///
+	/// No stored data changes: conversation sequences start counting from the upgrade. Only
+	/// the storage version is bumped, so indexers can tell from it which event layout a
+	/// block uses.
+	pub type MigrateV0ToV1<T> = VersionedMigration<
+		0,
+		1,
+		UncheckedMigrateV0ToV1<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV0ToV1`].
+	pub struct UncheckedMigrateV0ToV1<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV0ToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			Weight::zero()
+		}
+	}
+
+	/// Moves spam bonds from legacy unnamed reserves into the pallet's named
+	/// [`HoldReason::SpamBond`] hold, on the way to version 1.
+	///
+	/// Chains whose bonds predate holds run this in place of [`MigrateV0ToV1`], which changes
+	/// no data. `OldCurrency` is the `ReservableCurrency` the pallet was previously configured
+	/// with.
+	pub type MigrateReservesToHolds<T, OldCurrency> = VersionedMigration<
+		0,
+		1,
+		UncheckedMigrateReservesToHolds<T, OldCurrency>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Weight of moving one bond: the bond record, the account and its holds are read and
+	/// written.
+	fn move_bond_weight<T: Config>() -> Weight {
+		T::DbWeight::get().reads_writes(3, 3)
+	}
+
+	/// Move the bond of `who` from the legacy reserve into the hold.
+	///
+	/// Bonds taken after holds were introduced are left alone. Only what is actually
+	/// reserved is moved, and the bond record is trimmed to it. An account whose hold fails
+	/// keeps its bond on the reserve rather than having it freed.
+	fn move_bond<T, OldCurrency>(who: &T::AccountId, amount: BalanceOf<T>)
+	where
+		T: Config,
+		OldCurrency: ReservableCurrency<T::AccountId, Balance = BalanceOf<T>>,
+	{
+		let reason: T::RuntimeHoldReason = HoldReason::SpamBond.into();
+		if !T::Currency::balance_on_hold(&reason, who).is_zero() {
+			return;
+		}
+
+		let remaining = OldCurrency::unreserve(who, amount);
+		let moved = amount.saturating_sub(remaining);
+		if T::Currency::hold(&reason, who, moved).is_err() {
+			let _ = OldCurrency::reserve(who, moved);
+			return;
+		}
+		if moved != amount {
+			v4::SpamBonds::<T>::insert(who, moved);
+		}
+	}
+
+	/// Unversioned body of [`MigrateReservesToHolds`].
+	///
+	/// Bonds are read and written as bare amounts, the layout before version 4.
+	pub struct UncheckedMigrateReservesToHolds<T, OldCurrency>(PhantomData<(T, OldCurrency)>);
+
+	impl<T, OldCurrency> UncheckedOnRuntimeUpgrade
+		for UncheckedMigrateReservesToHolds<T, OldCurrency>
+	where
+		T: Config,
+		OldCurrency: ReservableCurrency<T::AccountId, Balance = BalanceOf<T>>,
+	{
+		fn on_runtime_upgrade() -> Weight {
+			let mut count = 0u64;
+			for (who, amount) in v4::SpamBonds::<T>::iter() {
+				count.saturating_inc();
+				move_bond::<T, OldCurrency>(&who, amount);
+			}
+
+			move_bond_weight::<T>().saturating_mul(count)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let reason: T::RuntimeHoldReason = HoldReason::SpamBond.into();
+			let mut count = 0u64;
+			let mut held = BalanceOf::<T>::zero();
+			for who in v4::SpamBonds::<T>::iter_keys() {
+				count.saturating_inc();
+				held.saturating_accrue(T::Currency::balance_on_hold(&reason, &who));
+			}
+			Ok((count, held).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let (count, held_before) = <(u64, BalanceOf<T>)>::decode(&mut &state[..])
+				.map_err(|_| "v1: undecodable pre-upgrade state")?;
+
+			let reason: T::RuntimeHoldReason = HoldReason::SpamBond.into();
+			let mut bonds = 0u64;
+			let mut held = BalanceOf::<T>::zero();
+			for (who, amount) in v4::SpamBonds::<T>::iter() {
+				bonds.saturating_inc();
+				let on_hold = T::Currency::balance_on_hold(&reason, &who);
+				ensure!(
+					on_hold == amount ||
+						(on_hold.is_zero() && !OldCurrency::reserved_balance(&who).is_zero()),
+					"v1: bond neither held in full nor left on the reserve"
+				);
+				held.saturating_accrue(on_hold);
+			}
+			ensure!(bonds == count, "v1: bond count changed");
+			ensure!(held >= held_before, "v1: total held decreased");
+			Ok(())
+		}
+	}
+
+	/// Multi-block version of [`MigrateReservesToHolds`].
+	///
+	/// Each step moves as many bonds as its weight allows, in storage order, continuing
+	/// after the last account visited. The step that finds no bond left bumps the storage
+	/// version to 1.
+	pub struct LazyMigrateReservesToHolds<T, OldCurrency>(PhantomData<(T, OldCurrency)>);
+
+	impl<T, OldCurrency> SteppedMigration for LazyMigrateReservesToHolds<T, OldCurrency>
+	where
+		T: Config,
+		OldCurrency: ReservableCurrency<T::AccountId, Balance = BalanceOf<T>>,
+	{
+		type Cursor = T::AccountId;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 0, version_to: 1 }
+		}
+
+		fn step(
+			mut cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 0 {
+				return Ok(None);
+			}
+
+			let required = move_bond_weight::<T>();
+			if !meter.can_consume(required) {
+				return Err(SteppedMigrationError::InsufficientWeight { required });
+			}
+
+			while meter.try_consume(required).is_ok() {
+				let mut remaining = match &cursor {
+					Some(last) => v4::SpamBonds::<T>::iter_from(
+						v4::SpamBonds::<T>::hashed_key_for(last),
+					),
+					None => v4::SpamBonds::<T>::iter(),
+				};
+				let Some((who, amount)) = remaining.next() else {
+					StorageVersion::new(1).put::<Pallet<T>>();
+					return Ok(None);
+				};
+
+				move_bond::<T, OldCurrency>(&who, amount);
+				cursor = Some(who);
+			}
+
+			Ok(cursor)
+		}
+	}
+}
+
+/// Version 2: messages stored as [`MessageRecord`]s.
+pub mod v2 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// Number of messages `pre_upgrade` records for `post_upgrade` to compare.
+	#[cfg(feature = "try-runtime")]
+	const SAMPLE_SIZE: usize = 16;
+
+	/// `MessageHashes` value before version 2: `(hash, sent_at, sender, recipient)`.
+	pub type OldMessageData<T> = (
+		<T as frame_system::Config>::Hash,
+		BlockNumberFor<T>,
+		<T as frame_system::Config>::AccountId,
+		<T as frame_system::Config>::AccountId,
+	);
+
+	/// `MessageHashes` as laid out before version 2.
+	#[frame_support::storage_alias]
+	pub type MessageHashes<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, MessageId, OldMessageData<T>, OptionQuery>;
+
+	/// Rewrites every stored message from the positional tuple into a [`MessageRecord`].
+	pub type MigrateV1ToV2<T> = VersionedMigration<
+		1,
+		2,
+		UncheckedMigrateV1ToV2<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Whether the message under `message_id` is stored as a [`MessageRecord`].
+	///
+	/// Below version 2, only messages up to the cursor of a running [`LazyMigrateV1ToV2`]
+	/// have been rewritten.
+	pub(crate) fn is_migrated<T: Config>(message_id: MessageId) -> bool {
+		if Pallet::<T>::on_chain_storage_version() >= 2 {
+			return true;
+		}
+		crate::MessageMigrationCursor::<T>::get().is_some_and(|last| {
+			crate::MessageHashes::<T>::hashed_key_for(message_id) <=
+				crate::MessageHashes::<T>::hashed_key_for(last)
+		})
+	}
+
+	/// Unversioned body of [`MigrateV1ToV2`].
+	pub struct UncheckedMigrateV1ToV2<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV1ToV2<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut count = 0u64;
+			crate::MessageHashes::<T>::translate::<OldMessageData<T>, _>(
+				|_, (hash, sent_at, sender, recipient)| {
+					count.saturating_inc();
+					Some(MessageRecord { sender, recipient, hash, sent_at })
+				},
+			);
+
+			T::DbWeight::get().reads_writes(count, count)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let count = MessageHashes::<T>::iter_keys().count() as u64;
+			let samples: Vec<(MessageId, OldMessageData<T>)> =
+				MessageHashes::<T>::iter().take(SAMPLE_SIZE).collect();
+			Ok((count, samples).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let (count, samples) =
+				<(u64, Vec<(MessageId, OldMessageData<T>)>)>::decode(&mut &state[..])
+					.map_err(|_| "v2: undecodable pre-upgrade state")?;
+
+			ensure!(
+				crate::MessageHashes::<T>::iter_values().count() as u64 == count,
+				"v2: message count changed"
+			);
+			for (message_id, (hash, sent_at, sender, recipient)) in samples {
+				ensure!(
+					crate::MessageHashes::<T>::get(message_id) ==
+						Some(MessageRecord { sender, recipient, hash, sent_at }),
+					"v2: message not carried over"
+				);
+			}
+			Ok(())
+		}
+	}
+
+	/// Multi-block version of [`MigrateV1ToV2`].
+	///
+	/// Each step rewrites as many messages as its weight allows, in storage order, and
+	/// records the last one in [`MessageMigrationCursor`](crate::MessageMigrationCursor) so
+	/// [`Pallet::message`] can tell rewritten messages from old ones meanwhile. The step that
+	/// finds no message left bumps the storage version to 2.
+	///
+	/// The message counter, not kept below version 7, tracks the messages rewritten so far
+	/// for the progress events, and so holds the number of messages once the migration
+	/// completes.
+	pub struct LazyMigrateV1ToV2<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV1ToV2<T> {
+		type Cursor = MessageId;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 1, version_to: 2 }
+		}
+
+		fn step(
+			mut cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 1 {
+				return Ok(None);
+			}
+
+			let required = T::WeightInfo::migrate_message_record();
+			if !meter.can_consume(required) {
+				return Err(SteppedMigrationError::InsufficientWeight { required });
+			}
+
+			// The first step starts counting afresh
+			let counted = match cursor {
+				Some(_) => CounterForMessageHashes::<T>::get(),
+				None => 0,
+			};
+			let mut migrated = 0u32;
+			while meter.try_consume(required).is_ok() {
+				let mut remaining = match cursor {
+					Some(last) =>
+						MessageHashes::<T>::iter_from(MessageHashes::<T>::hashed_key_for(last)),
+					None => MessageHashes::<T>::iter(),
+				};
+				let Some((message_id, (hash, sent_at, sender, recipient))) = remaining.next()
+				else {
+					let total = counted.saturating_add(migrated);
+					CounterForMessageHashes::<T>::put(total);
+					crate::MessageMigrationCursor::<T>::kill();
+					StorageVersion::new(2).put::<Pallet<T>>();
+					Pallet::<T>::deposit_event(Event::MessageMigrationAdvanced {
+						migrated,
+						total,
+						last: cursor,
+					});
+					Pallet::<T>::deposit_event(Event::MessageMigrationCompleted);
+					return Ok(None);
+				};
+
+				crate::MessageHashes::<T>::insert(
+					message_id,
+					MessageRecord { sender, recipient, hash, sent_at },
+				);
+				cursor = Some(message_id);
+				migrated.saturating_inc();
+			}
+
+			let total = counted.saturating_add(migrated);
+			CounterForMessageHashes::<T>::put(total);
+			crate::MessageMigrationCursor::<T>::set(cursor);
+			Pallet::<T>::deposit_event(Event::MessageMigrationAdvanced {
+				migrated,
+				total,
+				last: cursor,
+			});
+			Ok(cursor)
+		}
+	}
+}
+
+/// Version 3: profile events carry key hashes.
+pub mod v3 {
+	use super::*;
+
+	/// Marks the switch from raw public keys to key hashes in `ProfileRegistered` and
+	/// `ProfileUpdated`.
+	///
+	/// No stored data changes; the version tells indexers which profile event layout a
+	/// block uses.
+	pub type MigrateV2ToV3<T> = VersionedMigration<
+		2,
+		3,
+		UncheckedMigrateV2ToV3<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV2ToV3`].
+	pub struct UncheckedMigrateV2ToV3<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV2ToV3<T> {
+		fn on_runtime_upgrade() -> Weight {
+			Weight::zero()
+		}
+	}
+
+	/// [`MigrateV2ToV3`] as a single multi-block step, so it runs once
+	/// [`v2::LazyMigrateV1ToV2`](super::v2::LazyMigrateV1ToV2) has completed.
+	pub struct LazyMigrateV2ToV3<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV2ToV3<T> {
+		type Cursor = ();
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 2, version_to: 3 }
+		}
+
+		fn step(
+			_cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			let required = T::DbWeight::get().reads_writes(1, 1);
+			if meter.try_consume(required).is_err() {
+				return Err(SteppedMigrationError::InsufficientWeight { required });
+			}
+			if Pallet::<T>::on_chain_storage_version() == 2 {
+				StorageVersion::new(3).put::<Pallet<T>>();
+			}
+			Ok(None)
+		}
+	}
+}
+
+/// Version 4: spam bonds stored as [`BondRecord`]s.
+pub mod v4 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// Number of bonds `pre_upgrade` records for `post_upgrade` to compare.
+	#[cfg(feature = "try-runtime")]
+	const SAMPLE_SIZE: usize = 16;
+
+	/// `SpamBonds` as laid out before version 4: the amount held from the bonded account.
+	#[frame_support::storage_alias]
+	pub type SpamBonds<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		OptionQuery,
+	>;
+
+	/// Rewrites every spam bond from a bare amount into a [`BondRecord`] without a sponsor.
+	///
+	/// [`BondRecord`] also decodes the old layout, so bonds read correctly before and
+	/// during the migration.
+	pub type MigrateV3ToV4<T> = VersionedMigration<
+		3,
+		4,
+		UncheckedMigrateV3ToV4<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV3ToV4`].
+	pub struct UncheckedMigrateV3ToV4<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV3ToV4<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut count = 0u64;
+			crate::SpamBonds::<T>::translate::<BalanceOf<T>, _>(|_, amount| {
+				count.saturating_inc();
+				Some(BondRecord { amount, sponsor: None })
+			});
+
+			T::DbWeight::get().reads_writes(count, count)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let count = SpamBonds::<T>::iter_keys().count() as u64;
+			let samples: Vec<(T::AccountId, BalanceOf<T>)> =
+				SpamBonds::<T>::iter().take(SAMPLE_SIZE).collect();
+			Ok((count, samples).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let (count, samples) =
+				<(u64, Vec<(T::AccountId, BalanceOf<T>)>)>::decode(&mut &state[..])
+					.map_err(|_| "v4: undecodable pre-upgrade state")?;
+
+			ensure!(
+				crate::SpamBonds::<T>::iter_values().count() as u64 == count,
+				"v4: bond count changed"
+			);
+			for (who, amount) in samples {
+				ensure!(
+					crate::SpamBonds::<T>::get(&who) == BondRecord { amount, sponsor: None },
+					"v4: bond not carried over"
+				);
+			}
+			Ok(())
+		}
+	}
+
+	/// Multi-block version of [`MigrateV3ToV4`].
+	///
+	/// Each step rewrites as many bonds as its weight allows, in storage order, continuing
+	/// after the last account rewritten. The step that finds no bond left bumps the storage
+	/// version to 4.
+	pub struct LazyMigrateV3ToV4<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV3ToV4<T> {
+		type Cursor = T::AccountId;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 3, version_to: 4 }
+		}
+
+		fn step(
+			mut cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 3 {
+				return Ok(None);
+			}
+
+			let required = T::WeightInfo::migrate_spam_bond();
+			if !meter.can_consume(required) {
+				return Err(SteppedMigrationError::InsufficientWeight { required });
+			}
+
+			while meter.try_consume(required).is_ok() {
+				let mut remaining = match &cursor {
+					Some(last) => SpamBonds::<T>::iter_from(SpamBonds::<T>::hashed_key_for(last)),
+					None => SpamBonds::<T>::iter(),
+				};
+				let Some((who, amount)) = remaining.next() else {
+					StorageVersion::new(4).put::<Pallet<T>>();
+					return Ok(None);
+				};
+
+				crate::SpamBonds::<T>::insert(&who, BondRecord { amount, sponsor: None });
+				cursor = Some(who);
+			}
+
+			Ok(cursor)
+		}
+	}
+}
+
+/// Version 5: profiles keep their account alive.
+pub mod v5 {
+	use super::*;
+
+	/// Takes the sufficient reference that each profile now holds on its account. Version 11
+	/// replaces it with a consumer reference, see [`Pallet::inc_profile_ref`].
+	pub type MigrateV4ToV5<T> = VersionedMigration<
+		4,
+		5,
+		UncheckedMigrateV4ToV5<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV4ToV5`].
+	pub struct UncheckedMigrateV4ToV5<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV4ToV5<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut count = 0u64;
+			for who in crate::UserProfiles::<T>::iter_keys() {
+				frame_system::Pallet::<T>::inc_sufficients(&who);
+				count.saturating_inc();
+			}
+
+			T::DbWeight::get().reads_writes(count.saturating_mul(2), count)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let samples: Vec<(T::AccountId, u32)> = crate::UserProfiles::<T>::iter_keys()
+				.take(SAMPLE_SIZE)
+				.map(|who| {
+					let sufficients = frame_system::Pallet::<T>::sufficients(&who);
+					(who, sufficients)
+				})
+				.collect();
+			Ok(samples.encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let samples = <Vec<(T::AccountId, u32)>>::decode(&mut &state[..])
+				.map_err(|_| "v5: undecodable pre-upgrade state")?;
+
+			for (who, sufficients) in samples {
+				ensure!(
+					frame_system::Pallet::<T>::sufficients(&who) == sufficients + 1,
+					"v5: profile reference not taken"
+				);
+			}
+			Ok(())
+		}
+	}
+
+	/// Multi-block version of [`MigrateV4ToV5`].
+	///
+	/// Each step takes the reference of as many profiles as its weight allows, in storage
+	/// order, continuing after the last account handled. The step that finds no profile
+	/// left bumps the storage version to 5.
+	pub struct LazyMigrateV4ToV5<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV4ToV5<T> {
+		type Cursor = T::AccountId;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 4, version_to: 5 }
+		}
+
+		fn step(
+			mut cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 4 {
+				return Ok(None);
+			}
+
+			let required = T::DbWeight::get().reads_writes(2, 1);
+			if !meter.can_consume(required) {
+				return Err(SteppedMigrationError::InsufficientWeight { required });
+			}
+
+			while meter.try_consume(required).is_ok() {
+				let mut remaining = match &cursor {
+					Some(last) => crate::UserProfiles::<T>::iter_keys_from(
+						crate::UserProfiles::<T>::hashed_key_for(last),
+					),
+					None => crate::UserProfiles::<T>::iter_keys(),
+				};
+				let Some(who) = remaining.next() else {
+					StorageVersion::new(5).put::<Pallet<T>>();
+					return Ok(None);
+				};
+
+				frame_system::Pallet::<T>::inc_sufficients(&who);
+				cursor = Some(who);
+			}
+
+			Ok(cursor)
+		}
+	}
+}
+
+/// Version 6: governable slash parameters, snapshotted into challenges.
+pub mod v6 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// `ChallengeInfo` as laid out before version 6, without the slash terms.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+	pub struct OldChallengeInfo<AccountId, Balance, BlockNumber> {
+		pub challenger: Option<AccountId>,
+		pub sender: AccountId,
+		pub opened_at: BlockNumber,
+		pub bond: Balance,
+		pub escrowed: Balance,
+		pub status: ChallengeStatus<AccountId, Balance, BlockNumber>,
+	}
+
+	/// Challenge record as stored before version 6.
+	pub type OldChallengeInfoOf<T> = OldChallengeInfo<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		BlockNumberFor<T>,
+	>;
+
+	/// `Challenges` as laid out before version 6.
+	#[frame_support::storage_alias]
+	pub type Challenges<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, MessageId, OldChallengeInfoOf<T>, OptionQuery>;
+
+	impl<AccountId, Balance, BlockNumber> OldChallengeInfo<AccountId, Balance, BlockNumber> {
+		/// The challenge in the version 6 layout, settled with `terms`.
+		pub fn upgrade(
+			self,
+			terms: SlashTerms<Balance>,
+		) -> ChallengeInfo<AccountId, Balance, BlockNumber> {
+			ChallengeInfo {
+				challenger: self.challenger,
+				sender: self.sender,
+				opened_at: self.opened_at,
+				bond: self.bond,
+				escrowed: self.escrowed,
+				status: self.status,
+				terms,
+			}
+		}
+	}
+
+	/// Fills the slash parameters of stored [`PalletParameters`] in from the config.
+	///
+	/// Parameters stored before version 6 decode the new fields as zero; chains that never
+	/// stored parameters already read the config defaults.
+	fn migrate_parameters<T: Config>() {
+		crate::Parameters::<T>::mutate(|parameters| {
+			if let Some(parameters) = parameters {
+				parameters.challenger_reward_percent = T::ChallengerRewardPercent::get();
+				parameters.auto_slash_percent = T::AutoSlashPercent::get();
+				parameters.base_challenge_reward = T::BaseChallengeReward::get();
+				parameters.slash_destination = SlashDestination::Treasury;
+			}
+		});
+	}
+
+	/// Moves the slash parameters into [`PalletParameters`] and snapshots them into every
+	/// open challenge, which keeps settling with the config values it was opened under.
+	pub type MigrateV5ToV6<T> = VersionedMigration<
+		5,
+		6,
+		UncheckedMigrateV5ToV6<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV5ToV6`].
+	pub struct UncheckedMigrateV5ToV6<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV5ToV6<T> {
+		fn on_runtime_upgrade() -> Weight {
+			migrate_parameters::<T>();
+			let terms = Pallet::<T>::parameters().slash_terms();
+
+			let mut count = 0u64;
+			crate::Challenges::<T>::translate::<OldChallengeInfoOf<T>, _>(|_, challenge| {
+				count.saturating_inc();
+				Some(challenge.upgrade(terms.clone()))
+			});
+
+			T::DbWeight::get().reads_writes(count.saturating_add(1), count.saturating_add(1))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let count = Challenges::<T>::iter_keys().count() as u64;
+			Ok(count.encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let count = u64::decode(&mut &state[..])
+				.map_err(|_| "v6: undecodable pre-upgrade state")?;
+
+			let terms = Pallet::<T>::parameters().slash_terms();
+			ensure!(
+				crate::Challenges::<T>::iter_values().count() as u64 == count,
+				"v6: challenge count changed"
+			);
+			ensure!(
+				crate::Challenges::<T>::iter_values().all(|challenge| challenge.terms == terms),
+				"v6: slash terms not snapshotted"
+			);
+			Ok(())
+		}
+	}
+
+	/// Multi-block version of [`MigrateV5ToV6`].
+	///
+	/// The first step migrates the parameters. Each step then rewrites as many challenges as
+	/// its weight allows, in storage order, continuing after the last challenge rewritten. The
+	/// step that finds no challenge left bumps the storage version to 6.
+	pub struct LazyMigrateV5ToV6<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV5ToV6<T> {
+		type Cursor = MessageId;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 5, version_to: 6 }
+		}
+
+		fn step(
+			mut cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 5 {
+				return Ok(None);
+			}
+
+			// Every step reads the parameters, and the first rewrites them
+			let required = T::DbWeight::get().reads_writes(1, 1);
+			if !meter.can_consume(required.saturating_mul(2)) {
+				return Err(SteppedMigrationError::InsufficientWeight {
+					required: required.saturating_mul(2),
+				});
+			}
+			meter.consume(required);
+			if cursor.is_none() {
+				migrate_parameters::<T>();
+			}
+			let terms = Pallet::<T>::parameters().slash_terms();
+
+			while meter.try_consume(required).is_ok() {
+				let mut remaining = match cursor {
+					Some(last) => Challenges::<T>::iter_from(Challenges::<T>::hashed_key_for(last)),
+					None => Challenges::<T>::iter(),
+				};
+				let Some((message_id, challenge)) = remaining.next() else {
+					StorageVersion::new(6).put::<Pallet<T>>();
+					return Ok(None);
+				};
+
+				crate::Challenges::<T>::insert(message_id, challenge.upgrade(terms.clone()));
+				cursor = Some(message_id);
+			}
+
+			Ok(cursor)
+		}
+	}
+}
+
+/// Version 7: counted messages and profiles.
+pub mod v7 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// Initializes the counters of [`MessageHashes`](crate::MessageHashes) and
+	/// [`UserProfiles`](crate::UserProfiles), which became counted maps, from what they store.
+	pub type MigrateV6ToV7<T> = VersionedMigration<
+		6,
+		7,
+		UncheckedMigrateV6ToV7<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV6ToV7`].
+	pub struct UncheckedMigrateV6ToV7<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV6ToV7<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let messages = crate::MessageHashes::<T>::initialize_counter();
+			let profiles = crate::UserProfiles::<T>::initialize_counter();
+
+			T::DbWeight::get().reads_writes(u64::from(messages).saturating_add(profiles.into()), 2)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let messages = crate::MessageHashes::<T>::iter_keys().count() as u32;
+			let profiles = crate::UserProfiles::<T>::iter_keys().count() as u32;
+			Ok((messages, profiles).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let (messages, profiles) = <(u32, u32)>::decode(&mut &state[..])
+				.map_err(|_| "v7: undecodable pre-upgrade state")?;
+
+			ensure!(Pallet::<T>::message_count() == messages, "v7: wrong message count");
+			ensure!(Pallet::<T>::profile_count() == profiles, "v7: wrong profile count");
+			Ok(())
+		}
+	}
+
+	/// Position of a running [`LazyMigrateV6ToV7`].
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, MaxEncodedLen)]
+	pub enum CountCursor<AccountId> {
+		/// Counting messages, after the given one.
+		Messages(Option<MessageId>),
+		/// Every message counted, counting profiles after the given one.
+		Profiles(Option<AccountId>),
+	}
+
+	/// Multi-block version of [`MigrateV6ToV7`].
+	///
+	/// Each step counts as many messages, then profiles, as its weight allows, in storage
+	/// order, and adds them to the counters the first step resets. The step that finds no
+	/// profile left bumps the storage version to 7.
+	pub struct LazyMigrateV6ToV7<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV6ToV7<T> {
+		type Cursor = CountCursor<T::AccountId>;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 6, version_to: 7 }
+		}
+
+		fn step(
+			cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 6 {
+				return Ok(None);
+			}
+
+			// Every step reads and writes both counters, then reads one key per item
+			let overhead = T::DbWeight::get().reads_writes(2, 2);
+			let per_item = T::DbWeight::get().reads(1);
+			if !meter.can_consume(overhead.saturating_add(per_item)) {
+				return Err(SteppedMigrationError::InsufficientWeight {
+					required: overhead.saturating_add(per_item),
+				});
+			}
+			meter.consume(overhead);
+
+			let (mut messages, mut profiles) = match cursor {
+				Some(_) =>
+					(CounterForMessageHashes::<T>::get(), CounterForUserProfiles::<T>::get()),
+				None => (0, 0),
+			};
+			let mut position = cursor.unwrap_or(CountCursor::Messages(None));
+			while meter.try_consume(per_item).is_ok() {
+				position = match position {
+					CountCursor::Messages(last) => {
+						let mut remaining = match last {
+							Some(last) => crate::MessageHashes::<T>::iter_keys_from(
+								crate::MessageHashes::<T>::hashed_key_for(last),
+							),
+							None => crate::MessageHashes::<T>::iter_keys(),
+						};
+						match remaining.next() {
+							Some(message_id) => {
+								messages.saturating_inc();
+								CountCursor::Messages(Some(message_id))
+							},
+							None => CountCursor::Profiles(None),
+						}
+					},
+					CountCursor::Profiles(last) => {
+						let mut remaining = match &last {
+							Some(last) => crate::UserProfiles::<T>::iter_keys_from(
+								crate::UserProfiles::<T>::hashed_key_for(last),
+							),
+							None => crate::UserProfiles::<T>::iter_keys(),
+						};
+						let Some(who) = remaining.next() else {
+							CounterForMessageHashes::<T>::put(messages);
+							CounterForUserProfiles::<T>::put(profiles);
+							StorageVersion::new(7).put::<Pallet<T>>();
+							return Ok(None);
+						};
+						profiles.saturating_inc();
+						CountCursor::Profiles(Some(who))
+					},
+				};
+			}
+
+			CounterForMessageHashes::<T>::put(messages);
+			CounterForUserProfiles::<T>::put(profiles);
+			Ok(Some(position))
+		}
+	}
+}
+
+/// Version 8: dormancy and conversation sequences stored with the records they belong to.
+pub mod v8 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// Profiles flagged dormant, as stored before version 8.
+	#[frame_support::storage_alias]
+	pub type DormantProfiles<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		BlockNumberFor<T>,
+		OptionQuery,
+	>;
+
+	/// Number of messages sent in each conversation, as stored before version 8.
+	#[frame_support::storage_alias]
+	pub type ConversationSequences<T: Config> = StorageMap<
+		Pallet<T>,
+		Identity,
+		<T as frame_system::Config>::Hash,
+		u64,
+		OptionQuery,
+	>;
+
+	/// `profile` with the dormancy `who` was flagged with before version 8.
+	fn fold_profile<T: Config>(
+		who: &T::AccountId,
+		profile: ProfileRecordOf<T>,
+	) -> ProfileRecordOf<T> {
+		let dormant_since = DormantProfiles::<T>::take(who).or(profile.dormant_since);
+		ProfileRecord { dormant_since, ..profile }
+	}
+
+	/// `stats` with the sequence number `conversation_id` reached before version 8.
+	fn fold_stats<T: Config>(
+		conversation_id: &T::Hash,
+		stats: ConversationStatisticsOf<T>,
+	) -> ConversationStatisticsOf<T> {
+		let next_seq = ConversationSequences::<T>::take(conversation_id).unwrap_or(stats.next_seq);
+		ConversationStatistics { next_seq, ..stats }
+	}
+
+	/// Moves `DormantProfiles` into [`ProfileRecord::dormant_since`] and
+	/// `ConversationSequences` into [`ConversationStatistics::next_seq`].
+	///
+	/// [`ProfileRecord`] and [`ConversationStatistics`] also decode the old layouts, so
+	/// profiles and statistics read before and during the migration, as active and with no
+	/// message numbered.
+	pub type MigrateV7ToV8<T> = VersionedMigration<
+		7,
+		8,
+		UncheckedMigrateV7ToV8<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV7ToV8`].
+	pub struct UncheckedMigrateV7ToV8<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV7ToV8<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut count = 0u64;
+			crate::UserProfiles::<T>::translate::<ProfileRecordOf<T>, _>(|who, profile| {
+				count.saturating_inc();
+				Some(fold_profile::<T>(&who, profile))
+			});
+			crate::ConversationStats::<T>::translate::<ConversationStatisticsOf<T>, _>(
+				|conversation_id, stats| {
+					count.saturating_inc();
+					Some(fold_stats::<T>(&conversation_id, stats))
+				},
+			);
+
+			// Entries left over have no record to fold into
+			for (conversation_id, next_seq) in ConversationSequences::<T>::drain() {
+				count.saturating_inc();
+				crate::ConversationStats::<T>::mutate(conversation_id, |stats| {
+					stats.next_seq = next_seq
+				});
+			}
+			count.saturating_accrue(DormantProfiles::<T>::drain().count() as u64);
+
+			T::DbWeight::get().reads_writes(count.saturating_mul(2), count.saturating_mul(2))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let profiles = crate::UserProfiles::<T>::iter_keys().count() as u64;
+			let dormant = DormantProfiles::<T>::iter_keys()
+				.filter(|who| crate::UserProfiles::<T>::contains_key(who))
+				.count() as u64;
+			let numbered = ConversationSequences::<T>::iter_values()
+				.filter(|next_seq| *next_seq > 0)
+				.count() as u64;
+			Ok((profiles, dormant, numbered).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let (profiles, dormant, numbered) = <(u64, u64, u64)>::decode(&mut &state[..])
+				.map_err(|_| "v8: undecodable pre-upgrade state")?;
+
+			ensure!(
+				crate::UserProfiles::<T>::iter_values().count() as u64 == profiles,
+				"v8: profile count changed"
+			);
+			ensure!(
+				crate::UserProfiles::<T>::iter_values()
+					.filter(|profile| profile.dormant_since.is_some())
+					.count() as u64 == dormant,
+				"v8: dormancy not carried over"
+			);
+			ensure!(
+				crate::ConversationStats::<T>::iter_values()
+					.filter(|stats| stats.next_seq > 0)
+					.count() as u64 == numbered,
+				"v8: sequence numbers not carried over"
+			);
+			ensure!(DormantProfiles::<T>::iter_keys().next().is_none(), "v8: dormancy left over");
+			ensure!(
+				ConversationSequences::<T>::iter_keys().next().is_none(),
+				"v8: sequence numbers left over"
+			);
+			Ok(())
+		}
+	}
+
+	/// Position of a running [`LazyMigrateV7ToV8`].
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, MaxEncodedLen)]
+	pub enum FoldCursor<AccountId, Hash> {
+		/// Rewriting profiles, after the given one.
+		Profiles(Option<AccountId>),
+		/// Every profile rewritten, rewriting conversation statistics after the given one.
+		Conversations(Option<Hash>),
+		/// Every record rewritten, clearing the entries left over.
+		Leftovers,
+	}
+
+	/// Multi-block version of [`MigrateV7ToV8`].
+	///
+	/// Each step rewrites as many profiles, then conversation statistics, as its weight
+	/// allows, in storage order, continuing after the last record rewritten. It then clears
+	/// the old entries without a record. The step that finds none left bumps the storage
+	/// version to 8.
+	pub struct LazyMigrateV7ToV8<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV7ToV8<T> {
+		type Cursor = FoldCursor<T::AccountId, T::Hash>;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 7, version_to: 8 }
+		}
+
+		fn step(
+			cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 7 {
+				return Ok(None);
+			}
+
+			// Each item reads and writes a record and the old entry folded into it
+			let required = T::DbWeight::get().reads_writes(2, 2);
+			if !meter.can_consume(required) {
+				return Err(SteppedMigrationError::InsufficientWeight { required });
+			}
+
+			let mut position = cursor.unwrap_or(FoldCursor::Profiles(None));
+			while meter.try_consume(required).is_ok() {
+				position = match position {
+					FoldCursor::Profiles(last) => {
+						let mut remaining = match &last {
+							Some(last) => crate::UserProfiles::<T>::iter_from(
+								crate::UserProfiles::<T>::hashed_key_for(last),
+							),
+							None => crate::UserProfiles::<T>::iter(),
+						};
+						match remaining.next() {
+							Some((who, profile)) => {
+								let profile = fold_profile::<T>(&who, profile);
+								crate::UserProfiles::<T>::insert(&who, profile);
+								FoldCursor::Profiles(Some(who))
+							},
+							None => FoldCursor::Conversations(None),
+						}
+					},
+					FoldCursor::Conversations(last) => {
+						let mut remaining = match last {
+							Some(last) => crate::ConversationStats::<T>::iter_from(
+								crate::ConversationStats::<T>::hashed_key_for(last),
+							),
+							None => crate::ConversationStats::<T>::iter(),
+						};
+						match remaining.next() {
+							Some((conversation_id, stats)) => {
+								crate::ConversationStats::<T>::insert(
+									conversation_id,
+									fold_stats::<T>(&conversation_id, stats),
+								);
+								FoldCursor::Conversations(Some(conversation_id))
+							},
+							None => FoldCursor::Leftovers,
+						}
+					},
+					FoldCursor::Leftovers => {
+						if let Some((conversation_id, next_seq)) =
+							ConversationSequences::<T>::iter().next()
+						{
+							ConversationSequences::<T>::remove(conversation_id);
+							crate::ConversationStats::<T>::mutate(conversation_id, |stats| {
+								stats.next_seq = next_seq
+							});
+						} else if let Some(who) = DormantProfiles::<T>::iter_keys().next() {
+							DormantProfiles::<T>::remove(who);
+						} else {
+							StorageVersion::new(8).put::<Pallet<T>>();
+							return Ok(None);
+						}
+						FoldCursor::Leftovers
+					},
+				};
+			}
+
+			Ok(Some(position))
+		}
+	}
+}
+
+/// Version 9: second keys of the per-pair maps hashed with `Twox64Concat`.
+pub mod v9 {
+	use super::*;
+	use rekey::{move_entries, RawKey};
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// `ApprovedContacts` as laid out before version 9.
+	#[frame_support::storage_alias]
+	pub type ApprovedContacts<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		bool,
+		ValueQuery,
+	>;
+
+	/// `ConversationMessages` as laid out before version 9.
+	#[frame_support::storage_alias]
+	pub type ConversationMessages<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Identity,
+		<T as frame_system::Config>::Hash,
+		Blake2_128Concat,
+		u64,
+		MessageId,
+		OptionQuery,
+	>;
+
+	/// `InboxPasses` as laid out before version 9.
+	#[frame_support::storage_alias]
+	pub type InboxPasses<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		BlockNumberFor<T>,
+		OptionQuery,
+	>;
+
+	/// `ReplyRefundableDeposits` as laid out before version 9.
+	#[frame_support::storage_alias]
+	pub type ReplyRefundableDeposits<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		BoundedVec<MessageId, <T as Config>::MaxAutoRefundsPerReply>,
+		ValueQuery,
+	>;
+
+	/// Map a running [`LazyMigrateV8ToV9`] is re-keying, and the raw key of the last entry
+	/// it visited there.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, MaxEncodedLen)]
+	pub enum RekeyCursor {
+		/// Re-keying contact approvals.
+		ApprovedContacts(Option<RawKey>),
+		/// Re-keying the conversation message index.
+		ConversationMessages(Option<RawKey>),
+		/// Re-keying inbox passes.
+		InboxPasses(Option<RawKey>),
+		/// Re-keying the reply refund index.
+		ReplyRefundableDeposits(Option<RawKey>),
+	}
+
+	/// Re-keys entries from `cursor` on while `meter` allows, moving on to the next map once
+	/// one is done. Returns where to continue, or `None` once every map is re-keyed.
+	fn rekey_maps<T: Config>(
+		mut cursor: RekeyCursor,
+		meter: &mut WeightMeter,
+	) -> Result<Option<RekeyCursor>, SteppedMigrationError> {
+		// Benchmarked on `ReplyRefundableDeposits`, whose entries are the largest
+		let per_entry = T::WeightInfo::rekey_entry();
+		loop {
+			cursor = match cursor {
+				RekeyCursor::ApprovedContacts(last) => match move_entries::<
+					ApprovedContacts<T>,
+					crate::ApprovedContacts<T>,
+					_,
+					_,
+					_,
+				>(last, per_entry, meter)?
+				{
+					Some(last) => return Ok(Some(RekeyCursor::ApprovedContacts(Some(last)))),
+					None => RekeyCursor::ConversationMessages(None),
+				},
+				RekeyCursor::ConversationMessages(last) => match move_entries::<
+					ConversationMessages<T>,
+					crate::ConversationMessages<T>,
+					_,
+					_,
+					_,
+				>(last, per_entry, meter)?
+				{
+					Some(last) => return Ok(Some(RekeyCursor::ConversationMessages(Some(last)))),
+					None => RekeyCursor::InboxPasses(None),
+				},
+				RekeyCursor::InboxPasses(last) => match move_entries::<
+					InboxPasses<T>,
+					crate::InboxPasses<T>,
+					_,
+					_,
+					_,
+				>(last, per_entry, meter)?
+				{
+					Some(last) => return Ok(Some(RekeyCursor::InboxPasses(Some(last)))),
+					None => RekeyCursor::ReplyRefundableDeposits(None),
+				},
+				RekeyCursor::ReplyRefundableDeposits(last) => match move_entries::<
+					ReplyRefundableDeposits<T>,
+					crate::ReplyRefundableDeposits<T>,
+					_,
+					_,
+					_,
+				>(last, per_entry, meter)?
+				{
+					Some(last) => return Ok(Some(RekeyCursor::ReplyRefundableDeposits(Some(last)))),
+					None => return Ok(None),
+				},
+			};
+		}
+	}
+
+	/// Restarts running key reset notifications, whose cursors are raw `ApprovedContacts`
+	/// keys. Contacts are ordered differently under the new hasher, so a cursor cannot be
+	/// carried over without skipping some of them; contacts already notified are notified
+	/// again instead.
+	fn restart_key_resets<T: Config>() -> u64 {
+		let mut count = 0u64;
+		crate::KeyResetCursors::<T>::translate_values::<BoundedVec<u8, ConstU32<256>>, _>(|_| {
+			count.saturating_inc();
+			Some(BoundedVec::new())
+		});
+		count
+	}
+
+	/// Moves the entries of `ApprovedContacts`, `ConversationMessages`, `InboxPasses` and
+	/// `ReplyRefundableDeposits` to keys whose second part is hashed with `Twox64Concat`,
+	/// and restarts running key reset notifications.
+	pub type MigrateV8ToV9<T> = VersionedMigration<
+		8,
+		9,
+		UncheckedMigrateV8ToV9<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV8ToV9`].
+	pub struct UncheckedMigrateV8ToV9<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV8ToV9<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let restarted = restart_key_resets::<T>();
+			let mut meter = WeightMeter::new();
+			// An unlimited meter lets every map be re-keyed in one go
+			let _ = rekey_maps::<T>(RekeyCursor::ApprovedContacts(None), &mut meter);
+
+			meter.consumed().saturating_add(T::DbWeight::get().reads_writes(restarted, restarted))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let counts = (
+				ApprovedContacts::<T>::iter_keys().count() as u64,
+				ConversationMessages::<T>::iter_keys().count() as u64,
+				InboxPasses::<T>::iter_keys().count() as u64,
+				ReplyRefundableDeposits::<T>::iter_keys().count() as u64,
+			);
+			Ok(counts.encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let counts = <(u64, u64, u64, u64)>::decode(&mut &state[..])
+				.map_err(|_| "v9: undecodable pre-upgrade state")?;
+
+			let rekeyed = (
+				crate::ApprovedContacts::<T>::iter_keys().count() as u64,
+				crate::ConversationMessages::<T>::iter_keys().count() as u64,
+				crate::InboxPasses::<T>::iter_keys().count() as u64,
+				crate::ReplyRefundableDeposits::<T>::iter_keys().count() as u64,
+			);
+			ensure!(rekeyed == counts, "v9: entries lost while re-keying");
+			ensure!(
+				crate::KeyResetCursors::<T>::iter_values().all(|cursor| cursor.is_empty()),
+				"v9: key reset cursor not restarted"
+			);
+			Ok(())
+		}
+	}
+
+	/// Multi-block version of [`MigrateV8ToV9`].
+	///
+	/// The first step restarts key reset notifications. Each step then moves as many entries
+	/// as its weight allows, one map after the other in storage order, continuing after the
+	/// last entry visited. The step that finds no entry left bumps the storage version to 9.
+	///
+	/// Entries not moved yet are missing from what runtime APIs read until it completes.
+	pub struct LazyMigrateV8ToV9<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV8ToV9<T> {
+		type Cursor = RekeyCursor;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 8, version_to: 9 }
+		}
+
+		fn step(
+			cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 8 {
+				return Ok(None);
+			}
+
+			let cursor = match cursor {
+				Some(cursor) => cursor,
+				None => {
+					let restarted = restart_key_resets::<T>();
+					meter.consume(T::DbWeight::get().reads_writes(restarted, restarted));
+					RekeyCursor::ApprovedContacts(None)
+				},
+			};
+			let next = rekey_maps::<T>(cursor, meter)?;
+			if next.is_none() {
+				StorageVersion::new(9).put::<Pallet<T>>();
+			}
+			Ok(next)
+		}
+	}
+}
+
+/// Version 10: registration bonds and session requirements stored in profile records.
+pub mod v10 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// Spam bond each account was required to post when it registered, as stored before
+	/// version 10.
+	#[frame_support::storage_alias]
+	pub type RegistrationBonds<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		OptionQuery,
+	>;
+
+	/// Accounts that only receive messages over a confirmed session, as stored before
+	/// version 10.
+	#[frame_support::storage_alias]
+	pub type SessionRequired<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		(),
+		OptionQuery,
+	>;
+
+	/// `profile` with the registration bond and session requirement `who` had before
+	/// version 10.
+	fn fold_profile<T: Config>(
+		who: &T::AccountId,
+		profile: ProfileRecordOf<T>,
+	) -> ProfileRecordOf<T> {
+		let registration_bond = RegistrationBonds::<T>::take(who).or(profile.registration_bond);
+		let session_required =
+			SessionRequired::<T>::take(who).is_some() || profile.session_required;
+		ProfileRecord { registration_bond, session_required, ..profile }
+	}
+
+	/// Moves `RegistrationBonds` into [`ProfileRecord::registration_bond`] and
+	/// `SessionRequired` into [`ProfileRecord::session_required`].
+	///
+	/// [`ProfileRecord`] also decodes the old layout, so profiles read before and during the
+	/// migration as holding the constant bond and requiring no session.
+	pub type MigrateV9ToV10<T> = VersionedMigration<
+		9,
+		10,
+		UncheckedMigrateV9ToV10<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV9ToV10`].
+	pub struct UncheckedMigrateV9ToV10<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV9ToV10<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut count = 0u64;
+			crate::UserProfiles::<T>::translate::<ProfileRecordOf<T>, _>(|who, profile| {
+				count.saturating_inc();
+				Some(fold_profile::<T>(&who, profile))
+			});
+
+			// Entries left over have no profile to fold into
+			count.saturating_accrue(RegistrationBonds::<T>::drain().count() as u64);
+			count.saturating_accrue(SessionRequired::<T>::drain().count() as u64);
+
+			T::DbWeight::get().reads_writes(count.saturating_mul(3), count.saturating_mul(3))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let profiles = crate::UserProfiles::<T>::iter_keys().count() as u64;
+			let bonded = RegistrationBonds::<T>::iter_keys()
+				.filter(|who| crate::UserProfiles::<T>::contains_key(who))
+				.count() as u64;
+			let required = SessionRequired::<T>::iter_keys()
+				.filter(|who| crate::UserProfiles::<T>::contains_key(who))
+				.count() as u64;
+			Ok((profiles, bonded, required).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let (profiles, bonded, required) = <(u64, u64, u64)>::decode(&mut &state[..])
+				.map_err(|_| "v10: undecodable pre-upgrade state")?;
+
+			ensure!(
+				crate::UserProfiles::<T>::iter_values().count() as u64 == profiles,
+				"v10: profile count changed"
+			);
+			ensure!(
+				crate::UserProfiles::<T>::iter_values()
+					.filter(|profile| profile.registration_bond.is_some())
+					.count() as u64 == bonded,
+				"v10: registration bonds not carried over"
+			);
+			ensure!(
+				crate::UserProfiles::<T>::iter_values()
+					.filter(|profile| profile.session_required)
+					.count() as u64 == required,
+				"v10: session requirements not carried over"
+			);
+			ensure!(
+				RegistrationBonds::<T>::iter_keys().next().is_none(),
+				"v10: registration bonds left over"
+			);
+			ensure!(
+				SessionRequired::<T>::iter_keys().next().is_none(),
+				"v10: session requirements left over"
+			);
+			Ok(())
+		}
+	}
+
+	/// Position of a running [`LazyMigrateV9ToV10`].
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, MaxEncodedLen)]
+	pub enum FoldCursor<AccountId> {
+		/// Rewriting profiles, after the given one.
+		Profiles(Option<AccountId>),
+		/// Every profile rewritten, clearing the entries left over.
+		Leftovers,
+	}
+
+	/// Multi-block version of [`MigrateV9ToV10`].
+	///
+	/// Each step rewrites as many profiles as its weight allows, in storage order, continuing
+	/// after the last profile rewritten. It then clears the old entries without a profile.
+	/// The step that finds none left bumps the storage version to 10.
+	pub struct LazyMigrateV9ToV10<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV9ToV10<T> {
+		type Cursor = FoldCursor<T::AccountId>;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 9, version_to: 10 }
+		}
+
+		fn step(
+			cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 9 {
+				return Ok(None);
+			}
+
+			// Each profile reads and writes its record and the two old entries folded into it
+			let required = T::DbWeight::get().reads_writes(3, 3);
+			if !meter.can_consume(required) {
+				return Err(SteppedMigrationError::InsufficientWeight { required });
+			}
+
+			let mut position = cursor.unwrap_or(FoldCursor::Profiles(None));
+			while meter.try_consume(required).is_ok() {
+				position = match position {
+					FoldCursor::Profiles(last) => {
+						let mut remaining = match &last {
+							Some(last) => crate::UserProfiles::<T>::iter_from(
+								crate::UserProfiles::<T>::hashed_key_for(last),
+							),
+							None => crate::UserProfiles::<T>::iter(),
+						};
+						match remaining.next() {
+							Some((who, profile)) => {
+								let profile = fold_profile::<T>(&who, profile);
+								crate::UserProfiles::<T>::insert(&who, profile);
+								FoldCursor::Profiles(Some(who))
+							},
+							None => FoldCursor::Leftovers,
+						}
+					},
+					FoldCursor::Leftovers => {
+						if let Some(who) = RegistrationBonds::<T>::iter_keys().next() {
+							RegistrationBonds::<T>::remove(who);
+						} else if let Some(who) = SessionRequired::<T>::iter_keys().next() {
+							SessionRequired::<T>::remove(who);
+						} else {
+							StorageVersion::new(10).put::<Pallet<T>>();
+							return Ok(None);
+						}
+						FoldCursor::Leftovers
+					},
+				};
+			}
+
+			Ok(Some(position))
+		}
+	}
+}
+
+/// Version 11: profiles keep their account alive with a consumer reference.
+pub mod v11 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use sp_runtime::TryRuntimeError;
+
+	/// Swap the sufficient reference the profile of `who` holds for a consumer reference,
+	/// or list it in [`SufficientProfileRefs`] when the account has no funds to take one.
+	fn swap_profile_ref<T: Config>(who: &T::AccountId) {
+		if Pallet::<T>::inc_profile_ref(who).is_ok() {
+			frame_system::Pallet::<T>::dec_sufficients(who);
+		} else {
+			SufficientProfileRefs::<T>::insert(who, ());
+		}
+	}
+
+	/// Replaces the sufficient reference each profile holds on its account with a consumer
+	/// reference, see [`Pallet::inc_profile_ref`].
+	pub type MigrateV10ToV11<T> = VersionedMigration<
+		10,
+		11,
+		UncheckedMigrateV10ToV11<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+
+	/// Unversioned body of [`MigrateV10ToV11`].
+	pub struct UncheckedMigrateV10ToV11<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateV10ToV11<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut count = 0u64;
+			for who in crate::UserProfiles::<T>::iter_keys() {
+				swap_profile_ref::<T>(&who);
+				count.saturating_inc();
+			}
+
+			T::DbWeight::get().reads_writes(count.saturating_mul(2), count)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let samples: Vec<(T::AccountId, u32, u32)> = crate::UserProfiles::<T>::iter_keys()
+				.take(SAMPLE_SIZE)
+				.map(|who| {
+					let sufficients = frame_system::Pallet::<T>::sufficients(&who);
+					let consumers = frame_system::Pallet::<T>::consumers(&who);
+					(who, sufficients, consumers)
+				})
+				.collect();
+			Ok(samples.encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let samples = <Vec<(T::AccountId, u32, u32)>>::decode(&mut &state[..])
+				.map_err(|_| "v11: undecodable pre-upgrade state")?;
+
+			for (who, sufficients, consumers) in samples {
+				let swapped = if SufficientProfileRefs::<T>::contains_key(&who) {
+					frame_system::Pallet::<T>::sufficients(&who) == sufficients
+				} else {
+					frame_system::Pallet::<T>::sufficients(&who) + 1 == sufficients &&
+						frame_system::Pallet::<T>::consumers(&who) == consumers + 1
+				};
+				ensure!(swapped, "v11: profile reference not swapped");
+			}
+			Ok(())
+		}
+	}
+
+	/// Multi-block version of [`MigrateV10ToV11`].
+	///
+	/// Each step swaps the reference of as many profiles as its weight allows, in storage
+	/// order, continuing after the last account handled. The step that finds no profile
+	/// left bumps the storage version to 11.
+	pub struct LazyMigrateV10ToV11<T>(PhantomData<T>);
+
+	impl<T: Config> SteppedMigration for LazyMigrateV10ToV11<T> {
+		type Cursor = T::AccountId;
+		type Identifier = MigrationId<16>;
+
+		fn id() -> Self::Identifier {
+			MigrationId { pallet_id: *PALLET_MIGRATIONS_ID, version_from: 10, version_to: 11 }
+		}
+
+		fn step(
+			mut cursor: Option<Self::Cursor>,
+			meter: &mut WeightMeter,
+		) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+			if Pallet::<T>::on_chain_storage_version() != 10 {
+				return Ok(None);
+			}
+
+			let required = T::DbWeight::get().reads_writes(2, 1);
+			if !meter.can_consume(required) {
+				return Err(SteppedMigrationError::InsufficientWeight { required });
+			}
+
+			while meter.try_consume(required).is_ok() {
+				let mut remaining = match &cursor {
+					Some(last) => crate::UserProfiles::<T>::iter_keys_from(
+						crate::UserProfiles::<T>::hashed_key_for(last),
+					),
+					None => crate::UserProfiles::<T>::iter_keys(),
+				};
+				let Some(who) = remaining.next() else {
+					StorageVersion::new(11).put::<Pallet<T>>();
+					return Ok(None);
+				};
+
+				swap_profile_ref::<T>(&who);
+				cursor = Some(who);
+			}
+
+			Ok(cursor)
+		}
+	}
+}
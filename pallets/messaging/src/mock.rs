@@ -1,11 +1,29 @@
 use crate as pallet_messaging;
 use frame_support::{
-	derive_impl, parameter_types,
-	traits::{ConstU128, ConstU32, ConstU64},
+	derive_impl, ord_parameter_types, parameter_types,
+	traits::{
+		fungible::NativeOrWithId, tokens::imbalance::ResolveTo, AsEnsureOriginWithArg, ConstU32,
+		ConstU64, ConstU8, Contains, NeverEnsureOrigin, OnGenesis,
+	},
+	weights::{FixedFee, Weight},
+	PalletId,
 };
-use sp_runtime::BuildStorage;
+use frame_system::{EnsureRoot, EnsureSigned, EnsureSignedBy};
+use pallet_messaging::{
+	membership::{HashChain, VerifyMembershipProof},
+	BondCurrency,
+};
+use pallet_transaction_payment::FungibleAdapter;
+use sp_core::H256;
+use sp_runtime::{
+	testing::{TestSignature, UintAuthorityId},
+	traits::BlakeTwo256,
+	BuildStorage, Perbill,
+};
+use xcm::latest::Location;
 
 type Block = frame_system::mocking::MockBlock<Test>;
+pub type Extrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 pub type Balance = u128;
 
 // Configure a mock runtime to test the pallet.
@@ -37,6 +55,15 @@ mod runtime {
 
 	#[runtime::pallet_index(3)]
 	pub type Messaging = pallet_messaging;
+
+	#[runtime::pallet_index(4)]
+	pub type TransactionPayment = pallet_transaction_payment;
+
+	#[runtime::pallet_index(5)]
+	pub type Assets = pallet_assets;
+
+	#[runtime::pallet_index(6)]
+	pub type AssetsHolder = pallet_assets_holder;
 }
 
 #[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
@@ -52,7 +79,7 @@ impl pallet_balances::Config for Test {
 	type Balance = Balance;
 	type RuntimeEvent = RuntimeEvent;
 	type DustRemoval = ();
-	type ExistentialDeposit = ConstU128<1>;
+	type ExistentialDeposit = ExistentialDeposit;
 	type AccountStore = System;
 	type WeightInfo = ();
 	type FreezeIdentifier = ();
@@ -62,6 +89,21 @@ impl pallet_balances::Config for Test {
 	type DoneSlashHandler = ();
 }
 
+#[derive_impl(pallet_assets::config_preludes::TestDefaultConfig)]
+impl pallet_assets::Config for Test {
+	type Balance = Balance;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<u64>>;
+	type ForceOrigin = EnsureRoot<u64>;
+	type Freezer = ();
+	type Holder = AssetsHolder;
+}
+
+impl pallet_assets_holder::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeHoldReason = RuntimeHoldReason;
+}
+
 impl pallet_timestamp::Config for Test {
 	type Moment = u64;
 	type OnTimestampSet = ();
@@ -69,39 +111,345 @@ impl pallet_timestamp::Config for Test {
 	type WeightInfo = ();
 }
 
+impl pallet_transaction_payment::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type OnChargeTransaction = FungibleAdapter<Balances, ()>;
+	type OperationalFeeMultiplier = ConstU8<5>;
+	type WeightToFee = FixedFee<1, Balance>;
+	type LengthToFee = FixedFee<0, Balance>;
+	type FeeMultiplierUpdate = ();
+	type WeightInfo = ();
+}
+
 parameter_types! {
-	pub const SpamBond: Balance = 100;
-	pub const MaxContactsPerUser: u32 = 100;
-	pub const MessageHashExpiry: u64 = 1000;
+	pub static ExistentialDeposit: Balance = 1;
+	pub static SpamBond: Balance = 100;
+	pub static MaxContactsPerUser: u32 = 100;
+	pub const MaxPendingContactRequests: u32 = 3;
+	pub static MessageHashExpiry: u64 = 1000;
+	pub const MaxMessagesPerWindow: u32 = 10;
+	pub const RateLimitWindow: u64 = 10;
+	pub const RateLimitPenaltyStrikes: u32 = 1;
+	pub const BondPenaltyStrikes: u32 = 2;
+	pub const MaxStrikes: u32 = 3;
+	pub const StrikeDecayPeriod: u64 = 100;
+	pub const MinBondIncrement: Balance = 10;
+	pub const BondUnbondingPeriod: u64 = 50;
+	pub const InactivityThreshold: u64 = 200;
+	pub const DormantSweepThreshold: u64 = 1000;
+	pub const MessagingPalletId: PalletId = PalletId(*b"py/msgng");
+	pub const ChallengeBond: Balance = 20;
+	pub const ChallengeSlashPercent: Perbill = Perbill::from_percent(50);
+	pub const ChallengerRewardPercent: Perbill = Perbill::from_percent(50);
+	pub const BaseChallengeReward: Balance = 10;
+	pub static RewardPoolFeeShare: Perbill = Perbill::zero();
+	pub const AppealWindow: u64 = 10;
+	pub const ChallengeWindow: u64 = 500;
+	pub const AppealBond: Balance = 40;
+	pub const MaxReportsPerWindow: u32 = 3;
+	pub const ReportDecayPeriod: u64 = 100;
+	pub const AutoChallengeThreshold: u32 = 3;
+	pub static AutoSlashEnabled: bool = true;
+	pub static AutoSlashThreshold: u32 = 3;
+	pub const AutoSlashPercent: Perbill = Perbill::from_percent(50);
+	pub const ChallengeMaxDuration: u64 = 100;
+	pub const MaxFreeMessagesPerBlock: u32 = 2;
+	pub const InboxPassDuration: u64 = 20;
+	pub const MaxFreezeDuration: u64 = 100;
+	pub const TreasuryAccount: u64 = 99;
+	pub const MaxKeyResetNotifications: u32 = 3;
+	pub const TakedownRetention: u64 = 50;
+	pub const MaxPrunesPerCall: u32 = 3;
+	pub const MaxExpiryEventsPerBlock: u32 = 2;
+	pub const PruneInterval: u64 = 5;
+	pub static OffchainIndexing: bool = true;
+	pub const RemoteProfileTtl: u64 = 100;
+	pub const ProfileQueryTimeout: u64 = 10;
+	pub static BlockedRegistrants: Vec<u64> = vec![];
+	pub static VerifiedIdentities: Vec<u64> = vec![];
+	pub const GroupBaseDeposit: Balance = 40;
+	pub const GroupPerMemberDeposit: Balance = 10;
+	pub const MaxGroupsPerAccount: u32 = 3;
+	pub static MaxGroupMembers: u32 = 4;
+	pub const MaxGroupAdmins: u32 = 2;
+	pub const MaxGroupMessagesPerWindow: u32 = 5;
+	pub const GroupMessageExpiry: u64 = 500;
+	pub const GroupInviteExpiry: u64 = 10;
+	pub const MaxGroupInvites: u32 = 2;
+	pub const MaxPendingGroupInvites: u32 = 2;
+	pub const MaxGroupBans: u32 = 2;
+	pub const MaxDisbandRemovalsPerCall: u32 = 3;
+	pub const GroupMetadataDepositPerByte: Balance = 1;
+	pub const MaxDiscoverableGroups: u32 = 2;
+	pub const MaxGroupJoinRequests: u32 = 2;
+	pub const MaxGroupModerationLog: u32 = 3;
+	pub const StealthMessageDeposit: Balance = 30;
+	pub const RelayBond: Balance = 500;
+	pub const MaxRelayCarriesPerBlock: u32 = 2;
+	pub const PendingMessageDeposit: Balance = 40;
+	pub const PendingMessageExpiry: u64 = 3000;
+	pub const PendingMessageRefund: Perbill = Perbill::from_percent(75);
+	pub const MaxClaimsPerCall: u32 = 2;
+	pub const EvidenceRevealPeriod: u64 = 20;
+	pub const MaxEphemeralKeys: u32 = 3;
+	pub const RecipientSetDeposit: Balance = 10;
+	pub const BackupDeposit: Balance = 5;
+	pub const MaxAutoRefundsPerReply: u32 = 2;
+	pub const MaxSendBatch: u32 = 4;
+	pub const MaxDevicesPerAccount: u32 = 2;
+	pub const DeviceDeposit: Balance = 20;
+	pub const CreditPrice: Balance = 10;
+	pub const MaxCreditsPerAccount: u32 = 20;
+	pub const CreditRefund: Perbill = Perbill::from_percent(50);
 }
 
+ord_parameter_types! {
+	pub const Admin: u64 = 10;
+}
+
+/// Asset that bonds are held in while `BondAsset` selects it.
+pub const BOND_ASSET: u32 = 1;
+
+parameter_types! {
+	pub static BondAsset: NativeOrWithId<u32> = NativeOrWithId::Native;
+}
+
+/// Holds bonds in the native currency, or in `BOND_ASSET` once `BondAsset` selects it.
+pub type MessagingCurrency = BondCurrency<Balances, AssetsHolder, BondAsset, u64>;
+
 impl pallet_messaging::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
-	type Currency = Balances;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type Currency = MessagingCurrency;
 	type Time = Timestamp;
 	type WeightInfo = ();
 	type SpamBond = SpamBond;
 	type MaxContactsPerUser = MaxContactsPerUser;
+	type MaxPendingContactRequests = MaxPendingContactRequests;
 	type MessageHashExpiry = MessageHashExpiry;
+	type ResolutionOrigin = EnsureRoot<u64>;
+	type MaxMessagesPerWindow = MaxMessagesPerWindow;
+	type RateLimitWindow = RateLimitWindow;
+	type RateLimitPenaltyStrikes = RateLimitPenaltyStrikes;
+	type BondPenaltyStrikes = BondPenaltyStrikes;
+	type MaxStrikes = MaxStrikes;
+	type StrikeDecayPeriod = StrikeDecayPeriod;
+	type MinBondIncrement = MinBondIncrement;
+	type BondUnbondingPeriod = BondUnbondingPeriod;
+	type InactivityThreshold = InactivityThreshold;
+	type DormantSweepThreshold = DormantSweepThreshold;
+	type PalletId = MessagingPalletId;
+	type ChallengeBond = ChallengeBond;
+	type ChallengeSlashPercent = ChallengeSlashPercent;
+	type ChallengerRewardPercent = ChallengerRewardPercent;
+	type BaseChallengeReward = BaseChallengeReward;
+	type RewardPoolFeeShare = RewardPoolFeeShare;
+	type AppealWindow = AppealWindow;
+	type ChallengeWindow = ChallengeWindow;
+	type AppealBond = AppealBond;
+	type AppealOrigin = EnsureRoot<u64>;
+	type AdminOrigin = EnsureSignedBy<Admin, u64>;
+	type ForceOrigin = EnsureRoot<u64>;
+	type MaxFreezeDuration = MaxFreezeDuration;
+	type MaxReportsPerWindow = MaxReportsPerWindow;
+	type ReportDecayPeriod = ReportDecayPeriod;
+	type AutoChallengeThreshold = AutoChallengeThreshold;
+	type AutoSlashEnabled = AutoSlashEnabled;
+	type AutoSlashThreshold = AutoSlashThreshold;
+	type AutoSlashPercent = AutoSlashPercent;
+	type ChallengeMaxDuration = ChallengeMaxDuration;
+	type MaxFreeMessagesPerBlock = MaxFreeMessagesPerBlock;
+	type InboxPassDuration = InboxPassDuration;
+	type SlashIncome = ();
+	type FeeIncome = ResolveTo<TreasuryAccount, MessagingCurrency>;
+	type ForfeitedDeposits = ResolveTo<TreasuryAccount, MessagingCurrency>;
+	type ProtocolFeeIncome = ();
+	type MaxKeyResetNotifications = MaxKeyResetNotifications;
+	type TakedownRetention = TakedownRetention;
+	type MaxPrunesPerCall = MaxPrunesPerCall;
+	type MaxExpiryEventsPerBlock = MaxExpiryEventsPerBlock;
+	type PruneInterval = PruneInterval;
+	type PruneUnsignedPriority = ConstU64<100>;
+	type OffchainIndexing = OffchainIndexing;
+	type XcmSender = ();
+	type XcmOrigin = NeverEnsureOrigin<Location>;
+	type RemoteProfileTtl = RemoteProfileTtl;
+	type ProfileQueryTimeout = ProfileQueryTimeout;
+	type RegistrationGate = RegistrationGate;
+	type IdentityVerifier = IdentityVerifier;
+	type GroupBaseDeposit = GroupBaseDeposit;
+	type GroupPerMemberDeposit = GroupPerMemberDeposit;
+	type MaxGroupsPerAccount = MaxGroupsPerAccount;
+	type MaxGroupMembers = MaxGroupMembers;
+	type MaxGroupAdmins = MaxGroupAdmins;
+	type MaxGroupMessagesPerWindow = MaxGroupMessagesPerWindow;
+	type GroupMessageExpiry = GroupMessageExpiry;
+	type GroupInviteExpiry = GroupInviteExpiry;
+	type MaxGroupInvites = MaxGroupInvites;
+	type MaxPendingGroupInvites = MaxPendingGroupInvites;
+	type MaxGroupBans = MaxGroupBans;
+	type MaxDisbandRemovalsPerCall = MaxDisbandRemovalsPerCall;
+	type GroupMetadataDepositPerByte = GroupMetadataDepositPerByte;
+	type MaxDiscoverableGroups = MaxDiscoverableGroups;
+	type MaxGroupJoinRequests = MaxGroupJoinRequests;
+	type MaxGroupModerationLog = MaxGroupModerationLog;
+	type StealthMessageDeposit = StealthMessageDeposit;
+	type RelayBond = RelayBond;
+	type MaxRelayCarriesPerBlock = MaxRelayCarriesPerBlock;
+	type PendingMessageDeposit = PendingMessageDeposit;
+	type PendingMessageExpiry = PendingMessageExpiry;
+	type PendingMessageRefund = PendingMessageRefund;
+	type MaxClaimsPerCall = MaxClaimsPerCall;
+	type EvidenceRevealPeriod = EvidenceRevealPeriod;
+	type MembershipProofVerifier = AccumulatorProof;
+	type AccumulatorUpdater = HashChain<BlakeTwo256>;
+	type MaxEphemeralKeys = MaxEphemeralKeys;
+	type RecipientSetDeposit = RecipientSetDeposit;
+	type BackupDeposit = BackupDeposit;
+	type MaxAutoRefundsPerReply = MaxAutoRefundsPerReply;
+	type MaxSendBatch = MaxSendBatch;
+	type MaxDevicesPerAccount = MaxDevicesPerAccount;
+	type DeviceDeposit = DeviceDeposit;
+	type CreditPrice = CreditPrice;
+	type MaxCreditsPerAccount = MaxCreditsPerAccount;
+	type CreditRefund = CreditRefund;
+	type OffchainSignature = TestSignature;
+	type SigningPublicKey = UintAuthorityId;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = SponsorshipSigner;
+}
+
+/// Accepts a non-empty proof made of the recipient's accumulator bytes, repeated as needed.
+pub struct AccumulatorProof;
+impl AccumulatorProof {
+	pub fn proof_for(accumulator: &H256, proof_len: u32) -> Vec<u8> {
+		accumulator.as_bytes().iter().copied().cycle().take(proof_len as usize).collect()
+	}
+}
+
+impl VerifyMembershipProof<u64, H256> for AccumulatorProof {
+	fn verify(_: &u64, accumulator: &H256, _: &H256, proof: &[u8]) -> bool {
+		!proof.is_empty() && proof == Self::proof_for(accumulator, proof.len() as u32)
+	}
+
+	fn verification_weight(proof_len: u32) -> Weight {
+		Weight::from_parts(1_000 * proof_len as u64, 0)
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn valid_proof(_: &u64, accumulator: &H256, _: &H256, proof_len: u32) -> Option<Vec<u8>> {
+		Some(Self::proof_for(accumulator, proof_len))
+	}
+}
+
+/// Signs sponsorship proofs as account 7 for benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct SponsorshipSigner;
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_messaging::SponsorshipBenchmarkHelper<u64, TestSignature> for SponsorshipSigner {
+	fn sign(payload: &[u8]) -> Option<(u64, TestSignature)> {
+		Some((7, TestSignature(7, payload.to_vec())))
+	}
+}
+
+/// Admits every account not listed in `BlockedRegistrants`.
+pub struct RegistrationGate;
+impl Contains<u64> for RegistrationGate {
+	fn contains(who: &u64) -> bool {
+		!BlockedRegistrants::get().contains(who)
+	}
+}
+
+/// Admits the accounts listed in `VerifiedIdentities`.
+pub struct IdentityVerifier;
+impl Contains<u64> for IdentityVerifier {
+	fn contains(who: &u64) -> bool {
+		VerifiedIdentities::get().contains(who)
+	}
+}
+
+impl<C> frame_system::offchain::CreateTransactionBase<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	type Extrinsic = Extrinsic;
+	type RuntimeCall = RuntimeCall;
+}
+
+impl<C> frame_system::offchain::CreateBare<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	fn create_bare(call: RuntimeCall) -> Extrinsic {
+		Extrinsic::new_bare(call)
+	}
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	ExtBuilder::default().build()
+}
+
+/// Builder for test externalities with messaging state seeded at genesis.
+#[derive(Default)]
+pub struct ExtBuilder {
+	messaging: crate::GenesisConfig<Test>,
+}
+
+impl ExtBuilder {
+	/// Register `who` with `public_key` at genesis.
+	pub fn with_profile(mut self, who: u64, public_key: Vec<u8>) -> Self {
+		self.messaging.profiles.push((who, public_key));
+		self
+	}
+
+	/// Approve `a` and `b` as contacts of each other at genesis.
+	pub fn with_contact(mut self, a: u64, b: u64) -> Self {
+		self.messaging.initial_contacts.push((a, b));
+		self
+	}
 
-	pallet_balances::GenesisConfig::<Test> {
-		balances: vec![
+	/// Start message IDs at `next_message_id`.
+	pub fn with_next_message_id(mut self, next_message_id: crate::MessageId) -> Self {
+		self.messaging.initial_next_message_id = next_message_id;
+		self
+	}
+
+	pub fn build(self) -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+
+		let balances = vec![
 			(1, 1000),
 			(2, 1000),
 			(3, 1000),
 			(4, 50), // Insufficient balance for bond
-		],
-		dev_accounts: None,
-	}
-	.assimilate_storage(&mut t)
-	.unwrap();
+			(5, 1000),
+			(6, 1000),
+			(7, 1000),
+			(TreasuryAccount::get(), 1),
+		];
 
-	let mut ext = sp_io::TestExternalities::new(t);
-	ext.execute_with(|| System::set_block_number(1));
-	ext
+		// The bond asset mirrors the native balances
+		pallet_assets::GenesisConfig::<Test> {
+			assets: vec![(BOND_ASSET, Admin::get(), true, 1)],
+			accounts: balances.iter().map(|&(who, amount)| (BOND_ASSET, who, amount)).collect(),
+			..Default::default()
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+		pallet_balances::GenesisConfig::<Test> { balances, dev_accounts: None }
+			.assimilate_storage(&mut t)
+			.unwrap();
+
+		self.messaging.assimilate_storage(&mut t).unwrap();
+
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| {
+			System::set_block_number(1);
+			// Genesis records the in-code storage version, as on a real chain
+			<Messaging as OnGenesis>::on_genesis();
+		});
+		ext
+	}
 }
@@ -0,0 +1,106 @@
+//! Offchain index of messages by recipient.
+//!
+//! When [`Config::OffchainIndexing`] is enabled, the pallet mirrors every stored message into
+//! the node's offchain database through `sp_io::offchain_index`. Nodes started with
+//! `--enable-offchain-indexing=true` can then answer inbox queries locally, without an
+//! external indexer. Index writes cost the runtime nothing, but are skipped entirely while
+//! the switch is off.
+//!
+//! ## Layout
+//!
+//! Each message is one entry under [`inbox_key`]:
+//!
+//! ```text
+//! INBOX_PREFIX ++ SCALE(recipient) ++ message_id as big-endian u64
+//! ```
+//!
+//! so all entries of a recipient share a prefix and sort in ID order. Messages sent to a
+//! recipient set have an entry in the inbox of every listed account. Values are
+//! SCALE-encoded [`IndexEntry`]s. An entry is written when the message is sent, rewritten with
+//! `acknowledged` set when its payment or deposit is acknowledged or its deposit is refunded
+//! on a reply, and cleared once the message is pruned, retracted or taken down.
+//!
+//! ## Reading
+//!
+//! Offchain workers read entries back with [`inbox_entry`]. External tools open the node's
+//! offchain database and look up the same keys in its persistent storage.
+
+use crate::{Config, MessageHashes, MessageId, MessageRecord};
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::*;
+use frame_system::pallet_prelude::BlockNumberFor;
+use sp_runtime::offchain::StorageKind;
+use sp_std::vec::Vec;
+
+/// Prefix shared by all inbox index keys.
+pub const INBOX_PREFIX: &[u8] = b"pallet-messaging::inbox::";
+
+/// A message as recorded in the offchain index.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct IndexEntry<AccountId, Hash, BlockNumber> {
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Hash of the message content.
+	pub hash: Hash,
+	/// Block at which the message was sent.
+	pub sent_at: BlockNumber,
+	/// Whether the recipient acknowledged a payment or deposit attached to the message.
+	pub acknowledged: bool,
+}
+
+pub type IndexEntryOf<T> = IndexEntry<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::Hash,
+	BlockNumberFor<T>,
+>;
+
+/// Offchain index key of message `message_id` in the inbox of `recipient`.
+pub fn inbox_key<AccountId: Encode>(recipient: &AccountId, message_id: MessageId) -> Vec<u8> {
+	let mut key = INBOX_PREFIX.to_vec();
+	recipient.encode_to(&mut key);
+	key.extend_from_slice(&message_id.to_be_bytes());
+	key
+}
+
+/// Read the index entry of message `message_id` in the inbox of `recipient`.
+///
+/// Only available in offchain contexts, and only returns entries once the block that wrote
+/// them has been imported by a node with offchain indexing enabled.
+pub fn inbox_entry<T: Config>(
+	recipient: &T::AccountId,
+	message_id: MessageId,
+) -> Option<IndexEntryOf<T>> {
+	sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, &inbox_key(recipient, message_id))
+		.and_then(|value| IndexEntryOf::<T>::decode(&mut &value[..]).ok())
+}
+
+/// Write the index entry of a message, if indexing is enabled.
+pub(crate) fn set<T: Config>(
+	recipient: &T::AccountId,
+	message_id: MessageId,
+	entry: &IndexEntryOf<T>,
+) {
+	if T::OffchainIndexing::get() {
+		sp_io::offchain_index::set(&inbox_key(recipient, message_id), &entry.encode());
+	}
+}
+
+/// Clear the index entry of a message, if indexing is enabled.
+pub(crate) fn clear<T: Config>(recipient: &T::AccountId, message_id: MessageId) {
+	if T::OffchainIndexing::get() {
+		sp_io::offchain_index::clear(&inbox_key(recipient, message_id));
+	}
+}
+
+/// Rewrite the index entry of a stored message as acknowledged, if indexing is enabled.
+pub(crate) fn acknowledge<T: Config>(message_id: MessageId) {
+	if !T::OffchainIndexing::get() {
+		return;
+	}
+	if let Some(MessageRecord { sender, recipient, hash, sent_at }) =
+		MessageHashes::<T>::get(message_id)
+	{
+		let entry = IndexEntryOf::<T> { sender, hash, sent_at, acknowledged: true };
+		sp_io::offchain_index::set(&inbox_key(&recipient, message_id), &entry.encode());
+	}
+}
@@ -1,6 +1,69 @@
-use crate::{mock::*, Error, Event};
-use frame_support::{assert_noop, assert_ok};
-use sp_core::H256;
+use crate::{
+	compact_events,
+	extensions::{CheckRegisteredSender, InvalidSend, SkipFeeForContacts},
+	membership::AccumulatorUpdater,
+	mock::*,
+	offchain_index::{inbox_entry, inbox_key, IndexEntry, INBOX_PREFIX},
+	stealth,
+	weights::WeightInfo,
+	xcm_mock::{parachain, sibling, MockNet, ParaA, ParaB, ALICE, BOB, CHARLIE},
+	AccountStandingOf, AccountStatistics, AcknowledgedMessages, AuditAction, BackupCommitment,
+	BondRecord, BondStatus, BondStatusViewFunction, Call, CanMessageError,
+	ChallengeEvidenceViewFunction, CompactEventSenders, ContactsOfViewFunction,
+	ConversationSummary, ConversationSummaryViewFunction, ConversationViewFunction,
+	DepartedSenders, DeviceAttestation, DeviceSets, DiscoverableGroupsViewFunction,
+	EnsureModerator, EphemeralKey, Error, Event, EventPrivacyMode, Evidence, EvidenceRecord,
+	GlobalStatistics, GroupMessageIds, GroupMessageRecord, GroupMessageTombstone, GroupMessages,
+	GroupMessagesAfterViewFunction, GroupModerationAction, GroupModerationEntry, GroupPruneCursor,
+	GroupRole, HasProfileViewFunction, HoldReason, InboxOfViewFunction, InboxPricing,
+	InboxRevisionViewFunction, InboxTier, IncomeCategory, IncomingContactRequests,
+	IncomingRequestsViewFunction, IsContactViewFunction, ItemError, KeyAlgorithm, MembershipProof,
+	MessageByHashViewFunction, MessageHashes, MessageId, MessageIdByHash, MessageRecord,
+	MessageRecordView, MessageState, MessageStatus, MessageSummary, MessageVerification,
+	MessageVerifications, MessagesByTagPrefixViewFunction, ModeratorActionKind, NextGroupMessageId,
+	NextMessageId, NotificationHint, NotificationHooks, OrderReference,
+	OutgoingRequestsViewFunction, PalletParameters, PalletParametersOf, PausedCalls,
+	PendingMessageRecord, PostingPolicy, ProfileActivity, PruneCosts, PruneCursor, RecipientSet,
+	RecipientSetMessageRecord, RegistrationMode, RelayInfo, ReputationRecord, RetractedMessages,
+	SendBatchOf, SessionInfo, SessionStatus, Sessions, SlashDestination, SlashTerms, SpamReports,
+	StandingViewFunction, StealthMessageRecord, TakedownRecord, VerificationBatch,
+	VerificationResult, VerifyMessageBatchViewFunction, VersionedRemoteProfile,
+	MAX_CONVERSATION_PAGE_SIZE, MAX_VERIFICATION_BATCH, REMOTE_PROFILE_VERSION, SPONSORSHIP_CONTEXT,
+};
+use codec::{Decode, Encode};
+use frame_support::{
+	assert_err, assert_noop, assert_ok,
+	dispatch::GetDispatchInfo,
+	storage::unhashed,
+	traits::{
+		fungible::{Inspect, InspectHold, Mutate, NativeOrWithId},
+		Get, GetStorageVersion, Hooks, OnRuntimeUpgrade, ReservableCurrency, StorageVersion,
+		UnfilteredDispatchable,
+	},
+	view_functions::{ViewFunction, ViewFunctionDispatchError, ViewFunctionId},
+	weights::{Weight, WeightMeter},
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use sp_core::{
+	hexdisplay::HexDisplay,
+	offchain::{
+		testing::{TestOffchainExt, TestTransactionPoolExt},
+		OffchainDbExt, OffchainWorkerExt, TransactionPoolExt,
+	},
+	H256,
+};
+use sp_runtime::{
+	testing::TestSignature,
+	traits::{
+		AccountIdConversion, BlakeTwo256, DispatchTransaction, Dispatchable, Hash,
+		TransactionExtension, ValidateUnsigned,
+	},
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidityError},
+	DispatchError, DispatchResult, Perbill, StateVersion,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use xcm::latest::Location;
+use xcm_simulator::TestExt;
 
 #[test]
 fn register_profile_works() {
@@ -11,15 +74,20 @@ fn register_profile_works() {
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), public_key.clone()));
 		
 		// Verify profile exists
-		assert!(crate::UserProfiles::<Test>::contains_key(&1));
+		assert!(crate::UserProfiles::<Test>::contains_key(1));
 		
 		// Verify bond was reserved
-		assert_eq!(crate::SpamBonds::<Test>::get(&1), 100);
-		assert_eq!(Balances::reserved_balance(&1), 100);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 100);
+		assert_eq!(Balances::reserved_balance(1), 100);
 		
 		// Verify event was emitted
 		System::assert_last_event(
-			Event::ProfileRegistered { who: 1, public_key }.into()
+			Event::ProfileRegistered {
+				who: 1,
+				key_hash: Messaging::key_hash(&public_key),
+				version: 0,
+			}
+			.into()
 		);
 	});
 }
@@ -85,7 +153,12 @@ fn update_profile_works() {
 		
 		// Verify event was emitted
 		System::assert_last_event(
-			Event::ProfileUpdated { who: 1, public_key: public_key2 }.into()
+			Event::ProfileUpdated {
+				who: 1,
+				key_hash: Messaging::key_hash(&public_key2),
+				version: 1,
+			}
+			.into()
 		);
 	});
 }
@@ -110,6 +183,7 @@ fn send_message_hash_works() {
 		// Register both sender and recipient
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), public_key.clone()));
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), public_key));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
 		
 		// Send message hash
 		let message_hash = H256::from([1; 32]);
@@ -125,6 +199,9 @@ fn send_message_hash_works() {
 				from: 1,
 				to: 2,
 				hash: message_hash,
+				expires_at: 1001,
+				sequence: 0,
+				conversation_id: Messaging::conversation_id(&1, &2),
 			}
 			.into()
 		);
@@ -134,6 +211,35 @@ fn send_message_hash_works() {
 	});
 }
 
+#[test]
+fn send_message_hash_fails_without_approval() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+
+		// A stranger has to pay its way in
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::ContactNotApproved
+		);
+
+		// Approving the recipient is not enough
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::ContactNotApproved
+		);
+
+		// An inbox pass stands in for the recipient's approval until it lapses
+		crate::InboxPasses::<Test>::insert(2, 1, 5);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		System::set_block_number(5);
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([2; 32])),
+			Error::<Test>::ContactNotApproved
+		);
+	});
+}
+
 #[test]
 fn send_message_hash_fails_without_sender_profile() {
 	new_test_ext().execute_with(|| {
@@ -175,10 +281,10 @@ fn approve_contact_works() {
 		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
 		
 		// Verify contact was approved
-		assert!(crate::ApprovedContacts::<Test>::get(&1, &2));
+		assert!(crate::ApprovedContacts::<Test>::get(1, 2));
 		
 		// Verify contact count increased
-		assert_eq!(crate::ContactCount::<Test>::get(&1), 1);
+		assert_eq!(crate::ContactCount::<Test>::get(1), 1);
 		
 		// Verify event was emitted
 		System::assert_last_event(
@@ -236,16 +342,16 @@ fn remove_contact_works() {
 		
 		// Approve contact
 		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
-		assert_eq!(crate::ContactCount::<Test>::get(&1), 1);
+		assert_eq!(crate::ContactCount::<Test>::get(1), 1);
 		
 		// Remove contact
 		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 2));
 		
 		// Verify contact was removed
-		assert!(!crate::ApprovedContacts::<Test>::get(&1, &2));
+		assert!(!crate::ApprovedContacts::<Test>::get(1, 2));
 		
 		// Verify contact count decreased
-		assert_eq!(crate::ContactCount::<Test>::get(&1), 0);
+		assert_eq!(crate::ContactCount::<Test>::get(1), 0);
 		
 		// Verify event was emitted
 		System::assert_last_event(
@@ -254,6 +360,16 @@ fn remove_contact_works() {
 	});
 }
 
+/// Evidence challengers in tests commit to.
+const EVIDENCE: &[u8] = b"decrypted message";
+
+/// Salt challengers in tests commit to `EVIDENCE` with.
+const SALT: H256 = H256::repeat_byte(7);
+
+fn evidence_commitment() -> H256 {
+	Messaging::evidence_commitment(EVIDENCE, &SALT)
+}
+
 #[test]
 fn challenge_spam_works() {
 	new_test_ext().execute_with(|| {
@@ -262,13 +378,14 @@ fn challenge_spam_works() {
 		// Register users
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), public_key.clone()));
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), public_key));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
 		
 		// Send message
 		let message_hash = H256::from([1; 32]);
 		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, message_hash));
 		
 		// Challenge as spam
-		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
 		
 		// Verify event was emitted
 		System::assert_last_event(
@@ -281,7 +398,7 @@ fn challenge_spam_works() {
 fn challenge_spam_fails_for_nonexistent_message() {
 	new_test_ext().execute_with(|| {
 		assert_noop!(
-			Messaging::challenge_spam(RuntimeOrigin::signed(1), 999),
+			Messaging::challenge_spam(RuntimeOrigin::signed(1), 999, evidence_commitment()),
 			Error::<Test>::MessageNotFound
 		);
 	});
@@ -296,18 +413,18 @@ fn refund_bond_works() {
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), public_key));
 		
 		// Verify bond was reserved
-		assert_eq!(Balances::reserved_balance(&1), 100);
-		assert_eq!(Balances::free_balance(&1), 900);
+		assert_eq!(Balances::reserved_balance(1), 100);
+		assert_eq!(Balances::free_balance(1), 900);
 		
 		// Refund bond
 		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
 		
 		// Verify bond was unreserved
-		assert_eq!(Balances::reserved_balance(&1), 0);
-		assert_eq!(Balances::free_balance(&1), 1000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 1000);
 		
 		// Verify bond record was removed
-		assert_eq!(crate::SpamBonds::<Test>::get(&1), 0);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 0);
 		
 		// Verify event was emitted
 		System::assert_last_event(
@@ -343,6 +460,7 @@ fn verify_message_hash_works() {
 		// Register users
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), public_key.clone()));
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), public_key));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
 		
 		// Send message
 		let message_hash = H256::from([1; 32]);
@@ -350,11 +468,11 @@ fn verify_message_hash_works() {
 		
 		// Verify correct hash
 		assert_ok!(Messaging::verify_message_hash(0, message_hash));
-		assert_eq!(Messaging::verify_message_hash(0, message_hash).unwrap(), true);
+		assert!(Messaging::verify_message_hash(0, message_hash).unwrap());
 		
 		// Verify incorrect hash
 		let wrong_hash = H256::from([2; 32]);
-		assert_eq!(Messaging::verify_message_hash(0, wrong_hash).unwrap(), false);
+		assert!(!Messaging::verify_message_hash(0, wrong_hash).unwrap());
 	});
 }
 
@@ -366,19 +484,20 @@ fn message_expiry_works() {
 		// Register users
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), public_key.clone()));
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), public_key));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
 		
 		// Send message at block 1
 		let message_hash = H256::from([1; 32]);
 		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, message_hash));
 		
 		// Message should not be expired yet
-		assert!(!Messaging::is_message_expired(0));
+		assert!(Messaging::message_state(0).is_live());
 		
 		// Advance blocks past expiry (MessageHashExpiry = 1000)
 		System::set_block_number(1002);
 		
 		// Message should now be expired
-		assert!(Messaging::is_message_expired(0));
+		assert_eq!(Messaging::message_state(0), MessageState::Expired);
 		
 		// Verification should fail for expired message
 		assert_noop!(
@@ -397,13 +516,13644 @@ fn contact_limit_works() {
 		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), public_key.clone()));
 		
 		// MaxContactsPerUser is 100, but we'll test with realistic numbers
-		// Register and approve 5 contacts
-		for i in 2..7 {
+		// Register and approve 5 contacts (account 4 cannot afford the bond)
+		for i in [2, 3, 5, 6, 7] {
 			assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(i), public_key.clone()));
 			assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), i));
 		}
 		
 		// Verify contact count
-		assert_eq!(crate::ContactCount::<Test>::get(&1), 5);
+		assert_eq!(crate::ContactCount::<Test>::get(1), 5);
+	});
+}
+
+fn setup_pair() {
+	let public_key = vec![1, 2, 3, 4];
+	assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), public_key.clone()));
+	assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), public_key));
+}
+
+/// Register accounts 1 and 2, with 2 approving 1 so that 1 can message it.
+fn setup_approved_pair() {
+	setup_pair();
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+}
+
+fn add_strike(sender: u64, recipient: u64) {
+	// Restore the bond slashed by previous strikes
+	let required = Messaging::required_bond(&sender);
+	if crate::SpamBonds::<Test>::get(sender).amount < required {
+		top_up_to(sender, required);
+	}
+
+	// Each strike needs its own message, so the hash has to differ too
+	let message_id = crate::NextMessageId::<Test>::get();
+	assert_ok!(Messaging::send_message_hash(
+		RuntimeOrigin::signed(sender),
+		recipient,
+		BlakeTwo256::hash_of(&message_id)
+	));
+	assert_ok!(Messaging::challenge_spam(
+		RuntimeOrigin::signed(3),
+		message_id,
+		evidence_commitment()
+	));
+	assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), message_id, true));
+}
+
+fn top_up_to(who: u64, target: Balance) {
+	let bond = crate::SpamBonds::<Test>::get(who).amount;
+	assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(who), target - bond));
+}
+
+#[test]
+fn challenge_spam_fails_if_already_challenged() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+
+		assert_noop!(
+			Messaging::challenge_spam(RuntimeOrigin::signed(2), 0, evidence_commitment()),
+			Error::<Test>::AlreadyChallenged
+		);
+	});
+}
+
+#[test]
+fn resolve_challenge_requires_resolution_origin() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+
+		assert_noop!(
+			Messaging::resolve_challenge(RuntimeOrigin::signed(3), 0, true),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::resolve_challenge(RuntimeOrigin::root(), 1, true),
+			Error::<Test>::ChallengeNotFound
+		);
+	});
+}
+
+#[test]
+fn dismissed_challenge_records_no_strike() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, false));
+
+		assert_eq!(Messaging::current_strikes(&1), 0);
+		assert_eq!(
+			crate::Challenges::<Test>::get(0).unwrap().status,
+			crate::ChallengeStatus::Resolved { upheld: false, at: 1 }
+		);
+		System::assert_last_event(Event::ChallengeResolved { message_id: 0, upheld: false }.into());
+	});
+}
+
+#[test]
+fn upheld_challenge_records_strike() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		add_strike(1, 2);
+
+		assert_eq!(crate::Reputation::<Test>::get(1).strikes, 1);
+		System::assert_has_event(Event::StrikeRecorded { who: 1, strikes: 1 }.into());
+		System::assert_last_event(Event::ChallengeResolved { message_id: 0, upheld: true }.into());
+	});
+}
+
+#[test]
+fn rate_limit_is_enforced_per_window() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+
+		// MaxMessagesPerWindow is 10
+		for byte in 1..=10 {
+			assert_ok!(Messaging::send_message_hash(
+				RuntimeOrigin::signed(1),
+				2,
+				H256::repeat_byte(byte)
+			));
+		}
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([11; 32])),
+			Error::<Test>::RateLimitExceeded
+		);
+
+		// A new window resets the counter
+		System::set_block_number(11);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([11; 32])));
+	});
+}
+
+#[test]
+fn first_strike_halves_rate_limit() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		add_strike(1, 2);
+		assert_eq!(Messaging::rate_limit(&1), 5);
+		top_up_to(1, 100);
+
+		// One message already counted in this window
+		System::set_block_number(20);
+		for byte in 1..=5 {
+			assert_ok!(Messaging::send_message_hash(
+				RuntimeOrigin::signed(1),
+				2,
+				H256::repeat_byte(byte)
+			));
+		}
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([6; 32])),
+			Error::<Test>::RateLimitExceeded
+		);
+	});
+}
+
+#[test]
+fn second_strike_doubles_required_bond() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		add_strike(1, 2);
+		assert_eq!(Messaging::required_bond(&1), 100);
+		add_strike(1, 2);
+		assert_eq!(Messaging::required_bond(&1), 200);
+
+		// Restoring the slashed bond to 100 no longer covers the requirement
+		top_up_to(1, 100);
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::InsufficientBond
+		);
+	});
+}
+
+#[test]
+fn max_strikes_graylists_account() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		for _ in 0..3 {
+			add_strike(1, 2);
+		}
+
+		assert!(Messaging::is_graylisted(&1));
+		assert!(crate::GraylistedAccounts::<Test>::contains_key(1));
+		System::assert_has_event(Event::AccountGraylisted { who: 1, strikes: 3 }.into());
+
+		// Cannot message a non-mutual contact
+		System::set_block_number(20);
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::Graylisted
+		);
+
+		// Cannot approve new contacts
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::Graylisted
+		);
+	});
+}
+
+#[test]
+fn graylisted_account_can_message_mutual_contacts() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		for _ in 0..3 {
+			add_strike(1, 2);
+		}
+		assert!(Messaging::is_graylisted(&1));
+		top_up_to(1, 200);
+
+		System::set_block_number(20);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+	});
+}
+
+#[test]
+fn strikes_decay_lazily_and_restore_account() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		for _ in 0..3 {
+			add_strike(1, 2);
+		}
+		assert!(Messaging::is_graylisted(&1));
+		top_up_to(1, 200);
+
+		// One decay period forgives one strike; storage is untouched until interaction
+		System::set_block_number(1 + 100);
+		assert_eq!(Messaging::current_strikes(&1), 2);
+		assert!(!Messaging::is_graylisted(&1));
+		assert_eq!(crate::Reputation::<Test>::get(1).strikes, 3);
+		assert!(crate::GraylistedAccounts::<Test>::contains_key(1));
+
+		// Interacting applies the decay and restores the account
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_eq!(crate::Reputation::<Test>::get(1).strikes, 2);
+		assert!(!crate::GraylistedAccounts::<Test>::contains_key(1));
+		System::assert_has_event(Event::AccountRestored { who: 1 }.into());
+
+		// All strikes eventually decay and the record is removed
+		System::set_block_number(1 + 300);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([2; 32])));
+		assert!(!crate::Reputation::<Test>::contains_key(1));
+		assert_eq!(Messaging::required_bond(&1), 100);
+	});
+}
+
+#[test]
+fn spam_bond_is_held_under_named_reason() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &1), 100);
+		assert_eq!(Balances::total_balance_on_hold(&1), 100);
+	});
+}
+
+#[test]
+fn spam_bond_hold_does_not_interfere_with_other_reserves() {
+	new_test_ext().execute_with(|| {
+		// Another pallet reserves funds on the same account
+		assert_ok!(<Balances as ReservableCurrency<u64>>::reserve(&1, 300));
+
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &1), 100);
+		assert_eq!(Balances::reserved_balance(1), 400);
+		assert_eq!(Balances::free_balance(1), 600);
+
+		// Refunding releases only the bond
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &1), 0);
+		assert_eq!(Balances::reserved_balance(1), 300);
+		assert_eq!(Balances::free_balance(1), 700);
+
+		// The other pallet can still unreserve its funds in full
+		assert_eq!(<Balances as ReservableCurrency<u64>>::unreserve(&1, 300), 0);
+		assert_eq!(Balances::free_balance(1), 1000);
+	});
+}
+
+/// Record legacy bonds at version 0: bare amounts reserved without a hold reason, of which
+/// only `reserved` is actually reserved.
+fn insert_legacy_bond(who: u64, amount: u128, reserved: u128) {
+	crate::migrations::v4::SpamBonds::<Test>::insert(who, amount);
+	assert_ok!(<Balances as ReservableCurrency<u64>>::reserve(&who, reserved));
+}
+
+#[test]
+fn reserves_to_holds_migration_works() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<Messaging>();
+		insert_legacy_bond(1, 100, 100);
+		insert_legacy_bond(2, 100, 100);
+		// Account 3 has a bond record but only part of it was reserved
+		insert_legacy_bond(3, 100, 40);
+
+		crate::migrations::v1::MigrateReservesToHolds::<Test, Balances>::on_runtime_upgrade();
+
+		for who in [1, 2] {
+			assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &who), 100);
+			assert_eq!(Balances::reserved_balance(who), 100);
+		}
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &3), 40);
+		assert_eq!(crate::migrations::v4::SpamBonds::<Test>::get(3), Some(40));
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(1));
+
+		// Already at version 1, so a second run moves no reserve, whatever is on hold
+		insert_legacy_bond(4, 100, 100);
+		crate::migrations::v1::MigrateReservesToHolds::<Test, Balances>::on_runtime_upgrade();
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &4), 0);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &1), 100);
+		assert_eq!(Balances::reserved_balance(1), 100);
+
+		// Migrated bonds are refundable through the hold
+		crate::migrations::Migrations::<Test>::on_runtime_upgrade();
+		let public_key = sp_runtime::BoundedVec::truncate_from(vec![1]);
+		crate::UserProfiles::<Test>::insert(
+			1,
+			crate::ProfileRecord {
+				public_key,
+				dormant_since: None,
+				registration_bond: None,
+				session_required: false,
+			},
+		);
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+		assert_eq!(Balances::free_balance(1), 1000);
+	});
+}
+
+#[test]
+fn reserves_to_holds_migration_keeps_bonds_taken_as_holds() {
+	new_test_ext().execute_with(|| {
+		// Registered after holds came in, while another pallet reserves funds of its own
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		assert_ok!(<Balances as ReservableCurrency<u64>>::reserve(&1, 300));
+		StorageVersion::new(0).put::<Messaging>();
+
+		crate::migrations::v1::MigrateReservesToHolds::<Test, Balances>::on_runtime_upgrade();
+
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &1), 100);
+		assert_eq!(Balances::reserved_balance(1), 400);
+	});
+}
+
+#[test]
+fn lazy_reserves_to_holds_migration_moves_bonds_and_bumps_the_version() {
+	use crate::migrations::v1::LazyMigrateReservesToHolds;
+	use frame_support::{migrations::SteppedMigration, weights::WeightMeter};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<Messaging>();
+		insert_legacy_bond(1, 100, 100);
+		insert_legacy_bond(2, 100, 60);
+
+		assert_eq!(
+			LazyMigrateReservesToHolds::<Test, Balances>::step(None, &mut WeightMeter::new()),
+			Ok(None)
+		);
+
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &1), 100);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &2), 60);
+		assert_eq!(crate::migrations::v4::SpamBonds::<Test>::get(2), Some(60));
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(1));
+
+		// Past version 0 the migration does nothing
+		insert_legacy_bond(3, 100, 100);
+		assert_eq!(
+			LazyMigrateReservesToHolds::<Test, Balances>::step(None, &mut WeightMeter::new()),
+			Ok(None)
+		);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &3), 0);
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn reserves_to_holds_migration_passes_try_runtime_checks() {
+	use crate::migrations::v1::MigrateReservesToHolds;
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<Messaging>();
+		insert_legacy_bond(1, 100, 100);
+		insert_legacy_bond(2, 100, 40);
+
+		assert_ok!(MigrateReservesToHolds::<Test, Balances>::try_on_runtime_upgrade(true));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &2), 40);
+	});
+}
+
+#[test]
+fn top_up_bond_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(1), 50));
+
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 150);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &1), 150);
+		System::assert_last_event(Event::BondIncreased { who: 1, amount: 50, total: 150 }.into());
+	});
+}
+
+#[test]
+fn top_up_bond_fails_below_minimum_increment() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+
+		// MinBondIncrement is 10
+		assert_noop!(
+			Messaging::top_up_bond(RuntimeOrigin::signed(1), 9),
+			Error::<Test>::BondIncrementTooSmall
+		);
+	});
+}
+
+#[test]
+fn top_up_bond_fails_without_profile_or_funds() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::top_up_bond(RuntimeOrigin::signed(1), 50),
+			Error::<Test>::ProfileNotFound
+		);
+
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		assert_noop!(
+			Messaging::top_up_bond(RuntimeOrigin::signed(1), 1000),
+			Error::<Test>::InsufficientBond
+		);
+	});
+}
+
+#[test]
+fn top_up_restores_bond_after_refund() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::InsufficientBond
+		);
+
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(1), 100));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+	});
+}
+
+#[test]
+fn withdraw_excess_bond_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(1), 50));
+
+		assert_ok!(Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)));
+
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 100);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &1), 100);
+		assert_eq!(Balances::free_balance(1), 900);
+		System::assert_last_event(Event::BondDecreased { who: 1, amount: 50, total: 100 }.into());
+	});
+}
+
+#[test]
+fn withdraw_excess_bond_fails_without_excess() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+
+		assert_noop!(
+			Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::NoExcessBond
+		);
+	});
+}
+
+#[test]
+fn withdraw_excess_bond_keeps_strike_adjusted_requirement() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		add_strike(1, 2);
+		add_strike(1, 2);
+		top_up_to(1, 250);
+
+		// Required bond is doubled to 200; only the remaining 50 is withdrawable
+		System::set_block_number(100);
+		for message_id in 0..2 {
+			assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(3), message_id));
+		}
+		assert_ok!(Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)));
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 200);
+	});
+}
+
+#[test]
+fn withdraw_excess_bond_blocked_by_open_challenge() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(1), 50));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+
+		assert_noop!(
+			Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::OpenChallengesPending
+		);
+		assert_noop!(
+			Messaging::refund_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::OpenChallengesPending
+		);
+
+		// The bond stays blocked until the verdict is final
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, false));
+		assert_noop!(
+			Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::OpenChallengesPending
+		);
+
+		// Finalizing the challenge unblocks the withdrawal
+		System::set_block_number(12);
+		assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(3), 0));
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 0);
+		assert_ok!(Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)));
+	});
+}
+
+#[test]
+fn withdraw_excess_bond_respects_unbonding_period() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(1), 50));
+
+		// Sending at block 1 locks the full 150 until block 51
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_eq!(Messaging::locked_bond(&1), 150);
+		assert_noop!(
+			Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::BondLocked
+		);
+
+		// A later top-up did not secure any message and can be withdrawn
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(1), 30));
+		assert_ok!(Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)));
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 150);
+
+		// Once the unbonding period elapses the rest of the excess is released
+		System::set_block_number(51);
+		assert_ok!(Messaging::withdraw_excess_bond(RuntimeOrigin::signed(1)));
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 100);
+	});
+}
+
+#[test]
+fn refund_bond_respects_unbonding_period() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+
+		assert_noop!(Messaging::refund_bond(RuntimeOrigin::signed(1)), Error::<Test>::BondLocked);
+
+		System::set_block_number(51);
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+		assert!(!crate::BondLocks::<Test>::contains_key(1));
+	});
+}
+
+/// Send a message from 1 to 2 and have 3 challenge it.
+fn open_challenge() {
+	setup_approved_pair();
+	assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+	assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+}
+
+fn challenge_and_resolve(upheld: bool) {
+	open_challenge();
+	assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, upheld));
+}
+
+fn held(reason: HoldReason, who: u64) -> Balance {
+	Balances::balance_on_hold(&reason.into(), &who)
+}
+
+#[test]
+fn challenge_spam_holds_challenge_bond() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 20);
+		assert_eq!(Balances::free_balance(3), 980);
+	});
+}
+
+#[test]
+fn challenge_spam_fails_without_challenge_bond() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(4), 5, 40));
+
+		assert_noop!(
+			Messaging::challenge_spam(RuntimeOrigin::signed(4), 0, evidence_commitment()),
+			Error::<Test>::InsufficientChallengeBond
+		);
+	});
+}
+
+#[test]
+fn upheld_challenge_slashes_bond_into_escrow() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		challenge_and_resolve(true);
+
+		let escrow = Messaging::escrow_account();
+		assert_eq!(held(HoldReason::SpamBond, 1), 50);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 50);
+		assert_eq!(Balances::free_balance(escrow), 50);
+		assert_eq!(crate::Challenges::<Test>::get(0).unwrap().escrowed, 50);
+		assert_eq!(Balances::total_issuance(), issuance);
+		System::assert_has_event(Event::BondSlashed { who: 1, amount: 50 }.into());
+
+		// Cannot be resolved twice
+		assert_noop!(
+			Messaging::resolve_challenge(RuntimeOrigin::root(), 0, false),
+			Error::<Test>::ChallengeNotOpen
+		);
+	});
+}
+
+#[test]
+fn finalize_upheld_challenge_rewards_challenger_and_burns_rest() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		challenge_and_resolve(true);
+
+		// Cannot finalize while the appeal window is open
+		System::set_block_number(11);
+		assert_noop!(
+			Messaging::finalize_challenge(RuntimeOrigin::signed(5), 0),
+			Error::<Test>::AppealWindowOpen
+		);
+
+		System::set_block_number(12);
+		assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(5), 0));
+
+		// Challenger gets their bond back plus half of the 50 slash, the rest is burned
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(Balances::free_balance(3), 1025);
+		assert_eq!(Balances::free_balance(Messaging::escrow_account()), 0);
+		assert_eq!(Balances::total_issuance(), issuance - 25);
+
+		// Sender keeps the slashed bond and the strike
+		assert_eq!(Balances::free_balance(1), 900);
+		assert_eq!(held(HoldReason::SpamBond, 1), 50);
+		assert_eq!(Messaging::current_strikes(&1), 1);
+
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 0);
+		System::assert_last_event(Event::ChallengeFinalized { message_id: 0, upheld: true }.into());
+	});
+}
+
+#[test]
+fn finalize_dismissed_challenge_pays_challenge_bond_to_sender() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		challenge_and_resolve(false);
+
+		System::set_block_number(12);
+		assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(5), 0));
+
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(Balances::free_balance(3), 980);
+		assert_eq!(Balances::free_balance(1), 920);
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(Balances::total_issuance(), issuance);
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn finalize_challenge_requires_resolution() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+
+		System::set_block_number(100);
+		assert_noop!(
+			Messaging::finalize_challenge(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::ChallengeNotResolved
+		);
+		assert_noop!(
+			Messaging::finalize_challenge(RuntimeOrigin::signed(3), 1),
+			Error::<Test>::ChallengeNotFound
+		);
+	});
+}
+
+#[test]
+fn only_losing_party_can_appeal_within_window() {
+	new_test_ext().execute_with(|| {
+		challenge_and_resolve(true);
+
+		// The challenger won and the recipient is not a party
+		assert_noop!(
+			Messaging::appeal_challenge(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::NotAuthorized
+		);
+		assert_noop!(
+			Messaging::appeal_challenge(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::NotAuthorized
+		);
+
+		// The window closes AppealWindow blocks after resolution
+		System::set_block_number(12);
+		assert_noop!(
+			Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::AppealWindowClosed
+		);
+
+		System::set_block_number(11);
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0));
+		assert_eq!(held(HoldReason::AppealBond, 1), 40);
+		System::assert_last_event(Event::ChallengeAppealed { message_id: 0, appellant: 1 }.into());
+
+		// An appealed challenge cannot be appealed again or finalized
+		assert_noop!(
+			Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::ChallengeNotResolved
+		);
+		System::set_block_number(100);
+		assert_noop!(
+			Messaging::finalize_challenge(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::ChallengeNotResolved
+		);
+	});
+}
+
+#[test]
+fn appeal_requires_open_challenge_resolution() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+
+		assert_noop!(
+			Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::ChallengeNotResolved
+		);
+		assert_noop!(
+			Messaging::resolve_appeal(RuntimeOrigin::root(), 0, false),
+			Error::<Test>::AppealNotFound
+		);
+	});
+}
+
+#[test]
+fn appeal_fails_without_appeal_bond() {
+	new_test_ext().execute_with(|| {
+		challenge_and_resolve(true);
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(1), 5, 880));
+
+		assert_noop!(
+			Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::InsufficientAppealBond
+		);
+	});
+}
+
+#[test]
+fn resolve_appeal_requires_appeal_origin() {
+	new_test_ext().execute_with(|| {
+		challenge_and_resolve(true);
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0));
+
+		assert_noop!(
+			Messaging::resolve_appeal(RuntimeOrigin::signed(1), 0, false),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn failed_sender_appeal_pays_appeal_bond_to_challenger() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		challenge_and_resolve(true);
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0));
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, true));
+
+		// Sender loses the 50 slash and the 40 appeal bond
+		assert_eq!(Balances::free_balance(1), 860);
+		assert_eq!(held(HoldReason::SpamBond, 1), 50);
+		assert_eq!(held(HoldReason::AppealBond, 1), 0);
+		assert_eq!(Messaging::current_strikes(&1), 1);
+
+		// Challenger gets their bond, the appeal bond and half of the slash
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(Balances::free_balance(3), 1065);
+
+		assert_eq!(Balances::free_balance(Messaging::escrow_account()), 0);
+		assert_eq!(Balances::total_issuance(), issuance - 25);
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 0);
+		System::assert_has_event(Event::AppealResolved { message_id: 0, upheld: true }.into());
+		System::assert_last_event(Event::ChallengeFinalized { message_id: 0, upheld: true }.into());
+	});
+}
+
+#[test]
+fn successful_sender_appeal_reverses_slash_and_strike() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		challenge_and_resolve(true);
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0));
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, false));
+
+		// Sender's bond is restored from escrow, appeal bond returned, challenge bond won
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 100);
+		assert_eq!(held(HoldReason::AppealBond, 1), 0);
+		assert_eq!(Balances::free_balance(1), 920);
+		assert_eq!(Messaging::current_strikes(&1), 0);
+		System::assert_has_event(Event::SlashReversed { who: 1, amount: 50 }.into());
+		System::assert_has_event(Event::StrikeRevoked { who: 1, strikes: 0 }.into());
+
+		// Challenger forfeits their bond
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(Balances::free_balance(3), 980);
+
+		assert_eq!(Balances::free_balance(Messaging::escrow_account()), 0);
+		assert_eq!(Balances::total_issuance(), issuance);
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn successful_challenger_appeal_slashes_sender() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		challenge_and_resolve(false);
+		assert_eq!(Messaging::current_strikes(&1), 0);
+
+		// The sender cannot appeal a dismissal
+		assert_noop!(
+			Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::NotAuthorized
+		);
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(3), 0));
+		assert_eq!(held(HoldReason::AppealBond, 3), 40);
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, true));
+
+		// Sender receives the strike and the slash
+		assert_eq!(Messaging::current_strikes(&1), 1);
+		assert_eq!(held(HoldReason::SpamBond, 1), 50);
+		assert_eq!(Balances::free_balance(1), 900);
+
+		// Challenger recovers both bonds and half of the slash
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(held(HoldReason::AppealBond, 3), 0);
+		assert_eq!(Balances::free_balance(3), 1025);
+
+		assert_eq!(Balances::free_balance(Messaging::escrow_account()), 0);
+		assert_eq!(Balances::total_issuance(), issuance - 25);
+	});
+}
+
+#[test]
+fn failed_challenger_appeal_pays_both_bonds_to_sender() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		challenge_and_resolve(false);
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(3), 0));
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, false));
+
+		assert_eq!(Messaging::current_strikes(&1), 0);
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(Balances::free_balance(1), 960);
+
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(held(HoldReason::AppealBond, 3), 0);
+		assert_eq!(Balances::free_balance(3), 940);
+
+		assert_eq!(Balances::total_issuance(), issuance);
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn escrow_tracks_slashes_per_challenge() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(2), 100));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, H256::from([2; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(5), 1, evidence_commitment()));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 1, true));
+
+		let escrow = Messaging::escrow_account();
+		assert_eq!(Balances::free_balance(escrow), 150);
+
+		// Reversing one slash leaves the other escrowed
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0));
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, false));
+		assert_eq!(Balances::free_balance(escrow), 100);
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+
+		System::set_block_number(12);
+		assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(5), 1));
+		assert_eq!(Balances::free_balance(escrow), 0);
+		assert_eq!(Balances::free_balance(5), 1050);
+	});
+}
+
+#[test]
+fn report_spam_works() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 0));
+
+		assert_eq!(Messaging::current_reports(&1), 1);
+		assert!(crate::ReportedMessages::<Test>::get(0, 2));
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+		assert_eq!(Balances::total_balance_on_hold(&2), 100);
+		System::assert_last_event(
+			Event::SpamReported { message_id: 0, reporter: 2, sender: 1, reports: 1 }.into(),
+		);
+	});
+}
+
+#[test]
+fn report_spam_fails_for_messages_not_addressed_to_reporter() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+
+		assert_noop!(Messaging::report_spam(RuntimeOrigin::signed(3), 0), Error::<Test>::NotAuthorized);
+		assert_noop!(Messaging::report_spam(RuntimeOrigin::signed(1), 0), Error::<Test>::NotAuthorized);
+		assert_noop!(
+			Messaging::report_spam(RuntimeOrigin::signed(2), 1),
+			Error::<Test>::MessageNotFound
+		);
+	});
+}
+
+#[test]
+fn report_spam_fails_if_already_reported() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 0));
+
+		assert_noop!(
+			Messaging::report_spam(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::AlreadyReported
+		);
+	});
+}
+
+#[test]
+fn report_spam_is_rate_limited_per_reporter() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		for byte in 1..=4 {
+			assert_ok!(Messaging::send_message_hash(
+				RuntimeOrigin::signed(1),
+				2,
+				H256::repeat_byte(byte)
+			));
+		}
+
+		// MaxReportsPerWindow is 3
+		for message_id in 0..3 {
+			assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), message_id));
+		}
+		assert_noop!(
+			Messaging::report_spam(RuntimeOrigin::signed(2), 3),
+			Error::<Test>::RateLimitExceeded
+		);
+
+		// A new window resets the counter
+		System::set_block_number(11);
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 3));
+	});
+}
+
+#[test]
+fn spam_reports_decay_lazily() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		for byte in 1..=3 {
+			assert_ok!(Messaging::send_message_hash(
+				RuntimeOrigin::signed(1),
+				2,
+				H256::repeat_byte(byte)
+			));
+		}
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 0));
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 1));
+
+		// One decay period forgives one report, so the third stays below the threshold
+		System::set_block_number(1 + 100);
+		assert_eq!(Messaging::current_reports(&1), 1);
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 2));
+		assert_eq!(crate::SpamReports::<Test>::get(1).reports, 2);
+		assert!(!crate::Challenges::<Test>::contains_key(2));
+	});
+}
+
+#[test]
+fn report_threshold_opens_system_challenge() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		for byte in 1..=3 {
+			assert_ok!(Messaging::send_message_hash(
+				RuntimeOrigin::signed(1),
+				2,
+				H256::repeat_byte(byte)
+			));
+		}
+		for message_id in 0..3 {
+			assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), message_id));
+		}
+
+		// AutoChallengeThreshold is 3; the counter resets once the challenge is opened
+		let challenge = crate::Challenges::<Test>::get(2).unwrap();
+		assert_eq!(challenge.challenger, None);
+		assert_eq!(challenge.bond, 0);
+		assert_eq!(challenge.status, crate::ChallengeStatus::Open);
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 1);
+		assert_eq!(Messaging::current_reports(&1), 0);
+		assert_eq!(Balances::total_balance_on_hold(&2), 100);
+		System::assert_last_event(Event::AutoChallengeOpened { message_id: 2, sender: 1 }.into());
+
+		// A user challenge cannot be opened on top of it
+		assert_noop!(
+			Messaging::challenge_spam(RuntimeOrigin::signed(3), 2, evidence_commitment()),
+			Error::<Test>::AlreadyChallenged
+		);
+	});
+}
+
+fn open_system_challenge() {
+	setup_approved_pair();
+	for byte in 1..=3 {
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::repeat_byte(byte)
+		));
+	}
+	for message_id in 0..3 {
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), message_id));
+	}
+}
+
+#[test]
+fn upheld_system_challenge_burns_whole_slash() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		open_system_challenge();
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 2, true));
+		assert_eq!(Messaging::current_strikes(&1), 1);
+
+		System::set_block_number(12);
+		assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(3), 2));
+
+		assert_eq!(held(HoldReason::SpamBond, 1), 50);
+		assert_eq!(Balances::free_balance(Messaging::escrow_account()), 0);
+		assert_eq!(Balances::total_issuance(), issuance - 50);
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 0);
+	});
+}
+
+#[test]
+fn failed_appeal_of_system_challenge_forfeits_appeal_bond() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		open_system_challenge();
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 2, true));
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 2));
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 2, true));
+
+		// The slash is burned and the appeal bond goes to the treasury
+		assert_eq!(held(HoldReason::AppealBond, 1), 0);
+		assert_eq!(Balances::free_balance(1), 860);
+		assert_eq!(Balances::total_issuance(), issuance - 50);
+		assert_eq!(Balances::free_balance(TreasuryAccount::get()), 41);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::ForfeitedDeposit), 40);
+	});
+}
+
+#[test]
+fn dismissed_system_challenge_cannot_be_appealed() {
+	new_test_ext().execute_with(|| {
+		open_system_challenge();
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 2, false));
+
+		for who in [1, 2] {
+			assert_noop!(
+				Messaging::appeal_challenge(RuntimeOrigin::signed(who), 2),
+				Error::<Test>::NotAuthorized
+			);
+		}
+
+		System::set_block_number(12);
+		assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(3), 2));
+		assert_eq!(Balances::free_balance(1), 900);
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 0);
+	});
+}
+
+fn auto_slash_events() -> usize {
+	System::events()
+		.iter()
+		.filter(|record| {
+			matches!(record.event, RuntimeEvent::Messaging(Event::BondAutoSlashed { .. }))
+		})
+		.count()
+}
+
+#[test]
+fn reaching_auto_slash_threshold_slashes_bond() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		for _ in 0..3 {
+			add_strike(1, 2);
+		}
+
+		// The third strike slashes 100 of the 200 bond, then half of the remaining 100
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 50);
+		assert_eq!(held(HoldReason::SpamBond, 1), 50);
+		assert_eq!(crate::Challenges::<Test>::get(2).unwrap().escrowed, 150);
+		assert_eq!(crate::LastAutoSlash::<Test>::get(1), Some(1));
+		assert!(Messaging::is_graylisted(&1));
+		System::assert_has_event(Event::BondAutoSlashed { who: 1, amount: 50, strikes: 3 }.into());
+		assert_eq!(auto_slash_events(), 1);
+	});
+}
+
+#[test]
+fn auto_slash_graylists_before_max_strikes() {
+	new_test_ext().execute_with(|| {
+		AutoSlashThreshold::set(2);
+		setup_approved_pair();
+		add_strike(1, 2);
+		assert!(!Messaging::is_graylisted(&1));
+		add_strike(1, 2);
+
+		assert!(Messaging::is_graylisted(&1));
+		assert!(crate::GraylistedAccounts::<Test>::contains_key(1));
+		System::assert_has_event(Event::AccountGraylisted { who: 1, strikes: 2 }.into());
+		System::assert_has_event(Event::BondAutoSlashed { who: 1, amount: 25, strikes: 2 }.into());
+	});
+}
+
+#[test]
+fn auto_slash_can_be_disabled() {
+	new_test_ext().execute_with(|| {
+		AutoSlashEnabled::set(false);
+		AutoSlashThreshold::set(2);
+		setup_approved_pair();
+		for _ in 0..2 {
+			add_strike(1, 2);
+		}
+
+		// Only the challenge slash applies and graylisting waits for MaxStrikes
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 50);
+		assert!(!Messaging::is_graylisted(&1));
+		assert!(!crate::LastAutoSlash::<Test>::contains_key(1));
+		assert_eq!(auto_slash_events(), 0);
+	});
+}
+
+#[test]
+fn auto_slash_applies_once_per_block() {
+	new_test_ext().execute_with(|| {
+		AutoSlashThreshold::set(1);
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([2; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(5), 1, evidence_commitment()));
+
+		// First verdict reaches the threshold and is overturned within the same block
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+		assert_eq!(auto_slash_events(), 1);
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0));
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, false));
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 100);
+
+		// Reaching the threshold again in the same block does not slash twice
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 1, true));
+		assert_eq!(Messaging::current_strikes(&1), 1);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 50);
+		assert_eq!(auto_slash_events(), 1);
+	});
+}
+
+#[test]
+fn auto_slash_handles_bond_smaller_than_slash() {
+	new_test_ext().execute_with(|| {
+		AutoSlashThreshold::set(1);
+		setup_approved_pair();
+		// Recorded bond exceeds what is actually held
+		crate::SpamBonds::<Test>::mutate(1, |bond| bond.amount = 400);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+
+		// Only the 100 actually held is slashed
+		assert_eq!(held(HoldReason::SpamBond, 1), 0);
+		assert_eq!(crate::Challenges::<Test>::get(0).unwrap().escrowed, 100);
+		assert_eq!(Balances::free_balance(Messaging::escrow_account()), 100);
+		System::assert_has_event(Event::BondAutoSlashed { who: 1, amount: 0, strikes: 1 }.into());
+	});
+}
+
+#[test]
+fn expire_challenge_refunds_challenger() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+
+		// ChallengeMaxDuration is 100 blocks from opening at block 1
+		System::set_block_number(101);
+		assert_noop!(
+			Messaging::expire_challenge(RuntimeOrigin::signed(5), 0),
+			Error::<Test>::ChallengeNotExpired
+		);
+
+		System::set_block_number(102);
+		assert_ok!(Messaging::expire_challenge(RuntimeOrigin::signed(5), 0));
+
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(Balances::free_balance(3), 1000);
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 0);
+		System::assert_last_event(Event::ChallengeExpired { message_id: 0 }.into());
+	});
+}
+
+#[test]
+fn expired_challenge_does_not_affect_reputation() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		System::set_block_number(102);
+		assert_ok!(Messaging::expire_challenge(RuntimeOrigin::signed(1), 0));
+
+		assert_eq!(Messaging::current_strikes(&1), 0);
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(Balances::free_balance(1), 900);
+
+		// The sender's bond is no longer pinned and the message can be challenged again
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+	});
+}
+
+#[test]
+fn expire_challenge_closes_system_challenges() {
+	new_test_ext().execute_with(|| {
+		open_system_challenge();
+		System::set_block_number(102);
+		assert_ok!(Messaging::expire_challenge(RuntimeOrigin::signed(5), 2));
+
+		assert!(!crate::Challenges::<Test>::contains_key(2));
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 0);
+	});
+}
+
+#[test]
+fn resolve_then_expire_in_same_block() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		System::set_block_number(102);
+
+		// Resolution lands first; the challenge is no longer open
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+		assert_noop!(
+			Messaging::expire_challenge(RuntimeOrigin::signed(5), 0),
+			Error::<Test>::ChallengeNotOpen
+		);
+		assert_eq!(Messaging::current_strikes(&1), 1);
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 20);
+	});
+}
+
+#[test]
+fn expire_then_resolve_in_same_block() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		System::set_block_number(102);
+
+		// Expiry lands first; the resolution finds nothing to resolve
+		assert_ok!(Messaging::expire_challenge(RuntimeOrigin::signed(5), 0));
+		assert_noop!(
+			Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true),
+			Error::<Test>::ChallengeNotFound
+		);
+		assert_eq!(Messaging::current_strikes(&1), 0);
+		assert_eq!(Balances::free_balance(3), 1000);
+	});
+}
+
+type ContactFee = SkipFeeForContacts<Test, ChargeTransactionPayment<Test>>;
+
+/// A `send_message_hash` call to `recipient`, with a hash unique to the next message ID.
+fn send_call(recipient: u64) -> RuntimeCall {
+	let message_id = crate::NextMessageId::<Test>::get();
+	RuntimeCall::Messaging(crate::Call::send_message_hash {
+		recipient,
+		message_hash: H256::repeat_byte(1 + message_id as u8),
+	})
+}
+
+fn make_mutual_contacts(a: u64, b: u64) {
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(a), b));
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(b), a));
+}
+
+/// Dispatch `call` from `who` through the fee extension and return the fee charged.
+fn dispatch_with_fee(who: u64, call: RuntimeCall) -> Balance {
+	let info = call.get_dispatch_info();
+	let len = call.encoded_size();
+	let before = Balances::free_balance(who);
+	let ext = ContactFee::from(ChargeTransactionPayment::from(0));
+	assert_ok!(ext
+		.dispatch_transaction(RuntimeOrigin::signed(who), call, &info, len, 0)
+		.expect("transaction is valid"));
+	before - Balances::free_balance(who)
+}
+
+fn full_fee(call: &RuntimeCall) -> Balance {
+	TransactionPayment::compute_fee(call.encoded_size() as u32, &call.get_dispatch_info(), 0)
+}
+
+#[test]
+fn send_between_mutual_contacts_is_fee_free() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+
+		assert_eq!(dispatch_with_fee(1, send_call(2)), 0);
+
+		// The send still counts against the rate limit
+		assert_eq!(crate::NextMessageId::<Test>::get(), 1);
+		assert_eq!(crate::SendRateLimits::<Test>::get(1).sent, 1);
+	});
+}
+
+#[test]
+fn send_to_non_contact_pays_fee() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+
+		// One-sided approval is not enough
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+
+		let call = send_call(2);
+		let fee = full_fee(&call);
+		assert!(fee > 0);
+		assert_eq!(dispatch_with_fee(1, call), fee);
+	});
+}
+
+#[test]
+fn other_calls_pay_fee_between_contacts() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+
+		let call = RuntimeCall::Messaging(crate::Call::remove_contact { contact: 2 });
+		let fee = full_fee(&call);
+		assert_eq!(dispatch_with_fee(1, call), fee);
+	});
+}
+
+#[test]
+fn send_pays_fee_without_active_bonds() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(2)));
+
+		// The recipient's bond is gone
+		let call = send_call(2);
+		let fee = full_fee(&call);
+		assert_eq!(dispatch_with_fee(1, call), fee);
+	});
+}
+
+#[test]
+fn free_sends_are_bounded_per_block() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+
+		assert_eq!(dispatch_with_fee(1, send_call(2)), 0);
+		assert_eq!(dispatch_with_fee(1, send_call(2)), 0);
+
+		// `MaxFreeMessagesPerBlock` is used up
+		let call = send_call(2);
+		let fee = full_fee(&call);
+		assert_eq!(dispatch_with_fee(1, call), fee);
+		assert_eq!(crate::SendRateLimits::<Test>::get(1).sent_in_block, 3);
+
+		// The allowance resets in the next block
+		System::set_block_number(2);
+		assert_eq!(dispatch_with_fee(1, send_call(2)), 0);
+		assert_eq!(crate::SendRateLimits::<Test>::get(1).sent_in_block, 1);
+	});
+}
+
+#[test]
+fn free_sends_stop_when_rate_limit_is_exhausted() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+
+		// Use up the window two messages per block
+		for block in 1..=5u8 {
+			System::set_block_number(block.into());
+			for hash in [H256::repeat_byte(block), H256::repeat_byte(block + 10)] {
+				assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+			}
+		}
+		System::set_block_number(6);
+		assert!(!Messaging::is_fee_exempt(&1, &2));
+
+		// Validation falls through to the payment extension, which charges the failed send
+		let call = send_call(2);
+		let fee = full_fee(&call);
+		let info = call.get_dispatch_info();
+		let len = call.encoded_size();
+		let ext = ContactFee::from(ChargeTransactionPayment::from(0));
+		let before = Balances::free_balance(1);
+		assert_err!(
+			ext.dispatch_transaction(RuntimeOrigin::signed(1), call, &info, len, 0)
+				.unwrap()
+				.map_err(|e| e.error),
+			Error::<Test>::RateLimitExceeded
+		);
+		assert_eq!(Balances::free_balance(1), before - fee);
+
+		// A new window restores the exemption
+		System::set_block_number(11);
+		assert!(Messaging::is_fee_exempt(&1, &2));
+	});
+}
+
+#[test]
+fn failed_sends_between_contacts_pay_fee() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		assert_eq!(dispatch_with_fee(1, send_call(2)), 0);
+		let used = H256::repeat_byte(1);
+
+		// Sends bound to fail are charged even while the exemption holds
+		for (message_hash, error) in [
+			(H256::zero(), Error::<Test>::InvalidMessageHash),
+			(used, Error::<Test>::DuplicateMessageHash),
+		] {
+			assert!(Messaging::is_fee_exempt(&1, &2));
+			let call = RuntimeCall::Messaging(crate::Call::send_message_hash {
+				recipient: 2,
+				message_hash,
+			});
+			let fee = full_fee(&call);
+			let info = call.get_dispatch_info();
+			let len = call.encoded_size();
+			let ext = ContactFee::from(ChargeTransactionPayment::from(0));
+			let before = Balances::free_balance(1);
+			assert_err!(
+				ext.dispatch_transaction(RuntimeOrigin::signed(1), call, &info, len, 0)
+					.unwrap()
+					.map_err(|e| e.error),
+				error
+			);
+			assert_eq!(Balances::free_balance(1), before - fee);
+		}
+
+		// Failed sends used none of the free quota
+		assert_eq!(crate::SendRateLimits::<Test>::get(1).sent_in_block, 1);
+	});
+}
+
+/// Register accounts 1 and 2, have 2 ask 50 for paid messages and send one from 1.
+fn send_paid_message() {
+	setup_pair();
+	assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+	assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+}
+
+#[test]
+fn set_inbox_price_works() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+		assert_eq!(crate::InboxPrices::<Test>::get(2), Some(50));
+		System::assert_last_event(Event::InboxPriceSet { who: 2, price: Some(50) }.into());
+
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), None));
+		assert_eq!(crate::InboxPrices::<Test>::get(2), None);
+
+		assert_noop!(
+			Messaging::set_inbox_price(RuntimeOrigin::signed(3), Some(50)),
+			Error::<Test>::ProfileNotFound
+		);
+	});
+}
+
+#[test]
+fn send_paid_message_holds_payment() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+
+		// The price is held from the sender on top of the bond
+		assert_eq!(held(HoldReason::InboxPayment, 1), 50);
+		assert_eq!(Balances::free_balance(1), 850);
+		assert_eq!(Balances::free_balance(2), 900);
+		assert_eq!(
+			crate::InboxPayments::<Test>::get(0),
+			Some(crate::InboxPayment { sender: 1, recipient: 2, amount: 50 })
+		);
+		assert!(crate::MessageHashes::<Test>::contains_key(0));
+		System::assert_last_event(
+			Event::InboxPaymentHeld {
+				message_id: 0,
+				sender: 1,
+				recipient: 2,
+				amount: 50,
+				tier: InboxTier::Others,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn send_paid_message_fails_when_inbox_closed() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+
+		assert_noop!(
+			Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::InboxClosed
+		);
+	});
+}
+
+#[test]
+fn send_paid_message_fails_without_funds() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(2000)));
+
+		assert_noop!(
+			Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::InsufficientInboxPayment
+		);
+	});
+}
+
+#[test]
+fn send_paid_message_is_free_for_approved_senders() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+
+		assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+
+		assert!(crate::MessageHashes::<Test>::contains_key(0));
+		assert!(!crate::InboxPayments::<Test>::contains_key(0));
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+	});
+}
+
+#[test]
+fn acknowledge_message_pays_recipient() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		System::set_block_number(4);
+
+		assert_noop!(
+			Messaging::acknowledge_message(RuntimeOrigin::signed(1), 0, false),
+			Error::<Test>::NotAuthorized
+		);
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+		assert_eq!(Balances::free_balance(1), 850);
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(Balances::free_balance(2), 950);
+		assert!(!crate::InboxPayments::<Test>::contains_key(0));
+		assert!(!crate::InboxPasses::<Test>::contains_key(2, 1));
+		System::assert_last_event(
+			Event::MessageAcknowledged { message_id: 0, recipient: 2, amount: 50, latency: 3 }
+				.into(),
+		);
+
+		// The payment can only be collected once
+		assert_noop!(
+			Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false),
+			Error::<Test>::PaymentNotFound
+		);
+	});
+}
+
+#[test]
+fn acknowledge_message_can_grant_pass() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, true));
+		assert_eq!(crate::InboxPasses::<Test>::get(2, 1), Some(21));
+		System::assert_last_event(Event::InboxPassGranted { recipient: 2, sender: 1, until: 21 }.into());
+
+		// The pass lets the sender through without paying
+		assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([2; 32])));
+		assert!(!crate::InboxPayments::<Test>::contains_key(1));
+
+		// Once it lapses the price applies again
+		System::set_block_number(21);
+		assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([3; 32])));
+		assert_eq!(crate::InboxPayments::<Test>::get(2).map(|p| p.amount), Some(50));
+	});
+}
+
+#[test]
+fn expired_payment_is_refunded_to_sender() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+
+		assert_noop!(
+			Messaging::refund_inbox_payment(RuntimeOrigin::signed(5), 0),
+			Error::<Test>::MessageNotExpired
+		);
+
+		// The recipient can no longer collect once the message expires
+		System::set_block_number(1002);
+		assert_noop!(
+			Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false),
+			Error::<Test>::MessageExpired
+		);
+
+		// Anyone can trigger the refund
+		assert_ok!(Messaging::refund_inbox_payment(RuntimeOrigin::signed(5), 0));
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+		assert_eq!(Balances::free_balance(1), 900);
+		assert_eq!(Balances::free_balance(2), 900);
+		assert!(!crate::InboxPayments::<Test>::contains_key(0));
+		System::assert_last_event(
+			Event::InboxPaymentRefunded { message_id: 0, sender: 1, amount: 50 }.into(),
+		);
+	});
+}
+
+#[test]
+fn retract_paid_message_refunds_sender() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+
+		assert_noop!(
+			Messaging::retract_paid_message(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::NotAuthorized
+		);
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+
+		// The payment is returned and the message withdrawn
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+		assert_eq!(Balances::free_balance(1), 900);
+		assert_eq!(Balances::free_balance(2), 900);
+		assert!(!crate::InboxPayments::<Test>::contains_key(0));
+		assert!(!crate::MessageHashes::<Test>::contains_key(0));
+		System::assert_last_event(
+			Event::PaidMessageRetracted { message_id: 0, sender: 1, amount: 50 }.into(),
+		);
+
+		// Nothing is left for the recipient to collect
+		assert_noop!(
+			Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false),
+			Error::<Test>::PaymentNotFound
+		);
+	});
+}
+
+#[test]
+fn retract_fails_after_acknowledgement() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+
+		assert_noop!(
+			Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::PaymentNotFound
+		);
+		assert_eq!(Balances::free_balance(2), 950);
+	});
+}
+
+#[test]
+fn retract_fails_while_challenged() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, evidence_commitment()));
+
+		assert_noop!(
+			Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::AlreadyChallenged
+		);
+	});
+}
+
+#[test]
+fn pause_requires_admin_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::pause(RuntimeOrigin::signed(1), PausedCalls::ALL),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::pause(RuntimeOrigin::root(), PausedCalls::ALL),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::unpause(RuntimeOrigin::signed(1), PausedCalls::ALL),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn pausing_sends_blocks_only_sends() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::SENDS));
+		System::assert_last_event(
+			Event::PalletPaused { calls: PausedCalls::SENDS, paused: PausedCalls::SENDS }.into(),
+		);
+
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::PalletPaused
+		);
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+		assert_noop!(
+			Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::PalletPaused
+		);
+
+		// Other groups keep working
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+	});
+}
+
+#[test]
+fn pause_and_unpause_combine_groups() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::REGISTRATIONS));
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::CONTACTS));
+		assert!(crate::Paused::<Test>::get().contains(PausedCalls::REGISTRATIONS));
+		assert!(crate::Paused::<Test>::get().contains(PausedCalls::CONTACTS));
+
+		assert_noop!(
+			Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]),
+			Error::<Test>::PalletPaused
+		);
+		assert_noop!(
+			Messaging::update_profile(RuntimeOrigin::signed(1), vec![5, 6, 7, 8]),
+			Error::<Test>::PalletPaused
+		);
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::PalletPaused
+		);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+
+		// Lifting one group leaves the other paused
+		assert_ok!(Messaging::unpause(RuntimeOrigin::signed(Admin::get()), PausedCalls::REGISTRATIONS));
+		System::assert_last_event(
+			Event::PalletUnpaused {
+				calls: PausedCalls::REGISTRATIONS,
+				paused: PausedCalls::CONTACTS,
+			}
+			.into(),
+		);
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::PalletPaused
+		);
+
+		assert_ok!(Messaging::unpause(RuntimeOrigin::signed(Admin::get()), PausedCalls::ALL));
+		assert!(crate::Paused::<Test>::get().is_empty());
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+	});
+}
+
+#[test]
+fn refunds_and_acknowledgements_work_while_paused() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([2; 32])));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::ALL));
+
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 1));
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 2));
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(2)));
+	});
+}
+
+#[test]
+fn housekeeping_works_while_paused() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+		assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, H256::from([2; 32])));
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::ALL));
+
+		// Governance and permissionless cleanup are unaffected
+		System::set_block_number(1002);
+		assert_ok!(Messaging::expire_challenge(RuntimeOrigin::signed(5), 0));
+		assert_ok!(Messaging::refund_inbox_payment(RuntimeOrigin::signed(5), 1));
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+	});
+}
+
+#[test]
+fn paused_sends_are_not_fee_exempt() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		assert!(Messaging::is_fee_exempt(&1, &2));
+
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::SENDS));
+		assert!(!Messaging::is_fee_exempt(&1, &2));
+	});
+}
+
+fn order_reference() -> OrderReference {
+	b"court-order-2024-17".to_vec().try_into().unwrap()
+}
+
+#[test]
+fn force_remove_message_works() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 0));
+		System::set_block_number(5);
+
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 0, order_reference()));
+
+		assert!(!crate::MessageHashes::<Test>::contains_key(0));
+		assert!(!crate::ReportedMessages::<Test>::get(0, 2));
+		assert_eq!(
+			crate::Takedowns::<Test>::get(0),
+			Some(TakedownRecord {
+				sender: 1,
+				recipient: 2,
+				removed_at: 5,
+				order_reference: order_reference(),
+			})
+		);
+		System::assert_last_event(
+			Event::MessageTakenDown {
+				message_id: 0,
+				sender: 1,
+				recipient: 2,
+				order_reference: order_reference(),
+			}
+			.into(),
+		);
+
+		assert_noop!(
+			Messaging::force_remove_message(RuntimeOrigin::root(), 0, order_reference()),
+			Error::<Test>::MessageNotFound
+		);
+	});
+}
+
+#[test]
+fn verifying_taken_down_message_fails() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 0, order_reference()));
+
+		assert_eq!(
+			Messaging::verify_message_hash(0, H256::from([1; 32])),
+			Err(Error::<Test>::MessageRemovedByGovernance.into())
+		);
+		// Unknown messages are still reported as such
+		assert_eq!(
+			Messaging::verify_message_hash(1, H256::from([1; 32])),
+			Err(Error::<Test>::MessageNotFound.into())
+		);
+	});
+}
+
+#[test]
+fn takedown_does_not_penalize_sender() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 0, order_reference()));
+
+		assert_eq!(Messaging::current_strikes(&1), 0);
+		assert_eq!(Messaging::bond(&1), Some(100));
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+	});
+}
+
+#[test]
+fn prune_takedown_works_after_retention() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 0, order_reference()));
+
+		// Retained until TakedownRetention blocks have passed
+		System::set_block_number(51);
+		assert_noop!(
+			Messaging::prune_takedown(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::TakedownRetained
+		);
+
+		System::set_block_number(52);
+		assert_ok!(Messaging::prune_takedown(RuntimeOrigin::signed(3), 0));
+		assert!(crate::Takedowns::<Test>::get(0).is_none());
+		System::assert_last_event(Event::TakedownPruned { message_id: 0 }.into());
+
+		assert_noop!(
+			Messaging::prune_takedown(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::TakedownNotFound
+		);
+	});
+}
+
+#[test]
+fn force_remove_message_requires_force_origin() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+
+		// Neither users nor the operational admin may remove messages
+		assert_noop!(
+			Messaging::force_remove_message(RuntimeOrigin::signed(2), 0, order_reference()),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::force_remove_message(
+				RuntimeOrigin::signed(Admin::get()),
+				0,
+				order_reference()
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn force_remove_message_refunds_inbox_payment() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 0, order_reference()));
+
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+		assert_eq!(Balances::free_balance(1), 900);
+		assert!(!crate::InboxPayments::<Test>::contains_key(0));
+	});
+}
+
+fn custom_parameters() -> PalletParametersOf<Test> {
+	PalletParameters {
+		spam_bond: 200,
+		message_expiry: 50,
+		max_contacts: 1,
+		event_privacy: EventPrivacyMode::Minimal,
+		message_protocol_fee: 10,
+		exempt_mutual_contacts: true,
+		challenger_reward_percent: Perbill::from_percent(30),
+		auto_slash_percent: Perbill::from_percent(20),
+		base_challenge_reward: 5,
+		slash_destination: SlashDestination::Burn,
+	}
+}
+
+#[test]
+fn parameters_default_to_constants() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			Messaging::parameters(),
+			PalletParameters {
+				spam_bond: 100,
+				message_expiry: 1000,
+				max_contacts: 100,
+				event_privacy: EventPrivacyMode::Full,
+				message_protocol_fee: 0,
+				exempt_mutual_contacts: false,
+				challenger_reward_percent: Perbill::from_percent(50),
+				auto_slash_percent: Perbill::from_percent(50),
+				base_challenge_reward: 10,
+				slash_destination: SlashDestination::Treasury,
+			}
+		);
+	});
+}
+
+#[test]
+fn update_parameters_works() {
+	new_test_ext().execute_with(|| {
+		let old = Messaging::parameters();
+		assert_ok!(Messaging::update_parameters(RuntimeOrigin::signed(Admin::get()), custom_parameters()));
+
+		assert_eq!(Messaging::parameters(), custom_parameters());
+		System::assert_last_event(Event::ParametersUpdated { old, new: custom_parameters() }.into());
+	});
+}
+
+#[test]
+fn update_parameters_requires_admin_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::update_parameters(RuntimeOrigin::signed(1), custom_parameters()),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn update_parameters_rejects_invalid_values() {
+	new_test_ext().execute_with(|| {
+		let admin = RuntimeOrigin::signed(Admin::get());
+		assert_noop!(
+			Messaging::update_parameters(
+				admin.clone(),
+				PalletParameters { message_expiry: 0, ..custom_parameters() }
+			),
+			Error::<Test>::ZeroMessageExpiry
+		);
+		assert_noop!(
+			Messaging::update_parameters(admin, PalletParameters { spam_bond: 0, ..custom_parameters() }),
+			Error::<Test>::SpamBondBelowMinimum
+		);
+	});
+}
+
+#[test]
+fn new_spam_bond_applies_to_new_registrations_only() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::update_parameters(RuntimeOrigin::signed(Admin::get()), custom_parameters()));
+
+		// Existing accounts keep the bond they registered with
+		assert_eq!(Messaging::required_bond(&1), 100);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+
+		// New registrations hold the new amount
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		assert_eq!(held(HoldReason::SpamBond, 3), 200);
+		assert_eq!(Messaging::required_bond(&3), 200);
+	});
+}
+
+#[test]
+fn legacy_accounts_keep_constant_bond_requirement() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		// Simulate an account registered before bonds were recorded
+		crate::UserProfiles::<Test>::mutate(1, |profile| {
+			profile.as_mut().unwrap().registration_bond = None
+		});
+		assert_ok!(Messaging::update_parameters(RuntimeOrigin::signed(Admin::get()), custom_parameters()));
+
+		assert_eq!(Messaging::required_bond(&1), 100);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+	});
+}
+
+#[test]
+fn updated_expiry_and_contact_limit_take_effect() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::update_parameters(RuntimeOrigin::signed(Admin::get()), custom_parameters()));
+
+		// Expiry is measured from the send block
+		System::set_block_number(51);
+		assert!(Messaging::message_state(0).is_live());
+		System::set_block_number(52);
+		assert_eq!(Messaging::message_state(0), MessageState::Expired);
+
+		// Only one contact is allowed
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(1), 3),
+			Error::<Test>::MaxContactsReached
+		);
+	});
+}
+
+const MODERATOR: u64 = 6;
+
+fn add_moderator() {
+	assert_ok!(Messaging::add_moderator(RuntimeOrigin::signed(Admin::get()), MODERATOR));
+}
+
+#[test]
+fn add_and_remove_moderator_work() {
+	new_test_ext().execute_with(|| {
+		add_moderator();
+		assert!(Messaging::is_moderator(&MODERATOR));
+		System::assert_last_event(Event::ModeratorAdded { who: MODERATOR }.into());
+		assert_noop!(
+			Messaging::add_moderator(RuntimeOrigin::signed(Admin::get()), MODERATOR),
+			Error::<Test>::AlreadyModerator
+		);
+
+		assert_ok!(Messaging::remove_moderator(RuntimeOrigin::signed(Admin::get()), MODERATOR));
+		assert!(!Messaging::is_moderator(&MODERATOR));
+		System::assert_last_event(Event::ModeratorRemoved { who: MODERATOR }.into());
+		assert_noop!(
+			Messaging::remove_moderator(RuntimeOrigin::signed(Admin::get()), MODERATOR),
+			Error::<Test>::NotModerator
+		);
+	});
+}
+
+#[test]
+fn moderator_set_requires_admin_and_is_bounded() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::add_moderator(RuntimeOrigin::signed(1), MODERATOR),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		for who in 100..132 {
+			assert_ok!(Messaging::add_moderator(RuntimeOrigin::signed(Admin::get()), who));
+		}
+		assert_noop!(
+			Messaging::add_moderator(RuntimeOrigin::signed(Admin::get()), MODERATOR),
+			Error::<Test>::TooManyModerators
+		);
+	});
+}
+
+#[test]
+fn ensure_moderator_checks_the_set() {
+	new_test_ext().execute_with(|| {
+		use frame_support::traits::EnsureOrigin;
+
+		assert!(EnsureModerator::<Test>::try_origin(RuntimeOrigin::signed(MODERATOR)).is_err());
+		add_moderator();
+		assert_eq!(
+			EnsureModerator::<Test>::try_origin(RuntimeOrigin::signed(MODERATOR)).ok(),
+			Some(MODERATOR)
+		);
+		assert!(EnsureModerator::<Test>::try_origin(RuntimeOrigin::root()).is_err());
+	});
+}
+
+#[test]
+fn moderator_challenge_opens_bondless_challenge() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+
+		assert_noop!(
+			Messaging::moderator_challenge(RuntimeOrigin::signed(MODERATOR), 0),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		add_moderator();
+		assert_ok!(Messaging::moderator_challenge(RuntimeOrigin::signed(MODERATOR), 0));
+
+		let challenge = crate::Challenges::<Test>::get(0).unwrap();
+		assert_eq!(challenge.challenger, None);
+		assert_eq!(challenge.sender, 1);
+		assert_eq!(held(HoldReason::ChallengeBond, MODERATOR), 0);
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 1);
+		System::assert_last_event(
+			Event::ModeratorAction {
+				who: MODERATOR,
+				action: ModeratorActionKind::OpenChallenge { message_id: 0 },
+				target: 1,
+			}
+			.into(),
+		);
+
+		assert_noop!(
+			Messaging::moderator_challenge(RuntimeOrigin::signed(MODERATOR), 0),
+			Error::<Test>::AlreadyChallenged
+		);
+	});
+}
+
+#[test]
+fn fast_track_resolves_system_challenges() {
+	new_test_ext().execute_with(|| {
+		open_system_challenge();
+		add_moderator();
+
+		assert_ok!(Messaging::fast_track_challenge(RuntimeOrigin::signed(MODERATOR), 2, true));
+
+		assert!(matches!(
+			crate::Challenges::<Test>::get(2).map(|c| c.status),
+			Some(crate::ChallengeStatus::Resolved { upheld: true, .. })
+		));
+		assert_eq!(Messaging::current_strikes(&1), 1);
+		System::assert_has_event(Event::ChallengeResolved { message_id: 2, upheld: true }.into());
+		System::assert_last_event(
+			Event::ModeratorAction {
+				who: MODERATOR,
+				action: ModeratorActionKind::FastTrackChallenge { message_id: 2, upheld: true },
+				target: 1,
+			}
+			.into(),
+		);
+
+		// The verdict stays appealable
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 2));
+	});
+}
+
+#[test]
+fn fast_track_leaves_user_challenges_to_governance() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		add_moderator();
+
+		assert_noop!(
+			Messaging::fast_track_challenge(RuntimeOrigin::signed(MODERATOR), 0, true),
+			Error::<Test>::NotSystemChallenge
+		);
+		assert_noop!(
+			Messaging::fast_track_challenge(RuntimeOrigin::signed(MODERATOR), 1, true),
+			Error::<Test>::ChallengeNotFound
+		);
+	});
+}
+
+#[test]
+fn freeze_sender_blocks_sends_until_expiry() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		add_moderator();
+
+		assert_ok!(Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10));
+		System::assert_last_event(
+			Event::ModeratorAction {
+				who: MODERATOR,
+				action: ModeratorActionKind::FreezeSender { until: 11 },
+				target: 1,
+			}
+			.into(),
+		);
+
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::SenderFrozen
+		);
+		assert!(!Messaging::is_fee_exempt(&1, &2));
+
+		// The other side of the conversation is unaffected
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, H256::from([1; 32])));
+
+		System::set_block_number(11);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([2; 32])));
+	});
+}
+
+#[test]
+fn unfreeze_sender_lifts_freeze() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		add_moderator();
+		assert_ok!(Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10));
+
+		assert_ok!(Messaging::unfreeze_sender(RuntimeOrigin::signed(MODERATOR), 1));
+		System::assert_last_event(
+			Event::ModeratorAction {
+				who: MODERATOR,
+				action: ModeratorActionKind::UnfreezeSender,
+				target: 1,
+			}
+			.into(),
+		);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+
+		assert_noop!(
+			Messaging::unfreeze_sender(RuntimeOrigin::signed(MODERATOR), 1),
+			Error::<Test>::SenderNotFrozen
+		);
+	});
+}
+
+#[test]
+fn freeze_sender_validates_duration_and_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		add_moderator();
+		assert_noop!(
+			Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 0),
+			Error::<Test>::InvalidFreezeDuration
+		);
+		assert_noop!(
+			Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 101),
+			Error::<Test>::InvalidFreezeDuration
+		);
+
+		// Removed moderators lose their powers
+		assert_ok!(Messaging::remove_moderator(RuntimeOrigin::signed(Admin::get()), MODERATOR));
+		assert_noop!(
+			Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+fn ban(who: u64) {
+	assert_ok!(Messaging::ban_account(RuntimeOrigin::root(), who, H256::from([9; 32])));
+}
+
+#[test]
+fn ban_account_removes_profile_and_slashes_bond() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+		let issuance = Balances::total_issuance();
+
+		ban(2);
+
+		assert!(!crate::UserProfiles::<Test>::contains_key(2));
+		assert_eq!(crate::SpamBonds::<Test>::get(2).amount, 0);
+		assert_eq!(crate::InboxPrices::<Test>::get(2), None);
+		assert_eq!(held(HoldReason::SpamBond, 2), 0);
+		assert_eq!(Balances::total_issuance(), issuance - 100);
+		assert_eq!(
+			crate::BannedAccounts::<Test>::get(2),
+			Some(crate::BanRecord { at: 1, reason: H256::from([9; 32]) })
+		);
+		System::assert_last_event(
+			Event::AccountBanned { who: 2, reason_hash: H256::from([9; 32]), slashed: 100 }.into(),
+		);
+
+		assert_noop!(
+			Messaging::ban_account(RuntimeOrigin::root(), 2, H256::from([9; 32])),
+			Error::<Test>::AccountBanned
+		);
+	});
+}
+
+#[test]
+fn ban_account_requires_force_origin() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_noop!(
+			Messaging::ban_account(RuntimeOrigin::signed(Admin::get()), 2, H256::from([9; 32])),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		ban(2);
+		assert_noop!(
+			Messaging::unban_account(RuntimeOrigin::signed(Admin::get()), 2),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn banned_mid_conversation() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, H256::from([2; 32])));
+
+		ban(2);
+
+		// Neither side can continue the conversation
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, H256::from([3; 32])),
+			Error::<Test>::AccountBanned
+		);
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([3; 32])),
+			Error::<Test>::AccountBanned
+		);
+		assert!(!Messaging::is_fee_exempt(&1, &2));
+
+		// Other user-facing calls report the ban too
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(2), 3),
+			Error::<Test>::AccountBanned
+		);
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::AccountBanned
+		);
+		assert_noop!(
+			Messaging::update_profile(RuntimeOrigin::signed(2), vec![5, 6, 7, 8]),
+			Error::<Test>::AccountBanned
+		);
+		assert_noop!(
+			Messaging::top_up_bond(RuntimeOrigin::signed(2), 10),
+			Error::<Test>::AccountBanned
+		);
+		assert_noop!(
+			Messaging::challenge_spam(RuntimeOrigin::signed(2), 0, evidence_commitment()),
+			Error::<Test>::AccountBanned
+		);
+		assert_noop!(
+			Messaging::report_spam(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::AccountBanned
+		);
+
+		// Messages already sent remain verifiable
+		assert_eq!(Messaging::verify_message_hash(1, H256::from([2; 32])), Ok(true));
+	});
+}
+
+#[test]
+fn reregistration_after_unban() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		ban(2);
+
+		assert_noop!(
+			Messaging::register_profile(RuntimeOrigin::signed(2), vec![1, 2, 3, 4]),
+			Error::<Test>::AccountBanned
+		);
+
+		assert_ok!(Messaging::unban_account(RuntimeOrigin::root(), 2));
+		System::assert_last_event(Event::AccountUnbanned { who: 2 }.into());
+		assert_noop!(
+			Messaging::unban_account(RuntimeOrigin::root(), 2),
+			Error::<Test>::AccountNotBanned
+		);
+
+		// Nothing is restored; the account starts over with a fresh bond
+		assert!(!crate::UserProfiles::<Test>::contains_key(2));
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![1, 2, 3, 4]));
+		assert_eq!(held(HoldReason::SpamBond, 2), 100);
+		assert_eq!(Balances::free_balance(2), 800);
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, H256::from([1; 32])));
+	});
+}
+
+#[test]
+fn ban_routes_slashed_bond_to_slash_income() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		ban(2);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::Slash), 100);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::ForfeitedDeposit), 0);
+	});
+}
+
+#[test]
+fn reset_income_clears_counter() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		ban(2);
+
+		assert_noop!(
+			Messaging::reset_income(RuntimeOrigin::signed(1), IncomeCategory::Slash),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(Messaging::reset_income(
+			RuntimeOrigin::signed(Admin::get()),
+			IncomeCategory::Slash
+		));
+		System::assert_last_event(
+			Event::IncomeReset { category: IncomeCategory::Slash, amount: 100 }.into(),
+		);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::Slash), 0);
+	});
+}
+
+#[test]
+fn issuance_changes_only_through_burning_income_handlers() {
+	// Slash income is burned in the mock, fee and forfeited-deposit income go to the
+	// treasury. Any other change to total issuance is a leak.
+	for seed in 0..40u64 {
+		new_test_ext().execute_with(|| {
+			let accounts = [1u64, 2, 3, 5, 6];
+			for who in accounts {
+				assert_ok!(Messaging::register_profile(
+					RuntimeOrigin::signed(who),
+					vec![1, 2, 3, 4]
+				));
+			}
+			make_mutual_contacts(1, 2);
+			make_mutual_contacts(3, 5);
+			assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(6), Some(30)));
+			add_moderator();
+
+			let issuance = Balances::total_issuance();
+			let treasury = Balances::free_balance(TreasuryAccount::get());
+			let check = || {
+				assert_eq!(
+					issuance - Balances::total_issuance(),
+					crate::PalletIncome::<Test>::get(IncomeCategory::Slash) +
+						crate::PalletIncome::<Test>::get(IncomeCategory::ProtocolFee)
+				);
+				assert_eq!(
+					Balances::free_balance(TreasuryAccount::get()) - treasury,
+					crate::PalletIncome::<Test>::get(IncomeCategory::Fee) +
+						crate::PalletIncome::<Test>::get(IncomeCategory::ForfeitedDeposit)
+				);
+			};
+
+			let mut state =
+				seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+			let mut next = |n: u64| {
+				state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+				(state >> 33) % n
+			};
+			for step in 0..200u64 {
+				let who = accounts[next(5) as usize];
+				let other = accounts[next(5) as usize];
+				let id = next(crate::NextMessageId::<Test>::get().max(1));
+				// Challenge lifecycle calls target a message that is actually challenged
+				let challenged: Vec<_> = crate::Challenges::<Test>::iter_keys().collect();
+				let challenged_id = match challenged.len() as u64 {
+					0 => id,
+					n => challenged[next(n) as usize],
+				};
+				let hash = H256::from_low_u64_be(step + 1);
+				let _ = match next(16) {
+					0 | 1 => Messaging::send_message_hash(RuntimeOrigin::signed(who), other, hash)
+						.map(|_| ())
+						.map_err(|e| e.error),
+					2 => Messaging::send_paid_message_hash(RuntimeOrigin::signed(who), 6, hash)
+						.map(|_| ())
+						.map_err(|e| e.error),
+					3 => Messaging::report_spam(RuntimeOrigin::signed(who), id),
+					4 => Messaging::challenge_spam(
+						RuntimeOrigin::signed(who),
+						id,
+						evidence_commitment(),
+					),
+					5 => Messaging::moderator_challenge(RuntimeOrigin::signed(MODERATOR), id),
+					6 => Messaging::resolve_challenge(
+						RuntimeOrigin::root(),
+						challenged_id,
+						next(2) == 0,
+					),
+					7 => {
+						// Appeals are mostly filed by the challenged sender
+						let appellant = match crate::Challenges::<Test>::get(challenged_id) {
+							Some(challenge) if next(2) == 0 => challenge.sender,
+							_ => who,
+						};
+						Messaging::appeal_challenge(RuntimeOrigin::signed(appellant), challenged_id)
+					},
+					8 => Messaging::resolve_appeal(
+						RuntimeOrigin::root(),
+						challenged_id,
+						next(2) == 0,
+					),
+					9 => Messaging::finalize_challenge(RuntimeOrigin::signed(who), challenged_id),
+					10 => Messaging::acknowledge_message(RuntimeOrigin::signed(6), id, false),
+					11 => Messaging::retract_paid_message(RuntimeOrigin::signed(who), id),
+					12 => Messaging::top_up_bond(RuntimeOrigin::signed(who), 10),
+					13 => Messaging::withdraw_excess_bond(RuntimeOrigin::signed(who)),
+					14 if next(10) == 0 => Messaging::ban_account(RuntimeOrigin::root(), who, hash),
+					_ => {
+						System::set_block_number(System::block_number() + next(15) + 1);
+						Ok(())
+					},
+				};
+				check();
+			}
+		});
+	}
+}
+
+fn key_reset_notices(who: u64) -> Vec<u64> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::Messaging(Event::ContactKeyReset { contact, who: reset })
+				if reset == who =>
+				Some(contact),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn force_reset_key_replaces_key_and_revokes_old_one() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		System::set_block_number(5);
+
+		assert_noop!(
+			Messaging::force_reset_key(
+				RuntimeOrigin::signed(1),
+				1,
+				vec![9; 32],
+				KeyAlgorithm::Ed25519
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::force_reset_key(
+				RuntimeOrigin::root(),
+				3,
+				vec![9; 32],
+				KeyAlgorithm::Ed25519
+			),
+			Error::<Test>::ProfileNotFound
+		);
+		assert_noop!(
+			Messaging::force_reset_key(RuntimeOrigin::root(), 1, vec![], KeyAlgorithm::Ed25519),
+			Error::<Test>::InvalidPublicKey
+		);
+
+		assert_ok!(Messaging::force_reset_key(
+			RuntimeOrigin::root(),
+			1,
+			vec![9; 32],
+			KeyAlgorithm::Ed25519
+		));
+
+		assert_eq!(Messaging::profile(&1).unwrap().to_vec(), vec![9; 32]);
+		assert_eq!(crate::KeyVersions::<Test>::get(1), 1);
+		assert_eq!(crate::KeyAlgorithms::<Test>::get(1), KeyAlgorithm::Ed25519);
+		let history = crate::KeyHistory::<Test>::get(1);
+		assert_eq!(history.len(), 1);
+		assert_eq!(history[0].public_key.to_vec(), vec![1, 2, 3, 4]);
+		assert_eq!(history[0].version, 0);
+		assert_eq!(history[0].revoked_at, 5);
+		System::assert_has_event(
+			Event::KeyReset { who: 1, version: 1, algorithm: KeyAlgorithm::Ed25519 }.into(),
+		);
+
+		// No contacts, so notification is already complete
+		System::assert_last_event(Event::KeyResetNotificationsCompleted { who: 1 }.into());
+		assert!(crate::KeyResetCursors::<Test>::get(1).is_none());
+	});
+}
+
+#[test]
+fn key_history_keeps_most_recent_keys() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		for version in 1..=10u8 {
+			assert_ok!(Messaging::update_profile(RuntimeOrigin::signed(1), vec![version; 4]));
+		}
+
+		assert_eq!(crate::KeyVersions::<Test>::get(1), 10);
+		let history = crate::KeyHistory::<Test>::get(1);
+		assert_eq!(history.len() as u32, crate::MAX_KEY_HISTORY);
+		assert_eq!(history.first().unwrap().version, 2);
+		assert_eq!(history.last().unwrap().version, 9);
+		assert_eq!(history.last().unwrap().public_key.to_vec(), vec![9; 4]);
+	});
+}
+
+#[test]
+fn key_reset_notifications_are_bounded_and_resumable() {
+	new_test_ext().execute_with(|| {
+		for who in [1, 2, 3, 5, 6, 7] {
+			assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![1, 2, 3, 4]));
+		}
+		for contact in [2, 3, 5, 6] {
+			make_mutual_contacts(1, contact);
+		}
+		// One-way relationship that must not be notified
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 7));
+
+		// The first batch scans MaxKeyResetNotifications relationships
+		assert_ok!(Messaging::force_reset_key(
+			RuntimeOrigin::root(),
+			1,
+			vec![9; 32],
+			KeyAlgorithm::X25519
+		));
+		let mut notified = key_reset_notices(1);
+		assert!(notified.len() <= 3);
+		assert!(crate::KeyResetCursors::<Test>::get(1).is_some());
+
+		// The rest is finished by anyone, in bounded steps
+		System::reset_events();
+		assert_ok!(Messaging::continue_key_reset_notices(RuntimeOrigin::signed(4), 1));
+		notified.extend(key_reset_notices(1));
+		System::assert_last_event(Event::KeyResetNotificationsCompleted { who: 1 }.into());
+		assert!(crate::KeyResetCursors::<Test>::get(1).is_none());
+
+		// Every mutual contact was notified exactly once
+		notified.sort();
+		assert_eq!(notified, vec![2, 3, 5, 6]);
+
+		assert_noop!(
+			Messaging::continue_key_reset_notices(RuntimeOrigin::signed(4), 1),
+			Error::<Test>::NoPendingKeyResetNotices
+		);
+	});
+}
+
+#[test]
+fn new_key_reset_restarts_pending_notifications() {
+	new_test_ext().execute_with(|| {
+		for who in [1, 2, 3, 5, 6, 7] {
+			assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![1, 2, 3, 4]));
+		}
+		for contact in [2, 3, 5, 6, 7] {
+			make_mutual_contacts(1, contact);
+		}
+
+		assert_ok!(Messaging::force_reset_key(
+			RuntimeOrigin::root(),
+			1,
+			vec![8; 32],
+			KeyAlgorithm::X25519
+		));
+		assert!(crate::KeyResetCursors::<Test>::get(1).is_some());
+
+		// A second reset notifies every contact of the newest key from the start
+		System::reset_events();
+		assert_ok!(Messaging::force_reset_key(
+			RuntimeOrigin::root(),
+			1,
+			vec![9; 32],
+			KeyAlgorithm::X25519
+		));
+		assert_ok!(Messaging::continue_key_reset_notices(RuntimeOrigin::signed(4), 1));
+		let mut notified = key_reset_notices(1);
+		notified.sort();
+		assert_eq!(notified, vec![2, 3, 5, 6, 7]);
+		assert_eq!(crate::KeyVersions::<Test>::get(1), 2);
+	});
+}
+
+/// Action and target hash a call is expected to log, if any.
+type ExpectedAudit = Option<(AuditAction, H256)>;
+
+fn last_audit_entry() -> (AuditAction, H256) {
+	let entry = Messaging::audit_log().last().cloned().expect("log is not empty");
+	(entry.action, entry.target_hash)
+}
+
+#[test]
+fn every_privileged_call_is_audited() {
+	new_test_ext().execute_with(|| {
+		for who in [1, 2, 3] {
+			assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![1, 2, 3, 4]));
+		}
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		for byte in 1..=4 {
+			let hash = H256::repeat_byte(byte);
+			assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		}
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(2), 1, evidence_commitment()));
+		System::set_block_number(7);
+
+		let admin = RuntimeOrigin::signed(Admin::get());
+		let calls: Vec<(RuntimeCall, RuntimeOrigin, ExpectedAudit)> = vec![
+			(
+				RuntimeCall::Messaging(crate::Call::add_moderator { who: MODERATOR }),
+				admin.clone(),
+				Some((AuditAction::AddModerator, BlakeTwo256::hash_of(&MODERATOR))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::moderator_challenge { message_id: 0 }),
+				RuntimeOrigin::signed(MODERATOR),
+				Some((AuditAction::ModeratorChallenge, BlakeTwo256::hash_of(&0u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::fast_track_challenge {
+					message_id: 0,
+					upheld: false,
+				}),
+				RuntimeOrigin::signed(MODERATOR),
+				Some((AuditAction::FastTrackChallenge, BlakeTwo256::hash_of(&0u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::freeze_sender { who: 1, duration: 10 }),
+				RuntimeOrigin::signed(MODERATOR),
+				Some((AuditAction::FreezeSender, BlakeTwo256::hash_of(&1u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::unfreeze_sender { who: 1 }),
+				RuntimeOrigin::signed(MODERATOR),
+				Some((AuditAction::UnfreezeSender, BlakeTwo256::hash_of(&1u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::remove_moderator { who: MODERATOR }),
+				admin.clone(),
+				Some((AuditAction::RemoveModerator, BlakeTwo256::hash_of(&MODERATOR))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::resolve_challenge {
+					message_id: 1,
+					upheld: true,
+				}),
+				RuntimeOrigin::root(),
+				Some((AuditAction::ResolveChallenge, BlakeTwo256::hash_of(&1u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::appeal_challenge { message_id: 1 }),
+				RuntimeOrigin::signed(1),
+				// Appeals are not privileged and must not be logged
+				None,
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::resolve_appeal { message_id: 1, upheld: true }),
+				RuntimeOrigin::root(),
+				Some((AuditAction::ResolveAppeal, BlakeTwo256::hash_of(&1u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::pause { calls: PausedCalls::CONTACTS }),
+				admin.clone(),
+				Some((AuditAction::Pause, BlakeTwo256::hash_of(&PausedCalls::CONTACTS))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::unpause { calls: PausedCalls::CONTACTS }),
+				admin.clone(),
+				Some((AuditAction::Unpause, BlakeTwo256::hash_of(&PausedCalls::CONTACTS))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::update_parameters { new: custom_parameters() }),
+				admin.clone(),
+				Some((AuditAction::UpdateParameters, BlakeTwo256::hash_of(&custom_parameters()))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::force_remove_message {
+					message_id: 2,
+					order_reference: order_reference(),
+				}),
+				RuntimeOrigin::root(),
+				Some((AuditAction::ForceRemoveMessage, BlakeTwo256::hash_of(&2u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::force_reset_key {
+					who: 2,
+					new_public_key: vec![9; 32],
+					algorithm: KeyAlgorithm::X25519,
+				}),
+				RuntimeOrigin::root(),
+				Some((AuditAction::ForceResetKey, BlakeTwo256::hash_of(&2u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::ban_account {
+					who: 3,
+					reason_hash: H256::zero(),
+				}),
+				RuntimeOrigin::root(),
+				Some((AuditAction::BanAccount, BlakeTwo256::hash_of(&3u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::unban_account { who: 3 }),
+				RuntimeOrigin::root(),
+				Some((AuditAction::UnbanAccount, BlakeTwo256::hash_of(&3u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::reset_income {
+					category: IncomeCategory::Slash,
+				}),
+				admin.clone(),
+				Some((AuditAction::ResetIncome, BlakeTwo256::hash_of(&IncomeCategory::Slash))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::set_registration_mode {
+					mode: RegistrationMode::Whitelisted,
+				}),
+				admin.clone(),
+				Some((
+					AuditAction::SetRegistrationMode,
+					BlakeTwo256::hash_of(&RegistrationMode::Whitelisted),
+				)),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::whitelist_account { who: 5 }),
+				admin.clone(),
+				Some((AuditAction::WhitelistAccount, BlakeTwo256::hash_of(&5u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::remove_from_whitelist { who: 5 }),
+				admin,
+				Some((AuditAction::RemoveFromWhitelist, BlakeTwo256::hash_of(&5u64))),
+			),
+			(
+				RuntimeCall::Messaging(crate::Call::force_register_profile {
+					who: 5,
+					public_key: vec![1, 2, 3, 4],
+					algorithm: KeyAlgorithm::X25519,
+					sponsor: None,
+				}),
+				RuntimeOrigin::root(),
+				Some((AuditAction::ForceRegisterProfile, BlakeTwo256::hash_of(&5u64))),
+			),
+		];
+
+		let mut audited = Vec::new();
+		for (call, origin, expected) in calls {
+			let before = Messaging::audit_log().len();
+			assert_ok!(call.dispatch(origin));
+
+			let Some(expected) = expected else {
+				assert_eq!(Messaging::audit_log().len(), before);
+				continue;
+			};
+			assert_eq!(Messaging::audit_log().len(), before + 1);
+			assert_eq!(last_audit_entry(), expected);
+			assert_eq!(Messaging::audit_log().last().unwrap().block, 7);
+			audited.push(expected.0);
+		}
+
+		// Every audited action was exercised
+		audited.sort_by_key(|action| action.encode());
+		audited.dedup();
+		assert_eq!(audited.len(), 20);
+	});
+}
+
+#[test]
+fn failed_privileged_call_is_not_audited() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::unban_account(RuntimeOrigin::root(), 3),
+			Error::<Test>::AccountNotBanned
+		);
+		assert!(Messaging::audit_log().is_empty());
+	});
+}
+
+#[test]
+fn audit_log_evicts_oldest_entries() {
+	new_test_ext().execute_with(|| {
+		let admin = || RuntimeOrigin::signed(Admin::get());
+		assert_ok!(Messaging::pause(admin(), PausedCalls::SENDS));
+		for _ in 0..crate::MAX_AUDIT_LOG_ENTRIES {
+			assert_ok!(Messaging::unpause(admin(), PausedCalls::CONTACTS));
+		}
+
+		let log = Messaging::audit_log();
+		assert_eq!(log.len() as u32, crate::MAX_AUDIT_LOG_ENTRIES);
+		assert!(log.iter().all(|entry| entry.action == AuditAction::Unpause));
+	});
+}
+
+#[test]
+fn open_mode_allows_any_registration() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(crate::CurrentRegistrationMode::<Test>::get(), RegistrationMode::Open);
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+	});
+}
+
+#[test]
+fn whitelisted_mode_requires_whitelist() {
+	new_test_ext().execute_with(|| {
+		let admin = || RuntimeOrigin::signed(Admin::get());
+		assert_ok!(Messaging::set_registration_mode(admin(), RegistrationMode::Whitelisted));
+		System::assert_last_event(
+			Event::RegistrationModeSet { mode: RegistrationMode::Whitelisted }.into(),
+		);
+
+		assert_noop!(
+			Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]),
+			Error::<Test>::NotWhitelisted
+		);
+
+		assert_ok!(Messaging::whitelist_account(admin(), 1));
+		System::assert_last_event(Event::AccountWhitelisted { who: 1 }.into());
+		assert_noop!(Messaging::whitelist_account(admin(), 1), Error::<Test>::AlreadyWhitelisted);
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		assert_eq!(Balances::reserved_balance(1), 100);
+
+		assert_ok!(Messaging::whitelist_account(admin(), 2));
+		assert_ok!(Messaging::remove_from_whitelist(admin(), 2));
+		System::assert_last_event(Event::AccountRemovedFromWhitelist { who: 2 }.into());
+		assert_noop!(
+			Messaging::remove_from_whitelist(admin(), 2),
+			Error::<Test>::NotWhitelisted
+		);
+		assert_noop!(
+			Messaging::register_profile(RuntimeOrigin::signed(2), vec![1, 2, 3, 4]),
+			Error::<Test>::NotWhitelisted
+		);
+	});
+}
+
+#[test]
+fn registration_whitelist_requires_admin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::set_registration_mode(
+				RuntimeOrigin::signed(1),
+				RegistrationMode::Whitelisted
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::whitelist_account(RuntimeOrigin::signed(1), 1),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::remove_from_whitelist(RuntimeOrigin::signed(1), 1),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::force_register_profile(
+				RuntimeOrigin::signed(Admin::get()),
+				1,
+				vec![1, 2, 3, 4],
+				KeyAlgorithm::X25519,
+				None
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn existing_profiles_survive_whitelisted_mode() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::set_registration_mode(
+			RuntimeOrigin::signed(Admin::get()),
+			RegistrationMode::Whitelisted
+		));
+
+		assert_ok!(Messaging::update_profile(RuntimeOrigin::signed(1), vec![5, 6, 7, 8]));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+	});
+}
+
+#[test]
+fn registration_gate_blocks_rejected_accounts() {
+	new_test_ext().execute_with(|| {
+		BlockedRegistrants::set(vec![1]);
+
+		assert_noop!(
+			Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]),
+			Error::<Test>::RegistrationGateFailed
+		);
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![1, 2, 3, 4]));
+
+		// Root registration bypasses the gate
+		assert_ok!(Messaging::force_register_profile(
+			RuntimeOrigin::root(),
+			1,
+			vec![1, 2, 3, 4],
+			KeyAlgorithm::X25519,
+			None
+		));
+		assert!(crate::UserProfiles::<Test>::contains_key(1));
+	});
+}
+
+#[cfg(feature = "identity")]
+#[test]
+fn judgement_levels_only_count_vouching_judgements() {
+	use crate::identity::JudgementLevel;
+	use pallet_identity::Judgement;
+
+	assert_eq!(JudgementLevel::of(&Judgement::<u128>::Reasonable), Some(JudgementLevel::Reasonable));
+	assert_eq!(JudgementLevel::of(&Judgement::<u128>::KnownGood), Some(JudgementLevel::KnownGood));
+	assert_eq!(JudgementLevel::of(&Judgement::<u128>::FeePaid(10)), None);
+	assert_eq!(JudgementLevel::of(&Judgement::<u128>::Unknown), None);
+	assert_eq!(JudgementLevel::of(&Judgement::<u128>::OutOfDate), None);
+	assert_eq!(JudgementLevel::of(&Judgement::<u128>::LowQuality), None);
+	assert_eq!(JudgementLevel::of(&Judgement::<u128>::Erroneous), None);
+	assert!(JudgementLevel::KnownGood > JudgementLevel::Reasonable);
+}
+
+#[test]
+fn force_register_profile_without_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::set_registration_mode(
+			RuntimeOrigin::signed(Admin::get()),
+			RegistrationMode::Whitelisted
+		));
+
+		// Account 8 needs the existential deposit to keep its profile alive
+		let register = || {
+			Messaging::force_register_profile(
+				RuntimeOrigin::root(),
+				8,
+				vec![1, 2, 3, 4],
+				KeyAlgorithm::Ed25519,
+				None,
+			)
+		};
+		assert_noop!(register(), Error::<Test>::AccountNotFunded);
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(5), 8, 1));
+		assert_ok!(register());
+		System::assert_last_event(
+			Event::ProfileRegistered {
+				who: 8,
+				key_hash: Messaging::key_hash(&[1, 2, 3, 4]),
+				version: 0,
+			}
+			.into(),
+		);
+		assert!(crate::UserProfiles::<Test>::contains_key(8));
+		assert_eq!(crate::KeyAlgorithms::<Test>::get(8), KeyAlgorithm::Ed25519);
+		assert_eq!(crate::SpamBonds::<Test>::get(8).amount, 0);
+		assert_eq!(crate::UserProfiles::<Test>::get(8).unwrap().registration_bond, Some(0));
+		assert_eq!(Balances::free_balance(8) + Balances::reserved_balance(8), 1);
+
+		assert_noop!(
+			Messaging::force_register_profile(
+				RuntimeOrigin::root(),
+				8,
+				vec![1, 2, 3, 4],
+				KeyAlgorithm::X25519,
+				None
+			),
+			Error::<Test>::ProfileAlreadyExists
+		);
+	});
+}
+
+#[test]
+fn force_register_profile_with_sponsor_holds_bond_on_profile() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::force_register_profile(
+			RuntimeOrigin::root(),
+			8,
+			vec![1, 2, 3, 4],
+			KeyAlgorithm::X25519,
+			Some(5)
+		));
+
+		// The sponsor pays the bond plus the existential deposit of the new account
+		assert_eq!(Balances::free_balance(5), 899);
+		assert_eq!(Balances::reserved_balance(5), 0);
+		assert_eq!(Balances::free_balance(8), 1);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SpamBond.into(), &8), 100);
+		assert_eq!(crate::SpamBonds::<Test>::get(8).amount, 100);
+		assert_eq!(crate::UserProfiles::<Test>::get(8).unwrap().registration_bond, Some(100));
+
+		// A sponsor unable to cover the bond leaves no trace
+		assert_noop!(
+			Messaging::force_register_profile(
+				RuntimeOrigin::root(),
+				9,
+				vec![1, 2, 3, 4],
+				KeyAlgorithm::X25519,
+				Some(4)
+			),
+			Error::<Test>::InsufficientBond
+		);
+	});
+}
+
+/// Execute a view function through the runtime's view function dispatcher.
+fn view<V: ViewFunction + Encode>(query: V) -> V::ReturnType
+where
+	V::ReturnType: Decode,
+{
+	let output =
+		Test::execute_view_function(V::id(), query.encode()).expect("view function exists");
+	Decode::decode(&mut &output[..]).expect("output decodes")
+}
+
+#[test]
+fn contacts_of_view_paginates() {
+	new_test_ext().execute_with(|| {
+		let contacts = [2, 3, 5, 6, 7];
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		for contact in contacts {
+			assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(contact), vec![1]));
+			assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), contact));
+		}
+
+		let mut seen = Vec::new();
+		let mut after = None;
+		loop {
+			let page = view(ContactsOfViewFunction::<Test>::new(1, after, 2));
+			assert!(page.len() <= 2);
+			let Some(last) = page.last() else { break };
+			after = Some(*last);
+			seen.extend(page);
+		}
+		seen.sort();
+		assert_eq!(seen, contacts);
+
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 3));
+		let mut all = view(ContactsOfViewFunction::<Test>::new(1, None, u32::MAX));
+		all.sort();
+		assert_eq!(all, vec![2, 5, 6, 7]);
+		assert!(view(ContactsOfViewFunction::<Test>::new(2, None, 10)).is_empty());
+	});
+}
+
+#[test]
+fn inbox_of_view_lists_received_messages_in_order() {
+	new_test_ext().execute_with(|| {
+		for who in [1, 2, 3] {
+			assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![1, 2, 3, 4]));
+		}
+		for (who, contact) in [(2, 1), (3, 1), (2, 3)] {
+			approve(who, contact);
+		}
+		let hash = |n: u8| H256::from([n + 1; 32]);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash(0)));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 3, hash(1)));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(3), 2, hash(2)));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash(3)));
+
+		let summary = |sender, n| MessageSummary {
+			sender,
+			hash: hash(n),
+			sent_at: 1,
+			expired: false,
+			paid: false,
+		};
+		assert_eq!(
+			view(InboxOfViewFunction::<Test>::new(2, None, None, 10)),
+			vec![(0, summary(1, 0)), (2, summary(3, 2)), (3, summary(1, 3))]
+		);
+		assert_eq!(
+			view(InboxOfViewFunction::<Test>::new(2, None, Some(0), 1)),
+			vec![(2, summary(3, 2))]
+		);
+		assert!(view(InboxOfViewFunction::<Test>::new(2, None, Some(3), 10)).is_empty());
+
+		System::set_block_number(1002);
+		let inbox = view(InboxOfViewFunction::<Test>::new(3, None, None, 10));
+		assert_eq!(inbox, vec![(1, MessageSummary { expired: true, ..summary(1, 1) })]);
+	});
+}
+
+#[test]
+fn bond_status_view_reports_locked_bond() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let status = |locked, locked_until| BondStatus {
+			bonded: 100,
+			required: 100,
+			locked,
+			locked_until,
+			strikes: 0,
+		};
+		assert_eq!(view(BondStatusViewFunction::<Test>::new(1)), status(0, None));
+
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		assert_eq!(view(BondStatusViewFunction::<Test>::new(1)), status(100, Some(51)));
+
+		System::set_block_number(51);
+		assert_eq!(view(BondStatusViewFunction::<Test>::new(1)), status(0, None));
+	});
+}
+
+#[test]
+fn conversation_view_pages_through_both_directions() {
+	new_test_ext().execute_with(|| {
+		for who in [1, 2, 3] {
+			assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![1, 2, 3, 4]));
+		}
+		for (who, contact) in [(2, 1), (2, 3), (1, 2)] {
+			approve(who, contact);
+		}
+		let hash = |n: u8| H256::from([n; 32]);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash(1)));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(3), 2, hash(2)));
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, hash(3)));
+
+		let ids = |from, limit| -> Vec<u64> {
+			view(ConversationViewFunction::<Test>::new(1, 2, from, limit))
+				.into_iter()
+				.map(|(id, _)| id)
+				.collect()
+		};
+		assert_eq!(ids(0, 10), vec![0, 2]);
+		assert_eq!(ids(0, 1), vec![0]);
+		assert_eq!(ids(1, 10), vec![2]);
+	});
+}
+
+#[test]
+fn is_contact_view_is_directional() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+
+		assert!(view(IsContactViewFunction::<Test>::new(1, 2)));
+		assert!(!view(IsContactViewFunction::<Test>::new(2, 1)));
+	});
+}
+
+#[test]
+fn view_functions_are_in_metadata() {
+	let metadata = Messaging::pallet_view_functions_metadata();
+	let entries: Vec<_> = metadata.iter().map(|view| (view.name, view.id)).collect();
+	let id = |id: ViewFunctionId| <[u8; 32]>::from(id);
+	assert_eq!(
+		entries,
+		vec![
+			("contacts_of", id(ContactsOfViewFunction::<Test>::id())),
+			("has_profile", id(crate::HasProfileViewFunction::<Test>::id())),
+			("account_stats", id(crate::AccountStatsViewFunction::<Test>::id())),
+			("global_stats", id(crate::GlobalStatsViewFunction::<Test>::id())),
+			("profile_activity", id(crate::ProfileActivityViewFunction::<Test>::id())),
+			("can_message", id(crate::CanMessageViewFunction::<Test>::id())),
+			("incoming_requests", id(crate::IncomingRequestsViewFunction::<Test>::id())),
+			("outgoing_requests", id(crate::OutgoingRequestsViewFunction::<Test>::id())),
+			("inbox_of", id(InboxOfViewFunction::<Test>::id())),
+			("inbox_revision", id(crate::InboxRevisionViewFunction::<Test>::id())),
+			("conversation", id(ConversationViewFunction::<Test>::id())),
+			("conversation_page", id(crate::ConversationPageViewFunction::<Test>::id())),
+			("conversation_summary", id(crate::ConversationSummaryViewFunction::<Test>::id())),
+			("bond_status", id(BondStatusViewFunction::<Test>::id())),
+			("is_contact", id(IsContactViewFunction::<Test>::id())),
+			("message_info", id(crate::MessageInfoViewFunction::<Test>::id())),
+			("message_by_hash", id(crate::MessageByHashViewFunction::<Test>::id())),
+			("discoverable_groups", id(DiscoverableGroupsViewFunction::<Test>::id())),
+			("group_messages_after", id(GroupMessagesAfterViewFunction::<Test>::id())),
+			("messages_by_tag_prefix", id(crate::MessagesByTagPrefixViewFunction::<Test>::id())),
+			("challenge_evidence", id(crate::ChallengeEvidenceViewFunction::<Test>::id())),
+			("standing", id(crate::StandingViewFunction::<Test>::id())),
+			("verify_message_batch", id(crate::VerifyMessageBatchViewFunction::<Test>::id())),
+			("devices_revision", id(crate::DevicesRevisionViewFunction::<Test>::id())),
+		]
+	);
+}
+
+#[test]
+fn unknown_view_function_is_not_found() {
+	new_test_ext().execute_with(|| {
+		let prefix = IsContactViewFunction::<Test>::id().prefix;
+		let id = ViewFunctionId { prefix, suffix: [0; 16] };
+		assert!(matches!(
+			Test::execute_view_function(id, Vec::new()),
+			Err(ViewFunctionDispatchError::NotFound(_))
+		));
+	});
+}
+
+fn offchain_ext(pool: TestTransactionPoolExt) -> sp_io::TestExternalities {
+	let (offchain, _) = TestOffchainExt::new();
+	let mut ext = new_test_ext();
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	ext
+}
+
+/// Send message 0 from 1 to 2 at block 1; it expires after block 1001.
+fn send_expiring_message() {
+	setup_approved_pair();
+	assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::repeat_byte(1)));
+}
+
+fn decode_calls(transactions: &[Vec<u8>]) -> Vec<RuntimeCall> {
+	transactions
+		.iter()
+		.map(|tx| Extrinsic::decode(&mut &tx[..]).unwrap().function)
+		.collect()
+}
+
+#[test]
+fn offchain_worker_waits_for_expiry() {
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	offchain_ext(pool).execute_with(|| {
+		send_expiring_message();
+
+		Messaging::offchain_worker(1001);
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn offchain_worker_submits_one_prune_per_interval() {
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	offchain_ext(pool).execute_with(|| {
+		send_expiring_message();
+
+		Messaging::offchain_worker(1002);
+		assert_eq!(
+			decode_calls(&pool_state.read().transactions),
+			vec![RuntimeCall::Messaging(crate::Call::prune_expired_unsigned { up_to_block: 1002 })]
+		);
+
+		// Still pending: later blocks within the interval submit nothing
+		Messaging::offchain_worker(1006);
+		assert_eq!(pool_state.read().transactions.len(), 1);
+
+		Messaging::offchain_worker(1007);
+		assert_eq!(pool_state.read().transactions.len(), 2);
+	});
+}
+
+#[test]
+fn prune_expired_unsigned_removes_expired_messages() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 0));
+		System::set_block_number(500);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::repeat_byte(2)));
+		System::set_block_number(1002);
+
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert!(!crate::MessageHashes::<Test>::contains_key(0));
+		assert!(!crate::ReportedMessages::<Test>::get(0, 2));
+		// Message 1 has not expired yet
+		assert!(crate::MessageHashes::<Test>::contains_key(1));
+		assert_eq!(crate::PruneCursor::<Test>::get(), 1);
+		System::assert_last_event(Event::ExpiredMessagesPruned { count: 1, next: 1 }.into());
+
+		assert_noop!(
+			Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002),
+			Error::<Test>::NothingToPrune
+		);
+	});
+}
+
+#[test]
+fn prune_expired_unsigned_is_bounded() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		for i in 1..=5 {
+			assert_ok!(Messaging::send_message_hash(
+				RuntimeOrigin::signed(1),
+				2,
+				H256::repeat_byte(i)
+			));
+			System::set_block_number(System::block_number() + RateLimitWindow::get());
+		}
+		System::set_block_number(2000);
+
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 2000));
+		assert_eq!(crate::PruneCursor::<Test>::get(), MaxPrunesPerCall::get() as u64);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 2000));
+		assert_eq!(crate::PruneCursor::<Test>::get(), 5);
+	});
+}
+
+#[test]
+fn prune_expired_unsigned_rejects_signed_origins() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		System::set_block_number(1002);
+
+		assert_noop!(
+			Messaging::prune_expired_unsigned(RuntimeOrigin::signed(1), 1002),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn validate_unsigned_checks_prune_calls() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		System::set_block_number(1002);
+		let validate = |up_to_block| {
+			Messaging::validate_unsigned(
+				TransactionSource::External,
+				&crate::Call::prune_expired_unsigned { up_to_block },
+			)
+		};
+
+		assert_eq!(validate(1003), InvalidTransaction::Future.into());
+		assert_eq!(validate(1001), InvalidTransaction::Stale.into());
+		let valid = validate(1002).unwrap();
+		assert_eq!(valid.provides, vec![("MessagingPrune", 0u64).encode()]);
+		assert_eq!(valid.longevity, PruneInterval::get());
+
+		let other = crate::Call::approve_contact { contact: 2 };
+		assert_eq!(
+			Messaging::validate_unsigned(TransactionSource::External, &other),
+			InvalidTransaction::Call.into()
+		);
+	});
+}
+
+fn index_entry(acknowledged: bool) -> Option<Vec<u8>> {
+	Some(IndexEntry { sender: 1u64, hash: H256::from([1; 32]), sent_at: 1u64, acknowledged }.encode())
+}
+
+#[test]
+fn offchain_index_tracks_sent_and_acknowledged_messages() {
+	let mut ext = new_test_ext();
+	let key = inbox_key(&2u64, 0);
+
+	ext.execute_with(send_paid_message);
+	ext.persist_offchain_overlay();
+	assert_eq!(ext.offchain_db().get(&key), index_entry(false));
+
+	ext.execute_with(|| {
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+	});
+	ext.persist_offchain_overlay();
+	assert_eq!(ext.offchain_db().get(&key), index_entry(true));
+
+	// Offchain workers read the entry back from the same database
+	let (offchain, _) = TestOffchainExt::with_offchain_db(ext.offchain_db());
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext.execute_with(|| {
+		assert_eq!(inbox_entry::<Test>(&2, 0).map(|entry| entry.encode()), index_entry(true));
+		assert_eq!(inbox_entry::<Test>(&2, 1), None);
+	});
+}
+
+#[test]
+fn offchain_index_clears_removed_messages() {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		send_paid_message();
+		approve(2, 1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([2; 32])));
+	});
+	ext.persist_offchain_overlay();
+	assert!(ext.offchain_db().get(&inbox_key(&2u64, 1)).is_some());
+
+	ext.execute_with(|| {
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+		System::set_block_number(1002);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+	});
+	ext.persist_offchain_overlay();
+	assert_eq!(ext.offchain_db().get(&inbox_key(&2u64, 0)), None);
+	assert_eq!(ext.offchain_db().get(&inbox_key(&2u64, 1)), None);
+}
+
+#[test]
+fn offchain_index_is_skipped_when_disabled() {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		OffchainIndexing::set(false);
+		send_paid_message();
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+	});
+	ext.persist_offchain_overlay();
+	assert_eq!(ext.offchain_db().get(&inbox_key(&2u64, 0)), None);
+}
+
+#[test]
+fn inbox_keys_group_by_recipient_in_id_order() {
+	let key = inbox_key(&2u64, 258);
+	assert!(key.starts_with(INBOX_PREFIX));
+	assert_eq!(key[INBOX_PREFIX.len()..], [2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+	assert!(inbox_key(&2u64, 255) < key);
+}
+
+#[test]
+fn request_remote_profile_fails_without_route() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_noop!(
+			Messaging::request_remote_profile(
+				RuntimeOrigin::signed(1),
+				Box::new(Location::parent().into()),
+				2
+			),
+			Error::<Test>::XcmSendFailed
+		);
+	});
+}
+
+#[test]
+fn profile_responses_require_an_xcm_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::receive_profile(RuntimeOrigin::signed(1), 0, VersionedRemoteProfile::V1(None)),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::respond_profile(RuntimeOrigin::signed(1), 0, 2, REMOTE_PROFILE_VERSION),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn remote_profile_lookup_caches_profile() {
+	MockNet::reset();
+	ParaB::execute_with(|| {
+		assert_ok!(parachain::Messaging::register_profile(
+			parachain::RuntimeOrigin::signed(BOB),
+			vec![7u8; 32]
+		));
+	});
+	ParaA::execute_with(|| {
+		assert_ok!(parachain::Messaging::request_remote_profile(
+			parachain::RuntimeOrigin::signed(ALICE),
+			Box::new(sibling(2).into()),
+			BOB
+		));
+	});
+
+	ParaB::execute_with(|| {
+		parachain::System::assert_has_event(
+			Event::ProfileLookupAnswered { query_id: 0, requester: sibling(1), who: BOB, found: true }
+				.into(),
+		);
+	});
+	ParaA::execute_with(|| {
+		parachain::System::assert_has_event(
+			Event::RemoteProfileFetched { query_id: 0, dest: sibling(2), who: BOB }.into(),
+		);
+		assert!(crate::ProfileQueries::<parachain::Runtime>::get(0).is_none());
+
+		let profile = parachain::Messaging::remote_profile(&sibling(2), &BOB).unwrap();
+		assert_eq!(profile.public_key.into_inner(), vec![7u8; 32]);
+		assert_eq!(profile.algorithm, KeyAlgorithm::X25519);
+
+		// Cached profiles go stale after `RemoteProfileTtl` blocks
+		parachain::System::set_block_number(1 + parachain::RemoteProfileTtl::get() + 1);
+		assert_eq!(parachain::Messaging::remote_profile(&sibling(2), &BOB), None);
+	});
+}
+
+#[test]
+fn remote_profile_lookup_reports_missing_profile() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| {
+		assert_ok!(parachain::Messaging::request_remote_profile(
+			parachain::RuntimeOrigin::signed(ALICE),
+			Box::new(sibling(2).into()),
+			CHARLIE
+		));
+	});
+	ParaB::execute_with(|| {
+		parachain::System::assert_has_event(
+			Event::ProfileLookupAnswered {
+				query_id: 0,
+				requester: sibling(1),
+				who: CHARLIE,
+				found: false,
+			}
+			.into(),
+		);
+	});
+	ParaA::execute_with(|| {
+		parachain::System::assert_has_event(
+			Event::RemoteProfileNotFound { query_id: 0, dest: sibling(2), who: CHARLIE }.into(),
+		);
+		assert!(crate::ProfileQueries::<parachain::Runtime>::get(0).is_none());
+		assert!(!crate::RemoteProfiles::<parachain::Runtime>::contains_key(sibling(2), CHARLIE));
+	});
+}
+
+#[test]
+fn expired_remote_profile_lookup_ignores_late_answer() {
+	MockNet::reset();
+	ParaB::execute_with(|| {
+		assert_ok!(parachain::Messaging::register_profile(
+			parachain::RuntimeOrigin::signed(BOB),
+			vec![7u8; 32]
+		));
+	});
+
+	// The lookup times out on A before B gets to answer it
+	ParaA::execute_without_dispatch(|| {
+		assert_ok!(parachain::Messaging::request_remote_profile(
+			parachain::RuntimeOrigin::signed(ALICE),
+			Box::new(sibling(2).into()),
+			BOB
+		));
+
+		assert_noop!(
+			parachain::Messaging::expire_profile_query(parachain::RuntimeOrigin::signed(ALICE), 0),
+			Error::<parachain::Runtime>::ProfileQueryNotExpired
+		);
+		parachain::System::set_block_number(1 + parachain::ProfileQueryTimeout::get() + 1);
+		assert_ok!(parachain::Messaging::expire_profile_query(
+			parachain::RuntimeOrigin::signed(CHARLIE),
+			0
+		));
+		parachain::System::assert_last_event(
+			Event::ProfileQueryTimedOut { query_id: 0, dest: sibling(2), who: BOB }.into(),
+		);
+	});
+	ParaB::execute_with(|| {});
+	ParaA::execute_with(|| {
+		assert!(!crate::RemoteProfiles::<parachain::Runtime>::contains_key(sibling(2), BOB));
+	});
+}
+
+#[test]
+fn respond_profile_negotiates_version() {
+	MockNet::reset();
+
+	ParaB::execute_with(|| {
+		let para_a = || parachain::RuntimeOrigin::from(pallet_xcm::Origin::Xcm(sibling(1)));
+		assert_noop!(
+			parachain::Messaging::respond_profile(para_a(), 0, BOB, 0),
+			Error::<parachain::Runtime>::UnsupportedProfileVersion
+		);
+
+		// Newer requesters are answered with the newest version both sides support
+		assert_ok!(parachain::Messaging::respond_profile(para_a(), 0, BOB, REMOTE_PROFILE_VERSION + 1));
+	});
+	ParaA::execute_with(|| {
+		// The answer is delivered, but no lookup with this ID is pending on A
+		assert!(!crate::RemoteProfiles::<parachain::Runtime>::contains_key(sibling(2), BOB));
+	});
+}
+
+#[test]
+fn accept_and_send_approves_and_sends() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		let message_hash = H256::from([1; 32]);
+
+		assert_ok!(Messaging::accept_and_send(RuntimeOrigin::signed(1), 2, message_hash));
+
+		assert!(Messaging::are_mutual_contacts(&1, &2));
+		assert_eq!(crate::ContactCount::<Test>::get(1), 1);
+		assert_eq!(
+			crate::MessageHashes::<Test>::get(0),
+			Some(MessageRecord { sender: 1, recipient: 2, hash: message_hash, sent_at: 1 })
+		);
+		System::assert_has_event(Event::ContactApproved { approver: 1, contact: 2 }.into());
+		System::assert_last_event(
+			Event::MessageSent {
+				message_id: 0,
+				from: 1,
+				to: 2,
+				hash: message_hash,
+				expires_at: 1001,
+				sequence: 0,
+				conversation_id: Messaging::conversation_id(&1, &2),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn accept_and_send_requires_contact_request() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+
+		assert_noop!(
+			Messaging::accept_and_send(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::NoContactRequest
+		);
+		assert_noop!(
+			Messaging::accept_and_send(RuntimeOrigin::signed(1), 1, H256::from([1; 32])),
+			Error::<Test>::CannotAddSelf
+		);
+	});
+}
+
+#[test]
+fn accept_and_send_rolls_back_approval_if_send_fails() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		add_moderator();
+		assert_ok!(Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10));
+
+		assert_noop!(
+			Messaging::accept_and_send(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::SenderFrozen
+		);
+		assert!(!crate::ApprovedContacts::<Test>::get(1, 2));
+	});
+}
+
+#[test]
+fn accept_and_send_respects_pauses() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::SENDS));
+
+		assert_noop!(
+			Messaging::accept_and_send(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::PalletPaused
+		);
+	});
+}
+
+#[test]
+fn register_and_approve_registers_and_approves() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![1, 2, 3, 4]));
+
+		assert_ok!(Messaging::register_and_approve(
+			RuntimeOrigin::signed(1),
+			vec![5, 6, 7, 8],
+			KeyAlgorithm::Ed25519,
+			2
+		));
+
+		assert!(crate::UserProfiles::<Test>::contains_key(1));
+		assert_eq!(crate::KeyAlgorithms::<Test>::get(1), KeyAlgorithm::Ed25519);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 100);
+		assert!(crate::ApprovedContacts::<Test>::get(1, 2));
+		System::assert_has_event(
+			Event::ProfileRegistered {
+				who: 1,
+				key_hash: Messaging::key_hash(&[5, 6, 7, 8]),
+				version: 0,
+			}
+			.into(),
+		);
+		System::assert_last_event(Event::ContactApproved { approver: 1, contact: 2 }.into());
+	});
+}
+
+#[test]
+fn register_and_approve_checks_contact_first() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::register_and_approve(
+				RuntimeOrigin::signed(1),
+				vec![1, 2, 3, 4],
+				KeyAlgorithm::X25519,
+				2
+			),
+			Error::<Test>::RecipientNotFound
+		);
+		assert_noop!(
+			Messaging::register_and_approve(
+				RuntimeOrigin::signed(1),
+				vec![1, 2, 3, 4],
+				KeyAlgorithm::X25519,
+				1
+			),
+			Error::<Test>::CannotAddSelf
+		);
+	});
+}
+
+#[test]
+fn register_and_approve_rolls_back_registration_if_approval_fails() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![1, 2, 3, 4]));
+		crate::Parameters::<Test>::put(PalletParameters {
+			max_contacts: 0,
+			..Messaging::parameters()
+		});
+
+		assert_noop!(
+			Messaging::register_and_approve(
+				RuntimeOrigin::signed(1),
+				vec![1, 2, 3, 4],
+				KeyAlgorithm::X25519,
+				2
+			),
+			Error::<Test>::MaxContactsReached
+		);
+		assert!(!crate::UserProfiles::<Test>::contains_key(1));
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn message_sent_numbers_messages_per_conversation() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		make_mutual_contacts(1, 2);
+		approve(1, 3);
+		let conversation_id = Messaging::conversation_id(&1, &2);
+		assert_eq!(conversation_id, Messaging::conversation_id(&2, &1));
+		assert_ne!(conversation_id, Messaging::conversation_id(&1, &3));
+
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])));
+		System::set_block_number(5);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, H256::from([2; 32])));
+		System::assert_last_event(
+			Event::MessageSent {
+				message_id: 1,
+				from: 2,
+				to: 1,
+				hash: H256::from([2; 32]),
+				expires_at: 1005,
+				sequence: 1,
+				conversation_id,
+			}
+			.into(),
+		);
+
+		// Other conversations are numbered on their own
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(3), 1, H256::from([3; 32])));
+		System::assert_last_event(
+			Event::MessageSent {
+				message_id: 2,
+				from: 3,
+				to: 1,
+				hash: H256::from([3; 32]),
+				expires_at: 1005,
+				sequence: 0,
+				conversation_id: Messaging::conversation_id(&1, &3),
+			}
+			.into(),
+		);
+		assert_eq!(crate::ConversationStats::<Test>::get(conversation_id).next_seq, 2);
+	});
+}
+
+#[test]
+fn v1_migration_bumps_storage_version() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<Messaging>();
+
+		crate::migrations::v1::MigrateV0ToV1::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(1));
+	});
+}
+
+/// Store a message in the pre-v2 `(hash, sent_at, sender, recipient)` layout, encoded by
+/// hand so the test does not depend on the pallet's types.
+fn insert_v1_message(message_id: u64, hash: H256, sent_at: u64, sender: u64, recipient: u64) {
+	let mut raw = hash.as_bytes().to_vec();
+	raw.extend_from_slice(&sent_at.to_le_bytes());
+	raw.extend_from_slice(&sender.to_le_bytes());
+	raw.extend_from_slice(&recipient.to_le_bytes());
+	frame_support::storage::unhashed::put_raw(
+		&crate::MessageHashes::<Test>::hashed_key_for(message_id),
+		&raw,
+	);
+}
+
+#[test]
+fn v2_migration_converts_messages_to_records() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(1).put::<Messaging>();
+		insert_v1_message(0, H256::from([1; 32]), 3, 1, 2);
+		insert_v1_message(5, H256::from([2; 32]), 7, 2, 1);
+
+		let weight = crate::migrations::v2::MigrateV1ToV2::<Test>::on_runtime_upgrade();
+
+		assert_eq!(
+			crate::MessageHashes::<Test>::get(0),
+			Some(MessageRecord { sender: 1, recipient: 2, hash: H256::from([1; 32]), sent_at: 3 })
+		);
+		assert_eq!(
+			crate::MessageHashes::<Test>::get(5),
+			Some(MessageRecord { sender: 2, recipient: 1, hash: H256::from([2; 32]), sent_at: 7 })
+		);
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(2));
+		assert!(
+			weight.all_gte(<Test as frame_system::Config>::DbWeight::get().reads_writes(2, 2))
+		);
+
+		// Already at version 2, so a second run leaves the records alone
+		crate::migrations::v2::MigrateV1ToV2::<Test>::on_runtime_upgrade();
+		assert_eq!(crate::MessageHashes::<Test>::iter().count(), 2);
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn v2_migration_passes_try_runtime_checks() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(1).put::<Messaging>();
+		for message_id in 0..20 {
+			insert_v1_message(message_id, H256::from([message_id as u8; 32]), message_id, 1, 2);
+		}
+
+		assert_ok!(crate::migrations::v2::MigrateV1ToV2::<Test>::try_on_runtime_upgrade(true));
+		assert_eq!(crate::MessageHashes::<Test>::iter().count(), 20);
+	});
+}
+
+/// Build a state with `build_state`, run every pallet migration over it and check the
+/// chain ends up at the in-code storage version. With `try-runtime`, the migrations' and
+/// the pallet's pre/post upgrade checks run as well. The migrations are then run a second
+/// time, which must leave storage untouched.
+fn run_all_migrations_on(build_state: impl FnOnce()) {
+	new_test_ext().execute_with(|| {
+		build_state();
+
+		#[cfg(feature = "try-runtime")]
+		{
+			assert_ok!(crate::migrations::Migrations::<Test>::try_on_runtime_upgrade(true));
+			assert_ok!(<Messaging as OnRuntimeUpgrade>::try_on_runtime_upgrade(true));
+		}
+		#[cfg(not(feature = "try-runtime"))]
+		crate::migrations::Migrations::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Messaging::on_chain_storage_version(), Messaging::in_code_storage_version());
+
+		let root = sp_io::storage::root(sp_runtime::StateVersion::V1);
+		crate::migrations::Migrations::<Test>::on_runtime_upgrade();
+		assert_eq!(sp_io::storage::root(sp_runtime::StateVersion::V1), root);
+	});
+}
+
+#[test]
+fn migrations_are_a_noop_on_fresh_genesis() {
+	run_all_migrations_on(|| {
+		<Messaging as frame_support::traits::OnGenesis>::on_genesis();
+		setup_approved_pair();
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+	});
+}
+
+#[test]
+fn migrations_upgrade_v0_state() {
+	run_all_migrations_on(|| {
+		StorageVersion::new(0).put::<Messaging>();
+		insert_v1_message(0, H256::from([1; 32]), 3, 1, 2);
+		insert_v1_message(1, H256::from([2; 32]), 4, 2, 1);
+	});
+}
+
+/// Run `call` from `who` through pool validation of `CheckRegisteredSender`.
+fn validate_send(who: u64, call: &RuntimeCall) -> Result<(), TransactionValidityError> {
+	let info = call.get_dispatch_info();
+	CheckRegisteredSender::<Test>::new()
+		.validate_only(
+			RuntimeOrigin::signed(who),
+			call,
+			&info,
+			call.encoded_size(),
+			TransactionSource::External,
+			0,
+		)
+		.map(|_| ())
+}
+
+/// Run `call` from `who` through block inclusion of `CheckRegisteredSender`.
+fn prepare_send(who: u64, call: &RuntimeCall) -> Result<(), TransactionValidityError> {
+	let info = call.get_dispatch_info();
+	CheckRegisteredSender::<Test>::new()
+		.validate_and_prepare(RuntimeOrigin::signed(who), call, &info, call.encoded_size(), 0)
+		.map(|_| ())
+}
+
+#[test]
+fn check_registered_sender_rejects_unregistered_accounts() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![1, 2, 3, 4]));
+
+		assert_eq!(validate_send(1, &send_call(2)), Err(InvalidSend::UnregisteredSender.into()));
+		assert_eq!(validate_send(2, &send_call(3)), Err(InvalidSend::UnknownRecipient.into()));
+		assert_eq!(
+			validate_send(
+				1,
+				&RuntimeCall::Messaging(crate::Call::accept_and_send {
+					requester: 2,
+					message_hash: H256::from([1; 32]),
+				})
+			),
+			Err(InvalidSend::UnregisteredSender.into())
+		);
+		assert_eq!(
+			validate_send(
+				2,
+				&RuntimeCall::Messaging(crate::Call::send_paid_message_hash {
+					recipient: 3,
+					message_hash: H256::from([1; 32]),
+				})
+			),
+			Err(InvalidSend::UnknownRecipient.into())
+		);
+		assert_eq!(
+			TransactionValidityError::from(InvalidSend::UnregisteredSender),
+			TransactionValidityError::Invalid(InvalidTransaction::Custom(1))
+		);
+
+		// Other calls pass through untouched
+		assert_ok!(validate_send(
+			1,
+			&RuntimeCall::Messaging(crate::Call::register_profile { public_key: vec![1, 2, 3, 4] })
+		));
+	});
+}
+
+#[test]
+fn check_registered_sender_admits_valid_sends() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+
+		assert_ok!(validate_send(1, &send_call(2)));
+		assert_ok!(prepare_send(1, &send_call(2)));
+		assert_eq!(
+			CheckRegisteredSender::<Test>::new().weight(&send_call(2)),
+			<Test as frame_system::Config>::DbWeight::get().reads(3)
+		);
+	});
+}
+
+#[test]
+fn check_registered_sender_rejects_paused_sends() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::SENDS));
+
+		assert_eq!(validate_send(1, &send_call(2)), Err(InvalidSend::SendsPaused.into()));
+	});
+}
+
+#[test]
+fn check_registered_sender_revalidates_on_inclusion() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let call = send_call(2);
+		assert_ok!(validate_send(1, &call));
+
+		// The recipient is banned while the transaction waits in the pool
+		ban(2);
+
+		assert_eq!(prepare_send(1, &call), Err(InvalidSend::UnknownRecipient.into()));
+	});
+}
+
+#[test]
+fn substrate_weights_account_for_proof_size() {
+	use crate::weights::{SubstrateWeight, WeightInfo};
+	type W = SubstrateWeight<Test>;
+
+	for weight in [
+		W::register_profile(256),
+		W::send_message_hash(),
+		W::approve_contact(),
+		W::acknowledge_message(),
+		W::prune_expired_unsigned(1),
+		W::accept_and_send(),
+		W::register_and_approve(256),
+	] {
+		assert!(weight.proof_size() > 0);
+	}
+
+	// Longer keys cost more, and composites cost more than their parts alone
+	assert!(W::register_profile(256).ref_time() > W::register_profile(1).ref_time());
+	assert!(W::accept_and_send().proof_size() >= W::send_message_hash().proof_size());
+	assert!(
+		W::prune_expired_unsigned(3).proof_size() > W::prune_expired_unsigned(1).proof_size()
+	);
+}
+
+#[test]
+fn profile_accessor_returns_registered_key() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Messaging::profile(&1), None);
+
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		assert_eq!(Messaging::profile(&1).map(|key| key.into_inner()), Some(vec![1, 2, 3, 4]));
+	});
+}
+
+#[test]
+fn bond_accessor_is_none_without_a_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Messaging::bond(&1), None);
+
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		assert!(Messaging::bond(&1).is_some_and(|bond| bond > 0));
+	});
+}
+
+#[test]
+fn message_accessor_returns_stored_record() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_eq!(Messaging::message(0), None);
+
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([3; 32])));
+		assert_eq!(
+			Messaging::message(0),
+			Some(MessageRecord { sender: 1, recipient: 2, hash: H256::from([3; 32]), sent_at: 1 })
+		);
+	});
+}
+
+#[test]
+fn is_contact_accessor_follows_approvals() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert!(!Messaging::is_contact(1, 2));
+
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert!(Messaging::is_contact(1, 2));
+
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 2));
+		assert!(!Messaging::is_contact(1, 2));
+	});
+}
+
+#[test]
+fn genesis_contacts_grant_inbox_access() {
+	ExtBuilder::default()
+		.with_profile(1, vec![1; 32])
+		.with_profile(2, vec![2; 32])
+		.with_profile(3, vec![3; 32])
+		.with_contact(1, 2)
+		.with_next_message_id(100)
+		.build()
+		.execute_with(|| {
+			assert!(Messaging::are_mutual_contacts(&1, &2));
+			assert_eq!(crate::ContactCount::<Test>::get(1), 1);
+			assert_eq!(crate::ContactCount::<Test>::get(2), 1);
+			assert_eq!(Messaging::bond(&3), Some(Messaging::parameters().spam_bond));
+
+			// Account 2 keeps its inbox closed, so only its seeded contact gets through
+			assert_noop!(
+				Messaging::send_paid_message_hash(RuntimeOrigin::signed(3), 2, H256::from([1; 32])),
+				Error::<Test>::InboxClosed
+			);
+			assert_ok!(Messaging::send_paid_message_hash(
+				RuntimeOrigin::signed(1),
+				2,
+				H256::from([1; 32])
+			));
+			assert_eq!(Messaging::message(100).map(|record| record.sender), Some(1));
+			assert_eq!(crate::NextMessageId::<Test>::get(), 101);
+		});
+}
+
+#[test]
+#[should_panic(expected = "genesis contact 3 has no genesis profile")]
+fn genesis_contacts_require_profiles() {
+	ExtBuilder::default().with_profile(1, vec![1; 32]).with_contact(1, 3).build();
+}
+
+#[test]
+#[should_panic(expected = "genesis account 4 cannot cover its spam bond")]
+fn genesis_profiles_require_endowment_for_bond() {
+	ExtBuilder::default().with_profile(4, vec![4; 32]).build();
+}
+
+#[test]
+fn profile_events_commit_to_the_stored_key() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1; 256]));
+		assert_ok!(Messaging::update_profile(RuntimeOrigin::signed(1), vec![2; 64]));
+
+		// A client fetches the current key and checks it against the latest event
+		let stored = Messaging::profile(&1).expect("profile is registered");
+		System::assert_last_event(
+			Event::ProfileUpdated {
+				who: 1,
+				key_hash: Messaging::key_hash(&stored),
+				version: crate::KeyVersions::<Test>::get(1),
+			}
+			.into(),
+		);
+		assert_eq!(Messaging::key_hash(&stored), BlakeTwo256::hash(&[2; 64]));
+		assert_ne!(Messaging::key_hash(&stored), Messaging::key_hash(&[1; 256]));
+	});
+}
+
+#[test]
+fn v3_migration_only_bumps_storage_version() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(2).put::<Messaging>();
+		setup_pair();
+
+		crate::migrations::v3::MigrateV2ToV3::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(3));
+		assert!(Messaging::profile(&1).is_some());
+	});
+}
+
+#[test]
+fn v4_migration_rewrites_bonds_as_unsponsored() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(3).put::<Messaging>();
+		crate::migrations::v4::SpamBonds::<Test>::insert(1, 100);
+		crate::migrations::v4::SpamBonds::<Test>::insert(2, 250);
+
+		// The old layout is readable before the migration runs
+		assert_eq!(
+			crate::SpamBonds::<Test>::get(1),
+			crate::BondRecord { amount: 100, sponsor: None }
+		);
+
+		crate::migrations::v4::MigrateV3ToV4::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(4));
+		assert_eq!(
+			crate::SpamBonds::<Test>::get(1),
+			crate::BondRecord { amount: 100, sponsor: None }
+		);
+		assert_eq!(
+			crate::SpamBonds::<Test>::get(2),
+			crate::BondRecord { amount: 250, sponsor: None }
+		);
+	});
+}
+
+#[test]
+fn lazy_v4_migration_rewrites_bonds_across_blocks() {
+	use crate::{migrations::v4::LazyMigrateV3ToV4, weights::WeightInfo};
+	use frame_support::{
+		migrations::{SteppedMigration, SteppedMigrationError},
+		weights::WeightMeter,
+	};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(3).put::<Messaging>();
+		for who in 10..30 {
+			crate::migrations::v4::SpamBonds::<Test>::insert(who, who as u128);
+		}
+
+		let per_bond = <Test as crate::Config>::WeightInfo::migrate_spam_bond();
+		assert_eq!(
+			LazyMigrateV3ToV4::<Test>::step(None, &mut WeightMeter::with_limit(per_bond / 2)),
+			Err(SteppedMigrationError::InsufficientWeight { required: per_bond })
+		);
+
+		let mut cursor = None;
+		let mut blocks = 0;
+		loop {
+			blocks += 1;
+			let mut meter = WeightMeter::with_limit(per_bond.saturating_mul(7));
+			cursor = LazyMigrateV3ToV4::<Test>::step(cursor, &mut meter).expect("step fits");
+			if cursor.is_none() {
+				break;
+			}
+			// Half-migrated: old and new layouts are both readable.
+			assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(3));
+			for who in 10..30 {
+				assert_eq!(crate::SpamBonds::<Test>::get(who).amount, who as u128);
+			}
+		}
+
+		assert_eq!(blocks, 3);
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(4));
+		for who in 10..30 {
+			assert_eq!(
+				crate::SpamBonds::<Test>::get(who),
+				crate::BondRecord { amount: who as u128, sponsor: None }
+			);
+		}
+	});
+}
+
+#[test]
+fn lazy_v2_migration_rewrites_messages_across_blocks() {
+	use crate::{migrations::v2::LazyMigrateV1ToV2, weights::WeightInfo};
+	use frame_support::{
+		migrations::{SteppedMigration, SteppedMigrationError},
+		weights::WeightMeter,
+	};
+
+	let expected = |message_id: u64| MessageRecord {
+		sender: 1 + message_id % 3,
+		recipient: 2,
+		hash: H256::from([message_id as u8; 32]),
+		sent_at: message_id,
+	};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(1).put::<Messaging>();
+		for message_id in 0..50 {
+			let record = expected(message_id);
+			insert_v1_message(message_id, record.hash, record.sent_at, record.sender, 2);
+		}
+
+		let per_record = <Test as crate::Config>::WeightInfo::migrate_message_record();
+		assert_eq!(
+			LazyMigrateV1ToV2::<Test>::step(None, &mut WeightMeter::with_limit(per_record / 2)),
+			Err(SteppedMigrationError::InsufficientWeight { required: per_record })
+		);
+
+		let mut cursor = None;
+		let mut blocks = 0;
+		loop {
+			blocks += 1;
+			let mut meter = WeightMeter::with_limit(per_record.saturating_mul(7));
+			cursor = LazyMigrateV1ToV2::<Test>::step(cursor, &mut meter).expect("step fits");
+			if cursor.is_none() {
+				break;
+			}
+			// Half-migrated: old and new layouts are both readable.
+			assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(1));
+			for message_id in 0..50 {
+				assert_eq!(Messaging::message(message_id), Some(expected(message_id)));
+			}
+		}
+
+		assert_eq!(blocks, 8);
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(2));
+		assert_eq!(crate::MessageMigrationCursor::<Test>::get(), None);
+		assert_eq!(crate::MessageHashes::<Test>::iter().count(), 50);
+		for message_id in 0..50 {
+			assert_eq!(crate::MessageHashes::<Test>::get(message_id), Some(expected(message_id)));
+		}
+		let progress: Vec<(u32, u32)> = System::events()
+			.into_iter()
+			.filter_map(|record| match record.event {
+				RuntimeEvent::Messaging(Event::MessageMigrationAdvanced {
+					migrated, total, ..
+				}) => Some((migrated, total)),
+				_ => None,
+			})
+			.collect();
+		assert_eq!(progress.iter().map(|(migrated, _)| migrated).sum::<u32>(), 50);
+		// Each step reports the running total, which ends up as the message count
+		let mut total = 0;
+		for (migrated, reported) in progress {
+			total += migrated;
+			assert_eq!(reported, total);
+		}
+		assert_eq!(Messaging::message_count(), 50);
+		System::assert_last_event(Event::MessageMigrationCompleted.into());
+	});
+}
+
+#[test]
+fn v5_migration_takes_profile_references() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		// Profiles registered before version 5 hold no reference
+		StorageVersion::new(4).put::<Messaging>();
+		for who in [1, 2] {
+			System::dec_consumers(&who);
+		}
+
+		crate::migrations::v5::MigrateV4ToV5::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(5));
+		assert_eq!(System::sufficients(&1), 1);
+		assert_eq!(System::sufficients(&2), 1);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn lazy_v5_migration_takes_profile_references() {
+	use crate::migrations::v5::LazyMigrateV4ToV5;
+	use frame_support::{migrations::SteppedMigration, weights::WeightMeter};
+
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		StorageVersion::new(4).put::<Messaging>();
+		for who in [1, 2] {
+			System::dec_consumers(&who);
+		}
+
+		assert_eq!(LazyMigrateV4ToV5::<Test>::step(None, &mut WeightMeter::new()), Ok(None));
+
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(5));
+		assert_eq!(System::sufficients(&1), 1);
+		assert_eq!(System::sufficients(&2), 1);
+	});
+}
+
+/// Stores `count` challenges and parameters as laid out before version 6.
+fn store_v5_challenges(count: u64) {
+	use crate::migrations::v6::{Challenges, OldChallengeInfo};
+
+	StorageVersion::new(5).put::<Messaging>();
+	frame_support::storage::unhashed::put_raw(
+		&crate::Parameters::<Test>::hashed_key(),
+		&(200u128, 50u64, 1u32, EventPrivacyMode::Full, 0u128, false).encode(),
+	);
+	for message_id in 0..count {
+		Challenges::<Test>::insert(
+			message_id,
+			OldChallengeInfo {
+				challenger: Some(3),
+				sender: 1,
+				opened_at: 1,
+				bond: 20,
+				escrowed: 0,
+				status: crate::ChallengeStatus::Open,
+			},
+		);
+	}
+}
+
+fn default_slash_terms() -> SlashTerms<Balance> {
+	SlashTerms {
+		challenger_reward_percent: Perbill::from_percent(50),
+		auto_slash_percent: Perbill::from_percent(50),
+		base_challenge_reward: 10,
+		slash_destination: SlashDestination::Treasury,
+	}
+}
+
+#[test]
+fn v6_migration_snapshots_slash_terms() {
+	new_test_ext().execute_with(|| {
+		store_v5_challenges(2);
+		assert_eq!(Messaging::parameters().slash_terms(), SlashTerms::default());
+
+		crate::migrations::v6::MigrateV5ToV6::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(6));
+		assert_eq!(Messaging::parameters().spam_bond, 200);
+		assert_eq!(Messaging::parameters().slash_terms(), default_slash_terms());
+		for message_id in 0..2 {
+			let challenge = crate::Challenges::<Test>::get(message_id).unwrap();
+			assert_eq!(challenge.terms, default_slash_terms());
+			assert_eq!(challenge.bond, 20);
+		}
+	});
+}
+
+#[test]
+fn lazy_v6_migration_snapshots_slash_terms() {
+	use crate::migrations::v6::LazyMigrateV5ToV6;
+	use frame_support::{migrations::SteppedMigration, weights::WeightMeter};
+
+	new_test_ext().execute_with(|| {
+		store_v5_challenges(3);
+
+		assert_eq!(LazyMigrateV5ToV6::<Test>::step(None, &mut WeightMeter::new()), Ok(None));
+
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(6));
+		assert_eq!(Messaging::parameters().slash_terms(), default_slash_terms());
+		assert_eq!(crate::Challenges::<Test>::iter().count(), 3);
+		assert!(crate::Challenges::<Test>::iter_values()
+			.all(|challenge| challenge.terms == default_slash_terms()));
+	});
+}
+
+/// Stores profiles for accounts 1 and 2 and three messages, with the counters dropped as
+/// before version 7.
+fn store_v6_state() {
+	use crate::migrations::{CounterForMessageHashes, CounterForUserProfiles};
+
+	setup_pair();
+	for message_id in 0..3 {
+		let hash = H256::repeat_byte(1 + message_id as u8);
+		crate::MessageHashes::<Test>::insert(
+			message_id,
+			MessageRecord { sender: 1, recipient: 2, hash, sent_at: 1 },
+		);
+	}
+	CounterForMessageHashes::<Test>::kill();
+	CounterForUserProfiles::<Test>::kill();
+	StorageVersion::new(6).put::<Messaging>();
+}
+
+#[test]
+fn v7_migration_initializes_counters() {
+	new_test_ext().execute_with(|| {
+		store_v6_state();
+		assert_eq!((Messaging::message_count(), Messaging::profile_count()), (0, 0));
+
+		crate::migrations::v7::MigrateV6ToV7::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(7));
+		assert_eq!((Messaging::message_count(), Messaging::profile_count()), (3, 2));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn lazy_v7_migration_counts_messages_then_profiles() {
+	use crate::migrations::{
+		v7::{CountCursor, LazyMigrateV6ToV7},
+		CounterForMessageHashes, CounterForUserProfiles,
+	};
+	use frame_support::{migrations::SteppedMigration, weights::WeightMeter};
+
+	new_test_ext().execute_with(|| {
+		store_v6_state();
+
+		assert_eq!(LazyMigrateV6ToV7::<Test>::step(None, &mut WeightMeter::new()), Ok(None));
+		assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(7));
+		assert_eq!((Messaging::message_count(), Messaging::profile_count()), (3, 2));
+
+		// A later step keeps the messages counted by earlier ones
+		StorageVersion::new(6).put::<Messaging>();
+		CounterForUserProfiles::<Test>::kill();
+		CounterForMessageHashes::<Test>::put(3);
+		let cursor = Some(CountCursor::Profiles(None));
+		assert_eq!(LazyMigrateV6ToV7::<Test>::step(cursor, &mut WeightMeter::new()), Ok(None));
+		assert_eq!((Messaging::message_count(), Messaging::profile_count()), (3, 2));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+/// Registers accounts 1, 2 and 3, makes 1 and 2 mutual contacts and sends two messages from 1
+/// to 2, then lays the profiles and the conversation out as before version 8, with account 2
+/// flagged dormant at block 5.
+fn store_v7_state() {
+	use crate::migrations::v8::{ConversationSequences, DormantProfiles};
+	use codec::Encode;
+
+	for who in 1..=3 {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![who as u8; 32]));
+	}
+	make_mutual_contacts(1, 2);
+	for byte in 1..=2 {
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::repeat_byte(byte)
+		));
+	}
+
+	for (who, profile) in crate::UserProfiles::<Test>::iter() {
+		let key = crate::UserProfiles::<Test>::hashed_key_for(who);
+		sp_io::storage::set(&key, &profile.public_key.encode());
+	}
+	DormantProfiles::<Test>::insert(2, 5);
+	let conversation_id = Messaging::conversation_id(&1, &2);
+	let stats = crate::ConversationStats::<Test>::get(conversation_id);
+	let old_stats =
+		(stats.messages, stats.unacknowledged, stats.last_message_id, stats.last_activity);
+	let key = crate::ConversationStats::<Test>::hashed_key_for(conversation_id);
+	sp_io::storage::set(&key, &old_stats.encode());
+	ConversationSequences::<Test>::insert(conversation_id, stats.next_seq);
+	StorageVersion::new(7).put::<Messaging>();
+}
+
+fn assert_v8_state() {
+	use crate::migrations::v8::{ConversationSequences, DormantProfiles};
+
+	assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(8));
+	assert_eq!(Messaging::profile_count(), 3);
+	assert_eq!(Messaging::profile_activity(2).unwrap().dormant_since, Some(5));
+	for who in [1, 3] {
+		assert_eq!(Messaging::profile_activity(who).unwrap().dormant_since, None);
+	}
+	let conversation_id = Messaging::conversation_id(&1, &2);
+	assert_eq!(crate::ConversationStats::<Test>::get(conversation_id).next_seq, 2);
+	assert_eq!(crate::ConversationStats::<Test>::get(conversation_id).messages, 2);
+	assert_eq!(DormantProfiles::<Test>::iter().count(), 0);
+	assert_eq!(ConversationSequences::<Test>::iter().count(), 0);
+	assert_ok!(Messaging::do_try_state());
+}
+
+#[test]
+fn v8_migration_folds_dormancy_and_sequences() {
+	new_test_ext().execute_with(|| {
+		store_v7_state();
+		// Old layouts still read, as active profiles and unnumbered conversations
+		assert_eq!(Messaging::profile(&2).map(|key| key.len()), Some(32));
+		assert_eq!(Messaging::profile_activity(2).unwrap().dormant_since, None);
+
+		crate::migrations::v8::MigrateV7ToV8::<Test>::on_runtime_upgrade();
+		assert_v8_state();
+
+		// The dormant account's reply wakes it and continues the conversation's numbering
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(2),
+			1,
+			H256::repeat_byte(9)
+		));
+		assert_eq!(Messaging::profile_activity(2).unwrap().dormant_since, None);
+		let conversation_id = Messaging::conversation_id(&1, &2);
+		assert_eq!(crate::ConversationMessages::<Test>::get(conversation_id, 2), Some(2));
+	});
+}
+
+#[test]
+fn lazy_v8_migration_folds_records_across_blocks() {
+	use crate::migrations::v8::{FoldCursor, LazyMigrateV7ToV8};
+	use frame_support::{
+		migrations::{SteppedMigration, SteppedMigrationError},
+		weights::WeightMeter,
+	};
+
+	new_test_ext().execute_with(|| {
+		store_v7_state();
+
+		let per_item = <Test as frame_system::Config>::DbWeight::get().reads_writes(2, 2);
+		assert_eq!(
+			LazyMigrateV7ToV8::<Test>::step(None, &mut WeightMeter::with_limit(per_item / 2)),
+			Err(SteppedMigrationError::InsufficientWeight { required: per_item })
+		);
+
+		let mut cursor = None;
+		let mut blocks = 0;
+		loop {
+			blocks += 1;
+			let mut meter = WeightMeter::with_limit(per_item.saturating_mul(2));
+			cursor = LazyMigrateV7ToV8::<Test>::step(cursor, &mut meter).expect("step fits");
+			if cursor.is_none() {
+				break;
+			}
+			assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(7));
+			assert_eq!(Messaging::profile_count(), 3);
+		}
+
+		// Three profiles, a conversation and the end of each phase
+		assert_eq!(blocks, 4);
+		assert_v8_state();
+
+		// Rewriting a record twice keeps what was folded into it
+		StorageVersion::new(7).put::<Messaging>();
+		let cursor = Some(FoldCursor::Profiles(None));
+		assert_eq!(LazyMigrateV7ToV8::<Test>::step(cursor, &mut WeightMeter::new()), Ok(None));
+		assert_v8_state();
+	});
+}
+
+/// Approves contacts 1 and 2 both ways, sends two messages from 1 to 2, and stores a pass
+/// from 2 to 1, a reply refund entry from 1 to 3 and a running key reset of 1, all keyed as
+/// before version 9.
+fn store_v8_state() {
+	use crate::migrations::v9;
+
+	for who in 1..=3 {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![1, 2, 3, 4]));
+	}
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+	for byte in 1..=2 {
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::repeat_byte(byte)
+		));
+	}
+
+	for (who, contact, approved) in crate::ApprovedContacts::<Test>::drain() {
+		v9::ApprovedContacts::<Test>::insert(who, contact, approved);
+	}
+	for (conversation_id, seq, message_id) in crate::ConversationMessages::<Test>::drain() {
+		v9::ConversationMessages::<Test>::insert(conversation_id, seq, message_id);
+	}
+	v9::InboxPasses::<Test>::insert(2, 1, 50);
+	v9::ReplyRefundableDeposits::<Test>::insert(1, 3, BoundedVec::truncate_from(vec![7]));
+	crate::KeyResetCursors::<Test>::insert(1, BoundedVec::truncate_from(vec![1, 2, 3]));
+	StorageVersion::new(8).put::<Messaging>();
+}
+
+fn assert_v9_state() {
+	use crate::migrations::v9;
+
+	assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(9));
+	assert!(crate::ApprovedContacts::<Test>::get(1, 2));
+	assert!(crate::ApprovedContacts::<Test>::get(2, 1));
+	let conversation_id = Messaging::conversation_id(&1, &2);
+	for seq in 0..2 {
+		assert_eq!(crate::ConversationMessages::<Test>::get(conversation_id, seq), Some(seq));
+	}
+	assert_eq!(crate::InboxPasses::<Test>::get(2, 1), Some(50));
+	assert_eq!(crate::ReplyRefundableDeposits::<Test>::get(1, 3).into_inner(), vec![7]);
+	assert_eq!(crate::KeyResetCursors::<Test>::get(1).map(|cursor| cursor.len()), Some(0));
+
+	assert!(!v9::ApprovedContacts::<Test>::contains_key(1, 2));
+	assert!(!v9::ConversationMessages::<Test>::contains_key(conversation_id, 0));
+	assert!(!v9::InboxPasses::<Test>::contains_key(2, 1));
+	assert!(!v9::ReplyRefundableDeposits::<Test>::contains_key(1, 3));
+}
+
+#[test]
+fn v9_migration_rekeys_pair_maps() {
+	new_test_ext().execute_with(|| {
+		store_v8_state();
+		// Entries under the old hashers are out of the pallet's reach
+		assert!(!crate::ApprovedContacts::<Test>::get(1, 2));
+		// and entries already under the new ones are left in place
+		crate::ApprovedContacts::<Test>::insert(3, 1, true);
+
+		crate::migrations::v9::MigrateV8ToV9::<Test>::on_runtime_upgrade();
+		assert_v9_state();
+		assert!(crate::ApprovedContacts::<Test>::get(3, 1));
+		assert_eq!(crate::ApprovedContacts::<Test>::iter().count(), 3);
+
+		// Sends see the approvals and continue the conversation index
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(2),
+			1,
+			H256::repeat_byte(9)
+		));
+		let conversation_id = Messaging::conversation_id(&1, &2);
+		assert_eq!(crate::ConversationMessages::<Test>::get(conversation_id, 2), Some(2));
+	});
+}
+
+#[test]
+fn lazy_v9_migration_rekeys_across_blocks() {
+	use crate::{
+		migrations::v9::{LazyMigrateV8ToV9, RekeyCursor},
+		weights::WeightInfo,
+	};
+	use frame_support::{
+		migrations::{SteppedMigration, SteppedMigrationError},
+		weights::WeightMeter,
+	};
+
+	new_test_ext().execute_with(|| {
+		store_v8_state();
+
+		let per_entry = <Test as crate::Config>::WeightInfo::rekey_entry();
+		let cursor = Some(RekeyCursor::ApprovedContacts(None));
+		assert_eq!(
+			LazyMigrateV8ToV9::<Test>::step(cursor, &mut WeightMeter::with_limit(per_entry / 2)),
+			Err(SteppedMigrationError::InsufficientWeight { required: per_entry })
+		);
+
+		// Six entries to move, some of them visited again once moved
+		let mut cursor = None;
+		let mut blocks = 0;
+		loop {
+			blocks += 1;
+			let mut meter = WeightMeter::with_limit(per_entry.saturating_mul(2));
+			cursor = LazyMigrateV8ToV9::<Test>::step(cursor, &mut meter).expect("step fits");
+			if cursor.is_none() {
+				break;
+			}
+			assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(8));
+			assert!(blocks < 20, "re-keying does not progress");
+		}
+
+		assert!(blocks >= 3);
+		assert_v9_state();
+	});
+}
+
+/// Registers accounts 1, 2 and 3, with 2 approving 1 and requiring sessions, then lays the
+/// profiles of 1 and 2 out as before version 10, with the bond of 1 recorded as 50.
+fn store_v9_state() {
+	use crate::migrations::v10::{RegistrationBonds, SessionRequired};
+	use codec::Encode;
+
+	for who in 1..=3 {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![who as u8; 32]));
+	}
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+
+	for who in [1, 2] {
+		let profile = crate::UserProfiles::<Test>::get(who).unwrap();
+		let key = crate::UserProfiles::<Test>::hashed_key_for(who);
+		sp_io::storage::set(&key, &(profile.public_key, profile.dormant_since).encode());
+	}
+	RegistrationBonds::<Test>::insert(1, 50);
+	RegistrationBonds::<Test>::insert(2, 100);
+	SessionRequired::<Test>::insert(2, ());
+	StorageVersion::new(9).put::<Messaging>();
+}
+
+fn assert_v10_state() {
+	use crate::migrations::v10::{RegistrationBonds, SessionRequired};
+
+	assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(10));
+	assert_eq!(Messaging::profile_count(), 3);
+	assert_eq!(Messaging::required_bond(&1), 50);
+	assert_eq!(Messaging::can_message(1, 2), Err(CanMessageError::SessionRequired));
+	for who in [1, 3] {
+		assert!(!crate::UserProfiles::<Test>::get(who).unwrap().session_required);
+	}
+	assert_eq!(RegistrationBonds::<Test>::iter().count(), 0);
+	assert_eq!(SessionRequired::<Test>::iter().count(), 0);
+	assert_ok!(Messaging::do_try_state());
+}
+
+#[test]
+fn v10_migration_folds_bonds_and_session_requirements() {
+	new_test_ext().execute_with(|| {
+		store_v9_state();
+		// Old layouts still read, with the constant bond and no session required
+		assert_eq!(Messaging::profile(&2).map(|key| key.len()), Some(32));
+		assert_eq!(Messaging::required_bond(&1), 100);
+		assert_eq!(Messaging::can_message(1, 2), Ok(()));
+
+		crate::migrations::v10::MigrateV9ToV10::<Test>::on_runtime_upgrade();
+		assert_v10_state();
+
+		// The folded requirement is lifted like one set after the upgrade
+		assert_ok!(Messaging::set_session_requirement(RuntimeOrigin::signed(2), false));
+		assert_eq!(Messaging::can_message(1, 2), Ok(()));
+	});
+}
+
+#[test]
+fn lazy_v10_migration_folds_profiles_across_blocks() {
+	use crate::migrations::v10::{FoldCursor, LazyMigrateV9ToV10};
+	use frame_support::{
+		migrations::{SteppedMigration, SteppedMigrationError},
+		weights::WeightMeter,
+	};
+
+	new_test_ext().execute_with(|| {
+		store_v9_state();
+
+		let per_item = <Test as frame_system::Config>::DbWeight::get().reads_writes(3, 3);
+		assert_eq!(
+			LazyMigrateV9ToV10::<Test>::step(None, &mut WeightMeter::with_limit(per_item / 2)),
+			Err(SteppedMigrationError::InsufficientWeight { required: per_item })
+		);
+
+		let mut cursor = None;
+		let mut blocks = 0;
+		loop {
+			blocks += 1;
+			let mut meter = WeightMeter::with_limit(per_item.saturating_mul(2));
+			cursor = LazyMigrateV9ToV10::<Test>::step(cursor, &mut meter).expect("step fits");
+			if cursor.is_none() {
+				break;
+			}
+			assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(9));
+			assert_eq!(Messaging::profile_count(), 3);
+		}
+
+		// Three profiles and the end of each phase
+		assert_eq!(blocks, 3);
+		assert_v10_state();
+
+		// Rewriting a profile twice keeps what was folded into it
+		StorageVersion::new(9).put::<Messaging>();
+		let cursor = Some(FoldCursor::Profiles(None));
+		assert_eq!(LazyMigrateV9ToV10::<Test>::step(cursor, &mut WeightMeter::new()), Ok(None));
+		assert_v10_state();
+	});
+}
+
+/// Registers accounts 1 and 2 with their profile references laid out as before version 11,
+/// and stores a profile for account 9, which holds no funds. Returns the consumer and
+/// sufficient references of 1 and 2.
+fn store_v10_state() -> [(u32, u32); 2] {
+	setup_pair();
+	for who in [1, 2] {
+		System::dec_consumers(&who);
+		System::inc_sufficients(&who);
+	}
+	System::inc_sufficients(&9);
+	crate::UserProfiles::<Test>::insert(9, crate::UserProfiles::<Test>::get(1).unwrap());
+	StorageVersion::new(10).put::<Messaging>();
+
+	[1, 2].map(|who| (System::consumers(&who), System::sufficients(&who)))
+}
+
+fn assert_v11_state(references: [(u32, u32); 2]) {
+	assert_eq!(Messaging::on_chain_storage_version(), StorageVersion::new(11));
+	assert_eq!(Messaging::on_chain_storage_version(), Messaging::in_code_storage_version());
+	for (who, (consumers, sufficients)) in [1, 2].into_iter().zip(references) {
+		assert_eq!(System::consumers(&who), consumers + 1);
+		assert_eq!(System::sufficients(&who), sufficients - 1);
+		assert!(!crate::SufficientProfileRefs::<Test>::contains_key(who));
+	}
+	// Without funds to take a consumer reference, account 9 keeps its sufficient one
+	assert_eq!(System::sufficients(&9), 1);
+	assert!(crate::SufficientProfileRefs::<Test>::contains_key(9));
+	assert_ok!(Messaging::do_try_state());
+}
+
+#[test]
+fn v11_migration_swaps_profile_references() {
+	new_test_ext().execute_with(|| {
+		let references = store_v10_state();
+
+		crate::migrations::v11::MigrateV10ToV11::<Test>::on_runtime_upgrade();
+		assert_v11_state(references);
+
+		// Removing the profile drops the sufficient reference it kept
+		ban(9);
+		assert!(!System::account_exists(&9));
+		assert_eq!(crate::SufficientProfileRefs::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn lazy_v11_migration_swaps_profile_references() {
+	use crate::migrations::v11::LazyMigrateV10ToV11;
+	use frame_support::{migrations::SteppedMigration, weights::WeightMeter};
+
+	new_test_ext().execute_with(|| {
+		let references = store_v10_state();
+
+		assert_eq!(LazyMigrateV10ToV11::<Test>::step(None, &mut WeightMeter::new()), Ok(None));
+
+		assert_v11_state(references);
+	});
+}
+
+#[test]
+fn every_storage_item_is_bounded() {
+	use frame_support::traits::StorageInfoTrait;
+
+	for info in Messaging::storage_info() {
+		assert!(
+			info.max_size.is_some(),
+			"{} has no max encoded size",
+			String::from_utf8_lossy(&info.storage_name)
+		);
+	}
+}
+
+/// Pins the max encoded sizes of stored values under the mock's types (8-byte accounts and
+/// block numbers, 32-byte hashes, 16-byte balances). A failure here means a storage value
+/// grew, which raises proof sizes and needs the weights regenerated.
+#[test]
+fn storage_value_sizes_are_pinned() {
+	use codec::MaxEncodedLen;
+
+	assert_eq!(crate::MessageRecordOf::<Test>::max_encoded_len(), 56);
+	assert_eq!(crate::PublicKey::max_encoded_len(), 258);
+	assert_eq!(crate::ProfileRecordOf::<Test>::max_encoded_len(), 285);
+	assert_eq!(crate::ReputationRecord::<u64>::max_encoded_len(), 12);
+	assert_eq!(crate::RateLimitState::<u64>::max_encoded_len(), 24);
+	assert_eq!(crate::SpamReportRecord::<u64>::max_encoded_len(), 12);
+	assert_eq!(crate::BondLock::<Balance, u64>::max_encoded_len(), 24);
+	assert_eq!(crate::ChallengeInfoOf::<Test>::max_encoded_len(), 108);
+	assert_eq!(crate::InboxPaymentOf::<Test>::max_encoded_len(), 32);
+	assert_eq!(PalletParametersOf::<Test>::max_encoded_len(), 71);
+	assert_eq!(crate::RevokedKey::<u64>::max_encoded_len(), 270);
+	assert_eq!(crate::TakedownRecordOf::<Test>::max_encoded_len(), 90);
+	assert_eq!(crate::AuditEntryOf::<Test>::max_encoded_len(), 41);
+	assert_eq!(crate::RemoteProfileV1::max_encoded_len(), 263);
+	assert_eq!(crate::GroupInfoOf::<Test>::max_encoded_len(), 317);
+	assert_eq!(crate::MemberInfo::<u64>::max_encoded_len(), 9);
+	assert_eq!(crate::GroupMessageRecordOf::<Test>::max_encoded_len(), 68);
+	assert_eq!(crate::GroupMetadataOf::<Test>::max_encoded_len(), 187);
+	assert_eq!(crate::GroupMessageTombstoneOf::<Test>::max_encoded_len(), 32);
+	assert_eq!(crate::GroupModerationEntryOf::<Test>::max_encoded_len(), 33);
+	assert_eq!(crate::StealthMessageRecordOf::<Test>::max_encoded_len(), 96);
+	assert_eq!(crate::RelayInfoOf::<Test>::max_encoded_len(), 29);
+	assert_eq!(crate::PendingMessageRecordOf::<Test>::max_encoded_len(), 64);
+	assert_eq!(crate::EvidenceRecordOf::<Test>::max_encoded_len(), 1068);
+	assert_eq!(crate::RecipientSetMessageRecordOf::<Test>::max_encoded_len(), 129);
+	assert_eq!(crate::BackupCommitmentOf::<Test>::max_encoded_len(), 60);
+	assert_eq!(crate::InboxPricingOf::<Test>::max_encoded_len(), 34);
+	assert_eq!(crate::BondRecordOf::<Test>::max_encoded_len(), 25);
+	assert_eq!(crate::DeviceSetOf::<Test>::max_encoded_len(), 6661);
+}
+
+#[test]
+#[should_panic(expected = "`MessageHashExpiry` must be non-zero")]
+fn integrity_test_rejects_zero_expiry() {
+	MessageHashExpiry::set(0);
+	<Messaging as Hooks<u64>>::integrity_test();
+}
+
+#[test]
+#[should_panic(expected = "`MaxContactsPerUser` must be non-zero")]
+fn integrity_test_rejects_zero_contact_limit() {
+	MaxContactsPerUser::set(0);
+	<Messaging as Hooks<u64>>::integrity_test();
+}
+
+#[test]
+#[should_panic(expected = "`SpamBond` must be at least the existential deposit")]
+fn integrity_test_rejects_bond_below_existential_deposit() {
+	SpamBond::set(0);
+	<Messaging as Hooks<u64>>::integrity_test();
+}
+
+#[test]
+#[should_panic(expected = "worst-case proof of `leave_group`")]
+fn integrity_test_rejects_bounds_over_the_proof_budget() {
+	// Leaving a group reads every member, at 2_584 bytes of proof each
+	MaxGroupMembers::set(1_000);
+	<Messaging as Hooks<u64>>::integrity_test();
+}
+
+#[test]
+fn heaviest_call_fits_the_proof_budget() {
+	let weights = Messaging::worst_case_weights();
+	let (_, heaviest) = weights.iter().max_by_key(|(_, weight)| weight.proof_size()).unwrap();
+	assert!(heaviest.proof_size() <= crate::MAX_CALL_PROOF_SIZE);
+
+	// A full batch proves the sender once and shares the refunds of one reply
+	let (_, batch) = weights.iter().find(|(call, _)| *call == "send_message_hash_batch").unwrap();
+	let (_, single) = weights.iter().find(|(call, _)| *call == "send_message_hash").unwrap();
+	assert!(batch.proof_size() < single.proof_size() * u64::from(MaxSendBatch::get()));
+}
+
+fn group_key() -> crate::GroupKey {
+	vec![7; 32].try_into().unwrap()
+}
+
+#[test]
+fn create_group_works() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+
+		assert_ok!(Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 4));
+
+		assert_eq!(
+			crate::Groups::<Test>::get(0),
+			Some(crate::GroupInfo {
+				owner: 1,
+				status: crate::GroupStatus::Active,
+				invite_only: false,
+				key: group_key(),
+				key_version: 0,
+				rotation_required: false,
+				created_at: 1,
+				member_count: 1,
+				admin_count: 0,
+				max_members: 4,
+				deposit: 50,
+				next_seq: 0,
+			})
+		);
+		assert_eq!(
+			crate::GroupMembers::<Test>::get(0, 1),
+			Some(crate::MemberInfo { joined_at: 1, role: GroupRole::Owner })
+		);
+		assert_eq!(crate::GroupCount::<Test>::get(1), 1);
+		assert_eq!(crate::NextGroupId::<Test>::get(), 1);
+
+		// The deposit is held on top of the spam bond
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 50);
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(Balances::free_balance(1), 850);
+
+		System::assert_last_event(
+			Event::GroupCreated {
+				group_id: 0,
+				owner: 1,
+				key_hash: Messaging::key_hash(&group_key()),
+				deposit: 50,
+			}
+			.into(),
+		);
+
+		// Group IDs are never reused
+		assert_ok!(Messaging::create_group(RuntimeOrigin::signed(2), group_key(), 4));
+		assert_eq!(crate::Groups::<Test>::get(1).map(|group| group.owner), Some(2));
+	});
+}
+
+#[test]
+fn create_group_is_limited_per_account() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+
+		for _ in 0..MaxGroupsPerAccount::get() {
+			assert_ok!(Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 4));
+		}
+		assert_noop!(
+			Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 4),
+			Error::<Test>::TooManyGroups
+		);
+
+		// One deposit per group, none for the rejected one
+		assert_eq!(crate::GroupCount::<Test>::get(1), 3);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 150);
+
+		// The limit is per account
+		assert_ok!(Messaging::create_group(RuntimeOrigin::signed(2), group_key(), 4));
+	});
+}
+
+#[test]
+fn create_group_fails_without_group_deposit() {
+	use frame_support::traits::fungible::Mutate;
+
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, 30));
+
+		assert_noop!(
+			Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 4),
+			Error::<Test>::InsufficientGroupDeposit
+		);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 0);
+	});
+}
+
+#[test]
+fn create_group_validates_input() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 4),
+			Error::<Test>::ProfileNotFound
+		);
+
+		setup_pair();
+		assert_noop!(
+			Messaging::create_group(RuntimeOrigin::signed(1), Default::default(), 10),
+			Error::<Test>::InvalidGroupKey
+		);
+		assert_noop!(
+			Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 0),
+			Error::<Test>::InvalidGroupSize
+		);
+		assert_noop!(
+			Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 5),
+			Error::<Test>::InvalidGroupSize
+		);
+	});
+}
+
+/// Register accounts 1, 2, 3 and 5, and let 1 create group 0.
+fn setup_group() {
+	setup_pair();
+	for who in [3, 5] {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![1, 2, 3, 4]));
+	}
+	assert_ok!(Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 4));
+}
+
+#[test]
+fn add_group_member_works() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		System::set_block_number(5);
+
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_eq!(
+			crate::GroupMembers::<Test>::get(0, 2),
+			Some(crate::MemberInfo { joined_at: 5, role: GroupRole::Member })
+		);
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().member_count, 2);
+		assert_eq!(crate::GroupCount::<Test>::get(2), 1);
+		System::assert_last_event(Event::MemberAdded { group_id: 0, who: 2 }.into());
+
+		// Members do not pay a deposit, the owner covers them
+		assert_eq!(held(HoldReason::GroupDeposit, 2), 0);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 60);
+	});
+}
+
+#[test]
+fn add_group_member_checks_caller_and_member() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(1), 1, 3),
+			Error::<Test>::GroupNotFound
+		);
+		// Members cannot add others
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(2), 0, 3),
+			Error::<Test>::NotGroupAdmin
+		);
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 4),
+			Error::<Test>::RecipientNotFound
+		);
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::AlreadyGroupMember
+		);
+	});
+}
+
+#[test]
+fn group_membership_is_bounded() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(6), vec![1, 2, 3, 4]));
+
+		// Owner plus three members fill the group
+		for who in [2, 3, 5] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 6),
+			Error::<Test>::GroupFull
+		);
+
+		// Account 2 is now in one group, and can join two more
+		for owner in [3, 5] {
+			let group_id = crate::NextGroupId::<Test>::get();
+			assert_ok!(Messaging::create_group(RuntimeOrigin::signed(owner), group_key(), 4));
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(owner), group_id, 2));
+		}
+		assert_eq!(crate::GroupCount::<Test>::get(2), 3);
+
+		assert_ok!(Messaging::create_group(RuntimeOrigin::signed(6), group_key(), 4));
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(6), 3, 2),
+			Error::<Test>::TooManyGroups
+		);
+	});
+}
+
+#[test]
+fn remove_group_member_works_for_owner_and_self() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 2));
+		System::assert_last_event(
+			Event::MemberRemoved { group_id: 0, who: 2, removed_by: 1 }.into(),
+		);
+
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(3), 0, 3));
+		System::assert_last_event(
+			Event::MemberRemoved { group_id: 0, who: 3, removed_by: 3 }.into(),
+		);
+
+		assert!(!crate::GroupMembers::<Test>::contains_key(0, 2));
+		assert!(!crate::GroupMembers::<Test>::contains_key(0, 3));
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().member_count, 1);
+		assert_eq!(crate::GroupCount::<Test>::get(2), 0);
+		assert_eq!(crate::GroupCount::<Test>::get(3), 0);
+
+		// Removed members can be added again
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().member_count, 2);
+	});
+}
+
+#[test]
+fn remove_group_member_checks_caller_and_member() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(1), 1, 2),
+			Error::<Test>::GroupNotFound
+		);
+		// Members can only remove themselves
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(2), 0, 3),
+			Error::<Test>::NotGroupAdmin
+		);
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 1),
+			Error::<Test>::CannotRemoveGroupOwner
+		);
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 5),
+			Error::<Test>::NotGroupMember
+		);
+	});
+}
+
+/// Group 0 owned by 1, with admin 2 and ordinary members 3 and 5.
+fn setup_group_roles() {
+	setup_group();
+	for who in [2, 3, 5] {
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+	}
+	assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+}
+
+#[test]
+fn owner_manages_admins_and_members() {
+	new_test_ext().execute_with(|| {
+		setup_group_roles();
+
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 3));
+		System::assert_last_event(Event::MemberPromoted { group_id: 0, who: 3 }.into());
+		assert_eq!(Messaging::group_role(0, &3), Some(GroupRole::Admin));
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().admin_count, 2);
+
+		assert_ok!(Messaging::demote_member(RuntimeOrigin::signed(1), 0, 3));
+		System::assert_last_event(Event::MemberDemoted { group_id: 0, who: 3 }.into());
+		assert_eq!(Messaging::group_role(0, &3), Some(GroupRole::Member));
+
+		// Owners can remove admins
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().admin_count, 0);
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().member_count, 3);
+	});
+}
+
+#[test]
+fn admins_manage_ordinary_members_only() {
+	new_test_ext().execute_with(|| {
+		setup_group_roles();
+
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(2), 0, 3));
+		System::assert_last_event(
+			Event::MemberRemoved { group_id: 0, who: 3, removed_by: 2 }.into(),
+		);
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(2), 0, 3));
+		assert_eq!(Messaging::group_role(0, &3), Some(GroupRole::Member));
+
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(2), 0, 1),
+			Error::<Test>::CannotRemoveGroupOwner
+		);
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 5));
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(2), 0, 5),
+			Error::<Test>::NotGroupOwner
+		);
+
+		// Only the owner hands out roles
+		assert_noop!(
+			Messaging::promote_member(RuntimeOrigin::signed(2), 0, 3),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_noop!(
+			Messaging::demote_member(RuntimeOrigin::signed(2), 0, 5),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_noop!(
+			Messaging::transfer_group_ownership(RuntimeOrigin::signed(2), 0, 5),
+			Error::<Test>::NotGroupOwner
+		);
+	});
+}
+
+#[test]
+fn members_cannot_manage_the_group() {
+	new_test_ext().execute_with(|| {
+		setup_group_roles();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(6), vec![1, 2, 3, 4]));
+
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(3), 0, 6),
+			Error::<Test>::NotGroupAdmin
+		);
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(3), 0, 5),
+			Error::<Test>::NotGroupAdmin
+		);
+		assert_noop!(
+			Messaging::promote_member(RuntimeOrigin::signed(3), 0, 5),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_noop!(
+			Messaging::demote_member(RuntimeOrigin::signed(3), 0, 2),
+			Error::<Test>::NotGroupOwner
+		);
+
+		// Every role can leave on its own, except the owner
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(3), 0, 3));
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(2), 0, 2));
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 1),
+			Error::<Test>::CannotRemoveGroupOwner
+		);
+	});
+}
+
+#[test]
+fn admin_roles_are_checked_and_bounded() {
+	new_test_ext().execute_with(|| {
+		setup_group_roles();
+
+		assert_noop!(
+			Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::AlreadyGroupAdmin
+		);
+		assert_noop!(
+			Messaging::promote_member(RuntimeOrigin::signed(1), 0, 1),
+			Error::<Test>::AlreadyGroupAdmin
+		);
+		assert_noop!(
+			Messaging::promote_member(RuntimeOrigin::signed(1), 0, 6),
+			Error::<Test>::NotGroupMember
+		);
+		assert_noop!(
+			Messaging::demote_member(RuntimeOrigin::signed(1), 0, 3),
+			Error::<Test>::MemberNotAdmin
+		);
+
+		// At most `MaxGroupAdmins` admins
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 3));
+		assert_noop!(
+			Messaging::promote_member(RuntimeOrigin::signed(1), 0, 5),
+			Error::<Test>::TooManyGroupAdmins
+		);
+	});
+}
+
+#[test]
+fn ownership_transfer_needs_acceptance() {
+	new_test_ext().execute_with(|| {
+		setup_group_roles();
+
+		assert_noop!(
+			Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 3),
+			Error::<Test>::MemberNotAdmin
+		);
+		assert_ok!(Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 2));
+		System::assert_last_event(
+			Event::GroupOwnershipOffered { group_id: 0, owner: 1, new_owner: 2 }.into(),
+		);
+
+		// Nothing changes until the admin accepts
+		assert_eq!(Messaging::group_role(0, &1), Some(GroupRole::Owner));
+		assert_noop!(
+			Messaging::accept_group_ownership(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::NoPendingOwnershipTransfer
+		);
+
+		assert_ok!(Messaging::accept_group_ownership(RuntimeOrigin::signed(2), 0));
+		System::assert_last_event(
+			Event::GroupOwnershipTransferred { group_id: 0, old_owner: 1, new_owner: 2 }
+				.into(),
+		);
+
+		let group = crate::Groups::<Test>::get(0).unwrap();
+		assert_eq!(group.owner, 2);
+		assert_eq!(group.admin_count, 1);
+		assert_eq!(Messaging::group_role(0, &2), Some(GroupRole::Owner));
+		assert_eq!(Messaging::group_role(0, &1), Some(GroupRole::Admin));
+		assert!(!crate::PendingGroupOwners::<Test>::contains_key(0));
+
+		// The deposit moved with the group
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 0);
+		assert_eq!(held(HoldReason::GroupDeposit, 2), 80);
+
+		// The previous owner is now an ordinary admin
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::CannotRemoveGroupOwner
+		);
+		assert_noop!(
+			Messaging::promote_member(RuntimeOrigin::signed(1), 0, 3),
+			Error::<Test>::NotGroupOwner
+		);
+	});
+}
+
+#[test]
+fn ownership_offer_is_withdrawn_when_admin_loses_role() {
+	new_test_ext().execute_with(|| {
+		setup_group_roles();
+
+		assert_ok!(Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::demote_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_noop!(
+			Messaging::accept_group_ownership(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::NoPendingOwnershipTransfer
+		);
+
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(2), 0, 2));
+		assert!(!crate::PendingGroupOwners::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn new_owner_must_cover_the_group_deposit() {
+	use frame_support::traits::fungible::Mutate;
+
+	new_test_ext().execute_with(|| {
+		setup_group_roles();
+		assert_ok!(Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&2, 30));
+
+		assert_noop!(
+			Messaging::accept_group_ownership(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::InsufficientGroupDeposit
+		);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 80);
+	});
+}
+
+fn rotated_key(version: u32) -> crate::GroupKey {
+	vec![version as u8; 32].try_into().unwrap()
+}
+
+#[test]
+fn rotate_group_key_works() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+		System::set_block_number(5);
+
+		assert_ok!(Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(1), 1));
+		System::assert_last_event(
+			Event::GroupKeyRotated {
+				group_id: 0,
+				version: 1,
+				key_hash: Messaging::key_hash(&rotated_key(1)),
+			}
+			.into(),
+		);
+
+		let group = crate::Groups::<Test>::get(0).unwrap();
+		assert_eq!(group.key, rotated_key(1));
+		assert_eq!(group.key_version, 1);
+		assert_eq!(
+			crate::GroupKeyHistory::<Test>::get(0).into_inner(),
+			vec![crate::RevokedKey { public_key: group_key(), version: 0, revoked_at: 5 }]
+		);
+
+		// Admins can rotate too, ordinary members cannot
+		assert_ok!(Messaging::rotate_group_key(RuntimeOrigin::signed(2), 0, rotated_key(2), 2));
+		assert_noop!(
+			Messaging::rotate_group_key(RuntimeOrigin::signed(3), 0, rotated_key(3), 3),
+			Error::<Test>::NotGroupAdmin
+		);
+	});
+}
+
+#[test]
+fn rotate_group_key_rejects_stale_versions() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(1), 1));
+
+		// A second admin rotating from the same starting point loses the race
+		assert_noop!(
+			Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(1), 1),
+			Error::<Test>::InvalidGroupKeyVersion
+		);
+		assert_noop!(
+			Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(3), 3),
+			Error::<Test>::InvalidGroupKeyVersion
+		);
+		assert_noop!(
+			Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, Default::default(), 2),
+			Error::<Test>::InvalidGroupKey
+		);
+	});
+}
+
+#[test]
+fn removing_a_member_requires_key_rotation() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		// Joining does not require a rotation
+		assert!(!crate::Groups::<Test>::get(0).unwrap().rotation_required);
+
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert!(crate::Groups::<Test>::get(0).unwrap().rotation_required);
+
+		assert_ok!(Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(1), 1));
+		assert!(!crate::Groups::<Test>::get(0).unwrap().rotation_required);
+
+		// Members leaving on their own count too
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(3), 0, 3));
+		assert!(crate::Groups::<Test>::get(0).unwrap().rotation_required);
+	});
+}
+
+#[test]
+fn group_key_history_is_bounded() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+
+		for version in 1..=crate::MAX_KEY_HISTORY + 1 {
+			assert_ok!(Messaging::rotate_group_key(
+				RuntimeOrigin::signed(1),
+				0,
+				rotated_key(version),
+				version
+			));
+		}
+
+		// The oldest key was dropped
+		let history = crate::GroupKeyHistory::<Test>::get(0);
+		assert_eq!(history.len() as u32, crate::MAX_KEY_HISTORY);
+		assert_eq!(history.first().map(|key| key.version), Some(1));
+		assert_eq!(history.last().map(|key| key.version), Some(crate::MAX_KEY_HISTORY));
+	});
+}
+
+#[test]
+fn send_group_message_hash_works() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		System::set_block_number(5);
+
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(2),
+			0,
+			H256::repeat_byte(1)
+		));
+
+		// One record for the whole group
+		assert_eq!(
+			crate::GroupMessages::<Test>::get(0, 0),
+			Some(crate::GroupMessageRecord {
+				hash: H256::repeat_byte(1),
+				sender: 2,
+				sent_at: 5,
+				key_version: 0,
+				expires_at: 505,
+				seq: 0,
+			})
+		);
+		assert_eq!(crate::GroupMessageIds::<Test>::get(0), Some(0));
+
+		// Group messages are numbered apart from direct messages
+		assert_eq!(crate::NextGroupMessageId::<Test>::get(), 1);
+		assert_eq!(crate::NextMessageId::<Test>::get(), 0);
+		System::assert_last_event(
+			Event::GroupMessageSent {
+				group_id: 0,
+				message_id: 0,
+				sender: 2,
+				hash: H256::repeat_byte(1),
+				key_version: 0,
+				seq: 0,
+			}
+			.into(),
+		);
+
+		assert_eq!(Messaging::verify_group_message(0, 0, H256::repeat_byte(1)), Ok(true));
+		assert_eq!(Messaging::verify_group_message(0, 0, H256::repeat_byte(2)), Ok(false));
+		assert_noop!(
+			Messaging::verify_group_message(1, 0, H256::repeat_byte(1)),
+			Error::<Test>::MessageNotFound
+		);
+	});
+}
+
+#[test]
+fn only_members_can_send_to_groups() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+
+		assert_noop!(
+			Messaging::send_group_message_hash(RuntimeOrigin::signed(3), 0, H256::repeat_byte(1)),
+			Error::<Test>::NotGroupMember
+		);
+		assert_noop!(
+			Messaging::send_group_message_hash(RuntimeOrigin::signed(1), 1, H256::repeat_byte(1)),
+			Error::<Test>::GroupNotFound
+		);
+
+		// Removed members lose access
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 3));
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 3));
+		assert_ok!(Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(1), 1));
+		assert_noop!(
+			Messaging::send_group_message_hash(RuntimeOrigin::signed(3), 0, H256::repeat_byte(1)),
+			Error::<Test>::NotGroupMember
+		);
+	});
+}
+
+#[test]
+fn group_sends_wait_for_key_rotation() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 3));
+
+		assert_noop!(
+			Messaging::send_group_message_hash(RuntimeOrigin::signed(2), 0, H256::repeat_byte(1)),
+			Error::<Test>::GroupKeyRotationPending
+		);
+
+		assert_ok!(Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(1), 1));
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(2),
+			0,
+			H256::repeat_byte(1)
+		));
+		assert_eq!(crate::GroupMessages::<Test>::get(0, 0).unwrap().key_version, 1);
+	});
+}
+
+#[test]
+fn group_sends_are_rate_limited_per_group() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+
+		// MaxGroupMessagesPerWindow is 5, shared by all members
+		for sender in [1, 2, 1, 2, 1] {
+			assert_ok!(Messaging::send_group_message_hash(
+				RuntimeOrigin::signed(sender),
+				0,
+				H256::repeat_byte(1)
+			));
+		}
+		assert_noop!(
+			Messaging::send_group_message_hash(RuntimeOrigin::signed(2), 0, H256::repeat_byte(1)),
+			Error::<Test>::RateLimitExceeded
+		);
+
+		// Direct sends have their own limit
+		approve(2, 1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::repeat_byte(1)));
+
+		// A new window resets the counter
+		System::set_block_number(11);
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(2),
+			0,
+			H256::repeat_byte(1)
+		));
+	});
+}
+
+#[test]
+fn expired_group_messages_are_pruned() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		approve(2, 1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::repeat_byte(1)));
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(1),
+			0,
+			H256::repeat_byte(2)
+		));
+
+		// GroupMessageExpiry is 500, so the group message expires first
+		System::set_block_number(502);
+		assert_noop!(
+			Messaging::verify_group_message(0, 0, H256::repeat_byte(2)),
+			Error::<Test>::MessageExpired
+		);
+		assert_eq!(Messaging::verify_message_hash(0, H256::repeat_byte(1)), Ok(true));
+		assert!(Messaging::has_overdue_messages(502));
+
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 502));
+		assert!(!crate::GroupMessages::<Test>::contains_key(0, 0));
+		assert!(!crate::GroupMessageIds::<Test>::contains_key(0));
+		assert!(crate::MessageHashes::<Test>::contains_key(0));
+		System::assert_has_event(Event::ExpiredGroupMessagesPruned { count: 1, next: 1 }.into());
+		System::assert_last_event(Event::ExpiredMessagesPruned { count: 0, next: 0 }.into());
+		assert!(!Messaging::has_overdue_messages(502));
+
+		System::set_block_number(1002);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert!(!crate::MessageHashes::<Test>::contains_key(0));
+		System::assert_last_event(Event::ExpiredMessagesPruned { count: 1, next: 1 }.into());
+	});
+}
+
+#[test]
+fn group_retention_can_only_be_shortened() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_eq!(Messaging::group_retention(0), 500);
+
+		assert_noop!(
+			Messaging::set_group_retention(RuntimeOrigin::signed(2), 0, 100),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_noop!(
+			Messaging::set_group_retention(RuntimeOrigin::signed(1), 0, 0),
+			Error::<Test>::InvalidGroupRetention
+		);
+		assert_noop!(
+			Messaging::set_group_retention(RuntimeOrigin::signed(1), 0, 501),
+			Error::<Test>::InvalidGroupRetention
+		);
+
+		assert_ok!(Messaging::set_group_retention(RuntimeOrigin::signed(1), 0, 100));
+		System::assert_last_event(Event::GroupRetentionSet { group_id: 0, blocks: 100 }.into());
+		assert_eq!(Messaging::group_retention(0), 100);
+
+		// Back to the maximum
+		assert_ok!(Messaging::set_group_retention(RuntimeOrigin::signed(1), 0, 500));
+		assert!(!crate::GroupRetentions::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn shortened_retention_applies_to_sent_messages() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(1),
+			0,
+			H256::repeat_byte(1)
+		));
+
+		assert_ok!(Messaging::set_group_retention(RuntimeOrigin::signed(1), 0, 100));
+		System::set_block_number(102);
+		assert_noop!(
+			Messaging::verify_group_message(0, 0, H256::repeat_byte(1)),
+			Error::<Test>::MessageExpired
+		);
+
+		// New messages record the shortened deadline
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(1),
+			0,
+			H256::repeat_byte(2)
+		));
+		assert_eq!(crate::GroupMessages::<Test>::get(0, 1).unwrap().expires_at, 202);
+		assert_eq!(Messaging::verify_group_message(0, 1, H256::repeat_byte(2)), Ok(true));
+	});
+}
+
+#[test]
+fn groups_with_different_retentions_are_pruned_together() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::create_group(RuntimeOrigin::signed(2), group_key(), 4));
+		assert_ok!(Messaging::set_group_retention(RuntimeOrigin::signed(1), 0, 300));
+		assert_ok!(Messaging::set_group_retention(RuntimeOrigin::signed(2), 1, 100));
+
+		// Group 1 messages are sent later but expire earlier
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(1),
+			0,
+			H256::repeat_byte(1)
+		));
+		System::set_block_number(51);
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(2),
+			1,
+			H256::repeat_byte(2)
+		));
+		assert_eq!(crate::GroupMessages::<Test>::get(0, 0).unwrap().expires_at, 301);
+		assert_eq!(crate::GroupMessages::<Test>::get(1, 1).unwrap().expires_at, 151);
+
+		System::set_block_number(200);
+		assert_eq!(Messaging::verify_group_message(0, 0, H256::repeat_byte(1)), Ok(true));
+		assert_noop!(
+			Messaging::verify_group_message(1, 1, H256::repeat_byte(2)),
+			Error::<Test>::MessageExpired
+		);
+
+		System::set_block_number(302);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 302));
+		assert!(!crate::GroupMessages::<Test>::contains_key(0, 0));
+		assert!(!crate::GroupMessages::<Test>::contains_key(1, 1));
+		System::assert_has_event(Event::ExpiredGroupMessagesPruned { count: 2, next: 2 }.into());
+	});
+}
+
+#[test]
+fn last_ordinary_member_can_leave() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(2), 0));
+		System::assert_last_event(Event::MemberLeft { group_id: 0, who: 2 }.into());
+
+		assert!(!crate::GroupMembers::<Test>::contains_key(0, 2));
+		assert_eq!(crate::GroupCount::<Test>::get(2), 0);
+		let group = crate::Groups::<Test>::get(0).unwrap();
+		assert_eq!(group.member_count, 1);
+		assert!(group.rotation_required);
+		assert_eq!(group.status, crate::GroupStatus::Active);
+
+		assert_noop!(
+			Messaging::leave_group(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::NotGroupMember
+		);
+		assert_noop!(
+			Messaging::leave_group(RuntimeOrigin::signed(2), 1),
+			Error::<Test>::GroupNotFound
+		);
+	});
+}
+
+#[test]
+fn leaving_admins_withdraw_ownership_offers() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(2), 0));
+
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().admin_count, 0);
+		assert!(!crate::PendingGroupOwners::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn owner_leaving_passes_ownership_to_longest_standing_admin() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		System::set_block_number(2);
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 3));
+		System::set_block_number(3);
+		for who in [2, 5] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		for who in [2, 3] {
+			assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(1), 0));
+		System::assert_has_event(Event::MemberLeft { group_id: 0, who: 1 }.into());
+		System::assert_last_event(
+			Event::GroupOwnershipTransferred { group_id: 0, old_owner: 1, new_owner: 3 }.into(),
+		);
+
+		// Admin 3 joined first
+		let group = crate::Groups::<Test>::get(0).unwrap();
+		assert_eq!(group.owner, 3);
+		assert_eq!(group.status, crate::GroupStatus::Active);
+		assert_eq!(group.member_count, 3);
+		assert_eq!(group.admin_count, 1);
+		assert!(group.rotation_required);
+		assert_eq!(Messaging::group_role(0, &3), Some(GroupRole::Owner));
+		assert_eq!(Messaging::group_role(0, &2), Some(GroupRole::Admin));
+		assert_eq!(crate::GroupCount::<Test>::get(1), 0);
+
+		// The deposit moved and the pending offer lapsed
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 0);
+		assert_eq!(held(HoldReason::GroupDeposit, 3), 70);
+		assert!(!crate::PendingGroupOwners::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn owner_leaving_skips_admins_unable_to_cover_the_deposit() {
+	use frame_support::traits::fungible::Mutate;
+
+	new_test_ext().execute_with(|| {
+		setup_group();
+		System::set_block_number(2);
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 3));
+		System::set_block_number(3);
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		for who in [2, 3] {
+			assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&3, 30));
+
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(1), 0));
+
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().owner, 2);
+		assert_eq!(held(HoldReason::GroupDeposit, 2), 60);
+		assert_eq!(held(HoldReason::GroupDeposit, 3), 0);
+	});
+}
+
+#[test]
+fn owner_leaving_without_admins_freezes_group() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(Event::GroupFrozen { group_id: 0 }.into());
+
+		// The deposit stays with the owner that left
+		let group = crate::Groups::<Test>::get(0).unwrap();
+		assert_eq!(group.status, crate::GroupStatus::Frozen);
+		assert_eq!(group.owner, 1);
+		assert_eq!(group.member_count, 2);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 60);
+		assert_eq!(Messaging::group_role(0, &1), None);
+
+		// Nothing but leaving works
+		assert_noop!(
+			Messaging::send_group_message_hash(RuntimeOrigin::signed(2), 0, H256::repeat_byte(1)),
+			Error::<Test>::GroupFrozen
+		);
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 5),
+			Error::<Test>::GroupFrozen
+		);
+		assert_noop!(
+			Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::GroupFrozen
+		);
+		assert_noop!(
+			Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(1), 1),
+			Error::<Test>::GroupFrozen
+		);
+
+		for who in [2, 3] {
+			assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(who), 0));
+		}
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().member_count, 0);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 40);
+	});
+}
+
+#[test]
+fn owner_leaving_alone_freezes_group() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(Event::GroupFrozen { group_id: 0 }.into());
+
+		let group = crate::Groups::<Test>::get(0).unwrap();
+		assert_eq!(group.status, crate::GroupStatus::Frozen);
+		assert_eq!(group.member_count, 0);
+		assert_eq!(crate::GroupCount::<Test>::get(1), 0);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 40);
+	});
+}
+
+#[test]
+fn group_invites_work() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2));
+		System::assert_last_event(
+			Event::GroupInviteSent { group_id: 0, who: 2, invited_by: 1, expires_at: 11 }.into(),
+		);
+		assert_eq!(
+			crate::GroupInvites::<Test>::get(0, 2),
+			Some(crate::GroupInvite { invited_by: 1, expires_at: 11 })
+		);
+		assert_eq!(crate::GroupInviteCount::<Test>::get(0), 1);
+		assert_eq!(crate::PendingGroupInviteCount::<Test>::get(2), 1);
+
+		// Invites do not make members
+		assert_eq!(Messaging::group_role(0, &2), None);
+
+		System::set_block_number(11);
+		assert_ok!(Messaging::accept_group_invite(RuntimeOrigin::signed(2), 0));
+		System::assert_has_event(Event::GroupInviteAccepted { group_id: 0, who: 2 }.into());
+		System::assert_last_event(Event::MemberAdded { group_id: 0, who: 2 }.into());
+
+		assert_eq!(
+			crate::GroupMembers::<Test>::get(0, 2),
+			Some(crate::MemberInfo { joined_at: 11, role: GroupRole::Member })
+		);
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().member_count, 2);
+		assert_eq!(crate::GroupCount::<Test>::get(2), 1);
+		assert!(!crate::GroupInvites::<Test>::contains_key(0, 2));
+		assert_eq!(crate::GroupInviteCount::<Test>::get(0), 0);
+		assert_eq!(crate::PendingGroupInviteCount::<Test>::get(2), 0);
+
+		assert_noop!(
+			Messaging::accept_group_invite(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::GroupInviteNotFound
+		);
+	});
+}
+
+#[test]
+fn invite_to_group_checks_invitee() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_noop!(
+			Messaging::invite_to_group(RuntimeOrigin::signed(3), 0, 5),
+			Error::<Test>::NotGroupAdmin
+		);
+		assert_noop!(
+			Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 9),
+			Error::<Test>::RecipientNotFound
+		);
+		assert_noop!(
+			Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::AlreadyGroupMember
+		);
+
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 3));
+		assert_noop!(
+			Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 3),
+			Error::<Test>::AlreadyGroupInvited
+		);
+	});
+}
+
+#[test]
+fn group_invites_are_bounded() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+
+		// MaxGroupInvites is 2
+		for who in [2, 3] {
+			assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_noop!(
+			Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 5),
+			Error::<Test>::TooManyGroupInvites
+		);
+
+		// MaxPendingGroupInvites is 2
+		for _ in 0..2 {
+			assert_ok!(Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 4));
+		}
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 1, 2));
+		assert_noop!(
+			Messaging::invite_to_group(RuntimeOrigin::signed(1), 2, 2),
+			Error::<Test>::TooManyPendingGroupInvites
+		);
+
+		// Declining frees both slots
+		assert_ok!(Messaging::decline_group_invite(RuntimeOrigin::signed(2), 0));
+		System::assert_last_event(Event::GroupInviteDeclined { group_id: 0, who: 2 }.into());
+		assert_eq!(crate::GroupInviteCount::<Test>::get(0), 1);
+		assert_eq!(crate::PendingGroupInviteCount::<Test>::get(2), 1);
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 5));
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 2, 2));
+
+		assert_noop!(
+			Messaging::decline_group_invite(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::GroupInviteNotFound
+		);
+	});
+}
+
+#[test]
+fn expired_group_invites_are_removed() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, who));
+		}
+
+		assert_noop!(
+			Messaging::remove_expired_group_invite(RuntimeOrigin::signed(5), 0, 2),
+			Error::<Test>::GroupInviteNotExpired
+		);
+
+		// GroupInviteExpiry is 10
+		System::set_block_number(12);
+		assert_noop!(
+			Messaging::accept_group_invite(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::GroupInviteExpired
+		);
+
+		// Anyone can clean up
+		assert_ok!(Messaging::remove_expired_group_invite(RuntimeOrigin::signed(5), 0, 2));
+		System::assert_last_event(Event::GroupInviteExpired { group_id: 0, who: 2 }.into());
+		assert!(!crate::GroupInvites::<Test>::contains_key(0, 2));
+		assert_eq!(crate::PendingGroupInviteCount::<Test>::get(2), 0);
+
+		// Inviting again replaces a lapsed invite
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 3));
+		System::assert_has_event(Event::GroupInviteExpired { group_id: 0, who: 3 }.into());
+		assert_eq!(crate::GroupInvites::<Test>::get(0, 3).unwrap().expires_at, 22);
+		assert_eq!(crate::GroupInviteCount::<Test>::get(0), 1);
+		assert_ok!(Messaging::accept_group_invite(RuntimeOrigin::signed(3), 0));
+	});
+}
+
+#[test]
+fn invite_only_groups_reject_direct_adds() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+
+		assert_noop!(
+			Messaging::set_group_invite_only(RuntimeOrigin::signed(2), 0, true),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_ok!(Messaging::set_group_invite_only(RuntimeOrigin::signed(1), 0, true));
+		System::assert_last_event(Event::GroupInviteOnlySet { group_id: 0, invite_only: true }.into());
+
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::GroupInviteRequired
+		);
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::accept_group_invite(RuntimeOrigin::signed(2), 0));
+	});
+}
+
+#[test]
+fn adding_a_member_clears_its_invite() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert!(!crate::GroupInvites::<Test>::contains_key(0, 2));
+		assert_eq!(crate::GroupInviteCount::<Test>::get(0), 0);
+		assert_eq!(crate::PendingGroupInviteCount::<Test>::get(2), 0);
+	});
+}
+
+#[test]
+fn accounts_at_group_limit_cannot_accept_invites() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2));
+
+		// MaxGroupsPerAccount is 3
+		for _ in 0..3 {
+			assert_ok!(Messaging::create_group(RuntimeOrigin::signed(2), group_key(), 4));
+		}
+
+		assert_noop!(
+			Messaging::accept_group_invite(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::TooManyGroups
+		);
+
+		// The invite stays pending until the account makes room
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(2), 1));
+		assert_ok!(Messaging::accept_group_invite(RuntimeOrigin::signed(2), 0));
+	});
+}
+
+#[test]
+fn group_deposit_scales_with_members() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		let free = Balances::free_balance(1);
+
+		// GroupBaseDeposit is 40 and GroupPerMemberDeposit 10
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 50);
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 60);
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 3));
+		assert_ok!(Messaging::accept_group_invite(RuntimeOrigin::signed(3), 0));
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 70);
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().deposit, 70);
+		assert_eq!(Balances::free_balance(1), free - 20);
+
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 60);
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(3), 0));
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 50);
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().deposit, 50);
+		assert_eq!(Balances::free_balance(1), free);
+
+		// Members never pay
+		assert_eq!(held(HoldReason::GroupDeposit, 2), 0);
+		assert_eq!(held(HoldReason::GroupDeposit, 3), 0);
+	});
+}
+
+#[test]
+fn adding_members_fails_without_owner_deposit() {
+	use frame_support::traits::fungible::Mutate;
+
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(2), 0, 5));
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, 5));
+
+		// Admins add members at the owner's expense
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(2), 0, 3),
+			Error::<Test>::InsufficientGroupDeposit
+		);
+		assert_noop!(
+			Messaging::accept_group_invite(RuntimeOrigin::signed(5), 0),
+			Error::<Test>::InsufficientGroupDeposit
+		);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 60);
+	});
+}
+
+#[test]
+fn ownership_transfer_moves_the_scaled_deposit() {
+	use frame_support::traits::fungible::Mutate;
+
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 2));
+		let (free_1, free_2) = (Balances::free_balance(1), Balances::free_balance(2));
+
+		// All or nothing: a new owner short of the full deposit takes none of it
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&2, 65));
+		assert_noop!(
+			Messaging::accept_group_ownership(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::InsufficientGroupDeposit
+		);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 70);
+		assert_eq!(held(HoldReason::GroupDeposit, 2), 0);
+
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&2, free_2));
+		assert_ok!(Messaging::accept_group_ownership(RuntimeOrigin::signed(2), 0));
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 0);
+		assert_eq!(held(HoldReason::GroupDeposit, 2), 70);
+		assert_eq!(Balances::free_balance(1), free_1 + 70);
+		assert_eq!(Balances::free_balance(2), free_2 - 70);
+
+		// Later departures release to the new owner
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(3), 0));
+		assert_eq!(held(HoldReason::GroupDeposit, 2), 60);
+	});
+}
+
+fn send_to_group_0(who: u64) -> sp_runtime::DispatchResult {
+	Messaging::send_group_message_hash(RuntimeOrigin::signed(who), 0, H256::repeat_byte(1))
+}
+
+#[test]
+fn muted_members_cannot_send_until_the_deadline() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(1), 0, 2, 10));
+		System::assert_last_event(
+			Event::MemberMuted { group_id: 0, who: 2, until: 11, muted_by: 1 }.into(),
+		);
+		assert_eq!(Messaging::muted_until(0, &2), Some(11));
+		assert_noop!(send_to_group_0(2), Error::<Test>::MemberMuted);
+		assert_ok!(send_to_group_0(1));
+
+		System::set_block_number(10);
+		assert_noop!(send_to_group_0(2), Error::<Test>::MemberMuted);
+
+		// The mute lapses at its deadline and is removed by the next send
+		System::set_block_number(11);
+		assert_eq!(Messaging::muted_until(0, &2), None);
+		assert!(crate::GroupMutes::<Test>::contains_key(0, 2));
+		assert_ok!(send_to_group_0(2));
+		assert!(!crate::GroupMutes::<Test>::contains_key(0, 2));
+	});
+}
+
+#[test]
+fn mute_member_checks_roles() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3, 5] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_noop!(
+			Messaging::mute_member(RuntimeOrigin::signed(3), 0, 5, 10),
+			Error::<Test>::NotGroupModerator
+		);
+		assert_noop!(
+			Messaging::mute_member(RuntimeOrigin::signed(2), 0, 1, 10),
+			Error::<Test>::CannotMuteGroupOwner
+		);
+		assert_noop!(
+			Messaging::mute_member(RuntimeOrigin::signed(2), 0, 4, 10),
+			Error::<Test>::NotGroupMember
+		);
+		assert_noop!(
+			Messaging::mute_member(RuntimeOrigin::signed(2), 0, 3, 0),
+			Error::<Test>::ZeroMuteDuration
+		);
+
+		// Admins can mute ordinary members, only the owner can mute admins
+		assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(2), 0, 3, 10));
+		assert_noop!(
+			Messaging::unmute_member(RuntimeOrigin::signed(2), 0, 2),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(1), 0, 2, 10));
+		assert_noop!(send_to_group_0(2), Error::<Test>::MemberMuted);
+		assert_noop!(
+			Messaging::unmute_member(RuntimeOrigin::signed(2), 0, 2),
+			Error::<Test>::NotGroupOwner
+		);
+	});
+}
+
+#[test]
+fn unmute_member_works() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_noop!(
+			Messaging::unmute_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::MemberNotMuted
+		);
+
+		assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(1), 0, 2, 10));
+		assert_ok!(Messaging::unmute_member(RuntimeOrigin::signed(1), 0, 2));
+		System::assert_last_event(
+			Event::MemberUnmuted { group_id: 0, who: 2, unmuted_by: 1 }.into(),
+		);
+		assert_ok!(send_to_group_0(2));
+
+		// Lapsed mutes cannot be lifted
+		assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(1), 0, 2, 10));
+		System::set_block_number(11);
+		assert_noop!(
+			Messaging::unmute_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::MemberNotMuted
+		);
+	});
+}
+
+#[test]
+fn leaving_a_group_clears_mutes() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(1), 0, 2, 100));
+		assert_ok!(Messaging::mute_group(RuntimeOrigin::signed(2), 0));
+		assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(1), 0, 3, 100));
+		assert_ok!(Messaging::mute_group(RuntimeOrigin::signed(3), 0));
+
+		assert_ok!(Messaging::remove_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(3), 0));
+		for who in [2, 3] {
+			assert!(!crate::GroupMutes::<Test>::contains_key(0, who));
+			assert!(!crate::MutedGroups::<Test>::contains_key(0, who));
+		}
+
+		// Rejoining starts afresh
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::rotate_group_key(RuntimeOrigin::signed(1), 0, rotated_key(1), 1));
+		assert_ok!(send_to_group_0(2));
+	});
+}
+
+#[test]
+fn posting_policies_restrict_senders() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_eq!(Messaging::posting_policy(0), PostingPolicy::Everyone);
+		for who in [1, 2, 3] {
+			assert_ok!(send_to_group_0(who));
+		}
+
+		assert_noop!(
+			Messaging::set_posting_policy(RuntimeOrigin::signed(2), 0, PostingPolicy::OwnerOnly),
+			Error::<Test>::NotGroupOwner
+		);
+
+		assert_ok!(Messaging::set_posting_policy(
+			RuntimeOrigin::signed(1),
+			0,
+			PostingPolicy::AdminsOnly
+		));
+		System::assert_last_event(
+			Event::GroupPostingPolicySet { group_id: 0, policy: PostingPolicy::AdminsOnly }
+				.into(),
+		);
+		System::set_block_number(11);
+		assert_ok!(send_to_group_0(1));
+		assert_ok!(send_to_group_0(2));
+		assert_noop!(send_to_group_0(3), Error::<Test>::PostingRestricted);
+
+		assert_ok!(Messaging::set_posting_policy(
+			RuntimeOrigin::signed(1),
+			0,
+			PostingPolicy::OwnerOnly
+		));
+		assert_ok!(send_to_group_0(1));
+		assert_noop!(send_to_group_0(2), Error::<Test>::PostingRestricted);
+		assert_noop!(send_to_group_0(3), Error::<Test>::PostingRestricted);
+
+		// Back to the default
+		assert_ok!(Messaging::set_posting_policy(
+			RuntimeOrigin::signed(1),
+			0,
+			PostingPolicy::Everyone
+		));
+		assert!(!crate::GroupPostingPolicies::<Test>::contains_key(0));
+		assert_ok!(send_to_group_0(3));
+	});
+}
+
+#[test]
+fn muting_a_group_is_personal() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_noop!(
+			Messaging::mute_group(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::NotGroupMember
+		);
+		assert_noop!(
+			Messaging::unmute_group(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::GroupNotMuted
+		);
+
+		assert_ok!(Messaging::mute_group(RuntimeOrigin::signed(2), 0));
+		System::assert_last_event(Event::GroupMuted { group_id: 0, who: 2 }.into());
+		assert!(crate::MutedGroups::<Test>::contains_key(0, 2));
+		assert!(!crate::MutedGroups::<Test>::contains_key(0, 1));
+		assert_noop!(
+			Messaging::mute_group(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::GroupAlreadyMuted
+		);
+
+		// Sending is unaffected
+		assert_ok!(send_to_group_0(2));
+
+		assert_ok!(Messaging::unmute_group(RuntimeOrigin::signed(2), 0));
+		System::assert_last_event(Event::GroupUnmuted { group_id: 0, who: 2 }.into());
+		assert!(!crate::MutedGroups::<Test>::contains_key(0, 2));
+	});
+}
+
+#[test]
+fn banning_a_member_removes_it_and_keeps_it_out() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		let reason = H256::repeat_byte(9);
+
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 2, reason));
+		System::assert_has_event(
+			Event::MemberRemoved { group_id: 0, who: 2, removed_by: 1 }.into(),
+		);
+		System::assert_last_event(
+			Event::BannedFromGroup { group_id: 0, who: 2, banned_by: 1, reason_hash: reason }
+				.into(),
+		);
+		assert_eq!(
+			crate::GroupBans::<Test>::get(0, 2),
+			Some(crate::BanInfo { banned_by: 1, reason, at: 1 })
+		);
+		assert_eq!(crate::GroupBanCount::<Test>::get(0), 1);
+		assert_eq!(Messaging::group_role(0, &2), None);
+		assert_eq!(crate::GroupCount::<Test>::get(2), 0);
+		let group = crate::Groups::<Test>::get(0).unwrap();
+		assert_eq!(group.member_count, 1);
+		assert!(group.rotation_required);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 50);
+
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::BannedFromGroup
+		);
+		assert_noop!(
+			Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::BannedFromGroup
+		);
+		assert_noop!(
+			Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 2, reason),
+			Error::<Test>::BannedFromGroup
+		);
+
+		// Other groups are unaffected
+		assert_ok!(Messaging::create_group(RuntimeOrigin::signed(3), group_key(), 4));
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(3), 1, 2));
+	});
+}
+
+#[test]
+fn banning_drops_pending_invites() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 2, H256::zero()));
+		assert!(!crate::GroupInvites::<Test>::contains_key(0, 2));
+		assert_eq!(crate::GroupInviteCount::<Test>::get(0), 0);
+		assert_eq!(crate::PendingGroupInviteCount::<Test>::get(2), 0);
+		assert_noop!(
+			Messaging::accept_group_invite(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::GroupInviteNotFound
+		);
+	});
+}
+
+#[test]
+fn ban_from_group_checks_roles() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_noop!(
+			Messaging::ban_from_group(RuntimeOrigin::signed(3), 0, 5, H256::zero()),
+			Error::<Test>::NotGroupAdmin
+		);
+		assert_noop!(
+			Messaging::ban_from_group(RuntimeOrigin::signed(2), 0, 1, H256::zero()),
+			Error::<Test>::CannotBanGroupOwner
+		);
+
+		// Admins can ban ordinary members and non-members, only the owner can ban admins
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(2), 0, 5, H256::zero()));
+		assert_ok!(Messaging::lift_group_ban(RuntimeOrigin::signed(2), 0, 5));
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(2), 0, 3, H256::zero()));
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 5));
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 5));
+		assert_noop!(
+			Messaging::ban_from_group(RuntimeOrigin::signed(2), 0, 5, H256::zero()),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 5, H256::zero()));
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().admin_count, 1);
+	});
+}
+
+#[test]
+fn group_bans_are_bounded() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+
+		// MaxGroupBans is 2
+		for who in [2, 3] {
+			assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, who, H256::zero()));
+		}
+		assert_noop!(
+			Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 5, H256::zero()),
+			Error::<Test>::TooManyGroupBans
+		);
+
+		assert_ok!(Messaging::lift_group_ban(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 5, H256::zero()));
+	});
+}
+
+#[test]
+fn lift_group_ban_works() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_noop!(
+			Messaging::lift_group_ban(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::NotBannedFromGroup
+		);
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 2, H256::zero()));
+		assert_noop!(
+			Messaging::lift_group_ban(RuntimeOrigin::signed(2), 0, 2),
+			Error::<Test>::NotGroupAdmin
+		);
+
+		assert_ok!(Messaging::lift_group_ban(RuntimeOrigin::signed(1), 0, 2));
+		System::assert_last_event(
+			Event::GroupBanLifted { group_id: 0, who: 2, lifted_by: 1 }.into(),
+		);
+		assert!(!crate::GroupBans::<Test>::contains_key(0, 2));
+		assert_eq!(crate::GroupBanCount::<Test>::get(0), 0);
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2));
+	});
+}
+
+#[test]
+fn group_bans_survive_reregistration() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 2, H256::zero()));
+
+		// Losing the profile to an account ban and registering again does not lift it
+		ban(2);
+		assert_ok!(Messaging::unban_account(RuntimeOrigin::root(), 2));
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![1, 2, 3, 4]));
+		assert_noop!(
+			Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2),
+			Error::<Test>::BannedFromGroup
+		);
+	});
+}
+
+#[test]
+fn disband_group_removes_everything_over_several_calls() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::transfer_group_ownership(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 5));
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 4, H256::zero()));
+		assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(1), 0, 3, 100));
+		assert_ok!(Messaging::mute_group(RuntimeOrigin::signed(3), 0));
+		assert_ok!(Messaging::set_group_retention(RuntimeOrigin::signed(1), 0, 100));
+		assert_ok!(Messaging::set_posting_policy(
+			RuntimeOrigin::signed(1),
+			0,
+			PostingPolicy::AdminsOnly
+		));
+		for who in [1, 2] {
+			assert_ok!(send_to_group_0(who));
+		}
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 70);
+
+		// MaxDisbandRemovalsPerCall is 3: an invite, a ban and three members take two calls
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::signed(1), 0));
+		assert_ok!(Messaging::do_try_state());
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().status, crate::GroupStatus::Disbanding);
+		assert!(!crate::PendingGroupOwners::<Test>::contains_key(0));
+		assert!(!crate::GroupMessages::<Test>::contains_key(0, 0));
+		assert!(!crate::GroupMessageIds::<Test>::contains_key(1));
+		assert!(!crate::GroupInvites::<Test>::contains_key(0, 5));
+		assert_eq!(crate::PendingGroupInviteCount::<Test>::get(5), 0);
+		assert!(!crate::GroupBans::<Test>::contains_key(0, 4));
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 70);
+
+		// Nothing else can change a disbanding group
+		assert_noop!(send_to_group_0(1), Error::<Test>::GroupDisbanding);
+		assert_noop!(
+			Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 5),
+			Error::<Test>::GroupDisbanding
+		);
+		assert_noop!(
+			Messaging::accept_group_ownership(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::NoPendingOwnershipTransfer
+		);
+		let remaining = crate::GroupMembers::<Test>::iter_key_prefix(0).next().unwrap();
+		assert_noop!(
+			Messaging::leave_group(RuntimeOrigin::signed(remaining), 0),
+			Error::<Test>::GroupDisbanding
+		);
+		assert_noop!(
+			Messaging::remove_group_member(RuntimeOrigin::signed(remaining), 0, remaining),
+			Error::<Test>::GroupDisbanding
+		);
+
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(
+			Event::GroupDisbanded { group_id: 0, owner: 1, deposit: 70 }.into(),
+		);
+		assert_ok!(Messaging::do_try_state());
+		assert!(!crate::Groups::<Test>::contains_key(0));
+		assert!(crate::GroupMembers::<Test>::iter_key_prefix(0).next().is_none());
+		assert!(!crate::GroupMutes::<Test>::contains_key(0, 3));
+		assert!(!crate::MutedGroups::<Test>::contains_key(0, 3));
+		assert!(!crate::GroupKeyHistory::<Test>::contains_key(0));
+		assert!(!crate::GroupRetentions::<Test>::contains_key(0));
+		assert!(!crate::GroupPostingPolicies::<Test>::contains_key(0));
+		assert!(!crate::GroupRateLimits::<Test>::contains_key(0));
+		assert_eq!(crate::GroupInviteCount::<Test>::get(0), 0);
+		assert_eq!(crate::GroupBanCount::<Test>::get(0), 0);
+		for who in [1, 2, 3] {
+			assert_eq!(crate::GroupCount::<Test>::get(who), 0);
+		}
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 0);
+
+		assert_noop!(
+			Messaging::disband_group(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::GroupNotFound
+		);
+	});
+}
+
+#[test]
+fn disband_group_continues_through_many_messages() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for _ in 0..5 {
+			assert_ok!(send_to_group_0(1));
+		}
+
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::signed(1), 0));
+		assert_ok!(Messaging::do_try_state());
+		assert_eq!(crate::GroupMessages::<Test>::iter_key_prefix(0).count(), 2);
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().member_count, 0);
+
+		// Governance can finish what the owner started
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::root(), 0));
+		assert_ok!(Messaging::do_try_state());
+		assert!(!crate::Groups::<Test>::contains_key(0));
+		assert!(crate::GroupMessageIds::<Test>::iter().next().is_none());
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 0);
+	});
+}
+
+#[test]
+fn disband_group_finishes_when_the_budget_is_exactly_used() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for who in [2, 3] {
+			assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+		}
+		for _ in 0..3 {
+			assert_ok!(send_to_group_0(1));
+		}
+
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(
+			Event::GroupDisbanded { group_id: 0, owner: 1, deposit: 70 }.into(),
+		);
+		assert!(!crate::Groups::<Test>::contains_key(0));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn disband_group_checks_origin() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_noop!(
+			Messaging::disband_group(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_noop!(
+			Messaging::disband_group(RuntimeOrigin::signed(1), 1),
+			Error::<Test>::GroupNotFound
+		);
+
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::root(), 0));
+		assert!(!crate::Groups::<Test>::contains_key(0));
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 0);
+	});
+}
+
+#[test]
+fn departed_owner_can_disband_frozen_group() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(1), 0));
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 50);
+
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(
+			Event::GroupDisbanded { group_id: 0, owner: 1, deposit: 50 }.into(),
+		);
+		assert_eq!(held(HoldReason::GroupDeposit, 1), 0);
+		assert_eq!(crate::GroupCount::<Test>::get(2), 0);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+/// Let account 1 list group 0 for discovery with a four-byte description CID.
+fn list_group_0(is_discoverable: bool) -> frame_support::dispatch::DispatchResult {
+	Messaging::set_group_metadata(
+		RuntimeOrigin::signed(1),
+		0,
+		H256::from([1; 32]),
+		vec![1, 2, 3, 4].try_into().unwrap(),
+		is_discoverable,
+	)
+}
+
+#[test]
+fn set_group_metadata_holds_deposit_and_toggles_discovery() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+
+		// 32 bytes of name hash, 5 of description CID and the flag
+		assert_ok!(list_group_0(true));
+		System::assert_last_event(
+			Event::GroupMetadataSet { group_id: 0, is_discoverable: true, deposit: 38 }.into(),
+		);
+		assert_eq!(held(HoldReason::GroupMetadataDeposit, 1), 38);
+		assert!(crate::DiscoverableGroups::<Test>::contains_key(0));
+		assert_eq!(crate::DiscoverableGroupCount::<Test>::get(), 1);
+
+		// Replacing the metadata replaces the deposit
+		assert_ok!(Messaging::set_group_metadata(
+			RuntimeOrigin::signed(1),
+			0,
+			H256::from([2; 32]),
+			vec![1; 8].try_into().unwrap(),
+			false,
+		));
+		assert_eq!(held(HoldReason::GroupMetadataDeposit, 1), 42);
+		assert!(!crate::DiscoverableGroups::<Test>::contains_key(0));
+		assert_eq!(crate::DiscoverableGroupCount::<Test>::get(), 0);
+		let metadata = crate::GroupsMetadata::<Test>::get(0).unwrap();
+		assert_eq!(metadata.name_hash, H256::from([2; 32]));
+		assert!(!metadata.is_discoverable);
+
+		assert_ok!(list_group_0(true));
+		assert_eq!(crate::DiscoverableGroupCount::<Test>::get(), 1);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn set_group_metadata_is_owner_only() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+
+		assert_noop!(
+			Messaging::set_group_metadata(
+				RuntimeOrigin::signed(2),
+				0,
+				H256::from([1; 32]),
+				Default::default(),
+				true,
+			),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_noop!(
+			Messaging::clear_group_metadata(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::NotGroupOwner
+		);
+	});
+}
+
+#[test]
+fn clear_group_metadata_refunds_deposit() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_noop!(
+			Messaging::clear_group_metadata(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::GroupMetadataNotFound
+		);
+		assert_ok!(list_group_0(true));
+
+		assert_ok!(Messaging::clear_group_metadata(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(
+			Event::GroupMetadataCleared { group_id: 0, depositor: 1, refunded: 38 }.into(),
+		);
+		assert_eq!(held(HoldReason::GroupMetadataDeposit, 1), 0);
+		assert!(!crate::GroupsMetadata::<Test>::contains_key(0));
+		assert!(!crate::DiscoverableGroups::<Test>::contains_key(0));
+		assert_eq!(crate::DiscoverableGroupCount::<Test>::get(), 0);
+	});
+}
+
+#[test]
+fn discoverable_groups_are_bounded() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for owner in [2, 3] {
+			assert_ok!(Messaging::create_group(RuntimeOrigin::signed(owner), group_key(), 4));
+		}
+		let list = |owner: u64, group_id, is_discoverable| {
+			Messaging::set_group_metadata(
+				RuntimeOrigin::signed(owner),
+				group_id,
+				H256::from([1; 32]),
+				Default::default(),
+				is_discoverable,
+			)
+		};
+
+		// MaxDiscoverableGroups is 2
+		assert_ok!(list(1, 0, true));
+		assert_ok!(list(2, 1, true));
+		assert_noop!(list(3, 2, true), Error::<Test>::TooManyDiscoverableGroups);
+		// Groups already listed can update their metadata, and unlisted ones set any
+		assert_ok!(list(1, 0, true));
+		assert_ok!(list(3, 2, false));
+
+		let mut listed = view(DiscoverableGroupsViewFunction::<Test>::new(None, 10));
+		listed.sort();
+		assert_eq!(listed, vec![0, 1]);
+		let first = view(DiscoverableGroupsViewFunction::<Test>::new(None, 1));
+		assert_eq!(first.len(), 1);
+		let rest = view(DiscoverableGroupsViewFunction::<Test>::new(Some(first[0]), 10));
+		assert_eq!(rest.len(), 1);
+		assert_ne!(rest, first);
+
+		assert_ok!(list(2, 1, false));
+		assert_ok!(list(3, 2, true));
+		let mut listed = view(DiscoverableGroupsViewFunction::<Test>::new(None, 10));
+		listed.sort();
+		assert_eq!(listed, vec![0, 2]);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn join_request_can_be_approved() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_noop!(
+			Messaging::request_to_join_group(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::GroupNotDiscoverable
+		);
+		assert_ok!(list_group_0(true));
+		System::set_block_number(5);
+
+		assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(2), 0));
+		System::assert_last_event(
+			Event::GroupJoinRequested { group_id: 0, who: 2, expires_at: 15 }.into(),
+		);
+		assert_eq!(crate::GroupJoinRequestCount::<Test>::get(0), 1);
+		assert_noop!(
+			Messaging::request_to_join_group(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::AlreadyRequestedToJoin
+		);
+		assert_noop!(
+			Messaging::request_to_join_group(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::AlreadyGroupMember
+		);
+		assert_noop!(
+			Messaging::request_to_join_group(RuntimeOrigin::signed(4), 0),
+			Error::<Test>::ProfileNotFound
+		);
+		assert_noop!(
+			Messaging::approve_join_request(RuntimeOrigin::signed(3), 0, 2),
+			Error::<Test>::NotGroupModerator
+		);
+		assert_noop!(
+			Messaging::approve_join_request(RuntimeOrigin::signed(1), 0, 3),
+			Error::<Test>::JoinRequestNotFound
+		);
+
+		assert_ok!(Messaging::approve_join_request(RuntimeOrigin::signed(1), 0, 2));
+		System::assert_has_event(
+			Event::GroupJoinRequestApproved { group_id: 0, who: 2, approved_by: 1 }.into(),
+		);
+		System::assert_last_event(Event::MemberAdded { group_id: 0, who: 2 }.into());
+		assert_eq!(Messaging::group_role(0, &2), Some(GroupRole::Member));
+		assert!(!crate::GroupJoinRequests::<Test>::contains_key(0, 2));
+		assert_eq!(crate::GroupJoinRequestCount::<Test>::get(0), 0);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn join_requests_expire_and_can_be_cancelled() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(list_group_0(true));
+		for who in [2, 3] {
+			assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(who), 0));
+		}
+		// MaxGroupJoinRequests is 2
+		assert_noop!(
+			Messaging::request_to_join_group(RuntimeOrigin::signed(5), 0),
+			Error::<Test>::TooManyJoinRequests
+		);
+
+		// Only the requester and admins can cancel a request before it expires
+		assert_noop!(
+			Messaging::cancel_join_request(RuntimeOrigin::signed(5), 0, 2),
+			Error::<Test>::JoinRequestNotExpired
+		);
+		assert_ok!(Messaging::cancel_join_request(RuntimeOrigin::signed(2), 0, 2));
+		System::assert_last_event(
+			Event::GroupJoinRequestCancelled { group_id: 0, who: 2 }.into(),
+		);
+		assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(5), 0));
+		assert_ok!(Messaging::cancel_join_request(RuntimeOrigin::signed(1), 0, 5));
+
+		System::set_block_number(12);
+		assert_noop!(
+			Messaging::approve_join_request(RuntimeOrigin::signed(1), 0, 3),
+			Error::<Test>::JoinRequestExpired
+		);
+		// Expired requests are replaced, or removed by anyone
+		assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(3), 0));
+		System::assert_last_event(
+			Event::GroupJoinRequested { group_id: 0, who: 3, expires_at: 22 }.into(),
+		);
+		assert_eq!(crate::GroupJoinRequestCount::<Test>::get(0), 1);
+		System::set_block_number(23);
+		assert_ok!(Messaging::cancel_join_request(RuntimeOrigin::signed(5), 0, 3));
+		assert_eq!(crate::GroupJoinRequestCount::<Test>::get(0), 0);
+		assert_noop!(
+			Messaging::cancel_join_request(RuntimeOrigin::signed(5), 0, 3),
+			Error::<Test>::JoinRequestNotFound
+		);
+	});
+}
+
+#[test]
+fn join_requests_end_with_invites_and_bans() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(list_group_0(true));
+		for who in [2, 3] {
+			assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(who), 0));
+		}
+
+		// Joining through an invite drops the request
+		assert_ok!(Messaging::invite_to_group(RuntimeOrigin::signed(1), 0, 2));
+		assert_ok!(Messaging::accept_group_invite(RuntimeOrigin::signed(2), 0));
+		assert!(!crate::GroupJoinRequests::<Test>::contains_key(0, 2));
+
+		assert_ok!(Messaging::ban_from_group(RuntimeOrigin::signed(1), 0, 3, H256::zero()));
+		assert!(!crate::GroupJoinRequests::<Test>::contains_key(0, 3));
+		assert_eq!(crate::GroupJoinRequestCount::<Test>::get(0), 0);
+		assert_noop!(
+			Messaging::request_to_join_group(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::BannedFromGroup
+		);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn disband_group_removes_metadata_and_join_requests() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(list_group_0(true));
+		for who in [2, 3] {
+			assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(who), 0));
+		}
+
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::signed(1), 0));
+		System::assert_has_event(
+			Event::GroupMetadataCleared { group_id: 0, depositor: 1, refunded: 38 }.into(),
+		);
+		System::assert_last_event(
+			Event::GroupDisbanded { group_id: 0, owner: 1, deposit: 50 }.into(),
+		);
+		assert_eq!(held(HoldReason::GroupMetadataDeposit, 1), 0);
+		assert!(!crate::GroupsMetadata::<Test>::contains_key(0));
+		assert_eq!(crate::DiscoverableGroupCount::<Test>::get(), 0);
+		assert!(crate::GroupJoinRequests::<Test>::iter_key_prefix(0).next().is_none());
+		assert!(!crate::GroupJoinRequestCount::<Test>::contains_key(0));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+/// Sequence numbers of the messages of group 0 after `after_seq`, and its lowest retained
+/// and next sequence numbers.
+fn synced_seqs(after_seq: Option<u64>, limit: u32) -> (Vec<u64>, u64, u64) {
+	let page = view(GroupMessagesAfterViewFunction::<Test>::new(0, after_seq, limit)).unwrap();
+	let seqs = page.messages.iter().map(|(_, message)| message.seq).collect();
+	(seqs, page.lowest_retained_seq, page.next_seq)
+}
+
+#[test]
+fn group_messages_get_sequence_numbers() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		assert_ok!(Messaging::create_group(RuntimeOrigin::signed(2), group_key(), 4));
+		assert!(view(GroupMessagesAfterViewFunction::<Test>::new(2, None, 10)).is_none());
+		assert_eq!(synced_seqs(None, 10), (vec![], 0, 0));
+
+		// Sequence numbers count per group, message IDs across groups
+		assert_ok!(send_to_group_0(1));
+		assert_ok!(Messaging::send_group_message_hash(
+			RuntimeOrigin::signed(2),
+			1,
+			H256::repeat_byte(2)
+		));
+		assert_ok!(send_to_group_0(1));
+		System::assert_last_event(
+			Event::GroupMessageSent {
+				group_id: 0,
+				message_id: 2,
+				sender: 1,
+				hash: H256::repeat_byte(1),
+				key_version: 0,
+				seq: 1,
+			}
+			.into(),
+		);
+		assert_ok!(send_to_group_0(1));
+		assert_eq!(crate::GroupMessagesBySeq::<Test>::get(0, 1), Some(2));
+		assert_eq!(crate::Groups::<Test>::get(1).unwrap().next_seq, 1);
+
+		assert_eq!(synced_seqs(None, 10), (vec![0, 1, 2], 0, 3));
+		assert_eq!(synced_seqs(Some(0), 1), (vec![1], 0, 3));
+		assert_eq!(synced_seqs(Some(2), 10), (vec![], 0, 3));
+		let page = view(GroupMessagesAfterViewFunction::<Test>::new(0, Some(1), 10)).unwrap();
+		assert_eq!(page.messages, vec![(3, crate::GroupMessages::<Test>::get(0, 3).unwrap())]);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn taken_down_group_messages_leave_gaps() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for _ in 0..3 {
+			assert_ok!(send_to_group_0(1));
+		}
+
+		assert_noop!(
+			Messaging::force_remove_group_message(RuntimeOrigin::signed(1), 1, order_reference()),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Messaging::force_remove_group_message(RuntimeOrigin::root(), 3, order_reference()),
+			Error::<Test>::MessageNotFound
+		);
+
+		assert_ok!(Messaging::force_remove_group_message(
+			RuntimeOrigin::root(),
+			1,
+			order_reference()
+		));
+		System::assert_last_event(
+			Event::GroupMessageTakenDown {
+				group_id: 0,
+				message_id: 1,
+				seq: 1,
+				order_reference: order_reference(),
+			}
+			.into(),
+		);
+		assert!(!crate::GroupMessageIds::<Test>::contains_key(1));
+		assert_eq!(
+			Messaging::verify_group_message(0, 1, H256::repeat_byte(1)),
+			Err(Error::<Test>::MessageNotFound.into())
+		);
+		assert_eq!(synced_seqs(None, 10), (vec![0, 2], 0, 3));
+		assert_eq!(synced_seqs(Some(0), 1), (vec![2], 0, 3));
+
+		// Taking down the lowest message raises the lowest retained sequence number
+		assert_ok!(Messaging::force_remove_group_message(
+			RuntimeOrigin::root(),
+			0,
+			order_reference()
+		));
+		assert_eq!(synced_seqs(None, 10), (vec![2], 2, 3));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn pruned_group_messages_raise_the_lowest_retained_seq() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		for _ in 0..2 {
+			assert_ok!(send_to_group_0(1));
+		}
+		System::set_block_number(100);
+		assert_ok!(send_to_group_0(1));
+		assert_ok!(Messaging::force_remove_group_message(
+			RuntimeOrigin::root(),
+			2,
+			order_reference()
+		));
+		assert_ok!(send_to_group_0(1));
+
+		// GroupMessageExpiry is 500, so only the messages sent at block 1 are pruned
+		System::set_block_number(502);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 502));
+		assert_eq!(crate::GroupSeqFloors::<Test>::get(0), 2);
+		assert!(!crate::GroupMessagesBySeq::<Test>::contains_key(0, 0));
+
+		// Syncing from before the floor starts at the lowest retained message
+		assert_eq!(synced_seqs(None, 10), (vec![3], 3, 4));
+		assert_eq!(synced_seqs(Some(0), 10), (vec![3], 3, 4));
+		assert_ok!(Messaging::do_try_state());
+
+		assert_ok!(Messaging::disband_group(RuntimeOrigin::signed(1), 0));
+		assert!(!crate::GroupSeqFloors::<Test>::contains_key(0));
+		assert!(crate::GroupMessagesBySeq::<Test>::iter_key_prefix(0).next().is_none());
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+/// Group 0 owned by 1, with admin 2, moderator 3 and ordinary members 5 and 6. Member 6
+/// sent message 0 and was muted by the owner, and 7 asked to join.
+fn setup_moderated_group() {
+	setup_group();
+	for who in [6, 7] {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), vec![1, 2, 3, 4]));
+	}
+	// Leave room for 7 to join
+	MaxGroupMembers::set(6);
+	for who in [2, 3, 5, 6] {
+		assert_ok!(Messaging::add_group_member(RuntimeOrigin::signed(1), 0, who));
+	}
+	assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 2));
+	assert_ok!(Messaging::appoint_moderator(RuntimeOrigin::signed(1), 0, 3));
+	assert_ok!(send_to_group_0(6));
+	assert_ok!(Messaging::mute_member(RuntimeOrigin::signed(1), 0, 6, 10));
+	assert_ok!(list_group_0(true));
+	assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(7), 0));
+}
+
+type Outcome = Result<(), Error<Test>>;
+
+const OK: Outcome = Ok(());
+const NOT_OWNER: Outcome = Err(Error::<Test>::NotGroupOwner);
+const NOT_ADMIN: Outcome = Err(Error::<Test>::NotGroupAdmin);
+const NOT_MODERATOR: Outcome = Err(Error::<Test>::NotGroupModerator);
+
+/// An action, with its outcome when taken by the owner, an admin, a moderator and an
+/// ordinary member, in that order.
+type Permission = (&'static str, fn(u64) -> DispatchResult, [Outcome; 4]);
+
+fn permissions() -> Vec<Permission> {
+	vec![
+		(
+			"mute a member",
+			|who| Messaging::mute_member(RuntimeOrigin::signed(who), 0, 5, 10),
+			[OK, OK, OK, NOT_MODERATOR],
+		),
+		(
+			"unmute a member",
+			|who| Messaging::unmute_member(RuntimeOrigin::signed(who), 0, 6),
+			[OK, OK, OK, NOT_MODERATOR],
+		),
+		(
+			"remove a member's message",
+			|who| Messaging::remove_group_message(RuntimeOrigin::signed(who), 0, 0),
+			[OK, OK, OK, NOT_MODERATOR],
+		),
+		(
+			"approve a join request",
+			|who| Messaging::approve_join_request(RuntimeOrigin::signed(who), 0, 7),
+			[OK, OK, OK, NOT_MODERATOR],
+		),
+		(
+			"reject a join request",
+			|who| Messaging::cancel_join_request(RuntimeOrigin::signed(who), 0, 7),
+			[OK, OK, OK, Err(Error::<Test>::JoinRequestNotExpired)],
+		),
+		(
+			"mute a moderator",
+			|who| Messaging::mute_member(RuntimeOrigin::signed(who), 0, 3, 10),
+			[OK, OK, NOT_ADMIN, NOT_MODERATOR],
+		),
+		(
+			"mute an admin",
+			|who| Messaging::mute_member(RuntimeOrigin::signed(who), 0, 2, 10),
+			[OK, NOT_OWNER, NOT_OWNER, NOT_MODERATOR],
+		),
+		(
+			"invite an account",
+			|who| Messaging::invite_to_group(RuntimeOrigin::signed(who), 0, 7),
+			[OK, OK, NOT_ADMIN, NOT_ADMIN],
+		),
+		(
+			"remove a member",
+			|who| Messaging::remove_group_member(RuntimeOrigin::signed(who), 0, 6),
+			[OK, OK, NOT_ADMIN, NOT_ADMIN],
+		),
+		(
+			"remove a moderator",
+			|who| Messaging::remove_group_member(RuntimeOrigin::signed(who), 0, 3),
+			[OK, OK, OK, NOT_ADMIN],
+		),
+		(
+			"ban a member",
+			|who| Messaging::ban_from_group(RuntimeOrigin::signed(who), 0, 6, H256::zero()),
+			[OK, OK, NOT_ADMIN, NOT_ADMIN],
+		),
+		(
+			"appoint a moderator",
+			|who| Messaging::appoint_moderator(RuntimeOrigin::signed(who), 0, 6),
+			[OK, NOT_OWNER, NOT_OWNER, NOT_OWNER],
+		),
+		(
+			"dismiss a moderator",
+			|who| Messaging::dismiss_moderator(RuntimeOrigin::signed(who), 0, 3),
+			[OK, NOT_OWNER, NOT_OWNER, NOT_OWNER],
+		),
+		(
+			"promote a member",
+			|who| Messaging::promote_member(RuntimeOrigin::signed(who), 0, 6),
+			[OK, NOT_OWNER, NOT_OWNER, NOT_OWNER],
+		),
+		(
+			"set the metadata",
+			|who| {
+				Messaging::set_group_metadata(
+					RuntimeOrigin::signed(who),
+					0,
+					H256::zero(),
+					Default::default(),
+					false,
+				)
+			},
+			[OK, NOT_OWNER, NOT_OWNER, NOT_OWNER],
+		),
+		(
+			"set the posting policy",
+			|who| {
+				Messaging::set_posting_policy(
+					RuntimeOrigin::signed(who),
+					0,
+					PostingPolicy::AdminsOnly,
+				)
+			},
+			[OK, NOT_OWNER, NOT_OWNER, NOT_OWNER],
+		),
+	]
+}
+
+#[test]
+fn permissions_by_role() {
+	let callers = [
+		(GroupRole::Owner, 1),
+		(GroupRole::Admin, 2),
+		(GroupRole::Moderator, 3),
+		(GroupRole::Member, 5),
+	];
+	for (action, call, outcomes) in permissions() {
+		for ((role, who), outcome) in callers.into_iter().zip(outcomes) {
+			new_test_ext().execute_with(|| {
+				setup_moderated_group();
+				let before = frame_support::storage_root(StateVersion::V1);
+
+				let result = call(who);
+
+				let expected = outcome.map_err(DispatchError::from);
+				assert_eq!(result, expected, "{action} as {role:?}");
+				if result.is_err() {
+					assert_eq!(
+						frame_support::storage_root(StateVersion::V1),
+						before,
+						"{action} as {role:?} changed storage"
+					);
+				}
+				assert_ok!(Messaging::do_try_state());
+			});
+		}
+	}
+}
+
+#[test]
+fn owner_appoints_and_dismisses_moderators() {
+	new_test_ext().execute_with(|| {
+		setup_moderated_group();
+
+		assert_ok!(Messaging::appoint_moderator(RuntimeOrigin::signed(1), 0, 5));
+		System::assert_last_event(Event::ModeratorAppointed { group_id: 0, who: 5 }.into());
+		assert_eq!(Messaging::group_role(0, &5), Some(GroupRole::Moderator));
+		assert_noop!(
+			Messaging::appoint_moderator(RuntimeOrigin::signed(1), 0, 5),
+			Error::<Test>::AlreadyGroupModerator
+		);
+		for who in [1, 2] {
+			assert_noop!(
+				Messaging::appoint_moderator(RuntimeOrigin::signed(1), 0, who),
+				Error::<Test>::AlreadyGroupAdmin
+			);
+		}
+		assert_noop!(
+			Messaging::appoint_moderator(RuntimeOrigin::signed(1), 0, 7),
+			Error::<Test>::NotGroupMember
+		);
+
+		assert_noop!(
+			Messaging::dismiss_moderator(RuntimeOrigin::signed(1), 0, 6),
+			Error::<Test>::MemberNotModerator
+		);
+		assert_ok!(Messaging::dismiss_moderator(RuntimeOrigin::signed(1), 0, 5));
+		System::assert_last_event(Event::ModeratorDismissed { group_id: 0, who: 5 }.into());
+		assert_eq!(Messaging::group_role(0, &5), Some(GroupRole::Member));
+
+		// Moderators are not admins, and cannot post where only admins can
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().admin_count, 1);
+		assert_ok!(Messaging::set_posting_policy(
+			RuntimeOrigin::signed(1),
+			0,
+			PostingPolicy::AdminsOnly
+		));
+		assert_noop!(send_to_group_0(3), Error::<Test>::PostingRestricted);
+
+		// Moderators can be made admins, and leave like anyone else
+		assert_ok!(Messaging::promote_member(RuntimeOrigin::signed(1), 0, 3));
+		assert_eq!(Messaging::group_role(0, &3), Some(GroupRole::Admin));
+		assert_eq!(crate::Groups::<Test>::get(0).unwrap().admin_count, 2);
+		assert_ok!(Messaging::appoint_moderator(RuntimeOrigin::signed(1), 0, 6));
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(6), 0));
+		assert_eq!(Messaging::group_role(0, &6), None);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn moderators_remove_messages_of_ordinary_members_only() {
+	new_test_ext().execute_with(|| {
+		setup_moderated_group();
+		assert_ok!(send_to_group_0(2));
+		assert_ok!(send_to_group_0(3));
+
+		assert_noop!(
+			Messaging::remove_group_message(RuntimeOrigin::signed(3), 0, 1),
+			Error::<Test>::NotGroupOwner
+		);
+		assert_noop!(
+			Messaging::remove_group_message(RuntimeOrigin::signed(3), 0, 2),
+			Error::<Test>::NotGroupAdmin
+		);
+		assert_ok!(Messaging::remove_group_message(RuntimeOrigin::signed(2), 0, 2));
+
+		System::set_block_number(5);
+		assert_ok!(Messaging::remove_group_message(RuntimeOrigin::signed(3), 0, 0));
+		System::assert_last_event(
+			Event::GroupMessageRemoved { group_id: 0, message_id: 0, seq: 0, removed_by: 3 }
+				.into(),
+		);
+		assert!(!crate::GroupMessages::<Test>::contains_key(0, 0));
+		assert_eq!(crate::GroupMessageIds::<Test>::get(0), Some(0));
+		assert_eq!(
+			crate::GroupMessageTombstones::<Test>::get(0, 0),
+			Some(GroupMessageTombstone {
+				seq: 0,
+				removed_by: 3,
+				removed_at: 5,
+				expires_at: 501
+			})
+		);
+		assert_noop!(
+			Messaging::remove_group_message(RuntimeOrigin::signed(3), 0, 0),
+			Error::<Test>::MessageNotFound
+		);
+		assert_noop!(
+			Messaging::remove_group_message(RuntimeOrigin::signed(3), 1, 1),
+			Error::<Test>::GroupNotFound
+		);
+
+		// Removed messages leave gaps for syncing members
+		assert_eq!(synced_seqs(None, 10), (vec![1], 1, 3));
+
+		// Senders that left count as ordinary members
+		assert_ok!(send_to_group_0(5));
+		assert_ok!(Messaging::leave_group(RuntimeOrigin::signed(5), 0));
+		assert_ok!(Messaging::remove_group_message(RuntimeOrigin::signed(3), 0, 3));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn tombstones_are_pruned_when_the_message_would_have_expired() {
+	new_test_ext().execute_with(|| {
+		setup_moderated_group();
+		assert_ok!(Messaging::remove_group_message(RuntimeOrigin::signed(3), 0, 0));
+
+		// GroupMessageExpiry is 500
+		System::set_block_number(501);
+		assert!(!Messaging::has_overdue_messages(501));
+		assert_noop!(
+			Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 501),
+			Error::<Test>::NothingToPrune
+		);
+
+		System::set_block_number(502);
+		assert!(Messaging::has_overdue_messages(502));
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 502));
+		assert!(!crate::GroupMessageTombstones::<Test>::contains_key(0, 0));
+		assert!(!crate::GroupMessageIds::<Test>::contains_key(0));
+		assert_eq!(crate::GroupSeqFloors::<Test>::get(0), 1);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn moderation_log_keeps_the_latest_actions() {
+	new_test_ext().execute_with(|| {
+		setup_moderated_group();
+		let entry = |by: u64, action: GroupModerationAction<u64, u64>, at: u64| {
+			GroupModerationEntry { by, action, at }
+		};
+
+		System::set_block_number(2);
+		assert_ok!(Messaging::unmute_member(RuntimeOrigin::signed(3), 0, 6));
+		assert_ok!(Messaging::remove_group_message(RuntimeOrigin::signed(3), 0, 0));
+		assert_eq!(
+			Messaging::group_moderation_log(0),
+			vec![
+				entry(1, GroupModerationAction::Mute { who: 6, until: 11 }, 1),
+				entry(3, GroupModerationAction::Unmute { who: 6 }, 2),
+				entry(3, GroupModerationAction::RemoveMessage { message_id: 0 }, 2),
+			]
+		);
+
+		// MaxGroupModerationLog is 3, so the oldest entry is evicted
+		System::set_block_number(3);
+		assert_ok!(Messaging::cancel_join_request(RuntimeOrigin::signed(3), 0, 7));
+		assert_eq!(
+			Messaging::group_moderation_log(0),
+			vec![
+				entry(3, GroupModerationAction::Unmute { who: 6 }, 2),
+				entry(3, GroupModerationAction::RemoveMessage { message_id: 0 }, 2),
+				entry(3, GroupModerationAction::RejectJoinRequest { who: 7 }, 3),
+			]
+		);
+
+		// Requesters withdrawing their own request are not moderating
+		assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(7), 0));
+		assert_ok!(Messaging::cancel_join_request(RuntimeOrigin::signed(7), 0, 7));
+		assert_eq!(
+			Messaging::group_moderation_log(0)[2],
+			entry(3, GroupModerationAction::RejectJoinRequest { who: 7 }, 3)
+		);
+
+		assert_ok!(Messaging::request_to_join_group(RuntimeOrigin::signed(7), 0));
+		assert_ok!(Messaging::approve_join_request(RuntimeOrigin::signed(3), 0, 7));
+		assert_eq!(
+			Messaging::group_moderation_log(0)[2],
+			entry(3, GroupModerationAction::ApproveJoinRequest { who: 7 }, 3)
+		);
+		assert!(Messaging::group_moderation_log(1).is_empty());
+	});
+}
+
+#[test]
+fn disband_group_removes_tombstones_and_the_moderation_log() {
+	new_test_ext().execute_with(|| {
+		setup_moderated_group();
+		assert_ok!(Messaging::remove_group_message(RuntimeOrigin::signed(3), 0, 0));
+
+		while crate::Groups::<Test>::contains_key(0) {
+			assert_ok!(Messaging::disband_group(RuntimeOrigin::signed(1), 0));
+			assert_ok!(Messaging::do_try_state());
+		}
+		assert!(crate::GroupMessageTombstones::<Test>::iter_key_prefix(0).next().is_none());
+		assert!(!crate::GroupMessageIds::<Test>::contains_key(0));
+		assert!(!crate::GroupModerationLogs::<Test>::contains_key(0));
+	});
+}
+
+const KEY: [u8; 4] = [1, 2, 3, 4];
+
+/// Tag of a message to the holder of `KEY`, sent with ephemeral value `[byte; 32]`.
+fn tag(byte: u8) -> H256 {
+	H256::from(stealth::recipient_tag(&KEY, &[byte; 32]))
+}
+
+fn send_stealth(who: u64, recipient_tag: H256) -> DispatchResult {
+	Messaging::send_stealth_message_hash(
+		RuntimeOrigin::signed(who),
+		recipient_tag,
+		H256::repeat_byte(1),
+	)
+}
+
+fn tagged_ids(prefix: stealth::TagPrefix, after: Option<u64>, limit: u32) -> Vec<u64> {
+	view(MessagesByTagPrefixViewFunction::<Test>::new(prefix, after, limit))
+		.into_iter()
+		.map(|(message_id, _)| message_id)
+		.collect()
+}
+
+#[test]
+fn tags_match_test_vectors() {
+	let hex = |bytes: &[u8]| HexDisplay::from(&bytes).to_string();
+	assert_eq!(hex(&stealth::tag_prefix(&KEY)), "9835");
+	assert_eq!(
+		hex(&stealth::recipient_tag(&KEY, &[7; 32])),
+		"98355c82c60dd8157bbd180adc2030b9ccc86ecbc50d8d901a76901ec56f1221"
+	);
+	assert_eq!(
+		hex(&stealth::recipient_tag(&KEY, &[8; 32])),
+		"9835829a01f46db597ea4d926ec2f536f4e81cf1f37f867d32292bbe5ab0a2c3"
+	);
+	assert_eq!(hex(&stealth::tag_prefix(&[9; 32])), "20bc");
+	assert_eq!(
+		hex(&stealth::recipient_tag(&[9; 32], &[0; 32])),
+		"20bc29dbf2884f74588b0b52e6dafdf541619c91305b9fd1789e7704dae42fb0"
+	);
+	assert_eq!(stealth::prefix_of(&[0xab]), [0xab, 0]);
+}
+
+#[test]
+fn send_stealth_message_holds_deposit_and_indexes_tag() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(send_stealth(1, tag(7)));
+
+		assert_eq!(held(HoldReason::MessageDeposit, 1), StealthMessageDeposit::get());
+		assert_eq!(
+			crate::StealthMessages::<Test>::get(0),
+			Some(StealthMessageRecord {
+				sender: 1,
+				recipient_tag: tag(7),
+				hash: H256::repeat_byte(1),
+				sent_at: 1,
+				deposit: StealthMessageDeposit::get(),
+			})
+		);
+		assert!(crate::StealthMessagesByPrefix::<Test>::contains_key(
+			stealth::tag_prefix(&KEY),
+			0
+		));
+		// Neither the recipient nor a conversation is recorded
+		assert!(!crate::MessageHashes::<Test>::contains_key(0));
+		assert_eq!(crate::ConversationStats::<Test>::iter().count(), 0);
+		System::assert_last_event(
+			Event::StealthMessageSent {
+				message_id: 0,
+				from: 1,
+				recipient_tag: tag(7),
+				hash: H256::repeat_byte(1),
+				expires_at: 1001,
+			}
+			.into(),
+		);
+
+		// Direct and stealth messages share IDs
+		let hash = H256::repeat_byte(2);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		assert!(crate::MessageHashes::<Test>::contains_key(1));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn messages_by_tag_prefix_pages_through_one_prefix() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let prefix = stealth::tag_prefix(&KEY);
+		for byte in [7, 8, 9] {
+			assert_ok!(send_stealth(1, tag(byte)));
+		}
+		assert_ok!(send_stealth(2, H256::from(stealth::recipient_tag(&[9; 32], &[0; 32]))));
+
+		let mut ids = tagged_ids(prefix, None, 10);
+		ids.sort();
+		assert_eq!(ids, vec![0, 1, 2]);
+		assert_eq!(tagged_ids(stealth::tag_prefix(&[9; 32]), None, 10), vec![3]);
+		assert!(tagged_ids([0, 0], None, 10).is_empty());
+
+		// Pages continue after the last ID of the previous page, in storage order
+		let first = tagged_ids(prefix, None, 2);
+		assert_eq!(first.len(), 2);
+		let rest = tagged_ids(prefix, first.last().copied(), 2);
+		assert_eq!(rest.len(), 1);
+		assert!(!first.contains(&rest[0]));
+
+		let page = view(MessagesByTagPrefixViewFunction::<Test>::new(prefix, None, 1));
+		assert_eq!(page[0].1.recipient_tag.as_bytes()[..2], prefix);
+	});
+}
+
+#[test]
+fn send_stealth_message_checks_sender() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_noop!(send_stealth(3, tag(7)), Error::<Test>::ProfileNotFound);
+
+		// Mutual contacts cannot be checked, so graylisted senders are refused outright
+		for _ in 0..3 {
+			add_strike(1, 2);
+		}
+		System::set_block_number(20);
+		assert_noop!(send_stealth(1, tag(7)), Error::<Test>::Graylisted);
+
+		assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::SENDS));
+		assert_noop!(send_stealth(2, tag(7)), Error::<Test>::PalletPaused);
+	});
+}
+
+#[test]
+fn send_stealth_message_fails_without_deposit() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let short = StealthMessageDeposit::get() - 1;
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, short));
+
+		assert_noop!(send_stealth(1, tag(7)), Error::<Test>::InsufficientStealthDeposit);
+	});
+}
+
+#[test]
+fn pruning_releases_stealth_deposit() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(send_stealth(1, tag(7)));
+		System::set_block_number(500);
+		assert_ok!(send_stealth(1, tag(8)));
+		System::set_block_number(1002);
+		assert!(Messaging::has_overdue_messages(1002));
+
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert!(!crate::StealthMessages::<Test>::contains_key(0));
+		assert_eq!(tagged_ids(stealth::tag_prefix(&KEY), None, 10), vec![1]);
+		assert_eq!(held(HoldReason::MessageDeposit, 1), StealthMessageDeposit::get());
+		System::assert_last_event(Event::ExpiredMessagesPruned { count: 1, next: 1 }.into());
+		assert!(!Messaging::has_overdue_messages(1002));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+/// Account 5 registered as a relay, with 1 and 2 registered as in `setup_pair`.
+fn setup_relay() {
+	setup_pair();
+	assert_ok!(Messaging::register_relay(RuntimeOrigin::signed(5)));
+}
+
+/// Carry a message to 2, with a hash unique to the next message ID.
+fn carry(relay: u64) -> sp_runtime::DispatchResult {
+	let hash = H256::repeat_byte(1 + crate::NextMessageId::<Test>::get() as u8);
+	Messaging::send_sealed_message_hash(RuntimeOrigin::signed(relay), 2, hash)
+}
+
+#[test]
+fn register_relay_holds_relay_bond() {
+	new_test_ext().execute_with(|| {
+		setup_relay();
+		assert_eq!(held(HoldReason::RelayBond, 5), RelayBond::get());
+		assert_eq!(
+			crate::RelayAccounts::<Test>::get(5),
+			Some(RelayInfo { bond: 500, last_carried_at: None, carried_in_block: 0 })
+		);
+		System::assert_last_event(
+			Event::RelayRegistered { relay: 5, held: RelayBond::get() }.into(),
+		);
+
+		assert_noop!(
+			Messaging::register_relay(RuntimeOrigin::signed(5)),
+			Error::<Test>::AlreadyRelay
+		);
+		assert_noop!(
+			Messaging::register_relay(RuntimeOrigin::signed(4)),
+			Error::<Test>::InsufficientRelayBond
+		);
+
+		assert_ok!(Messaging::deregister_relay(RuntimeOrigin::signed(5)));
+		assert_eq!(held(HoldReason::RelayBond, 5), 0);
+		assert!(!crate::RelayAccounts::<Test>::contains_key(5));
+		System::assert_last_event(
+			Event::RelayDeregistered { relay: 5, refunded: RelayBond::get() }.into(),
+		);
+		assert_noop!(
+			Messaging::deregister_relay(RuntimeOrigin::signed(5)),
+			Error::<Test>::NotRelay
+		);
+	});
+}
+
+#[test]
+fn sealed_message_names_relay_as_sender() {
+	new_test_ext().execute_with(|| {
+		setup_relay();
+		assert_ok!(carry(5));
+
+		assert_eq!(
+			Messaging::message(0),
+			Some(MessageRecord {
+				sender: 5,
+				recipient: 2,
+				hash: H256::repeat_byte(1),
+				sent_at: 1,
+			})
+		);
+		System::assert_last_event(
+			Event::SealedMessageSent {
+				message_id: 0,
+				relay: 5,
+				to: 2,
+				hash: H256::repeat_byte(1),
+				expires_at: 1001,
+			}
+			.into(),
+		);
+
+		assert_noop!(carry(1), Error::<Test>::NotRelay);
+		assert_noop!(
+			Messaging::send_sealed_message_hash(
+				RuntimeOrigin::signed(5),
+				3,
+				H256::repeat_byte(1)
+			),
+			Error::<Test>::RecipientNotFound
+		);
+	});
+}
+
+#[test]
+fn relays_carry_a_bounded_number_of_messages_per_block() {
+	new_test_ext().execute_with(|| {
+		setup_relay();
+		for _ in 0..MaxRelayCarriesPerBlock::get() {
+			assert_ok!(carry(5));
+		}
+		assert_noop!(carry(5), Error::<Test>::RelayCarryLimitReached);
+
+		System::set_block_number(2);
+		assert_ok!(carry(5));
+		assert_eq!(
+			crate::RelayAccounts::<Test>::get(5).map(|relay| relay.carried_in_block),
+			Some(1)
+		);
+	});
+}
+
+#[test]
+fn upheld_challenge_slashes_relay_bond() {
+	new_test_ext().execute_with(|| {
+		setup_relay();
+		assert_ok!(carry(5));
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			0,
+			evidence_commitment()
+		));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+
+		// Half of the relay bond is slashed, the spam bond of the relay is not touched
+		assert_eq!(held(HoldReason::RelayBond, 5), 250);
+		assert_eq!(crate::RelayAccounts::<Test>::get(5).unwrap().bond, 250);
+		assert_eq!(held(HoldReason::SpamBond, 5), 0);
+		assert_eq!(Balances::free_balance(Messaging::escrow_account()), 250);
+		System::assert_has_event(Event::BondSlashed { who: 5, amount: 250 }.into());
+
+		// The relay cannot carry until it restores its bond, nor leave with a challenge open
+		System::set_block_number(2);
+		assert_noop!(carry(5), Error::<Test>::InsufficientRelayBond);
+		assert_noop!(
+			Messaging::deregister_relay(RuntimeOrigin::signed(5)),
+			Error::<Test>::OpenChallengesPending
+		);
+		assert_ok!(Messaging::register_relay(RuntimeOrigin::signed(5)));
+		System::assert_last_event(Event::RelayRegistered { relay: 5, held: 250 }.into());
+		assert_ok!(carry(5));
+	});
+}
+
+#[test]
+fn successful_relay_appeal_restores_relay_bond() {
+	new_test_ext().execute_with(|| {
+		setup_relay();
+		assert_ok!(carry(5));
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			0,
+			evidence_commitment()
+		));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(5), 0));
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, false));
+
+		assert_eq!(held(HoldReason::RelayBond, 5), RelayBond::get());
+		assert_eq!(crate::RelayAccounts::<Test>::get(5).unwrap().bond, RelayBond::get());
+		assert_eq!(held(HoldReason::SpamBond, 5), 0);
+		assert_eq!(Balances::free_balance(Messaging::escrow_account()), 0);
+	});
+}
+
+#[test]
+fn relay_bond_stays_locked_after_carrying() {
+	new_test_ext().execute_with(|| {
+		setup_relay();
+		assert_ok!(carry(5));
+
+		System::set_block_number(BondUnbondingPeriod::get());
+		assert_noop!(
+			Messaging::deregister_relay(RuntimeOrigin::signed(5)),
+			Error::<Test>::BondLocked
+		);
+		System::set_block_number(1 + BondUnbondingPeriod::get());
+		assert_ok!(Messaging::deregister_relay(RuntimeOrigin::signed(5)));
+	});
+}
+
+#[test]
+fn accounts_with_open_challenges_cannot_become_relays() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let hash = H256::repeat_byte(1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			0,
+			evidence_commitment()
+		));
+
+		assert_noop!(
+			Messaging::register_relay(RuntimeOrigin::signed(1)),
+			Error::<Test>::OpenChallengesPending
+		);
+	});
+}
+
+/// Leave a message with hash `[byte; 32]` from 1 to the unregistered account 6.
+fn leave_for_6(byte: u8) {
+	assert_ok!(Messaging::send_to_unregistered(
+		RuntimeOrigin::signed(1),
+		Messaging::account_hash(&6),
+		H256::repeat_byte(byte),
+	));
+}
+
+fn register_6() {
+	assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(6), vec![1, 2, 3, 4]));
+}
+
+#[test]
+fn pending_message_is_claimed_after_registering() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		leave_for_6(1);
+		let account_hash = Messaging::account_hash(&6);
+		assert_eq!(held(HoldReason::MessageDeposit, 1), PendingMessageDeposit::get());
+		assert_eq!(
+			crate::PendingMessages::<Test>::get(account_hash, 0),
+			Some(PendingMessageRecord {
+				sender: 1,
+				hash: H256::repeat_byte(1),
+				sent_at: 1,
+				deposit: PendingMessageDeposit::get(),
+			})
+		);
+		System::assert_last_event(
+			Event::PendingMessageSent {
+				pending_id: 0,
+				from: 1,
+				recipient_account_hash: account_hash,
+				hash: H256::repeat_byte(1),
+				expires_at: 3001,
+			}
+			.into(),
+		);
+		assert_noop!(
+			Messaging::claim_pending_messages(RuntimeOrigin::signed(6)),
+			Error::<Test>::ProfileNotFound
+		);
+
+		System::set_block_number(5);
+		register_6();
+		assert_ok!(Messaging::claim_pending_messages(RuntimeOrigin::signed(6)));
+
+		// The message verifies from the block it was claimed at
+		assert_eq!(
+			Messaging::message(0),
+			Some(MessageRecord {
+				sender: 1,
+				recipient: 6,
+				hash: H256::repeat_byte(1),
+				sent_at: 5,
+			})
+		);
+		// 30 of the deposit is refunded, the rest is collected as a fee
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+		assert_eq!(Balances::free_balance(1), 890);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::Fee), 10);
+		System::assert_last_event(
+			Event::PendingMessageClaimed {
+				pending_id: 0,
+				message_id: 0,
+				from: 1,
+				to: 6,
+				refunded: 30,
+			}
+			.into(),
+		);
+		assert!(!crate::PendingMessages::<Test>::contains_key(account_hash, 0));
+		assert!(!crate::PendingMessageIds::<Test>::contains_key(0));
+
+		assert_noop!(
+			Messaging::claim_pending_messages(RuntimeOrigin::signed(6)),
+			Error::<Test>::NoPendingMessages
+		);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn pending_messages_are_claimed_in_batches() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		for byte in 1..=3 {
+			leave_for_6(byte);
+		}
+		register_6();
+
+		assert_ok!(Messaging::claim_pending_messages(RuntimeOrigin::signed(6)));
+		assert_eq!(crate::NextMessageId::<Test>::get(), MaxClaimsPerCall::get() as u64);
+		let account_hash = Messaging::account_hash(&6);
+		assert_eq!(crate::PendingMessages::<Test>::iter_prefix(account_hash).count(), 1);
+
+		assert_ok!(Messaging::claim_pending_messages(RuntimeOrigin::signed(6)));
+		assert_eq!(crate::NextMessageId::<Test>::get(), 3);
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+		let mut hashes: Vec<_> =
+			(0..3).map(|message_id| Messaging::message(message_id).unwrap().hash).collect();
+		hashes.sort();
+		assert_eq!(hashes, (1..=3).map(H256::repeat_byte).collect::<Vec<_>>());
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn unclaimed_pending_messages_expire() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		leave_for_6(1);
+		System::set_block_number(1500);
+		leave_for_6(2);
+
+		System::set_block_number(3002);
+		assert!(Messaging::has_overdue_messages(3002));
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 3002));
+		System::assert_has_event(
+			Event::ExpiredPendingMessagesPruned { count: 1, next: 1 }.into(),
+		);
+		assert!(!crate::PendingMessageIds::<Test>::contains_key(0));
+		// The expired message's deposit is returned in full
+		assert_eq!(held(HoldReason::MessageDeposit, 1), PendingMessageDeposit::get());
+		assert!(!Messaging::has_overdue_messages(3002));
+
+		register_6();
+		assert_ok!(Messaging::claim_pending_messages(RuntimeOrigin::signed(6)));
+		assert_eq!(
+			Messaging::message(0).map(|message| message.hash),
+			Some(H256::repeat_byte(2))
+		);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn claiming_prunes_expired_pending_messages() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		leave_for_6(1);
+		System::set_block_number(3002);
+		register_6();
+
+		assert_ok!(Messaging::claim_pending_messages(RuntimeOrigin::signed(6)));
+		assert_eq!(crate::NextMessageId::<Test>::get(), 0);
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+		assert_eq!(Balances::free_balance(1), 900);
+		assert!(!crate::PendingMessageIds::<Test>::contains_key(0));
+
+		// The prune cursor skips the claimed ID
+		assert!(!Messaging::has_overdue_messages(3002));
+	});
+}
+
+#[test]
+fn send_to_unregistered_checks_sender() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let send = |from| {
+			Messaging::send_to_unregistered(
+				RuntimeOrigin::signed(from),
+				Messaging::account_hash(&6),
+				H256::repeat_byte(1),
+			)
+		};
+		assert_noop!(send(3), Error::<Test>::ProfileNotFound);
+
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, 10));
+		assert_noop!(send(1), Error::<Test>::InsufficientPendingDeposit);
+	});
+}
+
+fn set_mode(event_privacy: EventPrivacyMode) {
+	let parameters = PalletParameters { event_privacy, ..Messaging::parameters() };
+	assert_ok!(Messaging::update_parameters(RuntimeOrigin::signed(Admin::get()), parameters));
+}
+
+/// Send a message from 1 to 2, then have 1 approve and remove 2 as a contact.
+///
+/// The hash is unique to the message ID, so the first message has hash `[1; 32]`.
+fn send_approve_and_remove() -> Vec<Event<Test>> {
+	System::reset_events();
+	let hash = H256::repeat_byte(1 + crate::NextMessageId::<Test>::get() as u8);
+	assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+	assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 2));
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::Messaging(event) => Some(event),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn full_mode_names_the_accounts() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_eq!(
+			send_approve_and_remove(),
+			vec![
+				Event::MessageSent {
+					message_id: 0,
+					from: 1,
+					to: 2,
+					hash: H256::from([1; 32]),
+					expires_at: 1001,
+					sequence: 0,
+					conversation_id: Messaging::conversation_id(&1, &2),
+				},
+				Event::ContactApproved { approver: 1, contact: 2 },
+				Event::ContactRemoved { remover: 1, contact: 2 },
+			]
+		);
+	});
+}
+
+#[test]
+fn minimal_mode_leaves_out_the_accounts() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_mode(EventPrivacyMode::Minimal);
+		let pair = Messaging::contact_pair_hash(&1, &2);
+		assert_ne!(pair, Messaging::contact_pair_hash(&2, &1));
+		assert_eq!(
+			send_approve_and_remove(),
+			vec![
+				Event::MessageSentMinimal {
+					message_id: 0,
+					conversation_id: Messaging::conversation_id(&1, &2),
+				},
+				Event::ContactApprovedMinimal { pair },
+				Event::ContactRemovedMinimal { pair },
+			]
+		);
+
+		// The parties read the rest from storage
+		assert_eq!(
+			view(crate::MessageInfoViewFunction::<Test>::new(0)),
+			Some(crate::MessageRecordView {
+				hash: H256::from([1; 32]),
+				sender: 1,
+				recipient: 2,
+				sent_at: 1,
+				expires_at: 1001,
+			})
+		);
+
+		set_mode(EventPrivacyMode::Full);
+		assert!(matches!(send_approve_and_remove()[0], Event::MessageSent { .. }));
+	});
+}
+
+#[test]
+fn parameters_stored_without_a_mode_decode_as_full() {
+	new_test_ext().execute_with(|| {
+		unhashed::put_raw(
+			&crate::Parameters::<Test>::hashed_key(),
+			&(200u128, 50u64, 1u32).encode(),
+		);
+		assert_eq!(
+			Messaging::parameters(),
+			PalletParameters {
+				spam_bond: 200,
+				message_expiry: 50,
+				max_contacts: 1,
+				event_privacy: EventPrivacyMode::Full,
+				message_protocol_fee: 0,
+				exempt_mutual_contacts: false,
+				challenger_reward_percent: Perbill::zero(),
+				auto_slash_percent: Perbill::zero(),
+				base_challenge_reward: 0,
+				slash_destination: SlashDestination::Treasury,
+			}
+		);
+
+		set_mode(EventPrivacyMode::Minimal);
+		assert_eq!(Messaging::parameters().event_privacy, EventPrivacyMode::Minimal);
+		assert_eq!(Messaging::parameters().max_contacts, 1);
+	});
+}
+
+#[test]
+fn parameters_stored_without_a_protocol_fee_decode_with_none() {
+	new_test_ext().execute_with(|| {
+		unhashed::put_raw(
+			&crate::Parameters::<Test>::hashed_key(),
+			&(200u128, 50u64, 1u32, EventPrivacyMode::Minimal).encode(),
+		);
+		let parameters = Messaging::parameters();
+		assert_eq!(parameters.event_privacy, EventPrivacyMode::Minimal);
+		assert_eq!(parameters.message_protocol_fee, 0);
+		assert!(!parameters.exempt_mutual_contacts);
+	});
+}
+
+fn evidence() -> Evidence {
+	EVIDENCE.to_vec().try_into().unwrap()
+}
+
+fn contest() {
+	assert_ok!(Messaging::contest_challenge(RuntimeOrigin::signed(1), 0));
+}
+
+fn reveal(evidence: Evidence) -> DispatchResult {
+	Messaging::reveal_evidence(RuntimeOrigin::signed(3), 0, evidence, SALT)
+}
+
+#[test]
+fn evidence_is_revealed_after_contest_and_pruned_on_settlement() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		assert_eq!(
+			crate::ChallengeEvidence::<Test>::get(0),
+			Some(EvidenceRecord {
+				commitment: evidence_commitment(),
+				reveal_by: None,
+				evidence: None,
+			})
+		);
+		assert_noop!(reveal(evidence()), Error::<Test>::ChallengeNotContested);
+
+		System::set_block_number(5);
+		contest();
+		System::assert_last_event(
+			Event::ChallengeContested { message_id: 0, reveal_by: 25 }.into(),
+		);
+		assert_eq!(view(ChallengeEvidenceViewFunction::<Test>::new(0)), None);
+
+		assert_ok!(reveal(evidence()));
+		System::assert_last_event(Event::EvidenceRevealed { message_id: 0 }.into());
+		assert_eq!(view(ChallengeEvidenceViewFunction::<Test>::new(0)), Some(evidence()));
+		assert_noop!(reveal(evidence()), Error::<Test>::EvidenceAlreadyRevealed);
+
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+		assert!(crate::ChallengeEvidence::<Test>::contains_key(0));
+		System::set_block_number(16);
+		assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(3), 0));
+		assert!(!crate::ChallengeEvidence::<Test>::contains_key(0));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn only_the_sender_contests_once() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		assert_noop!(
+			Messaging::contest_challenge(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::NotAuthorized
+		);
+		contest();
+		assert_noop!(
+			Messaging::contest_challenge(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::AlreadyContested
+		);
+		assert_noop!(
+			Messaging::reveal_evidence(RuntimeOrigin::signed(2), 0, evidence(), SALT),
+			Error::<Test>::NotAuthorized
+		);
+	});
+}
+
+#[test]
+fn mismatched_reveal_forfeits_the_challenge_bond() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		contest();
+
+		assert_ok!(Messaging::reveal_evidence(
+			RuntimeOrigin::signed(3),
+			0,
+			b"something else".to_vec().try_into().unwrap(),
+			SALT
+		));
+		System::assert_last_event(
+			Event::EvidenceMismatch { message_id: 0, challenger: 3, forfeited: 20 }.into(),
+		);
+		assert_eq!(held(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(Balances::free_balance(3), 980);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::ForfeitedDeposit), 20);
+
+		// The challenge is closed without a strike
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+		assert!(!crate::ChallengeEvidence::<Test>::contains_key(0));
+		assert_eq!(crate::OpenChallengeCount::<Test>::get(1), 0);
+		assert_eq!(Messaging::current_strikes(&1), 0);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn evidence_cannot_be_revealed_after_the_deadline() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		contest();
+
+		System::set_block_number(22);
+		assert_noop!(reveal(evidence()), Error::<Test>::EvidenceRevealClosed);
+
+		// The salt is part of the commitment
+		System::set_block_number(21);
+		let origin = RuntimeOrigin::signed(3);
+		assert_ok!(Messaging::reveal_evidence(origin, 0, evidence(), H256::zero()));
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn evidence_cannot_be_revealed_after_a_verdict() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		contest();
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, false));
+
+		assert_noop!(reveal(evidence()), Error::<Test>::ChallengeNotOpen);
+	});
+}
+
+#[test]
+fn expired_challenges_drop_their_evidence() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		System::set_block_number(102);
+
+		assert_ok!(Messaging::expire_challenge(RuntimeOrigin::signed(3), 0));
+		assert!(!crate::ChallengeEvidence::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn system_challenges_have_no_evidence() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let hash = H256::from([1; 32]);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		add_moderator();
+		assert_ok!(Messaging::moderator_challenge(RuntimeOrigin::signed(MODERATOR), 0));
+
+		assert_noop!(
+			Messaging::contest_challenge(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::NoEvidenceCommitment
+		);
+	});
+}
+
+type Updater = crate::membership::HashChain<BlakeTwo256>;
+
+/// Account 5 registered as a relay and account 2 with 1 as its only contact.
+fn setup_relay_and_contact() {
+	setup_pair();
+	assert_ok!(Messaging::register_relay(RuntimeOrigin::signed(5)));
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+}
+
+fn proof_for(recipient: u64) -> MembershipProof {
+	let accumulator = crate::ContactAccumulators::<Test>::get(recipient);
+	AccumulatorProof::proof_for(&accumulator, 64).try_into().unwrap()
+}
+
+/// Send with a hash unique to the next message ID.
+fn send_anonymous(relay: u64, recipient: u64, proof: MembershipProof) -> DispatchResult {
+	Messaging::send_anonymous_contact_message(
+		RuntimeOrigin::signed(relay),
+		recipient,
+		H256::repeat_byte(1 + crate::NextMessageId::<Test>::get() as u8),
+		proof,
+	)
+}
+
+#[test]
+fn approving_and_removing_contacts_updates_the_accumulator() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_eq!(crate::ContactAccumulators::<Test>::get(2), H256::zero());
+
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		let added = Updater::add(&H256::zero(), &1);
+		assert_eq!(crate::ContactAccumulators::<Test>::get(2), added);
+
+		// Approving an existing contact again is rejected
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(2), 1),
+			Error::<Test>::AlreadyApproved
+		);
+		assert_eq!(crate::ContactAccumulators::<Test>::get(2), added);
+
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(2), 1));
+		assert_eq!(
+			crate::ContactAccumulators::<Test>::get(2),
+			Updater::remove(&added, &1)
+		);
+	});
+}
+
+#[test]
+fn valid_proof_sends_through_the_relay() {
+	new_test_ext().execute_with(|| {
+		setup_relay_and_contact();
+		assert_ok!(send_anonymous(5, 2, proof_for(2)));
+
+		assert_eq!(
+			Messaging::message(0),
+			Some(MessageRecord {
+				sender: 5,
+				recipient: 2,
+				hash: H256::repeat_byte(1),
+				sent_at: 1,
+			})
+		);
+		System::assert_last_event(
+			Event::AnonymousContactMessageSent {
+				message_id: 0,
+				relay: 5,
+				to: 2,
+				hash: H256::repeat_byte(1),
+				expires_at: 1001,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn proofs_go_stale_when_a_contact_is_removed() {
+	new_test_ext().execute_with(|| {
+		setup_relay_and_contact();
+		let stale = proof_for(2);
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(2), 1));
+
+		assert_noop!(send_anonymous(5, 2, stale), Error::<Test>::InvalidMembershipProof);
+		assert_noop!(
+			send_anonymous(5, 2, MembershipProof::default()),
+			Error::<Test>::InvalidMembershipProof
+		);
+	});
+}
+
+#[test]
+fn relay_rules_still_apply() {
+	new_test_ext().execute_with(|| {
+		setup_relay_and_contact();
+		assert_noop!(send_anonymous(1, 2, proof_for(2)), Error::<Test>::NotRelay);
+
+		assert_ok!(send_anonymous(5, 2, proof_for(2)));
+		assert_ok!(send_anonymous(5, 2, proof_for(2)));
+		assert_noop!(send_anonymous(5, 2, proof_for(2)), Error::<Test>::RelayCarryLimitReached);
+	});
+}
+
+#[test]
+fn default_verifier_rejects_every_proof() {
+	use crate::membership::VerifyMembershipProof;
+
+	let accumulator = Updater::add(&H256::zero(), &1u64);
+	let proof = AccumulatorProof::proof_for(&accumulator, 64);
+	assert!(AccumulatorProof::verify(&2, &accumulator, &H256::zero(), &proof));
+	assert!(!<() as VerifyMembershipProof<u64, H256>>::verify(
+		&2,
+		&accumulator,
+		&H256::zero(),
+		&proof
+	));
+	assert_eq!(
+		<() as VerifyMembershipProof<u64, H256>>::verification_weight(64),
+		Weight::zero()
+	);
+}
+
+fn key(byte: u8) -> EphemeralKey {
+	vec![byte; 32].try_into().unwrap()
+}
+
+fn publish(index: u32) -> DispatchResult {
+	Messaging::publish_ephemeral_key(RuntimeOrigin::signed(1), index, key(index as u8))
+		.map(|_| ())
+		.map_err(|e| e.error)
+}
+
+fn send_under(index: u32) -> DispatchResult {
+	Messaging::send_message_hash_with_ephemeral_key(
+		RuntimeOrigin::signed(1),
+		2,
+		H256::repeat_byte(1),
+		index,
+	)
+	.map(|_| ())
+	.map_err(|e| e.error)
+}
+
+#[test]
+fn publishing_beyond_the_limit_evicts_the_oldest_key() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		for index in 0..3 {
+			System::set_block_number(index as u64 + 1);
+			assert_ok!(publish(index));
+		}
+		assert_eq!(crate::EphemeralKeys::<Test>::get(1, 0), Some((key(0), 1)));
+
+		// Republishing an existing index replaces it without evicting
+		System::set_block_number(4);
+		assert_ok!(publish(1));
+		System::assert_last_event(
+			Event::EphemeralKeyPublished { who: 1, index: 1, evicted: None }.into(),
+		);
+
+		// Index 0 is now the oldest
+		assert_ok!(publish(7));
+		System::assert_last_event(
+			Event::EphemeralKeyPublished { who: 1, index: 7, evicted: Some(0) }.into(),
+		);
+		assert_eq!(Messaging::ephemeral_key(&1, 0), None);
+		assert_eq!(Messaging::ephemeral_key(&1, 7), Some(key(7)));
+		assert_eq!(crate::EphemeralKeys::<Test>::iter_prefix(1).count(), 3);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn publishing_requires_a_profile_and_a_key() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(publish(0), Error::<Test>::ProfileNotFound);
+		setup_pair();
+		assert_noop!(
+			Messaging::publish_ephemeral_key(RuntimeOrigin::signed(1), 0, Default::default()),
+			Error::<Test>::EmptyEphemeralKey
+		);
+	});
+}
+
+#[test]
+fn messages_record_the_key_they_were_sent_under() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(publish(5));
+		assert_ok!(send_under(5));
+
+		assert_eq!(crate::MessageEphemeralKeys::<Test>::get(0), Some(5));
+		System::assert_last_event(
+			Event::EphemeralKeyReferenced { message_id: 0, index: 5 }.into(),
+		);
+	});
+}
+
+#[test]
+fn referencing_an_unpublished_key_fails() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_noop!(send_under(0), Error::<Test>::EphemeralKeyNotFound);
+
+		// Keys of other senders do not count
+		assert_ok!(Messaging::publish_ephemeral_key(RuntimeOrigin::signed(2), 0, key(1)));
+		assert_noop!(send_under(0), Error::<Test>::EphemeralKeyNotFound);
+	});
+}
+
+#[test]
+fn expired_keys_are_pruned_with_messages() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(publish(0));
+		assert_ok!(send_under(0));
+		System::set_block_number(500);
+		assert_ok!(publish(1));
+
+		// Expired keys can no longer be referenced, even before they are pruned
+		System::set_block_number(1002);
+		assert_eq!(Messaging::ephemeral_key(&1, 0), None);
+		assert_noop!(send_under(0), Error::<Test>::EphemeralKeyNotFound);
+
+		assert!(Messaging::has_overdue_messages(1002));
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		System::assert_has_event(
+			Event::ExpiredEphemeralKeysPruned { count: 1, next: 1 }.into(),
+		);
+		assert!(!crate::EphemeralKeys::<Test>::contains_key(1, 0));
+		assert!(!crate::MessageEphemeralKeys::<Test>::contains_key(0));
+		assert_eq!(Messaging::ephemeral_key(&1, 1), Some(key(1)));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn republished_keys_outlive_their_first_publication() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(publish(0));
+		System::set_block_number(500);
+		assert_ok!(publish(0));
+
+		System::set_block_number(1002);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		System::assert_has_event(
+			Event::ExpiredEphemeralKeysPruned { count: 0, next: 1 }.into(),
+		);
+		assert_eq!(Messaging::ephemeral_key(&1, 0), Some(key(0)));
+	});
+}
+
+/// Accounts 1, 2 and 3 registered, with 2 and 3 having approved 1.
+fn setup_approving_trio() {
+	setup_pair();
+	assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(3), 1));
+}
+
+fn recipient_set(recipients: Vec<u64>) -> RecipientSet<u64> {
+	recipients.try_into().unwrap()
+}
+
+fn send_to_set(recipients: Vec<u64>) -> DispatchResult {
+	Messaging::send_to_recipient_set(
+		RuntimeOrigin::signed(1),
+		recipient_set(recipients),
+		H256::repeat_byte(1),
+	)
+}
+
+#[test]
+fn deposit_scales_with_the_set() {
+	new_test_ext().execute_with(|| {
+		setup_approving_trio();
+		assert_ok!(send_to_set(vec![2]));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), RecipientSetDeposit::get());
+
+		assert_ok!(send_to_set(vec![3, 2]));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 3 * RecipientSetDeposit::get());
+		assert_eq!(
+			crate::RecipientSetMessages::<Test>::get(1),
+			Some(RecipientSetMessageRecord {
+				sender: 1,
+				recipients: recipient_set(vec![3, 2]),
+				hash: H256::repeat_byte(1),
+				sent_at: 1,
+				deposit: 2 * RecipientSetDeposit::get(),
+			})
+		);
+		System::assert_last_event(
+			Event::RecipientSetMessageSent {
+				message_id: 1,
+				from: 1,
+				recipients: recipient_set(vec![3, 2]),
+				hash: H256::repeat_byte(1),
+				expires_at: 1001,
+				deposit: 2 * RecipientSetDeposit::get(),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn every_member_must_accept_the_sender() {
+	new_test_ext().execute_with(|| {
+		setup_approving_trio();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(5), vec![1, 2, 3, 4]));
+
+		// A single member that has not approved the sender fails the whole set
+		assert_noop!(send_to_set(vec![2, 5, 3]), failed(1, ItemError::NotApproved));
+		assert_noop!(send_to_set(vec![2, 6]), failed(1, ItemError::NotFound));
+
+		// An inbox pass stands in for approval
+		crate::InboxPasses::<Test>::insert(5, 1, 100);
+		assert_ok!(send_to_set(vec![2, 5, 3]));
+	});
+}
+
+#[test]
+fn sets_must_be_distinct_and_exclude_the_sender() {
+	new_test_ext().execute_with(|| {
+		setup_approving_trio();
+		assert_noop!(send_to_set(vec![]), Error::<Test>::EmptyRecipientSet);
+		assert_noop!(send_to_set(vec![2, 3, 2]), failed(2, ItemError::Duplicate));
+		assert_noop!(send_to_set(vec![2, 1]), failed(1, ItemError::Duplicate));
+	});
+}
+
+fn failed(index: u8, error: ItemError) -> Error<Test> {
+	Error::<Test>::ItemFailed { index, error }
+}
+
+#[test]
+fn rejected_member_is_reported_by_position() {
+	new_test_ext().execute_with(|| {
+		setup_approving_trio();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(5), vec![1, 2, 3, 4]));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(5), 1));
+		assert_ok!(send_to_set(vec![2]));
+		let next_id = crate::NextMessageId::<Test>::get();
+		let deposit = held(HoldReason::MessageDeposit, 1);
+		ban(3);
+
+		// First, middle and last position
+		assert_noop!(send_to_set(vec![3, 2, 5]), failed(0, ItemError::Banned));
+		assert_noop!(send_to_set(vec![2, 3, 5]), failed(1, ItemError::Banned));
+		assert_noop!(send_to_set(vec![2, 5, 3]), failed(2, ItemError::Banned));
+		assert_noop!(send_to_set(vec![2, 5, 6]), failed(2, ItemError::NotFound));
+
+		assert_eq!(crate::NextMessageId::<Test>::get(), next_id);
+		assert_eq!(held(HoldReason::MessageDeposit, 1), deposit);
+		assert!(!crate::RecipientSetMessages::<Test>::contains_key(next_id));
+		assert_eq!(crate::ReplyRefundableDeposits::<Test>::get(1, 5).len(), 0);
+	});
+}
+
+#[test]
+fn item_failure_rolls_back_earlier_items() {
+	new_test_ext().execute_with(|| {
+		for k in 0..3u64 {
+			let result = Messaging::try_each_item(0..3u64, |i| {
+				crate::InboxRevisions::<Test>::insert(10 + i, 1);
+				if i == k {
+					Err(ItemError::NotFound)
+				} else {
+					Ok(())
+				}
+			});
+
+			assert_eq!(result, Err(failed(k as u8, ItemError::NotFound).into()));
+			for i in 0..3u64 {
+				assert!(!crate::InboxRevisions::<Test>::contains_key(10 + i));
+			}
+		}
+
+		assert_ok!(Messaging::try_each_item(0..3u64, |i| {
+			crate::InboxRevisions::<Test>::insert(10 + i, 1);
+			Ok(())
+		}));
+		assert_eq!(crate::InboxRevisions::<Test>::get(12), 1);
+	});
+}
+
+#[test]
+fn deposit_beyond_balance_fails() {
+	new_test_ext().execute_with(|| {
+		setup_approving_trio();
+		// Enough for one listed account, not two
+		let short = 2 * RecipientSetDeposit::get() - 1;
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, short));
+
+		assert_noop!(send_to_set(vec![2, 3]), Error::<Test>::InsufficientRecipientSetDeposit);
+	});
+}
+
+#[test]
+fn any_member_acknowledges_once() {
+	new_test_ext().execute_with(|| {
+		setup_approving_trio();
+		assert_ok!(send_to_set(vec![2, 3]));
+
+		assert_noop!(
+			Messaging::acknowledge_recipient_set_message(RuntimeOrigin::signed(4), 0),
+			Error::<Test>::NotAuthorized
+		);
+		assert_ok!(Messaging::acknowledge_recipient_set_message(RuntimeOrigin::signed(3), 0));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+		System::assert_last_event(
+			Event::RecipientSetMessageAcknowledged {
+				message_id: 0,
+				by: 3,
+				refunded: 2 * RecipientSetDeposit::get(),
+			}
+			.into(),
+		);
+
+		assert_noop!(
+			Messaging::acknowledge_recipient_set_message(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::AlreadyAcknowledged
+		);
+	});
+}
+
+#[test]
+fn unacknowledged_messages_release_their_deposit_when_pruned() {
+	new_test_ext().execute_with(|| {
+		setup_approving_trio();
+		assert_ok!(send_to_set(vec![2, 3]));
+
+		System::set_block_number(1002);
+		assert_noop!(
+			Messaging::acknowledge_recipient_set_message(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::MessageExpired
+		);
+		assert!(Messaging::has_overdue_messages(1002));
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert!(!crate::RecipientSetMessages::<Test>::contains_key(0));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+	});
+}
+
+fn commit(version: u32) -> DispatchResult {
+	let backup_hash = H256::repeat_byte(version as u8);
+	Messaging::commit_backup(RuntimeOrigin::signed(1), backup_hash, version)
+}
+
+#[test]
+fn commit_backup_records_the_latest_version() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_eq!(Messaging::latest_backup(&1), None);
+
+		assert_ok!(commit(3));
+		assert_eq!(
+			Messaging::latest_backup(&1),
+			Some(BackupCommitment {
+				backup_hash: H256::repeat_byte(3),
+				version: 3,
+				committed_at: 1,
+				deposit: BackupDeposit::get(),
+			})
+		);
+		assert_eq!(held(HoldReason::BackupDeposit, 1), BackupDeposit::get());
+		System::assert_last_event(
+			Event::BackupCommitted { who: 1, backup_hash: H256::repeat_byte(3), version: 3 }
+				.into(),
+		);
+	});
+}
+
+#[test]
+fn versions_must_increase() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(commit(3));
+
+		assert_noop!(commit(3), Error::<Test>::StaleBackupVersion);
+		assert_noop!(commit(2), Error::<Test>::StaleBackupVersion);
+		assert_ok!(commit(7));
+	});
+}
+
+#[test]
+fn history_keeps_the_last_four_and_refunds_the_rest() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		for version in 1..=4 {
+			assert_ok!(commit(version));
+		}
+		assert_eq!(held(HoldReason::BackupDeposit, 1), 4 * BackupDeposit::get());
+
+		assert_ok!(commit(5));
+		assert_eq!(held(HoldReason::BackupDeposit, 1), 4 * BackupDeposit::get());
+		let versions: Vec<_> = crate::BackupCommitments::<Test>::get(1)
+			.iter()
+			.map(|backup| backup.version)
+			.collect();
+		assert_eq!(versions, vec![2, 3, 4, 5]);
+	});
+}
+
+#[test]
+fn commit_backup_requires_a_profile_and_a_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(commit(1), Error::<Test>::ProfileNotFound);
+
+		setup_pair();
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, BackupDeposit::get() - 1));
+		assert_noop!(commit(1), Error::<Test>::InsufficientBackupDeposit);
+	});
+}
+
+
+fn set_protocol_fee(message_protocol_fee: u128, exempt_mutual_contacts: bool) {
+	let parameters = PalletParameters {
+		message_protocol_fee,
+		exempt_mutual_contacts,
+		..Messaging::parameters()
+	};
+	assert_ok!(Messaging::update_parameters(RuntimeOrigin::signed(Admin::get()), parameters));
+}
+
+/// Send from `from` to `to` with a hash unique to the next message ID.
+fn try_send(from: u64, to: u64) -> DispatchResult {
+	let hash = H256::repeat_byte(1 + crate::NextMessageId::<Test>::get() as u8);
+	Messaging::send_message_hash(RuntimeOrigin::signed(from), to, hash)
+		.map(|_| ())
+		.map_err(|e| e.error)
+}
+
+fn fee_events() -> usize {
+	System::events()
+		.iter()
+		.filter(|record| {
+			matches!(
+				record.event,
+				RuntimeEvent::Messaging(Event::ProtocolFeeCharged { .. })
+			)
+		})
+		.count()
+}
+
+#[test]
+fn protocol_fee_is_burned() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_protocol_fee(7, false);
+		let issuance = Balances::total_issuance();
+		let balance = Balances::free_balance(1);
+
+		assert_ok!(try_send(1, 2));
+
+		assert_eq!(Balances::free_balance(1), balance - 7);
+		assert_eq!(Balances::total_issuance(), issuance - 7);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::ProtocolFee), 7);
+		System::assert_has_event(Event::ProtocolFeeCharged { who: 1, amount: 7 }.into());
+	});
+}
+
+#[test]
+fn zero_protocol_fee_charges_nothing() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let issuance = Balances::total_issuance();
+		System::reset_events();
+
+		assert_ok!(try_send(1, 2));
+
+		assert_eq!(Balances::total_issuance(), issuance);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::ProtocolFee), 0);
+		assert_eq!(fee_events(), 0);
+	});
+}
+
+#[test]
+fn mutual_contacts_are_exempt_only_when_enabled() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		set_protocol_fee(7, true);
+		let balance = Balances::free_balance(1);
+		System::reset_events();
+
+		assert_ok!(try_send(1, 2));
+		assert_eq!(Balances::free_balance(1), balance);
+		assert_eq!(fee_events(), 0);
+
+		set_protocol_fee(7, false);
+		System::set_block_number(2);
+		assert_ok!(try_send(1, 2));
+		assert_eq!(Balances::free_balance(1), balance - 7);
+		assert_eq!(fee_events(), 1);
+	});
+}
+
+#[test]
+fn exemption_does_not_cover_one_sided_contacts() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		set_protocol_fee(7, true);
+		let balance = Balances::free_balance(1);
+
+		assert_ok!(try_send(1, 2));
+
+		assert_eq!(Balances::free_balance(1), balance - 7);
+	});
+}
+
+#[test]
+fn paid_sends_pay_the_protocol_fee_on_top_of_the_price() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		set_protocol_fee(7, false);
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(10)));
+		let balance = Balances::free_balance(1);
+
+		assert_ok!(send_paid_next(1, 2));
+
+		assert_eq!(held(HoldReason::InboxPayment, 1), 10);
+		assert_eq!(Balances::free_balance(1), balance - 10 - 7);
+		System::assert_has_event(Event::ProtocolFeeCharged { who: 1, amount: 7 }.into());
+	});
+}
+
+#[test]
+fn ephemeral_key_sends_pay_the_protocol_fee() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_protocol_fee(7, false);
+		assert_ok!(publish(0));
+		let balance = Balances::free_balance(1);
+
+		assert_ok!(send_under(0));
+
+		assert_eq!(Balances::free_balance(1), balance - 7);
+		System::assert_has_event(Event::ProtocolFeeCharged { who: 1, amount: 7 }.into());
+	});
+}
+
+#[test]
+fn accept_and_send_pays_the_protocol_fee_unless_contacts_are_exempt() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(3), 1));
+		set_protocol_fee(7, false);
+		let balance = Balances::free_balance(1);
+
+		assert_ok!(Messaging::accept_and_send(RuntimeOrigin::signed(1), 2, H256::repeat_byte(1)));
+		assert_eq!(Balances::free_balance(1), balance - 7);
+
+		// Accepting makes the pair mutual contacts, which the exemption covers
+		set_protocol_fee(7, true);
+		System::set_block_number(2);
+		System::reset_events();
+		assert_ok!(Messaging::accept_and_send(RuntimeOrigin::signed(1), 3, H256::repeat_byte(2)));
+		assert_eq!(Balances::free_balance(1), balance - 7);
+		assert_eq!(fee_events(), 0);
+	});
+}
+
+#[test]
+fn insufficient_balance_for_protocol_fee_fails_cleanly() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_protocol_fee(7, false);
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, 7));
+
+		assert_noop!(try_send(1, 2), Error::<Test>::InsufficientProtocolFee);
+		assert_eq!(crate::NextMessageId::<Test>::get(), 0);
+	});
+}
+
+#[test]
+fn unaffordable_send_leaves_rate_limit_and_decay_alone() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		System::set_block_number(145);
+		assert_ok!(try_send(1, 2));
+
+		// A strike due to decay and a rate-limit window in progress
+		crate::Reputation::<Test>::insert(1, ReputationRecord { strikes: 1, last_decay: 1 });
+		System::set_block_number(150);
+		set_protocol_fee(7, false);
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, 7));
+		let rate_limit = crate::SendRateLimits::<Test>::get(1);
+		let reputation = crate::Reputation::<Test>::get(1);
+
+		assert_noop!(try_send(1, 2), Error::<Test>::InsufficientProtocolFee);
+		assert_eq!(crate::SendRateLimits::<Test>::get(1), rate_limit);
+		assert_eq!(crate::Reputation::<Test>::get(1), reputation);
+	});
+}
+
+fn set_pricing(verified: Option<u128>, others: Option<u128>) {
+	let pricing = InboxPricing { verified, others };
+	assert_ok!(Messaging::set_inbox_pricing(RuntimeOrigin::signed(2), pricing));
+}
+
+/// Send a paid message from `from` to `to` with a hash unique to the next message ID.
+fn send_paid_next(from: u64, to: u64) -> DispatchResult {
+	let hash = H256::repeat_byte(1 + crate::NextMessageId::<Test>::get() as u8);
+	Messaging::send_paid_message_hash(RuntimeOrigin::signed(from), to, hash)
+		.map(|_| ())
+		.map_err(|e| e.error)
+}
+
+/// Register 1 and 2, plus 3 with a verified identity.
+fn setup_verified_third() {
+	setup_pair();
+	assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+	VerifiedIdentities::set(vec![3]);
+}
+
+#[test]
+fn set_inbox_pricing_works() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+
+		set_pricing(Some(20), Some(50));
+		let pricing = InboxPricing { verified: Some(20), others: Some(50) };
+		assert_eq!(crate::InboxPricings::<Test>::get(2), Some(pricing.clone()));
+		System::assert_last_event(Event::InboxPricingSet { who: 2, pricing }.into());
+
+		// Closing every tier clears the pricing
+		set_pricing(None, None);
+		assert!(!crate::InboxPricings::<Test>::contains_key(2));
+	});
+}
+
+#[test]
+fn set_inbox_pricing_requires_profile() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::set_inbox_pricing(RuntimeOrigin::signed(2), InboxPricing::default()),
+			Error::<Test>::ProfileNotFound
+		);
+	});
+}
+
+#[test]
+fn contacts_are_not_charged() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		set_pricing(Some(20), Some(50));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+
+		assert_eq!(Messaging::inbox_price_for(&2, &1), Ok((InboxTier::Contact, 0)));
+		assert_ok!(send_paid_next(1, 2));
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+		assert!(!crate::InboxPayments::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn verified_senders_pay_the_verified_tier() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		set_pricing(Some(20), Some(50));
+
+		assert_eq!(Messaging::inbox_price_for(&2, &3), Ok((InboxTier::Verified, 20)));
+		assert_ok!(send_paid_next(3, 2));
+		assert_eq!(held(HoldReason::InboxPayment, 3), 20);
+		System::assert_last_event(
+			Event::InboxPaymentHeld {
+				message_id: 0,
+				sender: 3,
+				recipient: 2,
+				amount: 20,
+				tier: InboxTier::Verified,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn other_senders_pay_the_others_tier() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		set_pricing(Some(20), Some(50));
+
+		assert_eq!(Messaging::inbox_price_for(&2, &1), Ok((InboxTier::Others, 50)));
+		assert_ok!(send_paid_next(1, 2));
+		assert_eq!(held(HoldReason::InboxPayment, 1), 50);
+		System::assert_last_event(
+			Event::InboxPaymentHeld {
+				message_id: 0,
+				sender: 1,
+				recipient: 2,
+				amount: 50,
+				tier: InboxTier::Others,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn closed_tiers_reject_their_class() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		set_pricing(Some(20), None);
+
+		assert_noop!(send_paid_next(1, 2), Error::<Test>::InboxClosed);
+		assert_ok!(send_paid_next(3, 2));
+
+		set_pricing(None, Some(50));
+		assert_noop!(send_paid_next(3, 2), Error::<Test>::InboxClosed);
+		assert_ok!(send_paid_next(1, 2));
+	});
+}
+
+#[test]
+fn tiered_pricing_takes_precedence_over_flat_price() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(30)));
+		assert_eq!(Messaging::inbox_price_for(&2, &3), Ok((InboxTier::Others, 30)));
+
+		set_pricing(Some(20), Some(50));
+		assert_eq!(Messaging::inbox_price_for(&2, &3), Ok((InboxTier::Verified, 20)));
+		assert_eq!(Messaging::inbox_price_for(&2, &1), Ok((InboxTier::Others, 50)));
+
+		// Clearing the tiers falls back to the flat price
+		set_pricing(None, None);
+		assert_eq!(Messaging::inbox_price_for(&2, &1), Ok((InboxTier::Others, 30)));
+	});
+}
+
+/// Check that `sender` cannot reach 2 for free with `send_message_hash` and pays `price`
+/// once it sends paid.
+fn assert_price_is_not_bypassed(sender: u64, tier: InboxTier, price: u128) {
+	assert_eq!(Messaging::inbox_price_for(&2, &sender), Ok((tier, price)));
+	assert_noop!(try_send(sender, 2), Error::<Test>::ContactNotApproved);
+	assert_noop!(
+		Messaging::send_message_hash_batch(RuntimeOrigin::signed(sender), batch(vec![(2, 1)])),
+		failed(0, ItemError::CannotMessage(CanMessageError::NotApproved))
+	);
+
+	assert_ok!(send_paid_next(sender, 2));
+	assert_eq!(held(HoldReason::InboxPayment, sender), price);
+}
+
+#[test]
+fn verified_tier_cannot_be_bypassed() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		set_pricing(Some(20), Some(50));
+		assert_price_is_not_bypassed(3, InboxTier::Verified, 20);
+	});
+}
+
+#[test]
+fn others_tier_cannot_be_bypassed() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		set_pricing(Some(20), Some(50));
+		assert_price_is_not_bypassed(1, InboxTier::Others, 50);
+	});
+}
+
+#[test]
+fn flat_price_cannot_be_bypassed() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(30)));
+		assert_price_is_not_bypassed(1, InboxTier::Others, 30);
+	});
+}
+
+#[test]
+fn removing_pricing_keeps_open_escrows() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		set_pricing(Some(20), Some(50));
+		assert_ok!(send_paid_next(1, 2));
+
+		set_pricing(None, None);
+		assert_noop!(send_paid_next(1, 2), Error::<Test>::InboxClosed);
+
+		// The held payment is still paid out at the price it was taken at
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+		assert_eq!(Balances::free_balance(2), 950);
+	});
+}
+
+#[test]
+fn ban_clears_inbox_pricing() {
+	new_test_ext().execute_with(|| {
+		setup_verified_third();
+		set_pricing(Some(20), Some(50));
+
+		assert_ok!(Messaging::ban_account(RuntimeOrigin::root(), 2, H256::zero()));
+		assert!(!crate::InboxPricings::<Test>::contains_key(2));
+	});
+}
+
+/// Register 1, 2 and 3 approving each other, so that 2 and 3 can reply to 1.
+fn setup_replying_trio() {
+	setup_approving_trio();
+	for who in [2, 3] {
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), who));
+	}
+}
+
+fn reply(from: u64) -> DispatchResult {
+	Messaging::send_message_hash(RuntimeOrigin::signed(from), 1, H256::repeat_byte(2))
+		.map(|_| ())
+		.map_err(|e| e.error)
+}
+
+fn refunded_on_reply() -> Vec<u64> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::Messaging(Event::DepositReleasedOnReply { message_id, .. }) =>
+				Some(message_id),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn reply_refunds_the_deposit() {
+	new_test_ext().execute_with(|| {
+		setup_replying_trio();
+		assert_ok!(send_to_set(vec![2, 3]));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 2 * RecipientSetDeposit::get());
+
+		assert_ok!(reply(2));
+
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+		assert_eq!(crate::RecipientSetMessages::<Test>::get(0).map(|m| m.deposit), Some(0));
+		System::assert_has_event(
+			Event::DepositReleasedOnReply {
+				message_id: 0,
+				sender: 1,
+				replier: 2,
+				refunded: 2 * RecipientSetDeposit::get(),
+			}
+			.into(),
+		);
+		// The message is settled for every member
+		assert!(!crate::ReplyRefundableDeposits::<Test>::contains_key(1, 2));
+		assert!(!crate::ReplyRefundableDeposits::<Test>::contains_key(1, 3));
+		assert_noop!(
+			Messaging::acknowledge_recipient_set_message(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::AlreadyAcknowledged
+		);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn reply_refunds_the_most_recent_deposits_only() {
+	new_test_ext().execute_with(|| {
+		setup_replying_trio();
+		for _ in 0..3 {
+			assert_ok!(send_to_set(vec![2]));
+		}
+		assert_eq!(crate::ReplyRefundableDeposits::<Test>::get(1, 2).into_inner(), vec![1, 2]);
+		System::reset_events();
+
+		assert_ok!(reply(2));
+
+		assert_eq!(refunded_on_reply(), vec![2, 1]);
+		assert_eq!(held(HoldReason::MessageDeposit, 1), RecipientSetDeposit::get());
+
+		// The oldest message waits for acknowledgement
+		assert_ok!(Messaging::acknowledge_recipient_set_message(RuntimeOrigin::signed(2), 0));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn only_members_of_the_set_trigger_refunds() {
+	new_test_ext().execute_with(|| {
+		setup_replying_trio();
+		assert_ok!(send_to_set(vec![2]));
+		System::reset_events();
+
+		assert_ok!(reply(3));
+
+		assert!(refunded_on_reply().is_empty());
+		assert_eq!(held(HoldReason::MessageDeposit, 1), RecipientSetDeposit::get());
+	});
+}
+
+#[test]
+fn acknowledged_messages_are_not_refunded_twice() {
+	new_test_ext().execute_with(|| {
+		setup_replying_trio();
+		assert_ok!(send_to_set(vec![2, 3]));
+		assert_ok!(Messaging::acknowledge_recipient_set_message(RuntimeOrigin::signed(3), 0));
+		assert!(!crate::ReplyRefundableDeposits::<Test>::contains_key(1, 2));
+		System::reset_events();
+
+		assert_ok!(reply(2));
+
+		assert!(refunded_on_reply().is_empty());
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+	});
+}
+
+#[test]
+fn replies_after_expiry_leave_the_deposit_to_pruning() {
+	new_test_ext().execute_with(|| {
+		setup_replying_trio();
+		assert_ok!(send_to_set(vec![2]));
+		System::set_block_number(1002);
+		System::reset_events();
+
+		assert_ok!(reply(2));
+
+		assert!(refunded_on_reply().is_empty());
+		assert_eq!(held(HoldReason::MessageDeposit, 1), RecipientSetDeposit::get());
+		assert!(!crate::ReplyRefundableDeposits::<Test>::contains_key(1, 2));
+
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn pruning_drops_messages_from_the_index() {
+	new_test_ext().execute_with(|| {
+		setup_approving_trio();
+		assert_ok!(send_to_set(vec![2, 3]));
+		System::set_block_number(1002);
+
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+
+		assert!(!crate::ReplyRefundableDeposits::<Test>::contains_key(1, 2));
+		assert!(!crate::ReplyRefundableDeposits::<Test>::contains_key(1, 3));
+	});
+}
+
+#[test]
+fn challenging_the_reply_keeps_the_refund() {
+	new_test_ext().execute_with(|| {
+		setup_replying_trio();
+		assert_ok!(send_to_set(vec![2]));
+		assert_ok!(reply(2));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+
+		// The reply can still be challenged as spam, without reviving the deposit
+		let commitment = evidence_commitment();
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(1), 1, commitment));
+		assert!(crate::Challenges::<Test>::contains_key(1));
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+		assert_eq!(crate::RecipientSetMessages::<Test>::get(0).map(|m| m.deposit), Some(0));
+	});
+}
+
+#[test]
+fn challenged_senders_are_still_refunded() {
+	new_test_ext().execute_with(|| {
+		setup_replying_trio();
+		let hash = H256::repeat_byte(3);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		let commitment = evidence_commitment();
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, commitment));
+		assert_ok!(send_to_set(vec![2]));
+
+		assert_ok!(reply(2));
+
+		assert_eq!(refunded_on_reply(), vec![1]);
+		assert_eq!(held(HoldReason::MessageDeposit, 1), 0);
+	});
+}
+
+fn standing(who: u64) -> AccountStandingOf<Test> {
+	view(StandingViewFunction::<Test>::new(who))
+}
+
+/// The error `send_message_hash` to a non-contact should fail with, in dispatch order.
+fn predicted(standing: &AccountStandingOf<Test>) -> Result<(), Error<Test>> {
+	let checks = [
+		(!standing.can_pay_protocol_fee, Error::<Test>::InsufficientProtocolFee),
+		(standing.sends_paused, Error::<Test>::PalletPaused),
+		(standing.banned, Error::<Test>::AccountBanned),
+		(standing.frozen_until.is_some(), Error::<Test>::SenderFrozen),
+		(!standing.registered, Error::<Test>::ProfileNotFound),
+		(standing.graylisted, Error::<Test>::Graylisted),
+		(standing.bonded < standing.required_bond, Error::<Test>::InsufficientBond),
+		(standing.rate_limit_remaining == 0, Error::<Test>::RateLimitExceeded),
+	];
+	checks.into_iter().find(|(failed, _)| *failed).map_or(Ok(()), |(_, error)| Err(error))
+}
+
+#[test]
+fn standing_predicts_send_outcome() {
+	let scenarios: [(&str, u64, fn()); 9] = [
+		("healthy", 1, || {}),
+		("unregistered", 3, || {}),
+		("banned", 1, || ban(1)),
+		("frozen", 1, || {
+			add_moderator();
+			assert_ok!(Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10));
+		}),
+		("paused", 1, || {
+			let origin = RuntimeOrigin::signed(Admin::get());
+			assert_ok!(Messaging::pause(origin, PausedCalls::SENDS));
+		}),
+		("graylisted", 1, || {
+			for _ in 0..3 {
+				add_strike(1, 2);
+			}
+			System::set_block_number(20);
+		}),
+		("underbonded", 1, || {
+			add_strike(1, 2);
+			add_strike(1, 2);
+			top_up_to(1, 100);
+			System::set_block_number(20);
+		}),
+		("rate limited", 1, || {
+			for byte in 1..=10 {
+				assert_ok!(Messaging::send_message_hash(
+					RuntimeOrigin::signed(1),
+					2,
+					H256::repeat_byte(byte)
+				));
+			}
+		}),
+		("fee unaffordable", 1, || {
+			let parameters =
+				PalletParameters { message_protocol_fee: 2000, ..Messaging::parameters() };
+			let origin = RuntimeOrigin::signed(Admin::get());
+			assert_ok!(Messaging::update_parameters(origin, parameters));
+		}),
+	];
+
+	for (name, sender, setup) in scenarios {
+		new_test_ext().execute_with(|| {
+			setup_approved_pair();
+			setup();
+
+			let expected = predicted(&standing(sender)).map_err(DispatchError::from);
+			let hash = H256::repeat_byte(11);
+			let result = Messaging::send_message_hash(RuntimeOrigin::signed(sender), 2, hash)
+				.map(|_| ())
+				.map_err(|e| e.error);
+			assert_eq!(result, expected, "scenario: {name}");
+		});
+	}
+}
+
+#[test]
+fn standing_reports_each_restriction() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let fresh = standing(1);
+		assert!(fresh.registered && !fresh.banned && !fresh.sends_paused);
+		assert_eq!((fresh.strikes, fresh.graylisted), (0, false));
+		assert_eq!((fresh.bonded, fresh.required_bond), (100, 100));
+		assert_eq!(fresh.rate_limit_remaining, MaxMessagesPerWindow::get());
+		assert!(fresh.can_pay_protocol_fee);
+
+		add_strike(1, 2);
+		let struck = standing(1);
+		assert_eq!(struck.strikes, 1);
+		assert_eq!(struck.required_bond, 100);
+		// The challenged message counts against the halved window
+		assert_eq!(struck.rate_limit_remaining, MaxMessagesPerWindow::get() / 2 - 1);
+
+		add_moderator();
+		assert_ok!(Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10));
+		assert_eq!(standing(1).frozen_until, Some(11));
+		System::set_block_number(11);
+		assert_eq!(standing(1).frozen_until, None);
+	});
+}
+
+#[test]
+fn standing_tracks_bond_refundability() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert!(standing(1).can_refund_bond);
+
+		let hash = H256::repeat_byte(1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		let locked = standing(1);
+		assert_eq!(locked.bond_locked_until, Some(1 + BondUnbondingPeriod::get()));
+		assert!(!locked.can_refund_bond);
+
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			0,
+			evidence_commitment()
+		));
+		System::set_block_number(1 + BondUnbondingPeriod::get());
+		let challenged = standing(1);
+		assert_eq!((challenged.open_challenges, challenged.bond_locked_until), (1, None));
+		assert!(!challenged.can_refund_bond);
+		assert_noop!(
+			Messaging::refund_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::OpenChallengesPending
+		);
+
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, false));
+		assert!(standing(1).can_refund_bond);
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+		assert!(!standing(1).can_refund_bond);
+	});
+}
+
+fn buy(count: u32) -> DispatchResult {
+	Messaging::purchase_credits(RuntimeOrigin::signed(1), count)
+}
+
+fn credits(who: u64) -> u32 {
+	crate::MessageCredits::<Test>::get(who)
+}
+
+#[test]
+fn purchase_holds_refundable_part_and_collects_the_rest() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let balance = Balances::free_balance(1);
+
+		assert_ok!(buy(4));
+
+		assert_eq!(credits(1), 4);
+		assert_eq!(Balances::free_balance(1), balance - 4 * CreditPrice::get());
+		assert_eq!(held(HoldReason::MessageCredits, 1), 2 * CreditPrice::get());
+		assert_eq!(
+			crate::PalletIncome::<Test>::get(IncomeCategory::Fee),
+			2 * CreditPrice::get()
+		);
+		System::assert_last_event(
+			Event::CreditsPurchased { who: 1, count: 4, paid: 4 * CreditPrice::get() }.into(),
+		);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn purchase_is_capped_per_account() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_noop!(buy(0), Error::<Test>::ZeroCreditPurchase);
+		assert_noop!(
+			buy(MaxCreditsPerAccount::get() + 1),
+			Error::<Test>::CreditLimitExceeded
+		);
+
+		assert_ok!(buy(MaxCreditsPerAccount::get() - 1));
+		assert_noop!(buy(2), Error::<Test>::CreditLimitExceeded);
+		assert_noop!(buy(u32::MAX), Error::<Test>::CreditLimitExceeded);
+		assert_ok!(buy(1));
+		assert_eq!(credits(1), MaxCreditsPerAccount::get());
+
+		// Using a credit makes room for another
+		set_protocol_fee(7, false);
+		assert_ok!(try_send(1, 2));
+		assert_ok!(buy(1));
+	});
+}
+
+#[test]
+fn purchase_requires_profile_and_funds() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(buy(1), Error::<Test>::ProfileNotFound);
+
+		setup_pair();
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, 1));
+		assert_noop!(buy(1), Error::<Test>::InsufficientCreditPayment);
+	});
+}
+
+#[test]
+fn credit_is_used_instead_of_protocol_fee() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_protocol_fee(7, false);
+		assert_ok!(buy(1));
+		let balance = Balances::free_balance(1);
+		System::reset_events();
+
+		assert_ok!(try_send(1, 2));
+
+		assert_eq!(credits(1), 0);
+		assert_eq!(Balances::free_balance(1), balance);
+		assert_eq!(held(HoldReason::MessageCredits, 1), 0);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::ProtocolFee), 0);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::Fee), CreditPrice::get());
+		System::assert_has_event(Event::CreditsRemaining { who: 1, remaining: 0 }.into());
+		assert!(!System::events().iter().any(|record| matches!(
+			record.event,
+			RuntimeEvent::Messaging(Event::ProtocolFeeCharged { .. })
+		)));
+
+		// Without credits the fee is charged again
+		assert_ok!(try_send(1, 2));
+		assert_eq!(Balances::free_balance(1), balance - 7);
+		System::assert_has_event(Event::ProtocolFeeCharged { who: 1, amount: 7 }.into());
+	});
+}
+
+#[test]
+fn credits_are_kept_when_no_fee_is_due() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(buy(2));
+
+		// No protocol fee
+		assert_ok!(try_send(1, 2));
+		assert_eq!(credits(1), 2);
+
+		// Fee waived for mutual contacts
+		approve(1, 2);
+		set_protocol_fee(7, true);
+		assert_ok!(try_send(1, 2));
+		assert_eq!(credits(1), 2);
+	});
+}
+
+#[test]
+fn credits_do_not_bypass_rate_limits_or_bonds() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_protocol_fee(7, false);
+		assert_ok!(buy(MaxCreditsPerAccount::get()));
+		for _ in 0..MaxMessagesPerWindow::get() {
+			assert_ok!(try_send(1, 2));
+		}
+		let left = credits(1);
+		assert_eq!(left, MaxCreditsPerAccount::get() - MaxMessagesPerWindow::get());
+
+		assert_noop!(try_send(1, 2), Error::<Test>::RateLimitExceeded);
+		assert_eq!(credits(1), left);
+
+		System::set_block_number(RateLimitWindow::get() + 1);
+		add_strike(1, 2);
+		add_strike(1, 2);
+		assert_noop!(try_send(1, 2), Error::<Test>::InsufficientBond);
+	});
+}
+
+#[test]
+fn milestones_are_reported_every_ten_credits() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_protocol_fee(7, false);
+		assert_ok!(buy(crate::CREDIT_MILESTONE + 2));
+		System::reset_events();
+
+		assert_ok!(try_send(1, 2));
+		assert_ok!(try_send(1, 2));
+
+		let remaining: Vec<u32> = System::events()
+			.into_iter()
+			.filter_map(|record| match record.event {
+				RuntimeEvent::Messaging(Event::CreditsRemaining { remaining, .. }) =>
+					Some(remaining),
+				_ => None,
+			})
+			.collect();
+		assert_eq!(remaining, vec![crate::CREDIT_MILESTONE]);
+	});
+}
+
+#[test]
+fn refund_returns_held_part_of_unused_credits() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_protocol_fee(7, false);
+		assert_ok!(buy(5));
+		assert_ok!(try_send(1, 2));
+		let balance = Balances::free_balance(1);
+
+		assert_ok!(Messaging::refund_unused_credits(RuntimeOrigin::signed(1)));
+
+		let refunded = 4 * CreditRefund::get().mul_floor(CreditPrice::get());
+		assert_eq!(Balances::free_balance(1), balance + refunded);
+		assert_eq!(held(HoldReason::MessageCredits, 1), 0);
+		assert_eq!(credits(1), 0);
+		System::assert_last_event(Event::CreditsRefunded { who: 1, count: 4, refunded }.into());
+
+		assert_noop!(
+			Messaging::refund_unused_credits(RuntimeOrigin::signed(1)),
+			Error::<Test>::NoUnusedCredits
+		);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+const SPONSOR: u64 = 5;
+
+fn proof(who: u64, sponsor: u64, algorithm: KeyAlgorithm) -> TestSignature {
+	let sponsorship = (sponsor, vec![1u8; 32], algorithm).encode();
+	TestSignature(who, [SPONSORSHIP_CONTEXT, &sponsorship[..]].concat())
+}
+
+fn sponsor(who: u64) -> DispatchResult {
+	Messaging::sponsor_registration(
+		RuntimeOrigin::signed(SPONSOR),
+		who,
+		vec![1u8; 32],
+		KeyAlgorithm::X25519,
+		proof(who, SPONSOR, KeyAlgorithm::X25519),
+	)
+}
+
+#[test]
+fn sponsor_holds_the_bond() {
+	new_test_ext().execute_with(|| {
+		// Account 4 cannot afford the bond itself
+		assert_ok!(sponsor(4));
+
+		assert!(Messaging::profile(&4).is_some());
+		assert_eq!(crate::KeyAlgorithms::<Test>::get(4), KeyAlgorithm::X25519);
+		assert_eq!(
+			crate::SpamBonds::<Test>::get(4),
+			BondRecord { amount: 100, sponsor: Some(SPONSOR) }
+		);
+		assert_eq!(held(HoldReason::SpamBond, SPONSOR), 100);
+		assert_eq!(held(HoldReason::SpamBond, 4), 0);
+		assert_eq!(Balances::free_balance(4), 50);
+		System::assert_last_event(
+			Event::RegistrationSponsored { who: 4, sponsor: SPONSOR, amount: 100 }.into(),
+		);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn sponsorship_needs_the_accounts_consent() {
+	new_test_ext().execute_with(|| {
+		let register = |proof_of_possession| {
+			Messaging::sponsor_registration(
+				RuntimeOrigin::signed(SPONSOR),
+				4,
+				vec![1u8; 32],
+				KeyAlgorithm::X25519,
+				proof_of_possession,
+			)
+		};
+
+		// Signed by another account, for another sponsor or for another algorithm
+		for proof_of_possession in [
+			proof(3, SPONSOR, KeyAlgorithm::X25519),
+			proof(4, 6, KeyAlgorithm::X25519),
+			proof(4, SPONSOR, KeyAlgorithm::Ed25519),
+		] {
+			assert_noop!(register(proof_of_possession), Error::<Test>::InvalidSponsorshipProof);
+		}
+
+		assert_noop!(
+			Messaging::sponsor_registration(
+				RuntimeOrigin::signed(SPONSOR),
+				SPONSOR,
+				vec![1u8; 32],
+				KeyAlgorithm::X25519,
+				proof(SPONSOR, SPONSOR, KeyAlgorithm::X25519),
+			),
+			Error::<Test>::CannotSponsorSelf
+		);
+	});
+}
+
+#[test]
+fn refund_returns_the_bond_to_the_sponsor() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(sponsor(4));
+
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(4)));
+
+		assert_eq!(held(HoldReason::SpamBond, SPONSOR), 0);
+		assert_eq!(Balances::free_balance(SPONSOR), 1000);
+		assert_eq!(Balances::free_balance(4), 50);
+		System::assert_last_event(Event::BondRefunded { who: 4, amount: 100 }.into());
+	});
+}
+
+#[test]
+fn sponsored_bond_cannot_be_topped_up() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(sponsor(1));
+
+		assert_noop!(
+			Messaging::top_up_bond(RuntimeOrigin::signed(1), 50),
+			Error::<Test>::BondSponsored
+		);
+	});
+}
+
+#[test]
+fn slashes_and_bans_fall_on_the_sponsor() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(sponsor(1));
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![2u8; 32]));
+		approve(2, 1);
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			0,
+			evidence_commitment()
+		));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+
+		assert_eq!(held(HoldReason::SpamBond, SPONSOR), 50);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 50);
+		assert_eq!(Balances::free_balance(1), 1000);
+
+		ban(1);
+
+		assert_eq!(held(HoldReason::SpamBond, SPONSOR), 0);
+		assert_eq!(Balances::total_balance(&SPONSOR), 900);
+		assert_eq!(Balances::free_balance(1), 1000);
+	});
+}
+
+#[test]
+fn reversed_slash_returns_to_the_sponsor() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(sponsor(1));
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![2u8; 32]));
+		approve(2, 1);
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			0,
+			evidence_commitment()
+		));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0));
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, false));
+
+		assert_eq!(held(HoldReason::SpamBond, SPONSOR), 100);
+		assert_eq!(held(HoldReason::SpamBond, 1), 0);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 100);
+		System::assert_has_event(Event::SlashReversed { who: 1, amount: 50 }.into());
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn assume_own_bond_moves_the_hold_to_the_account() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(sponsor(1));
+
+		assert_ok!(Messaging::assume_own_bond(RuntimeOrigin::signed(1)));
+
+		assert_eq!(crate::SpamBonds::<Test>::get(1), BondRecord { amount: 100, sponsor: None });
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(held(HoldReason::SpamBond, SPONSOR), 0);
+		assert_eq!(Balances::free_balance(SPONSOR), 1000);
+		System::assert_last_event(
+			Event::BondAssumed { who: 1, sponsor: SPONSOR, amount: 100 }.into(),
+		);
+
+		// The bond is now the account's own
+		assert_noop!(
+			Messaging::assume_own_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::BondNotSponsored
+		);
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(1), 50));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn assume_own_bond_checks_funds_and_open_challenges() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(sponsor(4));
+		assert_noop!(
+			Messaging::assume_own_bond(RuntimeOrigin::signed(4)),
+			Error::<Test>::InsufficientBond
+		);
+
+		assert_ok!(sponsor(1));
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(2), vec![2u8; 32]));
+		approve(2, 1);
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			0,
+			evidence_commitment()
+		));
+		assert_noop!(
+			Messaging::assume_own_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::OpenChallengesPending
+		);
+	});
+}
+
+/// Runs `test` with bonds held in the native currency, then again in `BOND_ASSET`.
+fn in_each_bond_currency(test: impl Fn()) {
+	for asset in [NativeOrWithId::Native, NativeOrWithId::WithId(BOND_ASSET)] {
+		BondAsset::set(asset);
+		new_test_ext().execute_with(&test);
+	}
+}
+
+fn balance(who: u64) -> Balance {
+	MessagingCurrency::balance(&who)
+}
+
+fn on_hold(reason: HoldReason, who: u64) -> Balance {
+	MessagingCurrency::balance_on_hold(&reason.into(), &who)
+}
+
+#[test]
+fn bond_is_held_and_refunded_in_the_bond_currency() {
+	in_each_bond_currency(|| {
+		setup_pair();
+		assert_eq!(on_hold(HoldReason::SpamBond, 1), 100);
+		assert_eq!(balance(1), 900);
+
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+
+		assert_eq!(on_hold(HoldReason::SpamBond, 1), 0);
+		assert_eq!(balance(1), 1000);
+	});
+}
+
+#[test]
+fn challenge_settles_in_the_bond_currency() {
+	in_each_bond_currency(|| {
+		let issuance = MessagingCurrency::total_issuance();
+		challenge_and_resolve(true);
+		assert_eq!(on_hold(HoldReason::ChallengeBond, 3), 20);
+		assert_eq!(on_hold(HoldReason::SpamBond, 1), 50);
+		assert_eq!(balance(Messaging::escrow_account()), 50);
+
+		System::set_block_number(12);
+		assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(5), 0));
+
+		// Challenger gets their bond back plus half of the 50 slash, the rest is burned
+		assert_eq!(on_hold(HoldReason::ChallengeBond, 3), 0);
+		assert_eq!(balance(3), 1025);
+		assert_eq!(balance(Messaging::escrow_account()), 0);
+		assert_eq!(MessagingCurrency::total_issuance(), issuance - 25);
+	});
+}
+
+#[test]
+fn fees_are_collected_in_the_bond_currency() {
+	in_each_bond_currency(|| {
+		setup_pair();
+		let treasury = balance(TreasuryAccount::get());
+
+		assert_ok!(Messaging::purchase_credits(RuntimeOrigin::signed(1), 4));
+
+		assert_eq!(balance(1), 900 - 4 * CreditPrice::get());
+		assert_eq!(on_hold(HoldReason::MessageCredits, 1), 2 * CreditPrice::get());
+		assert_eq!(balance(TreasuryAccount::get()), treasury + 2 * CreditPrice::get());
+	});
+}
+
+#[test]
+fn asset_bonds_leave_native_balances_untouched() {
+	BondAsset::set(NativeOrWithId::WithId(BOND_ASSET));
+	new_test_ext().execute_with(|| {
+		challenge_and_resolve(true);
+
+		for who in [1, 2, 3] {
+			assert_eq!(Balances::free_balance(who), 1000);
+			assert_eq!(Balances::total_balance_on_hold(&who), 0);
+		}
+		assert_eq!(Assets::balance(BOND_ASSET, 1), 900);
+	});
+}
+
+#[test]
+fn parameters_are_fixed_amounts_of_the_bond_asset() {
+	BondAsset::set(NativeOrWithId::WithId(BOND_ASSET));
+	new_test_ext().execute_with(|| {
+		let parameters = PalletParameters { spam_bond: 300, ..Messaging::parameters() };
+		assert_ok!(Messaging::update_parameters(
+			RuntimeOrigin::signed(Admin::get()),
+			parameters
+		));
+
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1u8; 32]));
+
+		assert_eq!(on_hold(HoldReason::SpamBond, 1), 300);
+		assert_eq!(Assets::balance(BOND_ASSET, 1), 700);
+	});
+}
+
+/// Existential deposit well above the spam bond.
+const ED: Balance = 500;
+
+fn try_register(who: u64) -> sp_runtime::DispatchResult {
+	Messaging::register_profile(RuntimeOrigin::signed(who), vec![1u8; 32])
+}
+
+fn transfer_all(who: u64) {
+	assert_ok!(Balances::transfer_all(RuntimeOrigin::signed(who), 5, false));
+}
+
+#[test]
+fn registered_account_survives_transferring_out_its_funds() {
+	new_test_ext().execute_with(|| {
+		ExistentialDeposit::set(ED);
+		let consumers = System::consumers(&1);
+		assert_ok!(try_register(1));
+		// The profile's reference comes on top of the one taken for the held bond
+		assert_eq!(System::consumers(&1), consumers + 2);
+
+		// The held bond keeps the existential deposit in place
+		transfer_all(1);
+		assert_eq!(Balances::free_balance(1), ED);
+		assert!(Balances::transfer_allow_death(RuntimeOrigin::signed(1), 5, ED).is_err());
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+
+		// Once refunded, the profile alone keeps the account from being reaped
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+		assert_eq!(Balances::free_balance(1), ED + 100);
+		assert_eq!(System::consumers(&1), consumers + 1);
+		transfer_all(1);
+		assert_eq!(Balances::free_balance(1), ED);
+		assert!(Balances::transfer_allow_death(RuntimeOrigin::signed(1), 5, ED).is_err());
+		assert!(System::account_exists(&1));
+		assert!(Messaging::profile(&1).is_some());
+		assert_ok!(Messaging::do_try_state());
+
+		// Funded again, the account bonds again
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(5), 1, 100));
+		assert_ok!(Messaging::top_up_bond(RuntimeOrigin::signed(1), 100));
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(crate::SpamBonds::<Test>::get(1).amount, 100);
+		assert_eq!(Balances::free_balance(1), ED);
+	});
+}
+
+#[test]
+fn ban_releases_the_account_for_reregistration() {
+	new_test_ext().execute_with(|| {
+		ExistentialDeposit::set(ED);
+		assert_ok!(try_register(1));
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+		transfer_all(1);
+		assert_eq!(Balances::free_balance(1), ED);
+
+		// Without its profile, nothing keeps the account from being emptied
+		ban(1);
+		transfer_all(1);
+		assert_eq!(Balances::free_balance(1), 0);
+
+		assert_ok!(Messaging::unban_account(RuntimeOrigin::root(), 1));
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(5), 1, ED + 100));
+		assert_ok!(try_register(1));
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn sponsors_cover_the_existential_deposit_of_empty_accounts() {
+	new_test_ext().execute_with(|| {
+		ExistentialDeposit::set(ED);
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(6), 5, ED));
+
+		// Sponsored: the bond is held from the sponsor, which also funds the account
+		let sponsorship = (5u64, vec![1u8; 32], KeyAlgorithm::X25519).encode();
+		assert_ok!(Messaging::sponsor_registration(
+			RuntimeOrigin::signed(5),
+			8,
+			vec![1u8; 32],
+			KeyAlgorithm::X25519,
+			TestSignature(8, [SPONSORSHIP_CONTEXT, &sponsorship[..]].concat()),
+		));
+		assert_eq!(Balances::free_balance(8), ED);
+		assert_eq!(Balances::free_balance(5), 900);
+		assert_eq!(held(HoldReason::SpamBond, 5), 100);
+
+		// Forced without a sponsor, nothing funds the account
+		assert_noop!(
+			Messaging::force_register_profile(
+				RuntimeOrigin::root(),
+				9,
+				vec![1u8; 32],
+				KeyAlgorithm::X25519,
+				None
+			),
+			Error::<Test>::AccountNotFunded
+		);
+		assert_ok!(Messaging::do_try_state());
+
+		// The profile keeps the existential deposit in place until it is removed
+		assert!(Balances::transfer_allow_death(RuntimeOrigin::signed(8), 6, ED).is_err());
+		ban(8);
+		assert_eq!(held(HoldReason::SpamBond, 5), 0);
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(8), 6, ED));
+		assert!(!System::account_exists(&8));
+	});
+}
+
+fn pool_balance() -> Balance {
+	Balances::free_balance(Messaging::reward_pool_account())
+}
+
+fn reward_events() -> usize {
+	System::events()
+		.iter()
+		.filter(|record| {
+			matches!(
+				record.event,
+				RuntimeEvent::Messaging(Event::ChallengeRewardPaid { .. })
+			)
+		})
+		.count()
+}
+
+fn finalize() {
+	System::set_block_number(12);
+	assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(5), 0));
+}
+
+#[test]
+fn anyone_can_fund_the_pool() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::fund_reward_pool(RuntimeOrigin::signed(6), 50));
+
+		assert_eq!(Balances::free_balance(6), 950);
+		assert_eq!(Messaging::reward_pool_balance(), 50);
+		System::assert_last_event(Event::RewardPoolFunded { who: 6, amount: 50 }.into());
+
+		assert_noop!(
+			Messaging::fund_reward_pool(RuntimeOrigin::signed(6), 0),
+			Error::<Test>::ZeroRewardPoolFunding
+		);
+	});
+}
+
+#[test]
+fn protocol_fees_pay_their_share_into_the_pool() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		RewardPoolFeeShare::set(Perbill::from_percent(20));
+		set_protocol_fee(10, false);
+		let issuance = Balances::total_issuance();
+
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+
+		assert_eq!(Messaging::reward_pool_balance(), 2);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::ProtocolFee), 8);
+		assert_eq!(Balances::total_issuance(), issuance - 8);
+	});
+}
+
+#[test]
+fn fee_share_too_small_to_open_the_pool_is_income() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		ExistentialDeposit::set(5);
+		RewardPoolFeeShare::set(Perbill::from_percent(20));
+		set_protocol_fee(10, false);
+
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+
+		assert_eq!(Messaging::reward_pool_balance(), 0);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::ProtocolFee), 10);
+	});
+}
+
+#[test]
+fn upheld_challenger_gets_base_reward_on_top_of_slash_share() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::fund_reward_pool(RuntimeOrigin::signed(6), 100));
+		challenge_and_resolve(true);
+
+		finalize();
+
+		// Half of the 50 slash plus the 10 base reward
+		assert_eq!(Balances::free_balance(3), 1035);
+		assert_eq!(pool_balance(), 90);
+		System::assert_has_event(
+			Event::ChallengeRewardPaid { message_id: 0, challenger: 3, amount: 10 }.into(),
+		);
+	});
+}
+
+#[test]
+fn short_pool_pays_what_it_holds_above_existential_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::fund_reward_pool(RuntimeOrigin::signed(6), 5));
+		challenge_and_resolve(true);
+
+		finalize();
+
+		assert_eq!(Balances::free_balance(3), 1029);
+		assert_eq!(pool_balance(), 1);
+		System::assert_has_event(
+			Event::ChallengeRewardPaid { message_id: 0, challenger: 3, amount: 4 }.into(),
+		);
+	});
+}
+
+#[test]
+fn dry_pool_does_not_hold_up_the_verdict() {
+	new_test_ext().execute_with(|| {
+		challenge_and_resolve(true);
+		System::reset_events();
+
+		finalize();
+
+		assert_eq!(Balances::free_balance(3), 1025);
+		assert!(!crate::Challenges::<Test>::contains_key(0));
+		assert_eq!(reward_events(), 0);
+		System::assert_last_event(
+			Event::ChallengeFinalized { message_id: 0, upheld: true }.into(),
+		);
+	});
+}
+
+#[test]
+fn dismissed_challenge_pays_no_reward() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::fund_reward_pool(RuntimeOrigin::signed(6), 100));
+		challenge_and_resolve(false);
+		System::reset_events();
+
+		finalize();
+
+		assert_eq!(pool_balance(), 100);
+		assert_eq!(reward_events(), 0);
+	});
+}
+
+#[test]
+fn upheld_appeal_verdict_pays_the_reward() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Messaging::fund_reward_pool(RuntimeOrigin::signed(6), 100));
+		challenge_and_resolve(true);
+		assert_ok!(Messaging::appeal_challenge(RuntimeOrigin::signed(1), 0));
+
+		assert_ok!(Messaging::resolve_appeal(RuntimeOrigin::root(), 0, true));
+
+		assert_eq!(pool_balance(), 90);
+		System::assert_has_event(
+			Event::ChallengeRewardPaid { message_id: 0, challenger: 3, amount: 10 }.into(),
+		);
+	});
+}
+
+fn update(parameters: PalletParametersOf<Test>) -> sp_runtime::DispatchResult {
+	Messaging::update_parameters(RuntimeOrigin::signed(Admin::get()), parameters)
+}
+
+fn uphold_and_finalize() {
+	assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+	System::set_block_number(12);
+	assert_ok!(Messaging::finalize_challenge(RuntimeOrigin::signed(5), 0));
+}
+
+#[test]
+fn update_parameters_validates_slash_parameters() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			update(PalletParameters {
+				challenger_reward_percent: Perbill::zero(),
+				base_challenge_reward: 0,
+				..Messaging::parameters()
+			}),
+			Error::<Test>::ChallengerUnrewarded
+		);
+		assert_noop!(
+			update(PalletParameters {
+				auto_slash_percent: Perbill::zero(),
+				..Messaging::parameters()
+			}),
+			Error::<Test>::ZeroAutoSlashPercent
+		);
+		// `ChallengeSlashPercent` is 50%
+		assert_noop!(
+			update(PalletParameters {
+				auto_slash_percent: Perbill::from_percent(60),
+				..Messaging::parameters()
+			}),
+			Error::<Test>::SlashPercentagesTooHigh
+		);
+
+		// A base reward alone rewards challengers
+		assert_ok!(update(PalletParameters {
+			challenger_reward_percent: Perbill::zero(),
+			auto_slash_percent: Perbill::from_percent(50),
+			..Messaging::parameters()
+		}));
+	});
+}
+
+#[test]
+fn challenges_snapshot_the_parameters_they_are_opened_under() {
+	new_test_ext().execute_with(|| {
+		open_challenge();
+		assert_eq!(
+			crate::Challenges::<Test>::get(0).unwrap().terms,
+			Messaging::parameters().slash_terms()
+		);
+
+		assert_ok!(update(PalletParameters {
+			challenger_reward_percent: Perbill::one(),
+			slash_destination: SlashDestination::RewardPool,
+			..Messaging::parameters()
+		}));
+		uphold_and_finalize();
+
+		// Half of the 50 slash, the rest routed to `SlashIncome`
+		assert_eq!(Balances::free_balance(3), 1025);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::Slash), 25);
+		assert_eq!(Messaging::reward_pool_balance(), 0);
+	});
+}
+
+#[test]
+fn updated_parameters_apply_to_new_challenges() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(update(PalletParameters {
+			challenger_reward_percent: Perbill::one(),
+			..Messaging::parameters()
+		}));
+		open_challenge();
+
+		uphold_and_finalize();
+
+		assert_eq!(Balances::free_balance(3), 1050);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::Slash), 0);
+	});
+}
+
+#[test]
+fn auto_slash_uses_the_percentage_of_the_challenge() {
+	new_test_ext().execute_with(|| {
+		AutoSlashThreshold::set(2);
+		setup_approved_pair();
+		add_strike(1, 2);
+		top_up_to(1, 100);
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			1,
+			evidence_commitment()
+		));
+
+		assert_ok!(update(PalletParameters {
+			auto_slash_percent: Perbill::from_percent(10),
+			..Messaging::parameters()
+		}));
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 1, true));
+
+		// Half of the remaining 50, not a tenth
+		System::assert_has_event(
+			Event::BondAutoSlashed { who: 1, amount: 25, strikes: 2 }.into(),
+		);
+	});
+}
+
+#[test]
+fn burned_slashes_reduce_issuance() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(update(PalletParameters {
+			slash_destination: SlashDestination::Burn,
+			..Messaging::parameters()
+		}));
+		let issuance = Balances::total_issuance();
+		open_challenge();
+
+		uphold_and_finalize();
+
+		assert_eq!(Balances::total_issuance(), issuance - 25);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::Slash), 0);
+	});
+}
+
+#[test]
+fn slashes_can_fund_the_reward_pool() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(update(PalletParameters {
+			slash_destination: SlashDestination::RewardPool,
+			..Messaging::parameters()
+		}));
+		open_challenge();
+
+		uphold_and_finalize();
+
+		assert_eq!(Messaging::reward_pool_balance(), 25);
+		assert_eq!(crate::PalletIncome::<Test>::get(IncomeCategory::Slash), 0);
+
+		// Bans slash to the live destination
+		ban(2);
+		assert_eq!(Messaging::reward_pool_balance(), 125);
+	});
+}
+
+fn send_next_block(from: u64, to: u64, hash: u8) {
+	System::set_block_number(System::block_number() + 1);
+	assert_ok!(Messaging::send_message_hash(
+		RuntimeOrigin::signed(from),
+		to,
+		H256::from([hash; 32])
+	));
+}
+
+fn seqs(a: u64, b: u64, from_seq: u64) -> Vec<(u64, crate::MessageId)> {
+	Messaging::conversation_page(a, b, from_seq, 10)
+		.messages
+		.into_iter()
+		.map(|message| (message.seq, message.message_id))
+		.collect()
+}
+
+#[test]
+fn conversation_page_reports_status() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		approve(1, 2);
+		send_next_block(2, 1, 2);
+		assert_ok!(Messaging::challenge_spam(
+			RuntimeOrigin::signed(3),
+			1,
+			evidence_commitment()
+		));
+
+		let statuses = |a, b| -> Vec<MessageStatus> {
+			Messaging::conversation_page(a, b, 0, 10)
+				.messages
+				.into_iter()
+				.map(|message| message.status)
+				.collect()
+		};
+		assert_eq!(
+			statuses(2, 1),
+			vec![MessageStatus::AwaitingAcknowledgement, MessageStatus::Challenged]
+		);
+
+		// Expired messages are listed until they are pruned
+		System::set_block_number(1003);
+		assert_eq!(statuses(1, 2), vec![MessageStatus::Expired; 2]);
+	});
+}
+
+#[test]
+fn removed_messages_leave_gaps() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		make_mutual_contacts(1, 2);
+		send_next_block(1, 2, 2);
+		send_next_block(2, 1, 3);
+
+		let order = order_reference();
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 1, order));
+		assert_eq!(seqs(1, 2, 0), vec![(0, 0), (2, 2)]);
+		assert!(!crate::MessageSequences::<Test>::contains_key(1));
+
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+		let page = Messaging::conversation_page(1, 2, 0, 10);
+		assert_eq!((page.lowest_retained_seq, page.next_seq), (2, 3));
+		assert_eq!(seqs(1, 2, 0), vec![(2, 2)]);
+
+		// Removals only leave gaps, the floor moves on pruning
+		let conversation_id = Messaging::conversation_id(&1, &2);
+		assert_eq!(crate::ConversationSeqFloors::<Test>::get(conversation_id), 0);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn pruning_raises_the_floor() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		send_next_block(1, 2, 1);
+		send_next_block(2, 1, 2);
+		let sent_at = System::block_number();
+
+		// Only the first message has expired by then
+		System::set_block_number(sent_at + 1000);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), sent_at + 1000));
+
+		let conversation_id = Messaging::conversation_id(&1, &2);
+		assert_eq!(crate::ConversationSeqFloors::<Test>::get(conversation_id), 1);
+		assert!(!crate::ConversationMessages::<Test>::contains_key(conversation_id, 0));
+		let page = Messaging::conversation_page(2, 1, 0, 10);
+		assert_eq!(page.lowest_retained_seq, 1);
+		assert_eq!(seqs(2, 1, 0), vec![(1, 1)]);
+
+		System::set_block_number(sent_at + 1001);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), sent_at + 1001));
+		let page = Messaging::conversation_page(2, 1, 0, 10);
+		assert_eq!((page.lowest_retained_seq, page.next_seq), (2, 2));
+		assert!(page.messages.is_empty());
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn page_size_is_capped() {
+	new_test_ext().execute_with(|| {
+		let conversation_id = Messaging::conversation_id(&1, &2);
+		let hash = H256::from([1; 32]);
+		for seq in 0..130 {
+			let record = MessageRecord { sender: 1, recipient: 2, hash, sent_at: 1 };
+			crate::MessageHashes::<Test>::insert(seq, record);
+			crate::ConversationMessages::<Test>::insert(conversation_id, seq, seq);
+			crate::MessageSequences::<Test>::insert(seq, seq);
+		}
+		crate::ConversationStats::<Test>::mutate(conversation_id, |stats| stats.next_seq = 130);
+
+		let page = Messaging::conversation_page(1, 2, 0, u32::MAX);
+		assert_eq!(page.messages.len(), MAX_CONVERSATION_PAGE_SIZE as usize);
+		let page = Messaging::conversation_page(1, 2, 128, u32::MAX);
+		assert_eq!(page.messages.len(), 2);
+	});
+}
+
+fn register_all(accounts: &[u64]) {
+	for who in accounts {
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(*who), vec![1, 2, 3, 4]));
+	}
+}
+
+fn approve(who: u64, contact: u64) {
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(who), contact));
+}
+
+fn no_requests(who: u64) -> bool {
+	!crate::IncomingContactRequests::<Test>::contains_key(who) &&
+		!crate::OutgoingContactRequests::<Test>::contains_key(who)
+}
+
+#[test]
+fn requests_are_listed_in_both_directions() {
+	new_test_ext().execute_with(|| {
+		register_all(&[1, 2, 3]);
+		approve(1, 2);
+		System::set_block_number(2);
+		approve(3, 2);
+
+		assert_eq!(Messaging::incoming_requests(2, 10), vec![(1, 1), (3, 2)]);
+		assert_eq!(Messaging::incoming_requests(2, 1), vec![(1, 1)]);
+		assert_eq!(Messaging::outgoing_requests(1, 10), vec![(2, 1)]);
+		assert!(Messaging::outgoing_requests(2, 10).is_empty());
+
+		assert_eq!(
+			view(IncomingRequestsViewFunction::<Test>::new(2, 10)),
+			vec![(1, 1), (3, 2)]
+		);
+		assert_eq!(view(OutgoingRequestsViewFunction::<Test>::new(3, 10)), vec![(2, 2)]);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn accepting_drops_the_request() {
+	new_test_ext().execute_with(|| {
+		register_all(&[1, 2, 3]);
+		approve(1, 2);
+		approve(2, 1);
+		assert!(no_requests(1) && no_requests(2));
+
+		approve(3, 2);
+		assert_ok!(Messaging::accept_and_send(
+			RuntimeOrigin::signed(2),
+			3,
+			H256::from([1; 32])
+		));
+		assert!(no_requests(2) && no_requests(3));
+
+		// Approving again after removing a mutual contact sends no request
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 2));
+		approve(1, 2);
+		assert!(no_requests(1) && no_requests(2));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn remove_contact_withdraws_and_rejects_requests() {
+	new_test_ext().execute_with(|| {
+		register_all(&[1, 2, 3]);
+		approve(1, 2);
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 2));
+		assert!(no_requests(1) && no_requests(2));
+
+		// Rejecting leaves the requester's approval in place
+		approve(3, 2);
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(2), 3));
+		assert!(no_requests(2) && no_requests(3));
+		assert!(crate::ApprovedContacts::<Test>::get(3, 2));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn full_indexes_drop_the_oldest_request() {
+	new_test_ext().execute_with(|| {
+		register_all(&[1, 2, 3, 5, 6]);
+		// `MaxPendingContactRequests` is 3
+		for requester in [1, 3, 5, 6] {
+			approve(requester, 2);
+		}
+
+		let requesters: Vec<_> =
+			Messaging::incoming_requests(2, 10).into_iter().map(|(who, _)| who).collect();
+		assert_eq!(requesters, vec![3, 5, 6]);
+		assert!(no_requests(1));
+		assert!(crate::ApprovedContacts::<Test>::get(1, 2));
+
+		// The dropped request can still be accepted
+		for requester in [1, 3, 5, 6] {
+			approve(2, requester);
+		}
+		assert!(no_requests(2));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+type Case = (fn(), u64, u64, Result<(), CanMessageError>);
+
+fn pause_sends() {
+	assert_ok!(Messaging::pause(RuntimeOrigin::signed(Admin::get()), PausedCalls::SENDS));
+}
+
+fn graylist_sender() {
+	crate::Reputation::<Test>::insert(1, ReputationRecord { strikes: 3, last_decay: 1 });
+}
+
+#[test]
+fn has_profile_works() {
+	new_test_ext().execute_with(|| {
+		assert!(!Messaging::has_profile(1));
+		setup_pair();
+		assert!(Messaging::has_profile(1));
+		assert!(view(HasProfileViewFunction::<Test>::new(2)));
+	});
+}
+
+#[test]
+fn can_message_agrees_with_send_message_hash() {
+	let cases: [Case; 16] = [
+		(|| {}, 1, 2, Ok(())),
+		(pause_sends, 1, 2, Err(CanMessageError::Paused)),
+		// Checks run in send order, so pausing masks the missing profiles
+		(pause_sends, 3, 4, Err(CanMessageError::Paused)),
+		(|| ban(1), 1, 1, Err(CanMessageError::MessageToSelf)),
+		(|| {}, 1, Messaging::reward_pool_account(), Err(CanMessageError::ReservedRecipient)),
+		(|| ban(1), 1, 2, Err(CanMessageError::SenderBanned)),
+		(|| ban(2), 1, 2, Err(CanMessageError::RecipientBanned)),
+		(
+			|| {
+				add_moderator();
+				assert_ok!(Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10));
+			},
+			1,
+			2,
+			Err(CanMessageError::SenderFrozen),
+		),
+		(|| {}, 3, 2, Err(CanMessageError::NoSenderProfile)),
+		(|| {}, 1, 3, Err(CanMessageError::NoRecipientProfile)),
+		(
+			|| {
+				crate::UserProfiles::<Test>::mutate(2, |profile| {
+					profile.as_mut().unwrap().dormant_since = Some(1)
+				})
+			},
+			1,
+			2,
+			Err(CanMessageError::RecipientDormant),
+		),
+		// 2 approved 1, but not the other way round
+		(|| {}, 2, 1, Err(CanMessageError::NotApproved)),
+		(graylist_sender, 1, 2, Err(CanMessageError::SenderGraylisted)),
+		(
+			|| crate::SpamBonds::<Test>::mutate(1, |bond| bond.amount = 50),
+			1,
+			2,
+			Err(CanMessageError::InsufficientBond),
+		),
+		(
+			|| {
+				for byte in 2..12 {
+					assert_ok!(Messaging::send_message_hash(
+						RuntimeOrigin::signed(1),
+						2,
+						H256::repeat_byte(byte)
+					));
+				}
+			},
+			1,
+			2,
+			Err(CanMessageError::RateLimited),
+		),
+		(
+			|| {
+				assert_ok!(Messaging::update_parameters(
+					RuntimeOrigin::signed(Admin::get()),
+					PalletParameters { message_protocol_fee: 2000, ..Messaging::parameters() }
+				));
+			},
+			1,
+			2,
+			Err(CanMessageError::InsufficientProtocolFee),
+		),
+	];
+
+	for (setup, sender, recipient, expected) in cases {
+		new_test_ext().execute_with(|| {
+			setup_approved_pair();
+			setup();
+
+			assert_eq!(Messaging::can_message(sender, recipient), expected);
+			let send = || {
+				Messaging::send_message_hash(
+					RuntimeOrigin::signed(sender),
+					recipient,
+					H256::from([1; 32]),
+				)
+			};
+			match expected {
+				Ok(()) => assert_ok!(send()),
+				Err(error) => assert_noop!(send(), Error::<Test>::from(error)),
+			}
+		});
+	}
+}
+
+/// Conditions that each fail a send from 1 to 2 on their own, in the order the send path
+/// checks them, with the error `can_message` reports for them.
+const SEND_CONDITIONS: [CanMessageError; 11] = [
+	CanMessageError::Paused,
+	CanMessageError::SenderBanned,
+	CanMessageError::RecipientBanned,
+	CanMessageError::SenderFrozen,
+	CanMessageError::RecipientDormant,
+	CanMessageError::NotApproved,
+	CanMessageError::SessionRequired,
+	CanMessageError::SenderGraylisted,
+	CanMessageError::InsufficientBond,
+	CanMessageError::RateLimited,
+	CanMessageError::InsufficientProtocolFee,
+];
+
+/// Bring about the conditions of `SEND_CONDITIONS` selected by `mask` for a send from 1 to 2.
+///
+/// Conditions are applied in reverse check order, so that none of them stops a later one
+/// from being set up.
+fn apply_send_conditions(mask: u16) {
+	let set = |condition: CanMessageError| {
+		let index = SEND_CONDITIONS.iter().position(|c| *c == condition).unwrap();
+		mask & (1 << index) != 0
+	};
+	if set(CanMessageError::RateLimited) {
+		for byte in 2..12 {
+			assert_ok!(Messaging::send_message_hash(
+				RuntimeOrigin::signed(1),
+				2,
+				H256::repeat_byte(byte)
+			));
+		}
+	}
+	if set(CanMessageError::InsufficientProtocolFee) {
+		set_protocol_fee(2000, false);
+	}
+	if set(CanMessageError::InsufficientBond) {
+		crate::SpamBonds::<Test>::mutate(1, |bond| bond.amount = 50);
+	}
+	if set(CanMessageError::SenderGraylisted) {
+		graylist_sender();
+	}
+	if set(CanMessageError::SessionRequired) {
+		crate::UserProfiles::<Test>::mutate(2, |profile| {
+			profile.as_mut().unwrap().session_required = true
+		});
+	}
+	if set(CanMessageError::NotApproved) {
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(2), 1));
+	}
+	if set(CanMessageError::RecipientDormant) {
+		crate::UserProfiles::<Test>::mutate(2, |profile| {
+			profile.as_mut().unwrap().dormant_since = Some(1)
+		});
+	}
+	if set(CanMessageError::SenderFrozen) {
+		add_moderator();
+		assert_ok!(Messaging::freeze_sender(RuntimeOrigin::signed(MODERATOR), 1, 10));
+	}
+	if set(CanMessageError::RecipientBanned) {
+		ban(2);
+	}
+	if set(CanMessageError::SenderBanned) {
+		ban(1);
+	}
+	if set(CanMessageError::Paused) {
+		pause_sends();
+	}
+}
+
+#[test]
+fn can_message_agrees_with_dispatch_for_every_combination_of_conditions() {
+	for mask in 0..1u16 << SEND_CONDITIONS.len() {
+		new_test_ext().execute_with(|| {
+			setup_approved_pair();
+			apply_send_conditions(mask);
+
+			// The first condition in check order decides
+			let expected = match mask.trailing_zeros() as usize {
+				index if index < SEND_CONDITIONS.len() => Err(SEND_CONDITIONS[index]),
+				_ => Ok(()),
+			};
+			assert_eq!(Messaging::can_message(1, 2), expected, "conditions {mask:#b}");
+			let send = || {
+				Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32]))
+			};
+			match expected {
+				Ok(()) => assert_ok!(send()),
+				Err(error) => assert_noop!(send(), Error::<Test>::from(error)),
+			}
+		});
+	}
+}
+
+#[test]
+fn mutual_contacts_are_exempt_from_the_fee() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::update_parameters(
+			RuntimeOrigin::signed(Admin::get()),
+			PalletParameters {
+				message_protocol_fee: 2000,
+				exempt_mutual_contacts: true,
+				..Messaging::parameters()
+			}
+		));
+		assert_eq!(Messaging::can_message(1, 2), Err(CanMessageError::InsufficientProtocolFee));
+
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert_eq!(Messaging::can_message(1, 2), Ok(()));
+		let hash = H256::from([1; 32]);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+	});
+}
+
+#[test]
+fn sends_and_acknowledgements_are_counted() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		make_mutual_contacts(1, 2);
+		System::set_block_number(3);
+		assert_ok!(try_send(2, 1));
+		assert_ok!(try_send(1, 2));
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+
+		assert_eq!(
+			Messaging::account_stats(1),
+			AccountStatistics {
+				sent: 2,
+				received: 1,
+				acknowledged: 0,
+				conversations: 1,
+				last_active: 3
+			}
+		);
+		assert_eq!(
+			Messaging::account_stats(2),
+			AccountStatistics {
+				sent: 1,
+				received: 2,
+				acknowledged: 1,
+				conversations: 1,
+				last_active: 3
+			}
+		);
+		assert_eq!(Messaging::global_stats().messages, 3);
+	});
+}
+
+#[test]
+fn conversations_count_once_per_pair() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		make_mutual_contacts(1, 2);
+		approve(1, 3);
+		assert_ok!(try_send(1, 2));
+		assert_ok!(try_send(2, 1));
+		assert_ok!(try_send(3, 1));
+
+		assert_eq!(Messaging::account_stats(1).conversations, 2);
+		assert_eq!(Messaging::account_stats(2).conversations, 1);
+		assert_eq!(Messaging::account_stats(3).conversations, 1);
+		assert_eq!(Messaging::account_stats(5), AccountStatistics::default());
+	});
+}
+
+#[test]
+fn global_stats_count_profiles_and_groups() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_ok!(Messaging::create_group(RuntimeOrigin::signed(1), group_key(), 4));
+		assert_ok!(try_send(1, 2));
+
+		// Removing a profile does not take it back off the count
+		ban(2);
+		assert_eq!(
+			Messaging::global_stats(),
+			GlobalStatistics {
+				messages: 1,
+				profiles: 2,
+				groups: 1,
+				stored_messages: 1,
+				stored_profiles: 1
+			}
+		);
+	});
+}
+
+fn flag_dormant(who: u64) {
+	System::set_block_number(201);
+	assert_ok!(Messaging::flag_inactive(RuntimeOrigin::signed(3), who));
+}
+
+#[test]
+fn flag_inactive_requires_inactivity() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_noop!(
+			Messaging::flag_inactive(RuntimeOrigin::signed(3), 5),
+			Error::<Test>::ProfileNotFound
+		);
+
+		// Registration counts as activity
+		System::set_block_number(200);
+		assert_noop!(
+			Messaging::flag_inactive(RuntimeOrigin::signed(3), 2),
+			Error::<Test>::AccountNotInactive
+		);
+
+		flag_dormant(2);
+		System::assert_last_event(
+			Event::ProfileFlaggedDormant { who: 2, flagged_by: 3 }.into(),
+		);
+		assert_eq!(
+			Messaging::profile_activity(2),
+			Some(ProfileActivity { last_activity: 1, dormant_since: Some(201) })
+		);
+		assert_noop!(
+			Messaging::flag_inactive(RuntimeOrigin::signed(3), 2),
+			Error::<Test>::AlreadyDormant
+		);
+	});
+}
+
+#[test]
+fn dormant_profiles_receive_nothing() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		flag_dormant(2);
+
+		assert_eq!(Messaging::can_message(1, 2), Err(CanMessageError::RecipientDormant));
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::RecipientDormant
+		);
+	});
+}
+
+#[test]
+fn activity_reactivates_dormant_profiles() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		flag_dormant(2);
+
+		System::set_block_number(300);
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		System::assert_has_event(Event::ProfileReactivated { who: 2 }.into());
+		assert_eq!(
+			Messaging::profile_activity(2),
+			Some(ProfileActivity { last_activity: 300, dormant_since: None })
+		);
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([1; 32])
+		));
+
+		// Sending reactivates the sender
+		System::set_block_number(500);
+		assert_ok!(Messaging::flag_inactive(RuntimeOrigin::signed(3), 1));
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([2; 32])
+		));
+		assert_eq!(crate::UserProfiles::<Test>::get(1).unwrap().dormant_since, None);
+	});
+}
+
+#[test]
+fn sweep_removes_long_dormant_profiles() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		flag_dormant(2);
+
+		assert_noop!(
+			Messaging::sweep_dormant_profile(RuntimeOrigin::root(), 1),
+			Error::<Test>::ProfileNotDormant
+		);
+		System::set_block_number(1000);
+		assert_noop!(
+			Messaging::sweep_dormant_profile(RuntimeOrigin::root(), 2),
+			Error::<Test>::SweepTooEarly
+		);
+
+		System::set_block_number(1001);
+		assert_noop!(
+			Messaging::sweep_dormant_profile(RuntimeOrigin::signed(Admin::get()), 2),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(Messaging::sweep_dormant_profile(RuntimeOrigin::root(), 2));
+		System::assert_last_event(Event::DormantProfileSwept { who: 2, refunded: 100 }.into());
+
+		// The bond is back with its owner and the profile gone
+		assert!(!Messaging::has_profile(2));
+		assert_eq!(Messaging::profile_activity(2), None);
+		assert_eq!(held(HoldReason::SpamBond, 2), 0);
+		assert_eq!(Balances::free_balance(2), 1000);
+		assert_eq!(Messaging::profile_activity(2), None);
+	});
+}
+
+fn send_messages(count: u8) {
+	for _ in 0..count {
+		let hash = H256::repeat_byte(crate::NextMessageId::<Test>::get() as u8);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+	}
+}
+
+fn prune(at: u64) {
+	System::set_block_number(at);
+	assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), at));
+}
+
+fn expiry_events() -> Vec<Event<Test>> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::Messaging(
+				event @ (Event::MessageExpired { .. } | Event::MessagesExpiredBatch { .. }),
+			) => Some(event),
+			_ => None,
+		})
+		.collect()
+}
+
+fn expired(message_id: crate::MessageId) -> Event<Test> {
+	Event::MessageExpired {
+		message_id,
+		conversation_id: Some(Messaging::conversation_id(&1, &2)),
+	}
+}
+
+#[test]
+fn expiries_up_to_the_cap_are_reported_individually() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		send_messages(2);
+
+		prune(1002);
+		assert_eq!(expiry_events(), vec![expired(0), expired(1)]);
+		assert_eq!(crate::ExpiryEventCount::<Test>::get(), (1002, 2));
+	});
+}
+
+#[test]
+fn expiries_beyond_the_cap_are_batched() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		send_messages(5);
+
+		// The first call reaches the cap and batches the rest
+		prune(1002);
+		assert_eq!(
+			expiry_events(),
+			vec![
+				expired(0),
+				expired(1),
+				Event::MessagesExpiredBatch { count: 1, first_id: 2, last_id: 2 },
+			]
+		);
+
+		// Later calls in the same block only batch
+		System::reset_events();
+		prune(1002);
+		assert_eq!(
+			expiry_events(),
+			vec![Event::MessagesExpiredBatch { count: 2, first_id: 3, last_id: 4 }]
+		);
+	});
+}
+
+#[test]
+fn cap_resets_every_block() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		send_messages(3);
+		System::set_block_number(2);
+		send_messages(1);
+
+		prune(1002);
+		System::reset_events();
+		prune(1003);
+		assert_eq!(expiry_events(), vec![expired(3)]);
+	});
+}
+
+fn lookup(hash: H256) -> Option<(crate::MessageId, MessageRecordView<u64, H256, u64>)> {
+	view(MessageByHashViewFunction::<Test>::new(hash))
+}
+
+#[test]
+fn sent_messages_are_found_by_hash() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let hash = H256::from([1; 32]);
+		assert_eq!(lookup(hash), None);
+
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		assert_eq!(Messaging::message_id_by_hash(hash), Some(0));
+		let record =
+			MessageRecordView { hash, sender: 1, recipient: 2, sent_at: 1, expires_at: 1001 };
+		assert_eq!(lookup(hash), Some((0, record)));
+		assert_eq!(lookup(H256::from([2; 32])), None);
+	});
+}
+
+#[test]
+fn live_hashes_cannot_be_sent_again() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		let hash = H256::from([1; 32]);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+
+		// Not even by another sender
+		for (from, to) in [(1, 2), (2, 1)] {
+			assert_noop!(
+				Messaging::send_message_hash(RuntimeOrigin::signed(from), to, hash),
+				Error::<Test>::DuplicateMessageHash
+			);
+		}
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+		assert_noop!(
+			Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, hash),
+			Error::<Test>::DuplicateMessageHash
+		);
+		assert_eq!(Messaging::message_id_by_hash(hash), Some(0));
+	});
+}
+
+#[test]
+fn expired_messages_are_superseded_by_a_new_send() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		let hash = H256::from([1; 32]);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+
+		// Message 0 has expired but is not pruned yet
+		System::set_block_number(1002);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, hash));
+		let found = lookup(hash).map(|(message_id, record)| (message_id, record.sender));
+		assert_eq!(found, Some((1, 2)));
+
+		// Pruning message 0 leaves the entry of its successor alone
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert!(Messaging::message(0).is_none());
+		assert_eq!(Messaging::message_id_by_hash(hash), Some(1));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn pruned_messages_are_no_longer_found() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let hash = H256::from([1; 32]);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+
+		System::set_block_number(1002);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert_eq!(lookup(hash), None);
+		assert!(!crate::MessageIdByHash::<Test>::contains_key(hash));
+	});
+}
+
+#[test]
+fn retracted_messages_free_their_hash() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		let hash = H256::from([1; 32]);
+		assert_eq!(Messaging::message_id_by_hash(hash), Some(0));
+
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+		assert_eq!(lookup(hash), None);
+
+		// The hash can be sent again right away
+		assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		assert_eq!(Messaging::message_id_by_hash(hash), Some(1));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn removed_messages_are_no_longer_found() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let hash = H256::from([1; 32]);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+
+		let order = order_reference();
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 0, order));
+		assert_eq!(lookup(hash), None);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn claimed_messages_keep_a_live_entry() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		for byte in [1, 2] {
+			assert_ok!(Messaging::send_to_unregistered(
+				RuntimeOrigin::signed(1),
+				Messaging::account_hash(&6),
+				H256::repeat_byte(byte),
+			));
+		}
+		let hash = H256::repeat_byte(1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+
+		// The deposits are already paid, so claiming cannot fail on a hash in use
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(6), vec![1, 2, 3, 4]));
+		assert_ok!(Messaging::claim_pending_messages(RuntimeOrigin::signed(6)));
+		assert_eq!(Messaging::message_id_by_hash(hash), Some(0));
+		let claimed = lookup(H256::repeat_byte(2)).map(|(_, record)| record.recipient);
+		assert_eq!(claimed, Some(6));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+/// Send a message from 1 to 2 with a hash of repeated `byte`.
+fn send_byte(byte: u8) {
+	let hash = H256::repeat_byte(byte);
+	assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+}
+
+fn revision(who: u64) -> u64 {
+	view(InboxRevisionViewFunction::<Test>::new(who))
+}
+
+#[test]
+fn sends_bump_the_recipient_only() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		assert_eq!(revision(2), 0);
+
+		send_byte(1);
+		assert_eq!(revision(2), 1);
+		assert_eq!(revision(1), 0);
+
+		// Failed sends leave the revision alone
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, H256::from([1; 32])),
+			Error::<Test>::DuplicateMessageHash
+		);
+		assert_eq!(revision(2), 1);
+	});
+}
+
+#[test]
+fn unchanged_inboxes_list_nothing_since_their_revision() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		send_byte(1);
+		let inbox = |since| view(InboxOfViewFunction::<Test>::new(2, since, None, 10));
+		assert_eq!(inbox(None).len(), 1);
+		assert_eq!(inbox(Some(0)).len(), 1);
+		assert!(inbox(Some(1)).is_empty());
+
+		send_byte(2);
+		assert_eq!(inbox(Some(1)).len(), 2);
+	});
+}
+
+#[test]
+fn acknowledging_and_refunding_bump_the_recipient() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		let hash = H256::repeat_byte(2);
+		assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		assert_eq!(revision(2), 2);
+
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+		assert_eq!(revision(2), 3);
+
+		System::set_block_number(1002);
+		assert_ok!(Messaging::refund_inbox_payment(RuntimeOrigin::signed(3), 1));
+		assert_eq!(revision(2), 4);
+		assert_eq!(revision(1), 0);
+	});
+}
+
+#[test]
+fn retracting_and_removing_bump_the_recipient() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+		assert_eq!(revision(2), 2);
+
+		approve(2, 1);
+		send_byte(2);
+		let order = order_reference();
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 1, order));
+		assert_eq!(revision(2), 4);
+	});
+}
+
+#[test]
+fn expiry_bumps_on_pruning_only() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		send_byte(1);
+
+		System::set_block_number(1002);
+		assert_eq!(Messaging::message_state(0), MessageState::Expired);
+		assert_eq!(revision(2), 1);
+
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert_eq!(revision(2), 2);
+	});
+}
+
+#[test]
+fn claims_bump_the_claimant() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::send_to_unregistered(
+			RuntimeOrigin::signed(1),
+			Messaging::account_hash(&6),
+			H256::from([1; 32]),
+		));
+		assert_eq!(revision(6), 0);
+
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(6), vec![1, 2, 3, 4]));
+		assert_ok!(Messaging::claim_pending_messages(RuntimeOrigin::signed(6)));
+		assert_eq!(revision(6), 1);
+	});
+}
+
+#[test]
+fn sealed_messages_bump_the_recipient() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::register_relay(RuntimeOrigin::signed(5)));
+		assert_ok!(Messaging::send_sealed_message_hash(
+			RuntimeOrigin::signed(5),
+			2,
+			H256::from([1; 32]),
+		));
+		assert_eq!(revision(2), 1);
+	});
+}
+
+fn summary(a: u64, b: u64) -> Option<ConversationSummary<u64>> {
+	view(ConversationSummaryViewFunction::<Test>::new(a, b))
+}
+
+/// Accounts 1 and 2 registered, each asking 30 of the other per message.
+fn setup_priced_pair() {
+	setup_pair();
+	for who in [1, 2] {
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(who), Some(30)));
+	}
+}
+
+/// Unacknowledged messages sent and received by 1 in its conversation with 2.
+fn unacknowledged() -> (u32, u32) {
+	let summary = summary(1, 2).unwrap();
+	(summary.unacknowledged_sent, summary.unacknowledged_received)
+}
+
+#[test]
+fn sends_count_messages_and_activity() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		assert_eq!(summary(1, 2), None);
+
+		let hash = H256::repeat_byte(1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		System::set_block_number(3);
+		let hash = H256::repeat_byte(2);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, hash));
+		let expected = ConversationSummary {
+			messages: 2,
+			unacknowledged_sent: 0,
+			unacknowledged_received: 0,
+			last_message_id: 1,
+			last_activity: 3,
+		};
+		assert_eq!(summary(1, 2), Some(expected.clone()));
+		assert_eq!(summary(2, 1), Some(expected));
+		assert_eq!(summary(1, 3), None);
+	});
+}
+
+#[test]
+fn paid_messages_are_counted_per_direction_until_acknowledged() {
+	new_test_ext().execute_with(|| {
+		setup_priced_pair();
+		assert_ok!(send_paid_next(1, 2));
+		assert_ok!(send_paid_next(2, 1));
+		assert_ok!(send_paid_next(1, 2));
+		assert_eq!(unacknowledged(), (2, 1));
+		let seen_by_2 = summary(2, 1).unwrap();
+		assert_eq!((seen_by_2.unacknowledged_sent, seen_by_2.unacknowledged_received), (1, 2));
+
+		System::set_block_number(5);
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+		assert_eq!(unacknowledged(), (1, 1));
+		assert_eq!(summary(1, 2).unwrap().last_activity, 5);
+		assert_eq!(summary(1, 2).unwrap().messages, 3);
+	});
+}
+
+#[test]
+fn removals_decrement_the_counts() {
+	new_test_ext().execute_with(|| {
+		setup_priced_pair();
+		for _ in 0..4 {
+			assert_ok!(send_paid_next(1, 2));
+		}
+
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+		assert_eq!((summary(1, 2).unwrap().messages, unacknowledged()), (3, (3, 0)));
+
+		let order = order_reference();
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 1, order));
+		assert_eq!((summary(1, 2).unwrap().messages, unacknowledged()), (2, (2, 0)));
+
+		// Pruning removes the messages, their refunds settle the payments
+		System::set_block_number(1002);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert_eq!((summary(1, 2).unwrap().messages, unacknowledged()), (0, (2, 0)));
+		for message_id in [2, 3] {
+			assert_ok!(Messaging::refund_inbox_payment(RuntimeOrigin::signed(3), message_id));
+		}
+		let summary = summary(1, 2).unwrap();
+		assert_eq!((summary.messages, unacknowledged()), (0, (0, 0)));
+		assert_eq!((summary.last_message_id, summary.last_activity), (3, 1));
+	});
+}
+
+#[test]
+fn summaries_match_the_events_of_random_histories() {
+	for seed in 0..20u64 {
+		new_test_ext().execute_with(|| {
+			setup_priced_pair();
+
+			// Ground truth, replayed from the events
+			let mut live = BTreeSet::new();
+			let mut awaiting = BTreeMap::new();
+			let mut last = None;
+			let mut seen = 0;
+
+			let mut state =
+				seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+			let mut next = |n: u64| {
+				state =
+					state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+				(state >> 33) % n
+			};
+			for step in 0..150u64 {
+				let (who, other) = if next(2) == 0 { (1, 2) } else { (2, 1) };
+				let id = next(crate::NextMessageId::<Test>::get().max(1));
+				let hash = H256::from_low_u64_be(step + 1);
+				let now = System::block_number();
+				let origin = RuntimeOrigin::signed(who);
+				let _ = match next(8) {
+					0 | 1 => Messaging::send_message_hash(origin, other, hash),
+					2 | 3 => Messaging::send_paid_message_hash(origin, other, hash),
+					4 => Messaging::acknowledge_message(origin, id, false),
+					5 => Messaging::retract_paid_message(origin, id),
+					6 => Messaging::refund_inbox_payment(RuntimeOrigin::signed(3), id),
+					_ => {
+						System::set_block_number(now + next(400) + 1);
+						let _ = Messaging::prune_expired_unsigned(
+							RuntimeOrigin::none(),
+							System::block_number(),
+						);
+						Ok(())
+					},
+				};
+
+				let events = System::events();
+				for record in &events[seen..] {
+					let RuntimeEvent::Messaging(event) = &record.event else { continue };
+					match *event {
+						Event::MessageSent { message_id, .. } => {
+							live.insert(message_id);
+							last = Some((message_id, System::block_number()));
+						},
+						Event::InboxPaymentHeld { message_id, sender, .. } => {
+							awaiting.insert(message_id, sender);
+						},
+						Event::MessageAcknowledged { message_id, .. } => {
+							awaiting.remove(&message_id);
+							last = last.map(|(id, _)| (id, System::block_number()));
+						},
+						Event::PaidMessageRetracted { message_id, .. } => {
+							awaiting.remove(&message_id);
+							live.remove(&message_id);
+						},
+						Event::InboxPaymentRefunded { message_id, .. } => {
+							awaiting.remove(&message_id);
+						},
+						Event::MessageExpired { message_id, .. } => {
+							live.remove(&message_id);
+						},
+						Event::MessagesExpiredBatch { first_id, last_id, .. } => {
+							live.retain(|id| !(first_id..=last_id).contains(id));
+						},
+						_ => {},
+					}
+				}
+				seen = events.len();
+
+				let expected = last.map(|(last_message_id, last_activity)| {
+					let from = |sender| awaiting.values().filter(|s| **s == sender).count();
+					ConversationSummary {
+						messages: live.len() as u32,
+						unacknowledged_sent: from(1) as u32,
+						unacknowledged_received: from(2) as u32,
+						last_message_id,
+						last_activity,
+					}
+				});
+				assert_eq!(summary(1, 2), expected, "seed {seed}, step {step}");
+			}
+		});
+	}
+}
+
+fn hint() -> NotificationHint {
+	NotificationHint::truncate_from(b"relay.example/7f3a".to_vec())
+}
+
+fn set_hint(who: u64) {
+	assert_ok!(Messaging::set_notification_hint(RuntimeOrigin::signed(who), Some(hint())));
+}
+
+fn notifications() -> Vec<(H256, u64, crate::MessageId)> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::Messaging(Event::NotifyRelay { hint_hash, recipient, message_id }) =>
+				Some((hint_hash, recipient, message_id)),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn only_opted_in_recipients_are_notified() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		make_mutual_contacts(1, 2);
+		set_hint(2);
+		let hint_hash = BlakeTwo256::hash(&hint());
+		System::assert_last_event(
+			Event::NotificationHintSet { who: 2, hint_hash: Some(hint_hash) }.into(),
+		);
+
+		let hash = H256::repeat_byte(1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		let hash = H256::repeat_byte(2);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(2), 1, hash));
+		assert_eq!(notifications(), vec![(hint_hash, 2, 0)]);
+	});
+}
+
+#[test]
+fn cleared_hints_stop_notifications() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		set_hint(2);
+		assert_ok!(Messaging::set_notification_hint(RuntimeOrigin::signed(2), None));
+		System::assert_last_event(
+			Event::NotificationHintSet { who: 2, hint_hash: None }.into(),
+		);
+		assert!(!NotificationHooks::<Test>::contains_key(2));
+
+		let hash = H256::repeat_byte(1);
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash));
+		assert!(notifications().is_empty());
+	});
+}
+
+#[test]
+fn hints_need_a_profile() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::set_notification_hint(RuntimeOrigin::signed(3), Some(hint())),
+			Error::<Test>::ProfileNotFound
+		);
+	});
+}
+
+#[test]
+fn hints_are_removed_with_the_profile() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		set_hint(1);
+		set_hint(2);
+
+		ban(1);
+		assert!(!NotificationHooks::<Test>::contains_key(1));
+
+		System::set_block_number(201);
+		assert_ok!(Messaging::flag_inactive(RuntimeOrigin::signed(3), 2));
+		System::set_block_number(1001);
+		assert_ok!(Messaging::sweep_dormant_profile(RuntimeOrigin::root(), 2));
+		assert!(!NotificationHooks::<Test>::contains_key(2));
+	});
+}
+
+#[test]
+fn approving_twice_fails_without_an_event() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		System::reset_events();
+
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::AlreadyApproved
+		);
+		assert!(System::events().is_empty());
+		assert_eq!(crate::ContactCount::<Test>::get(1), 1);
+	});
+}
+
+#[test]
+fn already_approved_comes_before_the_contact_limit() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		crate::Parameters::<Test>::put(PalletParameters {
+			max_contacts: 1,
+			..Messaging::parameters()
+		});
+
+		assert_noop!(
+			Messaging::approve_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::AlreadyApproved
+		);
+	});
+}
+
+#[test]
+fn accepting_an_approved_contact_fails() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+
+		assert_noop!(
+			Messaging::accept_and_send(RuntimeOrigin::signed(1), 2, H256::repeat_byte(1)),
+			Error::<Test>::AlreadyApproved
+		);
+	});
+}
+
+#[test]
+fn removing_an_unknown_contact_fails() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		System::reset_events();
+
+		assert_noop!(
+			Messaging::remove_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::ContactNotFound
+		);
+		assert_noop!(
+			Messaging::remove_contact(RuntimeOrigin::signed(1), 9),
+			Error::<Test>::ContactNotFound
+		);
+		assert!(System::events().is_empty());
+
+		// Removing twice fails the second time
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 2));
+		assert_noop!(
+			Messaging::remove_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::ContactNotFound
+		);
+	});
+}
+
+#[test]
+fn removing_rejects_a_pending_request() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		assert_eq!(IncomingContactRequests::<Test>::get(1).len(), 1);
+
+		// Nothing approved by 1, but the request from 2 is rejected
+		assert_ok!(Messaging::remove_contact(RuntimeOrigin::signed(1), 2));
+		assert!(IncomingContactRequests::<Test>::get(1).is_empty());
+		assert!(crate::ApprovedContacts::<Test>::get(2, 1));
+		assert_eq!(crate::ContactCount::<Test>::get(1), 0);
+
+		assert_noop!(
+			Messaging::remove_contact(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::ContactNotFound
+		);
+	});
+}
+
+#[test]
+fn updating_to_the_same_key_fails() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_noop!(
+			Messaging::update_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]),
+			Error::<Test>::KeyUnchanged
+		);
+		assert_eq!(crate::KeyVersions::<Test>::get(1), 0);
+
+		assert_ok!(Messaging::update_profile(RuntimeOrigin::signed(1), vec![5, 6, 7, 8]));
+		assert_noop!(
+			Messaging::update_profile(RuntimeOrigin::signed(1), vec![5, 6, 7, 8]),
+			Error::<Test>::KeyUnchanged
+		);
+	});
+}
+
+#[test]
+fn unchanged_notification_hint_fails() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let hint = NotificationHint::truncate_from(vec![7; 8]);
+		let origin = || RuntimeOrigin::signed(2);
+
+		assert_noop!(
+			Messaging::set_notification_hint(origin(), None),
+			Error::<Test>::NotificationHintUnchanged
+		);
+		assert_ok!(Messaging::set_notification_hint(origin(), Some(hint.clone())));
+		assert_noop!(
+			Messaging::set_notification_hint(origin(), Some(hint.clone())),
+			Error::<Test>::NotificationHintUnchanged
+		);
+		assert_ok!(Messaging::set_notification_hint(origin(), None));
+		assert!(!NotificationHooks::<Test>::contains_key(2));
+	});
+}
+
+/// Accounts 1 and 2 registered, with message 0 sent from 1 to 2 with hash `[1; 32]`.
+fn verify_on_chain(who: u64, hash: H256) -> DispatchResult {
+	Messaging::verify_message_on_chain(RuntimeOrigin::signed(who), 0, hash)
+}
+
+#[test]
+fn match_is_recorded() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		System::set_block_number(5);
+
+		assert_ok!(verify_on_chain(2, H256::repeat_byte(1)));
+		assert_eq!(
+			MessageVerifications::<Test>::get(0),
+			Some(MessageVerification { matched: true, at: 5 })
+		);
+		System::assert_last_event(
+			Event::MessageVerificationRecorded { message_id: 0, matched: true }.into(),
+		);
+		assert_eq!(SpamReports::<Test>::get(1).reports, 0);
+	});
+}
+
+#[test]
+fn mismatch_counts_as_a_report() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+
+		assert_ok!(verify_on_chain(2, H256::repeat_byte(9)));
+		assert_eq!(MessageVerifications::<Test>::get(0).map(|v| v.matched), Some(false));
+		System::assert_has_event(
+			Event::MessageVerificationRecorded { message_id: 0, matched: false }.into(),
+		);
+		System::assert_last_event(
+			Event::SpamReported { message_id: 0, reporter: 2, sender: 1, reports: 1 }.into(),
+		);
+		assert!(crate::ReportedMessages::<Test>::get(0, 2));
+
+		// The mismatch used up the report for this message
+		assert_noop!(
+			Messaging::report_spam(RuntimeOrigin::signed(2), 0),
+			Error::<Test>::AlreadyReported
+		);
+	});
+}
+
+#[test]
+fn mismatch_after_a_report_is_not_counted_twice() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		assert_ok!(Messaging::report_spam(RuntimeOrigin::signed(2), 0));
+
+		assert_ok!(verify_on_chain(2, H256::repeat_byte(9)));
+		assert_eq!(SpamReports::<Test>::get(1).reports, 1);
+		System::assert_last_event(
+			Event::MessageVerificationRecorded { message_id: 0, matched: false }.into(),
+		);
+	});
+}
+
+#[test]
+fn only_the_recipient_verifies_once() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		assert_noop!(verify_on_chain(1, H256::repeat_byte(1)), Error::<Test>::NotAuthorized);
+		assert_noop!(verify_on_chain(3, H256::repeat_byte(1)), Error::<Test>::NotAuthorized);
+
+		assert_ok!(verify_on_chain(2, H256::repeat_byte(1)));
+		assert_noop!(verify_on_chain(2, H256::repeat_byte(1)), Error::<Test>::AlreadyVerified);
+		assert_noop!(verify_on_chain(2, H256::repeat_byte(9)), Error::<Test>::AlreadyVerified);
+	});
+}
+
+#[test]
+fn expired_and_missing_messages_cannot_be_verified() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		assert_noop!(
+			Messaging::verify_message_on_chain(RuntimeOrigin::signed(2), 7, H256::zero()),
+			Error::<Test>::MessageNotFound
+		);
+
+		System::set_block_number(1002);
+		assert_noop!(verify_on_chain(2, H256::repeat_byte(1)), Error::<Test>::MessageExpired);
+	});
+}
+
+#[test]
+fn record_is_dropped_with_the_message() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		assert_ok!(verify_on_chain(2, H256::repeat_byte(1)));
+
+		let origin = RuntimeOrigin::root();
+		assert_ok!(Messaging::force_remove_message(origin, 0, order_reference()));
+		assert!(!MessageVerifications::<Test>::contains_key(0));
+	});
+}
+
+fn state() -> MessageState<u64> {
+	Messaging::message_state(0)
+}
+
+fn verify_message_0() -> Result<bool, sp_runtime::DispatchError> {
+	Messaging::verify_message_hash(0, H256::repeat_byte(1))
+}
+
+#[test]
+fn active_messages_verify() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_eq!(state(), MessageState::Active { expires_at: 1001 });
+		assert_eq!(verify_message_0(), Ok(true));
+		assert_eq!(Messaging::verify_message_hash(0, H256::zero()), Ok(false));
+		assert_eq!(
+			Messaging::verification_result(0, H256::repeat_byte(1)),
+			VerificationResult::Valid
+		);
+
+		// Still active on the last block before expiry
+		System::set_block_number(1001);
+		assert_eq!(state(), MessageState::Active { expires_at: 1001 });
+	});
+}
+
+#[test]
+fn challenged_messages_still_verify() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		let commitment = evidence_commitment();
+		assert_ok!(Messaging::challenge_spam(RuntimeOrigin::signed(3), 0, commitment));
+		assert_eq!(state(), MessageState::Challenged);
+		assert_eq!(verify_message_0(), Ok(true));
+
+		// Expiry wins over an open challenge
+		System::set_block_number(1002);
+		assert_eq!(state(), MessageState::Expired);
+	});
+}
+
+#[test]
+fn expired_messages_fail_with_message_expired() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		System::set_block_number(1002);
+		assert_eq!(state(), MessageState::Expired);
+		assert_noop!(verify_message_0(), Error::<Test>::MessageExpired);
+		assert_eq!(
+			Messaging::verification_result(0, H256::repeat_byte(1)),
+			VerificationResult::Expired
+		);
+	});
+}
+
+#[test]
+fn missing_messages_fail_with_message_not_found() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(state(), MessageState::NotFound);
+		assert_noop!(verify_message_0(), Error::<Test>::MessageNotFound);
+
+		// Pruned messages are no longer told apart from ones never sent
+		send_paid_message();
+		System::set_block_number(1002);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert_eq!(state(), MessageState::NotFound);
+		assert_eq!(
+			Messaging::verification_result(0, H256::repeat_byte(1)),
+			VerificationResult::NotFound
+		);
+	});
+}
+
+#[test]
+fn retracted_messages_fail_with_message_retracted() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+		assert_eq!(state(), MessageState::Retracted);
+		assert_noop!(verify_message_0(), Error::<Test>::MessageRetracted);
+		assert_eq!(
+			Messaging::verification_result(0, H256::repeat_byte(1)),
+			VerificationResult::Retracted
+		);
+		assert_noop!(
+			Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false),
+			Error::<Test>::PaymentNotFound
+		);
+
+		// The tombstone is pruned when the message would have been
+		System::set_block_number(1001);
+		assert!(!Messaging::has_overdue_messages(1001));
+		System::set_block_number(1002);
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert!(!RetractedMessages::<Test>::contains_key(0));
+		assert_eq!(state(), MessageState::NotFound);
+	});
+}
+
+#[test]
+fn taken_down_messages_fail_with_removed_by_governance() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		let origin = RuntimeOrigin::root();
+		assert_ok!(Messaging::force_remove_message(origin, 0, order_reference()));
+		assert_eq!(state(), MessageState::TakenDown);
+		assert_noop!(verify_message_0(), Error::<Test>::MessageRemovedByGovernance);
+		assert_eq!(
+			Messaging::verification_result(0, H256::repeat_byte(1)),
+			VerificationResult::RemovedByGovernance
+		);
+	});
+}
+
+#[test]
+fn only_stored_unexpired_messages_are_live() {
+	assert!(MessageState::Active { expires_at: 1u64 }.is_live());
+	assert!(MessageState::<u64>::Challenged.is_live());
+	for state in [
+		MessageState::<u64>::Expired,
+		MessageState::NotFound,
+		MessageState::Retracted,
+		MessageState::TakenDown,
+	] {
+		assert!(!state.is_live());
+	}
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecated_wrapper_reports_anything_not_live() {
+	new_test_ext().execute_with(|| {
+		assert!(Messaging::is_message_expired(0));
+		send_paid_message();
+		assert!(!Messaging::is_message_expired(0));
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+		assert!(Messaging::is_message_expired(0));
+	});
+}
+
+/// Challenge message 0 as `who`.
+fn challenge_message_0(who: u64) -> DispatchResult {
+	Messaging::challenge_spam(RuntimeOrigin::signed(who), 0, evidence_commitment())
+}
+
+#[test]
+fn window_closes_after_challenge_window_blocks() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		let last_open = 1 + ChallengeWindow::get();
+
+		System::set_block_number(last_open + 1);
+		assert_noop!(challenge_message_0(3), Error::<Test>::ChallengeWindowClosed);
+
+		System::set_block_number(last_open);
+		assert_ok!(challenge_message_0(3));
+	});
+}
+
+#[test]
+fn expired_messages_cannot_be_challenged() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		System::set_block_number(1002);
+		assert_noop!(challenge_message_0(3), Error::<Test>::MessageExpired);
+	});
+}
+
+#[test]
+fn acknowledged_messages_cannot_be_challenged() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+		assert_eq!(AcknowledgedMessages::<Test>::get(0), Some(1));
+
+		assert_noop!(challenge_message_0(3), Error::<Test>::AlreadyAcknowledged);
+	});
+}
+
+#[test]
+fn retracted_and_removed_messages_cannot_be_challenged() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 0));
+		assert_noop!(challenge_message_0(3), Error::<Test>::MessageRetracted);
+		approve(2, 1);
+
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::from([2; 32])
+		));
+		let reference = order_reference();
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 1, reference));
+		assert_noop!(
+			Messaging::challenge_spam(RuntimeOrigin::signed(3), 1, evidence_commitment()),
+			Error::<Test>::MessageRemovedByGovernance
+		);
+	});
+}
+
+#[test]
+fn senders_cannot_challenge_their_own_messages() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_noop!(challenge_message_0(1), Error::<Test>::CannotChallengeOwnMessage);
+		assert_ok!(challenge_message_0(2));
+	});
+}
+
+#[test]
+fn acknowledgements_are_forgotten_with_the_message() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(Messaging::acknowledge_message(RuntimeOrigin::signed(2), 0, false));
+		let reference = order_reference();
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 0, reference));
+		assert!(!AcknowledgedMessages::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn self_addressed_sends_are_rejected() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		let hash = H256::repeat_byte(2);
+		assert_noop!(
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 1, hash),
+			Error::<Test>::CannotMessageSelf
+		);
+		assert_noop!(
+			Messaging::send_paid_message_hash(RuntimeOrigin::signed(2), 2, hash),
+			Error::<Test>::CannotMessageSelf
+		);
+		assert_eq!(crate::AccountStats::<Test>::get(1).sent, 1);
+	});
+}
+
+#[test]
+fn default_hashes_are_rejected() {
+	new_test_ext().execute_with(|| {
+		setup_group();
+		approve(2, 1);
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+		let hash = H256::zero();
+		for result in [
+			Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, hash),
+			Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, hash),
+			Messaging::send_group_message_hash(RuntimeOrigin::signed(1), 0, hash),
+			Messaging::send_to_recipient_set(
+				RuntimeOrigin::signed(1),
+				vec![2, 3].try_into().unwrap(),
+				hash,
+			),
+			Messaging::send_to_unregistered(
+				RuntimeOrigin::signed(1),
+				Messaging::account_hash(&6),
+				hash,
+			),
+		] {
+			assert_eq!(result, Err(Error::<Test>::InvalidMessageHash.into()));
+		}
+		assert_eq!(crate::NextMessageId::<Test>::get(), 0);
+	});
+}
+
+#[test]
+fn pallet_accounts_cannot_be_messaged() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let pallet_account: u64 = MessagingPalletId::get().into_account_truncating();
+		for account in [
+			pallet_account,
+			Messaging::escrow_account(),
+			Messaging::reward_pool_account(),
+		] {
+			assert!(Messaging::is_reserved_account(&account));
+			assert_noop!(
+				Messaging::send_message_hash(
+					RuntimeOrigin::signed(1),
+					account,
+					H256::repeat_byte(1)
+				),
+				Error::<Test>::ReservedRecipient
+			);
+		}
+		assert!(!Messaging::is_reserved_account(&2));
+	});
+}
+
+fn deregister(who: u64) -> DispatchResult {
+	Messaging::deregister_profile(RuntimeOrigin::signed(who))
+}
+
+fn message_status(message_id: u64) -> MessageStatus {
+	Messaging::conversation_page(1, 2, 0, 10)
+		.messages
+		.into_iter()
+		.find(|message| message.message_id == message_id)
+		.expect("message is listed")
+		.status
+}
+
+#[test]
+fn open_challenges_block_deregistration() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(challenge_message_0(3));
+		assert_noop!(deregister(1), Error::<Test>::OpenChallengesPending);
+	});
+}
+
+#[test]
+fn unlocked_bonds_are_refunded_on_deregistration() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(deregister(1));
+
+		assert!(!crate::UserProfiles::<Test>::contains_key(1));
+		assert_eq!(held(HoldReason::SpamBond, 1), 0);
+		assert_eq!(Balances::free_balance(1), 1000);
+		System::assert_last_event(
+			Event::ProfileDeregistered { who: 1, refunded: 100, forced: false }.into(),
+		);
+	});
+}
+
+#[test]
+fn departed_senders_messages_stay_verifiable() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(deregister(1));
+
+		assert_eq!(DepartedSenders::<Test>::get(1), Some(1));
+		assert_eq!(Messaging::verify_message_hash(0, H256::from([1; 32])), Ok(true));
+		assert_eq!(message_status(0), MessageStatus::SenderDeparted);
+		System::assert_last_event(
+			Event::ProfileDeregistered { who: 1, refunded: 0, forced: false }.into(),
+		);
+	});
+}
+
+#[test]
+fn payments_are_refundable_once_the_sender_departs() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_noop!(
+			Messaging::refund_inbox_payment(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::MessageNotExpired
+		);
+
+		assert_ok!(deregister(1));
+		assert_ok!(Messaging::refund_inbox_payment(RuntimeOrigin::signed(3), 0));
+		assert_eq!(held(HoldReason::InboxPayment, 1), 0);
+	});
+}
+
+#[test]
+fn locked_bonds_wait_for_the_unbonding_period() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(deregister(1));
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_noop!(
+			Messaging::refund_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::BondLocked
+		);
+
+		// The old bond has to be claimed before registering again
+		let register = || Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2]);
+		assert_noop!(register(), Error::<Test>::UnrefundedBond);
+
+		System::set_block_number(1 + BondUnbondingPeriod::get());
+		assert_ok!(Messaging::refund_bond(RuntimeOrigin::signed(1)));
+		assert_ok!(register());
+		assert!(!DepartedSenders::<Test>::contains_key(1));
+		assert_eq!(message_status(0), MessageStatus::AwaitingAcknowledgement);
+	});
+}
+
+#[test]
+fn forced_deregistration_keeps_the_bond_for_open_challenges() {
+	new_test_ext().execute_with(|| {
+		send_paid_message();
+		assert_ok!(challenge_message_0(3));
+		assert_ok!(Messaging::force_deregister_profile(RuntimeOrigin::root(), 1));
+
+		assert!(!crate::UserProfiles::<Test>::contains_key(1));
+		assert_eq!(held(HoldReason::SpamBond, 1), 100);
+		assert_eq!(message_status(0), MessageStatus::Challenged);
+		assert_noop!(
+			Messaging::refund_inbox_payment(RuntimeOrigin::signed(3), 0),
+			Error::<Test>::MessageNotExpired
+		);
+		assert_noop!(
+			Messaging::refund_bond(RuntimeOrigin::signed(1)),
+			Error::<Test>::OpenChallengesPending
+		);
+		System::assert_has_event(
+			Event::ProfileDeregistered { who: 1, refunded: 0, forced: true }.into(),
+		);
+
+		// An upheld challenge still slashes the bond left in place
+		assert_ok!(Messaging::resolve_challenge(RuntimeOrigin::root(), 0, true));
+		assert_eq!(held(HoldReason::SpamBond, 1), 50);
+	});
+}
+
+#[test]
+fn forced_deregistration_is_audited() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Messaging::force_deregister_profile(RuntimeOrigin::signed(1), 1),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		setup_pair();
+		assert_ok!(Messaging::force_deregister_profile(RuntimeOrigin::root(), 2));
+		let entry = Messaging::audit_log().last().cloned().expect("log is not empty");
+		assert_eq!(entry.action, AuditAction::ForceDeregisterProfile);
+	});
+}
+
+#[test]
+fn counts_follow_registrations_and_removals() {
+	new_test_ext().execute_with(|| {
+		assert_eq!((Messaging::message_count(), Messaging::profile_count()), (0, 0));
+		send_paid_message();
+		approve(1, 2);
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(2),
+			1,
+			H256::from([2; 32])
+		));
+		assert_eq!((Messaging::message_count(), Messaging::profile_count()), (2, 2));
+
+		let reference = order_reference();
+		assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 1, reference));
+		ban(2);
+		assert_eq!((Messaging::message_count(), Messaging::profile_count()), (1, 1));
+		let stats = Messaging::global_stats();
+		assert_eq!((stats.stored_messages, stats.stored_profiles), (1, 1));
+		assert_eq!((stats.messages, stats.profiles), (2, 2));
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn pruning_uncounts_messages() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		System::set_block_number(1002);
+
+		assert_ok!(Messaging::prune_expired_unsigned(RuntimeOrigin::none(), 1002));
+		assert_eq!(Messaging::message_count(), 0);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn try_state_catches_a_stale_counter() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		crate::migrations::CounterForUserProfiles::<Test>::put(3);
+		assert_err!(Messaging::do_try_state(), "messaging: wrong profile count");
+	});
+}
+
+type W = <Test as crate::Config>::WeightInfo;
+
+/// Dispatch `call` from `origin` and return the weight it reports, checking that it does
+/// not exceed the declared weight, and the declared weight.
+fn dispatch(origin: RuntimeOrigin, call: Call<Test>) -> (Weight, Weight) {
+	let declared = call.get_dispatch_info().call_weight;
+	let post_info = call.dispatch_bypass_filter(origin).expect("call succeeds");
+	let actual = post_info.actual_weight.expect("call reports its weight");
+	assert!(actual.all_lte(declared));
+	(actual, declared)
+}
+
+fn send_hash_call(recipient: u64, byte: u8) -> Call<Test> {
+	Call::send_message_hash { recipient, message_hash: H256::repeat_byte(byte) }
+}
+
+#[test]
+fn sends_without_deposits_to_refund() {
+	new_test_ext().execute_with(|| {
+		setup_approved_pair();
+		let (actual, declared) = dispatch(RuntimeOrigin::signed(1), send_hash_call(2, 1));
+		assert_eq!(actual, W::send_message_hash() + W::refund_deposits_on_reply(0));
+		assert!(actual.all_lt(declared));
+
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(1), Some(50)));
+		let call =
+			Call::send_paid_message_hash { recipient: 1, message_hash: H256::repeat_byte(2) };
+		let (actual, declared) = dispatch(RuntimeOrigin::signed(2), call);
+		assert_eq!(actual, W::send_paid_message_hash() + W::refund_deposits_on_reply(0));
+		assert!(actual.all_lt(declared));
+
+		let call = Call::accept_and_send { requester: 2, message_hash: H256::repeat_byte(3) };
+		let (actual, declared) = dispatch(RuntimeOrigin::signed(1), call);
+		assert_eq!(actual, W::accept_and_send() + W::refund_deposits_on_reply(0));
+		assert!(actual.all_lt(declared));
+	});
+}
+
+#[test]
+fn replies_pay_for_the_deposits_they_refund() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(2), 1));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(3), 1));
+		assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(1), 2));
+		let recipients: RecipientSet<u64> = vec![2, 3].try_into().unwrap();
+		assert_ok!(Messaging::send_to_recipient_set(
+			RuntimeOrigin::signed(1),
+			recipients,
+			H256::repeat_byte(1)
+		));
+
+		let (actual, _) = dispatch(RuntimeOrigin::signed(2), send_hash_call(1, 2));
+		assert_eq!(actual, W::send_message_hash() + W::refund_deposits_on_reply(1));
+	});
+}
+
+#[test]
+fn batched_replies_share_one_refund_budget() {
+	new_test_ext().execute_with(|| {
+		setup_replying_trio();
+		// 2 and 3 each send two messages listing 1, IDs 0 and 1 from 2, 2 and 3 from 3
+		for sender in [2, 3] {
+			for byte in [1, 2] {
+				assert_ok!(Messaging::send_to_recipient_set(
+					RuntimeOrigin::signed(sender),
+					recipient_set(vec![1]),
+					H256::repeat_byte(sender as u8 * 10 + byte)
+				));
+			}
+		}
+		System::reset_events();
+
+		let call = Call::send_message_hash_batch { messages: batch(vec![(2, 1), (3, 2)]) };
+		let (actual, _) = dispatch(RuntimeOrigin::signed(1), call);
+
+		assert_eq!(refunded_on_reply(), vec![1, 0]);
+		let refunds = W::refund_deposits_on_reply(MaxAutoRefundsPerReply::get());
+		assert_eq!(actual, W::send_message_hash_batch(2) + refunds);
+		// The deposits of 3 wait for the next reply
+		assert_eq!(crate::ReplyRefundableDeposits::<Test>::get(3, 1).into_inner(), vec![2, 3]);
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			3,
+			H256::repeat_byte(3)
+		));
+		assert_eq!(refunded_on_reply(), vec![1, 0, 3, 2]);
+		assert_eq!(held(HoldReason::MessageDeposit, 3), 0);
+		assert_ok!(Messaging::do_try_state());
+	});
+}
+
+#[test]
+fn key_reset_notices_pay_for_the_contacts_scanned() {
+	new_test_ext().execute_with(|| {
+		for who in [1, 2, 3, 5, 6] {
+			let public_key = vec![1, 2, 3, 4];
+			assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(who), public_key));
+		}
+		for contact in [2, 3, 5, 6] {
+			make_mutual_contacts(1, contact);
+		}
+
+		// A full batch uses the declared weight
+		let call = Call::force_reset_key {
+			who: 1,
+			new_public_key: vec![9; 32],
+			algorithm: KeyAlgorithm::X25519,
+		};
+		let (actual, declared) = dispatch(RuntimeOrigin::root(), call);
+		assert_eq!(actual, declared);
+
+		// The last one only what is left
+		let call = Call::continue_key_reset_notices { who: 1 };
+		let (actual, declared) = dispatch(RuntimeOrigin::signed(4), call);
+		assert_eq!(actual, W::continue_key_reset_notices(1));
+		assert!(actual.all_lt(declared));
+	});
+}
+
+#[test]
+fn ephemeral_keys_pay_for_the_keys_scanned() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let publish = |index: u32| Call::publish_ephemeral_key {
+			index,
+			key: vec![index as u8 + 1; 32].try_into().unwrap(),
+		};
+
+		let (actual, declared) = dispatch(RuntimeOrigin::signed(1), publish(0));
+		assert_eq!(actual, W::publish_ephemeral_key(0));
+		assert!(actual.all_lt(declared));
+
+		// Replacing a key under its index scans nothing either
+		let (actual, _) = dispatch(RuntimeOrigin::signed(1), publish(0));
+		assert_eq!(actual, W::publish_ephemeral_key(0));
+		let (actual, _) = dispatch(RuntimeOrigin::signed(1), publish(1));
+		assert_eq!(actual, W::publish_ephemeral_key(1));
+	});
+}
+
+/// Messages 0 to 3 from 1 to 2, with hash `[id + 1; 32]`: 0 has expired, 1 was
+/// retracted, 2 was taken down and 3 is live.
+fn setup_settled_messages() {
+	send_expiring_message();
+	System::set_block_number(1002);
+	assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(50)));
+	for id in 1..4u8 {
+		let hash = H256::repeat_byte(id + 1);
+		assert_ok!(Messaging::send_paid_message_hash(RuntimeOrigin::signed(1), 2, hash));
+	}
+	assert_ok!(Messaging::retract_paid_message(RuntimeOrigin::signed(1), 1));
+	assert_ok!(Messaging::force_remove_message(RuntimeOrigin::root(), 2, order_reference()));
+}
+
+fn verify_batch(batch: Vec<(MessageId, H256)>) -> Vec<VerificationResult> {
+	view(VerifyMessageBatchViewFunction::<Test>::new(batch.try_into().unwrap()))
+}
+
+#[test]
+fn reports_each_entry_in_order() {
+	new_test_ext().execute_with(|| {
+		setup_settled_messages();
+		let batch = vec![
+			(9, H256::repeat_byte(10)),
+			(3, H256::repeat_byte(4)),
+			(0, H256::repeat_byte(1)),
+			(3, H256::zero()),
+			(2, H256::repeat_byte(3)),
+			(1, H256::repeat_byte(2)),
+		];
+
+		let results = verify_batch(batch.clone());
+		assert_eq!(
+			results,
+			vec![
+				VerificationResult::NotFound,
+				VerificationResult::Valid,
+				VerificationResult::Expired,
+				VerificationResult::Mismatch,
+				VerificationResult::RemovedByGovernance,
+				VerificationResult::Retracted,
+			]
+		);
+
+		// Each entry is answered as if checked on its own
+		let single: Vec<_> = batch
+			.into_iter()
+			.map(|(message_id, hash)| Messaging::verification_result(message_id, hash))
+			.collect();
+		assert_eq!(results, single);
+	});
+}
+
+#[test]
+fn repeated_ids_are_checked_against_their_own_hash() {
+	new_test_ext().execute_with(|| {
+		setup_settled_messages();
+		let batch = vec![
+			(3, H256::zero()),
+			(3, H256::repeat_byte(4)),
+			(3, H256::zero()),
+			(3, H256::repeat_byte(4)),
+		];
+		assert_eq!(
+			Messaging::verify_messages(batch),
+			vec![
+				VerificationResult::Mismatch,
+				VerificationResult::Valid,
+				VerificationResult::Mismatch,
+				VerificationResult::Valid,
+			]
+		);
+	});
+}
+
+#[test]
+fn empty_batches_verify_nothing() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(verify_batch(Vec::new()), Vec::new());
+	});
+}
+
+#[test]
+fn batches_are_bounded() {
+	new_test_ext().execute_with(|| {
+		send_expiring_message();
+		let full = vec![(0, H256::repeat_byte(1)); MAX_VERIFICATION_BATCH as usize];
+		assert_eq!(
+			verify_batch(full.clone()),
+			vec![VerificationResult::Valid; MAX_VERIFICATION_BATCH as usize]
+		);
+
+		let mut oversized = full;
+		oversized.push((0, H256::repeat_byte(1)));
+		assert!(VerificationBatch::<H256>::try_from(oversized.clone()).is_err());
+		let id = VerifyMessageBatchViewFunction::<Test>::id();
+		assert!(Test::execute_view_function(id, oversized.encode()).is_err());
+	});
+}
+
+const DIRECT: u64 = 3000;
+const GROUP: u64 = 1000;
+
+/// Store `DIRECT` message IDs, every third a retracted message's marker and the rest
+/// direct messages, and `GROUP` group messages, all sent at block 1.
+///
+/// Returns a block by which all of them had expired.
+fn store_expired() -> u64 {
+	for id in 0..DIRECT {
+		if id % 3 == 2 {
+			RetractedMessages::<Test>::insert(id, 1);
+			continue;
+		}
+		let hash = H256::from_low_u64_be(id);
+		MessageHashes::<Test>::insert(
+			id,
+			MessageRecord { sender: 1, recipient: 2, hash, sent_at: 1 },
+		);
+		MessageIdByHash::<Test>::insert(hash, id);
+	}
+	for id in 0..GROUP {
+		GroupMessages::<Test>::insert(
+			0,
+			id,
+			GroupMessageRecord {
+				hash: H256::repeat_byte(1),
+				sender: 1,
+				sent_at: 1,
+				key_version: 0,
+				expires_at: 501,
+				seq: id,
+			},
+		);
+		GroupMessageIds::<Test>::insert(id, 0);
+	}
+	NextMessageId::<Test>::put(DIRECT);
+	NextGroupMessageId::<Test>::put(GROUP);
+	let now = 1002;
+	System::set_block_number(now);
+	now
+}
+
+fn progress() -> u64 {
+	PruneCursor::<Test>::get() + GroupPruneCursor::<Test>::get()
+}
+
+#[test]
+fn on_idle_never_exceeds_the_weight_it_was_given() {
+	let base = <() as WeightInfo>::prune_expired_unsigned(0);
+	let costs = PruneCosts::of::<Test>();
+	let per_message = costs.index_entry.saturating_add(costs.direct_message);
+	let limits = [
+		base.saturating_add(per_message),
+		base.saturating_add(per_message.saturating_mul(7)),
+		base.saturating_add(per_message.saturating_mul(50)),
+		// Proof size runs out long before the time does
+		Weight::from_parts(u64::MAX, base.proof_size() + 3 * per_message.proof_size()),
+	];
+
+	for limit in limits {
+		new_test_ext().execute_with(|| {
+			let now = store_expired();
+			let mut calls = 0;
+			while Messaging::has_overdue_messages(now) {
+				let before = progress();
+				let used = Messaging::on_idle(now, limit);
+				let mut meter = WeightMeter::with_limit(limit);
+				assert!(meter.try_consume(used).is_ok(), "{used:?} exceeds {limit:?}");
+				assert!(progress() > before);
+				calls += 1;
+			}
+
+			assert!(calls > 1);
+			assert_eq!(progress(), DIRECT + GROUP);
+			assert_eq!(MessageHashes::<Test>::iter_keys().count(), 0);
+			assert_eq!(RetractedMessages::<Test>::iter_keys().count(), 0);
+			assert_eq!(GroupMessages::<Test>::iter_keys().count(), 0);
+		});
+	}
+}
+
+#[test]
+fn on_idle_prunes_nothing_without_room_for_its_base_weight() {
+	new_test_ext().execute_with(|| {
+		let now = store_expired();
+		let base = <() as WeightInfo>::prune_expired_unsigned(0);
+		let limit = base.saturating_sub(Weight::from_parts(1, 0));
+
+		assert!(Messaging::on_idle(now, limit).all_lte(limit));
+		assert_eq!(progress(), 0);
+
+		// Enough for the base but no message prunes nothing either
+		assert_eq!(Messaging::on_idle(now, base), base);
+		assert_eq!(progress(), 0);
+	});
+}
+
+#[test]
+fn on_idle_with_ample_weight_prunes_everything_at_once() {
+	new_test_ext().execute_with(|| {
+		let now = store_expired();
+
+		Messaging::on_idle(now, Weight::MAX);
+
+		assert_eq!(progress(), DIRECT + GROUP);
+		assert!(!Messaging::has_overdue_messages(now));
+	});
+}
+
+#[test]
+fn on_idle_only_charges_its_base_without_overdue_messages() {
+	new_test_ext().execute_with(|| {
+		let base = <() as WeightInfo>::prune_expired_unsigned(0);
+		assert_eq!(Messaging::on_idle(1, Weight::MAX), base);
+		assert_eq!(progress(), 0);
+	});
+}
+
+/// Register accounts 1, 2 and 3, with 2 and 3 approving 1.
+fn setup_trio() {
+	setup_approved_pair();
+	assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+	assert_ok!(Messaging::approve_contact(RuntimeOrigin::signed(3), 1));
+}
+
+fn batch(messages: Vec<(u64, u8)>) -> SendBatchOf<Test> {
+	messages
+		.into_iter()
+		.map(|(to, byte)| (to, H256::repeat_byte(byte)))
+		.collect::<Vec<_>>()
+		.try_into()
+		.unwrap()
+}
+
+fn send_batch_events(messages: Vec<(u64, u8)>) -> Vec<Event<Test>> {
+	System::reset_events();
+	assert_ok!(Messaging::send_message_hash_batch(RuntimeOrigin::signed(1), batch(messages)));
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::Messaging(event) => Some(event),
+			_ => None,
+		})
+		.collect()
+}
+
+fn leaves(first_id: u64, count: u64) -> Vec<H256> {
+	(first_id..first_id + count)
+		.map(|message_id| {
+			let message = crate::MessageHashes::<Test>::get(message_id).unwrap();
+			compact_events::leaf::<BlakeTwo256, _>(
+				message_id,
+				&message.recipient,
+				&message.hash,
+			)
+		})
+		.collect()
+}
+
+#[test]
+fn tree_matches_test_vectors() {
+	let hex = |hash: H256| HexDisplay::from(&hash.as_bytes()).to_string();
+	let leaf = |message_id: u64, to: u8, hash: u8| {
+		compact_events::leaf::<BlakeTwo256, _>(
+			message_id,
+			&[to; 32],
+			&H256::repeat_byte(hash),
+		)
+	};
+	let leaves = vec![leaf(0, 1, 2), leaf(1, 3, 4), leaf(2, 5, 6)];
+	assert_eq!(
+		hex(leaves[0]),
+		"172a7f3a72da2496a3c7eaf0947f7a19f4878dd07706976094445c2db7947c1b"
+	);
+	assert_eq!(
+		hex(leaves[1]),
+		"646b1b5bf825b860f6959a36d7e1df40ece62f1299e768a64397ae38ac1a7451"
+	);
+	assert_eq!(
+		hex(leaves[2]),
+		"1fb8eba1eace82d809e4319af6daf13788da2f80ce9e3f7c7f3a45bf5a7d98ed"
+	);
+
+	let root = |count: usize| compact_events::root::<BlakeTwo256>(leaves[..count].to_vec());
+	assert_eq!(root(1), leaves[0]);
+	assert_eq!(
+		hex(root(2)),
+		"e226756276f5c91c8c201fbaf3f66c58a02fe068e20652ae5bdcdbade522ba36"
+	);
+	assert_eq!(
+		hex(root(3)),
+		"b6c0790e158f780bf82cccbc27c80c92aa121e996b927035f6f0074771855a22"
+	);
+	assert_eq!(root(0), H256::zero());
+}
+
+#[test]
+fn proofs_verify_each_leaf_and_nothing_else() {
+	for count in 1..=9usize {
+		let leaves: Vec<H256> = (0..count as u8).map(H256::repeat_byte).collect();
+		let root = compact_events::root::<BlakeTwo256>(leaves.clone());
+		for (index, leaf) in leaves.iter().enumerate() {
+			let proof = compact_events::proof::<BlakeTwo256>(leaves.clone(), index);
+			let verify = |leaf: H256, index: usize, count: usize, proof: &[H256]| {
+				compact_events::verify::<BlakeTwo256>(&root, leaf, index, count, proof)
+			};
+			assert!(verify(*leaf, index, count, &proof));
+
+			assert!(!verify(H256::repeat_byte(0xff), index, count, &proof));
+			assert!(!verify(*leaf, count, count, &proof));
+			if count > 1 {
+				assert!(!verify(*leaf, index ^ 1, count, &proof));
+				assert!(!verify(*leaf, index, count, &proof[1..]));
+			}
+			let mut padded = proof.clone();
+			padded.push(H256::zero());
+			assert!(!verify(*leaf, index, count, &padded));
+		}
+	}
+}
+
+#[test]
+fn batches_are_reported_per_message_by_default() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		let events = send_batch_events(vec![(2, 1), (3, 2)]);
+
+		let sent: Vec<_> = events
+			.iter()
+			.filter_map(|event| match event {
+				Event::MessageSent { message_id, to, .. } => Some((*message_id, *to)),
+				_ => None,
+			})
+			.collect();
+		assert_eq!(sent, vec![(0, 2), (1, 3)]);
+		assert!(!events.iter().any(|event| matches!(event, Event::MessagesSentCompact { .. })));
+	});
+}
+
+#[test]
+fn compact_batches_emit_one_event_committing_to_every_message() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		assert_ok!(Messaging::send_message_hash(
+			RuntimeOrigin::signed(1),
+			2,
+			H256::repeat_byte(9)
+		));
+		assert_ok!(Messaging::set_compact_events(RuntimeOrigin::signed(1), true));
+		System::assert_last_event(Event::CompactEventsSet { who: 1, enabled: true }.into());
+
+		let events = send_batch_events(vec![(2, 1), (3, 2), (2, 3)]);
+
+		let ids_root = compact_events::root::<BlakeTwo256>(leaves(1, 3));
+		let compact = Event::MessagesSentCompact { from: 1, count: 3, first_id: 1, ids_root };
+		assert_eq!(events.last(), Some(&compact));
+		assert!(!events.iter().any(|event| matches!(event, Event::MessageSent { .. })));
+
+		// Stored, indexed and verifiable like any other message
+		assert_eq!(crate::NextMessageId::<Test>::get(), 4);
+		assert_eq!(Messaging::verify_message_hash(2, H256::repeat_byte(2)), Ok(true));
+		assert_eq!(crate::MessageHashes::<Test>::get(2).unwrap().recipient, 3);
+
+		// The recipient of message 2 proves its inclusion from the stored batch
+		let proof = compact_events::proof::<BlakeTwo256>(leaves(1, 3), 1);
+		let leaf = compact_events::leaf::<BlakeTwo256, _>(2, &3u64, &H256::repeat_byte(2));
+		assert!(compact_events::verify::<BlakeTwo256>(&ids_root, leaf, 1, 3, &proof));
+	});
+}
+
+#[test]
+fn minimal_privacy_reports_compact_senders_per_message() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		assert_ok!(Messaging::set_compact_events(RuntimeOrigin::signed(1), true));
+		let parameters = PalletParameters {
+			event_privacy: EventPrivacyMode::Minimal,
+			..Messaging::parameters()
+		};
+		assert_ok!(Messaging::update_parameters(
+			RuntimeOrigin::signed(Admin::get()),
+			parameters
+		));
+
+		let events = send_batch_events(vec![(2, 1), (3, 2)]);
+
+		let minimal = events
+			.iter()
+			.filter(|event| matches!(event, Event::MessageSentMinimal { .. }))
+			.count();
+		assert_eq!(minimal, 2);
+		assert!(!events.iter().any(|event| matches!(event, Event::MessagesSentCompact { .. })));
+	});
+}
+
+#[test]
+fn batches_fail_as_a_whole() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		let send = |messages| {
+			Messaging::send_message_hash_batch(RuntimeOrigin::signed(1), batch(messages))
+		};
+
+		assert_noop!(send(vec![]), Error::<Test>::EmptySendBatch);
+		assert_noop!(
+			send(vec![(2, 1), (4, 2)]),
+			failed(1, ItemError::CannotMessage(CanMessageError::NoRecipientProfile))
+		);
+		assert_noop!(send(vec![(2, 1), (3, 1)]), failed(1, ItemError::DuplicateHash));
+		assert_noop!(send(vec![(2, 0)]), failed(0, ItemError::InvalidHash));
+		assert_eq!(crate::NextMessageId::<Test>::get(), 0);
+		assert!(batch(vec![(2, 1); MaxSendBatch::get() as usize]).is_full());
+	});
+}
+
+#[test]
+fn failing_batch_item_is_reported_and_rolls_back_the_batch() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		set_protocol_fee(10, false);
+		assert_ok!(try_send(1, 2));
+		let next_id = crate::NextMessageId::<Test>::get();
+		let balance = Balances::free_balance(1);
+
+		// First, middle and last position, 4 having no profile
+		let failures = [
+			vec![(4, 10), (2, 11), (3, 12)],
+			vec![(2, 10), (4, 11), (3, 12)],
+			vec![(2, 10), (3, 11), (4, 12)],
+		];
+		for (index, messages) in failures.into_iter().enumerate() {
+			assert_noop!(
+				Messaging::send_message_hash_batch(RuntimeOrigin::signed(1), batch(messages)),
+				failed(index as u8, ItemError::CannotMessage(CanMessageError::NoRecipientProfile))
+			);
+		}
+
+		assert_eq!(crate::NextMessageId::<Test>::get(), next_id);
+		assert_eq!(Balances::free_balance(1), balance);
+		assert!(!crate::MessageHashes::<Test>::contains_key(next_id));
+		assert_eq!(crate::MessageIdByHash::<Test>::get(H256::repeat_byte(10)), None);
+
+		let messages = batch(vec![(2, 10), (3, 11), (2, 12)]);
+		assert_ok!(Messaging::send_message_hash_batch(RuntimeOrigin::signed(1), messages));
+		assert_eq!(crate::NextMessageId::<Test>::get(), next_id + 3);
+		assert_eq!(Balances::free_balance(1), balance - 30);
+	});
+}
+
+#[test]
+fn compact_events_are_toggled_once_and_dropped_with_the_profile() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		assert_noop!(
+			Messaging::set_compact_events(RuntimeOrigin::signed(4), true),
+			Error::<Test>::ProfileNotFound
+		);
+		assert_noop!(
+			Messaging::set_compact_events(RuntimeOrigin::signed(1), false),
+			Error::<Test>::CompactEventsUnchanged
+		);
+		assert_ok!(Messaging::set_compact_events(RuntimeOrigin::signed(1), true));
+		assert_noop!(
+			Messaging::set_compact_events(RuntimeOrigin::signed(1), true),
+			Error::<Test>::CompactEventsUnchanged
+		);
+		assert!(CompactEventSenders::<Test>::contains_key(1));
+
+		assert_ok!(Messaging::set_compact_events(RuntimeOrigin::signed(1), false));
+		assert!(!CompactEventSenders::<Test>::contains_key(1));
+
+		assert_ok!(Messaging::set_compact_events(RuntimeOrigin::signed(3), true));
+		assert_ok!(Messaging::deregister_profile(RuntimeOrigin::signed(3)));
+		assert!(!CompactEventSenders::<Test>::contains_key(3));
+	});
+}
+
+fn commitment(byte: u8) -> H256 {
+	H256::repeat_byte(byte)
+}
+
+fn session_status(initiator: u64, peer: u64) -> Option<(u32, SessionStatus)> {
+	Sessions::<Test>::get(initiator, peer).map(|session| (session.epoch, session.status))
+}
+
+#[test]
+fn handshake_records_confirms_and_terminates_a_session() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		System::set_block_number(5);
+		let key: crate::EphemeralKey = vec![9; 32].try_into().unwrap();
+		assert_ok!(Messaging::publish_ephemeral_key(RuntimeOrigin::signed(2), 7, key));
+
+		assert_ok!(Messaging::record_session(
+			RuntimeOrigin::signed(1),
+			2,
+			commitment(1),
+			Some(7),
+		));
+		System::assert_last_event(
+			Event::SessionRecorded {
+				initiator: 1,
+				peer: 2,
+				commitment: commitment(1),
+				prekey_index: Some(7),
+				epoch: 0,
+			}
+			.into(),
+		);
+		assert_eq!(
+			Sessions::<Test>::get(1, 2),
+			Some(SessionInfo {
+				commitment: commitment(1),
+				prekey_index: Some(7),
+				established_at: 5,
+				epoch: 0,
+				status: SessionStatus::Pending,
+			})
+		);
+		assert!(!Messaging::has_confirmed_session(&1, &2));
+
+		assert_ok!(Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(1)));
+		System::assert_last_event(
+			Event::SessionConfirmed { initiator: 1, peer: 2, epoch: 0 }.into(),
+		);
+		assert_eq!(session_status(1, 2), Some((0, SessionStatus::Confirmed)));
+		assert!(Messaging::has_confirmed_session(&1, &2));
+		assert!(Messaging::has_confirmed_session(&2, &1));
+		assert_noop!(
+			Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(1)),
+			Error::<Test>::SessionAlreadyConfirmed
+		);
+
+		// Only the parties can terminate it, and only once
+		assert_noop!(
+			Messaging::terminate_session(RuntimeOrigin::signed(3), 1, 2),
+			Error::<Test>::NotAuthorized
+		);
+		assert_ok!(Messaging::terminate_session(RuntimeOrigin::signed(2), 1, 2));
+		System::assert_last_event(
+			Event::SessionTerminated { initiator: 1, peer: 2, epoch: 0, by: 2 }.into(),
+		);
+		assert_eq!(session_status(1, 2), Some((0, SessionStatus::Terminated)));
+		assert!(!Messaging::has_confirmed_session(&1, &2));
+		assert_noop!(
+			Messaging::terminate_session(RuntimeOrigin::signed(1), 1, 2),
+			Error::<Test>::SessionNotFound
+		);
+		assert_noop!(
+			Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(1)),
+			Error::<Test>::SessionNotFound
+		);
+	});
+}
+
+#[test]
+fn mismatched_commitments_and_unknown_sessions_are_rejected() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+
+		assert_noop!(
+			Messaging::record_session(RuntimeOrigin::signed(1), 1, commitment(1), None),
+			Error::<Test>::CannotMessageSelf
+		);
+		assert_noop!(
+			Messaging::record_session(RuntimeOrigin::signed(1), 4, commitment(1), None),
+			Error::<Test>::RecipientNotFound
+		);
+		assert_noop!(
+			Messaging::record_session(RuntimeOrigin::signed(1), 2, commitment(1), Some(0)),
+			Error::<Test>::EphemeralKeyNotFound
+		);
+
+		assert_ok!(Messaging::record_session(
+			RuntimeOrigin::signed(1),
+			2,
+			commitment(1),
+			None,
+		));
+		assert_noop!(
+			Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(2)),
+			Error::<Test>::SessionCommitmentMismatch
+		);
+		// Only the peer can confirm, and sessions are directional
+		assert_noop!(
+			Messaging::confirm_session(RuntimeOrigin::signed(3), 1, commitment(1)),
+			Error::<Test>::SessionNotFound
+		);
+		assert_noop!(
+			Messaging::confirm_session(RuntimeOrigin::signed(1), 2, commitment(1)),
+			Error::<Test>::SessionNotFound
+		);
+		assert_eq!(session_status(1, 2), Some((0, SessionStatus::Pending)));
+	});
+}
+
+#[test]
+fn recording_again_bumps_the_epoch_and_needs_a_fresh_confirmation() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		assert_ok!(Messaging::record_session(
+			RuntimeOrigin::signed(1),
+			2,
+			commitment(1),
+			None,
+		));
+		assert_ok!(Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(1)));
+
+		assert_ok!(Messaging::record_session(
+			RuntimeOrigin::signed(1),
+			2,
+			commitment(2),
+			None,
+		));
+		assert_eq!(session_status(1, 2), Some((1, SessionStatus::Pending)));
+		assert!(!Messaging::has_confirmed_session(&1, &2));
+		assert_noop!(
+			Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(1)),
+			Error::<Test>::SessionCommitmentMismatch
+		);
+		assert_ok!(Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(2)));
+
+		// Terminated sessions keep their epoch for the next one
+		assert_ok!(Messaging::terminate_session(RuntimeOrigin::signed(1), 1, 2));
+		assert_ok!(Messaging::record_session(
+			RuntimeOrigin::signed(1),
+			2,
+			commitment(3),
+			None,
+		));
+		assert_eq!(session_status(1, 2), Some((2, SessionStatus::Pending)));
+	});
+}
+
+#[test]
+fn sends_need_a_confirmed_session_once_the_recipient_requires_one() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		let send =
+			|byte| Messaging::send_message_hash(RuntimeOrigin::signed(1), 2, commitment(byte));
+		assert_ok!(send(10));
+
+		assert_ok!(Messaging::set_session_requirement(RuntimeOrigin::signed(2), true));
+		System::assert_last_event(
+			Event::SessionRequirementSet { who: 2, required: true }.into(),
+		);
+		assert_noop!(
+			Messaging::set_session_requirement(RuntimeOrigin::signed(2), true),
+			Error::<Test>::SessionRequirementUnchanged
+		);
+		assert_noop!(send(11), Error::<Test>::SessionRequired);
+		assert_eq!(Messaging::can_message(1, 2), Err(CanMessageError::SessionRequired));
+		// Other recipients are unaffected
+		assert_ok!(Messaging::send_message_hash(RuntimeOrigin::signed(1), 3, commitment(12)));
+
+		// A pending session is not enough, a confirmed one in either direction is
+		assert_ok!(Messaging::record_session(
+			RuntimeOrigin::signed(2),
+			1,
+			commitment(1),
+			None,
+		));
+		assert_noop!(send(11), Error::<Test>::SessionRequired);
+		assert_ok!(Messaging::confirm_session(RuntimeOrigin::signed(1), 2, commitment(1)));
+		assert_ok!(send(11));
+
+		assert_ok!(Messaging::terminate_session(RuntimeOrigin::signed(1), 2, 1));
+		assert_noop!(send(13), Error::<Test>::SessionRequired);
+
+		assert_ok!(Messaging::set_session_requirement(RuntimeOrigin::signed(2), false));
+		assert_ok!(send(13));
+	});
+}
+
+#[test]
+fn session_requirement_is_dropped_with_the_profile() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		assert_ok!(Messaging::set_session_requirement(RuntimeOrigin::signed(3), true));
+		assert!(crate::UserProfiles::<Test>::get(3).unwrap().session_required);
+
+		// Registering again starts without the requirement
+		assert_ok!(Messaging::deregister_profile(RuntimeOrigin::signed(3)));
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(3), vec![1, 2, 3, 4]));
+		assert!(!crate::UserProfiles::<Test>::get(3).unwrap().session_required);
+	});
+}
+
+/// Have 2 require sessions and 1 send it a message with `send`, which must fail until the two
+/// confirm a session.
+fn assert_send_needs_a_session(send: impl Fn() -> DispatchResult) {
+	assert_ok!(Messaging::set_session_requirement(RuntimeOrigin::signed(2), true));
+	assert_noop!(send(), Error::<Test>::SessionRequired);
+
+	assert_ok!(Messaging::record_session(RuntimeOrigin::signed(1), 2, commitment(1), None));
+	assert_ok!(Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(1)));
+	assert_ok!(send());
+}
+
+#[test]
+fn paid_sends_need_a_confirmed_session() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		assert_ok!(Messaging::set_inbox_price(RuntimeOrigin::signed(2), Some(10)));
+		assert_send_needs_a_session(|| send_paid_next(1, 2));
+	});
+}
+
+#[test]
+fn accept_and_send_needs_a_confirmed_session() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		assert_send_needs_a_session(|| {
+			Messaging::accept_and_send(RuntimeOrigin::signed(1), 2, commitment(10))
+				.map(|_| ())
+				.map_err(|e| e.error)
+		});
+		assert!(crate::ApprovedContacts::<Test>::get(1, 2));
+	});
+}
+
+#[test]
+fn ephemeral_key_sends_need_a_confirmed_session() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		assert_ok!(publish(0));
+		assert_send_needs_a_session(|| send_under(0));
+	});
+}
+
+#[test]
+fn batched_sends_need_a_confirmed_session() {
+	new_test_ext().execute_with(|| {
+		setup_trio();
+		let send = || {
+			Messaging::send_message_hash_batch(
+				RuntimeOrigin::signed(1),
+				batch(vec![(3, 10), (2, 11)]),
+			)
+		};
+		assert_ok!(Messaging::set_session_requirement(RuntimeOrigin::signed(2), true));
+		assert_noop!(send(), failed(1, ItemError::CannotMessage(CanMessageError::SessionRequired)));
+
+		assert_ok!(Messaging::record_session(RuntimeOrigin::signed(1), 2, commitment(1), None));
+		assert_ok!(Messaging::confirm_session(RuntimeOrigin::signed(2), 1, commitment(1)));
+		assert_ok!(send());
+		assert_eq!(crate::NextMessageId::<Test>::get(), 2);
+	});
+}
+
+fn attestation(byte: u8) -> DeviceAttestation {
+	vec![byte; 64].try_into().unwrap()
+}
+
+fn add(key: u8) -> DispatchResult {
+	Messaging::add_device_key(RuntimeOrigin::signed(1), vec![key; 32], attestation(key))
+}
+
+fn keys() -> Vec<(u32, Vec<u8>)> {
+	DeviceSets::<Test>::get(1)
+		.map(|set| {
+			set.devices
+				.into_iter()
+				.map(|device| (device.device_id, device.public_key.into_inner()))
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+#[test]
+fn devices_are_capped_at_max_devices_per_account() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_noop!(
+			Messaging::add_device_key(RuntimeOrigin::signed(3), vec![1; 32], attestation(1)),
+			Error::<Test>::ProfileNotFound
+		);
+		assert_noop!(
+			Messaging::add_device_key(
+				RuntimeOrigin::signed(1),
+				vec![1; 32],
+				DeviceAttestation::default()
+			),
+			Error::<Test>::EmptyDeviceAttestation
+		);
+
+		assert_ok!(add(1));
+		System::assert_last_event(
+			Event::DeviceKeyAdded {
+				who: 1,
+				device_id: 0,
+				key_hash: Messaging::key_hash(&[1; 32]),
+				devices_revision: 1,
+			}
+			.into(),
+		);
+		assert_ok!(add(2));
+		assert_eq!(MaxDevicesPerAccount::get(), 2);
+		assert_noop!(add(3), Error::<Test>::TooManyDevices);
+
+		// Removing a device frees its slot, but not its ID
+		assert_ok!(Messaging::remove_device_key(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(
+			Event::DeviceKeyRemoved { who: 1, device_id: 0, devices_revision: 3 }.into(),
+		);
+		assert_noop!(
+			Messaging::remove_device_key(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::DeviceNotFound
+		);
+		assert_ok!(add(3));
+		assert_eq!(keys(), vec![(1, vec![2; 32]), (2, vec![3; 32])]);
+	});
+}
+
+#[test]
+fn replacing_a_key_keeps_its_device_id() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(add(1));
+		assert_ok!(add(2));
+
+		assert_ok!(Messaging::replace_device_key(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![9; 32],
+			attestation(9),
+		));
+		System::assert_last_event(
+			Event::DeviceKeyReplaced {
+				who: 1,
+				device_id: 0,
+				old_key_hash: Messaging::key_hash(&[1; 32]),
+				new_key_hash: Messaging::key_hash(&[9; 32]),
+				devices_revision: 3,
+			}
+			.into(),
+		);
+		assert_eq!(keys(), vec![(0, vec![9; 32]), (1, vec![2; 32])]);
+		assert_eq!(DeviceSets::<Test>::get(1).unwrap().devices[0].attestation, attestation(9));
+
+		// A full set can still be rolled, but only existing devices
+		assert_noop!(
+			Messaging::replace_device_key(
+				RuntimeOrigin::signed(1),
+				2,
+				vec![9; 32],
+				attestation(9)
+			),
+			Error::<Test>::DeviceNotFound
+		);
+		assert_noop!(
+			Messaging::replace_device_key(
+				RuntimeOrigin::signed(2),
+				0,
+				vec![9; 32],
+				attestation(9)
+			),
+			Error::<Test>::DeviceNotFound
+		);
+		assert_noop!(
+			Messaging::replace_device_key(RuntimeOrigin::signed(1), 0, vec![], attestation(9)),
+			Error::<Test>::InvalidPublicKey
+		);
+		assert_noop!(
+			Messaging::replace_device_key(
+				RuntimeOrigin::signed(1),
+				0,
+				vec![9; 257],
+				attestation(9)
+			),
+			Error::<Test>::PublicKeyTooLarge
+		);
+	});
+}
+
+#[test]
+fn devices_hold_a_deposit_until_removed() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(add(1));
+		assert_ok!(add(2));
+		assert_eq!(held(HoldReason::DeviceDeposit, 1), 2 * DeviceDeposit::get());
+
+		// Replacing a key keeps its deposit, removing the device releases it
+		assert_ok!(Messaging::replace_device_key(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![9; 32],
+			attestation(9),
+		));
+		assert_eq!(held(HoldReason::DeviceDeposit, 1), 2 * DeviceDeposit::get());
+		assert_ok!(Messaging::remove_device_key(RuntimeOrigin::signed(1), 0));
+		assert_eq!(held(HoldReason::DeviceDeposit, 1), DeviceDeposit::get());
+
+		// Removing the profile releases the rest
+		assert_ok!(Messaging::deregister_profile(RuntimeOrigin::signed(1)));
+		assert_eq!(held(HoldReason::DeviceDeposit, 1), 0);
+	});
+}
+
+#[test]
+fn device_deposit_beyond_balance_fails() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		assert_ok!(<Balances as Mutate<u64>>::set_balance(&1, DeviceDeposit::get() - 1));
+		assert_noop!(add(1), Error::<Test>::InsufficientDeviceDeposit);
+		assert_eq!(Messaging::devices_revision(1), 0);
+	});
+}
+
+#[test]
+fn every_device_change_bumps_the_revision() {
+	new_test_ext().execute_with(|| {
+		setup_pair();
+		let revision = || Messaging::devices_revision(1);
+		assert_eq!(revision(), 0);
+
+		assert_ok!(add(1));
+		assert_eq!(revision(), 1);
+		assert_ok!(Messaging::replace_device_key(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![2; 32],
+			attestation(2),
+		));
+		assert_eq!(revision(), 2);
+		assert_ok!(Messaging::remove_device_key(RuntimeOrigin::signed(1), 0));
+		assert_eq!(revision(), 3);
+
+		// Failed changes leave it alone
+		assert_noop!(
+			Messaging::remove_device_key(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::DeviceNotFound
+		);
+		assert_eq!(revision(), 3);
+
+		// Removing the profile drops its devices, bumping the revision once more
+		assert_ok!(add(3));
+		assert_ok!(Messaging::deregister_profile(RuntimeOrigin::signed(1)));
+		assert!(!DeviceSets::<Test>::contains_key(1));
+		assert_eq!(revision(), 5);
+		assert_eq!(Messaging::devices_revision(2), 0);
+
+		// Devices of a new profile continue from the old revision
+		assert_ok!(Messaging::register_profile(RuntimeOrigin::signed(1), vec![1, 2, 3, 4]));
+		assert_ok!(add(4));
+		assert_eq!(revision(), 6);
+		assert_eq!(keys(), vec![(0, vec![4; 32])]);
 	});
 }
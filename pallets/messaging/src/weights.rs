@@ -1,9 +1,19 @@
-//! Autogenerated weights for pallet_messaging
+//! Weights for pallet_messaging
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI
-//! DATE: 2024-01-01
-//! 
-//! These are placeholder weights. In production, run benchmarks to get accurate values.
+//! HAND-ESTIMATED. None of these weights were produced by the benchmark CLI. Execution
+//! times are reference values, and proof sizes sum the maximum encoded length of the storage
+//! items each call was read to access, so they may miss accesses added since. Every function
+//! is marked as such until the file is regenerated on reference hardware with:
+//!
+//! ```text
+//! ./target/release/secure-messaging-node benchmark pallet \
+//!     --chain dev \
+//!     --pallet pallet_messaging \
+//!     --extrinsic "*" \
+//!     --steps 50 \
+//!     --repeat 20 \
+//!     --output pallets/messaging/src/weights.rs
+//! ```
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -14,116 +24,1966 @@ use core::marker::PhantomData;
 
 /// Weight functions needed for pallet_messaging.
 pub trait WeightInfo {
-	fn register_profile() -> Weight;
-	fn update_profile() -> Weight;
+	fn register_profile(k: u32) -> Weight;
+	fn update_profile(k: u32) -> Weight;
 	fn send_message_hash() -> Weight;
+	fn send_message_hash_batch(n: u32) -> Weight;
 	fn approve_contact() -> Weight;
 	fn remove_contact() -> Weight;
 	fn challenge_spam() -> Weight;
 	fn refund_bond() -> Weight;
+	fn resolve_challenge() -> Weight;
+	fn top_up_bond() -> Weight;
+	fn withdraw_excess_bond() -> Weight;
+	fn appeal_challenge() -> Weight;
+	fn resolve_appeal() -> Weight;
+	fn finalize_challenge() -> Weight;
+	fn report_spam() -> Weight;
+	fn expire_challenge() -> Weight;
+	fn set_inbox_price() -> Weight;
+	fn send_paid_message_hash() -> Weight;
+	fn acknowledge_message() -> Weight;
+	fn retract_paid_message() -> Weight;
+	fn refund_inbox_payment() -> Weight;
+	fn pause() -> Weight;
+	fn unpause() -> Weight;
+	fn force_remove_message() -> Weight;
+	fn update_parameters() -> Weight;
+	fn add_moderator() -> Weight;
+	fn remove_moderator() -> Weight;
+	fn moderator_challenge() -> Weight;
+	fn fast_track_challenge() -> Weight;
+	fn freeze_sender() -> Weight;
+	fn unfreeze_sender() -> Weight;
+	fn ban_account() -> Weight;
+	fn unban_account() -> Weight;
+	fn reset_income() -> Weight;
+	fn force_reset_key(n: u32) -> Weight;
+	fn continue_key_reset_notices(n: u32) -> Weight;
+	fn prune_takedown() -> Weight;
+	fn set_registration_mode() -> Weight;
+	fn whitelist_account() -> Weight;
+	fn remove_from_whitelist() -> Weight;
+	fn force_register_profile(k: u32) -> Weight;
+	fn prune_expired_unsigned(n: u32) -> Weight;
+	fn request_remote_profile() -> Weight;
+	fn respond_profile() -> Weight;
+	fn receive_profile() -> Weight;
+	fn expire_profile_query() -> Weight;
+	fn accept_and_send() -> Weight;
+	fn register_and_approve(k: u32) -> Weight;
+	fn migrate_message_record() -> Weight;
+	fn create_group() -> Weight;
+	fn add_group_member() -> Weight;
+	fn remove_group_member() -> Weight;
+	fn promote_member() -> Weight;
+	fn demote_member() -> Weight;
+	fn transfer_group_ownership() -> Weight;
+	fn accept_group_ownership() -> Weight;
+	fn rotate_group_key() -> Weight;
+	fn send_group_message_hash() -> Weight;
+	fn leave_group(m: u32) -> Weight;
+	fn invite_to_group() -> Weight;
+	fn accept_group_invite() -> Weight;
+	fn decline_group_invite() -> Weight;
+	fn remove_expired_group_invite() -> Weight;
+	fn set_group_invite_only() -> Weight;
+	fn set_group_retention() -> Weight;
+	fn mute_member() -> Weight;
+	fn unmute_member() -> Weight;
+	fn set_posting_policy() -> Weight;
+	fn mute_group() -> Weight;
+	fn unmute_group() -> Weight;
+	fn ban_from_group() -> Weight;
+	fn lift_group_ban() -> Weight;
+	fn disband_group(m: u32, g: u32) -> Weight;
+	fn set_group_metadata() -> Weight;
+	fn clear_group_metadata() -> Weight;
+	fn request_to_join_group() -> Weight;
+	fn approve_join_request() -> Weight;
+	fn cancel_join_request() -> Weight;
+	fn force_remove_group_message() -> Weight;
+	fn appoint_moderator() -> Weight;
+	fn dismiss_moderator() -> Weight;
+	fn remove_group_message() -> Weight;
+	fn send_stealth_message_hash() -> Weight;
+	fn register_relay() -> Weight;
+	fn deregister_relay() -> Weight;
+	fn send_sealed_message_hash() -> Weight;
+	fn send_to_unregistered() -> Weight;
+	fn claim_pending_messages(n: u32) -> Weight;
+	fn contest_challenge() -> Weight;
+	fn reveal_evidence(e: u32) -> Weight;
+	fn send_anonymous_contact_message(p: u32) -> Weight;
+	fn publish_ephemeral_key(n: u32) -> Weight;
+	fn send_message_hash_with_ephemeral_key() -> Weight;
+	fn send_to_recipient_set(n: u32) -> Weight;
+	fn acknowledge_recipient_set_message() -> Weight;
+	fn commit_backup() -> Weight;
+	fn set_inbox_pricing() -> Weight;
+	fn refund_deposits_on_reply(n: u32) -> Weight;
+	fn purchase_credits() -> Weight;
+	fn refund_unused_credits() -> Weight;
+	fn sponsor_registration(k: u32) -> Weight;
+	fn assume_own_bond() -> Weight;
+	fn migrate_spam_bond() -> Weight;
+	fn rekey_entry() -> Weight;
+	fn prune_direct_messages(n: u32) -> Weight;
+	fn prune_group_messages(n: u32) -> Weight;
+	fn prune_index_entries(n: u32) -> Weight;
+	fn fund_reward_pool() -> Weight;
+	fn flag_inactive() -> Weight;
+	fn sweep_dormant_profile() -> Weight;
+	fn set_notification_hint() -> Weight;
+	fn set_compact_events() -> Weight;
+	fn record_session() -> Weight;
+	fn confirm_session() -> Weight;
+	fn terminate_session() -> Weight;
+	fn set_session_requirement() -> Weight;
+	fn add_device_key() -> Weight;
+	fn replace_device_key() -> Weight;
+	fn remove_device_key() -> Weight;
+	fn verify_message_on_chain() -> Weight;
+	fn deregister_profile() -> Weight;
+	fn force_deregister_profile() -> Weight;
+	fn verify_messages(n: u32) -> Weight;
 }
 
-/// Weights for pallet_messaging using the Substrate node and recommended hardware.
+/// Hand-estimated weights for pallet_messaging, pending benchmarks on reference hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
-	/// Storage: UserProfiles (r:1 w:1)
-	/// Storage: SpamBonds (r:0 w:1)
-	/// Proof: Currency reserve operation
-	fn register_profile() -> Weight {
-		Weight::from_parts(50_000_000, 0)
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `k` is `[1, 256]`.
+	fn register_profile(k: u32) -> Weight {
+		Weight::from_parts(50_000_000, 16_251)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `k` is `[1, 256]`.
+	fn update_profile(k: u32) -> Weight {
+		Weight::from_parts(30_000_000, 15_753)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_message_hash() -> Weight {
+		Weight::from_parts(53_000_000, 75_389)
+			.saturating_add(T::DbWeight::get().reads(31_u64))
+			.saturating_add(T::DbWeight::get().writes(20_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[1, 16]`.
+	fn send_message_hash_batch(n: u32) -> Weight {
+		Weight::from_parts(20_000_000, 33_689)
+			.saturating_add(Weight::from_parts(33_000_000, 41_830).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(16_u64))
+			.saturating_add(T::DbWeight::get().reads((16_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(13_u64))
+			.saturating_add(T::DbWeight::get().writes((7_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn approve_contact() -> Weight {
+		Weight::from_parts(35_000_000, 36_652)
+			.saturating_add(T::DbWeight::get().reads(14_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_contact() -> Weight {
+		Weight::from_parts(25_000_000, 26_095)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn challenge_spam() -> Weight {
+		Weight::from_parts(30_000_000, 19_726)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn refund_bond() -> Weight {
+		Weight::from_parts(30_000_000, 11_331)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn resolve_challenge() -> Weight {
+		Weight::from_parts(50_000_000, 27_995)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn top_up_bond() -> Weight {
+		Weight::from_parts(30_000_000, 9_033)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn withdraw_excess_bond() -> Weight {
+		Weight::from_parts(30_000_000, 16_453)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn appeal_challenge() -> Weight {
+		Weight::from_parts(30_000_000, 6_304)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn resolve_appeal() -> Weight {
+		Weight::from_parts(70_000_000, 33_157)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn finalize_challenge() -> Weight {
+		Weight::from_parts(40_000_000, 15_059)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn report_spam() -> Weight {
+		Weight::from_parts(35_000_000, 16_209)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn expire_challenge() -> Weight {
+		Weight::from_parts(30_000_000, 8_863)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_inbox_price() -> Weight {
+		Weight::from_parts(20_000_000, 2_836)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_paid_message_hash() -> Weight {
+		Weight::from_parts(57_000_000, 34_965)
+			.saturating_add(T::DbWeight::get().reads(26_u64))
+			.saturating_add(T::DbWeight::get().writes(12_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn acknowledge_message() -> Weight {
+		Weight::from_parts(40_000_000, 30_799)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn retract_paid_message() -> Weight {
+		Weight::from_parts(35_000_000, 22_185)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn refund_inbox_payment() -> Weight {
+		Weight::from_parts(30_000_000, 24_342)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn pause() -> Weight {
+		Weight::from_parts(15_000_000, 5_729)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unpause() -> Weight {
+		Weight::from_parts(15_000_000, 5_729)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn force_remove_message() -> Weight {
+		Weight::from_parts(35_000_000, 24_707)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(13_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn update_parameters() -> Weight {
+		Weight::from_parts(15_000_000, 5_795)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn add_moderator() -> Weight {
+		Weight::from_parts(20_000_000, 6_753)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_moderator() -> Weight {
+		Weight::from_parts(20_000_000, 6_753)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn moderator_challenge() -> Weight {
+		Weight::from_parts(25_000_000, 15_248)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn fast_track_challenge() -> Weight {
+		Weight::from_parts(50_000_000, 29_515)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn freeze_sender() -> Weight {
+		Weight::from_parts(20_000_000, 6_753)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unfreeze_sender() -> Weight {
+		Weight::from_parts(20_000_000, 9_312)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn ban_account() -> Weight {
+		Weight::from_parts(45_000_000, 26_473)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(12_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unban_account() -> Weight {
+		Weight::from_parts(15_000_000, 7_824)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn reset_income() -> Weight {
+		Weight::from_parts(12_000_000, 7_765)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
 			.saturating_add(T::DbWeight::get().writes(2_u64))
 	}
 
-	/// Storage: UserProfiles (r:1 w:1)
-	fn update_profile() -> Weight {
-		Weight::from_parts(30_000_000, 0)
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[0, MaxKeyResetNotifications]`.
+	fn force_reset_key(n: u32) -> Weight {
+		Weight::from_parts(40_000_000, 18_125)
+			.saturating_add(Weight::from_parts(5_000_000, 5_192).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[0, MaxKeyResetNotifications]`.
+	fn continue_key_reset_notices(n: u32) -> Weight {
+		Weight::from_parts(15_000_000, 2_813)
+			.saturating_add(Weight::from_parts(5_000_000, 5_192).saturating_mul(n.into()))
 			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(n.into())))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
 
-	/// Storage: UserProfiles (r:2 w:0)
-	/// Storage: SpamBonds (r:1 w:0)
-	/// Storage: MessageHashes (r:0 w:1)
-	/// Storage: NextMessageId (r:1 w:1)
-	fn send_message_hash() -> Weight {
-		Weight::from_parts(40_000_000, 0)
-			.saturating_add(T::DbWeight::get().reads(4_u64))
+	/// Hand-estimated, not benchmarked.
+	fn prune_takedown() -> Weight {
+		Weight::from_parts(15_000_000, 2_632)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_registration_mode() -> Weight {
+		Weight::from_parts(12_000_000, 5_233)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(2_u64))
 	}
 
-	/// Storage: UserProfiles (r:2 w:0)
-	/// Storage: ApprovedContacts (r:1 w:1)
-	/// Storage: ContactCount (r:1 w:1)
-	fn approve_contact() -> Weight {
-		Weight::from_parts(35_000_000, 0)
-			.saturating_add(T::DbWeight::get().reads(4_u64))
+	/// Hand-estimated, not benchmarked.
+	fn whitelist_account() -> Weight {
+		Weight::from_parts(15_000_000, 7_788)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
 			.saturating_add(T::DbWeight::get().writes(2_u64))
 	}
 
-	/// Storage: ApprovedContacts (r:1 w:1)
-	/// Storage: ContactCount (r:1 w:1)
-	fn remove_contact() -> Weight {
-		Weight::from_parts(25_000_000, 0)
+	/// Hand-estimated, not benchmarked.
+	fn remove_from_whitelist() -> Weight {
+		Weight::from_parts(15_000_000, 7_788)
 			.saturating_add(T::DbWeight::get().reads(2_u64))
 			.saturating_add(T::DbWeight::get().writes(2_u64))
 	}
 
-	/// Storage: MessageHashes (r:1 w:0)
-	fn challenge_spam() -> Weight {
-		Weight::from_parts(20_000_000, 0)
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `k` is `[1, 256]`.
+	fn force_register_profile(k: u32) -> Weight {
+		Weight::from_parts(65_000_000, 24_617)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[1, MaxPrunesPerCall]`.
+	fn prune_expired_unsigned(n: u32) -> Weight {
+		Weight::from_parts(14_000_000, 5_588)
+			.saturating_add(Weight::from_parts(6_000_000, 31_321).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().reads((10_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+			.saturating_add(T::DbWeight::get().writes((14_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn request_remote_profile() -> Weight {
+		Weight::from_parts(40_000_000, 3_094)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn respond_profile() -> Weight {
+		Weight::from_parts(35_000_000, 7_951)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn receive_profile() -> Weight {
+		Weight::from_parts(20_000_000, 3_180)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
 	}
 
-	/// Storage: SpamBonds (r:1 w:1)
-	/// Proof: Currency unreserve operation
-	fn refund_bond() -> Weight {
-		Weight::from_parts(30_000_000, 0)
+	/// Hand-estimated, not benchmarked.
+	fn expire_profile_query() -> Weight {
+		Weight::from_parts(15_000_000, 3_180)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
-}
 
-// For backwards compatibility and tests
-impl WeightInfo for () {
-	fn register_profile() -> Weight {
-		Weight::from_parts(50_000_000, 0)
-			.saturating_add(RocksDbWeight::get().reads(1_u64))
-			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	/// Hand-estimated, not benchmarked.
+	fn accept_and_send() -> Weight {
+		Weight::from_parts(75_000_000, 95_200)
+			.saturating_add(T::DbWeight::get().reads(38_u64))
+			.saturating_add(T::DbWeight::get().writes(22_u64))
 	}
 
-	fn update_profile() -> Weight {
-		Weight::from_parts(30_000_000, 0)
-			.saturating_add(RocksDbWeight::get().reads(1_u64))
-			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `k` is `[1, 256]`.
+	fn register_and_approve(k: u32) -> Weight {
+		Weight::from_parts(85_000_000, 47_352)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(T::DbWeight::get().reads(19_u64))
+			.saturating_add(T::DbWeight::get().writes(14_u64))
 	}
 
-	fn send_message_hash() -> Weight {
-		Weight::from_parts(40_000_000, 0)
-			.saturating_add(RocksDbWeight::get().reads(4_u64))
-			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	/// Hand-estimated, not benchmarked.
+	fn migrate_message_record() -> Weight {
+		Weight::from_parts(12_000_000, 3_665)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
 	}
 
-	fn approve_contact() -> Weight {
-		Weight::from_parts(35_000_000, 0)
-			.saturating_add(RocksDbWeight::get().reads(4_u64))
-			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	/// Hand-estimated, not benchmarked.
+	fn create_group() -> Weight {
+		Weight::from_parts(45_000_000, 14_584)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
 	}
 
-	fn remove_contact() -> Weight {
-		Weight::from_parts(25_000_000, 0)
-			.saturating_add(RocksDbWeight::get().reads(2_u64))
-			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	/// Hand-estimated, not benchmarked.
+	fn add_group_member() -> Weight {
+		Weight::from_parts(38_000_000, 35_006)
+			.saturating_add(T::DbWeight::get().reads(14_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
 	}
 
-	fn challenge_spam() -> Weight {
-		Weight::from_parts(20_000_000, 0)
-			.saturating_add(RocksDbWeight::get().reads(1_u64))
+	/// Hand-estimated, not benchmarked.
+	fn remove_group_member() -> Weight {
+		Weight::from_parts(28_000_000, 18_732)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
 	}
 
-	fn refund_bond() -> Weight {
-		Weight::from_parts(30_000_000, 0)
-			.saturating_add(RocksDbWeight::get().reads(1_u64))
-			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	/// Hand-estimated, not benchmarked.
+	fn promote_member() -> Weight {
+		Weight::from_parts(20_000_000, 5_452)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn demote_member() -> Weight {
+		Weight::from_parts(22_000_000, 8_015)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn transfer_group_ownership() -> Weight {
+		Weight::from_parts(18_000_000, 5_452)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn accept_group_ownership() -> Weight {
+		Weight::from_parts(55_000_000, 21_747)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn rotate_group_key() -> Weight {
+		Weight::from_parts(30_000_000, 10_112)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_group_message_hash() -> Weight {
+		Weight::from_parts(35_000_000, 26_836)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `m` is `[0, MaxGroupMembers]`.
+	fn leave_group(m: u32) -> Weight {
+		Weight::from_parts(45_000_000, 21_722)
+			.saturating_add(Weight::from_parts(3_000_000, 2_584).saturating_mul(m.into()))
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(m.into())))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn invite_to_group() -> Weight {
+		Weight::from_parts(35_000_000, 23_787)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn accept_group_invite() -> Weight {
+		Weight::from_parts(38_000_000, 31_586)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn decline_group_invite() -> Weight {
+		Weight::from_parts(20_000_000, 7_677)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_expired_group_invite() -> Weight {
+		Weight::from_parts(20_000_000, 7_677)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_group_invite_only() -> Weight {
+		Weight::from_parts(18_000_000, 2_868)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_group_retention() -> Weight {
+		Weight::from_parts(16_000_000, 5_371)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn mute_member() -> Weight {
+		Weight::from_parts(20_000_000, 17_837)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unmute_member() -> Weight {
+		Weight::from_parts(22_000_000, 20_420)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_posting_policy() -> Weight {
+		Weight::from_parts(16_000_000, 5_364)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn mute_group() -> Weight {
+		Weight::from_parts(17_000_000, 5_159)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unmute_group() -> Weight {
+		Weight::from_parts(15_000_000, 2_575)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn ban_from_group() -> Weight {
+		Weight::from_parts(45_000_000, 31_583)
+			.saturating_add(T::DbWeight::get().reads(12_u64))
+			.saturating_add(T::DbWeight::get().writes(12_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn lift_group_ban() -> Weight {
+		Weight::from_parts(20_000_000, 10_602)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// The range of component `m` is `[2, MaxDisbandRemovalsPerCall]`.
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `g` is `[0, MaxDisbandRemovalsPerCall]`.
+	fn disband_group(m: u32, g: u32) -> Weight {
+		Weight::from_parts(40_000_000, 32_804)
+			.saturating_add(Weight::from_parts(6_000_000, 5_143).saturating_mul(m.into()))
+			.saturating_add(Weight::from_parts(4_000_000, 2_639).saturating_mul(g.into()))
+			.saturating_add(T::DbWeight::get().reads(13_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(m.into())))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(g.into())))
+			.saturating_add(T::DbWeight::get().writes(18_u64))
+			.saturating_add(T::DbWeight::get().writes((4_u64).saturating_mul(m.into())))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(g.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_group_metadata() -> Weight {
+		Weight::from_parts(40_000_000, 14_150)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn clear_group_metadata() -> Weight {
+		Weight::from_parts(35_000_000, 14_150)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn request_to_join_group() -> Weight {
+		Weight::from_parts(30_000_000, 21_111)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn approve_join_request() -> Weight {
+		Weight::from_parts(40_000_000, 41_971)
+			.saturating_add(T::DbWeight::get().reads(14_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn cancel_join_request() -> Weight {
+		Weight::from_parts(20_000_000, 17_471)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn force_remove_group_message() -> Weight {
+		Weight::from_parts(30_000_000, 10_379)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn appoint_moderator() -> Weight {
+		Weight::from_parts(18_000_000, 5_452)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn dismiss_moderator() -> Weight {
+		Weight::from_parts(18_000_000, 5_452)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_group_message() -> Weight {
+		Weight::from_parts(30_000_000, 22_979)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_stealth_message_hash() -> Weight {
+		Weight::from_parts(45_000_000, 24_360)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn register_relay() -> Weight {
+		Weight::from_parts(30_000_000, 13_304)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn deregister_relay() -> Weight {
+		Weight::from_parts(28_000_000, 10_713)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_sealed_message_hash() -> Weight {
+		Weight::from_parts(35_000_000, 34_852)
+			.saturating_add(T::DbWeight::get().reads(15_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_to_unregistered() -> Weight {
+		Weight::from_parts(45_000_000, 24_360)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[1, MaxClaimsPerCall]`.
+	fn claim_pending_messages(n: u32) -> Weight {
+		Weight::from_parts(15_000_000, 8_960)
+			.saturating_add(Weight::from_parts(25_000_000, 23_809).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().reads((9_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+			.saturating_add(T::DbWeight::get().writes((8_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn contest_challenge() -> Weight {
+		Weight::from_parts(15_000_000, 6_278)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `e` is `[1, 1024]`.
+	fn reveal_evidence(e: u32) -> Weight {
+		Weight::from_parts(30_000_000, 16_943)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(e.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `p` is `[1, 2048]`.
+	fn send_anonymous_contact_message(p: u32) -> Weight {
+		Weight::from_parts(35_000_000, 37_407)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(16_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[1, MaxEphemeralKeys]`.
+	fn publish_ephemeral_key(n: u32) -> Weight {
+		Weight::from_parts(20_000_000, 8_502)
+			.saturating_add(Weight::from_parts(3_000_000, 2_572).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_message_hash_with_ephemeral_key() -> Weight {
+		Weight::from_parts(45_000_000, 61_429)
+			.saturating_add(T::DbWeight::get().reads(25_u64))
+			.saturating_add(T::DbWeight::get().writes(17_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[1, 8]`.
+	fn send_to_recipient_set(n: u32) -> Weight {
+		Weight::from_parts(40_000_000, 24_914)
+			.saturating_add(Weight::from_parts(10_000_000, 13_299).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn acknowledge_recipient_set_message() -> Weight {
+		Weight::from_parts(25_000_000, 30_568)
+			.saturating_add(T::DbWeight::get().reads(12_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn commit_backup() -> Weight {
+		Weight::from_parts(25_000_000, 13_749)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_inbox_pricing() -> Weight {
+		Weight::from_parts(20_000_000, 2_836)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[0, 8]`.
+	fn refund_deposits_on_reply(n: u32) -> Weight {
+		Weight::from_parts(5_000_000, 8_828)
+			.saturating_add(Weight::from_parts(15_000_000, 21_740).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().reads((8_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((8_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn purchase_credits() -> Weight {
+		Weight::from_parts(40_000_000, 13_560)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn refund_unused_credits() -> Weight {
+		Weight::from_parts(25_000_000, 8_133)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `k` is `[1, 256]`.
+	fn sponsor_registration(k: u32) -> Weight {
+		Weight::from_parts(95_000_000, 29_072)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(T::DbWeight::get().reads(15_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn assume_own_bond() -> Weight {
+		Weight::from_parts(45_000_000, 16_311)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn migrate_spam_bond() -> Weight {
+		Weight::from_parts(10_000_000, 2_604)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn rekey_entry() -> Weight {
+		Weight::from_parts(12_000_000, 2_700)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[0, MaxPrunesPerCall]`.
+	fn prune_direct_messages(n: u32) -> Weight {
+		Weight::from_parts(9_000_000, 2_570)
+			.saturating_add(Weight::from_parts(9_000_000, 15_286).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().reads((6_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+			.saturating_add(T::DbWeight::get().writes((11_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[0, MaxPrunesPerCall]`.
+	fn prune_group_messages(n: u32) -> Weight {
+		Weight::from_parts(6_000_000, 1_006)
+			.saturating_add(Weight::from_parts(7_000_000, 7_649).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((4_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[0, MaxPrunesPerCall]`.
+	fn prune_index_entries(n: u32) -> Weight {
+		Weight::from_parts(8_000_000, 2_071)
+			.saturating_add(Weight::from_parts(4_000_000, 7_781).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn fund_reward_pool() -> Weight {
+		Weight::from_parts(35_000_000, 5_206)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn flag_inactive() -> Weight {
+		Weight::from_parts(20_000_000, 5_423)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn sweep_dormant_profile() -> Weight {
+		Weight::from_parts(45_000_000, 35_858)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(13_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_notification_hint() -> Weight {
+		Weight::from_parts(20_000_000, 5_424)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_compact_events() -> Weight {
+		Weight::from_parts(15_000_000, 7_950)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn record_session() -> Weight {
+		Weight::from_parts(30_000_000, 14_510)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn confirm_session() -> Weight {
+		Weight::from_parts(18_000_000, 5_208)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn terminate_session() -> Weight {
+		Weight::from_parts(15_000_000, 2_617)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_session_requirement() -> Weight {
+		Weight::from_parts(15_000_000, 5_427)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn add_device_key() -> Weight {
+		Weight::from_parts(25_000_000, 14_791)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn replace_device_key() -> Weight {
+		Weight::from_parts(25_000_000, 11_955)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_device_key() -> Weight {
+		Weight::from_parts(18_000_000, 8_868)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn verify_message_on_chain() -> Weight {
+		Weight::from_parts(35_000_000, 21_365)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn deregister_profile() -> Weight {
+		Weight::from_parts(40_000_000, 28_038)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(13_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn force_deregister_profile() -> Weight {
+		Weight::from_parts(45_000_000, 33_271)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(14_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	/// The range of component `n` is `[1, 256]`.
+	fn verify_messages(n: u32) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(4_000_000, 7_798).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+	}
+}
+
+// For tests only. Runtimes should use `SubstrateWeight`.
+impl WeightInfo for () {
+	/// Hand-estimated, not benchmarked.
+	fn register_profile(k: u32) -> Weight {
+		Weight::from_parts(50_000_000, 16_251)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn update_profile(k: u32) -> Weight {
+		Weight::from_parts(30_000_000, 15_753)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_message_hash() -> Weight {
+		Weight::from_parts(53_000_000, 75_389)
+			.saturating_add(RocksDbWeight::get().reads(31_u64))
+			.saturating_add(RocksDbWeight::get().writes(20_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_message_hash_batch(n: u32) -> Weight {
+		Weight::from_parts(20_000_000, 33_689)
+			.saturating_add(Weight::from_parts(33_000_000, 41_830).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(16_u64))
+			.saturating_add(RocksDbWeight::get().reads((16_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(13_u64))
+			.saturating_add(RocksDbWeight::get().writes((7_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn approve_contact() -> Weight {
+		Weight::from_parts(35_000_000, 36_652)
+			.saturating_add(RocksDbWeight::get().reads(14_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_contact() -> Weight {
+		Weight::from_parts(25_000_000, 26_095)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn challenge_spam() -> Weight {
+		Weight::from_parts(30_000_000, 19_726)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn refund_bond() -> Weight {
+		Weight::from_parts(30_000_000, 11_331)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn resolve_challenge() -> Weight {
+		Weight::from_parts(50_000_000, 27_995)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn top_up_bond() -> Weight {
+		Weight::from_parts(30_000_000, 9_033)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn withdraw_excess_bond() -> Weight {
+		Weight::from_parts(30_000_000, 16_453)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn appeal_challenge() -> Weight {
+		Weight::from_parts(30_000_000, 6_304)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn resolve_appeal() -> Weight {
+		Weight::from_parts(70_000_000, 33_157)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn finalize_challenge() -> Weight {
+		Weight::from_parts(40_000_000, 15_059)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn report_spam() -> Weight {
+		Weight::from_parts(35_000_000, 16_209)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn expire_challenge() -> Weight {
+		Weight::from_parts(30_000_000, 8_863)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_inbox_price() -> Weight {
+		Weight::from_parts(20_000_000, 2_836)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_paid_message_hash() -> Weight {
+		Weight::from_parts(57_000_000, 34_965)
+			.saturating_add(RocksDbWeight::get().reads(26_u64))
+			.saturating_add(RocksDbWeight::get().writes(12_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn acknowledge_message() -> Weight {
+		Weight::from_parts(40_000_000, 30_799)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn retract_paid_message() -> Weight {
+		Weight::from_parts(35_000_000, 22_185)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn refund_inbox_payment() -> Weight {
+		Weight::from_parts(30_000_000, 24_342)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn pause() -> Weight {
+		Weight::from_parts(15_000_000, 5_729)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unpause() -> Weight {
+		Weight::from_parts(15_000_000, 5_729)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn force_remove_message() -> Weight {
+		Weight::from_parts(35_000_000, 24_707)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(13_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn update_parameters() -> Weight {
+		Weight::from_parts(15_000_000, 5_795)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn add_moderator() -> Weight {
+		Weight::from_parts(20_000_000, 6_753)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_moderator() -> Weight {
+		Weight::from_parts(20_000_000, 6_753)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn moderator_challenge() -> Weight {
+		Weight::from_parts(25_000_000, 15_248)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn fast_track_challenge() -> Weight {
+		Weight::from_parts(50_000_000, 29_515)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn freeze_sender() -> Weight {
+		Weight::from_parts(20_000_000, 6_753)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unfreeze_sender() -> Weight {
+		Weight::from_parts(20_000_000, 9_312)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn ban_account() -> Weight {
+		Weight::from_parts(45_000_000, 26_473)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(12_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unban_account() -> Weight {
+		Weight::from_parts(15_000_000, 7_824)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn reset_income() -> Weight {
+		Weight::from_parts(12_000_000, 7_765)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn force_reset_key(n: u32) -> Weight {
+		Weight::from_parts(40_000_000, 18_125)
+			.saturating_add(Weight::from_parts(5_000_000, 5_192).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn continue_key_reset_notices(n: u32) -> Weight {
+		Weight::from_parts(15_000_000, 2_813)
+			.saturating_add(Weight::from_parts(5_000_000, 5_192).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn prune_takedown() -> Weight {
+		Weight::from_parts(15_000_000, 2_632)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_registration_mode() -> Weight {
+		Weight::from_parts(12_000_000, 5_233)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn whitelist_account() -> Weight {
+		Weight::from_parts(15_000_000, 7_788)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_from_whitelist() -> Weight {
+		Weight::from_parts(15_000_000, 7_788)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn force_register_profile(k: u32) -> Weight {
+		Weight::from_parts(65_000_000, 24_617)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn prune_expired_unsigned(n: u32) -> Weight {
+		Weight::from_parts(14_000_000, 5_588)
+			.saturating_add(Weight::from_parts(6_000_000, 31_321).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().reads((10_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+			.saturating_add(RocksDbWeight::get().writes((14_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn request_remote_profile() -> Weight {
+		Weight::from_parts(40_000_000, 3_094)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn respond_profile() -> Weight {
+		Weight::from_parts(35_000_000, 7_951)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn receive_profile() -> Weight {
+		Weight::from_parts(20_000_000, 3_180)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn expire_profile_query() -> Weight {
+		Weight::from_parts(15_000_000, 3_180)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn accept_and_send() -> Weight {
+		Weight::from_parts(75_000_000, 95_200)
+			.saturating_add(RocksDbWeight::get().reads(38_u64))
+			.saturating_add(RocksDbWeight::get().writes(22_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn register_and_approve(k: u32) -> Weight {
+		Weight::from_parts(85_000_000, 47_352)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(RocksDbWeight::get().reads(19_u64))
+			.saturating_add(RocksDbWeight::get().writes(14_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn migrate_message_record() -> Weight {
+		Weight::from_parts(12_000_000, 3_665)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn create_group() -> Weight {
+		Weight::from_parts(45_000_000, 14_584)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn add_group_member() -> Weight {
+		Weight::from_parts(38_000_000, 35_006)
+			.saturating_add(RocksDbWeight::get().reads(14_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_group_member() -> Weight {
+		Weight::from_parts(28_000_000, 18_732)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn promote_member() -> Weight {
+		Weight::from_parts(20_000_000, 5_452)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn demote_member() -> Weight {
+		Weight::from_parts(22_000_000, 8_015)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn transfer_group_ownership() -> Weight {
+		Weight::from_parts(18_000_000, 5_452)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn accept_group_ownership() -> Weight {
+		Weight::from_parts(55_000_000, 21_747)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn rotate_group_key() -> Weight {
+		Weight::from_parts(30_000_000, 10_112)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_group_message_hash() -> Weight {
+		Weight::from_parts(35_000_000, 26_836)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn leave_group(m: u32) -> Weight {
+		Weight::from_parts(45_000_000, 21_722)
+			.saturating_add(Weight::from_parts(3_000_000, 2_584).saturating_mul(m.into()))
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(m.into())))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn invite_to_group() -> Weight {
+		Weight::from_parts(35_000_000, 23_787)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn accept_group_invite() -> Weight {
+		Weight::from_parts(38_000_000, 31_586)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn decline_group_invite() -> Weight {
+		Weight::from_parts(20_000_000, 7_677)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_expired_group_invite() -> Weight {
+		Weight::from_parts(20_000_000, 7_677)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_group_invite_only() -> Weight {
+		Weight::from_parts(18_000_000, 2_868)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_group_retention() -> Weight {
+		Weight::from_parts(16_000_000, 5_371)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn mute_member() -> Weight {
+		Weight::from_parts(20_000_000, 17_837)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unmute_member() -> Weight {
+		Weight::from_parts(22_000_000, 20_420)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_posting_policy() -> Weight {
+		Weight::from_parts(16_000_000, 5_364)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn mute_group() -> Weight {
+		Weight::from_parts(17_000_000, 5_159)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn unmute_group() -> Weight {
+		Weight::from_parts(15_000_000, 2_575)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn ban_from_group() -> Weight {
+		Weight::from_parts(45_000_000, 31_583)
+			.saturating_add(RocksDbWeight::get().reads(12_u64))
+			.saturating_add(RocksDbWeight::get().writes(12_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn lift_group_ban() -> Weight {
+		Weight::from_parts(20_000_000, 10_602)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn disband_group(m: u32, g: u32) -> Weight {
+		Weight::from_parts(40_000_000, 32_804)
+			.saturating_add(Weight::from_parts(6_000_000, 5_143).saturating_mul(m.into()))
+			.saturating_add(Weight::from_parts(4_000_000, 2_639).saturating_mul(g.into()))
+			.saturating_add(RocksDbWeight::get().reads(13_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(m.into())))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(g.into())))
+			.saturating_add(RocksDbWeight::get().writes(18_u64))
+			.saturating_add(RocksDbWeight::get().writes((4_u64).saturating_mul(m.into())))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(g.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_group_metadata() -> Weight {
+		Weight::from_parts(40_000_000, 14_150)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn clear_group_metadata() -> Weight {
+		Weight::from_parts(35_000_000, 14_150)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn request_to_join_group() -> Weight {
+		Weight::from_parts(30_000_000, 21_111)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn approve_join_request() -> Weight {
+		Weight::from_parts(40_000_000, 41_971)
+			.saturating_add(RocksDbWeight::get().reads(14_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn cancel_join_request() -> Weight {
+		Weight::from_parts(20_000_000, 17_471)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn force_remove_group_message() -> Weight {
+		Weight::from_parts(30_000_000, 10_379)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn appoint_moderator() -> Weight {
+		Weight::from_parts(18_000_000, 5_452)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn dismiss_moderator() -> Weight {
+		Weight::from_parts(18_000_000, 5_452)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_group_message() -> Weight {
+		Weight::from_parts(30_000_000, 22_979)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_stealth_message_hash() -> Weight {
+		Weight::from_parts(45_000_000, 24_360)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn register_relay() -> Weight {
+		Weight::from_parts(30_000_000, 13_304)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn deregister_relay() -> Weight {
+		Weight::from_parts(28_000_000, 10_713)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_sealed_message_hash() -> Weight {
+		Weight::from_parts(35_000_000, 34_852)
+			.saturating_add(RocksDbWeight::get().reads(15_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_to_unregistered() -> Weight {
+		Weight::from_parts(45_000_000, 24_360)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn claim_pending_messages(n: u32) -> Weight {
+		Weight::from_parts(15_000_000, 8_960)
+			.saturating_add(Weight::from_parts(25_000_000, 23_809).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().reads((9_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+			.saturating_add(RocksDbWeight::get().writes((8_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn contest_challenge() -> Weight {
+		Weight::from_parts(15_000_000, 6_278)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn reveal_evidence(e: u32) -> Weight {
+		Weight::from_parts(30_000_000, 16_943)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(e.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_anonymous_contact_message(p: u32) -> Weight {
+		Weight::from_parts(35_000_000, 37_407)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(p.into()))
+			.saturating_add(RocksDbWeight::get().reads(16_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn publish_ephemeral_key(n: u32) -> Weight {
+		Weight::from_parts(20_000_000, 8_502)
+			.saturating_add(Weight::from_parts(3_000_000, 2_572).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_message_hash_with_ephemeral_key() -> Weight {
+		Weight::from_parts(45_000_000, 61_429)
+			.saturating_add(RocksDbWeight::get().reads(25_u64))
+			.saturating_add(RocksDbWeight::get().writes(17_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn send_to_recipient_set(n: u32) -> Weight {
+		Weight::from_parts(40_000_000, 24_914)
+			.saturating_add(Weight::from_parts(10_000_000, 13_299).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn acknowledge_recipient_set_message() -> Weight {
+		Weight::from_parts(25_000_000, 30_568)
+			.saturating_add(RocksDbWeight::get().reads(12_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn commit_backup() -> Weight {
+		Weight::from_parts(25_000_000, 13_749)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_inbox_pricing() -> Weight {
+		Weight::from_parts(20_000_000, 2_836)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn refund_deposits_on_reply(n: u32) -> Weight {
+		Weight::from_parts(5_000_000, 8_828)
+			.saturating_add(Weight::from_parts(15_000_000, 21_740).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().reads((8_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((8_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn purchase_credits() -> Weight {
+		Weight::from_parts(40_000_000, 13_560)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn refund_unused_credits() -> Weight {
+		Weight::from_parts(25_000_000, 8_133)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn sponsor_registration(k: u32) -> Weight {
+		Weight::from_parts(95_000_000, 29_072)
+			.saturating_add(Weight::from_parts(1_450, 0).saturating_mul(k.into()))
+			.saturating_add(RocksDbWeight::get().reads(15_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn assume_own_bond() -> Weight {
+		Weight::from_parts(45_000_000, 16_311)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn migrate_spam_bond() -> Weight {
+		Weight::from_parts(10_000_000, 2_604)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn rekey_entry() -> Weight {
+		Weight::from_parts(12_000_000, 2_700)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn prune_direct_messages(n: u32) -> Weight {
+		Weight::from_parts(9_000_000, 2_570)
+			.saturating_add(Weight::from_parts(9_000_000, 15_286).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().reads((6_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+			.saturating_add(RocksDbWeight::get().writes((11_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn prune_group_messages(n: u32) -> Weight {
+		Weight::from_parts(6_000_000, 1_006)
+			.saturating_add(Weight::from_parts(7_000_000, 7_649).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((4_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn prune_index_entries(n: u32) -> Weight {
+		Weight::from_parts(8_000_000, 2_071)
+			.saturating_add(Weight::from_parts(4_000_000, 7_781).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn fund_reward_pool() -> Weight {
+		Weight::from_parts(35_000_000, 5_206)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn flag_inactive() -> Weight {
+		Weight::from_parts(20_000_000, 5_423)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn sweep_dormant_profile() -> Weight {
+		Weight::from_parts(45_000_000, 35_858)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(13_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_notification_hint() -> Weight {
+		Weight::from_parts(20_000_000, 5_424)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_compact_events() -> Weight {
+		Weight::from_parts(15_000_000, 7_950)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn record_session() -> Weight {
+		Weight::from_parts(30_000_000, 14_510)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn confirm_session() -> Weight {
+		Weight::from_parts(18_000_000, 5_208)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn terminate_session() -> Weight {
+		Weight::from_parts(15_000_000, 2_617)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn set_session_requirement() -> Weight {
+		Weight::from_parts(15_000_000, 5_427)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn add_device_key() -> Weight {
+		Weight::from_parts(25_000_000, 14_791)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn replace_device_key() -> Weight {
+		Weight::from_parts(25_000_000, 11_955)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn remove_device_key() -> Weight {
+		Weight::from_parts(18_000_000, 8_868)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn verify_message_on_chain() -> Weight {
+		Weight::from_parts(35_000_000, 21_365)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn deregister_profile() -> Weight {
+		Weight::from_parts(40_000_000, 28_038)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(13_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn force_deregister_profile() -> Weight {
+		Weight::from_parts(45_000_000, 33_271)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(14_u64))
+	}
+
+	/// Hand-estimated, not benchmarked.
+	fn verify_messages(n: u32) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(4_000_000, 7_798).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
 	}
 }
\ No newline at end of file
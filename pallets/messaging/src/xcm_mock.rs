@@ -0,0 +1,466 @@
+//! Two parachains running the messaging pallet, connected through `xcm-simulator`.
+
+use frame_support::traits::{Get, OnGenesis};
+use sp_runtime::{AccountId32, BuildStorage};
+use xcm_simulator::{decl_test_network, decl_test_parachain, decl_test_relay_chain, ParaId};
+
+pub const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
+pub const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+pub const CHARLIE: AccountId32 = AccountId32::new([3u8; 32]);
+
+decl_test_parachain! {
+	pub struct ParaA {
+		Runtime = parachain::Runtime,
+		XcmpMessageHandler = parachain::MsgQueue,
+		DmpMessageHandler = parachain::MsgQueue,
+		new_ext = para_ext(1),
+	}
+}
+
+decl_test_parachain! {
+	pub struct ParaB {
+		Runtime = parachain::Runtime,
+		XcmpMessageHandler = parachain::MsgQueue,
+		DmpMessageHandler = parachain::MsgQueue,
+		new_ext = para_ext(2),
+	}
+}
+
+decl_test_relay_chain! {
+	pub struct Relay {
+		Runtime = relay_chain::Runtime,
+		RuntimeCall = relay_chain::RuntimeCall,
+		RuntimeEvent = relay_chain::RuntimeEvent,
+		XcmConfig = relay_chain::XcmConfig,
+		MessageQueue = relay_chain::MessageQueue,
+		System = relay_chain::System,
+		new_ext = relay_ext(),
+	}
+}
+
+decl_test_network! {
+	pub struct MockNet {
+		relay_chain = Relay,
+		parachains = vec![
+			(1, ParaA),
+			(2, ParaB),
+		],
+	}
+}
+
+pub fn para_ext(para_id: u32) -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::<parachain::Runtime>::default().build_storage().unwrap();
+
+	pallet_balances::GenesisConfig::<parachain::Runtime> {
+		balances: vec![(ALICE, 1000), (BOB, 1000), (CHARLIE, 1000)],
+		dev_accounts: None,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		parachain::System::set_block_number(1);
+		parachain::MsgQueue::set_para_id(ParaId::from(para_id));
+		// Genesis records the in-code storage version, as on a real chain
+		<parachain::Messaging as OnGenesis>::on_genesis();
+	});
+	ext
+}
+
+pub fn relay_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::<relay_chain::Runtime>::default().build_storage().unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| relay_chain::System::set_block_number(1));
+	ext
+}
+
+/// Location of parachain `id` as seen from a sibling.
+pub fn sibling(id: u32) -> xcm::latest::Location {
+	xcm::latest::Location::new(1, [xcm::latest::Junction::Parachain(id)])
+}
+
+pub mod parachain {
+	use super::*;
+	use crate as pallet_messaging;
+	use frame_support::{
+		derive_impl, parameter_types,
+		traits::{ConstBool, ConstU128, ConstU32, ConstU64, Disabled, Everything, Nothing},
+		weights::Weight,
+		PalletId,
+	};
+	use frame_system::EnsureRoot;
+	use sp_runtime::{traits::IdentityLookup, Perbill};
+	use xcm::latest::prelude::*;
+	use xcm_builder::{
+		AccountId32Aliases, AllowUnpaidExecutionFrom, EnsureXcmOrigin, FixedWeightBounds,
+		FrameTransactionalProcessor, SignedAccountId32AsNative, SignedToAccountId32,
+		XcmPassthrough,
+	};
+	use xcm_executor::XcmExecutor;
+	use xcm_simulator::mock_message_queue;
+
+	pub type AccountId = AccountId32;
+	pub type Balance = u128;
+	type Block = frame_system::mocking::MockBlock<Runtime>;
+
+	#[frame_support::runtime]
+	mod runtime {
+		#[runtime::runtime]
+		#[runtime::derive(
+			RuntimeCall,
+			RuntimeEvent,
+			RuntimeError,
+			RuntimeOrigin,
+			RuntimeFreezeReason,
+			RuntimeHoldReason,
+			RuntimeSlashReason,
+			RuntimeLockId,
+			RuntimeTask,
+			RuntimeViewFunction
+		)]
+		pub struct Runtime;
+
+		#[runtime::pallet_index(0)]
+		pub type System = frame_system;
+
+		#[runtime::pallet_index(1)]
+		pub type Balances = pallet_balances;
+
+		#[runtime::pallet_index(2)]
+		pub type Timestamp = pallet_timestamp;
+
+		#[runtime::pallet_index(3)]
+		pub type MsgQueue = mock_message_queue;
+
+		#[runtime::pallet_index(4)]
+		pub type PolkadotXcm = pallet_xcm;
+
+		#[runtime::pallet_index(5)]
+		pub type Messaging = pallet_messaging;
+	}
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+	impl frame_system::Config for Runtime {
+		type Block = Block;
+		type AccountId = AccountId;
+		type Lookup = IdentityLookup<AccountId>;
+		type AccountData = pallet_balances::AccountData<Balance>;
+	}
+
+	#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+	impl pallet_balances::Config for Runtime {
+		type Balance = Balance;
+		type ExistentialDeposit = ConstU128<1>;
+		type AccountStore = System;
+	}
+
+	impl pallet_timestamp::Config for Runtime {
+		type Moment = u64;
+		type OnTimestampSet = ();
+		type MinimumPeriod = ConstU64<5>;
+		type WeightInfo = ();
+	}
+
+	parameter_types! {
+		pub const RelayNetwork: NetworkId = NetworkId::Kusama;
+		pub UniversalLocation: InteriorLocation =
+			[GlobalConsensus(RelayNetwork::get()), Parachain(MsgQueue::get().into())].into();
+		pub const UnitWeightCost: Weight = Weight::from_parts(1_000, 1_000);
+		pub const MaxInstructions: u32 = 100;
+		pub const MaxAssetsIntoHolding: u32 = 64;
+	}
+
+	pub type LocationToAccountId = AccountId32Aliases<RelayNetwork, AccountId>;
+	pub type LocalOriginToLocation = SignedToAccountId32<RuntimeOrigin, AccountId, RelayNetwork>;
+	pub type XcmRouter = super::ParachainXcmRouter<MsgQueue>;
+
+	pub struct XcmConfig;
+	impl xcm_executor::Config for XcmConfig {
+		type RuntimeCall = RuntimeCall;
+		type XcmSender = XcmRouter;
+		type AssetTransactor = ();
+		type OriginConverter = (
+			SignedAccountId32AsNative<RelayNetwork, RuntimeOrigin>,
+			XcmPassthrough<RuntimeOrigin>,
+		);
+		type IsReserve = ();
+		type IsTeleporter = ();
+		type UniversalLocation = UniversalLocation;
+		type Barrier = AllowUnpaidExecutionFrom<Everything>;
+		type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+		type Trader = ();
+		type ResponseHandler = ();
+		type AssetTrap = ();
+		type AssetLocker = ();
+		type AssetExchanger = ();
+		type AssetClaims = ();
+		type SubscriptionService = ();
+		type PalletInstancesInfo = ();
+		type FeeManager = ();
+		type MaxAssetsIntoHolding = MaxAssetsIntoHolding;
+		type MessageExporter = ();
+		type UniversalAliases = Nothing;
+		type CallDispatcher = RuntimeCall;
+		type SafeCallFilter = Everything;
+		type Aliasers = Nothing;
+		type TransactionalProcessor = FrameTransactionalProcessor;
+		type HrmpNewChannelOpenRequestHandler = ();
+		type HrmpChannelAcceptedHandler = ();
+		type HrmpChannelClosingHandler = ();
+		type XcmRecorder = ();
+	}
+
+	impl mock_message_queue::Config for Runtime {
+		type RuntimeEvent = RuntimeEvent;
+		type XcmExecutor = XcmExecutor<XcmConfig>;
+	}
+
+	impl pallet_xcm::Config for Runtime {
+		type RuntimeEvent = RuntimeEvent;
+		type SendXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>;
+		type XcmRouter = XcmRouter;
+		type ExecuteXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>;
+		type XcmExecuteFilter = Everything;
+		type XcmExecutor = XcmExecutor<XcmConfig>;
+		type XcmTeleportFilter = Nothing;
+		type XcmReserveTransferFilter = Nothing;
+		type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+		type UniversalLocation = UniversalLocation;
+		type RuntimeOrigin = RuntimeOrigin;
+		type RuntimeCall = RuntimeCall;
+		const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
+		type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
+		type Currency = Balances;
+		type CurrencyMatcher = ();
+		type TrustedLockers = ();
+		type SovereignAccountOf = LocationToAccountId;
+		type MaxLockers = ConstU32<8>;
+		type MaxRemoteLockConsumers = ConstU32<0>;
+		type RemoteLockConsumerIdentifier = ();
+		type WeightInfo = pallet_xcm::TestWeightInfo;
+		type AdminOrigin = EnsureRoot<AccountId>;
+		type AuthorizedAliasConsideration = Disabled;
+	}
+
+	parameter_types! {
+		pub const MessagingPalletId: PalletId = PalletId(*b"py/msgng");
+		pub const Percent50: Perbill = Perbill::from_percent(50);
+		pub const ProfileQueryTimeout: u64 = 10;
+		pub const RemoteProfileTtl: u64 = 100;
+	}
+
+	impl pallet_messaging::Config for Runtime {
+		type RuntimeEvent = RuntimeEvent;
+		type RuntimeHoldReason = RuntimeHoldReason;
+		type Currency = Balances;
+		type Time = Timestamp;
+		type WeightInfo = ();
+		type SpamBond = ConstU128<100>;
+		type MaxContactsPerUser = ConstU32<100>;
+		type MaxPendingContactRequests = ConstU32<3>;
+		type MessageHashExpiry = ConstU64<1000>;
+		type ResolutionOrigin = EnsureRoot<AccountId>;
+		type MaxMessagesPerWindow = ConstU32<10>;
+		type RateLimitWindow = ConstU64<10>;
+		type RateLimitPenaltyStrikes = ConstU32<1>;
+		type BondPenaltyStrikes = ConstU32<2>;
+		type MaxStrikes = ConstU32<3>;
+		type StrikeDecayPeriod = ConstU64<100>;
+		type MinBondIncrement = ConstU128<10>;
+		type BondUnbondingPeriod = ConstU64<50>;
+		type InactivityThreshold = ConstU64<200>;
+		type DormantSweepThreshold = ConstU64<1000>;
+		type PalletId = MessagingPalletId;
+		type ChallengeBond = ConstU128<20>;
+		type ChallengeSlashPercent = Percent50;
+		type ChallengerRewardPercent = Percent50;
+		type BaseChallengeReward = ConstU128<10>;
+		type RewardPoolFeeShare = Percent50;
+		type AppealWindow = ConstU64<10>;
+		type ChallengeWindow = ConstU64<500>;
+		type AppealBond = ConstU128<40>;
+		type AppealOrigin = EnsureRoot<AccountId>;
+		type AdminOrigin = EnsureRoot<AccountId>;
+		type ForceOrigin = EnsureRoot<AccountId>;
+		type MaxFreezeDuration = ConstU64<100>;
+		type MaxReportsPerWindow = ConstU32<3>;
+		type ReportDecayPeriod = ConstU64<100>;
+		type AutoChallengeThreshold = ConstU32<3>;
+		type AutoSlashEnabled = ConstBool<true>;
+		type AutoSlashThreshold = ConstU32<3>;
+		type AutoSlashPercent = Percent50;
+		type ChallengeMaxDuration = ConstU64<100>;
+		type MaxFreeMessagesPerBlock = ConstU32<2>;
+		type InboxPassDuration = ConstU64<20>;
+		type SlashIncome = ();
+		type FeeIncome = ();
+		type ForfeitedDeposits = ();
+		type ProtocolFeeIncome = ();
+		type MaxKeyResetNotifications = ConstU32<3>;
+		type TakedownRetention = ConstU64<50>;
+		type MaxPrunesPerCall = ConstU32<3>;
+		type MaxExpiryEventsPerBlock = ConstU32<2>;
+		type PruneInterval = ConstU64<5>;
+		type PruneUnsignedPriority = ConstU64<100>;
+		type OffchainIndexing = ConstBool<false>;
+		type XcmSender = XcmRouter;
+		type XcmOrigin = pallet_xcm::EnsureXcm<Everything>;
+		type RemoteProfileTtl = RemoteProfileTtl;
+		type ProfileQueryTimeout = ProfileQueryTimeout;
+		type RegistrationGate = Everything;
+		type IdentityVerifier = Nothing;
+		type GroupBaseDeposit = ConstU128<40>;
+		type GroupPerMemberDeposit = ConstU128<10>;
+		type MaxGroupsPerAccount = ConstU32<3>;
+		type MaxGroupMembers = ConstU32<4>;
+		type MaxGroupAdmins = ConstU32<2>;
+		type MaxGroupMessagesPerWindow = ConstU32<5>;
+		type GroupMessageExpiry = ConstU64<500>;
+		type GroupInviteExpiry = ConstU64<10>;
+		type MaxGroupInvites = ConstU32<2>;
+		type MaxPendingGroupInvites = ConstU32<2>;
+		type MaxGroupBans = ConstU32<2>;
+		type MaxDisbandRemovalsPerCall = ConstU32<3>;
+		type GroupMetadataDepositPerByte = ConstU128<1>;
+		type MaxDiscoverableGroups = ConstU32<2>;
+		type MaxGroupJoinRequests = ConstU32<2>;
+		type MaxGroupModerationLog = ConstU32<3>;
+		type StealthMessageDeposit = ConstU128<30>;
+		type RelayBond = ConstU128<500>;
+		type MaxRelayCarriesPerBlock = ConstU32<2>;
+		type PendingMessageDeposit = ConstU128<40>;
+		type PendingMessageExpiry = ConstU64<3000>;
+		type PendingMessageRefund = Percent50;
+		type MaxClaimsPerCall = ConstU32<2>;
+		type EvidenceRevealPeriod = ConstU64<20>;
+		type MembershipProofVerifier = ();
+		type AccumulatorUpdater =
+			pallet_messaging::membership::HashChain<sp_runtime::traits::BlakeTwo256>;
+		type MaxEphemeralKeys = ConstU32<3>;
+		type RecipientSetDeposit = ConstU128<10>;
+		type BackupDeposit = ConstU128<5>;
+		type MaxAutoRefundsPerReply = ConstU32<2>;
+		type MaxSendBatch = ConstU32<4>;
+		type MaxDevicesPerAccount = ConstU32<2>;
+		type DeviceDeposit = ConstU128<20>;
+		type CreditPrice = ConstU128<10>;
+		type MaxCreditsPerAccount = ConstU32<20>;
+		type CreditRefund = Percent50;
+		type OffchainSignature = sp_runtime::MultiSignature;
+		type SigningPublicKey = sp_runtime::MultiSigner;
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper = ();
+	}
+
+	impl<C> frame_system::offchain::CreateTransactionBase<C> for Runtime
+	where
+		RuntimeCall: From<C>,
+	{
+		type Extrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
+		type RuntimeCall = RuntimeCall;
+	}
+
+	impl<C> frame_system::offchain::CreateBare<C> for Runtime
+	where
+		RuntimeCall: From<C>,
+	{
+		fn create_bare(call: RuntimeCall) -> frame_system::mocking::MockUncheckedExtrinsic<Runtime> {
+			frame_system::mocking::MockUncheckedExtrinsic::<Runtime>::new_bare(call)
+		}
+	}
+}
+
+pub mod relay_chain {
+	use frame_support::{
+		derive_impl, parameter_types,
+		traits::{Everything, Nothing},
+		weights::Weight,
+	};
+	use xcm::latest::prelude::*;
+	use xcm_builder::{
+		AllowUnpaidExecutionFrom, FixedWeightBounds, FrameTransactionalProcessor,
+		ProcessXcmMessage,
+	};
+	use xcm_executor::XcmExecutor;
+	use xcm_simulator::AggregateMessageOrigin;
+
+	type Block = frame_system::mocking::MockBlock<Runtime>;
+
+	#[frame_support::runtime]
+	mod runtime {
+		#[runtime::runtime]
+		#[runtime::derive(RuntimeCall, RuntimeEvent, RuntimeError, RuntimeOrigin, RuntimeTask)]
+		pub struct Runtime;
+
+		#[runtime::pallet_index(0)]
+		pub type System = frame_system;
+
+		#[runtime::pallet_index(1)]
+		pub type MessageQueue = pallet_message_queue;
+	}
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+	impl frame_system::Config for Runtime {
+		type Block = Block;
+	}
+
+	parameter_types! {
+		pub const RelayNetwork: NetworkId = NetworkId::Kusama;
+		pub UniversalLocation: InteriorLocation = [GlobalConsensus(RelayNetwork::get())].into();
+		pub const UnitWeightCost: Weight = Weight::from_parts(1_000, 1_000);
+		pub const MaxInstructions: u32 = 100;
+		pub const MaxAssetsIntoHolding: u32 = 64;
+		pub MessageQueueServiceWeight: Weight = Weight::from_parts(1_000_000_000, 1_000_000);
+		pub const MessageQueueHeapSize: u32 = 65_536;
+		pub const MessageQueueMaxStale: u32 = 16;
+	}
+
+	pub struct XcmConfig;
+	impl xcm_executor::Config for XcmConfig {
+		type RuntimeCall = RuntimeCall;
+		type XcmSender = super::RelayChainXcmRouter;
+		type AssetTransactor = ();
+		type OriginConverter = ();
+		type IsReserve = ();
+		type IsTeleporter = ();
+		type UniversalLocation = UniversalLocation;
+		type Barrier = AllowUnpaidExecutionFrom<Everything>;
+		type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+		type Trader = ();
+		type ResponseHandler = ();
+		type AssetTrap = ();
+		type AssetLocker = ();
+		type AssetExchanger = ();
+		type AssetClaims = ();
+		type SubscriptionService = ();
+		type PalletInstancesInfo = ();
+		type FeeManager = ();
+		type MaxAssetsIntoHolding = MaxAssetsIntoHolding;
+		type MessageExporter = ();
+		type UniversalAliases = Nothing;
+		type CallDispatcher = RuntimeCall;
+		type SafeCallFilter = Everything;
+		type Aliasers = Nothing;
+		type TransactionalProcessor = FrameTransactionalProcessor;
+		type HrmpNewChannelOpenRequestHandler = ();
+		type HrmpChannelAcceptedHandler = ();
+		type HrmpChannelClosingHandler = ();
+		type XcmRecorder = ();
+	}
+
+	impl pallet_message_queue::Config for Runtime {
+		type RuntimeEvent = RuntimeEvent;
+		type Size = u32;
+		type HeapSize = MessageQueueHeapSize;
+		type MaxStale = MessageQueueMaxStale;
+		type ServiceWeight = MessageQueueServiceWeight;
+		type IdleMaxServiceWeight = ();
+		type MessageProcessor =
+			ProcessXcmMessage<AggregateMessageOrigin, XcmExecutor<XcmConfig>, RuntimeCall>;
+		type QueueChangeHandler = ();
+		type QueuePausedQuery = ();
+		type WeightInfo = ();
+	}
+}
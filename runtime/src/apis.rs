@@ -42,8 +42,9 @@ use sp_version::RuntimeVersion;
 
 // Local module imports
 use super::{
-	AccountId, Aura, Balance, Block, Executive, Grandpa, InherentDataExt, Nonce, Runtime,
-	RuntimeCall, RuntimeGenesisConfig, SessionKeys, System, TransactionPayment, VERSION,
+	AccountId, Aura, Balance, Block, BlockNumber, Executive, Grandpa, Hash, InherentDataExt,
+	Messaging, Nonce, Runtime, RuntimeCall, RuntimeGenesisConfig, SessionKeys, System,
+	TransactionPayment, VERSION,
 };
 
 impl_runtime_apis! {
@@ -176,6 +177,120 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_messaging_runtime_api::MessagingApi<Block, AccountId, Hash, BlockNumber> for Runtime {
+		fn admin_audit_log() -> Vec<pallet_messaging::AuditEntry<BlockNumber, Hash>> {
+			Messaging::audit_log()
+		}
+
+		fn verify_message(
+			message_id: pallet_messaging::MessageId,
+			hash: Hash,
+		) -> pallet_messaging::VerificationResult {
+			Messaging::verification_result(message_id, hash)
+		}
+
+		fn message_info(
+			message_id: pallet_messaging::MessageId,
+		) -> Option<pallet_messaging::MessageRecordView<AccountId, Hash, BlockNumber>> {
+			Messaging::message_view(message_id)
+		}
+
+		fn is_expired(message_id: pallet_messaging::MessageId) -> bool {
+			!Messaging::message_state(message_id).is_live()
+		}
+
+		fn profile(who: AccountId) -> Option<pallet_messaging::ProfileView> {
+			Messaging::profile_view(&who)
+		}
+
+		fn inbox(
+			who: AccountId,
+			after: Option<pallet_messaging::MessageId>,
+			limit: u32,
+		) -> Vec<(
+			pallet_messaging::MessageId,
+			pallet_messaging::MessageSummary<AccountId, Hash, BlockNumber>,
+		)> {
+			Messaging::inbox_of(who, None, after, limit)
+		}
+
+		fn contacts(who: AccountId, after: Option<AccountId>, limit: u32) -> Vec<AccountId> {
+			Messaging::contacts_of(who, after, limit)
+		}
+
+		fn conversation(
+			a: AccountId,
+			b: AccountId,
+			from_seq: u64,
+			limit: u32,
+		) -> pallet_messaging::ConversationPage<AccountId, Hash, BlockNumber> {
+			Messaging::conversation_page(a, b, from_seq, limit)
+		}
+
+		fn event_privacy() -> pallet_messaging::EventPrivacyMode {
+			Messaging::parameters().event_privacy
+		}
+
+		fn has_profile(who: AccountId) -> bool {
+			Messaging::has_profile(who)
+		}
+
+		fn can_message(
+			sender: AccountId,
+			recipient: AccountId,
+		) -> Result<(), pallet_messaging::CanMessageError> {
+			Messaging::can_message(sender, recipient)
+		}
+
+		fn profile_activity(
+			who: AccountId,
+		) -> Option<pallet_messaging::ProfileActivity<BlockNumber>> {
+			Messaging::profile_activity(who)
+		}
+
+		fn message_by_hash(
+			hash: Hash,
+		) -> Option<(
+			pallet_messaging::MessageId,
+			pallet_messaging::MessageRecordView<AccountId, Hash, BlockNumber>,
+		)> {
+			Messaging::message_by_hash(hash)
+		}
+
+		fn inbox_revision(who: AccountId) -> u64 {
+			Messaging::inbox_revision(who)
+		}
+
+		fn conversation_summary(
+			a: AccountId,
+			b: AccountId,
+		) -> Option<pallet_messaging::ConversationSummary<BlockNumber>> {
+			Messaging::conversation_summary(a, b)
+		}
+
+		fn message_state(
+			message_id: pallet_messaging::MessageId,
+		) -> pallet_messaging::MessageState<BlockNumber> {
+			Messaging::message_state(message_id)
+		}
+
+		fn verify_messages(
+			batch: pallet_messaging::VerificationBatch<Hash>,
+		) -> Vec<pallet_messaging::VerificationResult> {
+			Messaging::verify_message_batch(batch)
+		}
+	}
+
+	impl pallet_messaging_runtime_api::MessagingStandingApi<Block, AccountId, Balance, BlockNumber>
+		for Runtime
+	{
+		fn account_standing(
+			who: AccountId,
+		) -> pallet_messaging::AccountStanding<Balance, BlockNumber> {
+			Messaging::standing(who)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, Balance> for Runtime {
 		fn query_info(
 			uxt: <Block as BlockT>::Extrinsic,
@@ -26,23 +26,32 @@
 // Substrate and Polkadot dependencies
 use frame_support::{
 	derive_impl, parameter_types,
-	traits::{ConstBool, ConstU128, ConstU32, ConstU64, ConstU8, VariantCountOf},
+	traits::{
+		ConstBool, ConstU128, ConstU32, ConstU64, ConstU8, Everything, NeverEnsureOrigin,
+		Nothing, VariantCountOf,
+	},
 	weights::{
 		constants::{RocksDbWeight, WEIGHT_REF_TIME_PER_SECOND},
 		IdentityFee, Weight,
 	},
+	PalletId,
 };
 use frame_system::limits::{BlockLength, BlockWeights};
 use pallet_transaction_payment::{ConstFeeMultiplier, FungibleAdapter, Multiplier};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_runtime::{traits::One, Perbill};
+use sp_runtime::{
+	traits::{One, Verify},
+	transaction_validity::TransactionPriority,
+	Perbill,
+};
 use sp_version::RuntimeVersion;
+use xcm::latest::Location;
 
 // Local module imports
 use super::{
 	AccountId, Aura, Balance, Balances, Block, BlockNumber, Hash, Nonce, PalletInfo, Runtime,
 	RuntimeCall, RuntimeEvent, RuntimeFreezeReason, RuntimeHoldReason, RuntimeOrigin, RuntimeTask,
-	System, EXISTENTIAL_DEPOSIT, MILLI_UNIT, SLOT_DURATION, VERSION,
+	Signature, System, UncheckedExtrinsic, EXISTENTIAL_DEPOSIT, MILLI_UNIT, SLOT_DURATION, VERSION,
 };
 
 const NORMAL_DISPATCH_RATIO: Perbill = Perbill::from_percent(75);
@@ -143,8 +152,118 @@ parameter_types! {
 	pub const SpamBond: Balance = 10 * crate::UNIT;
 	// Maximum 1000 contacts per user
 	pub const MaxContactsPerUser: u32 = 1000;
+	// Index up to 16 pending contact requests per account and direction
+	pub const MaxPendingContactRequests: u32 = 16;
 	// Message hashes expire after 7 days (assuming 6 second blocks)
 	pub const MessageHashExpiry: BlockNumber = 7 * crate::DAYS;
+	// At most 100 messages per hour
+	pub const MaxMessagesPerWindow: u32 = 100;
+	pub const RateLimitWindow: BlockNumber = crate::HOURS;
+	// Rate limit halves after the first upheld challenge, bond doubles after the second
+	pub const RateLimitPenaltyStrikes: u32 = 1;
+	pub const BondPenaltyStrikes: u32 = 2;
+	// Graylisted after 3 strikes, each strike decays after 30 days
+	pub const MaxStrikes: u32 = 3;
+	pub const StrikeDecayPeriod: BlockNumber = 30 * crate::DAYS;
+	// Bond top-ups of at least 1 UNIT, locked for 7 days after each message
+	pub const MinBondIncrement: Balance = crate::UNIT;
+	pub const BondUnbondingPeriod: BlockNumber = 7 * crate::DAYS;
+	// Profiles can be flagged dormant after 90 days without activity and swept after a year
+	pub const InactivityThreshold: BlockNumber = 90 * crate::DAYS;
+	pub const DormantSweepThreshold: BlockNumber = 365 * crate::DAYS;
+	pub const MessagingPalletId: PalletId = PalletId(*b"py/msgng");
+	// Challenges bond 5 UNIT, appeals 20 UNIT within 2 days of the verdict
+	pub const ChallengeBond: Balance = 5 * crate::UNIT;
+	pub const AppealBond: Balance = 20 * crate::UNIT;
+	pub const AppealWindow: BlockNumber = 2 * crate::DAYS;
+	pub const ChallengeWindow: BlockNumber = 3 * crate::DAYS;
+	// Upheld challenges slash half the sender's bond, half of which rewards the challenger
+	pub const ChallengeSlashPercent: Perbill = Perbill::from_percent(50);
+	pub const ChallengerRewardPercent: Perbill = Perbill::from_percent(50);
+	// Upheld challengers also get 1 UNIT from a reward pool taking a fifth of protocol fees
+	pub const BaseChallengeReward: Balance = crate::UNIT;
+	pub const RewardPoolFeeShare: Perbill = Perbill::from_percent(20);
+	// 20 spam reports per hour; 10 outstanding reports against a sender open a challenge
+	pub const MaxReportsPerWindow: u32 = 20;
+	pub const ReportDecayPeriod: BlockNumber = 7 * crate::DAYS;
+	pub const AutoChallengeThreshold: u32 = 10;
+	// Reaching 3 strikes slashes half of the remaining bond
+	pub const AutoSlashThreshold: u32 = 3;
+	pub const AutoSlashPercent: Perbill = Perbill::from_percent(50);
+	// Challenges left unresolved for 14 days can be expired by anyone
+	pub const ChallengeMaxDuration: BlockNumber = 14 * crate::DAYS;
+	pub const MaxFreeMessagesPerBlock: u32 = 5;
+	pub const InboxPassDuration: BlockNumber = 7 * crate::DAYS;
+	pub const MaxFreezeDuration: BlockNumber = 7 * crate::DAYS;
+	pub const MaxKeyResetNotifications: u32 = 100;
+	// Takedown records are kept for a year
+	pub const TakedownRetention: BlockNumber = 365 * crate::DAYS;
+	// Keeps a full pruning transaction under `MAX_CALL_PROOF_SIZE`
+	pub const MaxPrunesPerCall: u32 = 30;
+	// Expired messages beyond the first 50 in a block are reported in one aggregate event
+	pub const MaxExpiryEventsPerBlock: u32 = 50;
+	// Offchain workers submit at most one pruning transaction per hour
+	pub const PruneInterval: BlockNumber = crate::HOURS;
+	pub const PruneUnsignedPriority: TransactionPriority = TransactionPriority::MAX / 2;
+	pub const RemoteProfileTtl: BlockNumber = crate::DAYS;
+	pub const ProfileQueryTimeout: BlockNumber = 10 * crate::MINUTES;
+	// Groups hold 10 UNIT plus 100 MILLI_UNIT per member from their owner and have at most 256
+	// members, 16 of them admins; accounts can be in at most 100 groups
+	pub const GroupBaseDeposit: Balance = 10 * crate::UNIT;
+	pub const GroupPerMemberDeposit: Balance = 100 * crate::MILLI_UNIT;
+	pub const MaxGroupsPerAccount: u32 = 100;
+	pub const MaxGroupMembers: u32 = 256;
+	pub const MaxGroupAdmins: u32 = 16;
+	// Members of a group share one send limit per `RateLimitWindow`; group message hashes
+	// verify for a day at most
+	pub const MaxGroupMessagesPerWindow: u32 = 500;
+	pub const GroupMessageExpiry: BlockNumber = crate::DAYS;
+	// Invites can be accepted for a week; groups and accounts have at most 64 and 20 pending
+	pub const GroupInviteExpiry: BlockNumber = 7 * crate::DAYS;
+	pub const MaxGroupInvites: u32 = 64;
+	pub const MaxPendingGroupInvites: u32 = 20;
+	// Each group can keep up to 256 accounts out
+	pub const MaxGroupBans: u32 = 256;
+	// Disbanding removes up to 100 messages and 100 other entries per call
+	pub const MaxDisbandRemovalsPerCall: u32 = 100;
+	// Group metadata holds 10 MILLI_UNIT per byte; up to 10_000 groups can be listed for
+	// discovery, each with up to 64 pending join requests
+	pub const GroupMetadataDepositPerByte: Balance = 10 * crate::MILLI_UNIT;
+	pub const MaxDiscoverableGroups: u32 = 10_000;
+	pub const MaxGroupJoinRequests: u32 = 64;
+	// Each group keeps its last 100 moderation actions
+	pub const MaxGroupModerationLog: u32 = 100;
+	// Stealth messages hold 1 UNIT each until they are pruned
+	pub const StealthMessageDeposit: Balance = crate::UNIT;
+	// Relays bond 500 UNIT and carry at most 50 sealed messages per block
+	pub const RelayBond: Balance = 500 * crate::UNIT;
+	pub const MaxRelayCarriesPerBlock: u32 = 50;
+	// Messages for unregistered accounts hold 2 UNIT for up to 30 days, 80% of which is
+	// refunded once claimed, 20 per claim
+	pub const PendingMessageDeposit: Balance = 2 * crate::UNIT;
+	pub const PendingMessageExpiry: BlockNumber = 30 * crate::DAYS;
+	pub const PendingMessageRefund: Perbill = Perbill::from_percent(80);
+	pub const MaxClaimsPerCall: u32 = 20;
+	// Challengers have 2 days to reveal their evidence once a challenge is contested
+	pub const EvidenceRevealPeriod: BlockNumber = 2 * crate::DAYS;
+	// Senders keep up to 16 ephemeral keys published at once
+	pub const MaxEphemeralKeys: u32 = 16;
+	// Recipient-set messages hold 0.2 UNIT per listed account until acknowledged
+	pub const RecipientSetDeposit: Balance = crate::UNIT / 5;
+	// Each kept backup commitment holds 0.1 UNIT
+	pub const BackupDeposit: Balance = crate::UNIT / 10;
+	// A reply refunds up to eight recipient-set deposits
+	pub const MaxAutoRefundsPerReply: u32 = 8;
+	// A batch shares the eight refunds of one reply, and each further message adds about
+	// 41 KiB of proof, so sixteen keep a full batch under `MAX_CALL_PROOF_SIZE`
+	pub const MaxSendBatch: u32 = 16;
+	// Accounts keep up to 8 devices, each holding 1 UNIT until it is removed
+	pub const MaxDevicesPerAccount: u32 = 8;
+	pub const DeviceDeposit: Balance = crate::UNIT;
+	// Message credits cost 0.01 UNIT each, 90% of which is refunded if unused
+	pub const CreditPrice: Balance = crate::UNIT / 100;
+	pub const MaxCreditsPerAccount: u32 = 1_000;
+	pub const CreditRefund: Perbill = Perbill::from_percent(90);
 }
 
 impl pallet_transaction_payment::Config for Runtime {
@@ -171,10 +290,160 @@ impl pallet_template::Config for Runtime {
 
 impl pallet_messaging::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
+	type RuntimeHoldReason = RuntimeHoldReason;
 	type Currency = Balances;
 	type Time = pallet_timestamp::Pallet<Runtime>;
 	type WeightInfo = pallet_messaging::weights::SubstrateWeight<Runtime>;
 	type SpamBond = SpamBond;
 	type MaxContactsPerUser = MaxContactsPerUser;
+	type MaxPendingContactRequests = MaxPendingContactRequests;
 	type MessageHashExpiry = MessageHashExpiry;
+	type ResolutionOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxMessagesPerWindow = MaxMessagesPerWindow;
+	type RateLimitWindow = RateLimitWindow;
+	type RateLimitPenaltyStrikes = RateLimitPenaltyStrikes;
+	type BondPenaltyStrikes = BondPenaltyStrikes;
+	type MaxStrikes = MaxStrikes;
+	type StrikeDecayPeriod = StrikeDecayPeriod;
+	type MinBondIncrement = MinBondIncrement;
+	type BondUnbondingPeriod = BondUnbondingPeriod;
+	type InactivityThreshold = InactivityThreshold;
+	type DormantSweepThreshold = DormantSweepThreshold;
+	type PalletId = MessagingPalletId;
+	type ChallengeBond = ChallengeBond;
+	type ChallengeSlashPercent = ChallengeSlashPercent;
+	type ChallengerRewardPercent = ChallengerRewardPercent;
+	type BaseChallengeReward = BaseChallengeReward;
+	type RewardPoolFeeShare = RewardPoolFeeShare;
+	type AppealWindow = AppealWindow;
+	type ChallengeWindow = ChallengeWindow;
+	type AppealBond = AppealBond;
+	type AppealOrigin = frame_system::EnsureRoot<AccountId>;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxFreezeDuration = MaxFreezeDuration;
+	type MaxReportsPerWindow = MaxReportsPerWindow;
+	type ReportDecayPeriod = ReportDecayPeriod;
+	type AutoChallengeThreshold = AutoChallengeThreshold;
+	type AutoSlashEnabled = ConstBool<true>;
+	type AutoSlashThreshold = AutoSlashThreshold;
+	type AutoSlashPercent = AutoSlashPercent;
+	type ChallengeMaxDuration = ChallengeMaxDuration;
+	type MaxFreeMessagesPerBlock = MaxFreeMessagesPerBlock;
+	type InboxPassDuration = InboxPassDuration;
+	// The runtime has no treasury, so all pallet income is burned
+	type SlashIncome = ();
+	type FeeIncome = ();
+	type ForfeitedDeposits = ();
+	type ProtocolFeeIncome = ();
+	type MaxKeyResetNotifications = MaxKeyResetNotifications;
+	type TakedownRetention = TakedownRetention;
+	type MaxPrunesPerCall = MaxPrunesPerCall;
+	type MaxExpiryEventsPerBlock = MaxExpiryEventsPerBlock;
+	type PruneInterval = PruneInterval;
+	type PruneUnsignedPriority = PruneUnsignedPriority;
+	type OffchainIndexing = ConstBool<true>;
+	// This chain has no XCM transport, so remote profile lookups are disabled
+	type XcmSender = ();
+	type XcmOrigin = NeverEnsureOrigin<Location>;
+	type RemoteProfileTtl = RemoteProfileTtl;
+	type ProfileQueryTimeout = ProfileQueryTimeout;
+	type RegistrationGate = Everything;
+	type IdentityVerifier = Nothing;
+	type GroupBaseDeposit = GroupBaseDeposit;
+	type GroupPerMemberDeposit = GroupPerMemberDeposit;
+	type MaxGroupsPerAccount = MaxGroupsPerAccount;
+	type MaxGroupMembers = MaxGroupMembers;
+	type MaxGroupAdmins = MaxGroupAdmins;
+	type MaxGroupMessagesPerWindow = MaxGroupMessagesPerWindow;
+	type GroupMessageExpiry = GroupMessageExpiry;
+	type GroupInviteExpiry = GroupInviteExpiry;
+	type MaxGroupInvites = MaxGroupInvites;
+	type MaxPendingGroupInvites = MaxPendingGroupInvites;
+	type MaxGroupBans = MaxGroupBans;
+	type MaxDisbandRemovalsPerCall = MaxDisbandRemovalsPerCall;
+	type GroupMetadataDepositPerByte = GroupMetadataDepositPerByte;
+	type MaxDiscoverableGroups = MaxDiscoverableGroups;
+	type MaxGroupJoinRequests = MaxGroupJoinRequests;
+	type MaxGroupModerationLog = MaxGroupModerationLog;
+	type StealthMessageDeposit = StealthMessageDeposit;
+	type RelayBond = RelayBond;
+	type MaxRelayCarriesPerBlock = MaxRelayCarriesPerBlock;
+	type PendingMessageDeposit = PendingMessageDeposit;
+	type PendingMessageExpiry = PendingMessageExpiry;
+	type PendingMessageRefund = PendingMessageRefund;
+	type MaxClaimsPerCall = MaxClaimsPerCall;
+	type EvidenceRevealPeriod = EvidenceRevealPeriod;
+	// No membership proof system is deployed yet, so anonymous contact messages are rejected.
+	type MembershipProofVerifier = ();
+	type AccumulatorUpdater =
+		pallet_messaging::membership::HashChain<sp_runtime::traits::BlakeTwo256>;
+	type MaxEphemeralKeys = MaxEphemeralKeys;
+	type RecipientSetDeposit = RecipientSetDeposit;
+	type BackupDeposit = BackupDeposit;
+	type MaxAutoRefundsPerReply = MaxAutoRefundsPerReply;
+	type MaxSendBatch = MaxSendBatch;
+	type MaxDevicesPerAccount = MaxDevicesPerAccount;
+	type DeviceDeposit = DeviceDeposit;
+	type CreditPrice = CreditPrice;
+	type MaxCreditsPerAccount = MaxCreditsPerAccount;
+	type CreditRefund = CreditRefund;
+	type OffchainSignature = Signature;
+	type SigningPublicKey = <Signature as Verify>::Signer;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = SponsorshipSigner;
+}
+
+/// Signs sponsorship proofs with a freshly generated sr25519 key for benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct SponsorshipSigner;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_messaging::SponsorshipBenchmarkHelper<AccountId, Signature> for SponsorshipSigner {
+	fn sign(payload: &[u8]) -> Option<(AccountId, Signature)> {
+		use sp_runtime::{app_crypto::RuntimePublic, traits::IdentifyAccount};
+
+		let key_type = sp_core::crypto::key_types::ACCOUNT;
+		let public = sp_core::sr25519::Public::generate_pair(key_type, None);
+		let signature = public.sign(key_type, &payload)?;
+		Some((<Signature as Verify>::Signer::from(public).into_account(), signature.into()))
+	}
+}
+
+impl<C> frame_system::offchain::CreateTransactionBase<C> for Runtime
+where
+	RuntimeCall: From<C>,
+{
+	type Extrinsic = UncheckedExtrinsic;
+	type RuntimeCall = RuntimeCall;
+}
+
+impl<C> frame_system::offchain::CreateBare<C> for Runtime
+where
+	RuntimeCall: From<C>,
+{
+	fn create_bare(call: RuntimeCall) -> UncheckedExtrinsic {
+		UncheckedExtrinsic::new_bare(call)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::dispatch::DispatchClass;
+
+	#[test]
+	fn full_send_batch_fits_in_a_block() {
+		let (_, batch) = pallet_messaging::Pallet::<Runtime>::worst_case_weights()
+			.into_iter()
+			.find(|(call, _)| *call == "send_message_hash_batch")
+			.expect("batches have a worst case");
+		let max_extrinsic = RuntimeBlockWeights::get()
+			.get(DispatchClass::Normal)
+			.max_extrinsic
+			.expect("normal extrinsics are limited");
+
+		assert!(batch.all_lte(max_extrinsic));
+		assert!(batch.proof_size() <= pallet_messaging::MAX_CALL_PROOF_SIZE);
+	}
 }